@@ -0,0 +1,102 @@
+/* Interface C du codage TLV de sim_icom (fichier généré par cbindgen, ne pas modifier) */
+
+#ifndef SIM_ICOM_TLV_H
+#define SIM_ICOM_TLV_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Erreur: Pointeur NULL en paramètre
+ */
+#define SIM_ICOM_TLV_ERR_NULL_POINTER -1
+
+/**
+ * Erreur: Buffer de sortie trop petit
+ */
+#define SIM_ICOM_TLV_ERR_BUFFER_TOO_SMALL -2
+
+/**
+ * Erreur: La trame est vide
+ */
+#define SIM_ICOM_TLV_ERR_IS_EMPTY -3
+
+/**
+ * Erreur: La trame n'est pas correcte
+ */
+#define SIM_ICOM_TLV_ERR_IS_JUNK -4
+
+/**
+ * Erreur: La trame n'est pas complètement construite
+ */
+#define SIM_ICOM_TLV_ERR_IS_BUILDING -5
+
+/**
+ * Erreur: La trame n'est pas un message correct
+ */
+#define SIM_ICOM_TLV_ERR_IS_NOT_OK -6
+
+/**
+ * Erreur: Inconsistance longueur des `DataItem`
+ */
+#define SIM_ICOM_TLV_ERR_BAD_DATA_LENGTH -7
+
+/**
+ * Erreur: Inconsistance décodage des `DataItem`
+ */
+#define SIM_ICOM_TLV_ERR_BAD_DATA_ITEM -8
+
+/**
+ * Erreur: Overflow de la longueur max. d'une trame
+ */
+#define SIM_ICOM_TLV_ERR_MAX_LENGTH_OVERFLOW -9
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Encode une trame TLV complète (STX + tag + len + données + XOR + ETX)
+ *
+ * * `tag`: Tag du message
+ * * `data`, `data_len`: Suite des `DataItem` encodés (tag + format + valeur)
+ * * `out`, `out_len`: Buffer pour la trame encodée
+ *
+ * Retourne la longueur de la trame encodée ou un code d'erreur négatif
+ *
+ * # Safety
+ * `data` doit pointer sur `data_len` octets valides et `out` sur `out_len` octets modifiables
+ */
+int32_t sim_icom_tlv_encode(uint8_t tag,
+                            const uint8_t *data,
+                            size_t data_len,
+                            uint8_t *out,
+                            size_t out_len);
+
+/**
+ * Décode une trame TLV complète
+ *
+ * * `frame`, `frame_len`: Octets de la trame reçue
+ * * `tag`: Tag du message décodé (`ACK` ou `NACK` pour une trame simple ACK ou NACK)
+ * * `out`, `out_len`: Buffer pour la suite des `DataItem` encodés (tag + format + valeur)
+ *
+ * Retourne la longueur des données du message ou un code d'erreur négatif
+ *
+ * # Safety
+ * `frame` doit pointer sur `frame_len` octets valides, `tag` sur un octet modifiable
+ * et `out` sur `out_len` octets modifiables
+ */
+int32_t sim_icom_tlv_decode(const uint8_t *frame,
+                            size_t frame_len,
+                            uint8_t *tag,
+                            uint8_t *out,
+                            size_t out_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SIM_ICOM_TLV_H */
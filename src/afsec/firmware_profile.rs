@@ -0,0 +1,336 @@
+//! Profils de comportement selon la version du firmware ICOM émulé
+//!
+//! Les différentes générations de firmware ICOM présentent des particularités ('quirks') dans
+//! le dialogue TLV avec l'AFSEC+. Plutôt que de maintenir une version du simulateur par
+//! génération de firmware, ces particularités sont regroupées dans un [`FirmwareProfile`] décrit
+//! par un fichier (option `--firmware <FICHIER>` de la ligne de commande). Sans cette option, le
+//! simulateur garde son comportement historique.
+//!
+//! Le simulateur n'embarque pas de profil d'une génération de firmware : les valeurs d'un profil
+//! doivent être relevées sur des traces de l'ICOM réel (ou sa spécification) par l'équipe qui
+//! l'utilise. Le fichier contient une particularité `<clé> = <valeur>` par ligne (lignes vides et
+//! commentaires `#` ignorés, particularités absentes selon le comportement historique) :
+//!
+//! * `protocol_version` : Version annoncée dans la réponse `IC_INIT` (`D_PROTOCOLE_VERSION`)
+//! * `icom_version` : Version annoncée dans la réponse `IC_INIT` (`D_ICOM_VERSION`)
+//! * `alive_answer` : Réponse à `AF_ALIVE` quand personne n'a rien à dire ([`AliveAnswer`],
+//!   modifiable par l'option `--alive-answer`)
+//! * `version_coding` : Conversion des versions `D_RESIDENT_VERSION` et `D_APPLI_VERSION` reçues
+//!   dans `AF_INIT` (`decimal` ou `bytes`)
+//! * `menu` : Gestion des menus `AF_MENU` (`nack` ou `ic_menu` pour une réponse `IC_MENU` vide)
+//! * `malformed_answer` : Réponse à une trame reçue mais inexploitable ([`MalformedAnswer`],
+//!   modifiable par l'option `--malformed-answer`)
+//!
+//! Exemple :
+//!
+//! ```text
+//! # ICOM relevé sur le banc de test
+//! icom_version = 5020
+//! alive_answer = ic_alive
+//! menu = ic_menu
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Réponse à un `AF_ALIVE` quand aucun `middleware` ne souhaite converser
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AliveAnswer {
+    /// Simple ACK
+    Ack,
+
+    /// Message `IC_ALIVE` sans donnée
+    IcAlive,
+
+    /// Message `IC_ALIVE` avec l'état de l'ICOM (`D_PROTOCOLE_VERSION` et `D_ICOM_VERSION`)
+    IcAliveWithStatus,
+}
+
+impl fmt::Display for AliveAnswer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AliveAnswer::Ack => write!(f, "ACK"),
+            AliveAnswer::IcAlive => write!(f, "IC_ALIVE"),
+            AliveAnswer::IcAliveWithStatus => write!(f, "IC_ALIVE with status"),
+        }
+    }
+}
+
+impl FromStr for AliveAnswer {
+    type Err = String;
+
+    /// Accepte 'ack', 'ic_alive' ou 'ic_alive_status' (sans tenir compte de la casse)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ack" => Ok(AliveAnswer::Ack),
+            "ic_alive" => Ok(AliveAnswer::IcAlive),
+            "ic_alive_status" => Ok(AliveAnswer::IcAliveWithStatus),
+            _ => Err(format!(
+                "Réponse AF_ALIVE '{s}' inconnue (attendu: ack, ic_alive ou ic_alive_status)"
+            )),
+        }
+    }
+}
+
+/// Réponse à une trame complète de l'AFSEC+ dont le contenu ne peut pas être décodé
+///
+/// Certaines versions du résident attendent un NACK pour retransmettre la trame, d'autres
+/// considèrent un NACK inattendu comme une erreur fatale et retransmettent sur timeout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MalformedAnswer {
+    /// Aucune réponse (retransmission sur timeout de l'AFSEC+)
+    Silent,
+
+    /// Simple NACK
+    Nack,
+
+    /// Simple NACK à partir de la N-ième trame inexploitable consécutive (aucune réponse aux
+    /// précédentes)
+    NackAfter(u32),
+}
+
+impl fmt::Display for MalformedAnswer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MalformedAnswer::Silent => write!(f, "silent"),
+            MalformedAnswer::Nack => write!(f, "NACK"),
+            MalformedAnswer::NackAfter(n) => write!(f, "NACK after {n}"),
+        }
+    }
+}
+
+impl FromStr for MalformedAnswer {
+    type Err = String;
+
+    /// Accepte 'silent', 'nack' ou 'nack-after=<n>' (sans tenir compte de la casse)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        if let Some(n) = name.strip_prefix("nack-after=") {
+            return match n.trim().parse::<u32>() {
+                Ok(n) if n > 0 => Ok(MalformedAnswer::NackAfter(n)),
+                _ => Err(format!("Nombre de trames '{}' incorrect", n.trim())),
+            };
+        }
+        match name.as_str() {
+            "silent" => Ok(MalformedAnswer::Silent),
+            "nack" => Ok(MalformedAnswer::Nack),
+            _ => Err(format!(
+                "Réponse aux trames inexploitables '{s}' inconnue (attendu: silent, nack ou \
+                 nack-after=<n>)"
+            )),
+        }
+    }
+}
+
+impl MalformedAnswer {
+    /// Indique si la réponse à la `nb_malformed`-ième trame inexploitable consécutive est un NACK
+    pub fn is_nack(self, nb_malformed: u32) -> bool {
+        match self {
+            MalformedAnswer::Silent => false,
+            MalformedAnswer::Nack => true,
+            MalformedAnswer::NackAfter(n) => nb_malformed >= n,
+        }
+    }
+}
+
+/// Profil de comportement du firmware ICOM émulé
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FirmwareProfile {
+    /// Version du protocole annoncée dans `IC_INIT` (`D_PROTOCOLE_VERSION`)
+    protocol_version: u16,
+
+    /// Version de l'ICOM annoncée dans `IC_INIT` (`D_ICOM_VERSION`)
+    icom_version: u16,
+
+    /// Réponse à `AF_ALIVE` quand aucun `middleware` ne souhaite converser
+    alive_answer: AliveAnswer,
+
+    /// Réponse à une trame de l'AFSEC+ dont le contenu ne peut pas être décodé
+    malformed_answer: MalformedAnswer,
+
+    /// Menus `AF_MENU` acceptés (réponse `IC_MENU` vide, sinon NACK)
+    accept_menu: bool,
+
+    /// Versions `u32` reçues de l'AFSEC+ codées par octets `0x00VVRREE`
+    byte_coded_versions: bool,
+}
+
+/// Comportement historique du simulateur
+impl Default for FirmwareProfile {
+    fn default() -> Self {
+        Self {
+            protocol_version: 0,
+            icom_version: 0,
+            alive_answer: AliveAnswer::Ack,
+            malformed_answer: MalformedAnswer::Silent,
+            accept_menu: false,
+            byte_coded_versions: false,
+        }
+    }
+}
+
+impl fmt::Display for FirmwareProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "protocol {}, ICOM {}, AF_ALIVE {}, malformed {}, AF_MENU {}, versions {}",
+            self.protocol_version,
+            self.icom_version,
+            self.alive_answer,
+            self.malformed_answer,
+            if self.accept_menu { "IC_MENU" } else { "NACK" },
+            if self.byte_coded_versions {
+                "0x00VVRREE"
+            } else {
+                "decimal"
+            }
+        )
+    }
+}
+
+impl FromStr for FirmwareProfile {
+    type Err = String;
+
+    /// Analyse du contenu d'un fichier de profil (une particularité `<clé> = <valeur>` par ligne)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut profile = FirmwareProfile::default();
+        for (num_line, line) in (1..).zip(s.lines()) {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!(
+                    "Ligne {num_line} '{line}' incorrecte (attendu: <clé> = <valeur>)"
+                ));
+            };
+            let value = value.trim();
+            let err_value = || format!("Ligne {num_line}: valeur '{value}' incorrecte");
+            match key.trim().to_lowercase().as_str() {
+                "protocol_version" => {
+                    profile.protocol_version = value.parse().map_err(|_| err_value())?;
+                }
+                "icom_version" => profile.icom_version = value.parse().map_err(|_| err_value())?,
+                "alive_answer" => profile.alive_answer = AliveAnswer::from_str(value)?,
+                "malformed_answer" => profile.malformed_answer = MalformedAnswer::from_str(value)?,
+                "menu" => {
+                    profile.accept_menu = match value.to_lowercase().as_str() {
+                        "nack" => false,
+                        "ic_menu" => true,
+                        _ => return Err(err_value()),
+                    };
+                }
+                "version_coding" => {
+                    profile.byte_coded_versions = match value.to_lowercase().as_str() {
+                        "decimal" => false,
+                        "bytes" => true,
+                        _ => return Err(err_value()),
+                    };
+                }
+                key => return Err(format!("Ligne {num_line}: particularité '{key}' inconnue")),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+impl FirmwareProfile {
+    /// Lecture d'un fichier de profil
+    /// # Errors
+    /// Message d'erreur si le fichier est illisible ou incorrect
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(filename)
+            .map_err(|e| format!("Lecture du profil firmware '{filename}' impossible: {e}"))?;
+        FirmwareProfile::from_str(&content)
+            .map_err(|e| format!("Profil firmware '{filename}': {e}"))
+    }
+
+    /// Version du protocole annoncée dans `IC_INIT` (`D_PROTOCOLE_VERSION`)
+    pub fn protocol_version(self) -> u16 {
+        self.protocol_version
+    }
+
+    /// Version de l'ICOM annoncée dans `IC_INIT` (`D_ICOM_VERSION`)
+    pub fn icom_version(self) -> u16 {
+        self.icom_version
+    }
+
+    /// Réponse à `AF_ALIVE` quand aucun `middleware` ne souhaite converser
+    pub fn alive_answer(self) -> AliveAnswer {
+        self.alive_answer
+    }
+
+    /// Réponse à une trame de l'AFSEC+ dont le contenu ne peut pas être décodé
+    pub fn malformed_answer(self) -> MalformedAnswer {
+        self.malformed_answer
+    }
+
+    /// Indique si les menus `AF_MENU` sont acceptés (réponse `IC_MENU` vide, sinon NACK)
+    pub fn accept_menu(self) -> bool {
+        self.accept_menu
+    }
+
+    /// Indique si les versions `u32` reçues de l'AFSEC+ sont codées par octets `0x00VVRREE`
+    /// (sinon codage décimal 10000 * version + 100 * révision + édition)
+    pub fn byte_coded_versions(self) -> bool {
+        self.byte_coded_versions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            FirmwareProfile::from_str(""),
+            Ok(FirmwareProfile::default())
+        );
+        let profile = FirmwareProfile::from_str(
+            "# Commentaire\n\nicom_version = 5020\nALIVE_ANSWER = ic_alive # Réponse\nmenu=ic_menu\n",
+        )
+        .unwrap();
+        assert_eq!(profile.icom_version(), 5020);
+        assert_eq!(profile.protocol_version(), 0);
+        assert_eq!(profile.alive_answer(), AliveAnswer::IcAlive);
+        assert!(profile.accept_menu());
+        assert!(!profile.byte_coded_versions());
+        assert!(FirmwareProfile::from_str("version_coding = bytes")
+            .unwrap()
+            .byte_coded_versions());
+        assert!(FirmwareProfile::from_str("icom_version").is_err());
+        assert!(FirmwareProfile::from_str("icom_version = v5020").is_err());
+        assert!(FirmwareProfile::from_str("menu = oui").is_err());
+        assert!(FirmwareProfile::from_str("quirk = 1").is_err());
+    }
+
+    #[test]
+    fn test_alive_answer() {
+        assert_eq!(AliveAnswer::from_str("ACK"), Ok(AliveAnswer::Ack));
+        assert_eq!(
+            AliveAnswer::from_str("ic_alive_status"),
+            Ok(AliveAnswer::IcAliveWithStatus)
+        );
+        assert!(AliveAnswer::from_str("nack").is_err());
+    }
+
+    #[test]
+    fn test_malformed_answer() {
+        assert_eq!(MalformedAnswer::from_str("NACK"), Ok(MalformedAnswer::Nack));
+        assert_eq!(
+            MalformedAnswer::from_str("nack-after=2"),
+            Ok(MalformedAnswer::NackAfter(2))
+        );
+        assert!(MalformedAnswer::from_str("nack-after=0").is_err());
+        assert!(MalformedAnswer::from_str("ack").is_err());
+        assert!(!MalformedAnswer::Silent.is_nack(10));
+        assert!(MalformedAnswer::Nack.is_nack(1));
+        assert!(!MalformedAnswer::NackAfter(2).is_nack(1));
+        assert!(MalformedAnswer::NackAfter(2).is_nack(2));
+        assert_eq!(
+            FirmwareProfile::default().malformed_answer(),
+            MalformedAnswer::Silent
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Injection de trames TLV dans le pipeline des `middlewares` (sans port série)
+//!
+//! Une trame injectée est traitée comme si elle était reçue de l'AFSEC+ et la réponse du
+//! simulateur est retournée. Utile pour explorer le protocole et pour les tests 'négatifs'
+//! (trames incorrectes, messages inattendus...) sans matériel (commande `inject` de la console
+//! et `SimHandle::inject_frame`).
+//!
+//! Le contexte des conversations des trames injectées est propre au [`FrameInjector`] : il est
+//! indépendant de celui de la communication sur le port série.
+
+use std::sync::{Arc, Mutex};
+
+use crate::database::{IdUser, GROUP_AFSEC};
+use crate::profiling::{lock_database, Subsystem};
+use crate::test_summary;
+use crate::Database;
+
+use super::tlv_frame::RawFrame;
+use super::{
+    check_notification_changes, AliveAnswer, DatabaseAfsecComm, FirmwareProfile, MalformedAnswer,
+    Middlewares,
+};
+
+/// Décodage d'une trame au format hexa (ex: `02 0A 00 0A 03`, `020A000A03` ou `02:0A:00:0A:03`)
+/// # Errors
+/// Message d'erreur si la trame n'est pas une suite d'octets en hexa
+pub fn parse_hex_frame(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: String = hex
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, ':' | '-'))
+        .collect();
+    if digits.is_empty() || !digits.is_ascii() || !digits.len().is_multiple_of(2) {
+        return Err(format!("Trame hexa '{hex}' incorrecte"));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|pos| {
+            u8::from_str_radix(&digits[pos..pos + 2], 16)
+                .map_err(|_| format!("Octet '{}' incorrect", &digits[pos..pos + 2]))
+        })
+        .collect()
+}
+
+/// Encodage d'une trame au format hexa (octets séparés par un espace)
+pub fn format_hex_frame(octets: &[u8]) -> String {
+    octets
+        .iter()
+        .map(|octet| format!("{octet:02X}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Injecteur de trames TLV
+pub struct FrameInjector {
+    /// Communication avec l'AFSEC+ pour les trames injectées
+    afsec_service: DatabaseAfsecComm,
+
+    /// Gestionnaire des conversations pour les trames injectées
+    middlewares: Middlewares,
+}
+
+impl FrameInjector {
+    /// Constructeur
+    pub fn new(
+        thread_db: Arc<Mutex<Database>>,
+        debug_level: u8,
+        firmware_profile: FirmwareProfile,
+    ) -> Self {
+        let id_user: IdUser = lock_database(&thread_db, Subsystem::Afsec).get_id_user_in_group(
+            "AFSEC inject",
+            true,
+            GROUP_AFSEC,
+        );
+        Self {
+            afsec_service: DatabaseAfsecComm::new(thread_db, "inject".to_string(), debug_level)
+                .with_id_user(id_user)
+                .with_firmware_profile(firmware_profile),
+            middlewares: Middlewares::new(debug_level),
+        }
+    }
+
+    /// Impose la réponse à `AF_ALIVE` quand personne n'a rien à dire (voir
+    /// `DatabaseAfsecComm::with_alive_answer`)
+    #[must_use]
+    pub fn with_alive_answer(mut self, option_alive_answer: Option<AliveAnswer>) -> Self {
+        self.afsec_service = self.afsec_service.with_alive_answer(option_alive_answer);
+        self
+    }
+
+    /// Impose la réponse à une trame dont le contenu ne peut pas être décodé (voir
+    /// `DatabaseAfsecComm::with_malformed_answer`)
+    #[must_use]
+    pub fn with_malformed_answer(
+        mut self,
+        option_malformed_answer: Option<MalformedAnswer>,
+    ) -> Self {
+        self.afsec_service = self
+            .afsec_service
+            .with_malformed_answer(option_malformed_answer);
+        self
+    }
+
+    /// Traite une trame TLV comme si elle était reçue de l'AFSEC+ et retourne la réponse
+    /// Les modifications de la [`Database`] depuis la trame précédente sont d'abord notifiées
+    /// aux `middlewares`
+    pub fn inject(&mut self, request_raw_frame: RawFrame) -> RawFrame {
+        test_summary::record_fault("inject");
+        check_notification_changes(&mut self.afsec_service, &mut self.middlewares);
+        self.middlewares
+            .handle_request_raw_frame(&mut self.afsec_service, request_raw_frame)
+    }
+
+    /// Traite une trame TLV au format hexa et retourne la réponse au format hexa (vide si pas
+    /// de réponse)
+    /// # Errors
+    /// Message d'erreur si la trame n'est pas une suite d'octets en hexa
+    pub fn inject_hex(&mut self, hex: &str) -> Result<String, String> {
+        let octets = parse_hex_frame(hex)?;
+        let response = self.inject(RawFrame::new(&octets));
+        Ok(format_hex_frame(&response.encode()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_frame() {
+        assert_eq!(parse_hex_frame("02 0a 00 0A 03"), Ok(vec![2, 10, 0, 10, 3]));
+        assert_eq!(parse_hex_frame("02:0A-00"), Ok(vec![2, 10, 0]));
+        assert!(parse_hex_frame("020").is_err());
+        assert!(parse_hex_frame("0G").is_err());
+        assert!(parse_hex_frame("").is_err());
+        assert!(parse_hex_frame("0é").is_err());
+        assert_eq!(format_hex_frame(&[2, 10, 0]), "02 0A 00");
+    }
+
+    #[test]
+    fn test_inject_hex() {
+        let thread_db = Arc::new(Mutex::new(Database::default()));
+        let mut frame_injector = FrameInjector::new(thread_db, 0, FirmwareProfile::default());
+
+        // AF_ALIVE acquitté
+        let af_alive = format_hex_frame(&RawFrame::new_message(0x00).encode());
+        assert_eq!(
+            frame_injector.inject_hex(&af_alive),
+            Ok(format_hex_frame(&RawFrame::new_ack().encode()))
+        );
+
+        // Trame incorrecte: pas de réponse
+        assert_eq!(frame_injector.inject_hex("00"), Ok(String::new()));
+        assert!(frame_injector.inject_hex("zz").is_err());
+    }
+}
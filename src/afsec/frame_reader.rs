@@ -0,0 +1,117 @@
+//! Construction des trames reçues de l'AFSEC+ sur un port
+//!
+//! Au rythme de la liaison série, une trame arrive souvent en plusieurs morceaux lus lors de
+//! passages successifs dans la boucle de communication : la trame en cours de construction
+//! (`FrameState::Building`) est conservée d'une lecture à l'autre et abandonnée si aucun octet
+//! n'est reçu pendant le délai `FRAME_TIMEOUT` (voir `FrameReader::check_timeout`).
+
+use std::time::{Duration, Instant};
+
+use super::{FrameState, RawFrame};
+
+/// Délai max. sans octet reçu avant l'abandon d'une trame en cours de construction
+pub const FRAME_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Trame en cours de construction sur un port
+#[derive(Debug)]
+pub struct FrameReader {
+    /// Trame en cours de construction
+    raw_frame: RawFrame,
+
+    /// Date de réception du dernier octet de la trame en cours
+    option_last_octet: Option<Instant>,
+
+    /// Délai max. sans octet reçu avant l'abandon de la trame en cours
+    timeout: Duration,
+}
+
+impl Default for FrameReader {
+    fn default() -> Self {
+        Self::new(FRAME_TIMEOUT)
+    }
+}
+
+impl FrameReader {
+    /// Constructeur avec le délai max. sans octet reçu avant l'abandon de la trame en cours
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            raw_frame: RawFrame::default(),
+            option_last_octet: None,
+            timeout,
+        }
+    }
+
+    /// Ajoute des octets reçus à la date `now` à la trame en cours
+    /// Retourne l'état de la trame en cours
+    pub fn push(&mut self, octets: &[u8], now: Instant) -> FrameState {
+        if !octets.is_empty() {
+            self.raw_frame.extend(octets);
+            self.option_last_octet = Some(now);
+        }
+        self.raw_frame.get_state()
+    }
+
+    /// Retire la trame en cours (une nouvelle trame débute)
+    pub fn take(&mut self) -> RawFrame {
+        self.option_last_octet = None;
+        std::mem::take(&mut self.raw_frame)
+    }
+
+    /// Retire et retourne la trame en cours si elle est incomplète et qu'aucun octet n'est reçu
+    /// depuis le délai max. à la date `now`
+    pub fn check_timeout(&mut self, now: Instant) -> Option<RawFrame> {
+        let last_octet = self.option_last_octet?;
+        (self.raw_frame.get_state() == FrameState::Building
+            && now.saturating_duration_since(last_octet) >= self.timeout)
+            .then(|| self.take())
+    }
+
+    /// Délai max. sans octet reçu avant l'abandon de la trame en cours
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::afsec::tlv_frame::{ETX, STX};
+
+    #[test]
+    fn test_frame_reader() {
+        let start = Instant::now();
+        let mut frame_reader = FrameReader::default();
+
+        // Trame reçue en 2 morceaux
+        assert_eq!(frame_reader.push(&[STX, 0x10], start), FrameState::Building);
+        assert!(frame_reader
+            .check_timeout(start + FRAME_TIMEOUT / 2)
+            .is_none());
+        let now = start + FRAME_TIMEOUT / 2;
+        assert_eq!(frame_reader.push(&[0, 0x10, ETX], now), FrameState::Ok);
+        assert_eq!(frame_reader.take().encode(), vec![STX, 0x10, 0, 0x10, ETX]);
+        assert_eq!(frame_reader.push(&[], now), FrameState::Empty);
+    }
+
+    #[test]
+    fn test_frame_reader_timeout() {
+        let start = Instant::now();
+        let mut frame_reader = FrameReader::new(Duration::from_millis(10));
+        assert!(frame_reader.check_timeout(start).is_none());
+
+        // Trame incomplète abandonnée après le délai max. sans octet reçu
+        frame_reader.push(&[STX, 0x10, 1], start);
+        assert!(frame_reader
+            .check_timeout(start + Duration::from_millis(9))
+            .is_none());
+        let raw_frame = frame_reader
+            .check_timeout(start + Duration::from_millis(10))
+            .unwrap();
+        assert_eq!(raw_frame.get_state(), FrameState::Building);
+        assert_eq!(
+            frame_reader.push(&[], start + Duration::from_millis(10)),
+            FrameState::Empty
+        );
+    }
+}
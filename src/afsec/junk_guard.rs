@@ -0,0 +1,149 @@
+//! Protection contre un flot de trames inexploitables ('junk') reçues de l'AFSEC+
+//!
+//! Si l'AFSEC+ émet des octets inexploitables en continu, le simulateur ne doit pas tracer une
+//! ligne par trame 'junk' reçue. Au delà de `max_junk_per_sec` trames 'junk' par seconde :
+//!
+//! * Les trames 'junk' ne sont plus tracées individuellement: une trace récapitulative est faite
+//!   à la fin de chaque seconde de 'flood'
+//! * Optionnellement, le simulateur devient silencieux (les octets reçus sont ignorés) pendant
+//!   `silence` (comme le fait l'ICOM réel)
+//!
+//! Le nombre de trames 'junk' reçues est également décompté dans le tag `ID_TAG_SIM_JUNK_FRAMES`
+//! (voir le module `afsec`).
+
+use std::time::{Duration, Instant};
+
+/// Nombre de trames 'junk' par seconde par défaut au delà duquel on considère un 'flood'
+pub const DEFAULT_MAX_JUNK_PER_SEC: u32 = 10;
+
+/// Durée de la fenêtre de décompte des trames 'junk'
+const JUNK_WINDOW: Duration = Duration::from_secs(1);
+
+/// Détection d'un flot de trames 'junk'
+#[derive(Clone, Debug)]
+pub struct JunkGuard {
+    /// Nombre de trames 'junk' par seconde au delà duquel on considère un 'flood'
+    max_junk_per_sec: u32,
+
+    /// Durée pendant laquelle le simulateur devient silencieux lors d'un 'flood'
+    /// (`Duration::ZERO` pour rester actif)
+    silence: Duration,
+
+    /// Début de la fenêtre de décompte en cours
+    option_window_start: Option<Instant>,
+
+    /// Nombre de trames 'junk' dans la fenêtre de décompte en cours
+    nb_junk_in_window: u32,
+
+    /// Nombre de trames 'junk' non tracées dans la fenêtre de décompte en cours
+    nb_suppressed: u32,
+
+    /// Fin de la période silencieuse en cours
+    option_silent_until: Option<Instant>,
+}
+
+impl Default for JunkGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_JUNK_PER_SEC, Duration::ZERO)
+    }
+}
+
+impl JunkGuard {
+    /// Constructeur
+    pub fn new(max_junk_per_sec: u32, silence: Duration) -> Self {
+        Self {
+            max_junk_per_sec,
+            silence,
+            option_window_start: None,
+            nb_junk_in_window: 0,
+            nb_suppressed: 0,
+            option_silent_until: None,
+        }
+    }
+
+    /// Clôture la fenêtre de décompte si elle est terminée
+    /// Retourne le nombre de trames 'junk' non tracées de la fenêtre clôturée (None si aucune)
+    pub fn take_flood_report(&mut self, now: Instant) -> Option<u32> {
+        let window_start = self.option_window_start?;
+        if now.duration_since(window_start) < JUNK_WINDOW {
+            return None;
+        }
+        self.option_window_start = None;
+        self.nb_junk_in_window = 0;
+        let nb_suppressed = std::mem::take(&mut self.nb_suppressed);
+        (nb_suppressed > 0).then_some(nb_suppressed)
+    }
+
+    /// Prise en compte d'une trame 'junk' reçue
+    /// Retourne true si la trame peut être tracée individuellement (pas de 'flood')
+    pub fn record_junk(&mut self, now: Instant) -> bool {
+        if self.option_window_start.is_none() {
+            self.option_window_start = Some(now);
+        }
+        self.nb_junk_in_window = self.nb_junk_in_window.saturating_add(1);
+        if self.nb_junk_in_window <= self.max_junk_per_sec {
+            return true;
+        }
+
+        // 'Flood': Silence éventuel dès la détection
+        if self.nb_suppressed == 0 && !self.silence.is_zero() {
+            self.option_silent_until = Some(now + self.silence);
+        }
+        self.nb_suppressed += 1;
+        false
+    }
+
+    /// Indique si le simulateur doit rester silencieux (suite à un 'flood')
+    pub fn is_silent(&mut self, now: Instant) -> bool {
+        match self.option_silent_until {
+            Some(silent_until) if now < silent_until => true,
+            Some(_) => {
+                self.option_silent_until = None;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junk_flood() {
+        let mut junk_guard = JunkGuard::new(3, Duration::ZERO);
+        let start = Instant::now();
+
+        // Sous le seuil: Toutes les trames sont tracées
+        for _ in 0..3 {
+            assert!(junk_guard.record_junk(start));
+        }
+        assert_eq!(junk_guard.take_flood_report(start), None);
+
+        // Au delà du seuil: Les trames ne sont plus tracées
+        assert!(!junk_guard.record_junk(start));
+        assert!(!junk_guard.record_junk(start));
+        assert!(!junk_guard.is_silent(start));
+
+        // Fin de la fenêtre: Récapitulatif et nouvelle fenêtre
+        let later = start + JUNK_WINDOW;
+        assert_eq!(junk_guard.take_flood_report(later), Some(2));
+        assert_eq!(junk_guard.take_flood_report(later), None);
+        assert!(junk_guard.record_junk(later));
+    }
+
+    #[test]
+    fn test_junk_silence() {
+        let silence = Duration::from_millis(500);
+        let mut junk_guard = JunkGuard::new(1, silence);
+        let start = Instant::now();
+
+        assert!(junk_guard.record_junk(start));
+        assert!(!junk_guard.is_silent(start));
+        assert!(!junk_guard.record_junk(start));
+        assert!(junk_guard.is_silent(start));
+        assert!(junk_guard.is_silent(start + silence / 2));
+        assert!(!junk_guard.is_silent(start + silence));
+    }
+}
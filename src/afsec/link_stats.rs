@@ -0,0 +1,95 @@
+//! Statistiques d'un lien série avec l'AFSEC+
+//!
+//! Comme les registres de diagnostic de l'ICOM réel, chaque port (lien 1: principal, 2: secours)
+//! compte les trames reçues et émises, les ACK et NACK (dans les 2 sens), les trames
+//! inexploitables (erreurs de checksum, trames incomplètes...) et mémorise la date du dernier
+//! `AF_INIT`. Ces statistiques sont publiées chaque seconde dans les tags du simulateur (voir
+//! `Database::publish_link_stats`) pour la page de diagnostic de la supervision.
+
+use super::middleware::AF_INIT;
+use super::tlv_frame::RawFrame;
+use crate::database::SIM_NB_LINK_STATS;
+use crate::sim_clock;
+
+/// Compteurs d'un lien série avec l'AFSEC+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Nombre de trames valides reçues de l'AFSEC+
+    frames_in: u32,
+
+    /// Nombre de trames émises vers l'AFSEC+
+    frames_out: u32,
+
+    /// Nombre de ACK reçus ou émis
+    acks: u32,
+
+    /// Nombre de NACK reçus ou émis
+    nacks: u32,
+
+    /// Nombre de trames inexploitables reçues
+    junk: u32,
+
+    /// Date (secondes depuis le 01/01/1970 UTC, horloge simulée) du dernier `AF_INIT` reçu
+    last_init: u32,
+}
+
+impl LinkStats {
+    /// Comptage des ACK et NACK d'une trame
+    fn count_ack_nack(&mut self, raw_frame: &RawFrame) {
+        match raw_frame {
+            RawFrame::Ack => self.acks = self.acks.wrapping_add(1),
+            RawFrame::Nack => self.nacks = self.nacks.wrapping_add(1),
+            _ => (),
+        }
+    }
+
+    /// Comptage d'une trame valide reçue de l'AFSEC+
+    pub fn record_request(&mut self, raw_frame: &RawFrame) {
+        self.frames_in = self.frames_in.wrapping_add(1);
+        self.count_ack_nack(raw_frame);
+        if matches!(raw_frame, RawFrame::Ok(tag, ..) if *tag == AF_INIT) {
+            self.last_init = u32::try_from(sim_clock::now().as_secs()).unwrap_or(u32::MAX);
+        }
+    }
+
+    /// Comptage d'une trame émise vers l'AFSEC+
+    pub fn record_response(&mut self, raw_frame: &RawFrame) {
+        self.frames_out = self.frames_out.wrapping_add(1);
+        self.count_ack_nack(raw_frame);
+    }
+
+    /// Comptage d'une trame inexploitable reçue de l'AFSEC+
+    pub fn record_junk(&mut self) {
+        self.junk = self.junk.wrapping_add(1);
+    }
+
+    /// Valeurs des statistiques dans l'ordre de publication (voir `Database::publish_link_stats`)
+    pub fn values(&self) -> [u32; SIM_NB_LINK_STATS] {
+        [
+            self.frames_in,
+            self.frames_out,
+            self.acks,
+            self.nacks,
+            self.junk,
+            self.last_init,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_stats() {
+        let mut link_stats = LinkStats::default();
+        link_stats.record_request(&RawFrame::new_message(0x03));
+        link_stats.record_response(&RawFrame::new_ack());
+        link_stats.record_request(&RawFrame::new_nack());
+        link_stats.record_junk();
+        assert_eq!(link_stats.values(), [2, 1, 1, 1, 1, 0]);
+
+        link_stats.record_request(&RawFrame::new_message(AF_INIT));
+        assert!(link_stats.values()[5] > 0);
+    }
+}
@@ -0,0 +1,259 @@
+//! Surveillance du lien avec l'AFSEC+ : retransmissions et état 'lien coupé'
+//!
+//! Seules les trames transmises à l'initiative du simulateur sont surveillées : les données ou
+//! menus (`IC_DATA_IN`, `IC_PACK_IN`, `IC_MENU`, ...) poussés en réponse à `AF_ALIVE` (voir
+//! [`LinkWatchdog::is_sim_initiated`]). Les réponses aux requêtes de l'AFSEC+ (`IC_ALIVE`, ACK,
+//! ...) n'attendent pas de suite et ne sont pas surveillées.
+//!
+//! Du point de vue du simulateur, une trame surveillée est sans réponse si aucune
+//! trame valide n'est reçue de l'AFSEC+ dans le délai `retry_timeout` qui suit. La trame est
+//! alors retransmise, au plus `max_retries` fois de suite. Au delà, plutôt que de retransmettre
+//! indéfiniment sans rien dire, le lien passe dans l'état explicite 'coupé' : le simulateur
+//! tente périodiquement (`probe_period`) de rétablir la communication en retransmettant la
+//! dernière trame (trame en cours de réception abandonnée). La première trame valide reçue de
+//! l'AFSEC+ rétablit le lien.
+//!
+//! L'état du lien ([`LinkState`]) est publié dans le tag `ID_TAG_SIM_AFSEC_LINK` (voir le module
+//! `afsec`). Avec `max_retries` à 0 (par défaut), le lien n'est pas surveillé.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super::middleware::{AF_ALIVE, IC_ALIVE};
+use super::tlv_frame::RawFrame;
+
+/// Délai (en millisecondes) par défaut sans trame valide de l'AFSEC+ après une transmission
+/// au delà duquel la trame est retransmise
+pub const DEFAULT_RETRY_TIMEOUT: u64 = 1000;
+
+/// Période (en secondes) par défaut des tentatives de rétablissement d'un lien coupé
+pub const DEFAULT_PROBE_PERIOD: u64 = 5;
+
+/// État du lien avec l'AFSEC+ (valeur publiée dans le tag `ID_TAG_SIM_AFSEC_LINK`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkState {
+    /// Lien établi (ou non surveillé)
+    #[default]
+    Up,
+
+    /// Retransmissions en cours (nombre de transmissions consécutives sans réponse)
+    Retrying(u32),
+
+    /// Lien coupé : tentatives périodiques de rétablissement
+    Down,
+}
+
+impl LinkState {
+    /// Valeur publiée dans le tag `ID_TAG_SIM_AFSEC_LINK` (0: établi, 1: retransmissions,
+    /// 2: coupé)
+    pub fn to_u16(self) -> u16 {
+        match self {
+            LinkState::Up => 0,
+            LinkState::Retrying(_) => 1,
+            LinkState::Down => 2,
+        }
+    }
+}
+
+impl fmt::Display for LinkState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinkState::Up => write!(f, "Up"),
+            LinkState::Retrying(nb_unanswered) => write!(f, "Retrying ({nb_unanswered})"),
+            LinkState::Down => write!(f, "Down"),
+        }
+    }
+}
+
+/// Action demandée par la surveillance du lien
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkAction {
+    /// Retransmission de la dernière trame
+    Retry,
+
+    /// Passage à l'état 'lien coupé'
+    Down,
+
+    /// Tentative de rétablissement du lien coupé (retransmission de la dernière trame)
+    Probe,
+}
+
+/// Surveillance du lien avec l'AFSEC+
+#[derive(Clone, Debug)]
+pub struct LinkWatchdog {
+    /// Nombre max. de retransmissions consécutives avant de considérer le lien coupé
+    /// (0 pour ne pas surveiller le lien)
+    max_retries: u32,
+
+    /// Délai sans trame valide de l'AFSEC+ après une transmission avant de retransmettre
+    retry_timeout: Duration,
+
+    /// Période des tentatives de rétablissement d'un lien coupé
+    probe_period: Duration,
+
+    /// État du lien
+    state: LinkState,
+
+    /// Échéance de la réponse attendue de l'AFSEC+ (ou de la prochaine tentative de
+    /// rétablissement si le lien est coupé)
+    option_deadline: Option<Instant>,
+}
+
+impl Default for LinkWatchdog {
+    fn default() -> Self {
+        Self::new(
+            0,
+            Duration::from_millis(DEFAULT_RETRY_TIMEOUT),
+            Duration::from_secs(DEFAULT_PROBE_PERIOD),
+        )
+    }
+}
+
+impl LinkWatchdog {
+    /// Constructeur
+    pub fn new(max_retries: u32, retry_timeout: Duration, probe_period: Duration) -> Self {
+        Self {
+            max_retries,
+            retry_timeout,
+            probe_period,
+            state: LinkState::Up,
+            option_deadline: None,
+        }
+    }
+
+    /// Indique si le lien est surveillé
+    pub fn is_enabled(&self) -> bool {
+        self.max_retries > 0
+    }
+
+    /// État du lien
+    pub fn state(&self) -> LinkState {
+        self.state
+    }
+
+    /// Indique si la réponse à une requête de l'AFSEC+ est une transmission à l'initiative du
+    /// simulateur (message autre que `IC_ALIVE` en réponse à `AF_ALIVE`), seule surveillée
+    pub fn is_sim_initiated(request_raw_frame: &RawFrame, response_raw_frame: &RawFrame) -> bool {
+        matches!(request_raw_frame, RawFrame::Ok(AF_ALIVE, ..))
+            && matches!(response_raw_frame, RawFrame::Ok(tag, ..) if *tag != IC_ALIVE)
+    }
+
+    /// Enregistre la transmission d'une trame à l'initiative du simulateur (hors retransmission)
+    pub fn record_transmission(&mut self, now: Instant) {
+        if self.is_enabled() && self.state != LinkState::Down && self.option_deadline.is_none() {
+            self.option_deadline = Some(now + self.retry_timeout);
+        }
+    }
+
+    /// Enregistre la réception d'une trame valide de l'AFSEC+
+    /// Retourne true si l'état du lien change
+    pub fn record_answer(&mut self) -> bool {
+        self.option_deadline = None;
+        let is_changed = self.state != LinkState::Up;
+        self.state = LinkState::Up;
+        is_changed
+    }
+
+    /// Surveillance à une date : action à effectuer éventuellement
+    pub fn check(&mut self, now: Instant) -> Option<LinkAction> {
+        let deadline = self.option_deadline?;
+        if now < deadline {
+            return None;
+        }
+        match self.state {
+            LinkState::Down => {
+                self.option_deadline = Some(now + self.probe_period);
+                Some(LinkAction::Probe)
+            }
+            LinkState::Up | LinkState::Retrying(_) => {
+                let nb_unanswered = match self.state {
+                    LinkState::Retrying(nb_unanswered) => nb_unanswered + 1,
+                    _ => 1,
+                };
+                if nb_unanswered > self.max_retries {
+                    self.state = LinkState::Down;
+                    self.option_deadline = Some(now + self.probe_period);
+                    Some(LinkAction::Down)
+                } else {
+                    self.state = LinkState::Retrying(nb_unanswered);
+                    self.option_deadline = Some(now + self.retry_timeout);
+                    Some(LinkAction::Retry)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_watchdog() {
+        let mut watchdog = LinkWatchdog::new(2, Duration::from_millis(100), Duration::from_secs(1));
+        let t0 = Instant::now();
+        watchdog.record_transmission(t0);
+        assert_eq!(watchdog.check(t0 + Duration::from_millis(50)), None);
+
+        // 2 retransmissions puis lien coupé
+        let t1 = t0 + Duration::from_millis(100);
+        assert_eq!(watchdog.check(t1), Some(LinkAction::Retry));
+        assert_eq!(watchdog.state(), LinkState::Retrying(1));
+        let t2 = t1 + Duration::from_millis(100);
+        assert_eq!(watchdog.check(t2), Some(LinkAction::Retry));
+        let t3 = t2 + Duration::from_millis(100);
+        assert_eq!(watchdog.check(t3), Some(LinkAction::Down));
+        assert_eq!(watchdog.state().to_u16(), 2);
+
+        // Tentatives périodiques de rétablissement
+        assert_eq!(watchdog.check(t3 + Duration::from_millis(500)), None);
+        assert_eq!(
+            watchdog.check(t3 + Duration::from_secs(1)),
+            Some(LinkAction::Probe)
+        );
+
+        // Lien rétabli par une trame valide
+        assert!(watchdog.record_answer());
+        assert_eq!(watchdog.state(), LinkState::Up);
+        assert!(!watchdog.record_answer());
+        assert_eq!(watchdog.check(t3 + Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn test_is_sim_initiated() {
+        use crate::afsec::middleware::{AF_DATA_IN, IC_DATA_IN};
+
+        let af_alive = RawFrame::new_message(AF_ALIVE);
+        assert!(LinkWatchdog::is_sim_initiated(
+            &af_alive,
+            &RawFrame::new_message(IC_DATA_IN)
+        ));
+        assert!(!LinkWatchdog::is_sim_initiated(
+            &af_alive,
+            &RawFrame::new_message(IC_ALIVE)
+        ));
+        assert!(!LinkWatchdog::is_sim_initiated(
+            &af_alive,
+            &RawFrame::new_nack()
+        ));
+
+        // Réponse à une requête de l'AFSEC+ (qui n'attend pas de suite)
+        assert!(!LinkWatchdog::is_sim_initiated(
+            &RawFrame::new_message(AF_DATA_IN),
+            &RawFrame::new_message(IC_DATA_IN)
+        ));
+        assert!(!LinkWatchdog::is_sim_initiated(
+            &RawFrame::new_message(AF_DATA_IN),
+            &RawFrame::new_ack()
+        ));
+    }
+
+    #[test]
+    fn test_link_watchdog_disabled() {
+        let mut watchdog = LinkWatchdog::default();
+        let t0 = Instant::now();
+        watchdog.record_transmission(t0);
+        assert_eq!(watchdog.check(t0 + Duration::from_secs(60)), None);
+        assert_eq!(watchdog.state(), LinkState::Up);
+    }
+}
@@ -1,8 +1,10 @@
 //! Contexte d'exécution pour les différents `middlewares`
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Instant;
 
-use super::{IdTag, RecordData, TValue};
+use super::{IdTag, NotificationQueue, ProtocolStats, RecordData, TValue};
+use crate::database::MenuPush;
 
 /// Structure de contexte commune à tous les `middlewares`
 // ATTENTION: Chaque `middleware` ne doit pas avoir sa propre structure de données
@@ -13,20 +15,29 @@ pub struct Context {
     /// Niveau pour l'affichage des traces
     pub debug_level: u8,
 
-    /// Nombre de INIT depuis le début
-    pub nb_init: usize,
+    /// Compteurs des conversations (persistants avec l'option `--protocol-stats`)
+    pub stats: ProtocolStats,
 
-    /// Nombre de PACK_OUT depuis le début
-    pub nb_pack_out: usize,
+    /// Indicateur à true après une coupure d'alimentation simulée de l'AFSEC+ et jusqu'au
+    /// `AF_INIT` suivant (les autres requêtes sont refusées)
+    pub is_initializing: bool,
 
-    /// Nombre de PACK_IN depuis le début
-    pub nb_pack_in: usize,
+    /// Mode de fonctionnement de l'AFSEC+ (`D_MODE_AFSEC`) annoncé dans `AF_INIT` ou lors du
+    /// dernier changement de mode
+    pub option_mode_afsec: Option<u16>,
 
-    /// Nombre de DATA_OUT depuis le début
-    pub nb_data_out: usize,
+    /// Pictogrammes de la face avant (`D_MENU_PICTOS`) reçus en dernier de l'AFSEC+
+    pub option_menu_pictos: Option<u32>,
 
-    /// Nombre de DATA_IN depuis le début
-    pub nb_data_in: usize,
+    /// Fin de la pause des transmissions `DATA_IN` et `PACK_IN` demandée par l'AFSEC+
+    /// (`D_BUSY_DELAY`)
+    pub option_busy_until: Option<Instant>,
+
+    /// Menus à pousser vers l'afficheur de l'AFSEC+ (un menu à chaque `AF_ALIVE`)
+    pub menu_pushes: VecDeque<MenuPush>,
+
+    /// Identifiant du dernier menu poussé en attente de la réponse de l'opérateur (`AF_MENU`)
+    pub option_menu_pushed: Option<u16>,
 
     /// Numéro de zone de la conversation en cours
     pub option_zone: Option<u8>,
@@ -43,8 +54,16 @@ pub struct Context {
     /// `RecordData` vus pendant la conversation DATA_OUT
     pub record_datas: Vec<RecordData>,
 
-    /// Liste des notification_changes pour la conversation DATA_IN
-    pub notification_changes: Vec<(IdTag, TValue)>,
+    /// File (ordonnée par priorité) des notification_changes pour la conversation DATA_IN
+    pub notification_changes: NotificationQueue,
+
+    /// Modifications (tag, valeur, priorité) transmises dans le dernier `IC_DATA_IN` et en
+    /// attente de confirmation par l'AFSEC+ (remises en file en cas d'échec)
+    pub data_in_in_flight: Vec<(IdTag, TValue, u8)>,
+
+    /// Zone de la dernière modification transmise dans un `IC_DATA_IN` (entrelacement des zones,
+    /// voir `NotificationQueue::front_interleaved`)
+    pub option_data_in_zone: Option<u8>,
 
     /// Contexte pour les journaux des enregistrements
     pub records: Records,
@@ -64,6 +83,13 @@ impl Context {
             ..Default::default()
         }
     }
+
+    /// Indique si l'AFSEC+ a demandé une pause des transmissions `DATA_IN` et `PACK_IN` qui
+    /// n'est pas terminée
+    pub fn is_busy(&self) -> bool {
+        self.option_busy_until
+            .is_some_and(|busy_until| Instant::now() < busy_until)
+    }
 }
 
 /// Sous-structure du contexte pour les journaux (`DATA_OUT_TABLE_INDEX`)
@@ -104,6 +130,23 @@ impl Records {
             self.index_max.insert(zone, index);
         }
     }
+
+    /// Index (zone, min, max) de toutes les zones, par ordre croissant des zones
+    pub fn indexes(&self) -> Vec<(u8, u64, u64)> {
+        let mut zones: Vec<u8> = self.index_max.keys().copied().collect();
+        zones.sort_unstable();
+        zones
+            .into_iter()
+            .map(|zone| (zone, self.get_index_min(zone), self.get_index_max(zone)))
+            .collect()
+    }
+
+    /// Attribue l'index suivant de la zone à un nouvel enregistrement
+    pub fn next_index(&mut self, zone: u8) -> u64 {
+        let index = self.get_index_max(zone) + 1;
+        self.set_index(zone, index);
+        index
+    }
 }
 
 /// Sous-structure du contexte pour les transactions 'pack-in'
@@ -159,5 +202,10 @@ mod tests {
         records.set_index(2, 6789);
         assert_eq!(records.get_index_min(2), 1234);
         assert_eq!(records.get_index_max(2), 6789);
+
+        assert_eq!(records.next_index(2), 6790);
+        assert_eq!(records.next_index(3), 1);
+        assert_eq!(records.get_index_min(2), 1234);
+        assert_eq!(records.get_index_max(2), 6790);
     }
 }
@@ -0,0 +1,268 @@
+//! Sauvegarde du contexte des conversations avec l'AFSEC+ (option `--context-state`)
+//!
+//! Lors de l'arrêt du simulateur (ctrl+C), le thread de communication avec l'AFSEC+ sauvegarde
+//! dans un fichier la partie du [`Context`] qui ne doit pas être perdue en cours de campagne :
+//! les blocs `PACK_IN` à transmettre, les index des journaux et la file des modifications à
+//! transmettre par `DATA_IN` (précédée des modifications transmises mais non encore confirmées
+//! par l'AFSEC+). Ce contexte est restauré au démarrage suivant.
+//!
+//! Les conversations en cours ne sont pas sauvegardées : les blocs d'une transaction `PACK_IN`
+//! en cours sont de nouveau à transmettre après la restauration.
+//!
+//! Format (entiers en 'big endian') :
+//!
+//! * `CONTEXT_MAGIC` (4 octets) puis version du format (`u16`, `CONTEXT_VERSION`)
+//! * Nombre de blocs `PACK_IN` (`u16`) puis chaque numéro de bloc (`u8`)
+//! * Nombre de journaux (`u16`) puis chaque zone (`u8`), index min. et max. (`u64`)
+//! * Nombre de modifications (`u32`) puis chaque modification : [`IdTag`] et valeur (voir le
+//!   module `saved_state` de la `database`) et priorité (`u8`)
+//!
+//! La version 1 du format (nombres de blocs et de journaux en `u8`, limités à 255 journaux alors
+//! qu'il peut y en avoir un par zone) est toujours acceptée en lecture.
+
+use std::fmt;
+
+use super::{Context, IdTag, TValue};
+use crate::database::{id_tag_bytes, t_value_bytes, StateReader};
+
+/// Identification d'un fichier de contexte des conversations avec l'AFSEC+
+pub const CONTEXT_MAGIC: [u8; 4] = *b"ICCX";
+
+/// Version courante du format
+pub const CONTEXT_VERSION: u16 = 2;
+
+/// Contexte sauvegardé des conversations avec l'AFSEC+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContextState {
+    /// Blocs `PACK_IN` (0 à 7) à transmettre
+    pub pack_in_blocs: Vec<u8>,
+
+    /// Index des journaux (zone, index min., index max.)
+    pub record_indexes: Vec<(u8, u64, u64)>,
+
+    /// Modifications à transmettre par `DATA_IN` (tag, valeur, priorité) dans l'ordre de
+    /// transmission
+    pub notification_changes: Vec<(IdTag, TValue, u8)>,
+}
+
+impl fmt::Display for ContextState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} blocs PACK_IN, {} journaux, {} modifications DATA_IN",
+            self.pack_in_blocs.len(),
+            self.record_indexes.len(),
+            self.notification_changes.len()
+        )
+    }
+}
+
+impl ContextState {
+    /// Contenu du fichier de contexte (format `CONTEXT_VERSION`)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = CONTEXT_MAGIC.to_vec();
+        bytes.extend(CONTEXT_VERSION.to_be_bytes());
+        bytes.extend(
+            u16::try_from(self.pack_in_blocs.len())
+                .unwrap()
+                .to_be_bytes(),
+        );
+        bytes.extend(&self.pack_in_blocs);
+        bytes.extend(
+            u16::try_from(self.record_indexes.len())
+                .unwrap()
+                .to_be_bytes(),
+        );
+        for (zone, index_min, index_max) in &self.record_indexes {
+            bytes.push(*zone);
+            bytes.extend(index_min.to_be_bytes());
+            bytes.extend(index_max.to_be_bytes());
+        }
+        bytes.extend(
+            u32::try_from(self.notification_changes.len())
+                .unwrap()
+                .to_be_bytes(),
+        );
+        for (id_tag, t_value, priority) in &self.notification_changes {
+            bytes.extend(id_tag_bytes(*id_tag));
+            bytes.extend(t_value_bytes(t_value));
+            bytes.push(*priority);
+        }
+        bytes
+    }
+
+    /// Décodage d'un fichier de contexte
+    /// # Errors
+    /// Fichier qui n'est pas un contexte, version inconnue ou contenu incorrect
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = StateReader::new(bytes);
+        if reader.take(CONTEXT_MAGIC.len()).ok() != Some(&CONTEXT_MAGIC[..]) {
+            return Err("Fichier de contexte AFSEC non reconnu".to_string());
+        }
+        match reader.take_u16()? {
+            version @ 1..=CONTEXT_VERSION => Self::from_reader(&mut reader, version),
+            version => Err(format!(
+                "Version {version} du fichier de contexte non supportée (version max. {CONTEXT_VERSION})"
+            )),
+        }
+    }
+
+    /// Décodage du contenu d'un fichier de contexte dans une version (après la version)
+    fn from_reader(reader: &mut StateReader, version: u16) -> Result<Self, String> {
+        // Nombres de blocs et de journaux en `u8` dans la version 1
+        let take_count = |reader: &mut StateReader| -> Result<u16, String> {
+            if version == 1 {
+                Ok(u16::from(reader.take_u8()?))
+            } else {
+                reader.take_u16()
+            }
+        };
+        let nb_blocs = take_count(reader)?;
+        let pack_in_blocs = reader.take(usize::from(nb_blocs))?.to_vec();
+        if let Some(bloc) = pack_in_blocs.iter().find(|bloc| **bloc > 7) {
+            return Err(format!("Bloc PACK_IN {bloc} incorrect"));
+        }
+        let nb_zones = take_count(reader)?;
+        let mut record_indexes = vec![];
+        for _ in 0..nb_zones {
+            record_indexes.push((reader.take_u8()?, reader.take_u64()?, reader.take_u64()?));
+        }
+        let nb_changes = reader.take_u32()?;
+        let mut notification_changes = vec![];
+        for _ in 0..nb_changes {
+            let id_tag = reader.take_id_tag()?;
+            let t_value = reader.take_t_value(id_tag)?;
+            notification_changes.push((id_tag, t_value, reader.take_u8()?));
+        }
+        if !reader.is_empty() {
+            return Err("Octets en trop à la fin du fichier de contexte".to_string());
+        }
+        Ok(Self {
+            pack_in_blocs,
+            record_indexes,
+            notification_changes,
+        })
+    }
+
+    /// Lecture d'un fichier de contexte
+    /// # Errors
+    /// Erreur de lecture ou contenu incorrect du fichier
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        let bytes =
+            std::fs::read(filename).map_err(|e| format!("Erreur lecture '{filename}': {e}"))?;
+        Self::from_bytes(&bytes).map_err(|e| format!("'{filename}': {e}"))
+    }
+
+    /// Écriture dans un fichier de contexte
+    /// # Errors
+    /// Erreur d'écriture du fichier
+    pub fn to_file(&self, filename: &str) -> Result<(), String> {
+        std::fs::write(filename, self.to_bytes())
+            .map_err(|e| format!("Erreur écriture '{filename}': {e}"))
+    }
+}
+
+impl Context {
+    /// Contexte à sauvegarder
+    pub fn save_state(&self) -> ContextState {
+        let mut pack_in_blocs: Vec<u8> = self
+            .pack_in
+            .set_blocs
+            .union(&self.pack_in.set_pending_blocs)
+            .copied()
+            .collect();
+        pack_in_blocs.sort_unstable();
+        let mut notification_changes = self.data_in_in_flight.clone();
+        notification_changes.extend(self.notification_changes.entries());
+        ContextState {
+            pack_in_blocs,
+            record_indexes: self.records.indexes(),
+            notification_changes,
+        }
+    }
+
+    /// Restauration d'un contexte sauvegardé (hors transaction `PACK_IN` en cours)
+    pub fn restore_state(&mut self, context_state: &ContextState) {
+        self.pack_in
+            .set_blocs
+            .extend(context_state.pack_in_blocs.iter().copied());
+        for (zone, index_min, index_max) in &context_state.record_indexes {
+            self.records.set_index(*zone, *index_min);
+            self.records.set_index(*zone, *index_max);
+        }
+        for (id_tag, t_value, priority) in &context_state.notification_changes {
+            self.notification_changes
+                .push(*id_tag, t_value.clone(), *priority);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_state() {
+        let mut context = Context::default();
+        context.pack_in.is_transaction = true;
+        context.pack_in.set_blocs.insert(3);
+        context.pack_in.set_pending_blocs.insert(1);
+        context.records.set_index(2, 10);
+        context.records.set_index(2, 42);
+        context.records.next_index(3);
+        let id_tag = IdTag::new(4, 0x0100, [0, 0, 0]);
+        context
+            .notification_changes
+            .push(id_tag, TValue::U16(1234), 0);
+        context
+            .notification_changes
+            .push(IdTag::new(4, 0x0200, [1, 0, 0]), TValue::F32(-1.5), 5);
+        context
+            .data_in_in_flight
+            .push((IdTag::new(4, 0x0300, [0, 0, 0]), TValue::U16(7), 0));
+
+        let context_state = context.save_state();
+        assert_eq!(context_state.pack_in_blocs, [1, 3]);
+        assert_eq!(context_state.record_indexes, [(2, 10, 42), (3, 1, 1)]);
+        let bytes = context_state.to_bytes();
+        assert_eq!(ContextState::from_bytes(&bytes), Ok(context_state.clone()));
+        assert!(ContextState::from_bytes(b"ICST").is_err());
+        assert!(ContextState::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        // Version 1 (nombres de blocs et de journaux en `u8`)
+        let v1_bytes = [
+            &CONTEXT_MAGIC[..],
+            &[0, 1, 1, 3, 1, 2],
+            &10_u64.to_be_bytes(),
+            &42_u64.to_be_bytes(),
+            &[0, 0, 0, 0],
+        ]
+        .concat();
+        let v1_state = ContextState::from_bytes(&v1_bytes).unwrap();
+        assert_eq!(v1_state.pack_in_blocs, [3]);
+        assert_eq!(v1_state.record_indexes, [(2, 10, 42)]);
+
+        let mut restored = Context::default();
+        restored.restore_state(&context_state);
+        assert!(!restored.pack_in.is_transaction);
+        assert_eq!(restored.pack_in.set_blocs.len(), 2);
+        assert_eq!(restored.records.get_index_min(2), 10);
+        assert_eq!(restored.records.next_index(2), 43);
+        assert_eq!(
+            restored.notification_changes.front(),
+            Some((IdTag::new(4, 0x0200, [1, 0, 0]), TValue::F32(-1.5)))
+        );
+        assert_eq!(restored.save_state().notification_changes.len(), 3);
+    }
+
+    #[test]
+    fn test_context_state_all_zones() {
+        // Un journal dans chacune des 256 zones
+        let context_state = ContextState {
+            record_indexes: (0..=u8::MAX).map(|zone| (zone, 1, 2)).collect(),
+            ..Default::default()
+        };
+        let bytes = context_state.to_bytes();
+        assert_eq!(ContextState::from_bytes(&bytes), Ok(context_state));
+    }
+}
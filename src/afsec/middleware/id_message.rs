@@ -1,5 +1,7 @@
 //! Liste des constantes pour les types de messages TLV entre l'AFSEC+ et l'ICOM et
 //! les types de données dans les messages
+//!
+//! `name_of` et `data_item_name` retournent le nom symbolique de ces constantes pour les traces.
 
 #![allow(dead_code)]
 
@@ -45,6 +47,13 @@ pub const D_APPLI_VERSION: u8 = 0x05;
 pub const D_APPLI_CONFIG: u8 = 0x06;
 pub const D_MODE_AFSEC: u8 = 0x07;
 pub const D_LANGUAGE: u8 = 0x08;
+// Projet de spécification: délai (ms, u16) pendant lequel le résident ne veut plus recevoir de
+// `IC_DATA_IN` ou `IC_PACK_IN` (0 pour reprendre immédiatement)
+pub const D_BUSY_DELAY: u8 = 0x09;
+// Projet de spécification: numéro de série et adresse 'MAC' de l'ICOM (chaînes de caractères
+// dans `IC_INIT`, voir le module `sim_identity` de la `Database`)
+pub const D_ICOM_SERIAL_NUMBER: u8 = 0x0A;
+pub const D_ICOM_MAC_ADDRESS: u8 = 0x0B;
 
 pub const D_MENU_ID: u8 = 0x10;
 pub const D_MENU_ID_IN_PROGRESS: u8 = 0x11;
@@ -78,3 +87,91 @@ pub const D_TEST_NB_REQS: u8 = 0x71;
 pub const D_TEST_NB_REPS: u8 = 0x72;
 
 pub const D_PACK_PAYLOAD: u8 = 0xB0;
+
+/// Nom symbolique d'un type de message (`?` si inconnu)
+pub fn name_of(tag: u8) -> &'static str {
+    match tag {
+        AF_ALIVE => "AF_ALIVE",
+        IC_ALIVE => "IC_ALIVE",
+        AF_INIT => "AF_INIT",
+        IC_INIT => "IC_INIT",
+        AF_MENU => "AF_MENU",
+        IC_MENU => "IC_MENU",
+        AF_DATA_OUT => "AF_DATA_OUT",
+        IC_DATA_OUT => "IC_DATA_OUT",
+        AF_DATA_IN => "AF_DATA_IN",
+        IC_DATA_IN => "IC_DATA_IN",
+        AF_DATA_OUT_TABLE_INDEX => "AF_DATA_OUT_TABLE_INDEX",
+        IC_DATA_OUT_TABLE_INDEX => "IC_DATA_OUT_TABLE_INDEX",
+        AF_DOWNLOAD => "AF_DOWNLOAD",
+        IC_DOWNLOAD => "IC_DOWNLOAD",
+        AF_TEST => "AF_TEST",
+        IC_TEST => "IC_TEST",
+        AF_PACK_OUT => "AF_PACK_OUT",
+        IC_PACK_OUT => "IC_PACK_OUT",
+        AF_PACK_IN => "AF_PACK_IN",
+        IC_PACK_IN => "IC_PACK_IN",
+        _ => "?",
+    }
+}
+
+/// Nom symbolique d'un type de donnée dans un message (`?` si inconnu)
+pub fn data_item_name(tag: u8) -> &'static str {
+    match tag {
+        D_PROTOCOLE_VERSION => "D_PROTOCOLE_VERSION",
+        D_ICOM_VERSION => "D_ICOM_VERSION",
+        D_RESIDENT_VERSION => "D_RESIDENT_VERSION",
+        D_APPLI_NUMBER => "D_APPLI_NUMBER",
+        D_APPLI_VERSION => "D_APPLI_VERSION",
+        D_APPLI_CONFIG => "D_APPLI_CONFIG",
+        D_MODE_AFSEC => "D_MODE_AFSEC",
+        D_LANGUAGE => "D_LANGUAGE",
+        D_BUSY_DELAY => "D_BUSY_DELAY",
+        D_ICOM_SERIAL_NUMBER => "D_ICOM_SERIAL_NUMBER",
+        D_ICOM_MAC_ADDRESS => "D_ICOM_MAC_ADDRESS",
+        D_MENU_ID => "D_MENU_ID",
+        D_MENU_ID_IN_PROGRESS => "D_MENU_ID_IN_PROGRESS",
+        D_MENU_SHORT_DISPLAY => "D_MENU_SHORT_DISPLAY",
+        D_MENU_LONG_DISPLAY => "D_MENU_LONG_DISPLAY",
+        D_MENU_PICTOS => "D_MENU_PICTOS",
+        D_MENU_ID_ON_BP_OK => "D_MENU_ID_ON_BP_OK",
+        D_MENU_ID_ON_BP_MENU => "D_MENU_ID_ON_BP_MENU",
+        D_MENU_ID_ON_BP_CLEAR => "D_MENU_ID_ON_BP_CLEAR",
+        D_MENU_VALUE_INIT => "D_MENU_VALUE_INIT",
+        D_MENU_CHOICE_LIST => "D_MENU_CHOICE_LIST",
+        D_MENU_INPUT_MASK => "D_MENU_INPUT_MASK",
+        D_MENU_USER_INPUT => "D_MENU_USER_INPUT",
+        D_DATA_ERROR => "D_DATA_ERROR",
+        D_DATA_ZONE => "D_DATA_ZONE",
+        D_DATA_TABLE_INDEX => "D_DATA_TABLE_INDEX",
+        D_DATA_TAG => "D_DATA_TAG",
+        D_DATA_VALUE => "D_DATA_VALUE",
+        D_DATA_FIRST_TABLE_INDEX => "D_DATA_FIRST_TABLE_INDEX",
+        D_DATA_LAST_TABLE_INDEX => "D_DATA_LAST_TABLE_INDEX",
+        D_DOWNLOAD_SECTION => "D_DOWNLOAD_SECTION",
+        D_DOWNLOAD_NAME => "D_DOWNLOAD_NAME",
+        D_DOWNLOAD_NB_RECORDS => "D_DOWNLOAD_NB_RECORDS",
+        D_DOWNLOAD_STATUS => "D_DOWNLOAD_STATUS",
+        D_DOWNLOAD_RECORD => "D_DOWNLOAD_RECORD",
+        D_DOWNLOAD_END => "D_DOWNLOAD_END",
+        D_TEST_NB_REQS => "D_TEST_NB_REQS",
+        D_TEST_NB_REPS => "D_TEST_NB_REPS",
+        D_PACK_PAYLOAD => "D_PACK_PAYLOAD",
+        _ => "?",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(name_of(AF_DATA_OUT), "AF_DATA_OUT");
+        assert_eq!(name_of(IC_PACK_IN), "IC_PACK_IN");
+        assert_eq!(name_of(0x42), "?");
+        assert_eq!(data_item_name(D_DATA_VALUE), "D_DATA_VALUE");
+        assert_eq!(data_item_name(D_PACK_PAYLOAD), "D_PACK_PAYLOAD");
+        assert_eq!(data_item_name(0x99), "?");
+    }
+}
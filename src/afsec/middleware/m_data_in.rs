@@ -4,20 +4,46 @@
 //! La conversation est engagée par l'ICOM sur un `AF_ALIVE` ou sur invitation à poursuivre par
 //! un `AF_DATA_IN`
 //!
-//! Les données transmises sont les `notification_changes` reçues des autres utilisateurs.
+//! Les données transmises sont les `notification_changes` reçues des autres utilisateurs,
+//! par ordre de priorité des tags et en entrelaçant les zones d'une même priorité d'une trame à
+//! l'autre (voir `NotificationQueue`) : chaque `IC_DATA_IN` transmet un échantillon
+//! représentatif des zones modifiées plutôt que toutes les modifications de la zone la plus
+//! active.
+//!
+//! Les données d'un `IC_DATA_IN` restent 'en vol' (`Context::data_in_in_flight`) jusqu'à la
+//! requête suivante de l'AFSEC+ : un ACK, un `AF_DATA_IN` ou toute autre requête confirme leur
+//! réception. Un NACK, l'abandon de la conversation (délai d'inactivité) ou une réinitialisation
+//! des communications les remet en tête de file pour être transmises de nouveau.
+//!
+//! Rien n'est transmis pendant une pause demandée par l'AFSEC+ (`D_BUSY_DELAY`).
 
 use crate::afsec::DEBUG_LEVEL_SOME;
 
 use super::{
     id_message, utils, CommonMiddlewareTrait, Context, DataFrame, DataItem, DatabaseAfsecComm,
-    IdTag, IdUser, RawFrame, TValue, TAG_DATA_PACK,
+    IdTag, IdTagPattern, IdUser, RawFrame, TValue, TAG_DATA_PACK,
 };
 
 #[derive(Default)]
 pub struct MDataIn {}
 
 impl CommonMiddlewareTrait for MDataIn {
-    fn reset_conversation(&self, _context: &mut Context) {}
+    fn reset_conversation(&self, context: &mut Context) {
+        // Conversation interrompue sans confirmation de l'AFSEC+
+        requeue_in_flight(context);
+    }
+
+    fn interests(&self) -> Vec<IdTagPattern> {
+        vec![IdTagPattern::ANY]
+    }
+
+    fn exclusions(&self) -> Vec<IdTagPattern> {
+        // Les changements des tags `DATA_PACK` sont gérés par le 'pack-in'
+        vec![IdTagPattern {
+            num_tag: Some(TAG_DATA_PACK),
+            ..IdTagPattern::ANY
+        }]
+    }
 
     fn get_conversation(
         &self,
@@ -25,20 +51,32 @@ impl CommonMiddlewareTrait for MDataIn {
         _afsec_service: &mut DatabaseAfsecComm,
         request_data_frame: &DataFrame,
     ) -> Option<RawFrame> {
+        // Réponse de l'AFSEC+ au `IC_DATA_IN` précédent
+        if request_data_frame.is_simple_nack() {
+            requeue_in_flight(context);
+        } else {
+            context.data_in_in_flight.clear();
+        }
+
         if ![id_message::AF_ALIVE, id_message::AF_DATA_IN].contains(&request_data_frame.get_tag()) {
             // Non concerné par cette conversation
             return None;
         }
 
+        if context.is_busy() {
+            // L'AFSEC+ a demandé une pause des transmissions (`D_BUSY_DELAY`)
+            return None;
+        }
+
         if context.notification_changes.is_empty() {
             // Rien à transmettre à l'AFSEC+
             return None;
         }
 
         // Décompte des AF_DATA_IN traités
-        context.nb_data_in += 1;
+        context.stats.nb_data_in += 1;
         if context.debug_level >= DEBUG_LEVEL_SOME {
-            println!("AFSEC Comm: AF_DATA_IN #{}...", context.nb_data_in);
+            println!("AFSEC Comm: AF_DATA_IN #{}...", context.stats.nb_data_in);
         }
 
         // Préparation d'un message `IC_DATA_IN` pour transmettre des datas à l'AFSEC+
@@ -46,19 +84,16 @@ impl CommonMiddlewareTrait for MDataIn {
 
         // On gave la trame de réponse avec des données à transmettre à l'AFSEC+
         let mut cur_zone = 0xFF_u8;
-        loop {
-            if context.notification_changes.is_empty() {
-                // Plus rien à transmettre
-                break;
-            }
-
-            // Tente de transmettre l'item #0 des notification_changes dans la trame
+        // Tente de transmettre la prochaine modification des notification_changes (zones
+        // entrelacées) dans la trame (jusqu'à ce qu'il n'y ait plus rien à transmettre)
+        // On laisse l'item dans la liste tant que pas sûr de pouvoir l'intégrer dans le message
+        while let Some((index, id_tag, t_value)) = context
+            .notification_changes
+            .front_interleaved(context.option_data_in_zone)
+        {
             // On préserve la construction actuelle
             let mut new_raw_frame = raw_frame.clone();
 
-            // On laisse l'item dans la liste tant que pas sûr de pouvoir l'intégrer dans le message
-            let (id_tag, t_value) = context.notification_changes[0].clone();
-
             // Dans le message, on doit mettre 3 choses : `D_DATA_ZONE`, `D_DATA_TAG` et `D_DATA_VALUE`
 
             // La zone peut être omise si elle est idem à la donnée précédente du message
@@ -91,9 +126,12 @@ impl CommonMiddlewareTrait for MDataIn {
                 break;
             }
 
-            // Tout est passé
+            // Tout est passé, en attente de confirmation par l'AFSEC+
             raw_frame = new_raw_frame.clone();
-            context.notification_changes.remove(0);
+            context.option_data_in_zone = Some(id_tag.zone);
+            context
+                .data_in_in_flight
+                .extend(context.notification_changes.remove(index));
         }
 
         // Réponse
@@ -108,14 +146,70 @@ impl CommonMiddlewareTrait for MDataIn {
         id_tag: IdTag,
         t_value: &TValue,
     ) {
-        if id_user != afsec_service.id_user && id_tag.num_tag != TAG_DATA_PACK {
-            // On ne retient que les changements d'autres utilisateurs et qui ne
-            // concernent pas les changements gérés par le 'pack-in'
-            context.notification_changes.push((id_tag, t_value.clone()));
+        if id_user != afsec_service.id_user {
+            // On ne retient que les changements d'autres utilisateurs
+            let priority = afsec_service
+                .lock_database()
+                .get_tag_from_id_tag(id_tag)
+                .map_or(0, |tag| tag.priority);
+            context
+                .notification_changes
+                .push(id_tag, t_value.clone(), priority);
+        }
+    }
+
+    fn notification_changes(
+        &self,
+        context: &mut Context,
+        afsec_service: &mut DatabaseAfsecComm,
+        changes: &[(IdUser, IdTag, TValue)],
+    ) {
+        // On ne retient que la dernière valeur de chaque tag modifié par d'autres utilisateurs
+        let mut merged_changes: Vec<(IdTag, &TValue)> = vec![];
+        for (id_user, id_tag, t_value) in changes {
+            if *id_user == afsec_service.id_user {
+                continue;
+            }
+            merged_changes.retain(|(merged_id_tag, _)| merged_id_tag != id_tag);
+            merged_changes.push((*id_tag, t_value));
+        }
+
+        // Priorités des tags avec un seul verrouillage de la database
+        let mut prioritized_changes: Vec<(IdTag, &TValue, u8)> = {
+            let db = afsec_service.lock_database();
+            merged_changes
+                .into_iter()
+                .map(|(id_tag, t_value)| {
+                    let priority = db.get_tag_from_id_tag(id_tag).map_or(0, |tag| tag.priority);
+                    (id_tag, t_value, priority)
+                })
+                .collect()
+        };
+        prioritized_changes.sort_by_key(|(_, _, priority)| std::cmp::Reverse(*priority));
+        for (id_tag, t_value, priority) in prioritized_changes {
+            context
+                .notification_changes
+                .push(id_tag, t_value.clone(), priority);
         }
     }
 }
 
+/// Remet en tête de file les modifications transmises dans le dernier `IC_DATA_IN` et non
+/// confirmées par l'AFSEC+
+fn requeue_in_flight(context: &mut Context) {
+    if context.data_in_in_flight.is_empty() {
+        return;
+    }
+    if context.debug_level >= DEBUG_LEVEL_SOME {
+        println!(
+            "AFSEC Comm: IC_DATA_IN non confirmé, {} modification(s) remise(s) en file...",
+            context.data_in_in_flight.len()
+        );
+    }
+    let in_flight = std::mem::take(&mut context.data_in_in_flight);
+    context.notification_changes.requeue_front(in_flight);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,9 +248,8 @@ mod tests {
 
         // Inscription pour être notifié des changements dans la database
         afsec_service.id_user = {
-            let mut db: std::sync::MutexGuard<'_, crate::database::Database> =
             // Verrouiller la database partagée
-            afsec_service.thread_db.lock().unwrap();
+            let mut db = afsec_service.lock_database();
 
             db.get_id_user("TEST", true)
         };
@@ -164,8 +257,7 @@ mod tests {
         // Par défaut, la valeur 0 dans la database
         {
             // Verrouiller la database partagée
-            let db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let db = afsec_service.lock_database();
 
             assert_eq!(db.get_u16_from_id_tag(0, id_tag), 0);
         }
@@ -187,17 +279,17 @@ mod tests {
         // On modifie le contenu de l'id_tag dans la database (par un autre utilisateur)
         {
             // Verrouiller la database partagée
-            let mut db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let mut db = afsec_service.lock_database();
 
-            db.set_u16_to_id_tag(ID_ANONYMOUS_USER, id_tag, 123);
+            db.set_u16_to_id_tag(ID_ANONYMOUS_USER, id_tag, 123)
+                .unwrap();
         }
 
         // Active le système de notification pour notifier les middlewares
         let mut vec_changes = vec![];
         loop {
             // Verrouiller la database partagée
-            let mut db = afsec_service.thread_db.lock().unwrap();
+            let mut db = afsec_service.lock_database();
 
             // Voir s'il y a une notification d'un autre utilisateur
             if let Some(notification_change) = db.get_change(afsec_service.id_user, false, true) {
@@ -236,31 +328,116 @@ mod tests {
         assert_eq!(response.get_tag(), id_message::IC_DATA_IN);
 
         // On doit y retrouve la zone = 0, le num_tag et les indices et la valeur
-        let mut zone_ok = false;
-        let mut tag_ok = false;
-        let mut value_ok = false;
-        for data_item in response.get_data_items() {
-            match data_item.tag {
-                id_message::D_DATA_ZONE => {
-                    assert_eq!(u8::from(&data_item.t_value), 0);
-                    zone_ok = true;
-                }
-                id_message::D_DATA_TAG => {
-                    assert_eq!(
-                        data_item.t_value.to_vec_u8()[0..5],
-                        vec![0x01, 0x02, 0, 0, 0]
-                    );
-                    tag_ok = true;
-                }
-                id_message::D_DATA_VALUE => {
-                    assert_eq!(u16::from(&data_item.t_value), 123);
-                    value_ok = true;
-                }
-                _ => (),
-            }
+        let zone = response.find_item(id_message::D_DATA_ZONE).unwrap();
+        assert_eq!(u8::from(&zone.t_value), 0);
+        let tag = response.find_item(id_message::D_DATA_TAG).unwrap();
+        assert_eq!(tag.t_value.to_vec_u8()[0..5], vec![0x01, 0x02, 0, 0, 0]);
+        let value = response.find_item(id_message::D_DATA_VALUE).unwrap();
+        assert_eq!(u16::from(&value.t_value), 123);
+    }
+
+    #[test]
+    fn test_notification_changes_merged() {
+        let mut db = Database::default();
+        let id_tags = [IdTag::new(0, 1, [0, 0, 0]), IdTag::new(0, 2, [0, 0, 0])];
+        for (word_address, id_tag) in [0x0000, 0x0001].into_iter().zip(id_tags) {
+            db.add_tag(&Tag {
+                word_address,
+                id_tag,
+                t_format: TFormat::U16,
+                ..Default::default()
+            });
         }
-        assert!(zone_ok);
-        assert!(tag_ok);
-        assert!(value_ok);
+        let id_user_afsec = db.get_id_user("AFSEC", true);
+        let shared_db = Arc::new(Mutex::new(db));
+        let mut context = Context::new(DEBUG_LEVEL_ALL);
+        let mut afsec_service =
+            DatabaseAfsecComm::new(shared_db, "fake".to_string(), 0).with_id_user(id_user_afsec);
+
+        // 2 modifications successives du même tag : seule la dernière valeur est retenue
+        let changes = [
+            (ID_ANONYMOUS_USER, id_tags[0], TValue::U16(1)),
+            (ID_ANONYMOUS_USER, id_tags[1], TValue::U16(2)),
+            (ID_ANONYMOUS_USER, id_tags[0], TValue::U16(3)),
+        ];
+        MDataIn::default().notification_changes(&mut context, &mut afsec_service, &changes);
+        assert_eq!(
+            context.notification_changes.front(),
+            Some((id_tags[1], TValue::U16(2)))
+        );
+        context.notification_changes.remove(0);
+        assert_eq!(
+            context.notification_changes.front(),
+            Some((id_tags[0], TValue::U16(3)))
+        );
+        context.notification_changes.remove(0);
+        assert!(context.notification_changes.is_empty());
+    }
+
+    #[test]
+    fn test_nack_requeue() {
+        let mut db = Database::default();
+        let id_tags = [IdTag::new(0, 1, [0, 0, 0]), IdTag::new(0, 2, [0, 0, 0])];
+        for (word_address, id_tag) in [0x0000, 0x0001].into_iter().zip(id_tags) {
+            db.add_tag(&Tag {
+                word_address,
+                id_tag,
+                t_format: TFormat::U16,
+                ..Default::default()
+            });
+        }
+        let id_user_afsec = db.get_id_user("AFSEC", true);
+        let shared_db = Arc::new(Mutex::new(db));
+        let mut context = Context::new(DEBUG_LEVEL_ALL);
+        let mut afsec_service =
+            DatabaseAfsecComm::new(shared_db, "fake".to_string(), 0).with_id_user(id_user_afsec);
+        let middleware = MDataIn::default();
+        let changes = [
+            (ID_ANONYMOUS_USER, id_tags[0], TValue::U16(1)),
+            (ID_ANONYMOUS_USER, id_tags[1], TValue::U16(2)),
+        ];
+        middleware.notification_changes(&mut context, &mut afsec_service, &changes);
+
+        let alive = DataFrame::try_from(RawFrame::new_message(id_message::AF_ALIVE)).unwrap();
+        let nack = DataFrame::try_from(RawFrame::new_nack()).unwrap();
+        let ack = DataFrame::try_from(RawFrame::new_ack()).unwrap();
+
+        // IC_DATA_IN transmis: les modifications sont en attente de confirmation
+        assert!(middleware
+            .get_conversation(&mut context, &mut afsec_service, &alive)
+            .is_some());
+        assert!(context.notification_changes.is_empty());
+        assert_eq!(context.data_in_in_flight.len(), 2);
+
+        // NACK de l'AFSEC+: les modifications sont remises en file, dans le même ordre
+        assert!(middleware
+            .get_conversation(&mut context, &mut afsec_service, &nack)
+            .is_none());
+        assert!(context.data_in_in_flight.is_empty());
+        assert_eq!(
+            context.notification_changes.front(),
+            Some((id_tags[0], TValue::U16(1)))
+        );
+
+        // Nouvelle transmission puis abandon de la conversation (délai d'inactivité)
+        let response = middleware
+            .get_conversation(&mut context, &mut afsec_service, &alive)
+            .unwrap();
+        let response = DataFrame::try_from(response).unwrap();
+        assert_eq!(response.get_tag(), id_message::IC_DATA_IN);
+        middleware.reset_conversation(&mut context);
+        assert_eq!(context.notification_changes.entries().len(), 2);
+
+        // Nouvelle transmission confirmée par ACK: plus rien à transmettre
+        assert!(middleware
+            .get_conversation(&mut context, &mut afsec_service, &alive)
+            .is_some());
+        assert!(middleware
+            .get_conversation(&mut context, &mut afsec_service, &ack)
+            .is_none());
+        assert!(context.data_in_in_flight.is_empty());
+        assert!(context.notification_changes.is_empty());
+        middleware.reset_conversation(&mut context);
+        assert!(context.notification_changes.is_empty());
     }
 }
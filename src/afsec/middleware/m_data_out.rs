@@ -3,6 +3,10 @@
 //! Prend en charge les conversations `AF_DATA_OUT` du résident qui transmet des données.
 //! Il peut s'agir de données pour renseigner la `Database` (`ZONE` + `IdTag` + `TValue`)
 //! ou de donnée pour un enregistrement dans un journal (`TABLE_INDEX` en sus)
+//!
+//! Les valeurs d'un même `AF_DATA_OUT` destinées à la `Database` sont appliquées ensemble à la
+//! fin du message (voir `utils::update_database_batch`) : un client MODBUS ne lit jamais un
+//! message partiellement appliqué
 
 use crate::afsec::DEBUG_LEVEL_SOME;
 
@@ -33,15 +37,18 @@ impl CommonMiddlewareTrait for MDataOut {
             return None;
         }
         // Décompte des AF_DATA_OUT traités
-        context.nb_data_out += 1;
+        context.stats.nb_data_out += 1;
         if context.debug_level >= DEBUG_LEVEL_SOME {
-            println!("AFSEC Comm: AF_DATA_OUT #{}...", context.nb_data_out);
+            println!("AFSEC Comm: AF_DATA_OUT #{}...", context.stats.nb_data_out);
         }
 
         // Init avant traitement
         context.option_vec_u8_tag = None;
         context.option_t_value = None;
 
+        // Valeurs à écrire dans la database (appliquées ensemble à la fin du message)
+        let mut updates: Vec<(IdTag, TValue)> = Vec::new();
+
         // Exploitation des informations reçues et mise à jour de la database
         for data_item in request_data_frame.get_data_items() {
             match data_item.tag {
@@ -51,8 +58,8 @@ impl CommonMiddlewareTrait for MDataOut {
                 }
                 id_message::D_DATA_TAG => {
                     let tag_as_string = data_item.t_value.to_t_value_vec_u8(5);
-                    if let TValue::VecU8(_, vec_u8) = tag_as_string {
-                        context.option_vec_u8_tag = Some(vec_u8);
+                    if let Some(vec_u8) = tag_as_string.as_bytes() {
+                        context.option_vec_u8_tag = Some(vec_u8.to_vec());
                     }
                 }
                 id_message::D_DATA_VALUE => context.option_t_value = Some(data_item.t_value),
@@ -70,7 +77,7 @@ impl CommonMiddlewareTrait for MDataOut {
                             utils::add_record(context, record);
                         } else {
                             // Mise à jour de la database
-                            utils::update_database(afsec_service, id_tag, t_value.clone());
+                            updates.push((id_tag, t_value.clone()));
                         }
                         // RAZ après traitement
                         context.option_vec_u8_tag = None;
@@ -80,6 +87,14 @@ impl CommonMiddlewareTrait for MDataOut {
             }
         }
 
+        // Mise à jour de la database (une valeur incohérente n'empêche pas l'acquittement du
+        // message, comme sur l'ICOM)
+        if let Err(db_error) = utils::update_database_batch(afsec_service, &updates) {
+            if context.debug_level >= DEBUG_LEVEL_SOME {
+                println!("AFSEC Comm: !!! AF_DATA_OUT: {db_error}");
+            }
+        }
+
         // Réponse
         Some(RawFrame::new_ack())
     }
@@ -133,8 +148,7 @@ mod tests {
         // Par défaut, la valeur 0 dans la database
         {
             // Verrouiller la database partagée
-            let db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let db = afsec_service.lock_database();
 
             assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag), 0);
         }
@@ -164,10 +178,55 @@ mod tests {
         // Et on doit maintenant lire la valeur 123 dans la database
         {
             // Verrouiller la database partagée
-            let db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let db = afsec_service.lock_database();
 
             assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag), 123);
         }
     }
+
+    #[test]
+    fn test_multi_tag_record() {
+        let mut db = Database::default();
+        let id_tags = [
+            IdTag::new(0, 0x0102, [0, 0, 0]),
+            IdTag::new(0, 0x0103, [0, 0, 0]),
+        ];
+        for (word_address, id_tag) in (0..).zip(id_tags) {
+            db.add_tag(&Tag {
+                word_address,
+                id_tag,
+                ..Default::default()
+            });
+        }
+        let shared_db = Arc::new(Mutex::new(db));
+        let mut context = Context::new(DEBUG_LEVEL_ALL);
+        let mut afsec_service =
+            DatabaseAfsecComm::new(Arc::clone(&shared_db), "fake".to_string(), DEBUG_LEVEL_ALL);
+
+        // AF_DATA_OUT avec 2 triplets zone/tag/valeur
+        let mut request = RawFrame::new_message(id_message::AF_DATA_OUT);
+        for (num_tag, value) in [(0x03_u8, 456_u16), (0x02, 123)] {
+            for data_item in [
+                DataItem::new(id_message::D_DATA_ZONE, TValue::U8(0)),
+                DataItem::new(
+                    id_message::D_DATA_TAG,
+                    TValue::VecU8(5, vec![0x01, num_tag, 0, 0, 0]),
+                ),
+                DataItem::new(id_message::D_DATA_VALUE, TValue::U16(value)),
+            ] {
+                request.try_extend_data_item(&data_item).unwrap();
+            }
+        }
+        let request = DataFrame::try_from(request).unwrap();
+
+        let response = MDataOut::default()
+            .get_conversation(&mut context, &mut afsec_service, &request)
+            .unwrap();
+        assert_eq!(response, RawFrame::new_ack());
+
+        // Les 2 valeurs sont appliquées
+        let db = shared_db.lock().unwrap();
+        assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tags[0]), 123);
+        assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tags[1]), 456);
+    }
 }
@@ -1,6 +1,11 @@
 //! `middleware` pour le traitement `AF_INIT`
+//!
+//! `IC_INIT` annonce les versions du protocole et de l'ICOM, ainsi que le numéro de série et
+//! l'adresse 'MAC' si l'identité du simulateur est définie (voir le module `sim_identity` de la
+//! `Database`)
 
 use crate::afsec::DEBUG_LEVEL_SOME;
+use crate::database::format_mac_address;
 
 use super::{
     id_message, utils, CommonMiddlewareTrait, Context, DataFrame, DataItem, DatabaseAfsecComm,
@@ -23,90 +28,82 @@ impl CommonMiddlewareTrait for MInit {
             return None;
         }
         // Décompte des AF_INIT traités
-        context.nb_init += 1;
+        context.stats.nb_init += 1;
+        context.is_initializing = false;
         if context.debug_level >= DEBUG_LEVEL_SOME {
-            println!("AFSEC Comm: AF_INIT #{}...", context.nb_init);
+            println!("AFSEC Comm: AF_INIT #{}...", context.stats.nb_init);
         }
 
         // Exploitation des informations reçues et mise à jour de la database
+        let mut updates: Vec<(IdTag, TValue)> = Vec::new();
         for data_item in request_data_frame.get_data_items() {
             match data_item.tag {
                 id_message::D_RESIDENT_VERSION => {
                     let version_revision_edition = u32::from(&data_item.t_value);
                     let (version, revision, edition) =
-                        utils::u32_to_version_revision_edition(version_revision_edition);
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(0, 0x0001, [0, 0, 0]),
-                        TValue::U16(version),
-                    );
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(0, 0x0002, [0, 0, 0]),
-                        TValue::U16(revision),
-                    );
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(0, 0x0003, [0, 0, 0]),
-                        TValue::U16(edition),
-                    );
+                        utils::split_version(afsec_service, version_revision_edition);
+                    updates.push((IdTag::new(0, 0x0001, [0, 0, 0]), TValue::U16(version)));
+                    updates.push((IdTag::new(0, 0x0002, [0, 0, 0]), TValue::U16(revision)));
+                    updates.push((IdTag::new(0, 0x0003, [0, 0, 0]), TValue::U16(edition)));
                 }
                 id_message::D_APPLI_NUMBER => {
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(0, 0x0010, [0, 0, 0]),
-                        data_item.t_value,
-                    );
+                    updates.push((IdTag::new(0, 0x0010, [0, 0, 0]), data_item.t_value));
                 }
                 id_message::D_APPLI_VERSION => {
                     let version_revision_edition = u32::from(&data_item.t_value);
                     let (version, revision, edition) =
-                        utils::u32_to_version_revision_edition(version_revision_edition);
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(0, 0x0011, [0, 0, 0]),
-                        TValue::U16(version),
-                    );
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(0, 0x0012, [0, 0, 0]),
-                        TValue::U16(revision),
-                    );
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(0, 0x0013, [0, 0, 0]),
-                        TValue::U16(edition),
-                    );
+                        utils::split_version(afsec_service, version_revision_edition);
+                    updates.push((IdTag::new(0, 0x0011, [0, 0, 0]), TValue::U16(version)));
+                    updates.push((IdTag::new(0, 0x0012, [0, 0, 0]), TValue::U16(revision)));
+                    updates.push((IdTag::new(0, 0x0013, [0, 0, 0]), TValue::U16(edition)));
                 }
                 id_message::D_APPLI_CONFIG => {
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(0, 0x0014, [0, 0, 0]),
-                        data_item.t_value,
-                    );
+                    updates.push((IdTag::new(0, 0x0014, [0, 0, 0]), data_item.t_value));
                 }
                 id_message::D_LANGUAGE => {
-                    utils::update_database(
-                        afsec_service,
-                        IdTag::new(1, 0x2042, [0, 0, 0]),
-                        data_item.t_value,
-                    );
+                    updates.push((IdTag::new(1, 0x2042, [0, 0, 0]), data_item.t_value));
                 }
                 _ => (),
             }
         }
+        if let Err(db_error) = utils::update_database_batch(afsec_service, &updates) {
+            if context.debug_level >= DEBUG_LEVEL_SOME {
+                println!("AFSEC Comm: !!! AF_INIT: {db_error}");
+            }
+        }
 
         // Création de la réponse
         let mut response_raw_frame = RawFrame::new_message(id_message::IC_INIT);
         response_raw_frame
             .try_extend_data_item(&DataItem::new(
                 id_message::D_PROTOCOLE_VERSION,
-                TValue::U16(0),
+                TValue::U16(afsec_service.firmware_profile.protocol_version()),
             ))
             .unwrap();
         response_raw_frame
-            .try_extend_data_item(&DataItem::new(id_message::D_ICOM_VERSION, TValue::U16(0)))
+            .try_extend_data_item(&DataItem::new(
+                id_message::D_ICOM_VERSION,
+                TValue::U16(afsec_service.firmware_profile.icom_version()),
+            ))
             .unwrap();
+        let option_identity = afsec_service.lock_database().get_sim_identity().cloned();
+        if let Some(identity) = option_identity {
+            let mac_address = format_mac_address(&identity.mac_address);
+            for (data_tag, value) in [
+                (
+                    id_message::D_ICOM_SERIAL_NUMBER,
+                    identity.serial_number.as_bytes(),
+                ),
+                (id_message::D_ICOM_MAC_ADDRESS, mac_address.as_bytes()),
+            ] {
+                response_raw_frame
+                    .try_extend_data_item(&DataItem::new(
+                        data_tag,
+                        TValue::new_vec_u8(value.len(), value),
+                    ))
+                    .unwrap();
+            }
+        }
 
         // Réponse
         Some(response_raw_frame)
@@ -1,31 +1,101 @@
 //! `middleware` pour le traitement `AF_MENU`
 //!
 //! Le simulateur ICOM ne gère pas de menu.
-//! Toute tentative de conversation pour des menus par l'AFSEC+ aboutira à une réponse NACK
+//! Toute tentative de conversation pour des menus par l'AFSEC+ aboutira à une réponse NACK,
+//! sauf si le profil firmware émulé accepte les menus (réponse `IC_MENU` vide)
+//!
+//! L'ICOM peut toutefois pousser un menu (message opérateur) vers l'afficheur de l'AFSEC+ : les
+//! menus déposés par la commande `menu` de la console (voir `Middlewares::queue_menu_pushes`)
+//! sont transmis un par un en réponse (`IC_MENU`) aux `AF_ALIVE` dont personne d'autre ne veut.
+//! La réponse de l'opérateur (`AF_MENU` avec le `D_MENU_ID` du menu poussé) est tracée et
+//! acquittée.
 
 use super::{
-    id_message, CommonMiddlewareTrait, Context, DataFrame, DatabaseAfsecComm, IdTag, IdUser,
-    RawFrame, TValue, DEBUG_LEVEL_ALL,
+    id_message, CommonMiddlewareTrait, Context, DataFrame, DataItem, DatabaseAfsecComm, IdTag,
+    IdUser, RawFrame, TValue, DEBUG_LEVEL_ALL, DEBUG_LEVEL_SOME,
 };
 
 #[derive(Default)]
 pub struct MMenu {}
 
+impl MMenu {
+    /// Transmission du prochain menu en attente en réponse à `AF_ALIVE`
+    fn push_menu(context: &mut Context) -> Option<RawFrame> {
+        let menu_push = context.menu_pushes.pop_front()?;
+        if context.debug_level >= DEBUG_LEVEL_SOME {
+            println!("AFSEC Comm: AF_ALIVE IC_MENU {menu_push}");
+        }
+        let mut response = RawFrame::new_message(id_message::IC_MENU);
+        response
+            .try_extend_data_item(&DataItem::new(
+                id_message::D_MENU_ID,
+                TValue::U16(menu_push.id_menu),
+            ))
+            .ok()?;
+        response
+            .try_extend_data_item(&DataItem::new(
+                id_message::D_MENU_SHORT_DISPLAY,
+                TValue::new_vec_u8(menu_push.text.len(), menu_push.text.as_bytes()),
+            ))
+            .ok()?;
+        context.option_menu_pushed = Some(menu_push.id_menu);
+        Some(response)
+    }
+
+    /// Indique si la requête `AF_MENU` est la réponse de l'opérateur au dernier menu poussé
+    fn is_menu_answer(context: &Context, request_data_frame: &DataFrame) -> bool {
+        let Some(id_menu) = context.option_menu_pushed else {
+            return false;
+        };
+        request_data_frame.get_data_items().iter().any(|data_item| {
+            data_item.tag == id_message::D_MENU_ID && u16::from(&data_item.t_value) == id_menu
+        })
+    }
+}
+
 impl CommonMiddlewareTrait for MMenu {
     fn reset_conversation(&self, _context: &mut Context) {}
 
     fn get_conversation(
         &self,
         context: &mut Context,
-        _afsec_service: &mut DatabaseAfsecComm,
+        afsec_service: &mut DatabaseAfsecComm,
         request_data_frame: &DataFrame,
     ) -> Option<RawFrame> {
+        if request_data_frame.get_tag() == id_message::AF_ALIVE {
+            // Menu poussé vers l'afficheur de l'AFSEC+ (si un menu est en attente)
+            return Self::push_menu(context);
+        }
+
         if request_data_frame.get_tag() != id_message::AF_MENU {
             // Non concerné par cette conversation
             return None;
         }
 
+        // Réponse de l'opérateur au dernier menu poussé
+        if Self::is_menu_answer(context, request_data_frame) {
+            let id_menu = context.option_menu_pushed.take().unwrap_or_default();
+            if context.debug_level >= DEBUG_LEVEL_SOME {
+                let data_items: Vec<String> = request_data_frame
+                    .get_data_items()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                println!(
+                    "AFSEC Comm: AF_MENU réponse opérateur au menu #{id_menu}: [{}]",
+                    data_items.join(", ")
+                );
+            }
+            return Some(RawFrame::new_ack());
+        }
+
         // Réponse
+        if afsec_service.firmware_profile.accept_menu() {
+            if context.debug_level >= DEBUG_LEVEL_ALL {
+                println!("AFSEC Comm: AF_MENU IC_MENU");
+            }
+            return Some(RawFrame::new_message(id_message::IC_MENU));
+        }
         if context.debug_level >= DEBUG_LEVEL_ALL {
             println!("AFSEC Comm: AF_MENU NACK");
         }
@@ -42,3 +112,61 @@ impl CommonMiddlewareTrait for MMenu {
     ) {
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use crate::database::{Database, MenuPush};
+
+    #[test]
+    fn test_menu_push() {
+        let shared_db = Arc::new(Mutex::new(Database::default()));
+        let mut afsec_service = DatabaseAfsecComm::new(shared_db, "fake".to_string(), 0);
+        let mut context = Context::new(0);
+        let middleware = MMenu::default();
+        let alive = DataFrame::try_from(RawFrame::new_message(id_message::AF_ALIVE)).unwrap();
+
+        // Pas de menu en attente: AF_ALIVE non concerné
+        assert!(middleware
+            .get_conversation(&mut context, &mut afsec_service, &alive)
+            .is_none());
+
+        // Menu poussé au AF_ALIVE suivant
+        context.menu_pushes.push_back(MenuPush {
+            id_menu: 7,
+            text: "Valider".to_string(),
+        });
+        let response = middleware
+            .get_conversation(&mut context, &mut afsec_service, &alive)
+            .unwrap();
+        let response = DataFrame::try_from(response).unwrap();
+        assert_eq!(response.get_tag(), id_message::IC_MENU);
+        let data_items = response.get_data_items();
+        assert_eq!(data_items[0].tag, id_message::D_MENU_ID);
+        assert_eq!(u16::from(&data_items[0].t_value), 7);
+        assert_eq!(data_items[1].tag, id_message::D_MENU_SHORT_DISPLAY);
+        assert_eq!(data_items[1].t_value.as_bytes(), Some(&b"Valider"[..]));
+        assert!(context.menu_pushes.is_empty());
+
+        // Réponse de l'opérateur acquittée
+        let mut answer = RawFrame::new_message(id_message::AF_MENU);
+        answer
+            .try_extend_data_item(&DataItem::new(id_message::D_MENU_ID, TValue::U16(7)))
+            .unwrap();
+        let answer = DataFrame::try_from(answer).unwrap();
+        assert_eq!(
+            middleware.get_conversation(&mut context, &mut afsec_service, &answer),
+            Some(RawFrame::new_ack())
+        );
+        assert_eq!(context.option_menu_pushed, None);
+
+        // AF_MENU suivant: comportement habituel (NACK avec le profil par défaut)
+        assert_eq!(
+            middleware.get_conversation(&mut context, &mut afsec_service, &answer),
+            Some(RawFrame::new_nack())
+        );
+    }
+}
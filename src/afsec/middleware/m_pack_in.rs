@@ -25,20 +25,38 @@
 //!     Les `blocs` restant à transmettre sont dans `private_datas.len()`
 //! * `set_pending_blocs: HashSet<u8>`: Idem à `set_blocs` pour enregistrer les blocs à transmettre lorsque
 //!     la transaction en cours sera terminée (`notification_changes` reçues pendant une transaction `pack_in`)
+//!
+//! Les blocs modifiés sont déterminés par un bitmap des mots de la zone 'pack-in' (voir le module
+//! `dirty_words` de la `Database`) : une écriture par adresse mot qui n'est pas alignée sur les
+//! tags `DATA_PACK` marque exactement les blocs dont des mots ont été modifiés.
+//!
+//! La copie privée des blocs d'une transaction est prise en un seul verrouillage de la
+//! `Database` (voir `DatabaseSnapshot`) : l'assemblage des trames `IC_PACK_IN` se fait sans le
+//! verrou.
 
 use std::vec;
 
+use crate::database::WordAddress;
+
 use super::{
     id_message, CommonMiddlewareTrait, Context, DataFrame, DataItem, DatabaseAfsecComm, IdTag,
-    IdUser, RawFrame, TValue, DEBUG_LEVEL_ALL, DEBUG_LEVEL_SOME, TAG_DATA_PACK,
+    IdTagPattern, IdUser, RawFrame, TValue, DEBUG_LEVEL_ALL, DEBUG_LEVEL_SOME, TAG_DATA_PACK,
 };
 
+/// Nombre d'octets d'un bloc `DATA_PACK`
+const BLOC_NB_BYTES: usize = 64;
+
 #[derive(Default)]
 pub struct MPackIn {}
 
 impl CommonMiddlewareTrait for MPackIn {
     fn reset_conversation(&self, _context: &mut Context) {}
 
+    fn interests(&self) -> Vec<IdTagPattern> {
+        // Tags `DATA_PACK` de la zone de commande (zone = 5), quels que soient les indices
+        vec![IdTagPattern::new(5, TAG_DATA_PACK)]
+    }
+
     fn get_conversation(
         &self,
         context: &mut Context,
@@ -50,6 +68,15 @@ impl CommonMiddlewareTrait for MPackIn {
             return None;
         }
 
+        if context.is_busy() {
+            // L'AFSEC+ a demandé une pause des transmissions (`D_BUSY_DELAY`) : une transaction
+            // en cours reprendra après la pause
+            return None;
+        }
+
+        // Blocs modifiés depuis la dernière conversation
+        MPackIn::collect_dirty_blocs(context, afsec_service);
+
         // Vérifie si transaction en cours ou s'il faut démarrer une nouvelle transaction
         if !context.pack_in.is_transaction {
             if context.pack_in.set_blocs.is_empty() {
@@ -61,9 +88,9 @@ impl CommonMiddlewareTrait for MPackIn {
         }
 
         // Décompte des AF_PACK_IN traités
-        context.nb_pack_in += 1;
+        context.stats.nb_pack_in += 1;
         if context.debug_level >= DEBUG_LEVEL_SOME {
-            println!("AFSEC Comm: AF_PACK_IN #{}...", context.nb_pack_in);
+            println!("AFSEC Comm: AF_PACK_IN #{}...", context.stats.nb_pack_in);
         }
 
         // Préparation d'un message `IC_PACK_IN` pour transmettre des datas à l'AFSEC+
@@ -151,21 +178,84 @@ impl CommonMiddlewareTrait for MPackIn {
         id_tag: IdTag,
         _t_value: &TValue,
     ) {
-        if id_user != afsec_service.id_user && id_tag.zone == 5 && id_tag.num_tag == TAG_DATA_PACK {
-            // On ne retient que les changements d'autres utilisateurs d'un tag `DATA_PACK`
-            // dans la zone de commande (zone = 5)
-            // On identifie le 'bloc' de 64 octets concerné par le dernier indice du tag
-            if context.pack_in.is_transaction {
-                // Une transaction est en cours, on mémorise le changement pour la transaction à suivre
-                context.pack_in.set_pending_blocs.insert(id_tag.indice_2);
-            } else {
-                context.pack_in.set_blocs.insert(id_tag.indice_2);
+        if id_user != afsec_service.id_user {
+            // On ne retient que les changements d'autres utilisateurs
+            // On identifie le 'bloc' de 64 octets concerné par le dernier indice du tag (utile tant
+            // que la zone 'pack-in' n'est pas surveillée) et les blocs modifiés selon le bitmap
+            MPackIn::insert_bloc(context, id_tag.indice_2);
+            MPackIn::collect_dirty_blocs(context, afsec_service);
+        }
+    }
+
+    fn notification_changes(
+        &self,
+        context: &mut Context,
+        afsec_service: &mut DatabaseAfsecComm,
+        changes: &[(IdUser, IdTag, TValue)],
+    ) {
+        // Blocs des changements d'autres utilisateurs puis une seule lecture du bitmap
+        let mut is_changed = false;
+        for (id_user, id_tag, _) in changes {
+            if *id_user != afsec_service.id_user {
+                MPackIn::insert_bloc(context, id_tag.indice_2);
+                is_changed = true;
             }
         }
+        if is_changed {
+            MPackIn::collect_dirty_blocs(context, afsec_service);
+        }
     }
 }
 
 impl MPackIn {
+    /// Mémorise un bloc à transmettre (pour la transaction à suivre si une transaction est en
+    /// cours)
+    fn insert_bloc(context: &mut Context, bloc: u8) {
+        if context.pack_in.is_transaction {
+            context.pack_in.set_pending_blocs.insert(bloc);
+        } else {
+            context.pack_in.set_blocs.insert(bloc);
+        }
+    }
+
+    /// Mémorise les blocs dont des mots ont été modifiés par d'autres utilisateurs selon le
+    /// bitmap des mots de la zone 'pack-in' (la surveillance des blocs définis dans la database
+    /// débute au premier appel)
+    fn collect_dirty_blocs(context: &mut Context, afsec_service: &mut DatabaseAfsecComm) {
+        let (bloc_areas, dirty_ranges) = {
+            // Verrouiller la database partagée
+            let mut db = afsec_service.lock_database();
+
+            let mut bloc_areas = vec![];
+            for bloc in 0..8 {
+                let id_tag = IdTag::new(5, TAG_DATA_PACK, [0, 0, bloc]);
+                if let Some(tag) = db.get_tag_from_id_tag(id_tag) {
+                    let (word_address, nb_words) = (tag.word_address, tag.t_format.nb_words());
+                    db.watch_dirty_words(afsec_service.id_user, word_address, nb_words);
+                    bloc_areas.push((bloc, usize::from(word_address), nb_words));
+                }
+            }
+            (bloc_areas, db.take_dirty_word_ranges(afsec_service.id_user))
+        };
+
+        for (word_address, nb_words) in dirty_ranges {
+            let (start, end) = (
+                usize::from(word_address),
+                usize::from(word_address) + nb_words,
+            );
+            for (bloc, bloc_start, bloc_nb_words) in &bloc_areas {
+                if start < bloc_start + bloc_nb_words && *bloc_start < end {
+                    if context.debug_level >= DEBUG_LEVEL_ALL {
+                        println!(
+                            "AFSEC Comm: AF_PACK_IN bloc #{bloc} modified @{word_address:04X} ({nb_words} words)"
+                        );
+                    }
+                    MPackIn::insert_bloc(context, *bloc);
+                }
+            }
+        }
+    }
+
     /// Nouvelle transaction `pack-in`
     fn start_transaction(context: &mut Context, afsec_service: &mut DatabaseAfsecComm) {
         if context.pack_in.is_transaction {
@@ -182,21 +272,44 @@ impl MPackIn {
             );
         }
 
+        // Copie des 64 octets de chaque `bloc` en un seul verrouillage de la database (voir
+        // `DatabaseSnapshot`) pour ne pas bloquer les autres utilisateurs pendant l'assemblage
+        let (bloc_word_addresses, snapshot) = {
+            // Verrouiller la database partagée
+            let db = afsec_service.lock_database();
+
+            let bloc_word_addresses: Vec<(u8, Option<WordAddress>)> = context
+                .pack_in
+                .set_blocs
+                .iter()
+                .map(|bloc| {
+                    let id_tag = IdTag::new(5, TAG_DATA_PACK, [0, 0, *bloc]);
+                    (
+                        *bloc,
+                        db.get_tag_from_id_tag(id_tag).map(|tag| tag.word_address),
+                    )
+                })
+                .collect();
+            let areas: Vec<(WordAddress, usize)> = bloc_word_addresses
+                .iter()
+                .filter_map(|(_, option_word_address)| *option_word_address)
+                .map(|word_address| (word_address, BLOC_NB_BYTES / 2))
+                .collect();
+            (bloc_word_addresses, db.snapshot(&areas))
+        };
+
         // Mise à jour de la copie privée des `blocs` à transmettre à l'AFSEC+
-        context.pack_in.private_datas = vec![];
-
-        for bloc in &context.pack_in.set_blocs {
-            // On va chercher les 64 octets correspondant dans la database
-            let id_tag = IdTag::new(5, TAG_DATA_PACK, [0, 0, *bloc]);
-            let vec_u8 = {
-                // Verrouiller la database partagée
-                let db: std::sync::MutexGuard<'_, crate::database::Database> =
-                    afsec_service.thread_db.lock().unwrap();
-
-                db.get_vec_u8_from_id_tag(afsec_service.id_user, id_tag, 64)
-            };
-            context.pack_in.private_datas.push((*bloc, vec_u8));
-        }
+        context.pack_in.private_datas = bloc_word_addresses
+            .into_iter()
+            .map(|(bloc, option_word_address)| {
+                let vec_u8 = option_word_address
+                    .and_then(|word_address| {
+                        snapshot.get_vec_u8_from_word_address(word_address, BLOC_NB_BYTES)
+                    })
+                    .unwrap_or_default();
+                (bloc, vec_u8)
+            })
+            .collect();
     }
 
     /// Termine la transaction `pack-in` en cours
@@ -266,9 +379,8 @@ mod tests {
 
         // Inscription pour être notifié des changements dans la database
         afsec_service.id_user = {
-            let mut db: std::sync::MutexGuard<'_, crate::database::Database> =
             // Verrouiller la database partagée
-            afsec_service.thread_db.lock().unwrap();
+            let mut db = afsec_service.lock_database();
 
             db.get_id_user("TEST", true)
         };
@@ -291,17 +403,17 @@ mod tests {
         let word_address = word_address_pack_out + test_address;
         {
             // Verrouiller la database partagée
-            let mut db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let mut db = afsec_service.lock_database();
 
-            db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, word_address, &test_values);
+            db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, word_address, &test_values)
+                .unwrap();
         }
 
         // Active le système de notification pour notifier les middlewares
         let mut vec_changes = vec![];
         loop {
             // Verrouiller la database partagée
-            let mut db = afsec_service.thread_db.lock().unwrap();
+            let mut db = afsec_service.lock_database();
 
             // Voir s'il y a une notification d'un autre utilisateur
             if let Some(notification_change) = db.get_change(afsec_service.id_user, false, true) {
@@ -383,4 +495,47 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_dirty_blocs() {
+        // Database avec 2 blocs 'pack-in' contigus de 32 mots
+        let mut db = Database::default();
+        for bloc in 0..2 {
+            db.add_tag(&Tag {
+                word_address: 0x0100 + 32 * u16::from(bloc),
+                id_tag: IdTag::new(5, TAG_DATA_PACK, [0, 0, bloc]),
+                t_format: TFormat::VecU8(64),
+                ..Default::default()
+            });
+        }
+        let mut afsec_service = DatabaseAfsecComm::new(
+            Arc::new(Mutex::new(db)),
+            "fake".to_string(),
+            DEBUG_LEVEL_ALL,
+        );
+        let id_user = afsec_service.lock_database().get_id_user("TEST", true);
+        afsec_service.id_user = id_user;
+        let mut context = Context::new(DEBUG_LEVEL_ALL);
+        let middleware = MPackIn::default();
+        let request = DataFrame::try_from(RawFrame::new_message(id_message::AF_ALIVE)).unwrap();
+
+        // Rien à dire (début de la surveillance de la zone 'pack-in')
+        assert!(middleware
+            .get_conversation(&mut context, &mut afsec_service, &request)
+            .is_none());
+
+        // Écriture (sans notification traitée) à cheval sur les 2 blocs
+        afsec_service
+            .lock_database()
+            .set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x011F, &[1, 2, 3, 4])
+            .unwrap();
+
+        // Les 2 blocs sont transmis
+        let response = middleware
+            .get_conversation(&mut context, &mut afsec_service, &request)
+            .unwrap();
+        let response = DataFrame::try_from(response).unwrap();
+        assert_eq!(response.get_tag(), id_message::IC_PACK_IN);
+        assert_eq!(response.get_data_items().len(), 2);
+    }
 }
@@ -45,9 +45,9 @@ impl CommonMiddlewareTrait for MPackOut {
         }
 
         // Décompte des AF_PACK_OUT traités
-        context.nb_pack_out += 1;
+        context.stats.nb_pack_out += 1;
         if context.debug_level >= DEBUG_LEVEL_SOME {
-            println!("AFSEC Comm: AF_PACK_OUT #{}...", context.nb_pack_out);
+            println!("AFSEC Comm: AF_PACK_OUT #{}...", context.stats.nb_pack_out);
         }
 
         // Vérifie si transaction en cours ou s'il faut démarrer une nouvelle transaction
@@ -169,8 +169,7 @@ impl MPackOut {
         let id_tag = IdTag::new(4, TAG_DATA_PACK, [0, 0, 0]);
         let some_base_word_address = {
             // Verrouiller la database partagée
-            let db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let db = afsec_service.lock_database();
 
             db.get_tag_from_id_tag(id_tag).map(|tag| tag.word_address)
         };
@@ -182,13 +181,18 @@ impl MPackOut {
                 let word_address = base_word_address + *word_address as u16;
                 {
                     // Verrouiller la database partagée
-                    let mut db: std::sync::MutexGuard<'_, crate::database::Database> =
-                        afsec_service.thread_db.lock().unwrap();
+                    let mut db = afsec_service.lock_database();
 
                     if context.debug_level >= DEBUG_LEVEL_ALL {
                         println!("AFSEC Comm: AF_PACK_OUT update @{word_address:04X} = {vec_u8:?}");
                     }
-                    db.set_vec_u8_to_word_address(afsec_service.id_user, word_address, vec_u8);
+                    if let Err(db_error) =
+                        db.set_vec_u8_to_word_address(afsec_service.id_user, word_address, vec_u8)
+                    {
+                        if context.debug_level >= DEBUG_LEVEL_SOME {
+                            println!("AFSEC Comm: !!! AF_PACK_OUT: {db_error}");
+                        }
+                    }
                 };
             }
         } else if context.debug_level >= DEBUG_LEVEL_SOME {
@@ -281,8 +285,7 @@ mod tests {
         // Et on doit maintenant lire les valeurs dans la zone pack-out de la database
         {
             // Verrouiller la database partagée
-            let db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let db = afsec_service.lock_database();
 
             assert_eq!(
                 db.get_vec_u8_from_word_address(
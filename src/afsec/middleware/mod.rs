@@ -13,14 +13,48 @@
 //! * `AF_DATA_OUT` / `IC_DATA_OUT`: pris en charge par le middleware `MDataOut`
 //! * `AF_DATA_IN` / `IC_DATA_IN`: pris en charge par le middleware `MDataIn`
 //! * `AF_DATA_OUT_TABLE_INDEX` / `IC_DATA_OUT_TABLE_INDEX`: pris en charge par le middleware `MDataOutTableIndex`
+//! * `AF_MENU` / `IC_MENU`: pris en charge par le middleware `MMenu`, qui pousse aussi les menus
+//!   en attente en réponse à `AF_ALIVE`
+//!
+//! Le mode de fonctionnement de l'AFSEC+ (`D_MODE_AFSEC`) est pris en compte dans toute requête
+//! (`AF_INIT` ou changement de mode) par `handle_request_data_frame` : il est publié dans le tag
+//! `ID_TAG_SIM_AFSEC_MODE` et les messages refusés dans ce mode (voir `ModePolicy`) sont NACK.
+//!
+//! Un résident AFSEC+ lent peut demander une pause des transmissions `IC_DATA_IN` et
+//! `IC_PACK_IN` (`D_BUSY_DELAY`, projet de spécification) dans toute requête : ces transmissions
+//! reprennent à la fin du délai indiqué (voir `Context::is_busy`).
+//!
+//! Chaque `middleware` déclare les [`IdTagPattern`] des tags qui l'intéressent (voir
+//! `CommonMiddlewareTrait::interests`) : les modifications de la `database` ne sont notifiées
+//! qu'aux `middlewares` intéressés.
+//!
+//! Si l'AFSEC+ n'envoie plus la suite d'une conversation en cours, celle-ci est abandonnée après
+//! un délai d'inactivité (voir `Middlewares::check_conversation_timeout`) sans attendre le
+//! prochain `AF_INIT`.
+//!
+//! Les blocs `PACK_IN`, les index des journaux et les modifications en attente du [`Context`]
+//! peuvent être sauvegardés à l'arrêt du simulateur et restaurés au démarrage suivant (voir le
+//! module `context_state`).
+//!
+//! Le firmware résident de l'AFSEC+ renvoie parfois 2 fois de suite le même `AF_INIT` : un
+//! `AF_INIT` identique au précédent reçu juste après (sans autre requête entre les 2) est un
+//! doublon qui reçoit la même réponse sans nouvelle initialisation (voir `DUPLICATE_INIT_WINDOW`).
+
+use std::time::{Duration, Instant};
 
 use crate::{
     afsec::tlv_frame::DataItem,
-    database::{IdTag, IdUser},
+    database::{
+        DbError, IdTag, IdTagPattern, IdUser, JournalRecord, MenuPush, ID_TAG_SIM_AFSEC_MODE,
+    },
+    profiling,
     t_data::TValue,
+    test_summary,
 };
 
-use super::{DataFrame, DatabaseAfsecComm, RawFrame, DEBUG_LEVEL_ALL, DEBUG_LEVEL_SOME};
+use super::{
+    AliveAnswer, DataFrame, DatabaseAfsecComm, RawFrame, DEBUG_LEVEL_ALL, DEBUG_LEVEL_SOME,
+};
 
 mod id_message;
 pub use id_message::*;
@@ -28,6 +62,15 @@ pub use id_message::*;
 mod context;
 pub use context::Context;
 
+mod protocol_stats;
+pub use protocol_stats::{ProtocolStats, StatsStore, DEFAULT_PROTOCOL_STATS_PERIOD_SECS};
+
+mod context_state;
+pub use context_state::{ContextState, CONTEXT_MAGIC, CONTEXT_VERSION};
+
+mod notification_queue;
+use notification_queue::NotificationQueue;
+
 mod utils;
 
 mod records;
@@ -58,6 +101,10 @@ use m_menu::MMenu;
 /// Voir SR DEV 004
 pub const TAG_DATA_PACK: u16 = 0x0F45;
 
+/// Délai d'inactivité par défaut (en millisecondes) au delà duquel une conversation en cours
+/// est abandonnée
+pub const DEFAULT_CONVERSATION_TIMEOUT: u64 = 5000;
+
 // On implémente des `middlewares` qu'on peut désigner dynamiquement par `&dyn CommonMiddlewareTrait`.
 //
 // Mais cette solution nécessite de gérer la `lifetime` des différents `middlewares` ce qui n'est
@@ -67,12 +114,25 @@ pub const TAG_DATA_PACK: u16 = 0x0F45;
 // On simplifie donc en identifiant les `middlewares` dans une liste des `middlewares` qu'on génère
 // dynamiquement à chaque fois besoin par `Self::all_middlewares`
 
+/// Délai pendant lequel un `AF_INIT` identique au précédent (sans autre requête entre les 2) est
+/// considéré comme un doublon
+const DUPLICATE_INIT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Type des trames reçues inexploitables dans le rapport de fin de test
+const MALFORMED_FRAME_NAME: &str = "MALFORMED";
+
 /// Identifiant des `middlewares`
 /// Il s'agit ici de l'indice du `middleware` dans la liste des `middlewares`
 type IdMiddleware = usize;
 
 /// Trait à implémenter pour chaque `middleware`
 pub trait CommonMiddlewareTrait {
+    /// Nom du `middleware` (pour les traces et les statistiques)
+    fn name(&self) -> &'static str {
+        let type_name = std::any::type_name::<Self>();
+        type_name.rsplit("::").next().unwrap_or(type_name)
+    }
+
     /// Fonction appelée lorsque la conversation en cours (s'il y en a une) est terminée.
     /// Indique qu'une nouvelle conversation va débuter
     /// Attention, self n'est pas mutable, il faut utiliser le `context`
@@ -88,7 +148,20 @@ pub trait CommonMiddlewareTrait {
         request_data_frame: &DataFrame,
     ) -> Option<RawFrame>;
 
+    /// Motifs des tags dont les modifications intéressent ce `middleware`
+    /// (par défaut, aucune modification n'est notifiée)
+    fn interests(&self) -> Vec<IdTagPattern> {
+        vec![]
+    }
+
+    /// Motifs des tags dont les modifications n'intéressent pas ce `middleware`, même s'ils
+    /// correspondent à un motif de `interests`
+    fn exclusions(&self) -> Vec<IdTagPattern> {
+        vec![]
+    }
+
     /// Fonction appelée pour indiquer une modification dans le contenu de la `database`
+    /// d'un tag qui intéresse ce `middleware` (voir `interests` et `exclusions`)
     /// Attention, self n'est pas mutable, il faut utiliser le `context`
     fn notification_change(
         &self,
@@ -98,6 +171,22 @@ pub trait CommonMiddlewareTrait {
         id_tag: IdTag,
         t_value: &TValue,
     );
+
+    /// Fonction appelée pour indiquer en une fois un lot de modifications (dans l'ordre) du
+    /// contenu de la `database` des tags qui intéressent ce `middleware`, qui peut ainsi les
+    /// trier ou les fusionner avant de les prendre en compte
+    /// (par défaut, `notification_change` est appelée pour chaque modification)
+    /// Attention, self n'est pas mutable, il faut utiliser le `context`
+    fn notification_changes(
+        &self,
+        context: &mut Context,
+        afsec_service: &mut DatabaseAfsecComm,
+        changes: &[(IdUser, IdTag, TValue)],
+    ) {
+        for (id_user, id_tag, t_value) in changes {
+            self.notification_change(context, afsec_service, *id_user, *id_tag, t_value);
+        }
+    }
 }
 
 /// Structure pour la gestion des `middlewares`
@@ -107,6 +196,22 @@ pub struct Middlewares {
 
     /// IDMiddleware en cours de conversation
     option_cur_middleware: Option<IdMiddleware>,
+
+    /// Délai d'inactivité au delà duquel la conversation en cours est abandonnée
+    /// (`Duration::ZERO` pour ne jamais abandonner)
+    conversation_timeout: Duration,
+
+    /// Date de la dernière requête reçue de l'AFSEC+
+    option_last_request: Option<Instant>,
+
+    /// Dernier `AF_INIT` traité (date, requête, réponse) si c'est la dernière requête reçue
+    option_last_init: Option<(Instant, RawFrame, RawFrame)>,
+
+    /// Nombre de trames inexploitables consécutives (voir `MalformedAnswer`)
+    nb_malformed_frames: u32,
+
+    /// Motifs (intérêts, exclusions) enregistrés pour chaque `middleware` (indice `IdMiddleware`)
+    interests: Vec<(Vec<IdTagPattern>, Vec<IdTagPattern>)>,
 }
 
 impl Middlewares {
@@ -115,7 +220,58 @@ impl Middlewares {
         Middlewares {
             context: Context::new(debug_level),
             option_cur_middleware: None,
+            conversation_timeout: Duration::from_millis(DEFAULT_CONVERSATION_TIMEOUT),
+            option_last_request: None,
+            option_last_init: None,
+            nb_malformed_frames: 0,
+            interests: Self::all_middlewares()
+                .iter()
+                .map(|middleware| (middleware.interests(), middleware.exclusions()))
+                .collect(),
+        }
+    }
+
+    /// Spécifie le délai d'inactivité au delà duquel la conversation en cours est abandonnée
+    /// (`Duration::ZERO` pour ne jamais abandonner)
+    #[must_use]
+    pub fn with_conversation_timeout(mut self, conversation_timeout: Duration) -> Self {
+        self.conversation_timeout = conversation_timeout;
+        self
+    }
+
+    /// Abandonne la conversation en cours si l'AFSEC+ n'a plus rien envoyé depuis le délai
+    /// d'inactivité : le `middleware` qui conversait est réinitialisé et la prochaine requête
+    /// débutera une nouvelle conversation
+    /// Retourne true si la conversation en cours est abandonnée
+    pub fn check_conversation_timeout(&mut self, now: Instant) -> bool {
+        let Some(id_middleware) = self.option_cur_middleware else {
+            return false;
+        };
+        let Some(last_request) = self.option_last_request else {
+            return false;
+        };
+        let inactivity = now.saturating_duration_since(last_request);
+        if self.conversation_timeout.is_zero() || inactivity < self.conversation_timeout {
+            return false;
+        }
+        if self.context.debug_level >= DEBUG_LEVEL_SOME {
+            println!(
+                "AFSEC Comm: Conversation abandonnée ({}, pas de requête depuis {} ms)...",
+                Self::all_middlewares()[id_middleware].name(),
+                inactivity.as_millis()
+            );
         }
+        Self::all_middlewares()[id_middleware].reset_conversation(&mut self.context);
+        self.option_cur_middleware = None;
+        self.context.stats.nb_abandoned += 1;
+        true
+    }
+
+    /// Indique si les modifications d'un tag intéressent un `middleware`
+    fn is_interested(&self, id_middleware: IdMiddleware, id_tag: IdTag) -> bool {
+        let (interests, exclusions) = &self.interests[id_middleware];
+        interests.iter().any(|pattern| pattern.matches(id_tag))
+            && !exclusions.iter().any(|pattern| pattern.matches(id_tag))
     }
 
     /// Retourne la liste des `middlewares`
@@ -131,6 +287,21 @@ impl Middlewares {
         ]
     }
 
+    /// Présente une requête de l'AFSEC+ à un `middleware` en mesurant son temps de traitement
+    /// (voir `profiling::record_middleware_time`)
+    fn get_conversation_timed(
+        &mut self,
+        middleware: &dyn CommonMiddlewareTrait,
+        afsec_service: &mut DatabaseAfsecComm,
+        request_data_frame: &DataFrame,
+    ) -> Option<RawFrame> {
+        let start = Instant::now();
+        let option_response_raw_frame =
+            middleware.get_conversation(&mut self.context, afsec_service, request_data_frame);
+        profiling::record_middleware_time(middleware.name(), start.elapsed());
+        option_response_raw_frame
+    }
+
     /// Reset conversation de tous les `middlewares`
     fn reset_conversation_all_middlewares(&mut self) {
         for middleware in Self::all_middlewares() {
@@ -138,6 +309,58 @@ impl Middlewares {
         }
     }
 
+    /// Simule une coupure d'alimentation de l'AFSEC+ : les conversations en cours sont
+    /// abandonnées et seul un `AF_INIT` est accepté jusqu'à la réinitialisation des
+    /// communications par l'AFSEC+
+    pub fn power_cycle(&mut self) {
+        self.reset_conversation_all_middlewares();
+        self.option_cur_middleware = None;
+        self.option_last_init = None;
+        self.context.is_initializing = true;
+    }
+
+    /// Ajoute des menus à pousser vers l'afficheur de l'AFSEC+ (transmis à chaque `AF_ALIVE`
+    /// suivant, voir le `middleware` `MMenu`)
+    pub fn queue_menu_pushes(&mut self, menu_pushes: Vec<MenuPush>) {
+        self.context.menu_pushes.extend(menu_pushes);
+    }
+
+    /// Ajoute aux journaux des enregistrements produits par le simulateur, avec le `TABLE_INDEX`
+    /// suivant de chaque journal (annoncé ensuite à l'AFSEC+ par `IC_DATA_OUT_TABLE_INDEX`)
+    pub fn append_journal_records(&mut self, journal_records: Vec<JournalRecord>) {
+        for journal_record in journal_records {
+            let table_index = self.context.records.next_index(journal_record.zone);
+            if self.context.debug_level >= DEBUG_LEVEL_SOME {
+                println!("AFSEC Comm: RECORD #{table_index} simulé en {journal_record}");
+            }
+        }
+    }
+
+    /// Contexte des conversations à sauvegarder avant l'arrêt du simulateur
+    pub fn save_context_state(&self) -> ContextState {
+        self.context.save_state()
+    }
+
+    /// Restauration du contexte des conversations sauvegardé lors d'un arrêt précédent
+    pub fn restore_context_state(&mut self, context_state: &ContextState) {
+        self.context.restore_state(context_state);
+    }
+
+    /// Compteurs des conversations
+    pub fn stats(&self) -> &ProtocolStats {
+        &self.context.stats
+    }
+
+    /// Reprise des compteurs des conversations (voir `StatsStore`)
+    pub fn set_stats(&mut self, stats: ProtocolStats) {
+        self.context.stats = stats;
+    }
+
+    /// Indique si un `AF_INIT` est attendu après une coupure d'alimentation simulée
+    pub fn is_initializing(&self) -> bool {
+        self.context.is_initializing
+    }
+
     /// Recherche un `middleware` pour accepter la conversation
     /// Si un `middleware` accepte la conversation, il retourne sa réponse à faire à l'AFSEC+
     /// et il est enregistré comme le `middleware` en cours pour converser.
@@ -148,7 +371,7 @@ impl Middlewares {
     ) -> Option<RawFrame> {
         for (id_middleware, middleware) in Self::all_middlewares().iter().enumerate() {
             if let Some(response_raw_frame) =
-                middleware.get_conversation(&mut self.context, afsec_service, request_data_frame)
+                self.get_conversation_timed(middleware.as_ref(), afsec_service, request_data_frame)
             {
                 self.option_cur_middleware = Some(id_middleware);
                 return Some(response_raw_frame);
@@ -159,7 +382,7 @@ impl Middlewares {
         None
     }
 
-    /// Dispatch un changement dans la database à tous les `middlewares`
+    /// Dispatch un changement dans la database aux `middlewares` intéressés par ce tag
     pub fn notification_change(
         &mut self,
         afsec_service: &mut DatabaseAfsecComm,
@@ -167,17 +390,34 @@ impl Middlewares {
         id_tag: IdTag,
         t_value: &TValue,
     ) {
+        self.notification_changes(afsec_service, &[(id_user, id_tag, t_value.clone())]);
+    }
+
+    /// Dispatch un lot de changements dans la database (dans l'ordre) : chaque `middleware`
+    /// reçoit en une fois les changements des tags qui l'intéressent
+    pub fn notification_changes(
+        &mut self,
+        afsec_service: &mut DatabaseAfsecComm,
+        changes: &[(IdUser, IdTag, TValue)],
+    ) {
+        if changes.is_empty() {
+            return;
+        }
         if afsec_service.debug_level >= DEBUG_LEVEL_ALL {
-            println!("AFSEC Comm: notification_change id_user={id_user} id_tag={id_tag}, t_value={t_value}");
+            for (id_user, id_tag, t_value) in changes {
+                println!("AFSEC Comm: notification_change id_user={id_user} id_tag={id_tag}, t_value={t_value}");
+            }
         }
-        for middleware in Self::all_middlewares() {
-            middleware.notification_change(
-                &mut self.context,
-                afsec_service,
-                id_user,
-                id_tag,
-                t_value,
-            );
+        for (id_middleware, middleware) in Self::all_middlewares().iter().enumerate() {
+            let interesting_changes: Vec<(IdUser, IdTag, TValue)> = changes
+                .iter()
+                .filter(|(_, id_tag, _)| self.is_interested(id_middleware, *id_tag))
+                .cloned()
+                .collect();
+            if interesting_changes.is_empty() {
+                continue;
+            }
+            middleware.notification_changes(&mut self.context, afsec_service, &interesting_changes);
         }
     }
 
@@ -188,20 +428,148 @@ impl Middlewares {
         afsec_service: &mut DatabaseAfsecComm,
         request_raw_frame: RawFrame,
     ) -> RawFrame {
+        let now = Instant::now();
+        self.check_conversation_timeout(now);
+        self.option_last_request = Some(now);
+
+        // `AF_INIT` répété par l'AFSEC+: même réponse, sans nouvelle initialisation
+        if let Some(response_raw_frame) = self.duplicate_init_response(&request_raw_frame, now) {
+            if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                println!(
+                    "AFSEC Comm: AF_INIT #{} répété (ignoré)...",
+                    self.context.stats.nb_init
+                );
+            }
+            return response_raw_frame;
+        }
+
+        let copy_request_raw_frame = request_raw_frame.clone();
         match DataFrame::try_from(request_raw_frame) {
             Ok(request_data_frame) => {
-                self.handle_request_data_frame(afsec_service, &request_data_frame)
+                test_summary::record_frame(id_message::name_of(request_data_frame.get_tag()));
+                self.nb_malformed_frames = 0;
+                let response_raw_frame =
+                    self.handle_request_data_frame(afsec_service, &request_data_frame);
+                self.option_last_init = (request_data_frame.get_tag() == id_message::AF_INIT)
+                    .then(|| (now, copy_request_raw_frame, response_raw_frame.clone()));
+                response_raw_frame
             }
             Err(e) => {
+                test_summary::record_frame(MALFORMED_FRAME_NAME);
                 if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
                     println!("AFSEC Comm: Got frame with error: {e}");
                 }
-                // On ne répond rien
-                RawFrame::new(&[])
+                self.option_last_init = None;
+                // Pas de réponse ou NACK selon le profil firmware ou l'option `--malformed-answer`
+                self.nb_malformed_frames = self.nb_malformed_frames.saturating_add(1);
+                if afsec_service
+                    .malformed_answer()
+                    .is_nack(self.nb_malformed_frames)
+                {
+                    if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                        println!(
+                            "AFSEC Comm: NACK (trame inexploitable #{})...",
+                            self.nb_malformed_frames
+                        );
+                    }
+                    RawFrame::new_nack()
+                } else {
+                    RawFrame::new(&[])
+                }
+            }
+        }
+    }
+
+    /// Réponse déjà faite à un `AF_INIT` si la requête est un doublon de ce `AF_INIT` (même
+    /// contenu, reçu juste après lui dans le délai `DUPLICATE_INIT_WINDOW`)
+    fn duplicate_init_response(
+        &self,
+        request_raw_frame: &RawFrame,
+        now: Instant,
+    ) -> Option<RawFrame> {
+        let (date_init, request_init, response_init) = self.option_last_init.as_ref()?;
+        (request_init == request_raw_frame
+            && now.saturating_duration_since(*date_init) < DUPLICATE_INIT_WINDOW)
+            .then(|| response_init.clone())
+    }
+
+    /// Prise en compte du mode de fonctionnement de l'AFSEC+ (`D_MODE_AFSEC`) éventuellement
+    /// présent dans une requête et publication dans le tag `ID_TAG_SIM_AFSEC_MODE`
+    fn update_mode_afsec(
+        &mut self,
+        afsec_service: &mut DatabaseAfsecComm,
+        request_data_frame: &DataFrame,
+    ) {
+        let Some(mode) = request_data_frame
+            .get_data_items()
+            .iter()
+            .find(|data_item| data_item.tag == id_message::D_MODE_AFSEC)
+            .map(|data_item| u16::from(&data_item.t_value))
+        else {
+            return;
+        };
+        if self.context.option_mode_afsec == Some(mode) {
+            return;
+        }
+        if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+            println!("AFSEC Comm: Mode AFSEC+ {mode}");
+        }
+        self.context.option_mode_afsec = Some(mode);
+        if let Err(db_error) =
+            utils::update_database(afsec_service, ID_TAG_SIM_AFSEC_MODE, TValue::U16(mode))
+        {
+            if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                println!("AFSEC Comm: !!! Mode AFSEC+: {db_error}");
             }
         }
     }
 
+    /// Prise en compte des pictogrammes de la face avant (`D_MENU_PICTOS`) éventuellement
+    /// présents dans une requête et publication dans le tag `ID_TAG_SIM_FRONT_PICTOS`
+    fn update_menu_pictos(
+        &mut self,
+        afsec_service: &mut DatabaseAfsecComm,
+        request_data_frame: &DataFrame,
+    ) {
+        let Some(pictos) = request_data_frame
+            .get_data_items()
+            .iter()
+            .find(|data_item| data_item.tag == id_message::D_MENU_PICTOS)
+            .map(|data_item| u32::from(&data_item.t_value))
+        else {
+            return;
+        };
+        if self.context.option_menu_pictos == Some(pictos) {
+            return;
+        }
+        if afsec_service.debug_level >= DEBUG_LEVEL_ALL {
+            println!("AFSEC Comm: Pictos 0x{pictos:08X}");
+        }
+        self.context.option_menu_pictos = Some(pictos);
+        let id_user = afsec_service.id_user;
+        afsec_service
+            .lock_database()
+            .set_front_panel_pictos(id_user, pictos);
+    }
+
+    /// Prise en compte d'une demande de pause des transmissions `DATA_IN` et `PACK_IN`
+    /// (`D_BUSY_DELAY`) éventuellement présente dans une requête
+    fn update_busy(&mut self, afsec_service: &DatabaseAfsecComm, request_data_frame: &DataFrame) {
+        let Some(busy_delay) = request_data_frame
+            .get_data_items()
+            .iter()
+            .find(|data_item| data_item.tag == id_message::D_BUSY_DELAY)
+            .map(|data_item| u16::from(&data_item.t_value))
+        else {
+            return;
+        };
+        if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+            println!("AFSEC Comm: AFSEC+ occupé, pause DATA_IN/PACK_IN de {busy_delay} ms");
+        }
+        self.context.option_busy_until =
+            (busy_delay > 0).then(|| Instant::now() + Duration::from_millis(u64::from(busy_delay)));
+    }
+
     /// Traite (privé) une requête TLV de l'AFSEC+ au format `DataFrame` (après décodage de la `RawFrame` reçue)
     /// et retourne la réponse à faire au format `RawFrame`
     fn handle_request_data_frame(
@@ -209,6 +577,15 @@ impl Middlewares {
         afsec_service: &mut DatabaseAfsecComm,
         request_data_frame: &DataFrame,
     ) -> RawFrame {
+        // Mode de fonctionnement de l'AFSEC+ annoncé dans `AF_INIT` ou changement de mode
+        self.update_mode_afsec(afsec_service, request_data_frame);
+
+        // Pictogrammes de la face avant
+        self.update_menu_pictos(afsec_service, request_data_frame);
+
+        // Pause des transmissions demandée par l'AFSEC+
+        self.update_busy(afsec_service, request_data_frame);
+
         if request_data_frame.get_tag() == id_message::AF_INIT {
             // L'AFSEC+ annonce une initialisation des communications
 
@@ -217,22 +594,53 @@ impl Middlewares {
 
             // Traitement AF_INIT
             let middleware = MInit::default();
-            return match middleware.get_conversation(
-                &mut self.context,
-                afsec_service,
-                request_data_frame,
-            ) {
+            return match self.get_conversation_timed(&middleware, afsec_service, request_data_frame)
+            {
                 Some(response_raw_frame) => response_raw_frame,
                 None => RawFrame::new_nack(),
             };
         }
 
+        // Après une coupure d'alimentation simulée, seul `AF_INIT` est accepté
+        if self.context.is_initializing {
+            if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                println!("AFSEC Comm: NACK (AF_INIT attendu)...");
+            }
+            return RawFrame::new_nack();
+        }
+
+        // Messages refusés dans le mode de fonctionnement courant de l'AFSEC+
+        if afsec_service
+            .mode_policy
+            .is_refused(self.context.option_mode_afsec, request_data_frame.get_tag())
+        {
+            if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                println!(
+                    "AFSEC Comm: NACK (message 0x{:02X} refusé en mode {})...",
+                    request_data_frame.get_tag(),
+                    self.context.option_mode_afsec.unwrap_or_default()
+                );
+            }
+            return RawFrame::new_nack();
+        }
+
+        // Réponse d'un 'hook' `on_frame` du script (feature `scripting`)
+        if let Some(response_raw_frame) = afsec_service.script_response(request_data_frame) {
+            if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                println!(
+                    "AFSEC Comm: Réponse du script au message 0x{:02X}...",
+                    request_data_frame.get_tag()
+                );
+            }
+            return response_raw_frame;
+        }
+
         // Sinon, on regarde si un `middleware` est déjà en cours de conversation
         if let Some(id_middleware) = &self.option_cur_middleware {
             // Conversation en cours, on passe la requête à ce `middleware`
             let middleware = &Self::all_middlewares()[*id_middleware];
             if let Some(response_raw_frame) =
-                middleware.get_conversation(&mut self.context, afsec_service, request_data_frame)
+                self.get_conversation_timed(middleware.as_ref(), afsec_service, request_data_frame)
             {
                 return response_raw_frame;
             }
@@ -252,11 +660,31 @@ impl Middlewares {
 
         // Pas de `middleware` pour répondre...
         if request_data_frame.get_tag() == id_message::AF_ALIVE {
-            // On peut répondre IC_ALIVE ou ACK
+            // On peut répondre IC_ALIVE ou ACK (selon le profil firmware émulé ou l'option
+            // `--alive-answer`)
             if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
                 println!("AFSEC Comm: AF_ALIVE...");
             }
-            RawFrame::new_ack()
+            match afsec_service.alive_answer() {
+                AliveAnswer::Ack => RawFrame::new_ack(),
+                AliveAnswer::IcAlive => RawFrame::new_message(id_message::IC_ALIVE),
+                AliveAnswer::IcAliveWithStatus => {
+                    let mut response_raw_frame = RawFrame::new_message(id_message::IC_ALIVE);
+                    response_raw_frame
+                        .try_extend_data_item(&DataItem::new(
+                            id_message::D_PROTOCOLE_VERSION,
+                            TValue::U16(afsec_service.firmware_profile.protocol_version()),
+                        ))
+                        .unwrap();
+                    response_raw_frame
+                        .try_extend_data_item(&DataItem::new(
+                            id_message::D_ICOM_VERSION,
+                            TValue::U16(afsec_service.firmware_profile.icom_version()),
+                        ))
+                        .unwrap();
+                    response_raw_frame
+                }
+            }
         } else {
             // Répond NACK
             if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
@@ -276,6 +704,7 @@ mod tests {
     use crate::afsec::check_notification_changes;
     use crate::afsec::tlv_frame::DataItem;
     use crate::afsec::tlv_frame::FrameState;
+    use crate::afsec::MalformedAnswer;
     use crate::database::Tag;
     use crate::database::ID_ANONYMOUS_USER;
     use crate::t_data::TFormat;
@@ -447,12 +876,10 @@ mod tests {
         };
         assert_eq!(response.get_tag(), tag);
 
-        for data_item in response.get_data_items() {
-            assert!(
-                data_item.tag != id_message::D_DATA_ERROR,
-                "Réponse avec D_DATA_ERROR"
-            );
-        }
+        assert!(
+            response.find_item(id_message::D_DATA_ERROR).is_none(),
+            "Réponse avec D_DATA_ERROR"
+        );
 
         true
     }
@@ -478,10 +905,10 @@ mod tests {
         // Modification de la database
         {
             // Verrouiller la database partagée
-            let mut db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let mut db = afsec_service.lock_database();
 
-            db.set_u16_to_id_tag(ID_ANONYMOUS_USER, test_tag().id_tag, value);
+            db.set_u16_to_id_tag(ID_ANONYMOUS_USER, test_tag().id_tag, value)
+                .unwrap();
         }
 
         // Active le système de notification
@@ -501,10 +928,10 @@ mod tests {
 
         {
             // Verrouiller la database partagée
-            let mut db: std::sync::MutexGuard<'_, crate::database::Database> =
-                afsec_service.thread_db.lock().unwrap();
+            let mut db = afsec_service.lock_database();
 
-            db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, ADDRESS_WORD_PACK_IN + address, value);
+            db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, ADDRESS_WORD_PACK_IN + address, value)
+                .unwrap();
         }
 
         // Active le système de notification
@@ -582,4 +1009,456 @@ mod tests {
             ok_ack_raw_frame(&response) || ok_response_raw_frame(id_message::IC_ALIVE, &response)
         );
     }
+
+    #[test]
+    fn test_firmware_profile() {
+        let afsec_service = database_setup();
+        let firmware_profile: crate::afsec::FirmwareProfile =
+            "icom_version = 5020\nalive_answer = ic_alive\nmenu = ic_menu"
+                .parse()
+                .unwrap();
+        let mut afsec_service = afsec_service.with_firmware_profile(firmware_profile);
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+
+        // IC_INIT annonce la version de l'ICOM
+        let request = request_raw_frame_init();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        let data_frame = DataFrame::try_from(response).unwrap();
+        assert_eq!(data_frame.get_tag(), id_message::IC_INIT);
+        let icom_version = data_frame
+            .get_data_items()
+            .iter()
+            .find(|data_item| data_item.tag == id_message::D_ICOM_VERSION)
+            .map(|data_item| u16::from(&data_item.t_value));
+        assert_eq!(icom_version, Some(5020));
+
+        // AF_ALIVE acquitté par IC_ALIVE
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(id_message::IC_ALIVE, &response));
+
+        // AF_MENU accepté
+        let request = RawFrame::new_message(id_message::AF_MENU);
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(id_message::IC_MENU, &response));
+    }
+
+    #[test]
+    fn test_init_identity() {
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+
+        // Pas d'identité: IC_INIT sans numéro de série
+        let request = request_raw_frame_init();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        let data_frame = DataFrame::try_from(response).unwrap();
+        assert!(!data_frame
+            .get_data_items()
+            .iter()
+            .any(|data_item| data_item.tag == id_message::D_ICOM_SERIAL_NUMBER));
+
+        afsec_service.lock_database().set_sim_identity(
+            crate::database::SimIdentity::with_serial_number("BENCH-01").unwrap(),
+        );
+        // Nouvelle instance: un `AF_INIT` identique juste après le précédent serait un doublon
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+        let request = request_raw_frame_init();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        let data_frame = DataFrame::try_from(response).unwrap();
+        let serial_number = data_frame
+            .get_data_items()
+            .iter()
+            .find(|data_item| data_item.tag == id_message::D_ICOM_SERIAL_NUMBER)
+            .and_then(|data_item| data_item.t_value.as_text());
+        assert_eq!(serial_number, Some("BENCH-01".to_string()));
+    }
+
+    #[test]
+    fn test_alive_answer() {
+        let afsec_service = database_setup();
+        let mut afsec_service =
+            afsec_service.with_alive_answer(Some(AliveAnswer::IcAliveWithStatus));
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+
+        // IC_ALIVE avec l'état de l'ICOM
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        let data_frame = DataFrame::try_from(response).unwrap();
+        assert_eq!(data_frame.get_tag(), id_message::IC_ALIVE);
+        assert!(data_frame
+            .get_data_items()
+            .iter()
+            .any(|data_item| data_item.tag == id_message::D_ICOM_VERSION));
+
+        // Réponse imposée prioritaire sur le profil firmware
+        let firmware_profile: crate::afsec::FirmwareProfile =
+            "alive_answer = ic_alive".parse().unwrap();
+        let mut afsec_service = afsec_service
+            .with_firmware_profile(firmware_profile)
+            .with_alive_answer(Some(AliveAnswer::Ack));
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_ack_raw_frame(&response));
+    }
+
+    #[test]
+    fn test_malformed_answer() {
+        let afsec_service = database_setup();
+        let mut afsec_service =
+            afsec_service.with_malformed_answer(Some(MalformedAnswer::NackAfter(2)));
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+
+        // Trame complète avec un `DataItem` incorrect
+        let malformed = || {
+            RawFrame::Ok(
+                id_message::AF_ALIVE,
+                4,
+                vec![3, u8::from(TFormat::VecU8(4)), b'A', b'B'],
+                0,
+            )
+        };
+
+        // Pas de réponse à la première trame, NACK à partir de la seconde
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, malformed());
+        assert_eq!(response, RawFrame::new(&[]));
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, malformed());
+        assert!(DataFrame::try_from(response).unwrap().is_simple_nack());
+
+        // Une trame correcte remet le compte à 0
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_ack_raw_frame(&response));
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, malformed());
+        assert_eq!(response, RawFrame::new(&[]));
+
+        // Profil avec NACK immédiat
+        let firmware_profile: crate::afsec::FirmwareProfile =
+            "malformed_answer = nack".parse().unwrap();
+        let mut afsec_service = afsec_service
+            .with_malformed_answer(None)
+            .with_firmware_profile(firmware_profile);
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, malformed());
+        assert!(DataFrame::try_from(response).unwrap().is_simple_nack());
+    }
+
+    #[test]
+    fn test_download_refused() {
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+
+        // Pas de `middleware` pour AF_DOWNLOAD (voir 'Non implémenté' dans le README)
+        let mut request = RawFrame::new_message(id_message::AF_DOWNLOAD);
+        request
+            .try_extend_data_item(&DataItem::new(
+                id_message::D_DOWNLOAD_SECTION,
+                TValue::U8(1),
+            ))
+            .unwrap();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(DataFrame::try_from(response).unwrap().is_simple_nack());
+    }
+
+    #[test]
+    fn test_power_cycle() {
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+
+        let request = request_raw_frame_init();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(id_message::IC_INIT, &response));
+
+        // Après la coupure, seul AF_INIT est accepté
+        middlewares.power_cycle();
+        assert!(middlewares.is_initializing());
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(DataFrame::try_from(response).unwrap().is_simple_nack());
+
+        let request = request_raw_frame_init();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(id_message::IC_INIT, &response));
+        assert!(!middlewares.is_initializing());
+
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(
+            ok_ack_raw_frame(&response) || ok_response_raw_frame(id_message::IC_ALIVE, &response)
+        );
+    }
+
+    #[test]
+    fn test_mode_afsec() {
+        let afsec_service = database_setup();
+        afsec_service.lock_database().add_sim_tags();
+        let mode_policy = crate::afsec::ModePolicy::from_rules(&["2=ALIVE".to_string()]).unwrap();
+        let mut afsec_service = afsec_service.with_mode_policy(mode_policy);
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+        let get_mode = |afsec_service: &DatabaseAfsecComm| {
+            afsec_service
+                .lock_database()
+                .get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_AFSEC_MODE)
+        };
+
+        // Mode annoncé dans AF_INIT
+        let mut request = request_raw_frame_init();
+        request
+            .try_extend_data_item(&DataItem::new(id_message::D_MODE_AFSEC, TValue::U8(2)))
+            .unwrap();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(id_message::IC_INIT, &response));
+        assert_eq!(get_mode(&afsec_service), 2);
+
+        // AF_ALIVE refusé dans ce mode
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(DataFrame::try_from(response).unwrap().is_simple_nack());
+
+        // Changement de mode
+        let mut request = request_raw_frame_alive();
+        request
+            .try_extend_data_item(&DataItem::new(id_message::D_MODE_AFSEC, TValue::U8(1)))
+            .unwrap();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(
+            ok_ack_raw_frame(&response) || ok_response_raw_frame(id_message::IC_ALIVE, &response)
+        );
+        assert_eq!(get_mode(&afsec_service), 1);
+    }
+
+    #[test]
+    fn test_menu_pictos() {
+        let mut afsec_service = database_setup();
+        afsec_service.lock_database().add_sim_tags();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+
+        let mut request = RawFrame::new_message(id_message::AF_MENU);
+        request
+            .try_extend_data_item(&DataItem::new(
+                id_message::D_MENU_PICTOS,
+                TValue::U32(0x0000_0003),
+            ))
+            .unwrap();
+        middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        let front_panel = afsec_service
+            .lock_database()
+            .get_front_panel(ID_ANONYMOUS_USER);
+        assert_eq!(front_panel.pictos, 0x0000_0003);
+        assert_eq!(
+            front_panel.leds & crate::database::FRONT_LED_ALARM,
+            crate::database::FRONT_LED_ALARM
+        );
+    }
+
+    #[test]
+    fn test_busy_delay() {
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+        let request = request_raw_frame_init();
+        middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        do_update_test_tag(&mut afsec_service, &mut middlewares, 123);
+        do_update_pack_in(&mut afsec_service, &mut middlewares, 10, &[1, 2]);
+
+        // AF_ALIVE avec demande de pause : pas de IC_PACK_IN ni de IC_DATA_IN
+        let mut request = request_raw_frame_alive();
+        request
+            .try_extend_data_item(&DataItem::new(
+                id_message::D_BUSY_DELAY,
+                TValue::U16(60_000),
+            ))
+            .unwrap();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(
+            ok_ack_raw_frame(&response) || ok_response_raw_frame(id_message::IC_ALIVE, &response)
+        );
+        assert!(middlewares.context.is_busy());
+
+        // Fin de la pause : les transmissions reprennent
+        middlewares.context.option_busy_until = Some(Instant::now());
+        let response =
+            middlewares.handle_request_raw_frame(&mut afsec_service, request_raw_frame_alive());
+        assert!(ok_response_raw_frame(id_message::IC_PACK_IN, &response));
+
+        // Nouvelle pause annulée par un délai nul
+        for busy_delay in [60_000, 0] {
+            let mut request = request_raw_frame_alive();
+            request
+                .try_extend_data_item(&DataItem::new(
+                    id_message::D_BUSY_DELAY,
+                    TValue::U16(busy_delay),
+                ))
+                .unwrap();
+            let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+            let response = DataFrame::try_from(response).unwrap();
+            assert_eq!(
+                response.get_tag() == id_message::IC_DATA_IN,
+                busy_delay == 0
+            );
+        }
+    }
+
+    #[test]
+    fn test_interests() {
+        let middlewares = Middlewares::new(DEBUG_LEVEL_SOME);
+        let id_pack_in = 1;
+        let id_data_in = 3;
+
+        // Tag `DATA_PACK` de la zone de commande: pour `MPackIn` seulement
+        let id_tag = IdTag::new(5, TAG_DATA_PACK, [0, 0, 2]);
+        assert!(middlewares.is_interested(id_pack_in, id_tag));
+        assert!(!middlewares.is_interested(id_data_in, id_tag));
+
+        // Autre tag: pour `MDataIn` seulement
+        let id_tag = test_tag().id_tag;
+        assert!(!middlewares.is_interested(id_pack_in, id_tag));
+        assert!(middlewares.is_interested(id_data_in, id_tag));
+
+        // Aucun autre `middleware` n'est intéressé par ce tag
+        assert!((0..Middlewares::all_middlewares().len())
+            .filter(|id_middleware| middlewares.is_interested(*id_middleware, id_tag))
+            .eq([id_data_in]));
+    }
+
+    #[test]
+    fn test_conversation_timeout() {
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level)
+            .with_conversation_timeout(Duration::from_secs(1));
+
+        let request = request_raw_frame_init();
+        middlewares.handle_request_raw_frame(&mut afsec_service, request);
+
+        // Conversation AF_ALIVE -> IC_DATA_IN en attente de l'acquittement de l'AFSEC+
+        do_update_test_tag(&mut afsec_service, &mut middlewares, 456);
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(id_message::IC_DATA_IN, &response));
+        assert!(middlewares.option_cur_middleware.is_some());
+
+        // Pas d'abandon avant le délai d'inactivité
+        let last_request = middlewares.option_last_request.unwrap();
+        assert!(!middlewares.check_conversation_timeout(last_request));
+        assert!(middlewares.option_cur_middleware.is_some());
+
+        // Abandon au delà du délai d'inactivité (une seule fois)
+        let now = last_request + Duration::from_secs(2);
+        assert!(middlewares.check_conversation_timeout(now));
+        assert!(middlewares.option_cur_middleware.is_none());
+        assert_eq!(middlewares.context.stats.nb_abandoned, 1);
+        assert!(!middlewares.check_conversation_timeout(now));
+
+        // Jamais d'abandon avec un délai nul
+        let mut afsec_service = database_setup();
+        let mut middlewares = middlewares.with_conversation_timeout(Duration::ZERO);
+        do_update_test_tag(&mut afsec_service, &mut middlewares, 789);
+        let request = request_raw_frame_alive();
+        middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(middlewares.option_cur_middleware.is_some());
+        let now = middlewares.option_last_request.unwrap() + Duration::from_secs(3600);
+        assert!(!middlewares.check_conversation_timeout(now));
+    }
+
+    #[test]
+    fn test_duplicate_init() {
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+
+        // Modification de la zone 'pack-in' à transmettre avant les AF_INIT
+        do_update_pack_in(&mut afsec_service, &mut middlewares, 10, &[1, 2, 3, 4]);
+
+        // 2 AF_INIT identiques à la suite: Même réponse et une seule initialisation
+        let request = request_raw_frame_init();
+        let response_1 = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(id_message::IC_INIT, &response_1));
+        let request = request_raw_frame_init();
+        let response_2 = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert_eq!(response_1, response_2);
+        assert_eq!(middlewares.context.stats.nb_init, 1);
+
+        // La transaction 'pack-in' n'est pas perdue
+        let request = request_raw_frame_alive();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(id_message::IC_PACK_IN, &response));
+
+        // Un AF_INIT après une autre requête est une nouvelle initialisation
+        let request = request_raw_frame_init();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert_eq!(response, response_1);
+        assert_eq!(middlewares.context.stats.nb_init, 2);
+
+        // Un AF_INIT différent du précédent aussi
+        let mut request = request_raw_frame_init();
+        request
+            .try_extend_data_item(&DataItem::new(id_message::D_MODE_AFSEC, TValue::U16(2)))
+            .unwrap();
+        middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert_eq!(middlewares.context.stats.nb_init, 3);
+    }
+
+    #[test]
+    fn test_middleware_stats() {
+        assert_eq!(MInit::default().name(), "MInit");
+        assert_eq!(MPackIn::default().name(), "MPackIn");
+
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+        let nb_calls = |name: &str| {
+            profiling::get_middleware_stats()
+                .iter()
+                .find(|stats| stats.name == name)
+                .map_or(0, |stats| stats.nb_calls)
+        };
+        let nb_init_calls = nb_calls("MInit");
+        let request = request_raw_frame_init();
+        middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(nb_calls("MInit") > nb_init_calls);
+    }
+
+    #[test]
+    fn test_append_journal_records() {
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+        let request = request_raw_frame_init();
+        middlewares.handle_request_raw_frame(&mut afsec_service, request);
+
+        // Indices annoncés par IC_DATA_OUT_TABLE_INDEX pour le journal des événements
+        let journal_record = JournalRecord {
+            zone: 3,
+            datas: vec![(IdTag::new(3, 0x0100, [0, 0, 0]), TValue::U16(1))],
+        };
+        middlewares.append_journal_records(vec![journal_record.clone(), journal_record]);
+        let mut request = RawFrame::new_message(id_message::AF_DATA_OUT_TABLE_INDEX);
+        request
+            .try_extend_data_item(&DataItem::new(id_message::D_DATA_ZONE, TValue::U8(3)))
+            .unwrap();
+        let response = middlewares.handle_request_raw_frame(&mut afsec_service, request);
+        assert!(ok_response_raw_frame(
+            id_message::IC_DATA_OUT_TABLE_INDEX,
+            &response
+        ));
+        assert_eq!(middlewares.context.records.get_index_min(3), 1);
+        assert_eq!(middlewares.context.records.get_index_max(3), 2);
+    }
+
+    #[test]
+    fn test_queue_menu_pushes() {
+        let mut afsec_service = database_setup();
+        let mut middlewares = Middlewares::new(afsec_service.debug_level);
+        let request = request_raw_frame_init();
+        middlewares.handle_request_raw_frame(&mut afsec_service, request);
+
+        // Menu poussé au prochain AF_ALIVE, puis AF_ALIVE habituel
+        let menu_push = MenuPush {
+            id_menu: 1,
+            text: "Valider".to_string(),
+        };
+        middlewares.queue_menu_pushes(vec![menu_push]);
+        let response =
+            middlewares.handle_request_raw_frame(&mut afsec_service, request_raw_frame_alive());
+        assert!(ok_response_raw_frame(id_message::IC_MENU, &response));
+        let response =
+            middlewares.handle_request_raw_frame(&mut afsec_service, request_raw_frame_alive());
+        assert!(
+            ok_ack_raw_frame(&response) || ok_response_raw_frame(id_message::IC_ALIVE, &response)
+        );
+    }
 }
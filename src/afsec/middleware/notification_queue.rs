@@ -0,0 +1,222 @@
+//! File des `notification_changes` à transmettre à l'AFSEC+ (conversation DATA_IN)
+//!
+//! Les modifications sont ordonnées par priorité (champ `priority` des tags) : une modification
+//! d'un tag de sécurité est transmise avant celles des valeurs de moindre importance, même si
+//! elle arrive après une rafale de modifications de ces valeurs.
+//!
+//! Pour éviter la famine des modifications de faible priorité, une modification ne peut être
+//! dépassée que `MAX_BYPASSES` fois : au delà, les nouvelles modifications (même prioritaires)
+//! sont placées derrière elle.
+//!
+//! Lorsque de nombreuses zones sont modifiées simultanément, une zone très active ne doit pas
+//! monopoliser les trames `IC_DATA_IN` : parmi les modifications de même priorité en tête de
+//! file, la prochaine modification transmise est la première de la zone qui suit celle de la
+//! modification précédente (tourniquet par ordre croissant des zones, voir `front_interleaved`).
+//! L'ordre des modifications d'une même zone est conservé.
+
+use std::collections::VecDeque;
+
+use super::{IdTag, TValue};
+
+/// Nombre max. de fois qu'une modification peut être dépassée par des modifications plus
+/// prioritaires
+const MAX_BYPASSES: usize = 32;
+
+/// Modification en attente de transmission
+#[derive(Clone, Debug)]
+struct QueuedChange {
+    /// Tag modifié
+    id_tag: IdTag,
+
+    /// Nouvelle valeur
+    t_value: TValue,
+
+    /// Priorité du tag
+    priority: u8,
+
+    /// Nombre de fois où la modification a été dépassée
+    nb_bypasses: usize,
+}
+
+/// File ordonnée des modifications à transmettre à l'AFSEC+
+#[derive(Clone, Debug, Default)]
+pub struct NotificationQueue {
+    /// Modifications en attente, dans l'ordre de transmission
+    changes: VecDeque<QueuedChange>,
+}
+
+impl NotificationQueue {
+    /// Indique si la file est vide
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Ajoute une modification selon sa priorité (derrière les modifications de priorité
+    /// supérieure ou égale et derrière celles qui ne peuvent plus être dépassées)
+    pub fn push(&mut self, id_tag: IdTag, t_value: TValue, priority: u8) {
+        let first_lower = self
+            .changes
+            .iter()
+            .position(|change| change.priority < priority)
+            .unwrap_or(self.changes.len());
+        let after_starving = self
+            .changes
+            .iter()
+            .rposition(|change| change.nb_bypasses >= MAX_BYPASSES)
+            .map_or(0, |index| index + 1);
+        let index = first_lower.max(after_starving);
+        for change in self.changes.iter_mut().skip(index) {
+            change.nb_bypasses += 1;
+        }
+        self.changes.insert(
+            index,
+            QueuedChange {
+                id_tag,
+                t_value,
+                priority,
+                nb_bypasses: 0,
+            },
+        );
+    }
+
+    /// Prochaine modification à transmettre
+    pub fn front(&self) -> Option<(IdTag, TValue)> {
+        self.changes
+            .front()
+            .map(|change| (change.id_tag, change.t_value.clone()))
+    }
+
+    /// Prochaine modification à transmettre (indice dans la file, tag et valeur) en entrelaçant
+    /// les zones après une modification de la zone `option_last_zone`
+    pub fn front_interleaved(
+        &self,
+        option_last_zone: Option<u8>,
+    ) -> Option<(usize, IdTag, TValue)> {
+        let Some(last_zone) = option_last_zone else {
+            return self.front().map(|(id_tag, t_value)| (0, id_tag, t_value));
+        };
+        let front = self.changes.front()?;
+        // Zone suivante (circulairement) parmi les modifications de la priorité de tête
+        self.changes
+            .iter()
+            .take_while(|change| change.priority == front.priority)
+            .enumerate()
+            .min_by_key(|(_, change)| change.id_tag.zone.wrapping_sub(last_zone).wrapping_sub(1))
+            .map(|(index, change)| (index, change.id_tag, change.t_value.clone()))
+    }
+
+    /// Retire la modification d'indice `index` et la retourne (tag, valeur et priorité)
+    pub fn remove(&mut self, index: usize) -> Option<(IdTag, TValue, u8)> {
+        self.changes
+            .remove(index)
+            .map(|change| (change.id_tag, change.t_value, change.priority))
+    }
+
+    /// Remet en tête de file (dans l'ordre) des modifications transmises mais non confirmées
+    /// par l'AFSEC+
+    pub fn requeue_front(&mut self, changes: Vec<(IdTag, TValue, u8)>) {
+        for (id_tag, t_value, priority) in changes.into_iter().rev() {
+            self.changes.push_front(QueuedChange {
+                id_tag,
+                t_value,
+                priority,
+                nb_bypasses: 0,
+            });
+        }
+    }
+
+    /// Modifications en attente (tag, valeur et priorité) dans l'ordre de transmission
+    pub fn entries(&self) -> Vec<(IdTag, TValue, u8)> {
+        self.changes
+            .iter()
+            .map(|change| (change.id_tag, change.t_value.clone(), change.priority))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id_tag(num_tag: u16) -> IdTag {
+        IdTag::new(0, num_tag, [0, 0, 0])
+    }
+
+    fn drain(queue: &mut NotificationQueue) -> Vec<u16> {
+        let mut num_tags = vec![];
+        while let Some((id_tag, _)) = queue.front() {
+            num_tags.push(id_tag.num_tag);
+            queue.remove(0);
+        }
+        num_tags
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut queue = NotificationQueue::default();
+        queue.push(id_tag(1), TValue::U16(1), 0);
+        queue.push(id_tag(2), TValue::U16(2), 0);
+        queue.push(id_tag(3), TValue::U16(3), 5);
+        queue.push(id_tag(4), TValue::U16(4), 5);
+        queue.push(id_tag(5), TValue::U16(5), 1);
+        assert_eq!(drain(&mut queue), [3, 4, 5, 1, 2]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_starvation() {
+        let mut queue = NotificationQueue::default();
+        queue.push(id_tag(0), TValue::U16(0), 0);
+        for num_tag in (1..).take(MAX_BYPASSES + 2) {
+            queue.push(id_tag(num_tag), TValue::U16(num_tag), 9);
+        }
+
+        // La modification de faible priorité n'est pas dépassée plus de MAX_BYPASSES fois
+        let num_tags = drain(&mut queue);
+        assert_eq!(num_tags[MAX_BYPASSES], 0);
+    }
+
+    #[test]
+    fn test_interleaved() {
+        let mut queue = NotificationQueue::default();
+        for (zone, num_tag, priority) in [
+            (1, 1, 0),
+            (1, 2, 0),
+            (1, 3, 0),
+            (2, 4, 0),
+            (3, 5, 0),
+            (2, 6, 9),
+        ] {
+            queue.push(
+                IdTag::new(zone, num_tag, [0, 0, 0]),
+                TValue::U16(num_tag),
+                priority,
+            );
+        }
+
+        // Priorité respectée puis une modification de chaque zone à tour de rôle
+        let mut num_tags = vec![];
+        let mut option_last_zone = None;
+        while let Some((index, id_tag, _)) = queue.front_interleaved(option_last_zone) {
+            num_tags.push(id_tag.num_tag);
+            option_last_zone = Some(id_tag.zone);
+            queue.remove(index);
+        }
+        assert_eq!(num_tags, [6, 5, 1, 4, 2, 3]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_requeue_front() {
+        let mut queue = NotificationQueue::default();
+        queue.push(id_tag(1), TValue::U16(1), 5);
+        queue.push(id_tag(2), TValue::U16(2), 0);
+        queue.push(id_tag(3), TValue::U16(3), 0);
+        let in_flight = vec![queue.remove(0).unwrap(), queue.remove(0).unwrap()];
+        assert_eq!(in_flight[0], (id_tag(1), TValue::U16(1), 5));
+
+        // Les modifications non confirmées repassent devant, dans le même ordre
+        queue.requeue_front(in_flight);
+        assert_eq!(drain(&mut queue), [1, 2, 3]);
+    }
+}
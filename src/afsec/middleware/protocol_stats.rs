@@ -0,0 +1,262 @@
+//! Compteurs des conversations avec l'AFSEC+ persistants d'un démarrage à l'autre
+//!
+//! Les compteurs des conversations (`AF_INIT`, `AF_PACK_OUT`, ...) sont regroupés dans
+//! [`ProtocolStats`] (voir `Context::stats`). Avec l'option `--protocol-stats <fichier>`, ils
+//! sont repris du fichier au démarrage du simulateur et y sont sauvegardés périodiquement
+//! ([`StatsStore`]) et à l'arrêt du simulateur : les statistiques d'une campagne de plusieurs
+//! jours survivent aux redémarrages du simulateur.
+//!
+//! Le fichier n'est lu qu'une fois : le [`StatsStore`] conserve les compteurs à jour et une
+//! tâche de communication redémarrée par la supervision reprend ces compteurs (et non ceux de la
+//! dernière sauvegarde du fichier).
+//!
+//! Le fichier est un texte `<compteur>=<valeur>` (une ligne par compteur, lignes `#` de
+//! commentaire) lisible directement pour le rapport de fin de campagne. Les compteurs inconnus
+//! sont ignorés et les compteurs absents sont à 0.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Période (en secondes) par défaut de la sauvegarde des compteurs
+pub const DEFAULT_PROTOCOL_STATS_PERIOD_SECS: u64 = 60;
+
+/// Compteurs des conversations avec l'AFSEC+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolStats {
+    /// Nombre de démarrages du simulateur avec le fichier des compteurs
+    pub nb_starts: usize,
+
+    /// Nombre de INIT depuis le début
+    pub nb_init: usize,
+
+    /// Nombre de PACK_OUT depuis le début
+    pub nb_pack_out: usize,
+
+    /// Nombre de PACK_IN depuis le début
+    pub nb_pack_in: usize,
+
+    /// Nombre de DATA_OUT depuis le début
+    pub nb_data_out: usize,
+
+    /// Nombre de DATA_IN depuis le début
+    pub nb_data_in: usize,
+
+    /// Nombre de conversations abandonnées (délai d'inactivité dépassé) depuis le début
+    pub nb_abandoned: usize,
+}
+
+impl fmt::Display for ProtocolStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} starts, {} INIT, {} PACK_OUT, {} PACK_IN, {} DATA_OUT, {} DATA_IN, {} abandoned",
+            self.nb_starts,
+            self.nb_init,
+            self.nb_pack_out,
+            self.nb_pack_in,
+            self.nb_data_out,
+            self.nb_data_in,
+            self.nb_abandoned
+        )
+    }
+}
+
+impl ProtocolStats {
+    /// Compteurs (nom dans le fichier, valeur)
+    fn counters(&self) -> [(&'static str, usize); 7] {
+        [
+            ("starts", self.nb_starts),
+            ("init", self.nb_init),
+            ("pack_out", self.nb_pack_out),
+            ("pack_in", self.nb_pack_in),
+            ("data_out", self.nb_data_out),
+            ("data_in", self.nb_data_in),
+            ("abandoned", self.nb_abandoned),
+        ]
+    }
+
+    /// Compteur (mutable) selon son nom dans le fichier
+    fn counter_mut(&mut self, name: &str) -> Option<&mut usize> {
+        match name {
+            "starts" => Some(&mut self.nb_starts),
+            "init" => Some(&mut self.nb_init),
+            "pack_out" => Some(&mut self.nb_pack_out),
+            "pack_in" => Some(&mut self.nb_pack_in),
+            "data_out" => Some(&mut self.nb_data_out),
+            "data_in" => Some(&mut self.nb_data_in),
+            "abandoned" => Some(&mut self.nb_abandoned),
+            _ => None,
+        }
+    }
+
+    /// Contenu du fichier des compteurs
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# Compteurs des conversations avec l'AFSEC+\n");
+        for (name, value) in self.counters() {
+            text.push_str(&format!("{name}={value}\n"));
+        }
+        text
+    }
+
+    /// Compteurs depuis le contenu d'un fichier
+    /// # Errors
+    /// Message d'erreur si une ligne est incorrecte
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut stats = ProtocolStats::default();
+        for (num_line, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, value)) = line.split_once('=') else {
+                return Err(format!("Ligne {} incorrecte: '{line}'", num_line + 1));
+            };
+            let Ok(value) = value.trim().parse::<usize>() else {
+                return Err(format!("Ligne {} incorrecte: '{line}'", num_line + 1));
+            };
+            if let Some(counter) = stats.counter_mut(name.trim()) {
+                *counter = value;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Lecture des compteurs dans un fichier (compteurs à 0 si le fichier n'existe pas)
+    /// # Errors
+    /// Message d'erreur si le fichier ne peut pas être lu ou est incorrect
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        match std::fs::read_to_string(filename) {
+            Ok(text) => Self::from_text(&text).map_err(|msg| format!("'{filename}': {msg}")),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ProtocolStats::default()),
+            Err(e) => Err(format!("Erreur lecture '{filename}': {e}")),
+        }
+    }
+
+    /// Sauvegarde des compteurs dans un fichier (remplacé d'un bloc pour ne jamais laisser un
+    /// fichier incomplet)
+    /// # Errors
+    /// Message d'erreur si le fichier ne peut pas être écrit
+    pub fn to_file(&self, filename: &str) -> Result<(), String> {
+        let tmp_filename = format!("{filename}.tmp");
+        std::fs::write(&tmp_filename, self.to_text())
+            .and_then(|()| std::fs::rename(&tmp_filename, filename))
+            .map_err(|e| format!("Erreur écriture '{filename}': {e}"))
+    }
+}
+
+/// Fichier de sauvegarde périodique des [`ProtocolStats`]
+#[derive(Clone, Debug)]
+pub struct StatsStore {
+    /// Nom du fichier
+    filename: String,
+
+    /// Période de la sauvegarde
+    period: Duration,
+
+    /// Compteurs à jour (partagés entre les démarrages successifs de la tâche de communication)
+    current: Arc<Mutex<ProtocolStats>>,
+
+    /// Date de la dernière sauvegarde
+    option_last_flush: Option<Instant>,
+}
+
+impl StatsStore {
+    /// Compteurs repris du fichier pour un nouveau démarrage du simulateur (`nb_starts`
+    /// incrémenté)
+    /// # Errors
+    /// Message d'erreur si le fichier ne peut pas être lu ou est incorrect
+    pub fn load(filename: &str, period: Duration, now: Instant) -> Result<Self, String> {
+        let mut stats = ProtocolStats::from_file(filename)?;
+        stats.nb_starts += 1;
+        Ok(Self {
+            filename: filename.to_string(),
+            period,
+            current: Arc::new(Mutex::new(stats)),
+            option_last_flush: Some(now),
+        })
+    }
+
+    /// Nom du fichier
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Compteurs à jour (à reprendre au démarrage de la tâche de communication)
+    pub fn stats(&self) -> ProtocolStats {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Mise à jour des compteurs et sauvegarde si la période est écoulée depuis la sauvegarde
+    /// précédente
+    /// # Errors
+    /// Message d'erreur si le fichier ne peut pas être écrit
+    pub fn flush_if_due(&mut self, stats: &ProtocolStats, now: Instant) -> Result<(), String> {
+        self.current.lock().unwrap().clone_from(stats);
+        if self
+            .option_last_flush
+            .is_some_and(|last_flush| now.saturating_duration_since(last_flush) < self.period)
+        {
+            return Ok(());
+        }
+        self.option_last_flush = Some(now);
+        stats.to_file(&self.filename)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_stats_text() {
+        let stats = ProtocolStats {
+            nb_starts: 2,
+            nb_init: 3,
+            nb_data_out: 1234,
+            ..Default::default()
+        };
+        assert_eq!(ProtocolStats::from_text(&stats.to_text()), Ok(stats));
+
+        // Compteurs inconnus ignorés, compteurs absents à 0
+        let stats = ProtocolStats::from_text("# Test\ninit=5\nfuture=1\n").unwrap();
+        assert_eq!(stats.nb_init, 5);
+        assert_eq!(stats.nb_data_in, 0);
+        assert!(ProtocolStats::from_text("init=abc").is_err());
+        assert!(ProtocolStats::from_text("init").is_err());
+    }
+
+    #[test]
+    fn test_stats_store() {
+        let filename = std::env::temp_dir()
+            .join(format!(
+                "sim_icom_protocol_stats_{}.txt",
+                std::process::id()
+            ))
+            .to_string_lossy()
+            .to_string();
+        let _ = std::fs::remove_file(&filename);
+        let now = Instant::now();
+        let period = Duration::from_secs(60);
+
+        // Premier démarrage: fichier absent
+        let mut store = StatsStore::load(&filename, period, now).unwrap();
+        let mut stats = store.stats();
+        assert_eq!(stats.nb_starts, 1);
+        stats.nb_init += 1;
+
+        // Pas de sauvegarde avant la fin de la période mais compteurs à jour pour un
+        // redémarrage de la tâche de communication
+        store.flush_if_due(&stats, now).unwrap();
+        assert!(!std::path::Path::new(&filename).exists());
+        assert_eq!(store.clone().stats(), stats);
+        store
+            .flush_if_due(&stats, now + Duration::from_secs(60))
+            .unwrap();
+
+        // Redémarrage du simulateur: compteurs repris du fichier
+        let stats = StatsStore::load(&filename, period, now).unwrap().stats();
+        assert_eq!((stats.nb_starts, stats.nb_init), (2, 1));
+        let _ = std::fs::remove_file(&filename);
+    }
+}
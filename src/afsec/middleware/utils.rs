@@ -1,6 +1,6 @@
 //! Helpers pour les `middlewares`
 
-use super::{Context, DatabaseAfsecComm, IdTag, RecordData, TValue, DEBUG_LEVEL_ALL};
+use super::{Context, DatabaseAfsecComm, DbError, IdTag, RecordData, TValue, DEBUG_LEVEL_ALL};
 
 /// Helper pour découper un `u32` au format 10000 * version + 100 * revision + edition
 pub fn u32_to_version_revision_edition(version_revision_edition: u32) -> (u16, u16, u16) {
@@ -13,6 +13,24 @@ pub fn u32_to_version_revision_edition(version_revision_edition: u32) -> (u16, u
     (version as u16, revision as u16, edition as u16)
 }
 
+/// Helper pour découper un `u32` au format `0x00VVRREE` (version, revision, edition par octet)
+pub fn u32_bytes_to_version_revision_edition(version_revision_edition: u32) -> (u16, u16, u16) {
+    let [_, version, revision, edition] = version_revision_edition.to_be_bytes();
+    (u16::from(version), u16::from(revision), u16::from(edition))
+}
+
+/// Helper pour découper une version `u32` reçue de l'AFSEC+ selon le profil firmware émulé
+pub fn split_version(
+    afsec_service: &DatabaseAfsecComm,
+    version_revision_edition: u32,
+) -> (u16, u16, u16) {
+    if afsec_service.firmware_profile.byte_coded_versions() {
+        u32_bytes_to_version_revision_edition(version_revision_edition)
+    } else {
+        u32_to_version_revision_edition(version_revision_edition)
+    }
+}
+
 /// Helper pour convertir une `zone` + `tag_str5` en `IdTag`
 pub fn zone_vec_u8_tag_to_id_tag(zone: u8, vec_u8_tag: &[u8]) -> IdTag {
     // Converti le vec_u8_tag en un Vec<u8> d'au moins 5 éléments
@@ -40,39 +58,66 @@ pub fn tag_num_indices_to_vec_u8(
 }
 
 /// Helper pour mettre à jour la `Database`
-pub fn update_database(afsec_service: &mut DatabaseAfsecComm, id_tag: IdTag, t_value: TValue) {
+/// # Errors
+/// [`DbError`] si la valeur ne peut pas être écrite
+pub fn update_database(
+    afsec_service: &mut DatabaseAfsecComm,
+    id_tag: IdTag,
+    t_value: TValue,
+) -> Result<(), DbError> {
+    update_database_batch(afsec_service, &[(id_tag, t_value)])
+}
+
+/// Helper pour mettre à jour la `Database` avec plusieurs valeurs d'un même message
+///
+/// Les valeurs sont écrites sous un seul verrouillage de la `Database` : un client MODBUS ne
+/// lit jamais un enregistrement partiellement appliqué et les notifications des modifications
+/// sont toutes disponibles ensemble pour les autres utilisateurs
+/// # Errors
+/// [`DbError`] de la première valeur qui ne peut pas être écrite (les autres valeurs sont écrites)
+pub fn update_database_batch(
+    afsec_service: &mut DatabaseAfsecComm,
+    updates: &[(IdTag, TValue)],
+) -> Result<(), DbError> {
+    if updates.is_empty() {
+        return Ok(());
+    }
     if afsec_service.debug_level >= DEBUG_LEVEL_ALL {
-        println!("AFSEC Comm: Database update {id_tag} = {t_value}");
+        for (id_tag, t_value) in updates {
+            println!("AFSEC Comm: Database update {id_tag} = {t_value}");
+        }
     }
 
     // Verrouiller la database partagée
-    let mut db: std::sync::MutexGuard<'_, crate::database::Database> =
-        afsec_service.thread_db.lock().unwrap();
+    let id_user = afsec_service.id_user;
+    let mut db = afsec_service.lock_database();
 
     /* Mise à jour database */
-    match t_value {
-        TValue::Bool(value) => db.set_bool_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::U8(value) => db.set_u8_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::I8(value) => db.set_i8_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::U16(value) => db.set_u16_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::I16(value) => db.set_i16_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::U32(value) => db.set_u32_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::I32(value) => db.set_i32_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::U64(value) => db.set_u64_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::I64(value) => db.set_i64_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::F32(value) => db.set_f32_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::F64(value) => db.set_f64_to_id_tag(afsec_service.id_user, id_tag, value),
-        TValue::VecU8(len, value) => {
-            let mut vec_u8 = value.clone();
-            while vec_u8.len() < len {
-                vec_u8.push(0);
+    let mut result = Ok(());
+    for (id_tag, t_value) in updates {
+        let id_tag = *id_tag;
+        let update_result = match t_value {
+            TValue::Bool(value) => db.set_bool_to_id_tag(id_user, id_tag, *value),
+            TValue::U8(value) => db.set_u8_to_id_tag(id_user, id_tag, *value),
+            TValue::I8(value) => db.set_i8_to_id_tag(id_user, id_tag, *value),
+            TValue::U16(value) => db.set_u16_to_id_tag(id_user, id_tag, *value),
+            TValue::I16(value) => db.set_i16_to_id_tag(id_user, id_tag, *value),
+            TValue::U32(value) => db.set_u32_to_id_tag(id_user, id_tag, *value),
+            TValue::I32(value) => db.set_i32_to_id_tag(id_user, id_tag, *value),
+            TValue::U64(value) => db.set_u64_to_id_tag(id_user, id_tag, *value),
+            TValue::I64(value) => db.set_i64_to_id_tag(id_user, id_tag, *value),
+            TValue::F32(value) => db.set_f32_to_id_tag(id_user, id_tag, *value),
+            TValue::F64(value) => db.set_f64_to_id_tag(id_user, id_tag, *value),
+            TValue::VecU8(..) => {
+                let vec_u8 = t_value.to_vec_u8();
+                db.set_vec_u8_to_id_tag(id_user, id_tag, &vec_u8)
             }
-            if vec_u8.len() > len {
-                vec_u8 = vec_u8[0..len].to_vec();
-            }
-            db.set_vec_u8_to_id_tag(afsec_service.id_user, id_tag, &vec_u8);
+        };
+        if result.is_ok() {
+            result = update_result;
         }
     }
+    result
 }
 
 /// Helper pour l'ajout d'une donnée d'un enregistrement d'une table
@@ -107,6 +152,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_u32_bytes_to_version_revision_edition() {
+        assert_eq!(
+            u32_bytes_to_version_revision_edition(0x0001_0203),
+            (1, 2, 3)
+        );
+    }
+
     #[test]
     fn test_zone_vec_u8_tag_to_id_tag() {
         let zone = 1_u8;
@@ -1,21 +1,109 @@
 //! Process en communication avec l'AFSEC+ via un port série
+//!
+//! Un second port série optionnel (`DatabaseAfsecComm::with_standby_port`) simule le câblage
+//! redondant de l'armoire AFSEC+ : le simulateur écoute et répond sur les 2 ports et le port
+//! 'actif' est celui qui a reçu la dernière trame valide (voir le tag `ID_TAG_SIM_AFSEC_ACTIVE_PORT`).
+//!
+//! Les statistiques de chaque lien (port) sont publiées chaque seconde (voir le module
+//! `link_stats`).
+//!
+//! Les trames transmises sans réponse de l'AFSEC+ sont retransmises un nombre limité de fois
+//! avant de considérer le lien coupé (voir le module `link_watchdog` et le tag
+//! `ID_TAG_SIM_AFSEC_LINK`).
+//!
+//! Le temps de réponse de chaque requête de l'AFSEC+ (de la réception du dernier octet de la
+//! requête à l'écriture de la réponse) est enregistré dans l'histogramme des statistiques de
+//! profilage (voir `profiling::record_round_trip`).
+//!
+//! À l'arrêt du simulateur (voir `Database::request_shutdown`), le contexte des conversations
+//! en attente peut être sauvegardé dans un fichier et restauré au démarrage suivant (voir
+//! `DatabaseAfsecComm::with_context_file` et `DatabaseAfsecComm::with_context_state`).
+//!
+//! Les compteurs des conversations peuvent être conservés d'un démarrage à l'autre dans un
+//! fichier sauvegardé périodiquement (voir `DatabaseAfsecComm::with_stats_store`).
+//!
+//! Le module `test_vectors` génère les vecteurs de test du codage TLV (sous-commande
+//! `gen-vectors`).
 
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::time::Instant;
 
-use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use crate::capture::{self, CaptureKind};
+use crate::database::{
+    Database, IdUser, FRONT_LED_COM, GROUP_AFSEC, ID_ANONYMOUS_USER, ID_TAG_SIM_AFSEC_ACTIVE_PORT,
+    ID_TAG_SIM_AFSEC_LINK, ID_TAG_SIM_AFSEC_STATE, ID_TAG_SIM_JUNK_FRAMES, ID_TAG_SIM_POWER_CYCLE,
+    ID_TAG_SIM_TIME_SYNC, SIM_NB_LINKS,
+};
+use crate::profiling::{self, lock_database, ProfiledGuard, Subsystem};
+#[cfg(feature = "scripting")]
+use crate::scripting::ScriptHost;
+use crate::sim_clock;
+use crate::sim_rng::SimRng;
+use crate::test_summary;
 
-use crate::database::{Database, IdUser, ID_ANONYMOUS_USER};
+mod frame_injector;
+pub use frame_injector::{format_hex_frame, parse_hex_frame, FrameInjector};
 
-mod tlv_frame;
+mod frame_reader;
+pub use frame_reader::{FrameReader, FRAME_TIMEOUT};
+
+pub mod firmware_profile;
+pub use firmware_profile::{AliveAnswer, FirmwareProfile, MalformedAnswer};
+
+mod link_stats;
+pub use link_stats::LinkStats;
+
+mod link_watchdog;
+pub use link_watchdog::{
+    LinkAction, LinkState, LinkWatchdog, DEFAULT_PROBE_PERIOD, DEFAULT_RETRY_TIMEOUT,
+};
+
+mod junk_guard;
+pub use junk_guard::{JunkGuard, DEFAULT_MAX_JUNK_PER_SEC};
+
+mod mode_policy;
+pub use mode_policy::{ModePolicy, ModeRefusal};
+
+mod paced_input;
+pub use paced_input::PacedInput;
+
+mod response_delay;
+pub use response_delay::ResponseDelay;
+
+pub mod test_vectors;
+pub use test_vectors::{write_test_vectors, DEFAULT_TEST_VECTORS_FILENAME};
+
+mod transcript;
+pub use transcript::{format_frame, format_junk, Direction, SessionTranscript};
+
+mod transport;
+pub use transport::AfsecTransport;
+
+pub mod tlv_frame;
 use tlv_frame::{DataFrame, FrameState, RawFrame};
 
 mod middleware;
-pub use middleware::Middlewares;
+pub use middleware::{
+    ContextState, Middlewares, ProtocolStats, StatsStore, CONTEXT_MAGIC, CONTEXT_VERSION,
+    DEFAULT_CONVERSATION_TIMEOUT, DEFAULT_PROTOCOL_STATS_PERIOD_SECS,
+};
 
 /// Temporisation entre chaque surveillance pour les `notification_changes`
 const DURATION_NOTIFICATION_CHANGES_SECS: f32 = 1.0;
 
+/// État de la communication avec l'AFSEC+ (`ID_TAG_SIM_AFSEC_STATE`): Fonctionnement normal
+pub const AFSEC_STATE_RUNNING: u16 = 0;
+
+/// État de la communication avec l'AFSEC+ (`ID_TAG_SIM_AFSEC_STATE`): Coupure d'alimentation
+/// simulée en cours (le simulateur ne répond plus)
+pub const AFSEC_STATE_POWER_OFF: u16 = 1;
+
+/// État de la communication avec l'AFSEC+ (`ID_TAG_SIM_AFSEC_STATE`): Initialisation en cours
+/// après une coupure d'alimentation simulée (seul `AF_INIT` est accepté)
+pub const AFSEC_STATE_INITIALIZING: u16 = 2;
+
 /// Niveau debug Some
 pub const DEBUG_LEVEL_SOME: u8 = 1;
 
@@ -33,8 +121,58 @@ pub struct DatabaseAfsecComm {
     /// Nom du port série choisi par l'utilisateur pour communiquer avec l'AFSEC+
     port_name: String,
 
+    /// Nom du port série de secours (câblage redondant) pour communiquer avec l'AFSEC+
+    option_standby_port_name: Option<String>,
+
     /// Niveau de debug pour les affichages (0: None, 1: Some, 2: All)
     debug_level: u8,
+
+    /// Générateur pseudo-aléatoire pour les comportements aléatoires de la communication
+    rng: SimRng,
+
+    /// Profil de comportement du firmware ICOM émulé
+    firmware_profile: FirmwareProfile,
+
+    /// Réponse à `AF_ALIVE` imposée (sinon celle du profil firmware)
+    option_alive_answer: Option<AliveAnswer>,
+
+    /// Réponse aux trames inexploitables imposée (sinon celle du profil firmware)
+    option_malformed_answer: Option<MalformedAnswer>,
+
+    /// Protection contre un flot de trames inexploitables reçues de l'AFSEC+
+    junk_guard: JunkGuard,
+
+    /// Refus de conversations selon le mode de fonctionnement de l'AFSEC+
+    mode_policy: ModePolicy,
+
+    /// Délai d'inactivité de l'AFSEC+ au delà duquel une conversation en cours est abandonnée
+    conversation_timeout: Duration,
+
+    /// Délai de réponse aux trames de l'AFSEC+
+    response_delay: ResponseDelay,
+
+    /// Surveillance du lien avec l'AFSEC+ (retransmissions et état 'lien coupé')
+    link_watchdog: LinkWatchdog,
+
+    /// Répertoire des fichiers de transcription des sessions (None si pas de transcription)
+    option_transcript_dir: Option<PathBuf>,
+
+    /// Fichier de sauvegarde du contexte des conversations à l'arrêt du simulateur
+    /// (None pour ne pas sauvegarder)
+    option_context_file: Option<String>,
+
+    /// Contexte des conversations à restaurer au démarrage de la communication
+    option_context_state: Option<ContextState>,
+
+    /// Fichier des compteurs des conversations persistants (None si pas de persistance)
+    option_stats_store: Option<StatsStore>,
+
+    /// Transcription de la session en cours
+    option_transcript: Option<SessionTranscript>,
+
+    /// Script Rhai avec des 'hooks' `on_frame` qui répondent à la place des `middlewares`
+    #[cfg(feature = "scripting")]
+    option_script: Option<Arc<ScriptHost>>,
 }
 
 impl DatabaseAfsecComm {
@@ -44,9 +182,238 @@ impl DatabaseAfsecComm {
             thread_db,
             id_user: ID_ANONYMOUS_USER, // Overwrite si le port est OK
             port_name,
+            option_standby_port_name: None,
             debug_level,
+            rng: SimRng::new(0),
+            firmware_profile: FirmwareProfile::default(),
+            option_alive_answer: None,
+            option_malformed_answer: None,
+            junk_guard: JunkGuard::default(),
+            mode_policy: ModePolicy::default(),
+            conversation_timeout: Duration::from_millis(DEFAULT_CONVERSATION_TIMEOUT),
+            response_delay: ResponseDelay::default(),
+            link_watchdog: LinkWatchdog::default(),
+            option_transcript_dir: None,
+            option_context_file: None,
+            option_context_state: None,
+            option_stats_store: None,
+            option_transcript: None,
+            #[cfg(feature = "scripting")]
+            option_script: None,
         }
     }
+
+    /// Spécifie l'[`IdUser`] pour les opérations (sinon attribué au démarrage de la communication
+    /// sur le port série)
+    #[must_use]
+    pub fn with_id_user(mut self, id_user: IdUser) -> Self {
+        self.id_user = id_user;
+        self
+    }
+
+    /// Spécifie le générateur pseudo-aléatoire (dérivé de la graine globale `--seed`)
+    #[must_use]
+    pub fn with_rng(mut self, rng: SimRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Spécifie le profil de comportement du firmware ICOM émulé
+    #[must_use]
+    pub fn with_firmware_profile(mut self, firmware_profile: FirmwareProfile) -> Self {
+        self.firmware_profile = firmware_profile;
+        self
+    }
+
+    /// Impose la réponse à `AF_ALIVE` quand personne n'a rien à dire (None pour la réponse du
+    /// profil firmware)
+    #[must_use]
+    pub fn with_alive_answer(mut self, option_alive_answer: Option<AliveAnswer>) -> Self {
+        self.option_alive_answer = option_alive_answer;
+        self
+    }
+
+    /// Réponse à `AF_ALIVE` quand personne n'a rien à dire
+    fn alive_answer(&self) -> AliveAnswer {
+        self.option_alive_answer
+            .unwrap_or_else(|| self.firmware_profile.alive_answer())
+    }
+
+    /// Impose la réponse à une trame dont le contenu ne peut pas être décodé (None pour la
+    /// réponse du profil firmware)
+    #[must_use]
+    pub fn with_malformed_answer(
+        mut self,
+        option_malformed_answer: Option<MalformedAnswer>,
+    ) -> Self {
+        self.option_malformed_answer = option_malformed_answer;
+        self
+    }
+
+    /// Réponse à une trame dont le contenu ne peut pas être décodé
+    fn malformed_answer(&self) -> MalformedAnswer {
+        self.option_malformed_answer
+            .unwrap_or_else(|| self.firmware_profile.malformed_answer())
+    }
+
+    /// Spécifie un port série de secours (câblage redondant actif/secours avec l'AFSEC+)
+    #[must_use]
+    pub fn with_standby_port(mut self, option_standby_port_name: Option<String>) -> Self {
+        self.option_standby_port_name = option_standby_port_name;
+        self
+    }
+
+    /// Spécifie la protection contre un flot de trames inexploitables reçues de l'AFSEC+
+    #[must_use]
+    pub fn with_junk_guard(mut self, junk_guard: JunkGuard) -> Self {
+        self.junk_guard = junk_guard;
+        self
+    }
+
+    /// Spécifie les refus de conversations selon le mode de fonctionnement de l'AFSEC+
+    #[must_use]
+    pub fn with_mode_policy(mut self, mode_policy: ModePolicy) -> Self {
+        self.mode_policy = mode_policy;
+        self
+    }
+
+    /// Spécifie le délai d'inactivité de l'AFSEC+ au delà duquel une conversation en cours est
+    /// abandonnée (`Duration::ZERO` pour ne jamais abandonner)
+    #[must_use]
+    pub fn with_conversation_timeout(mut self, conversation_timeout: Duration) -> Self {
+        self.conversation_timeout = conversation_timeout;
+        self
+    }
+
+    /// Spécifie le délai de réponse aux trames de l'AFSEC+ (fixe ou tiré avec le générateur
+    /// pseudo-aléatoire, voir `with_rng`)
+    #[must_use]
+    pub fn with_response_delay(mut self, response_delay: ResponseDelay) -> Self {
+        self.response_delay = response_delay;
+        self
+    }
+
+    /// Spécifie la surveillance du lien avec l'AFSEC+ (nombre max. de retransmissions d'une
+    /// trame sans réponse avant de considérer le lien coupé)
+    #[must_use]
+    pub fn with_link_watchdog(mut self, link_watchdog: LinkWatchdog) -> Self {
+        self.link_watchdog = link_watchdog;
+        self
+    }
+
+    /// Spécifie le fichier de sauvegarde du contexte des conversations à l'arrêt du simulateur
+    /// (None pour ne pas sauvegarder)
+    #[must_use]
+    pub fn with_context_file(mut self, option_context_file: Option<String>) -> Self {
+        self.option_context_file = option_context_file;
+        self
+    }
+
+    /// Spécifie le contexte des conversations à restaurer au démarrage de la communication
+    #[must_use]
+    pub fn with_context_state(mut self, option_context_state: Option<ContextState>) -> Self {
+        self.option_context_state = option_context_state;
+        self
+    }
+
+    /// Spécifie le fichier des compteurs des conversations repris au démarrage de la
+    /// communication et sauvegardés périodiquement (None pour ne pas les conserver)
+    /// Le même [`StatsStore`] (cloné) est donné à chaque redémarrage de la communication pour
+    /// reprendre les compteurs à jour
+    #[must_use]
+    pub fn with_stats_store(mut self, option_stats_store: Option<StatsStore>) -> Self {
+        self.option_stats_store = option_stats_store;
+        self
+    }
+
+    /// Spécifie le répertoire des fichiers de transcription des sessions avec l'AFSEC+
+    /// (None pour ne pas transcrire)
+    #[must_use]
+    pub fn with_transcript_dir(mut self, option_transcript_dir: Option<PathBuf>) -> Self {
+        self.option_transcript_dir = option_transcript_dir;
+        self
+    }
+
+    /// Spécifie le script Rhai dont les 'hooks' `on_frame` répondent aux requêtes de l'AFSEC+
+    /// (None pour laisser répondre les `middlewares`)
+    #[cfg(feature = "scripting")]
+    #[must_use]
+    pub fn with_script(mut self, option_script: Option<Arc<ScriptHost>>) -> Self {
+        self.option_script = option_script;
+        self
+    }
+
+    /// Réponse du script à une requête de l'AFSEC+ (None sans script ou si aucun 'hook'
+    /// `on_frame` ne répond)
+    #[cfg(feature = "scripting")]
+    fn script_response(&self, request_data_frame: &DataFrame) -> Option<RawFrame> {
+        self.option_script
+            .as_ref()
+            .and_then(|script| script.on_frame(request_data_frame))
+    }
+
+    /// Sans la feature `scripting`, les `middlewares` répondent à toutes les requêtes
+    #[cfg(not(feature = "scripting"))]
+    #[allow(clippy::unused_self)]
+    fn script_response(&self, _request_data_frame: &DataFrame) -> Option<RawFrame> {
+        None
+    }
+
+    /// Débute la transcription d'une nouvelle session (si un répertoire est spécifié)
+    fn start_transcript(&mut self) {
+        let Some(dir) = &self.option_transcript_dir else {
+            return;
+        };
+        self.option_transcript = match SessionTranscript::create(dir, &self.port_name) {
+            Ok(transcript) => {
+                println!(
+                    "AFSEC Comm: Transcript '{}'...",
+                    transcript.path().display()
+                );
+                Some(transcript)
+            }
+            Err(e) => {
+                println!(
+                    "AFSEC Comm: Erreur création transcription dans '{}': {e}",
+                    dir.display()
+                );
+                None
+            }
+        };
+    }
+
+    /// Ajoute une ligne à la transcription de la session en cours
+    /// (la transcription est arrêtée en cas d'erreur d'écriture)
+    fn transcribe(&mut self, line: &str) {
+        self.transcribe_with(|transcript| transcript.write_line(line));
+    }
+
+    /// Ajoute les derniers marqueurs (voir le module `timeline`) à la transcription de la
+    /// session en cours
+    fn transcribe_marks(&mut self) {
+        self.transcribe_with(SessionTranscript::write_marks);
+    }
+
+    /// Écriture dans la transcription de la session en cours (arrêtée en cas d'erreur)
+    fn transcribe_with<F: FnOnce(&mut SessionTranscript) -> std::io::Result<()>>(
+        &mut self,
+        write: F,
+    ) {
+        if let Some(transcript) = &mut self.option_transcript {
+            if let Err(e) = write(transcript) {
+                println!(
+                    "AFSEC Comm: Erreur écriture transcription '{}': {e}",
+                    transcript.path().display()
+                );
+                self.option_transcript = None;
+            }
+        }
+    }
+
+    /// Verrouille la database partagée (verrou instrumenté, voir le module `profiling`)
+    pub fn lock_database(&self) -> ProfiledGuard<'_> {
+        lock_database(&self.thread_db, Subsystem::Afsec)
+    }
 }
 
 /// Routine d'un thread en communication avec l'AFSEC+ via un port série.
@@ -57,35 +424,117 @@ pub async fn database_afsec_process(afsec_service: &mut DatabaseAfsecComm) {
     }
 
     println!("AFSEC Comm: Starting on '{}'...", afsec_service.port_name);
+    let mut ports = vec![open_port(&afsec_service.port_name)];
+    if let Some(standby_port_name) = &afsec_service.option_standby_port_name {
+        println!("AFSEC Comm: Standby port '{standby_port_name}'...");
+        ports.push(open_port(standby_port_name));
+    }
 
-    let mut port = match tokio_serial::new(&afsec_service.port_name, 115_200).open_native_async() {
-        Ok(port) => port,
-        Err(e) => {
-            eprintln!(
-                "!!! Erreur fatale ouverture du port '{}': {}",
-                afsec_service.port_name, e
-            );
-            std::process::exit(1);
-        }
-    };
+    // Transcription de cette session
+    afsec_service.start_transcript();
+
+    // État de la communication sur chaque port
+    let mut port_states: Vec<PortState> = ports.iter().map(|_| PortState::default()).collect();
 
     {
         // Verrouiller la database partagée
-        let mut db = afsec_service.thread_db.lock().unwrap();
+        let mut db = lock_database(&afsec_service.thread_db, Subsystem::Afsec);
 
         // Obtient un id_user pour les opérations
-        afsec_service.id_user = db.get_id_user("AFSEC Comm", true);
+        afsec_service.id_user = db.get_id_user_in_group("AFSEC Comm", true, GROUP_AFSEC);
     }
 
+    // Port actif (indice dans `ports`): Le port principal au démarrage
+    let mut active_port = 0;
+    set_active_port(afsec_service, active_port);
+    set_afsec_state(afsec_service, AFSEC_STATE_RUNNING);
+    set_link_state(afsec_service);
+
     // Création du gestionnaire des `middlewares` pour les conversations avec l'AFSEC+
-    let mut middlewares = Middlewares::new(afsec_service.debug_level);
+    let mut middlewares = Middlewares::new(afsec_service.debug_level)
+        .with_conversation_timeout(afsec_service.conversation_timeout);
+
+    // Contexte des conversations sauvegardé lors de l'arrêt précédent
+    if let Some(context_state) = afsec_service.option_context_state.take() {
+        println!("AFSEC Comm: Context restored ({context_state})");
+        middlewares.restore_context_state(&context_state);
+    }
+
+    // Compteurs des conversations repris du démarrage précédent (ou de la tâche de
+    // communication précédente en cas de redémarrage par la supervision)
+    if let Some(stats_store) = &afsec_service.option_stats_store {
+        let stats = stats_store.stats();
+        println!("AFSEC Comm: Protocol stats resumed ({stats})");
+        middlewares.set_stats(stats);
+    }
 
     // Timer pour surveiller les notifications
     let mut date_last_notification_changes = Instant::now();
 
+    // Date de fin de la coupure d'alimentation simulée de l'AFSEC+ en cours
+    let mut option_power_on_date: Option<Instant> = None;
+
+    // Date de la dernière synchronisation de l'horloge simulée
+    let mut last_time_sync = 0;
+
     loop {
         // Gestion communication AFSEC+ sur le port
-        let tempo = read_and_write(&mut port, afsec_service, &mut middlewares);
+        let tempo = match option_power_on_date {
+            Some(power_on_date) => {
+                if Instant::now() >= power_on_date {
+                    // Fin de la coupure: On attend la réinitialisation par `AF_INIT`
+                    if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                        println!("AFSEC Comm: Power on (AF_INIT attendu)...");
+                    }
+                    option_power_on_date = None;
+                    middlewares.power_cycle();
+                    set_afsec_state(afsec_service, AFSEC_STATE_INITIALIZING);
+                }
+                discard_input(&mut ports, &mut port_states)
+            }
+            None if afsec_service
+                .junk_guard
+                .is_silent(std::time::Instant::now()) =>
+            {
+                // Silencieux suite à un flot de trames inexploitables
+                discard_input(&mut ports, &mut port_states)
+            }
+            None => {
+                let was_initializing = middlewares.is_initializing();
+                for (index, (port, port_state)) in
+                    ports.iter_mut().zip(port_states.iter_mut()).enumerate()
+                {
+                    let frame_state =
+                        read_and_write(port, port_state, index, afsec_service, &mut middlewares);
+                    if frame_state == FrameState::Ok && index != active_port {
+                        // Le trafic valide bascule sur ce port
+                        if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                            println!("AFSEC Comm: Port #{} now active...", index + 1);
+                        }
+                        active_port = index;
+                        set_active_port(afsec_service, active_port);
+                    }
+                }
+                if was_initializing && !middlewares.is_initializing() {
+                    set_afsec_state(afsec_service, AFSEC_STATE_RUNNING);
+                }
+                // Conversation en cours sans suite de l'AFSEC+
+                middlewares.check_conversation_timeout(std::time::Instant::now());
+                // Trame transmise sans réponse de l'AFSEC+
+                check_link(afsec_service, &mut ports, &mut port_states, active_port);
+                1
+            }
+        };
+
+        // Récapitulatif d'un flot de trames inexploitables
+        if let Some(nb_junk) = afsec_service
+            .junk_guard
+            .take_flood_report(std::time::Instant::now())
+        {
+            if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                println!("AFSEC Comm: Junk flood ({nb_junk} junk frames not traced)...");
+            }
+        }
 
         // Laisse la main...
         tokio::time::sleep(tokio::time::Duration::from_millis(tempo)).await;
@@ -96,6 +545,66 @@ pub async fn database_afsec_process(afsec_service: &mut DatabaseAfsecComm) {
             date_last_notification_changes = current_date;
             // Gestion des notification_changes pour les `middlewares`
             check_notification_changes(afsec_service, &mut middlewares);
+
+            // Statistiques des liens avec l'AFSEC+
+            publish_link_stats(afsec_service, &port_states);
+
+            // Marqueurs de la console dans la transcription de la session
+            afsec_service.transcribe_marks();
+
+            // Sauvegarde périodique des compteurs des conversations
+            if let Some(stats_store) = &mut afsec_service.option_stats_store {
+                if let Err(msg) =
+                    stats_store.flush_if_due(middlewares.stats(), current_date.into_std())
+                {
+                    eprintln!("!!! {msg}");
+                }
+            }
+
+            // Menus à pousser vers l'afficheur de l'AFSEC+ (commande `menu` de la console)
+            let menu_pushes = afsec_service.lock_database().take_menu_pushes();
+            middlewares.queue_menu_pushes(menu_pushes);
+
+            // Enregistrements des journaux produits par le simulateur
+            let journal_records = afsec_service.lock_database().take_journal_records();
+            middlewares.append_journal_records(journal_records);
+
+            // Arrêt du simulateur demandé
+            if afsec_service.lock_database().is_shutdown_requested() {
+                shutdown(afsec_service, &middlewares);
+            }
+
+            // Demande de coupure d'alimentation simulée de l'AFSEC+
+            if let Some(duration) = take_power_cycle_request(afsec_service) {
+                if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                    println!("AFSEC Comm: Power off for {} secs...", duration.as_secs());
+                }
+                option_power_on_date = Some(current_date + duration);
+                set_afsec_state(afsec_service, AFSEC_STATE_POWER_OFF);
+            }
+
+            // Synchronisation de l'horloge simulée
+            if let Some(time_sync) = take_time_sync(afsec_service, last_time_sync) {
+                if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                    println!("AFSEC Comm: Time sync ({time_sync} secs since 01/01/1970)...");
+                }
+                last_time_sync = time_sync;
+            }
+
+            // Demande de remise aux valeurs par défaut des tags (démarrage à froid)
+            let option_reset = afsec_service
+                .lock_database()
+                .take_reset_defaults_request(afsec_service.id_user);
+            if let Some((option_zone, nb_tags)) = option_reset {
+                if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                    match option_zone {
+                        Some(zone) => println!(
+                            "AFSEC Comm: Reset to defaults of zone {zone} ({nb_tags} tags)..."
+                        ),
+                        None => println!("AFSEC Comm: Reset to defaults ({nb_tags} tags)..."),
+                    }
+                }
+            }
         }
 
         // Laisse la main encore un peu...
@@ -103,15 +612,74 @@ pub async fn database_afsec_process(afsec_service: &mut DatabaseAfsecComm) {
     }
 }
 
-/// Gestion communication avec l'AFSEC+ sur le port
-/// Retourne une temporisation en millisecondes avant de tenter à nouveau un cycle
-/// de gestion de la communication avec l'AFSEC+
+/// Ouverture d'un port série pour communiquer avec l'AFSEC+ (erreur fatale si impossible)
+fn open_port(port_name: &str) -> AfsecTransport {
+    match AfsecTransport::open(port_name) {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("!!! Erreur fatale ouverture du port '{port_name}': {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// État de la communication avec l'AFSEC+ sur un port
+#[derive(Default)]
+struct PortState {
+    /// Trame en cours de construction (conservée d'une lecture à l'autre)
+    frame_reader: FrameReader,
+
+    /// Réponse retardée (échéance, date de réception de la requête, réponse, transmission à
+    /// l'initiative du simulateur) en attente de transmission (voir `ResponseDelay`)
+    option_pending_response: Option<(std::time::Instant, std::time::Instant, RawFrame, bool)>,
+
+    /// Statistiques du lien (publiées chaque seconde, voir `publish_link_stats`)
+    link_stats: LinkStats,
+
+    /// Dernière trame transmise à l'initiative du simulateur (retransmise si sans réponse, voir
+    /// `check_link`)
+    option_last_response: Option<RawFrame>,
+}
+
+/// Gestion communication avec l'AFSEC+ sur un port (indice 0 pour le port principal)
+/// La trame en cours de construction est conservée entre 2 appels et abandonnée si aucun octet
+/// n'est reçu pendant le délai de son `FrameReader`
+/// Une réponse retardée (option `--response-delay`) est transmise à son échéance et aucune
+/// nouvelle trame n'est lue sur le port en attendant
+/// Retourne l'état de la trame reçue sur ce port (`FrameState::Empty` si rien reçu)
 fn read_and_write(
-    port: &mut SerialStream,
+    port: &mut AfsecTransport,
+    port_state: &mut PortState,
+    index: usize,
     afsec_service: &mut DatabaseAfsecComm,
     middlewares: &mut Middlewares,
-) -> u64 {
-    let mut request_raw_frame = RawFrame::default();
+) -> FrameState {
+    // Réponse retardée en attente
+    if let Some((due_date, request_date, response_raw_frame, is_sim_initiated)) =
+        port_state.option_pending_response.take()
+    {
+        if std::time::Instant::now() < due_date {
+            port_state.option_pending_response =
+                Some((due_date, request_date, response_raw_frame, is_sim_initiated));
+            return FrameState::Empty;
+        }
+        port_state.link_stats.record_response(&response_raw_frame);
+        write_response(
+            port,
+            index,
+            afsec_service,
+            &response_raw_frame,
+            Some(request_date),
+        );
+        record_transmission(
+            afsec_service,
+            port_state,
+            response_raw_frame,
+            is_sim_initiated,
+        );
+    }
+
+    let frame_reader = &mut port_state.frame_reader;
     let mut buff = [0_u8; 256];
 
     loop {
@@ -126,13 +694,13 @@ fn read_and_write(
                 0
             }
         };
+        let now = std::time::Instant::now();
 
         if n > 0 {
-            request_raw_frame.extend(&buff[..n]);
-            match request_raw_frame.get_state() {
+            match frame_reader.push(&buff[..n], now) {
                 // Ne doit pas arriver...
                 FrameState::Empty => {
-                    break 1;
+                    break FrameState::Empty;
                 }
 
                 // Trame en cours mais pas encore complète, on continue à lire sur le port
@@ -140,41 +708,302 @@ fn read_and_write(
 
                 // Reçu un message inexploitable... On zappe
                 FrameState::Junk => {
-                    if afsec_service.debug_level >= DEBUG_LEVEL_ALL {
-                        println!("AFSEC Comm: Got junk frame '{request_raw_frame}'");
-                    }
-                    break 1;
+                    let request_raw_frame = frame_reader.take();
+                    let transition = request_raw_frame.junk_transition().unwrap_or_default();
+                    port_state.link_stats.record_junk();
+                    record_junk_frame(afsec_service, index, &request_raw_frame, &transition);
+                    break FrameState::Junk;
                 }
 
                 // Trame correcte reçue. On traite pour répondre...
                 FrameState::Ok => {
+                    let request_raw_frame = frame_reader.take();
+                    port_state.link_stats.record_request(&request_raw_frame);
+                    if afsec_service.link_watchdog.record_answer() {
+                        if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                            println!("AFSEC Comm: Link up...");
+                        }
+                        set_link_state(afsec_service);
+                    }
                     if afsec_service.debug_level >= DEBUG_LEVEL_ALL {
                         println!("AFSEC Comm: -> REQ {request_raw_frame}");
                     }
-                    let response_raw_frame =
-                        middlewares.handle_request_raw_frame(afsec_service, request_raw_frame);
-                    match port.try_write(&response_raw_frame.encode()) {
-                        Ok(_n) => {
-                            if afsec_service.debug_level >= DEBUG_LEVEL_ALL {
-                                println!("AFSEC Comm: <- REP {response_raw_frame}");
-                            }
-                        }
-                        Err(e) => {
-                            if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
-                                println!("AFSEC Comm: Got error while writing: {e}");
-                            }
-                        }
+                    let line = format_frame(Direction::Request, index, &request_raw_frame);
+                    capture::record(CaptureKind::SerialFrame, || line.clone());
+                    afsec_service.transcribe(&line);
+                    let response_raw_frame = middlewares
+                        .handle_request_raw_frame(afsec_service, request_raw_frame.clone());
+                    let is_sim_initiated =
+                        LinkWatchdog::is_sim_initiated(&request_raw_frame, &response_raw_frame);
+                    let delay = afsec_service.response_delay.sample(&mut afsec_service.rng);
+                    if delay.is_zero() {
+                        port_state.link_stats.record_response(&response_raw_frame);
+                        write_response(port, index, afsec_service, &response_raw_frame, Some(now));
+                        record_transmission(
+                            afsec_service,
+                            port_state,
+                            response_raw_frame,
+                            is_sim_initiated,
+                        );
+                    } else {
+                        port_state.option_pending_response =
+                            Some((now + delay, now, response_raw_frame, is_sim_initiated));
                     }
-                    break 1;
+                    break FrameState::Ok;
                 }
             }
+        } else if let Some(request_raw_frame) = frame_reader.check_timeout(now) {
+            // Trame incomplète sans suite de l'AFSEC+ : Abandonnée comme inexploitable
+            let transition = format!(
+                "Timeout (aucun octet depuis {} ms)",
+                frame_reader.timeout().as_millis()
+            );
+            port_state.link_stats.record_junk();
+            record_junk_frame(afsec_service, index, &request_raw_frame, &transition);
+            break FrameState::Junk;
         } else {
-            // Aucune donnée reçue
-            break 1;
+            // Aucune donnée reçue (la trame éventuellement en cours sera complétée plus tard)
+            break FrameState::Empty;
+        }
+    }
+}
+
+/// Transmission d'une réponse à l'AFSEC+ sur un port
+/// `option_request_date` est la date de réception du dernier octet de la requête (None pour une
+/// retransmission) pour l'histogramme des temps de réponse (voir `profiling::record_round_trip`)
+fn write_response(
+    port: &mut AfsecTransport,
+    index: usize,
+    afsec_service: &mut DatabaseAfsecComm,
+    response_raw_frame: &RawFrame,
+    option_request_date: Option<std::time::Instant>,
+) {
+    let line = format_frame(Direction::Response, index, response_raw_frame);
+    capture::record(CaptureKind::SerialFrame, || line.clone());
+    afsec_service.transcribe(&line);
+    let write_date = std::time::Instant::now();
+    match port.try_write(&response_raw_frame.encode()) {
+        Ok(_n) => {
+            if let Some(request_date) = option_request_date {
+                profiling::record_round_trip(write_date.saturating_duration_since(request_date));
+            }
+            if afsec_service.debug_level >= DEBUG_LEVEL_ALL {
+                println!("AFSEC Comm: <- REP {response_raw_frame}");
+            }
+        }
+        Err(e) => {
+            if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+                println!("AFSEC Comm: Got error while writing: {e}");
+            }
         }
     }
 }
 
+/// Surveillance d'une trame transmise à l'AFSEC+ à l'initiative du simulateur (les autres
+/// réponses ne sont pas retransmises, voir `LinkWatchdog::is_sim_initiated`)
+fn record_transmission(
+    afsec_service: &mut DatabaseAfsecComm,
+    port_state: &mut PortState,
+    response_raw_frame: RawFrame,
+    is_sim_initiated: bool,
+) {
+    if is_sim_initiated {
+        afsec_service
+            .link_watchdog
+            .record_transmission(std::time::Instant::now());
+        port_state.option_last_response = Some(response_raw_frame);
+    }
+}
+
+/// Surveillance du lien avec l'AFSEC+ : retransmission sur le port actif de la dernière trame
+/// sans réponse, passage à l'état 'lien coupé' puis tentatives périodiques de rétablissement
+fn check_link(
+    afsec_service: &mut DatabaseAfsecComm,
+    ports: &mut [AfsecTransport],
+    port_states: &mut [PortState],
+    active_port: usize,
+) {
+    let Some(action) = afsec_service.link_watchdog.check(std::time::Instant::now()) else {
+        return;
+    };
+    if afsec_service.debug_level >= DEBUG_LEVEL_SOME {
+        match action {
+            LinkAction::Retry => println!(
+                "AFSEC Comm: No answer, retry... ({})",
+                afsec_service.link_watchdog.state()
+            ),
+            LinkAction::Down => println!("AFSEC Comm: Link down (no answer)..."),
+            LinkAction::Probe => println!("AFSEC Comm: Link down, probing..."),
+        }
+    }
+    let (Some(port), Some(port_state)) =
+        (ports.get_mut(active_port), port_states.get_mut(active_port))
+    else {
+        return;
+    };
+    match action {
+        LinkAction::Retry => set_link_state(afsec_service),
+        LinkAction::Down => {
+            set_link_state(afsec_service);
+            return;
+        }
+        // Trame en cours de réception abandonnée
+        LinkAction::Probe => port_state.frame_reader = FrameReader::default(),
+    }
+
+    // Retransmission de la dernière trame
+    if let Some(response_raw_frame) = port_state.option_last_response.clone() {
+        port_state.link_stats.record_response(&response_raw_frame);
+        write_response(port, active_port, afsec_service, &response_raw_frame, None);
+    }
+}
+
+/// Publication de l'état du lien avec l'AFSEC+ dans le [`Tag`] `ID_TAG_SIM_AFSEC_LINK`
+fn set_link_state(afsec_service: &DatabaseAfsecComm) {
+    let state = afsec_service.link_watchdog.state().to_u16();
+    afsec_service.lock_database().set_sim_tag_u16(
+        afsec_service.id_user,
+        ID_TAG_SIM_AFSEC_LINK,
+        state,
+    );
+}
+
+/// Comptage, capture et trace d'une trame inexploitable reçue sur un port
+fn record_junk_frame(
+    afsec_service: &mut DatabaseAfsecComm,
+    index: usize,
+    raw_frame: &RawFrame,
+    transition: &str,
+) {
+    {
+        let mut db = afsec_service.lock_database();
+        db.increment_sim_tag(ID_TAG_SIM_JUNK_FRAMES);
+        db.record_junk_capture(
+            u16::try_from(index + 1).unwrap_or(u16::MAX),
+            &raw_frame.encode(),
+            transition,
+        );
+    }
+    let line = format_junk(index, raw_frame, transition);
+    capture::record(CaptureKind::SerialJunk, || line.clone());
+    afsec_service.transcribe(&line);
+    let is_traced = afsec_service
+        .junk_guard
+        .record_junk(std::time::Instant::now());
+    if is_traced && afsec_service.debug_level >= DEBUG_LEVEL_ALL {
+        println!("AFSEC Comm: Got junk frame '{raw_frame}'");
+    }
+}
+
+/// Publication des statistiques de chaque lien (port) avec l'AFSEC+
+fn publish_link_stats(afsec_service: &DatabaseAfsecComm, port_states: &[PortState]) {
+    let mut db = afsec_service.lock_database();
+    for (link, port_state) in (1..=SIM_NB_LINKS).zip(port_states) {
+        db.publish_link_stats(link, &port_state.link_stats.values());
+    }
+}
+
+/// Lecture et abandon des données reçues de l'AFSEC+ sur tous les ports pendant une coupure
+/// d'alimentation simulée (ou un silence)
+/// Retourne une temporisation en millisecondes avant de tenter à nouveau
+fn discard_input(ports: &mut [AfsecTransport], port_states: &mut [PortState]) -> u64 {
+    let mut buff = [0_u8; 256];
+    for port in ports {
+        while matches!(port.try_read(&mut buff), Ok(n) if n > 0) {}
+    }
+    for port_state in port_states {
+        port_state.frame_reader.take();
+        port_state.option_pending_response = None;
+    }
+    1
+}
+
+/// Prise en compte (et remise à 0) d'une demande de coupure d'alimentation simulée de l'AFSEC+
+/// dans le [`Tag`] `ID_TAG_SIM_POWER_CYCLE` de la [`Database`]
+/// Retourne la durée de la coupure demandée (None si pas de demande)
+///
+/// [`Tag`]: crate::database::Tag
+fn take_power_cycle_request(afsec_service: &DatabaseAfsecComm) -> Option<Duration> {
+    let mut db = afsec_service.lock_database();
+    let secs = db.get_u16_from_id_tag(afsec_service.id_user, ID_TAG_SIM_POWER_CYCLE);
+    if secs == 0 {
+        return None;
+    }
+    db.set_sim_tag_u16(afsec_service.id_user, ID_TAG_SIM_POWER_CYCLE, 0);
+    test_summary::record_fault("powercycle");
+    Some(Duration::from_secs(u64::from(secs)))
+}
+
+/// Sauvegarde du contexte et des compteurs des conversations (si des fichiers sont spécifiés) et
+/// arrêt du simulateur
+fn shutdown(afsec_service: &DatabaseAfsecComm, middlewares: &Middlewares) -> ! {
+    let mut exit_code = 0;
+    if let Some(stats_store) = &afsec_service.option_stats_store {
+        match middlewares.stats().to_file(stats_store.filename()) {
+            Ok(()) => println!(
+                "AFSEC Comm: Protocol stats saved to '{}' ({})",
+                stats_store.filename(),
+                middlewares.stats()
+            ),
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                exit_code = 1;
+            }
+        }
+    }
+    if let Some(context_file) = &afsec_service.option_context_file {
+        let context_state = middlewares.save_context_state();
+        match context_state.to_file(context_file) {
+            Ok(()) => println!("AFSEC Comm: Context saved to '{context_file}' ({context_state})"),
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                exit_code = 1;
+            }
+        }
+    }
+    std::process::exit(exit_code);
+}
+
+/// Mise à jour du port actif (indice 0 pour le port principal) dans la [`Database`]
+fn set_active_port(afsec_service: &DatabaseAfsecComm, active_port: usize) {
+    let mut db = afsec_service.lock_database();
+    let active_port = u16::try_from(active_port + 1).unwrap_or(u16::MAX);
+    db.set_sim_tag_u16(
+        afsec_service.id_user,
+        ID_TAG_SIM_AFSEC_ACTIVE_PORT,
+        active_port,
+    );
+}
+
+/// Synchronisation de l'horloge simulée si une nouvelle date (différente de `last_time_sync`)
+/// est écrite dans le [`Tag`] `ID_TAG_SIM_TIME_SYNC` de la [`Database`]
+/// Retourne la date de la synchronisation (None si pas de nouvelle date)
+///
+/// [`Tag`]: crate::database::Tag
+fn take_time_sync(afsec_service: &DatabaseAfsecComm, last_time_sync: u32) -> Option<u32> {
+    let time_sync = afsec_service
+        .lock_database()
+        .get_u32_from_id_tag(afsec_service.id_user, ID_TAG_SIM_TIME_SYNC);
+    if time_sync == 0 || time_sync == last_time_sync {
+        return None;
+    }
+    sim_clock::synchronize(u64::from(time_sync));
+    Some(time_sync)
+}
+
+/// Mise à jour de l'état de la communication avec l'AFSEC+ (et de l'état des zones) dans la
+/// [`Database`]
+fn set_afsec_state(afsec_service: &DatabaseAfsecComm, state: u16) {
+    let mut db = afsec_service.lock_database();
+    db.set_sim_tag_u16(afsec_service.id_user, ID_TAG_SIM_AFSEC_STATE, state);
+    db.set_zone_states(afsec_service.id_user, state);
+    db.set_front_panel_led(
+        afsec_service.id_user,
+        FRONT_LED_COM,
+        state == AFSEC_STATE_RUNNING,
+    );
+}
+
 /// Surveillances des `notification_changes` dans la `database` pour informer les `middlewares`
 /// (public car utilisé pour les tests...)
 pub fn check_notification_changes(
@@ -186,7 +1015,7 @@ pub fn check_notification_changes(
 
     loop {
         // Verrouiller la database partagée
-        let mut db = afsec_service.thread_db.lock().unwrap();
+        let mut db = afsec_service.lock_database();
 
         // Voir s'il y a une notification d'un autre utilisateur
         if let Some(notification_change) = db.get_change(afsec_service.id_user, false, true) {
@@ -202,8 +1031,6 @@ pub fn check_notification_changes(
         }
     }
 
-    // Informe les `middlewares`
-    for (id_user, id_tag, t_value) in vec_changes {
-        middlewares.notification_change(afsec_service, id_user, id_tag, &t_value);
-    }
+    // Informe les `middlewares` (en un seul lot)
+    middlewares.notification_changes(afsec_service, &vec_changes);
 }
@@ -0,0 +1,164 @@
+//! Refus de conversations selon le mode de fonctionnement de l'AFSEC+
+//!
+//! L'AFSEC+ annonce son mode de fonctionnement (`D_MODE_AFSEC`) dans `AF_INIT` puis lors de
+//! chaque changement de mode (donnée `D_MODE_AFSEC` dans une requête quelconque). Le mode courant
+//! est publié dans le tag `ID_TAG_SIM_AFSEC_MODE` (voir le module `middleware`).
+//!
+//! Selon la SR DEV 004, l'ICOM refuse certaines conversations selon ce mode (par exemple pas de
+//! `PACK_IN` en mode maintenance). Les valeurs de `D_MODE_AFSEC` étant propres à l'application
+//! AFSEC+, les refus sont spécifiés par des règles `<mode>=<message>` (option `--mode-refuse` de
+//! la ligne de commande) :
+//!
+//! * `<mode>` : Valeur de `D_MODE_AFSEC` (décimale ou hexadécimale `0x..`)
+//! * `<message>` : Message `AF_*` refusé (NACK), désigné par son nom (`PACK_IN`, `AF_DATA_OUT`,
+//!   ...) ou par son code (décimal ou hexadécimal `0x..`)
+//!
+//! Exemple: `--mode-refuse 2=PACK_IN`
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::middleware::{
+    AF_ALIVE, AF_DATA_IN, AF_DATA_OUT, AF_DATA_OUT_TABLE_INDEX, AF_DOWNLOAD, AF_INIT, AF_MENU,
+    AF_PACK_IN, AF_PACK_OUT, AF_TEST,
+};
+
+/// Noms des messages `AF_*` pouvant être refusés
+const MESSAGE_NAMES: &[(&str, u8)] = &[
+    ("ALIVE", AF_ALIVE),
+    ("MENU", AF_MENU),
+    ("DATA_OUT", AF_DATA_OUT),
+    ("DATA_IN", AF_DATA_IN),
+    ("DATA_OUT_TABLE_INDEX", AF_DATA_OUT_TABLE_INDEX),
+    ("DOWNLOAD", AF_DOWNLOAD),
+    ("TEST", AF_TEST),
+    ("PACK_OUT", AF_PACK_OUT),
+    ("PACK_IN", AF_PACK_IN),
+];
+
+/// Décode un entier décimal ou hexadécimal (`0x..`)
+fn parse_number<T: TryFrom<u32>>(s: &str) -> Option<T> {
+    let s = s.trim();
+    let value = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => s.parse::<u32>().ok()?,
+    };
+    T::try_from(value).ok()
+}
+
+/// Règle de refus d'un message `AF_*` dans un mode de fonctionnement de l'AFSEC+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModeRefusal {
+    /// Mode de fonctionnement de l'AFSEC+ (`D_MODE_AFSEC`)
+    pub mode: u16,
+
+    /// Message `AF_*` refusé dans ce mode
+    pub message: u8,
+}
+
+impl fmt::Display for ModeRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match MESSAGE_NAMES.iter().find(|(_, code)| *code == self.message) {
+            Some((name, _)) => write!(f, "mode {}: AF_{name}", self.mode),
+            None => write!(f, "mode {}: 0x{:02X}", self.mode, self.message),
+        }
+    }
+}
+
+impl FromStr for ModeRefusal {
+    type Err = String;
+
+    /// Accepte '<mode>=<message>' (ex: '2=PACK_IN', '0x02=AF_PACK_IN' ou '2=0x0C')
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((mode, message)) = s.split_once('=') else {
+            return Err(format!(
+                "Règle '{s}' incorrecte (attendu: <mode>=<message>, ex: 2=PACK_IN)"
+            ));
+        };
+        let Some(mode) = parse_number::<u16>(mode) else {
+            return Err(format!("Mode '{mode}' incorrect dans la règle '{s}'"));
+        };
+        let name = message.trim().to_uppercase();
+        let name = name.strip_prefix("AF_").unwrap_or(&name);
+        let Some(message) = MESSAGE_NAMES
+            .iter()
+            .find(|(message_name, _)| *message_name == name)
+            .map(|(_, code)| *code)
+            .or_else(|| parse_number::<u8>(name))
+        else {
+            return Err(format!("Message '{message}' inconnu dans la règle '{s}'"));
+        };
+        if message == AF_INIT {
+            return Err(format!(
+                "Le message AF_INIT ne peut pas être refusé ('{s}')"
+            ));
+        }
+        Ok(ModeRefusal { mode, message })
+    }
+}
+
+/// Ensemble des règles de refus de messages selon le mode de fonctionnement de l'AFSEC+
+#[derive(Clone, Debug, Default)]
+pub struct ModePolicy {
+    refusals: Vec<ModeRefusal>,
+}
+
+impl ModePolicy {
+    /// Constructeur selon une liste de règles '<mode>=<message>'
+    pub fn from_rules(rules: &[String]) -> Result<Self, String> {
+        let refusals = rules
+            .iter()
+            .map(|rule| ModeRefusal::from_str(rule))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ModePolicy { refusals })
+    }
+
+    /// Règles de refus
+    pub fn refusals(&self) -> &[ModeRefusal] {
+        &self.refusals
+    }
+
+    /// Indique si un message `AF_*` est refusé dans un mode (`None` si mode inconnu)
+    pub fn is_refused(&self, option_mode: Option<u16>, message: u8) -> bool {
+        option_mode.is_some_and(|mode| {
+            self.refusals
+                .iter()
+                .any(|refusal| refusal.mode == mode && refusal.message == message)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_refusal_from_str() {
+        let refusal = ModeRefusal {
+            mode: 2,
+            message: AF_PACK_IN,
+        };
+        assert_eq!(ModeRefusal::from_str("2=PACK_IN"), Ok(refusal));
+        assert_eq!(ModeRefusal::from_str("0x02=af_pack_in"), Ok(refusal));
+        assert_eq!(ModeRefusal::from_str(" 2 = 0x0C "), Ok(refusal));
+        assert_eq!(refusal.to_string(), "mode 2: AF_PACK_IN");
+
+        assert!(ModeRefusal::from_str("2").is_err());
+        assert!(ModeRefusal::from_str("x=PACK_IN").is_err());
+        assert!(ModeRefusal::from_str("2=UNKNOWN").is_err());
+        assert!(ModeRefusal::from_str("2=INIT").is_err());
+    }
+
+    #[test]
+    fn test_mode_policy() {
+        let rules = ["2=PACK_IN".to_string(), "3=DATA_OUT".to_string()];
+        let mode_policy = ModePolicy::from_rules(&rules).unwrap();
+        assert_eq!(mode_policy.refusals().len(), 2);
+        assert!(mode_policy.is_refused(Some(2), AF_PACK_IN));
+        assert!(!mode_policy.is_refused(Some(2), AF_DATA_OUT));
+        assert!(!mode_policy.is_refused(Some(1), AF_PACK_IN));
+        assert!(!mode_policy.is_refused(None, AF_PACK_IN));
+
+        assert!(ModePolicy::from_rules(&["2=BAD".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Simulation d'une réception lente des octets de l'AFSEC+ (tests et injection de fautes)
+//!
+//! Sur la liaison série, les octets d'une trame de l'AFSEC+ arrivent au compte-gouttes : un
+//! [`PacedInput`] délivre les octets d'une ou plusieurs trames par morceaux de taille
+//! configurable séparés d'un délai configurable, pour valider la construction des trames
+//! (`FrameState::Building`) et l'abandon des trames incomplètes par un
+//! [`FrameReader`](super::FrameReader) au rythme réel de la liaison série.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Octets à délivrer par morceaux
+#[derive(Clone, Debug)]
+pub struct PacedInput {
+    /// Octets restant à délivrer
+    octets: VecDeque<u8>,
+
+    /// Nombre d'octets par morceau (au moins 1)
+    chunk_size: usize,
+
+    /// Délai entre 2 morceaux
+    inter_chunk_delay: Duration,
+
+    /// Date de délivrance du dernier morceau
+    option_last_chunk: Option<Instant>,
+}
+
+impl PacedInput {
+    /// Constructeur avec les octets à délivrer par morceaux de `chunk_size` octets séparés
+    /// de `inter_chunk_delay`
+    pub fn new(octets: &[u8], chunk_size: usize, inter_chunk_delay: Duration) -> Self {
+        Self {
+            octets: octets.iter().copied().collect(),
+            chunk_size: chunk_size.max(1),
+            inter_chunk_delay,
+            option_last_chunk: None,
+        }
+    }
+
+    /// Ajoute des octets à délivrer après ceux en attente
+    pub fn extend(&mut self, octets: &[u8]) {
+        self.octets.extend(octets);
+    }
+
+    /// Morceau suivant à délivrer à la date `now`
+    /// (vide si plus rien à délivrer ou si le délai depuis le morceau précédent n'est pas écoulé)
+    pub fn next_chunk(&mut self, now: Instant) -> Vec<u8> {
+        if self.octets.is_empty() {
+            return vec![];
+        }
+        if let Some(last_chunk) = self.option_last_chunk {
+            if now.saturating_duration_since(last_chunk) < self.inter_chunk_delay {
+                return vec![];
+            }
+        }
+        self.option_last_chunk = Some(now);
+        let n = self.chunk_size.min(self.octets.len());
+        self.octets.drain(..n).collect()
+    }
+
+    /// Tous les octets ont été délivrés
+    pub fn is_empty(&self) -> bool {
+        self.octets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::afsec::tlv_frame::{FrameState, ETX, STX};
+    use crate::afsec::FrameReader;
+
+    /// Délivre `paced_input` à un `FrameReader` toutes les ms à partir de `start`
+    /// Retourne les états successifs de la trame et la date de fin
+    fn feed(
+        frame_reader: &mut FrameReader,
+        paced_input: &mut PacedInput,
+        start: Instant,
+    ) -> (Vec<FrameState>, Instant) {
+        let mut states = vec![];
+        let mut now = start;
+        while !paced_input.is_empty() {
+            let chunk = paced_input.next_chunk(now);
+            if !chunk.is_empty() {
+                states.push(frame_reader.push(&chunk, now));
+            }
+            now += Duration::from_millis(1);
+        }
+        (states, now)
+    }
+
+    #[test]
+    fn test_paced_input() {
+        let mut paced_input = PacedInput::new(&[1, 2, 3, 4, 5], 2, Duration::from_millis(10));
+        let start = Instant::now();
+        assert_eq!(paced_input.next_chunk(start), vec![1, 2]);
+        assert!(paced_input
+            .next_chunk(start + Duration::from_millis(9))
+            .is_empty());
+        assert_eq!(
+            paced_input.next_chunk(start + Duration::from_millis(10)),
+            vec![3, 4]
+        );
+        assert_eq!(
+            paced_input.next_chunk(start + Duration::from_millis(20)),
+            vec![5]
+        );
+        assert!(paced_input.is_empty());
+        assert!(paced_input
+            .next_chunk(start + Duration::from_millis(30))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_byte_at_a_time() {
+        let frame = [STX, 0x10, 0, 0x10, ETX];
+        for chunk_size in [1, 2, 3] {
+            let mut frame_reader = FrameReader::default();
+            let mut paced_input = PacedInput::new(&frame, chunk_size, Duration::from_millis(5));
+            let (states, now) = feed(&mut frame_reader, &mut paced_input, Instant::now());
+
+            // En construction jusqu'au dernier morceau, sans abandon entre les morceaux
+            let (last, building) = states.split_last().unwrap();
+            assert!(building.iter().all(|state| *state == FrameState::Building));
+            assert_eq!(*last, FrameState::Ok);
+            assert!(frame_reader.check_timeout(now).is_none());
+            assert_eq!(frame_reader.take().encode(), frame.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_stalled_frame() {
+        // L'AFSEC+ s'interrompt au milieu d'une trame puis transmet une nouvelle trame
+        let timeout = Duration::from_millis(20);
+        let mut frame_reader = FrameReader::new(timeout);
+        let mut paced_input = PacedInput::new(&[STX, 0x10, 1], 1, Duration::from_millis(5));
+        let (states, now) = feed(&mut frame_reader, &mut paced_input, Instant::now());
+        assert!(states.iter().all(|state| *state == FrameState::Building));
+        assert!(frame_reader.check_timeout(now).is_none());
+
+        let now = now + timeout;
+        let abandoned = frame_reader.check_timeout(now).unwrap();
+        assert_eq!(abandoned.encode(), vec![STX, 0x10, 1]);
+
+        let frame = [STX, 0x11, 0, 0x11, ETX];
+        let mut paced_input = PacedInput::new(&frame, 1, Duration::from_millis(5));
+        let (states, _) = feed(&mut frame_reader, &mut paced_input, now);
+        assert_eq!(states.last(), Some(&FrameState::Ok));
+        assert_eq!(frame_reader.take().encode(), frame.to_vec());
+    }
+
+    #[test]
+    fn test_delay_over_timeout() {
+        // Morceaux trop espacés : la trame est abandonnée avant d'être complète
+        let timeout = Duration::from_millis(20);
+        let mut frame_reader = FrameReader::new(timeout);
+        let mut paced_input =
+            PacedInput::new(&[STX, 0x10, 0, 0x10, ETX], 2, Duration::from_millis(30));
+        let mut now = Instant::now();
+        let (mut nb_abandoned, mut nb_junk) = (0, 0);
+        while !paced_input.is_empty() {
+            let chunk = paced_input.next_chunk(now);
+            if chunk.is_empty() {
+                if frame_reader.check_timeout(now).is_some() {
+                    nb_abandoned += 1;
+                }
+            } else if frame_reader.push(&chunk, now) == FrameState::Junk {
+                frame_reader.take();
+                nb_junk += 1;
+            }
+            now += Duration::from_millis(1);
+        }
+        assert_eq!((nb_abandoned, nb_junk), (1, 2));
+    }
+}
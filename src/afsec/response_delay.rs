@@ -0,0 +1,138 @@
+//! Délai de réponse aux trames de l'AFSEC+ (option `--response-delay`)
+//!
+//! L'ICOM réel répond aux trames de l'AFSEC+ après 5 à 15 ms alors que le simulateur répond
+//! immédiatement. Pour tester la machine d'états de l'AFSEC+ (sensible aux temps de réponse) dans
+//! des conditions réalistes, la réponse peut être retardée d'un délai fixe (`10`) ou tiré
+//! uniformément dans une plage (`5-15`) avec le générateur pseudo-aléatoire de la simulation.
+//!
+//! Le délai est en millisecondes. La réponse retardée est transmise par la boucle de communication
+//! dès que son échéance est atteinte (voir `read_and_write` du module `afsec`).
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::sim_rng::SimRng;
+
+/// Délai de réponse aux trames de l'AFSEC+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResponseDelay {
+    /// Réponse immédiate
+    #[default]
+    None,
+
+    /// Délai fixe
+    Fixed(Duration),
+
+    /// Délai tiré uniformément dans la plage [min, max]
+    Uniform(Duration, Duration),
+}
+
+impl fmt::Display for ResponseDelay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResponseDelay::None => write!(f, "none"),
+            ResponseDelay::Fixed(delay) => write!(f, "{} ms", delay.as_millis()),
+            ResponseDelay::Uniform(min, max) => {
+                write!(f, "{}-{} ms", min.as_millis(), max.as_millis())
+            }
+        }
+    }
+}
+
+impl FromStr for ResponseDelay {
+    type Err = String;
+
+    /// Accepte `<ms>` (0 pour une réponse immédiate) ou `<min>-<max>` (en ms)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_ms = |ms: &str| {
+            ms.trim()
+                .parse::<u64>()
+                .map(Duration::from_millis)
+                .map_err(|_| format!("Délai de réponse '{s}' invalide (ex: '10' ou '5-15' en ms)"))
+        };
+        match s.split_once('-') {
+            Some((min, max)) => {
+                let (min, max) = (parse_ms(min)?, parse_ms(max)?);
+                if min > max {
+                    return Err(format!("Délai de réponse '{s}' invalide (min > max)"));
+                }
+                Ok(if min == max {
+                    Self::fixed(min)
+                } else {
+                    ResponseDelay::Uniform(min, max)
+                })
+            }
+            None => Ok(Self::fixed(parse_ms(s)?)),
+        }
+    }
+}
+
+impl ResponseDelay {
+    /// Délai fixe (`ResponseDelay::None` si nul)
+    fn fixed(delay: Duration) -> Self {
+        if delay.is_zero() {
+            ResponseDelay::None
+        } else {
+            ResponseDelay::Fixed(delay)
+        }
+    }
+
+    /// Délai à appliquer à la prochaine réponse
+    pub fn sample(&self, rng: &mut SimRng) -> Duration {
+        match self {
+            ResponseDelay::None => Duration::ZERO,
+            ResponseDelay::Fixed(delay) => *delay,
+            ResponseDelay::Uniform(min, max) => {
+                let min_us = u64::try_from(min.as_micros()).unwrap_or(u64::MAX);
+                let max_us = u64::try_from(max.as_micros()).unwrap_or(u64::MAX);
+                Duration::from_micros(rng.gen_range(min_us, max_us.saturating_add(1)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_delay_from_str() {
+        assert_eq!(ResponseDelay::from_str("0"), Ok(ResponseDelay::None));
+        assert_eq!(
+            ResponseDelay::from_str("10"),
+            Ok(ResponseDelay::Fixed(Duration::from_millis(10)))
+        );
+        assert_eq!(
+            ResponseDelay::from_str("5-15"),
+            Ok(ResponseDelay::Uniform(
+                Duration::from_millis(5),
+                Duration::from_millis(15)
+            ))
+        );
+        assert_eq!(
+            ResponseDelay::from_str("7-7"),
+            Ok(ResponseDelay::Fixed(Duration::from_millis(7)))
+        );
+        assert!(ResponseDelay::from_str("15-5").is_err());
+        assert!(ResponseDelay::from_str("abc").is_err());
+        assert_eq!(
+            ResponseDelay::from_str("5-15").unwrap().to_string(),
+            "5-15 ms"
+        );
+    }
+
+    #[test]
+    fn test_response_delay_sample() {
+        let mut rng = SimRng::new(42);
+        assert_eq!(ResponseDelay::None.sample(&mut rng), Duration::ZERO);
+        let delay = ResponseDelay::Fixed(Duration::from_millis(10));
+        assert_eq!(delay.sample(&mut rng), Duration::from_millis(10));
+
+        let delay = ResponseDelay::from_str("5-15").unwrap();
+        for _ in 0..100 {
+            let sample = delay.sample(&mut rng);
+            assert!(sample >= Duration::from_millis(5) && sample <= Duration::from_millis(15));
+        }
+    }
+}
@@ -0,0 +1,393 @@
+//! Vecteurs de test du codage des trames TLV (sous-commande `gen-vectors`)
+//!
+//! Fichier de référence ('golden data') pour l'équipe du firmware résident de l'AFSEC+ : chaque
+//! vecteur est une trame brute et le résultat de son décodage par l'implémentation Rust
+//! (`RawFrame` puis `DataFrame`). Les vecteurs couvrent tous les types de messages, tous les
+//! formats de données avec leurs valeurs limites, les longueurs limites et les trames erronées
+//! (XOR incorrect, ETX manquant, trame incomplète, octets parasites).
+//!
+//! Format du fichier : une ligne `<nom>;<octets en hexa>;<résultat attendu>` par vecteur, les
+//! lignes commençant par `#` sont des commentaires. Le résultat attendu est :
+//!
+//! * `ACK` ou `NACK`
+//! * `MSG <tag> <données>` : tag du message et liste des données `<tag>:<format>:<valeur>`
+//!   (tag et code du format sur 2 chiffres hexa, valeur 'big endian' en hexa, `-` si vide)
+//! * `ERR <code>` : code d'erreur `SIM_ICOM_TLV_ERR_*` de l'interface C (voir le module `ffi`)
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::format_hex_frame;
+use super::middleware::{
+    name_of, AF_ALIVE, AF_DATA_IN, AF_DATA_OUT, AF_DATA_OUT_TABLE_INDEX, AF_DOWNLOAD, AF_INIT,
+    AF_MENU, AF_PACK_IN, AF_PACK_OUT, AF_TEST, IC_ALIVE, IC_DATA_IN, IC_DATA_OUT,
+    IC_DATA_OUT_TABLE_INDEX, IC_DOWNLOAD, IC_INIT, IC_MENU, IC_PACK_IN, IC_PACK_OUT, IC_TEST,
+};
+use super::tlv_frame::{DataFrame, DataItem, RawFrame, ETX, STX};
+use crate::build_info;
+use crate::ffi::frame_error_to_code;
+use crate::t_data::{be_data, TValue};
+
+/// Nom par défaut du fichier des vecteurs de test
+pub const DEFAULT_TEST_VECTORS_FILENAME: &str = "tlv_vectors.txt";
+
+/// Longueur max. de la partie 'Value' d'une trame (voir `RawFrame::try_extend_data_item`)
+const VALUE_MAX_LEN: usize = 250;
+
+/// Longueur max. d'une donnée `VecU8` (code du format 0x80 + longueur sur 1 octet)
+const VEC_U8_MAX_LEN: usize = 0x7F;
+
+/// Tag des données des vecteurs de test
+const DATA_ITEM_TAG: u8 = 0x31;
+
+/// Vecteur de test : trame brute et résultat attendu de son décodage
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    /// Nom du vecteur
+    pub name: String,
+
+    /// Octets de la trame
+    pub octets: Vec<u8>,
+}
+
+impl TestVector {
+    /// Constructeur
+    fn new(name: &str, octets: Vec<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            octets,
+        }
+    }
+
+    /// Résultat attendu du décodage de la trame par l'implémentation de référence
+    pub fn expected(&self) -> String {
+        match DataFrame::try_from(RawFrame::new(&self.octets)) {
+            Ok(DataFrame::SimpleACK) => "ACK".to_string(),
+            Ok(DataFrame::SimpleNACK) => "NACK".to_string(),
+            Ok(DataFrame::Message(tag, data_items)) => {
+                let mut ret = format!("MSG {tag:02X}");
+                for data_item in data_items {
+                    let value = be_data::encode(&data_item.t_value);
+                    let value = if value.is_empty() {
+                        "-".to_string()
+                    } else {
+                        value.iter().map(|octet| format!("{octet:02X}")).collect()
+                    };
+                    ret += &format!(
+                        " {:02X}:{:02X}:{value}",
+                        data_item.tag,
+                        u8::from(data_item.t_format)
+                    );
+                }
+                ret
+            }
+            Err(frame_error) => format!("ERR {}", frame_error_to_code(&frame_error)),
+        }
+    }
+}
+
+/// XOR d'une trame (tag ^ len ^ values)
+fn calcul_xor(tag: u8, values: &[u8]) -> u8 {
+    #[allow(clippy::cast_possible_truncation)]
+    let len = values.len() as u8;
+    values.iter().fold(tag ^ len, |xor, octet| xor ^ octet)
+}
+
+/// Trame d'un message construite octet par octet (sans contrôle de la longueur max.)
+fn encode_message(tag: u8, values: &[u8]) -> Vec<u8> {
+    #[allow(clippy::cast_possible_truncation)]
+    let mut octets = vec![STX, tag, values.len() as u8];
+    octets.extend(values);
+    octets.push(calcul_xor(tag, values));
+    octets.push(ETX);
+    octets
+}
+
+/// Trame d'un message avec des données, construite par `RawFrame`
+fn message(tag: u8, data_items: &[DataItem]) -> Vec<u8> {
+    let mut raw_frame = RawFrame::new_message(tag);
+    for data_item in data_items {
+        raw_frame
+            .try_extend_data_item(data_item)
+            .expect("Vecteur de test trop long");
+    }
+    raw_frame.encode()
+}
+
+/// Trame d'un message avec une seule donnée `DATA_ITEM_TAG`
+fn message_with_value(t_value: TValue) -> Vec<u8> {
+    message(AF_DATA_IN, &[DataItem::new(DATA_ITEM_TAG, t_value)])
+}
+
+/// Vecteurs des messages vides, de ACK et de NACK
+fn message_vectors(vectors: &mut Vec<TestVector>) {
+    vectors.push(TestVector::new("ack", RawFrame::new_ack().encode()));
+    vectors.push(TestVector::new("nack", RawFrame::new_nack().encode()));
+    for tag in [
+        AF_ALIVE,
+        IC_ALIVE,
+        AF_INIT,
+        IC_INIT,
+        AF_MENU,
+        IC_MENU,
+        AF_DATA_OUT,
+        IC_DATA_OUT,
+        AF_DATA_IN,
+        IC_DATA_IN,
+        AF_DATA_OUT_TABLE_INDEX,
+        IC_DATA_OUT_TABLE_INDEX,
+        AF_DOWNLOAD,
+        IC_DOWNLOAD,
+        AF_TEST,
+        IC_TEST,
+        AF_PACK_OUT,
+        IC_PACK_OUT,
+        AF_PACK_IN,
+        IC_PACK_IN,
+    ] {
+        let name = format!("message_{}", name_of(tag).to_lowercase());
+        vectors.push(TestVector::new(&name, message(tag, &[])));
+    }
+}
+
+/// Vecteurs de tous les formats de données avec leurs valeurs limites
+fn format_vectors(vectors: &mut Vec<TestVector>) {
+    let values = [
+        ("bool_false", TValue::Bool(false)),
+        ("bool_true", TValue::Bool(true)),
+        ("u8_min", TValue::U8(u8::MIN)),
+        ("u8_max", TValue::U8(u8::MAX)),
+        ("i8_min", TValue::I8(i8::MIN)),
+        ("i8_minus_one", TValue::I8(-1)),
+        ("i8_max", TValue::I8(i8::MAX)),
+        ("u16_min", TValue::U16(u16::MIN)),
+        ("u16_max", TValue::U16(u16::MAX)),
+        ("i16_min", TValue::I16(i16::MIN)),
+        ("i16_minus_one", TValue::I16(-1)),
+        ("i16_max", TValue::I16(i16::MAX)),
+        ("u32_min", TValue::U32(u32::MIN)),
+        ("u32_max", TValue::U32(u32::MAX)),
+        ("i32_min", TValue::I32(i32::MIN)),
+        ("i32_minus_one", TValue::I32(-1)),
+        ("i32_max", TValue::I32(i32::MAX)),
+        ("u64_min", TValue::U64(u64::MIN)),
+        ("u64_max", TValue::U64(u64::MAX)),
+        ("i64_min", TValue::I64(i64::MIN)),
+        ("i64_minus_one", TValue::I64(-1)),
+        ("i64_max", TValue::I64(i64::MAX)),
+        ("f32_zero", TValue::F32(0.0)),
+        ("f32_minus_one_half", TValue::F32(-1.5)),
+        ("f32_min_positive", TValue::F32(f32::MIN_POSITIVE)),
+        ("f32_max", TValue::F32(f32::MAX)),
+        ("f64_zero", TValue::F64(0.0)),
+        ("f64_minus_one_half", TValue::F64(-1.5)),
+        ("f64_min_positive", TValue::F64(f64::MIN_POSITIVE)),
+        ("f64_max", TValue::F64(f64::MAX)),
+    ];
+    for (name, t_value) in values {
+        let name = format!("format_{name}");
+        vectors.push(TestVector::new(&name, message_with_value(t_value)));
+    }
+
+    let vec_u8: Vec<u8> = (1..=u8::try_from(VEC_U8_MAX_LEN).unwrap()).collect();
+    for len in [0, 1, VEC_U8_MAX_LEN] {
+        let name = format!("format_vec_u8_len_{len}");
+        let t_value = TValue::new_vec_u8(len, &vec_u8);
+        vectors.push(TestVector::new(&name, message_with_value(t_value)));
+    }
+
+    // Code de format inconnu (décodé comme une donnée vide)
+    vectors.push(TestVector::new(
+        "format_unknown",
+        encode_message(AF_DATA_IN, &[DATA_ITEM_TAG, 0x00]),
+    ));
+}
+
+/// Vecteurs des longueurs limites des trames
+fn length_vectors(vectors: &mut Vec<TestVector>) {
+    // Plusieurs données dans un même message
+    let data_items = [
+        DataItem::new(0x31, TValue::U8(1)),
+        DataItem::new(0x32, TValue::I16(-2)),
+        DataItem::new(0x33, TValue::new_vec_u8(3, &[1, 2, 3])),
+        DataItem::new(0x34, TValue::F32(4.5)),
+    ];
+    vectors.push(TestVector::new(
+        "length_several_items",
+        message(IC_DATA_OUT, &data_items),
+    ));
+
+    // Partie 'Value' de longueur max. : VecU8(127) + VecU8(119) = 129 + 121 octets
+    let data_items = [
+        DataItem::new(0x31, TValue::new_vec_u8(VEC_U8_MAX_LEN, &[0xAA])),
+        DataItem::new(
+            0x32,
+            TValue::new_vec_u8(VALUE_MAX_LEN - (VEC_U8_MAX_LEN + 2) - 2, &[0x55]),
+        ),
+    ];
+    vectors.push(TestVector::new(
+        "length_max",
+        message(IC_DATA_OUT, &data_items),
+    ));
+
+    // Partie 'Value' de longueur max. + 1 (refusée à l'encodage mais décodée)
+    let mut values = DataItem::new(0x31, TValue::new_vec_u8(VEC_U8_MAX_LEN, &[0xAA])).encode();
+    values.extend(
+        DataItem::new(
+            0x32,
+            TValue::new_vec_u8(VALUE_MAX_LEN - (VEC_U8_MAX_LEN + 2) - 1, &[0x55]),
+        )
+        .encode(),
+    );
+    vectors.push(TestVector::new(
+        "length_max_plus_one",
+        encode_message(IC_DATA_OUT, &values),
+    ));
+
+    // Longueur incohérente avec les données
+    let values = DataItem::new(0x31, TValue::U32(1)).encode();
+    vectors.push(TestVector::new(
+        "length_truncated_item",
+        encode_message(AF_DATA_IN, &values[..values.len() - 1]),
+    ));
+    vectors.push(TestVector::new(
+        "length_single_octet",
+        encode_message(AF_DATA_IN, &[0x31]),
+    ));
+}
+
+/// Vecteurs des trames erronées
+fn error_vectors(vectors: &mut Vec<TestVector>) {
+    vectors.push(TestVector::new("error_empty", vec![]));
+    vectors.push(TestVector::new("error_junk", vec![0x55, 0xAA]));
+
+    let octets = message_with_value(TValue::U16(0x1234));
+    let xor_index = octets.len() - 2;
+
+    let mut bad_xor = octets.clone();
+    bad_xor[xor_index] ^= 0xFF;
+    vectors.push(TestVector::new("error_bad_xor", bad_xor));
+
+    let mut bad_xor_empty = message(AF_ALIVE, &[]);
+    bad_xor_empty[3] ^= 0x01;
+    vectors.push(TestVector::new("error_bad_xor_empty", bad_xor_empty));
+
+    let mut bad_etx = octets.clone();
+    bad_etx[xor_index + 1] = STX;
+    vectors.push(TestVector::new("error_bad_etx", bad_etx));
+
+    vectors.push(TestVector::new(
+        "error_missing_etx",
+        octets[..=xor_index].to_vec(),
+    ));
+    vectors.push(TestVector::new("error_stx_only", vec![STX]));
+    vectors.push(TestVector::new(
+        "error_incomplete_value",
+        octets[..xor_index - 1].to_vec(),
+    ));
+
+    let mut trailing_junk = octets.clone();
+    trailing_junk.push(0x00);
+    vectors.push(TestVector::new("error_trailing_junk", trailing_junk));
+
+    let mut ack_and_junk = RawFrame::new_ack().encode();
+    ack_and_junk.push(0x00);
+    vectors.push(TestVector::new("error_ack_and_junk", ack_and_junk));
+}
+
+/// Liste des vecteurs de test
+pub fn test_vectors() -> Vec<TestVector> {
+    let mut vectors = vec![];
+    message_vectors(&mut vectors);
+    format_vectors(&mut vectors);
+    length_vectors(&mut vectors);
+    error_vectors(&mut vectors);
+    vectors
+}
+
+/// Contenu du fichier des vecteurs de test
+pub fn format_test_vectors(vectors: &[TestVector]) -> String {
+    let mut ret = format!(
+        "# Vecteurs de test TLV - {} {}\n",
+        build_info::NAME,
+        build_info::VERSION
+    );
+    ret += "# <nom>;<octets>;<ACK | NACK | MSG <tag> <tag>:<format>:<valeur>... | ERR <code>>\n";
+    for vector in vectors {
+        ret += &format!(
+            "{};{};{}\n",
+            vector.name,
+            format_hex_frame(&vector.octets),
+            vector.expected()
+        );
+    }
+    ret
+}
+
+/// Écrit le fichier des vecteurs de test
+/// Retourne le nombre de vecteurs écrits
+/// # Errors
+/// Erreur d'écriture du fichier
+pub fn write_test_vectors(path: &Path) -> io::Result<usize> {
+    let vectors = test_vectors();
+    fs::write(path, format_test_vectors(&vectors))?;
+    Ok(vectors.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ffi::{SIM_ICOM_TLV_ERR_BAD_DATA_ITEM, SIM_ICOM_TLV_ERR_IS_JUNK};
+
+    fn expected_of(vectors: &[TestVector], name: &str) -> String {
+        vectors
+            .iter()
+            .find(|vector| vector.name == name)
+            .unwrap()
+            .expected()
+    }
+
+    #[test]
+    fn test_test_vectors() {
+        let vectors = test_vectors();
+        assert_eq!(expected_of(&vectors, "ack"), "ACK");
+        assert_eq!(expected_of(&vectors, "message_ic_alive"), "MSG 80");
+        assert_eq!(expected_of(&vectors, "format_u16_max"), "MSG 04 31:02:FFFF");
+        assert_eq!(
+            expected_of(&vectors, "format_vec_u8_len_0"),
+            "MSG 04 31:80:-"
+        );
+        assert_eq!(
+            expected_of(&vectors, "error_bad_xor"),
+            format!("ERR {SIM_ICOM_TLV_ERR_IS_JUNK}")
+        );
+        assert_eq!(expected_of(&vectors, "format_unknown"), "MSG 04 31:80:-");
+        assert_eq!(
+            expected_of(&vectors, "length_truncated_item"),
+            format!("ERR {SIM_ICOM_TLV_ERR_BAD_DATA_ITEM}")
+        );
+
+        // Trame de longueur max.
+        let length_max = vectors
+            .iter()
+            .find(|vector| vector.name == "length_max")
+            .unwrap();
+        assert_eq!(length_max.octets.len(), VALUE_MAX_LEN + 5);
+
+        // Noms uniques
+        let mut names: Vec<&str> = vectors.iter().map(|vector| vector.name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), vectors.len());
+    }
+
+    #[test]
+    fn test_format_test_vectors() {
+        let vectors = vec![TestVector::new("ack", RawFrame::new_ack().encode())];
+        let content = format_test_vectors(&vectors);
+        assert!(content.starts_with('#'));
+        assert!(content.ends_with("ack;06;ACK\n"));
+    }
+}
@@ -14,6 +14,7 @@ use std::convert;
 use std::fmt;
 
 use super::{DataItem, FrameError, RawFrame, ACK, NACK};
+use crate::afsec::middleware::name_of;
 
 /// Abstraction logique du contenu d'une trame TLV
 #[derive(Debug)]
@@ -35,7 +36,7 @@ impl fmt::Display for DataFrame {
             DataFrame::SimpleACK => ret = "ACK".to_string(),
             DataFrame::SimpleNACK => ret = "NACK".to_string(),
             DataFrame::Message(tag, datas) => {
-                ret = format!("T={tag} datas=[");
+                ret = format!("T={}/0x{tag:02X} datas=[", name_of(*tag));
                 for data in datas {
                     ret += &format!("{data}, ");
                 }
@@ -148,6 +149,23 @@ mod tests {
         assert!(data_frame.is_simple_nack());
     }
 
+    #[test]
+    fn test_display() {
+        let mut raw_frame = RawFrame::new_message(0x03);
+        raw_frame
+            .try_extend_data_item(&DataItem::new(0x35, TValue::U16(123)))
+            .unwrap();
+        assert_eq!(
+            raw_frame.to_string(),
+            "frame OK AF_DATA_OUT: [2, 3, 4, 53, 2, 0, 123, 75, 3]"
+        );
+        let data_frame = DataFrame::try_from(raw_frame).unwrap();
+        assert_eq!(
+            data_frame.to_string(),
+            "T=AF_DATA_OUT/0x03 datas=[T=D_DATA_VALUE/0x35 L=U16 V=U16(123), ]"
+        );
+    }
+
     #[test]
     fn test_encode_message() {
         // Contenu du message
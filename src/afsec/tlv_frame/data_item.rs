@@ -13,6 +13,7 @@
 
 use std::fmt;
 
+use crate::afsec::middleware::data_item_name;
 use crate::t_data::{be_data, TFormat, TValue};
 
 use super::FrameError;
@@ -32,14 +33,23 @@ pub struct DataItem {
 
 impl fmt::Display for DataItem {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "T={} L={} V={}", self.tag, self.t_format, self.t_value)
+        write!(
+            f,
+            "T={}/0x{:02X} L={} V={}",
+            data_item_name(self.tag),
+            self.tag,
+            self.t_format,
+            self.t_value
+        )
     }
 }
 
 impl DataItem {
     /// Constructeur
+    /// Le contenu d'une `TValue::VecU8` est ajusté (tronqué ou complété par des 0) à sa longueur
     #[allow(dead_code)]
     pub fn new(tag: u8, t_value: TValue) -> Self {
+        let t_value = t_value.normalized();
         let t_format = TFormat::from(&t_value);
         Self {
             tag,
@@ -62,8 +72,8 @@ impl DataItem {
             return Err(FrameError::BadDataLength);
         }
         match be_data::decode(t_format, &values[2..]) {
-            Ok(t_value) => Ok((DataItem::new(tag, t_value), data_item_len)),
-            Err(_) => Err(FrameError::BadDataItem),
+            Ok(t_value) if t_value.is_valid() => Ok((DataItem::new(tag, t_value), data_item_len)),
+            _ => Err(FrameError::BadDataItem),
         }
     }
 
@@ -146,6 +156,10 @@ mod tests {
             DataItem::new(6, TValue::I16(-123)),
             DataItem::new(7, TValue::VecU8(0, vec![])),
             DataItem::new(8, TValue::I64(-1_000_000_000)),
+            // Longueur annoncée différente de la longueur du contenu
+            DataItem::new(9, TValue::VecU8(5, string_to_vec_u8("AB"))),
+            DataItem::new(10, TValue::VecU8(1, string_to_vec_u8("XYZ"))),
+            DataItem::new(11, TValue::U16(0x1234)),
         ];
 
         // Création d'un Vec<u8> contenant tous les test_data_items
@@ -173,4 +187,28 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_vec_u8_length() {
+        // Le contenu d'une chaîne qui a raccourci est complété à la longueur annoncée
+        let data_item = DataItem::new(1, TValue::VecU8(4, string_to_vec_u8("AB")));
+        assert_eq!(
+            data_item.encode(),
+            vec![1, u8::from(TFormat::VecU8(4)), b'A', b'B', 0, 0]
+        );
+
+        // Le contenu trop long est tronqué
+        let data_item = DataItem {
+            tag: 2,
+            t_format: TFormat::VecU8(1),
+            t_value: TValue::VecU8(1, string_to_vec_u8("XYZ")),
+        };
+        assert_eq!(
+            data_item.encode(),
+            vec![2, u8::from(TFormat::VecU8(1)), b'X']
+        );
+
+        // Données manquantes
+        assert!(DataItem::decode(&[3, u8::from(TFormat::VecU8(4)), b'A', b'B']).is_err());
+    }
 }
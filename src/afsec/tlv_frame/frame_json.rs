@@ -0,0 +1,375 @@
+//! Représentation JSON des trames TLV ([`RawFrame`], [`DataFrame`] et [`DataItem`])
+//!
+//! Représentation commune aux outils (captures, injection de trames, vecteurs de test, interface
+//! web...) : les octets sont en hexa (voir `format_hex_frame`) et les champs descriptifs
+//! (`state`, `name`, `value`) sont ignorés à la lecture.
+//!
+//! * [`RawFrame`]: `{"state":"OK","hex":"02 00 00 00 03"}`
+//! * [`DataItem`]: `{"tag":69,"name":"...","format":133,"hex":"41 42 43 44 45","value":"ABCDE"}`
+//!   (`format` est le code TLV du [`TFormat`] et `hex` contient les octets de la valeur)
+//! * [`DataFrame`]: `{"kind":"ACK"}`, `{"kind":"NACK"}` ou
+//!   `{"kind":"message","tag":35,"name":"...","items":[...]}`
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::{DataFrame, DataItem, RawFrame};
+use crate::afsec::middleware::{data_item_name, name_of};
+use crate::afsec::{format_hex_frame, parse_hex_frame};
+use crate::t_data::TFormat;
+use crate::webhook::{escape_json, json_value};
+
+/// Valeur d'un document JSON
+#[derive(Clone, Debug, PartialEq)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Décodage d'un document JSON
+    fn parse(json: &str) -> Result<Self, String> {
+        let mut chars = json.chars().peekable();
+        let value = parse_value(&mut chars)?;
+        skip_whitespaces(&mut chars);
+        match chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("Caractère '{c}' inattendu après le document JSON")),
+        }
+    }
+
+    /// Champ d'un objet JSON
+    fn field(&self, name: &str) -> Result<&JsonValue, String> {
+        let JsonValue::Object(fields) = self else {
+            return Err("Objet JSON attendu".to_string());
+        };
+        fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| format!("Champ '{name}' absent"))
+    }
+
+    /// Champ entier 0-255 d'un objet JSON
+    fn field_u8(&self, name: &str) -> Result<u8, String> {
+        match self.field(name)? {
+            JsonValue::Number(number)
+                if number.fract() == 0.0 && (0.0..=255.0).contains(number) =>
+            {
+                // Conversion exacte (entier vérifié entre 0 et 255)
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                Ok(*number as u8)
+            }
+            _ => Err(format!("Champ '{name}': entier 0-255 attendu")),
+        }
+    }
+
+    /// Champ chaîne d'un objet JSON
+    fn field_str(&self, name: &str) -> Result<&str, String> {
+        match self.field(name)? {
+            JsonValue::String(value) => Ok(value),
+            _ => Err(format!("Champ '{name}': chaîne attendue")),
+        }
+    }
+
+    /// Champ octets (chaîne en hexa, éventuellement vide) d'un objet JSON
+    fn field_hex(&self, name: &str) -> Result<Vec<u8>, String> {
+        let hex = self.field_str(name)?;
+        if hex.trim().is_empty() {
+            Ok(vec![])
+        } else {
+            parse_hex_frame(hex)
+        }
+    }
+}
+
+/// Saute les espaces d'un document JSON
+fn skip_whitespaces(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// Vérifie le mot attendu (`true`, `false` ou `null`) d'un document JSON
+fn expect_word(chars: &mut Peekable<Chars>, word: &str) -> Result<(), String> {
+    for expected in word.chars() {
+        if chars.next() != Some(expected) {
+            return Err(format!("'{word}' attendu"));
+        }
+    }
+    Ok(())
+}
+
+/// Décodage d'une valeur d'un document JSON
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<JsonValue, String> {
+    skip_whitespaces(chars);
+    match chars.peek().copied() {
+        Some('{') => parse_object(chars),
+        Some('[') => parse_array(chars),
+        Some('"') => Ok(JsonValue::String(parse_string(chars)?)),
+        Some('t') => expect_word(chars, "true").map(|()| JsonValue::Bool(true)),
+        Some('f') => expect_word(chars, "false").map(|()| JsonValue::Bool(false)),
+        Some('n') => expect_word(chars, "null").map(|()| JsonValue::Null),
+        Some(c) if c == '-' || c.is_ascii_digit() => {
+            let mut number = String::new();
+            while let Some(c) =
+                chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+            {
+                number.push(c);
+            }
+            number
+                .parse()
+                .map(JsonValue::Number)
+                .map_err(|_| format!("Nombre '{number}' incorrect"))
+        }
+        Some(c) => Err(format!("Caractère '{c}' inattendu")),
+        None => Err("Document JSON incomplet".to_string()),
+    }
+}
+
+/// Décodage d'une chaîne d'un document JSON
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    chars.next(); // '"'
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(value),
+            Some('\\') => match chars.next() {
+                Some('"') => value.push('"'),
+                Some('\\') => value.push('\\'),
+                Some('/') => value.push('/'),
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some('b') => value.push('\u{8}'),
+                Some('f') => value.push('\u{c}'),
+                Some('u') => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    let c = u32::from_str_radix(&code, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("Caractère '\\u{code}' incorrect"))?;
+                    value.push(c);
+                }
+                _ => return Err("Échappement incorrect dans une chaîne".to_string()),
+            },
+            Some(c) => value.push(c),
+            None => return Err("Chaîne non terminée".to_string()),
+        }
+    }
+}
+
+/// Décodage d'un tableau d'un document JSON
+fn parse_array(chars: &mut Peekable<Chars>) -> Result<JsonValue, String> {
+    chars.next(); // '['
+    let mut values = vec![];
+    skip_whitespaces(chars);
+    if chars.next_if_eq(&']').is_some() {
+        return Ok(JsonValue::Array(values));
+    }
+    loop {
+        values.push(parse_value(chars)?);
+        skip_whitespaces(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some(']') => return Ok(JsonValue::Array(values)),
+            _ => return Err("',' ou ']' attendu".to_string()),
+        }
+    }
+}
+
+/// Décodage d'un objet d'un document JSON
+fn parse_object(chars: &mut Peekable<Chars>) -> Result<JsonValue, String> {
+    chars.next(); // '{'
+    let mut fields = vec![];
+    skip_whitespaces(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Ok(JsonValue::Object(fields));
+    }
+    loop {
+        skip_whitespaces(chars);
+        if chars.peek() != Some(&'"') {
+            return Err("Nom de champ attendu".to_string());
+        }
+        let name = parse_string(chars)?;
+        skip_whitespaces(chars);
+        if chars.next() != Some(':') {
+            return Err(format!("':' attendu après le champ '{name}'"));
+        }
+        fields.push((name, parse_value(chars)?));
+        skip_whitespaces(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some('}') => return Ok(JsonValue::Object(fields)),
+            _ => return Err("',' ou '}' attendu".to_string()),
+        }
+    }
+}
+
+impl RawFrame {
+    /// Représentation JSON de la trame
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"state\":\"{}\",\"hex\":\"{}\"}}",
+            self.get_state(),
+            format_hex_frame(&self.encode())
+        )
+    }
+
+    /// Trame selon sa représentation JSON
+    /// # Errors
+    /// Document JSON incorrect
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value = JsonValue::parse(json)?;
+        Ok(RawFrame::new(&value.field_hex("hex")?))
+    }
+}
+
+impl DataItem {
+    /// Représentation JSON de la donnée
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"tag\":{},\"name\":\"{}\",\"format\":{},\"hex\":\"{}\",\"value\":{}}}",
+            self.tag,
+            escape_json(data_item_name(self.tag)),
+            u8::from(self.t_format),
+            format_hex_frame(&self.encode()[2..]),
+            json_value(&self.t_value)
+        )
+    }
+
+    /// Donnée selon sa représentation JSON
+    /// # Errors
+    /// Document JSON incorrect
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        Self::from_json_value(&JsonValue::parse(json)?)
+    }
+
+    /// Donnée selon sa représentation JSON décodée
+    fn from_json_value(value: &JsonValue) -> Result<Self, String> {
+        let tag = value.field_u8("tag")?;
+        let format = value.field_u8("format")?;
+        let t_format = TFormat::from(format);
+        let hex = value.field_hex("hex")?;
+        if t_format == TFormat::Unknown || t_format.nb_bytes() != hex.len() {
+            return Err(format!(
+                "Donnée 0x{tag:02X}: {} octets incorrects pour le format 0x{format:02X}",
+                hex.len()
+            ));
+        }
+        let mut octets = vec![tag, format];
+        octets.extend(hex);
+        DataItem::decode(&octets)
+            .map(|(data_item, _)| data_item)
+            .map_err(|e| format!("Donnée 0x{tag:02X}: {e}"))
+    }
+}
+
+impl DataFrame {
+    /// Représentation JSON de la trame
+    pub fn to_json(&self) -> String {
+        match self {
+            DataFrame::SimpleACK => "{\"kind\":\"ACK\"}".to_string(),
+            DataFrame::SimpleNACK => "{\"kind\":\"NACK\"}".to_string(),
+            DataFrame::Message(tag, data_items) => {
+                let items: Vec<String> = data_items.iter().map(DataItem::to_json).collect();
+                format!(
+                    "{{\"kind\":\"message\",\"tag\":{tag},\"name\":\"{}\",\"items\":[{}]}}",
+                    escape_json(name_of(*tag)),
+                    items.join(",")
+                )
+            }
+        }
+    }
+
+    /// Trame selon sa représentation JSON
+    /// # Errors
+    /// Document JSON incorrect
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value = JsonValue::parse(json)?;
+        match value.field_str("kind")? {
+            "ACK" => Ok(DataFrame::SimpleACK),
+            "NACK" => Ok(DataFrame::SimpleNACK),
+            "message" => {
+                let tag = value.field_u8("tag")?;
+                let items = match value.field("items")? {
+                    JsonValue::Array(items) => items
+                        .iter()
+                        .map(DataItem::from_json_value)
+                        .collect::<Result<Vec<_>, _>>()?,
+                    _ => return Err("Champ 'items': tableau attendu".to_string()),
+                };
+                Ok(DataFrame::Message(tag, items))
+            }
+            kind => Err(format!("Type de trame '{kind}' inconnu")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::t_data::{string_to_vec_u8, TValue};
+
+    #[test]
+    fn test_raw_frame_json() {
+        let mut raw_frame = RawFrame::new_message(0x23);
+        raw_frame
+            .try_extend_data_item(&DataItem::new(0x45, TValue::U16(1234)))
+            .unwrap();
+        let json = raw_frame.to_json();
+        assert!(json.contains("\"state\":\"OK\""));
+        assert_eq!(RawFrame::from_json(&json), Ok(raw_frame));
+        assert_eq!(RawFrame::from_json(r#"{ "hex": "" }"#), Ok(RawFrame::Empty));
+        assert!(RawFrame::from_json(r#"{"hex":"0"}"#).is_err());
+        assert!(RawFrame::from_json("[]").is_err());
+    }
+
+    #[test]
+    fn test_data_frame_json() {
+        let data_frame = DataFrame::Message(
+            0x23,
+            vec![
+                DataItem::new(0x45, TValue::VecU8(5, string_to_vec_u8("A\"BC"))),
+                DataItem::new(0x46, TValue::F32(-1.5)),
+                DataItem::new(0x47, TValue::VecU8(0, vec![])),
+            ],
+        );
+        let json = data_frame.to_json();
+        let decoded = DataFrame::from_json(&json).unwrap();
+        assert_eq!(decoded.to_json(), json);
+        assert_eq!(decoded.get_data_items()[1].t_value, TValue::F32(-1.5));
+
+        assert!(DataFrame::from_json(r#"{"kind":"ACK"}"#)
+            .unwrap()
+            .is_simple_ack());
+        assert!(DataFrame::from_json(r#"{"kind":"other"}"#).is_err());
+        assert!(DataFrame::from_json(
+            r#"{"kind":"message","tag":35,"items":[{"tag":1,"format":2,"hex":"00"}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_json_value() {
+        assert_eq!(
+            JsonValue::parse(r#" {"a": [1, -2.5e1, true, null], "b": "A\n"} "#),
+            Ok(JsonValue::Object(vec![
+                (
+                    "a".to_string(),
+                    JsonValue::Array(vec![
+                        JsonValue::Number(1.0),
+                        JsonValue::Number(-25.0),
+                        JsonValue::Bool(true),
+                        JsonValue::Null,
+                    ])
+                ),
+                ("b".to_string(), JsonValue::String("A\n".to_string())),
+            ]))
+        );
+        assert!(JsonValue::parse(r#"{"a":1"#).is_err());
+        assert!(JsonValue::parse("1 2").is_err());
+    }
+}
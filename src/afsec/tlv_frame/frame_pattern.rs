@@ -0,0 +1,165 @@
+//! Recherche et vérification des données d'une [`DataFrame`]
+//!
+//! Aides pour les tests des `middlewares` (et tout contrôle de conformité des trames) : au lieu
+//! de parcourir les `DataItem` d'une trame, un test recherche une donnée par son tag
+//! (`DataFrame::find_item`) ou vérifie d'un coup la liste des données attendues avec le motif de
+//! leur format (`DataFrame::expect_items`).
+
+use std::fmt;
+
+use super::{DataFrame, DataItem};
+use crate::t_data::TFormat;
+
+/// Motif du format d'une donnée attendue dans une [`DataFrame`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TFormatPattern {
+    /// Format quelconque
+    Any,
+
+    /// Format exact
+    Is(TFormat),
+
+    /// Format entier (signé ou non, y compris booléen)
+    Integer,
+
+    /// Chaîne d'octets de longueur quelconque
+    VecU8,
+}
+
+impl fmt::Display for TFormatPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TFormatPattern::Any => write!(f, "*"),
+            TFormatPattern::Is(t_format) => write!(f, "{t_format}"),
+            TFormatPattern::Integer => write!(f, "Entier"),
+            TFormatPattern::VecU8 => write!(f, "VecU8(*)"),
+        }
+    }
+}
+
+impl TFormatPattern {
+    /// Indique si un format correspond au motif
+    pub fn matches(&self, t_format: TFormat) -> bool {
+        match self {
+            TFormatPattern::Any => true,
+            TFormatPattern::Is(expected) => *expected == t_format,
+            TFormatPattern::Integer => !matches!(
+                t_format,
+                TFormat::F32 | TFormat::F64 | TFormat::VecU8(_) | TFormat::Unknown
+            ),
+            TFormatPattern::VecU8 => matches!(t_format, TFormat::VecU8(_)),
+        }
+    }
+}
+
+impl DataFrame {
+    /// Première donnée avec ce tag (None s'il n'y en a pas ou si ce n'est pas un message)
+    pub fn find_item(&self, tag: u8) -> Option<&DataItem> {
+        self.find_items(tag).into_iter().next()
+    }
+
+    /// Toutes les données avec ce tag, dans l'ordre de la trame
+    pub fn find_items(&self, tag: u8) -> Vec<&DataItem> {
+        match self {
+            DataFrame::Message(_, data_items) => data_items
+                .iter()
+                .filter(|data_item| data_item.tag == tag)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Vérifie que les données du message sont exactement celles attendues (tag et motif du
+    /// format, dans l'ordre)
+    /// # Errors
+    /// Description du premier écart (pas un message, donnée manquante, en trop ou différente)
+    pub fn expect_items(&self, expected: &[(u8, TFormatPattern)]) -> Result<(), String> {
+        let DataFrame::Message(_, data_items) = self else {
+            return Err(format!("{self} n'est pas un message"));
+        };
+        for (index, (tag, pattern)) in expected.iter().enumerate() {
+            let Some(data_item) = data_items.get(index) else {
+                return Err(format!(
+                    "Donnée #{index} manquante (attendu: 0x{tag:02X} {pattern})"
+                ));
+            };
+            if data_item.tag != *tag || !pattern.matches(data_item.t_format) {
+                return Err(format!(
+                    "Donnée #{index} {data_item} (attendu: 0x{tag:02X} {pattern})"
+                ));
+            }
+        }
+        match data_items.get(expected.len()) {
+            Some(data_item) => Err(format!("Donnée #{} {data_item} en trop", expected.len())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::afsec::tlv_frame::RawFrame;
+    use crate::t_data::TValue;
+
+    fn data_frame_setup() -> DataFrame {
+        let mut raw_frame = RawFrame::new_message(0x83);
+        for data_item in [
+            DataItem::new(0x31, TValue::U8(1)),
+            DataItem::new(0x33, TValue::new_vec_u8(5, &[1, 2, 0, 0, 0])),
+            DataItem::new(0x34, TValue::F32(1.5)),
+            DataItem::new(0x34, TValue::U16(7)),
+        ] {
+            raw_frame.try_extend_data_item(&data_item).unwrap();
+        }
+        DataFrame::try_from(raw_frame).unwrap()
+    }
+
+    #[test]
+    fn test_find_item() {
+        let data_frame = data_frame_setup();
+        assert_eq!(data_frame.find_item(0x31).unwrap().t_value, TValue::U8(1));
+        assert_eq!(
+            data_frame.find_item(0x34).unwrap().t_value,
+            TValue::F32(1.5)
+        );
+        assert_eq!(data_frame.find_items(0x34).len(), 2);
+        assert!(data_frame.find_item(0x99).is_none());
+        assert!(DataFrame::SimpleACK.find_item(0x31).is_none());
+    }
+
+    #[test]
+    fn test_expect_items() {
+        let data_frame = data_frame_setup();
+        assert_eq!(
+            data_frame.expect_items(&[
+                (0x31, TFormatPattern::Is(TFormat::U8)),
+                (0x33, TFormatPattern::VecU8),
+                (0x34, TFormatPattern::Any),
+                (0x34, TFormatPattern::Integer),
+            ]),
+            Ok(())
+        );
+
+        // Format différent, donnée en trop ou manquante
+        assert!(data_frame
+            .expect_items(&[
+                (0x31, TFormatPattern::Is(TFormat::U16)),
+                (0x33, TFormatPattern::VecU8),
+                (0x34, TFormatPattern::Any),
+                (0x34, TFormatPattern::Any),
+            ])
+            .is_err());
+        assert!(data_frame
+            .expect_items(&[(0x31, TFormatPattern::Integer)])
+            .unwrap_err()
+            .contains("en trop"));
+        assert!(DataFrame::SimpleNACK.expect_items(&[]).is_err());
+        assert!(DataFrame::try_from(RawFrame::new_message(0x83))
+            .unwrap()
+            .expect_items(&[(0x31, TFormatPattern::Any)])
+            .unwrap_err()
+            .contains("manquante"));
+    }
+}
@@ -11,6 +11,10 @@
 //! * `FrameState`: Identifie l'avance lors de la construction d'une `RawFrame`
 //! * `DataItem`: Donnée d'une trame avec un tag et une liste de données (elles-mêmes au format TLV)
 //! * `FrameErreur`: Situation d'erreur lors de l'encodage ou décodage des trames
+//! * `TFormatPattern`: Motif du format d'une donnée attendue (voir `DataFrame::expect_items`)
+//!
+//! Les trames et leurs données ont une représentation JSON commune aux outils (voir le module
+//! `frame_json`, `RawFrame::to_json` et `DataFrame::from_json` par exemple).
 //!
 
 mod data_frame;
@@ -19,6 +23,11 @@ pub use data_frame::DataFrame;
 mod data_item;
 pub use data_item::DataItem;
 
+mod frame_pattern;
+pub use frame_pattern::TFormatPattern;
+
+mod frame_json;
+
 mod raw_frame;
 pub use raw_frame::{FrameError, FrameState, RawFrame};
 pub use raw_frame::{ACK, ETX, NACK, STX};
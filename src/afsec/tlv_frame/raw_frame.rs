@@ -3,7 +3,7 @@
 //!
 //! Ce module est prévu pour construire une trame TLV au fur et à mesure que des octets sont reçus:
 //!
-//! ```
+//! ```ignore
 //! let frame = RawFrame::default();
 //! frame.push(octet);
 //! ```
@@ -27,7 +27,7 @@
 //! Enfin, ce module propose les primitives nécessaires pour encoder la réponse élaborée en créant
 //! un message simple ACK ou NACK ou en créant un message avec un tag de message des des `DataItem`
 //!
-//! ```
+//! ```ignore
 //! // Simple ACK
 //! let frame_ack = RawFrame::new_ack();
 //!
@@ -44,6 +44,7 @@
 use std::fmt;
 
 use super::DataItem;
+use crate::afsec::middleware::name_of;
 
 /// Longueur max des données d'un message TLV
 const RAW_FRAME_MAX_LEN: usize = 250;
@@ -173,7 +174,16 @@ pub enum RawFrame {
 
 impl fmt::Display for RawFrame {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "frame {}: {:?}", self.get_state(), self.encode())
+        match self {
+            RawFrame::Ok(tag, _, _, _) => write!(
+                f,
+                "frame {} {}: {:?}",
+                self.get_state(),
+                name_of(*tag),
+                self.encode()
+            ),
+            _ => write!(f, "frame {}: {:?}", self.get_state(), self.encode()),
+        }
     }
 }
 
@@ -392,6 +402,55 @@ impl RawFrame {
         }
     }
 
+    /// Nom de l'étape de construction de la `RawFrame` (nom de la variante)
+    fn step_name(&self) -> &'static str {
+        match self {
+            RawFrame::Empty => "Empty",
+            RawFrame::Ack => "Ack",
+            RawFrame::AckAndJunk(_) => "AckAndJunk",
+            RawFrame::Nack => "Nack",
+            RawFrame::NackAndJunk(_) => "NackAndJunk",
+            RawFrame::Stx => "Stx",
+            RawFrame::Tag(_) => "Tag",
+            RawFrame::TagLen(_, _) => "TagLen",
+            RawFrame::TagLenValue(_, _, _) => "TagLenValue",
+            RawFrame::Xor(_, _, _, _) => "Xor",
+            RawFrame::Ok(_, _, _, _) => "Ok",
+            RawFrame::OkAndJunk(_, _, _, _, _) => "OkAndJunk",
+            RawFrame::Junk(_) => "Junk",
+        }
+    }
+
+    /// Transition de construction qui a rendu la `RawFrame` inexploitable ('junk')
+    /// Retourne `<étape> + <octet> (octet n° <position>) -> <étape 'junk'>` avec le XOR attendu
+    /// si c'est le XOR qui est incorrect (None si la trame n'est pas 'junk')
+    pub fn junk_transition(&self) -> Option<String> {
+        let mut raw_frame = RawFrame::default();
+        for (index, octet) in self.encode().into_iter().enumerate() {
+            let previous = raw_frame.step_name();
+            let option_expected_xor = match &raw_frame {
+                RawFrame::TagLen(tag, 0) => Some(*tag),
+                RawFrame::TagLenValue(tag, len, values) if *len as usize == values.len() => {
+                    Some(RawFrame::calcul_xor(*tag, *len, values))
+                }
+                _ => None,
+            };
+            raw_frame.push(octet);
+            if raw_frame.get_state() == FrameState::Junk {
+                let mut transition = format!(
+                    "{previous} + 0x{octet:02X} (octet n° {}) -> {}",
+                    index + 1,
+                    raw_frame.step_name()
+                );
+                if let Some(expected_xor) = option_expected_xor {
+                    transition.push_str(&format!(" (XOR attendu 0x{expected_xor:02X})"));
+                }
+                return Some(transition);
+            }
+        }
+        None
+    }
+
     /// Tente de nettoyer une trame en retirant la partie 'junk' si possible
     #[allow(dead_code)]
     pub fn remove_junk(&mut self) {
@@ -550,6 +609,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_junk_transition() {
+        assert_eq!(RawFrame::new_ack().junk_transition(), None);
+        assert_eq!(
+            RawFrame::new(&[0x55]).junk_transition(),
+            Some("Empty + 0x55 (octet n° 1) -> Junk".to_string())
+        );
+        assert_eq!(
+            RawFrame::new(&[ACK, 0x00]).junk_transition(),
+            Some("Ack + 0x00 (octet n° 2) -> AckAndJunk".to_string())
+        );
+        assert_eq!(
+            RawFrame::new(&[STX, 0x23, 0x01, 0x45, 0x00]).junk_transition(),
+            Some("TagLenValue + 0x00 (octet n° 5) -> Junk (XOR attendu 0x67)".to_string())
+        );
+        assert_eq!(
+            RawFrame::new(&[STX, 0x23, 0x00, 0x23, 0x00]).junk_transition(),
+            Some("Xor + 0x00 (octet n° 5) -> Junk".to_string())
+        );
+    }
+
     #[test]
     fn test_remove_junk() {
         let tests: Vec<(&[u8], RawFrame)> = vec![
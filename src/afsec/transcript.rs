@@ -0,0 +1,275 @@
+//! Transcription lisible des échanges avec l'AFSEC+ (option `--transcript <DIR>`)
+//!
+//! Chaque connexion avec l'AFSEC+ (ouverture des ports par le thread de communication) crée un
+//! fichier `afsec_session_<secs>.txt` dans le répertoire choisi. Chaque trame reçue ou transmise y
+//! est décodée (`DataFrame`) avec le nom symbolique du message et de ses données (voir
+//! `id_message`) pour relire une conversation sans décoder à la main les traces en hexa :
+//!
+//! ```text
+//! [10:15:42.120] #1 -> AF_DATA_OUT (0x03)
+//!     D_DATA_ZONE (0x31) = U8(0)
+//!     D_DATA_TAG (0x33) = U16(4)
+//!     D_DATA_VALUE (0x35) = U16(123)
+//! [10:15:42.121] #1 <- IC_DATA_OUT (0x83)
+//! ```
+//!
+//! `#1` (ou `#2`) est le port de la trame (principal ou secours). Les trames inexploitables sont
+//! transcrites en hexa avec le motif du rejet. Les marqueurs de la commande `mark` de la console
+//! (module `timeline`) y sont ajoutés à chaque cycle de surveillance des notifications.
+//!
+//! Sur demande de rotation (signal `SIGUSR1` ou commande `rotate` de la console, voir le module
+//! `log_rotation`), la transcription se poursuit dans un nouveau fichier : la dernière ligne de
+//! l'ancien fichier et la 2ème ligne du nouveau fichier indiquent le fichier suivant/précédent.
+
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::{format_hex_frame, DataFrame, FrameState, RawFrame};
+use crate::afsec::middleware::{data_item_name, name_of};
+use crate::{log_rotation, sim_clock, timeline};
+
+/// Sens d'une trame transcrite
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Trame reçue de l'AFSEC+
+    Request,
+
+    /// Trame transmise à l'AFSEC+
+    Response,
+}
+
+impl Direction {
+    /// Flèche du sens de la trame
+    fn arrow(self) -> &'static str {
+        match self {
+            Direction::Request => "->",
+            Direction::Response => "<-",
+        }
+    }
+}
+
+/// Nom symbolique et code d'un tag
+fn symbolic(name: &str, tag: u8) -> String {
+    format!("{name} (0x{tag:02X})")
+}
+
+/// Transcription d'une trame (sans horodatage)
+/// `port` est l'indice du port (0 pour le port principal)
+pub fn format_frame(direction: Direction, port: usize, raw_frame: &RawFrame) -> String {
+    let header = format!("#{} {}", port + 1, direction.arrow());
+    if raw_frame.get_state() != FrameState::Ok {
+        return format!("{header} ??? {}", format_hex_frame(&raw_frame.encode()));
+    }
+    match DataFrame::try_from(raw_frame.clone()) {
+        Ok(DataFrame::SimpleACK) => format!("{header} ACK"),
+        Ok(DataFrame::SimpleNACK) => format!("{header} NACK"),
+        Ok(DataFrame::Message(tag, data_items)) => {
+            let mut ret = format!("{header} {}", symbolic(name_of(tag), tag));
+            for data_item in data_items {
+                ret += &format!(
+                    "\n    {} = {}",
+                    symbolic(data_item_name(data_item.tag), data_item.tag),
+                    data_item.t_value
+                );
+            }
+            ret
+        }
+        Err(e) => format!(
+            "{header} ??? {} ({e:?})",
+            format_hex_frame(&raw_frame.encode())
+        ),
+    }
+}
+
+/// Transcription d'une trame inexploitable reçue (sans horodatage)
+pub fn format_junk(port: usize, raw_frame: &RawFrame, rejection: &str) -> String {
+    format!(
+        "#{} -> JUNK {} ({rejection})",
+        port + 1,
+        format_hex_frame(&raw_frame.encode())
+    )
+}
+
+/// Nom d'un fichier (sans son répertoire)
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().to_string())
+}
+
+/// Fichier de transcription d'une session avec l'AFSEC+
+pub struct SessionTranscript {
+    /// Chemin du fichier
+    path: PathBuf,
+
+    /// Répertoire des fichiers de transcription
+    dir: PathBuf,
+
+    /// Nom du port de la session
+    port_name: String,
+
+    /// Numéro de la dernière rotation demandée à la création du fichier (voir `log_rotation`)
+    rotation: u32,
+
+    /// Fichier ouvert (écriture ligne par ligne pour une relecture en direct)
+    writer: LineWriter<File>,
+
+    /// Numéro du dernier marqueur (voir le module `timeline`) ajouté à la transcription
+    last_mark_num: u32,
+}
+
+impl SessionTranscript {
+    /// Création du fichier de transcription d'une nouvelle session dans le répertoire `dir`
+    /// # Errors
+    /// Erreur de création du fichier
+    pub fn create(dir: &Path, port_name: &str) -> io::Result<Self> {
+        let date = sim_clock::now();
+        let mut path = dir.join(format!("afsec_session_{}.txt", date.as_secs()));
+        let mut num = 1;
+        while path.exists() {
+            num += 1;
+            path = dir.join(format!("afsec_session_{}_{num}.txt", date.as_secs()));
+        }
+        let mut writer = LineWriter::new(File::create(&path)?);
+        writeln!(
+            writer,
+            "==== Session AFSEC+ '{port_name}' [{}] ====",
+            timeline::format_timestamp(date)
+        )?;
+        Ok(Self {
+            path,
+            dir: dir.to_path_buf(),
+            port_name: port_name.to_string(),
+            rotation: log_rotation::transcript_rotation(),
+            writer,
+            last_mark_num: timeline::last_mark_num(),
+        })
+    }
+
+    /// Poursuit la transcription dans un nouveau fichier si une rotation a été demandée depuis la
+    /// création du fichier actuel
+    /// # Errors
+    /// Erreur de création du nouveau fichier ou d'écriture de l'ancien fichier
+    fn rotate_if_requested(&mut self) -> io::Result<()> {
+        if log_rotation::transcript_rotation() == self.rotation {
+            return Ok(());
+        }
+        let mut next = Self::create(&self.dir, &self.port_name)?;
+        next.last_mark_num = self.last_mark_num;
+        writeln!(
+            next.writer,
+            "==== Suite de '{}' ====",
+            file_name(&self.path)
+        )?;
+        writeln!(
+            self.writer,
+            "==== Suite dans '{}' ====",
+            file_name(&next.path)
+        )?;
+        self.writer.flush()?;
+        *self = next;
+        Ok(())
+    }
+
+    /// Chemin du fichier de transcription
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Ajoute une ligne horodatée (heure de l'horloge simulée)
+    /// # Errors
+    /// Erreur d'écriture du fichier
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.rotate_if_requested()?;
+        writeln!(self.writer, "[{}] {line}", timeline::timestamp())
+    }
+
+    /// Ajoute les marqueurs insérés depuis le dernier appel (commande `mark` de la console)
+    /// # Errors
+    /// Erreur d'écriture du fichier
+    pub fn write_marks(&mut self) -> io::Result<()> {
+        self.rotate_if_requested()?;
+        for (num, line) in timeline::marks_after(self.last_mark_num) {
+            writeln!(self.writer, "{line}")?;
+            self.last_mark_num = num;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::afsec::middleware::{AF_ALIVE, AF_DATA_OUT, D_DATA_TAG, IC_ALIVE};
+    use crate::afsec::tlv_frame::DataItem;
+    use crate::t_data::TValue;
+
+    #[test]
+    fn test_format_frame() {
+        let mut raw_frame = RawFrame::new_message(AF_DATA_OUT);
+        raw_frame
+            .try_extend_data_item(&DataItem::new(D_DATA_TAG, TValue::U16(4)))
+            .unwrap();
+        raw_frame
+            .try_extend_data_item(&DataItem::new(0x99, TValue::U8(1)))
+            .unwrap();
+        assert_eq!(
+            format_frame(Direction::Request, 0, &raw_frame),
+            "#1 -> AF_DATA_OUT (0x03)\n    D_DATA_TAG (0x33) = U16(4)\n    ? (0x99) = U8(1)"
+        );
+        assert_eq!(
+            format_frame(Direction::Response, 1, &RawFrame::new_message(IC_ALIVE)),
+            "#2 <- IC_ALIVE (0x80)"
+        );
+        assert_eq!(
+            format_frame(Direction::Response, 0, &RawFrame::new_ack()),
+            "#1 <- ACK"
+        );
+        assert_eq!(
+            format_junk(0, &RawFrame::new(&[0x01, 0x02]), "Empty + 01"),
+            "#1 -> JUNK 01 02 (Empty + 01)"
+        );
+    }
+
+    #[test]
+    fn test_session_transcript() {
+        let dir = std::env::temp_dir().join(format!("sim_icom_transcript_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut transcript_1 = SessionTranscript::create(&dir, "COM1").unwrap();
+        let transcript_2 = SessionTranscript::create(&dir, "COM1").unwrap();
+        assert_ne!(transcript_1.path(), transcript_2.path());
+
+        transcript_1
+            .write_line(&format_frame(
+                Direction::Request,
+                0,
+                &RawFrame::new_message(AF_ALIVE),
+            ))
+            .unwrap();
+        let content = std::fs::read_to_string(transcript_1.path()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert!(lines[0].starts_with("==== Session AFSEC+ 'COM1' ["));
+        assert!(lines[1].ends_with("] #1 -> AF_ALIVE (0x00)"));
+
+        // Rotation: suite de la transcription dans un nouveau fichier
+        let old_path = transcript_1.path().to_path_buf();
+        log_rotation::request_transcript_rotation();
+        transcript_1.write_line("#1 -> ACK").unwrap();
+        assert_ne!(transcript_1.path(), old_path);
+        let content = std::fs::read_to_string(&old_path).unwrap();
+        assert_eq!(
+            content.lines().last(),
+            Some(format!("==== Suite dans '{}' ====", file_name(transcript_1.path())).as_str())
+        );
+        let content = std::fs::read_to_string(transcript_1.path()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            format!("==== Suite de '{}' ====", file_name(&old_path))
+        );
+        assert!(lines[2].ends_with("] #1 -> ACK"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
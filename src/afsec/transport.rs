@@ -0,0 +1,129 @@
+//! Transport des trames avec l'AFSEC+
+//!
+//! Avec la feature `serial` (par défaut), l'[`AfsecTransport`] est un port série `tokio_serial`.
+//! Sans cette feature (plateformes sans `libudev` ni support série, par exemple les conteneurs
+//! minimaux de la CI), l'[`AfsecTransport`] est un bouchon en mémoire : l'ouverture d'un port
+//! nommé échoue mais le reste du simulateur (database, MODBUS/TCP, console, tests) est disponible.
+
+use std::io;
+
+#[cfg(feature = "serial")]
+use tokio_serial::{SerialPortBuilderExt, SerialStream};
+
+#[cfg(not(feature = "serial"))]
+use std::collections::VecDeque;
+
+/// Vitesse de communication avec l'AFSEC+
+#[cfg(feature = "serial")]
+const BAUD_RATE: u32 = 115_200;
+
+/// Port de communication avec l'AFSEC+ (port série)
+#[cfg(feature = "serial")]
+pub struct AfsecTransport {
+    /// Port série ouvert
+    stream: SerialStream,
+}
+
+#[cfg(feature = "serial")]
+impl AfsecTransport {
+    /// Ouverture d'un port série
+    /// # Errors
+    /// Message d'erreur de `tokio_serial`
+    pub fn open(port_name: &str) -> Result<Self, String> {
+        tokio_serial::new(port_name, BAUD_RATE)
+            .open_native_async()
+            .map(|stream| AfsecTransport { stream })
+            .map_err(|e| e.to_string())
+    }
+
+    /// Lecture non bloquante (retourne le nombre d'octets lus)
+    /// # Errors
+    /// Erreur de lecture du port
+    pub fn try_read(&mut self, buff: &mut [u8]) -> io::Result<usize> {
+        self.stream.try_read(buff)
+    }
+
+    /// Écriture non bloquante (retourne le nombre d'octets écrits)
+    /// # Errors
+    /// Erreur d'écriture sur le port
+    pub fn try_write(&mut self, buff: &[u8]) -> io::Result<usize> {
+        self.stream.try_write(buff)
+    }
+}
+
+/// Port de communication avec l'AFSEC+ (bouchon en mémoire sans la feature `serial`)
+#[cfg(not(feature = "serial"))]
+#[derive(Debug, Default)]
+pub struct AfsecTransport {
+    /// Octets en attente de lecture (trames 'reçues' de l'AFSEC+)
+    input: VecDeque<u8>,
+
+    /// Octets écrits (trames 'transmises' à l'AFSEC+)
+    output: Vec<u8>,
+}
+
+#[cfg(not(feature = "serial"))]
+impl AfsecTransport {
+    /// Ouverture d'un port série : Toujours en erreur sans la feature `serial`
+    /// # Errors
+    /// Support du port série absent
+    pub fn open(port_name: &str) -> Result<Self, String> {
+        Err(format!(
+            "Port série '{port_name}' non supporté (compilé sans la feature `serial`)"
+        ))
+    }
+
+    /// Lecture non bloquante (retourne le nombre d'octets lus)
+    /// # Errors
+    /// Jamais en erreur
+    pub fn try_read(&mut self, buff: &mut [u8]) -> io::Result<usize> {
+        let n = buff.len().min(self.input.len());
+        for (byte, input) in buff.iter_mut().zip(self.input.drain(..n)) {
+            *byte = input;
+        }
+        Ok(n)
+    }
+
+    /// Écriture non bloquante (retourne le nombre d'octets écrits)
+    /// # Errors
+    /// Jamais en erreur
+    pub fn try_write(&mut self, buff: &[u8]) -> io::Result<usize> {
+        self.output.extend_from_slice(buff);
+        Ok(buff.len())
+    }
+
+    /// Ajoute des octets à lire (simulation d'une réception de l'AFSEC+)
+    pub fn push_input(&mut self, buff: &[u8]) {
+        self.input.extend(buff);
+    }
+
+    /// Retire et retourne les octets écrits
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(feature = "serial"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_transport() {
+        assert!(AfsecTransport::open("/dev/ttyUSB0").is_err());
+
+        let mut transport = AfsecTransport::default();
+        let mut buff = [0_u8; 2];
+        assert_eq!(transport.try_read(&mut buff).unwrap(), 0);
+
+        transport.push_input(&[1, 2, 3]);
+        assert_eq!(transport.try_read(&mut buff).unwrap(), 2);
+        assert_eq!(buff, [1, 2]);
+        assert_eq!(transport.try_read(&mut buff).unwrap(), 1);
+        assert_eq!(buff[0], 3);
+
+        assert_eq!(transport.try_write(&[4, 5]).unwrap(), 2);
+        assert_eq!(transport.take_output(), vec![4, 5]);
+        assert!(transport.take_output().is_empty());
+    }
+}
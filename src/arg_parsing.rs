@@ -0,0 +1,50 @@
+//! Analyse des arguments communs aux commandes de la console, aux scénarios et aux options de la
+//! ligne de commande (adresses de registres, textes entre guillemets)
+
+use crate::database::WordAddress;
+
+/// Analyse d'une adresse hexa (`@0010`, `0x0010` ou `0010`)
+pub fn parse_word_address(arg: &str) -> Option<WordAddress> {
+    let arg = arg.trim();
+    let arg = arg
+        .strip_prefix('@')
+        .or_else(|| arg.strip_prefix("0x"))
+        .or_else(|| arg.strip_prefix("0X"))
+        .unwrap_or(arg);
+    WordAddress::from_str_radix(arg, 16).ok()
+}
+
+/// Retire les guillemets éventuels autour d'un argument
+pub fn unquote(arg: &str) -> &str {
+    let arg = arg.trim();
+    if arg.len() >= 2 && arg.starts_with('"') && arg.ends_with('"') {
+        &arg[1..arg.len() - 1]
+    } else {
+        arg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_word_address() {
+        assert_eq!(parse_word_address("@0010"), Some(0x0010));
+        assert_eq!(parse_word_address(" 0x7FFF "), Some(0x7FFF));
+        assert_eq!(parse_word_address("0X00ab"), Some(0x00AB));
+        assert_eq!(parse_word_address("1F"), Some(0x001F));
+        assert_eq!(parse_word_address("@"), None);
+        assert_eq!(parse_word_address("@G000"), None);
+        assert_eq!(parse_word_address("@10000"), None);
+    }
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote("\"abc def\""), "abc def");
+        assert_eq!(unquote("  \"abc\"  "), "abc");
+        assert_eq!(unquote("abc"), "abc");
+        assert_eq!(unquote("\""), "\"");
+        assert_eq!(unquote("\"\""), "");
+    }
+}
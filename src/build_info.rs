@@ -0,0 +1,46 @@
+//! Identification du build du simulateur
+//!
+//! La version du simulateur, le hash git et la date du build sont publiés au démarrage dans des
+//! tags propres au simulateur (voir le module `sim_tags` de la `Database`) et tracés par une
+//! bannière structurée (`BUILD: clé=valeur ...`) pour que les écrans de supervision et les bancs
+//! automatisés vérifient le build déployé.
+//!
+//! Le hash git et la date du build sont fournis par l'environnement de compilation (variables
+//! `SIM_ICOM_GIT_HASH` et `SIM_ICOM_BUILD_DATE`, par exemple
+//! `SIM_ICOM_GIT_HASH=$(git rev-parse --short HEAD) SIM_ICOM_BUILD_DATE=$(date -I) cargo build`).
+
+/// Nom du simulateur
+pub const NAME: &str = env!("CARGO_PKG_NAME");
+
+/// Version du simulateur
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Hash git du build (`unknown` si non fourni lors de la compilation)
+pub const GIT_HASH: &str = match option_env!("SIM_ICOM_GIT_HASH") {
+    Some(git_hash) => git_hash,
+    None => "unknown",
+};
+
+/// Date du build (`unknown` si non fournie lors de la compilation)
+pub const BUILD_DATE: &str = match option_env!("SIM_ICOM_BUILD_DATE") {
+    Some(build_date) => build_date,
+    None => "unknown",
+};
+
+/// Bannière structurée du build (une ligne `BUILD: clé=valeur ...`)
+pub fn banner() -> String {
+    format!("BUILD: name={NAME} version={VERSION} git_hash={GIT_HASH} build_date={BUILD_DATE}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_banner() {
+        let banner = banner();
+        assert!(banner.starts_with("BUILD: name="));
+        assert!(banner.contains(&format!(" version={VERSION} ")));
+        assert!(!VERSION.is_empty());
+    }
+}
@@ -0,0 +1,441 @@
+//! Capture déclenchée du trafic du simulateur (option `--capture-trigger`)
+//!
+//! Comme un analyseur logique, le simulateur mémorise en permanence les derniers événements
+//! (trames de la liaison série avec l'AFSEC+, trames MODBUS/TCP et modifications des tags) dans
+//! un buffer tournant. Lorsqu'un événement correspond à une condition de déclenchement, la
+//! capture est 'figée' : les événements de la fenêtre `--capture-window` qui précède et qui suit
+//! le déclenchement sont écrits dans un fichier `capture_<secs>.txt` du répertoire
+//! `--capture-dir` :
+//!
+//! ```text
+//! ==== Capture 'junk' [10:15:42.120] (fenêtre 10s) ====
+//! [10:15:41.980] SERIAL #1 -> AF_ALIVE (0x00)
+//! [10:15:41.981] SERIAL #1 <- ACK
+//! ==== TRIGGER junk ====
+//! [10:15:42.120] SERIAL #1 -> JUNK 01 02 (Empty + 01)
+//! [10:15:42.500] MODBUS -> 00 01 00 00 00 06 01 03 00 10 00 02
+//! ```
+//!
+//! Conditions de déclenchement (option répétable) :
+//!
+//! * `junk` : Trame inexploitable reçue de l'AFSEC+
+//! * `modbus-exception` : Réponse d'exception MODBUS/TCP
+//! * `tag=<motif>` : Modification d'un tag du motif (ex: `tag=5/0F45`, voir [`IdTagPattern`])
+//!
+//! Un déclenchement pendant la fenêtre qui suit un déclenchement précédent est ignoré (ses
+//! événements font partie de la capture en cours).
+//!
+//! Sur demande de rotation (signal `SIGUSR2` ou commande `rotate` de la console, voir le module
+//! `log_rotation`), la capture en cours est écrite sans attendre la fin de sa fenêtre.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::database::{IdTag, IdTagPattern};
+use crate::{sim_clock, timeline};
+
+/// Fenêtre par défaut (en secondes) avant et après le déclenchement
+pub const DEFAULT_CAPTURE_WINDOW_SECS: u64 = 10;
+
+/// Nombre max. d'événements mémorisés (les plus anciens sont perdus au delà)
+const CAPTURE_MAX_EVENTS: usize = 100_000;
+
+/// Temps de cycle (en millisecondes) de la surveillance de la fin des captures en cours
+const CAPTURE_CYCLE_MSECS: u64 = 200;
+
+/// Type d'un événement capturé
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureKind {
+    /// Trame de la liaison série avec l'AFSEC+
+    SerialFrame,
+
+    /// Trame inexploitable reçue de l'AFSEC+
+    SerialJunk,
+
+    /// Trame MODBUS/TCP (requête ou réponse)
+    ModbusFrame,
+
+    /// Réponse d'exception MODBUS/TCP
+    ModbusException,
+
+    /// Modification d'un tag
+    TagChange(IdTag),
+
+    /// Marqueur temporel (voir le module `timeline`)
+    Mark,
+}
+
+impl CaptureKind {
+    /// Source de l'événement (préfixe des lignes de la capture)
+    fn source(self) -> &'static str {
+        match self {
+            CaptureKind::SerialFrame | CaptureKind::SerialJunk => "SERIAL",
+            CaptureKind::ModbusFrame | CaptureKind::ModbusException => "MODBUS",
+            CaptureKind::TagChange(_) => "TAG",
+            CaptureKind::Mark => "MARK",
+        }
+    }
+}
+
+/// Condition de déclenchement d'une capture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureTrigger {
+    /// Trame inexploitable reçue de l'AFSEC+
+    Junk,
+
+    /// Réponse d'exception MODBUS/TCP
+    ModbusException,
+
+    /// Modification d'un tag du motif
+    TagChange(IdTagPattern),
+}
+
+impl fmt::Display for CaptureTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CaptureTrigger::Junk => write!(f, "junk"),
+            CaptureTrigger::ModbusException => write!(f, "modbus-exception"),
+            CaptureTrigger::TagChange(pattern) => write!(f, "tag={pattern}"),
+        }
+    }
+}
+
+impl FromStr for CaptureTrigger {
+    type Err = String;
+
+    /// Accepte `junk`, `modbus-exception` ou `tag=<motif>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "junk" => Ok(CaptureTrigger::Junk),
+            "modbus-exception" => Ok(CaptureTrigger::ModbusException),
+            trigger => match trigger.strip_prefix("tag=") {
+                Some(pattern) => Ok(CaptureTrigger::TagChange(IdTagPattern::from_str(pattern)?)),
+                None => Err(format!(
+                    "Déclenchement de capture '{s}' inconnu (junk, modbus-exception ou tag=<motif>)"
+                )),
+            },
+        }
+    }
+}
+
+impl CaptureTrigger {
+    /// Indique si un événement déclenche la capture
+    fn matches(self, kind: CaptureKind) -> bool {
+        match (self, kind) {
+            (CaptureTrigger::Junk, CaptureKind::SerialJunk)
+            | (CaptureTrigger::ModbusException, CaptureKind::ModbusException) => true,
+            (CaptureTrigger::TagChange(pattern), CaptureKind::TagChange(id_tag)) => {
+                pattern.matches(id_tag)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Événement mémorisé
+#[derive(Clone, Debug)]
+struct CaptureEvent {
+    /// Date de l'événement (pour la fenêtre de capture)
+    instant: Instant,
+
+    /// Horodatage `HH:MM:SS.mmm` de l'horloge simulée
+    timestamp: String,
+
+    /// Type de l'événement
+    kind: CaptureKind,
+
+    /// Description de l'événement
+    line: String,
+}
+
+/// Déclenchement en cours
+#[derive(Clone, Debug)]
+struct Triggered {
+    /// Date du déclenchement
+    instant: Instant,
+
+    /// Horodatage `HH:MM:SS.mmm` du déclenchement
+    timestamp: String,
+
+    /// Secondes (horloge simulée) du déclenchement pour le nom du fichier
+    secs: u64,
+
+    /// Condition qui a déclenché la capture
+    trigger: CaptureTrigger,
+}
+
+/// Buffer tournant des événements et déclenchement des captures
+#[derive(Debug)]
+pub struct CaptureBuffer {
+    /// Conditions de déclenchement
+    triggers: Vec<CaptureTrigger>,
+
+    /// Fenêtre avant et après le déclenchement
+    window: Duration,
+
+    /// Répertoire des fichiers de capture
+    dir: PathBuf,
+
+    /// Derniers événements (dans l'ordre chronologique)
+    events: VecDeque<CaptureEvent>,
+
+    /// Déclenchement en cours (None si pas de capture en cours)
+    option_triggered: Option<Triggered>,
+}
+
+/// Capture figée à écrire dans un fichier
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capture {
+    /// Chemin du fichier
+    pub path: PathBuf,
+
+    /// Contenu du fichier
+    pub content: String,
+}
+
+impl CaptureBuffer {
+    /// Constructeur
+    pub fn new(triggers: Vec<CaptureTrigger>, window: Duration, dir: &Path) -> Self {
+        Self {
+            triggers,
+            window,
+            dir: dir.to_path_buf(),
+            events: VecDeque::new(),
+            option_triggered: None,
+        }
+    }
+
+    /// Enregistre un événement à la date `now`
+    /// Retourne la capture terminée par cet événement (si fin de la fenêtre d'une capture en
+    /// cours)
+    pub fn record(&mut self, now: Instant, kind: CaptureKind, line: String) -> Option<Capture> {
+        let option_capture = self.poll(now);
+        let timestamp = timeline::timestamp();
+        if self.option_triggered.is_none() {
+            if let Some(trigger) = self.triggers.iter().find(|trigger| trigger.matches(kind)) {
+                self.option_triggered = Some(Triggered {
+                    instant: now,
+                    timestamp: timestamp.clone(),
+                    secs: sim_clock::now().as_secs(),
+                    trigger: *trigger,
+                });
+            }
+        }
+        if self.events.len() >= CAPTURE_MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(CaptureEvent {
+            instant: now,
+            timestamp,
+            kind,
+            line,
+        });
+        option_capture
+    }
+
+    /// Oubli des événements trop anciens et fin de la capture en cours à la date `now`
+    /// Retourne la capture terminée (si fin de la fenêtre)
+    pub fn poll(&mut self, now: Instant) -> Option<Capture> {
+        let window = self.window;
+        let option_capture = self
+            .option_triggered
+            .take_if(|triggered| now.saturating_duration_since(triggered.instant) >= window)
+            .map(|triggered| self.freeze(&triggered));
+        let oldest = match &self.option_triggered {
+            Some(triggered) => triggered.instant,
+            None => now,
+        };
+        while let Some(event) = self.events.front() {
+            if oldest.saturating_duration_since(event.instant) <= self.window {
+                break;
+            }
+            self.events.pop_front();
+        }
+        option_capture
+    }
+
+    /// Fin immédiate de la capture en cours (sans attendre la fin de sa fenêtre)
+    /// Retourne la capture terminée (None si pas de capture en cours)
+    pub fn flush(&mut self) -> Option<Capture> {
+        self.option_triggered
+            .take()
+            .map(|triggered| self.freeze(&triggered))
+    }
+
+    /// Capture figée des événements de la fenêtre d'un déclenchement
+    fn freeze(&self, triggered: &Triggered) -> Capture {
+        let mut content = format!(
+            "==== Capture '{}' [{}] (fenêtre {}s) ====\n",
+            triggered.trigger,
+            triggered.timestamp,
+            self.window.as_secs()
+        );
+        let mut is_trigger_written = false;
+        for event in &self.events {
+            if event.instant.saturating_duration_since(triggered.instant) > self.window
+                || triggered.instant.saturating_duration_since(event.instant) > self.window
+            {
+                continue;
+            }
+            if !is_trigger_written
+                && event.instant >= triggered.instant
+                && triggered.trigger.matches(event.kind)
+            {
+                content += &format!("==== TRIGGER {} ====\n", triggered.trigger);
+                is_trigger_written = true;
+            }
+            content += &format!(
+                "[{}] {} {}\n",
+                event.timestamp,
+                event.kind.source(),
+                event.line
+            );
+        }
+        let mut path = self.dir.join(format!("capture_{}.txt", triggered.secs));
+        let mut num = 1;
+        while path.exists() {
+            num += 1;
+            path = self
+                .dir
+                .join(format!("capture_{}_{num}.txt", triggered.secs));
+        }
+        Capture { path, content }
+    }
+}
+
+impl Capture {
+    /// Écriture du fichier de la capture
+    /// # Errors
+    /// Erreur de création ou d'écriture du fichier
+    pub fn write(&self) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(&self.path)?);
+        writer.write_all(self.content.as_bytes())?;
+        writer.flush()
+    }
+}
+
+/// Buffer de capture du simulateur (None si pas de capture)
+static CAPTURE_BUFFER: Mutex<Option<CaptureBuffer>> = Mutex::new(None);
+
+/// Active la capture déclenchée du simulateur
+pub fn configure(capture_buffer: CaptureBuffer) {
+    *CAPTURE_BUFFER.lock().unwrap() = Some(capture_buffer);
+}
+
+/// Enregistre un événement si la capture est active
+/// `line` n'est évaluée que si la capture est active
+pub fn record<F: FnOnce() -> String>(kind: CaptureKind, line: F) {
+    let option_capture = {
+        let mut option_capture_buffer = CAPTURE_BUFFER.lock().unwrap();
+        let Some(capture_buffer) = option_capture_buffer.as_mut() else {
+            return;
+        };
+        capture_buffer.record(Instant::now(), kind, line())
+    };
+    if let Some(capture) = option_capture {
+        write_capture(&capture);
+    }
+}
+
+/// Écriture d'une capture terminée
+fn write_capture(capture: &Capture) {
+    match capture.write() {
+        Ok(()) => println!("Capture: '{}' written", capture.path.display()),
+        Err(e) => println!("Capture: Erreur écriture '{}': {e}", capture.path.display()),
+    }
+}
+
+/// Écrit immédiatement la capture en cours (rotation, voir le module `log_rotation`)
+pub fn rotate() {
+    let option_capture = match CAPTURE_BUFFER.lock().unwrap().as_mut() {
+        Some(capture_buffer) => capture_buffer.flush(),
+        None => return,
+    };
+    if let Some(capture) = option_capture {
+        write_capture(&capture);
+    }
+}
+
+/// Routine d'un thread qui termine les captures en cours à la fin de leur fenêtre (même en
+/// l'absence de nouveaux événements)
+pub async fn capture_process() {
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(CAPTURE_CYCLE_MSECS)).await;
+
+        let option_capture = match CAPTURE_BUFFER.lock().unwrap().as_mut() {
+            Some(capture_buffer) => capture_buffer.poll(Instant::now()),
+            None => return,
+        };
+        if let Some(capture) = option_capture {
+            write_capture(&capture);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_trigger() {
+        for spec in ["junk", "modbus-exception", "tag=5/0F45:*:*:*"] {
+            assert_eq!(CaptureTrigger::from_str(spec).unwrap().to_string(), spec);
+        }
+        assert!(CaptureTrigger::from_str("exception").is_err());
+        assert!(CaptureTrigger::from_str("tag=5").is_err());
+
+        let trigger = CaptureTrigger::from_str("tag=5/0F45").unwrap();
+        assert!(trigger.matches(CaptureKind::TagChange(IdTag::new(5, 0x0F45, [1, 0, 0]))));
+        assert!(!trigger.matches(CaptureKind::TagChange(IdTag::new(4, 0x0F45, [1, 0, 0]))));
+        assert!(!CaptureTrigger::Junk.matches(CaptureKind::SerialFrame));
+    }
+
+    #[test]
+    fn test_capture_buffer() {
+        let dir = std::env::temp_dir();
+        let window = Duration::from_secs(10);
+        let mut capture_buffer = CaptureBuffer::new(vec![CaptureTrigger::Junk], window, &dir);
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        assert!(capture_buffer
+            .record(at(0), CaptureKind::SerialFrame, "too old".to_string())
+            .is_none());
+        capture_buffer.record(at(15), CaptureKind::ModbusFrame, "before".to_string());
+        capture_buffer.record(at(20), CaptureKind::SerialJunk, "junk 1".to_string());
+        capture_buffer.record(at(25), CaptureKind::SerialJunk, "junk 2".to_string());
+        assert!(capture_buffer.poll(at(29)).is_none());
+        let capture = capture_buffer.poll(at(30)).unwrap();
+        assert!(capture.path.starts_with(&dir));
+
+        let lines: Vec<&str> = capture.content.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("==== Capture 'junk' ["));
+        assert!(lines[1].ends_with("] MODBUS before"));
+        assert_eq!(lines[2], "==== TRIGGER junk ====");
+        assert!(lines[3].ends_with("] SERIAL junk 1"));
+        assert!(lines[4].ends_with("] SERIAL junk 2"));
+
+        // Nouveau déclenchement après la fin de la capture précédente
+        assert!(capture_buffer.poll(at(40)).is_none());
+        capture_buffer.record(at(41), CaptureKind::SerialJunk, "junk 3".to_string());
+        let capture = capture_buffer
+            .record(at(60), CaptureKind::SerialFrame, "after".to_string())
+            .unwrap();
+        assert_eq!(capture.content.lines().count(), 3);
+
+        // Rotation: capture écrite avant la fin de sa fenêtre
+        assert!(capture_buffer.flush().is_none());
+        capture_buffer.record(at(61), CaptureKind::SerialJunk, "junk 4".to_string());
+        let capture = capture_buffer.flush().unwrap();
+        assert!(capture.content.ends_with("] SERIAL junk 4\n"));
+        assert!(capture_buffer.poll(at(80)).is_none());
+    }
+}
@@ -1,6 +1,18 @@
 //! Gestion de la configuration selon les arguments de la ligne de commande
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use sim_icom::afsec::{
+    DEFAULT_CONVERSATION_TIMEOUT, DEFAULT_MAX_JUNK_PER_SEC, DEFAULT_PROBE_PERIOD,
+    DEFAULT_PROTOCOL_STATS_PERIOD_SECS, DEFAULT_RETRY_TIMEOUT, DEFAULT_TEST_VECTORS_FILENAME,
+};
+use sim_icom::capture::DEFAULT_CAPTURE_WINDOW_SECS;
+use sim_icom::database::DEFAULT_JUNK_CAPTURE_CAPACITY;
+use sim_icom::federation::DEFAULT_FEDERATION_PERIOD_MSECS;
+use sim_icom::server_modbus_tcp::DEFAULT_MODBUS_MAX_CONCURRENCY;
+use sim_icom::shadow::DEFAULT_SHADOW_PERIOD_SECS;
+use sim_icom::snmp::DEFAULT_SNMP_COMMUNITY;
+use sim_icom::test_summary::SummaryFormat;
 
 /// Simulateur ICOM (c)ALMA - 2023
 ///
@@ -12,19 +24,74 @@ use clap::Parser;
 ///
 /// L'outil est également un serveur MODBUS/TCP pour interagir avec le contenu de la database.
 #[derive(Parser)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub struct CommandArgs {
+    /// Commande hors simulation
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Nom du port série pour communiquer avec l'AFSEC+
     /// ('fake' pour simuler une communication inexistante)
-    pub port_name: String,
+    #[arg(required = true)]
+    pub port_name: Option<String>,
+
+    /// Nom du port série de secours pour communiquer avec l'AFSEC+ (câblage redondant: le
+    /// simulateur répond sur les 2 ports)
+    #[arg(long)]
+    pub standby_port: Option<String>,
 
     /// Fichier descriptif de la database au format .csv
     #[arg(short, long, default_value_t = String::from("database.csv"))]
     pub filename: String,
 
+    /// Configuration des colonnes du fichier .csv (ex: 'sep=comma,id=1,address=0,label=Libellé')
+    /// Champs: sep, quote, id, address, format, unity, label, rw, zone, default, group, priority,
+    /// min, max
+    #[arg(long)]
+    pub csv_columns: Option<String>,
+
+    /// Ignore les lignes incorrectes du fichier .csv (sinon la première erreur stoppe l'application)
+    #[arg(long)]
+    pub csv_lenient: bool,
+
+    /// Fichier de rapport des erreurs du fichier .csv (avec --csv-lenient)
+    #[arg(long)]
+    pub csv_report: Option<String>,
+
+    /// Fichier d'état des valeurs des tags restauré au démarrage (sauvegardé par la commande
+    /// 'save' de la console)
+    #[arg(long)]
+    pub state: Option<String>,
+
+    /// Clone les tags d'une zone vers une zone avec un décalage des adresses (en mots) et un
+    /// incrément du premier indice (ex: '--clone-zone 1:2:0x0100:1', option répétable)
+    #[arg(long)]
+    pub clone_zone: Vec<String>,
+
+    /// Fichier du contexte des conversations avec l'AFSEC+ (blocs PACK_IN, index des journaux et
+    /// modifications DATA_IN en attente) sauvegardé sur ctrl+C et restauré au démarrage
+    #[arg(long)]
+    pub context_state: Option<String>,
+
+    /// Fichier des compteurs des conversations avec l'AFSEC+ (INIT, PACK_OUT, PACK_IN, DATA_OUT,
+    /// DATA_IN, ...) repris au démarrage et sauvegardé périodiquement et sur ctrl+C
+    #[arg(long)]
+    pub protocol_stats: Option<String>,
+
+    /// Période (en secondes) de la sauvegarde des compteurs des conversations
+    #[arg(long, default_value_t = DEFAULT_PROTOCOL_STATS_PERIOD_SECS)]
+    pub protocol_stats_period: u64,
+
     /// Numéro du port MODBUS/TCP
     #[arg(short, long, default_value_t = 502)]
     pub port: usize,
 
+    /// Adresse IPv4 ou IPv6 d'écoute du serveur MODBUS/TCP, avec ou sans port (ex: '192.168.1.10',
+    /// '[fe80::1]:5020'), option répétable pour plusieurs interfaces (toutes les interfaces IPv4
+    /// par défaut)
+    #[arg(long)]
+    pub bind: Vec<String>,
+
     /// Timer (en millisecondes) pour le watcher (0 pour inhiber le watcher)
     #[arg(short, long, default_value_t = 1000)]
     pub watcher: u64,
@@ -32,6 +99,393 @@ pub struct CommandArgs {
     /// Debug show level (0: None, 1: Some, 2 ou +: All)
     #[arg(short, long, default_value_t = 1)]
     pub debug: u8,
+
+    /// Active le cache des lectures MODBUS/TCP 'ReadHoldingRegisters'
+    #[arg(long)]
+    pub modbus_cache: bool,
+
+    /// Nombre max. de requêtes MODBUS/TCP 'pipelinées' en attente de réponse sur une connexion
+    /// (exception 'SERVER DEVICE BUSY' au delà, 0 pour une profondeur illimitée)
+    #[arg(long, default_value_t = 0)]
+    pub modbus_max_pipelining: usize,
+
+    /// Nombre max. de requêtes MODBUS/TCP traitées simultanément, toutes connexions confondues
+    /// (0 pour un nombre illimité)
+    #[arg(long, default_value_t = DEFAULT_MODBUS_MAX_CONCURRENCY)]
+    pub modbus_max_concurrency: usize,
+
+    /// Délai (en secondes) sans échange avant les sondes 'keepalive' TCP des connexions MODBUS/TCP
+    /// pour détecter les clients déconnectés brutalement (0 pour désactiver)
+    #[arg(long, default_value_t = 30)]
+    pub modbus_keepalive: u64,
+
+    /// Écritures MODBUS/TCP à des adresses sans tag: 'raw' (écriture sans notification),
+    /// 'strict' (exception ILLEGAL DATA ADDRESS) ou 'permissive' (création d'un tag anonyme U16)
+    #[arg(long, default_value_t = String::from("raw"))]
+    pub modbus_undefined_writes: String,
+
+    /// Traite les requêtes MODBUS/TCP diffusées (unité 0) comme sur une liaison série: les
+    /// écritures sont appliquées sans réponse et les autres requêtes sont ignorées
+    #[arg(long)]
+    pub modbus_broadcast: bool,
+
+    /// Réseau (ex: '192.168.1.0/24') ou adresse IP des clients MODBUS/TCP autorisés (tous les
+    /// clients si non spécifié), option répétable
+    #[arg(long)]
+    pub modbus_allow: Vec<String>,
+
+    /// Réseau (ex: '10.0.0.0/8') ou adresse IP des clients MODBUS/TCP refusés, option répétable
+    #[arg(long)]
+    pub modbus_deny: Vec<String>,
+
+    /// Adresse IPv4 ou IPv6 d'écoute d'un serveur MODBUS/UDP, avec ou sans port (port de
+    /// l'option --port par défaut, ex: '0.0.0.0:5020')
+    #[arg(long)]
+    pub modbus_udp: Option<String>,
+
+    /// Tramage des requêtes MODBUS/UDP: 'rtu' (unité, PDU et CRC) ou 'mbap' (comme MODBUS/TCP)
+    #[arg(long, default_value_t = String::from("rtu"))]
+    pub modbus_udp_framing: String,
+
+    /// Nombre de trames inexploitables ('junk') par seconde reçues de l'AFSEC+ au delà duquel
+    /// elles ne sont plus tracées individuellement
+    #[arg(long, default_value_t = DEFAULT_MAX_JUNK_PER_SEC)]
+    pub junk_max_rate: u32,
+
+    /// Durée (en millisecondes) pendant laquelle le simulateur ne répond plus à l'AFSEC+ au delà
+    /// de --junk-max-rate trames inexploitables par seconde (0 pour toujours répondre)
+    #[arg(long, default_value_t = 0)]
+    pub junk_silence: u64,
+
+    /// Nombre de dernières trames inexploitables conservées pour la commande 'junk' de la
+    /// console (0 pour ne rien conserver)
+    #[arg(long, default_value_t = DEFAULT_JUNK_CAPTURE_CAPACITY)]
+    pub junk_capture: usize,
+
+    /// Délai d'inactivité (en millisecondes) de l'AFSEC+ au delà duquel une conversation en cours
+    /// est abandonnée sans attendre le prochain AF_INIT (0 pour ne jamais abandonner)
+    #[arg(long, default_value_t = DEFAULT_CONVERSATION_TIMEOUT)]
+    pub conversation_timeout: u64,
+
+    /// Délai de réponse (en millisecondes) aux trames de l'AFSEC+ : fixe ('10') ou tiré dans une
+    /// plage ('5-15' comme l'ICOM réel), 0 pour répondre immédiatement
+    #[arg(long, default_value_t = String::from("0"))]
+    pub response_delay: String,
+
+    /// Nombre max. de retransmissions consécutives d'une trame sans réponse de l'AFSEC+ avant de
+    /// considérer le lien coupé (0 pour ne pas surveiller le lien)
+    #[arg(long, default_value_t = 0)]
+    pub max_retries: u32,
+
+    /// Délai (en millisecondes) sans trame valide de l'AFSEC+ après une transmission au delà
+    /// duquel la trame est retransmise
+    #[arg(long, default_value_t = DEFAULT_RETRY_TIMEOUT)]
+    pub retry_timeout: u64,
+
+    /// Période (en secondes) des tentatives de rétablissement d'un lien coupé avec l'AFSEC+
+    #[arg(long, default_value_t = DEFAULT_PROBE_PERIOD)]
+    pub probe_period: u64,
+
+    /// Exécution de la communication avec l'AFSEC+ sur un thread dédié (runtime séparé des
+    /// autres tâches du simulateur)
+    #[arg(long)]
+    pub afsec_thread: bool,
+
+    /// Priorité temps réel (SCHED_FIFO, 1 à 99, Linux) du thread dédié à la communication avec
+    /// l'AFSEC+ (implique '--afsec-thread')
+    #[arg(long)]
+    pub afsec_priority: Option<i32>,
+
+    /// Cœur (à partir de 0, Linux) auquel est attaché le thread dédié à la communication avec
+    /// l'AFSEC+ (implique '--afsec-thread')
+    #[arg(long)]
+    pub afsec_core: Option<usize>,
+
+    /// Répertoire des fichiers de transcription lisible (trames décodées) de chaque session avec
+    /// l'AFSEC+
+    #[arg(long)]
+    pub transcript: Option<String>,
+
+    /// Condition de déclenchement d'une capture du trafic autour d'un événement ('junk',
+    /// 'modbus-exception' ou 'tag=<motif>', ex: 'tag=5/0F45'), option répétable
+    #[arg(long)]
+    pub capture_trigger: Vec<String>,
+
+    /// Fenêtre (en secondes) capturée avant et après le déclenchement
+    #[arg(long, default_value_t = DEFAULT_CAPTURE_WINDOW_SECS)]
+    pub capture_window: u64,
+
+    /// Répertoire des fichiers de capture
+    #[arg(long, default_value_t = String::from("."))]
+    pub capture_dir: String,
+
+    /// Fichier du profil du firmware ICOM émulé (particularités '<clé> = <valeur>' relevées sur
+    /// l'ICOM réel, comportement historique du simulateur par défaut)
+    #[arg(long)]
+    pub firmware: Option<String>,
+
+    /// Réponse à AF_ALIVE quand personne n'a rien à dire (ack, ic_alive ou ic_alive_status),
+    /// imposée quel que soit le profil du firmware
+    #[arg(long)]
+    pub alive_answer: Option<String>,
+
+    /// Réponse à une trame de l'AFSEC+ dont le contenu ne peut pas être décodé (silent, nack ou
+    /// nack-after=<n>), imposée quel que soit le profil du firmware
+    #[arg(long)]
+    pub malformed_answer: Option<String>,
+
+    /// Refus (NACK) d'un message AF_* selon le mode de fonctionnement de l'AFSEC+ (D_MODE_AFSEC)
+    /// (ex: '--mode-refuse 2=PACK_IN', option répétable)
+    #[arg(long)]
+    pub mode_refuse: Vec<String>,
+
+    /// Rampe (vitesse de variation max. en unités par seconde) d'un tag numérique
+    /// (ex: '--ramp @0010=2.5', option répétable)
+    #[arg(long)]
+    pub ramp: Vec<String>,
+
+    /// Historique (nombre de valeurs horodatées conservées) d'un tag
+    /// (ex: '--history @0010=100', option répétable)
+    #[arg(long)]
+    pub history: Vec<String>,
+
+    /// Âge max. (en secondes) de la valeur d'un tag défini à une adresse (hexa) au delà duquel le
+    /// tag est signalé périmé (ex: '--max-age @0010=5', option répétable)
+    #[arg(long)]
+    pub max_age: Vec<String>,
+
+    /// Durée (en secondes) après laquelle un tag défini à une adresse (hexa) écrit à une valeur
+    /// non nulle est remis à 0, comme un bit de commande de l'ICOM (ex: '--pulse @0010=0.5',
+    /// option répétable)
+    #[arg(long)]
+    pub pulse: Vec<String>,
+
+    /// Période (en secondes) d'écriture de valeurs aléatoires dans les tags modifiables (dans
+    /// leur plage déclarée par les colonnes 'min' et 'max' du fichier .csv)
+    #[arg(long)]
+    pub randomize: Option<u64>,
+
+    /// Groupe de tags soumis aux valeurs aléatoires (option répétable, tous les groupes par
+    /// défaut)
+    #[arg(long)]
+    pub randomize_group: Vec<String>,
+
+    /// Zone des tags soumis aux valeurs aléatoires (option répétable, toutes les zones par
+    /// défaut)
+    #[arg(long)]
+    pub randomize_zone: Vec<u8>,
+
+    /// Profil journalier ou hebdomadaire (fichier .csv des valeurs selon l'heure) d'un tag
+    /// numérique défini à une adresse (hexa) (ex: '--profile @0100=conso.csv', option répétable)
+    #[arg(long)]
+    pub profile: Vec<String>,
+
+    /// Décalage (en minutes) de l'heure locale des profils par rapport à l'heure UTC
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    pub profile_utc_offset: i64,
+
+    /// Fichier d'un scénario de test exécuté au démarrage (séquences en parallèle avec des
+    /// points de synchronisation et des attentes conditionnelles)
+    #[arg(long)]
+    pub scenario: Option<String>,
+
+    /// Fichier du rapport (JSON) des vérifications du scénario de test
+    #[arg(long)]
+    pub scenario_report: Option<String>,
+
+    /// Arrêt du simulateur à la fin du scénario de test, avec un code de sortie non nul si une
+    /// vérification est en échec
+    #[arg(long)]
+    pub scenario_exit: bool,
+
+    /// Fichier du rapport de synthèse écrit à l'arrêt du simulateur (durée, trames, requêtes
+    /// MODBUS, notifications, fautes injectées et vérifications du scénario)
+    #[arg(long)]
+    pub summary: Option<String>,
+
+    /// Format du rapport de synthèse '--summary' (json ou md)
+    #[arg(long, default_value_t = SummaryFormat::default().to_string())]
+    pub summary_format: String,
+
+    /// ICOM de référence ('<hôte>:<port>' MODBUS/TCP) comparé périodiquement avec l'état du
+    /// simulateur pour les plages de registres '--shadow-range'
+    #[arg(long)]
+    pub shadow: Option<String>,
+
+    /// Plage de registres (adresses en hexa) comparée avec l'ICOM de référence
+    /// (ex: '--shadow-range @0010-@001F', option répétable)
+    #[arg(long)]
+    pub shadow_range: Vec<String>,
+
+    /// Période (en secondes) de la comparaison avec l'ICOM de référence
+    #[arg(long, default_value_t = DEFAULT_SHADOW_PERIOD_SECS)]
+    pub shadow_period: u64,
+
+    /// Groupe multicast ('<adresse IPv4>:<port>', ex: '239.255.0.1:5021') du partage de plages de
+    /// registres avec les autres instances du simulateur d'un même site
+    #[arg(long)]
+    pub federation: Option<String>,
+
+    /// Plage de registres (adresses en hexa) publiée aux autres instances du simulateur
+    /// (ex: '--federation-range @0010-@001F', option répétable)
+    #[arg(long)]
+    pub federation_range: Vec<String>,
+
+    /// Plage de registres (adresses en hexa) acceptée des autres instances du simulateur
+    /// (ex: '--federation-accept @0200-@02FF', option répétable, toutes les plages par défaut)
+    #[arg(long)]
+    pub federation_accept: Vec<String>,
+
+    /// Période (en millisecondes) de la publication des plages de registres partagées
+    #[arg(long, default_value_t = DEFAULT_FEDERATION_PERIOD_MSECS)]
+    pub federation_period: u64,
+
+    /// Adresse IPv4 de l'interface réseau du partage de plages de registres (interface par
+    /// défaut du système si non spécifiée)
+    #[arg(long)]
+    pub federation_interface: Option<String>,
+
+    /// Adresse ('<adresse IP>[:<port>]', port 161 par défaut, ex: '0.0.0.0:1161') de l'agent SNMP
+    /// en lecture seule des diagnostics du simulateur
+    #[arg(long)]
+    pub snmp: Option<String>,
+
+    /// Communauté des requêtes SNMP acceptées par l'agent '--snmp'
+    #[arg(long, default_value_t = DEFAULT_SNMP_COMMUNITY.to_string())]
+    pub snmp_community: String,
+
+    /// Période (en secondes) de la trace des statistiques de profilage: attente du verrou de
+    /// la database, allocations mémoire et files de notification (0 pour inhiber la trace)
+    #[arg(long, default_value_t = 0)]
+    pub stats: u64,
+
+    /// Période (en secondes) de publication des CRC des zones de la database dans les tags du
+    /// simulateur (0 pour inhiber la publication)
+    #[arg(long, default_value_t = 10)]
+    pub crc_period: u64,
+
+    /// URL du serveur InfluxDB destinataire des valeurs des tags exportés
+    /// (ex: 'http://localhost:8086/write?db=bench')
+    #[arg(long)]
+    pub influx_url: Option<String>,
+
+    /// Groupe de tags exporté vers InfluxDB à chaque modification ('<groupe>') ou échantillonné
+    /// périodiquement ('<groupe>=<secondes>'), option répétable
+    #[arg(long)]
+    pub influx_group: Vec<String>,
+
+    /// Notification HTTP (JSON) des modifications des tags d'un motif ('<motif>=<url>', ex:
+    /// '5/*=http://jenkins:8080/hook'), option répétable
+    #[arg(long)]
+    pub webhook: Vec<String>,
+
+    /// Budget d'erreurs par minute ('<compteur>:<max>:<action>' avec le compteur 'junk' ou
+    /// 'modbus-exceptions' et l'action 'tag', 'webhook=<url>' ou 'exit'), option répétable
+    #[arg(long)]
+    pub error_budget: Vec<String>,
+
+    /// Politique des écritures de l'utilisateur anonyme dans la database ('allow' ou 'deny' pour
+    /// refuser toute modification non attribuée à un utilisateur nommé)
+    #[arg(long)]
+    pub anonymous_writes: Option<String>,
+
+    /// Source ('afsec', 'modbus' ou 'sim') seule autorisée à écrire un tag défini à une adresse
+    /// (hexa) (ex: '--owner @0010=afsec', option répétable)
+    #[arg(long)]
+    pub owner: Vec<String>,
+
+    /// Politique des écritures d'un tag par une autre source que son propriétaire ('flag' pour
+    /// les signaler ou 'reject' pour les ignorer)
+    #[arg(long, default_value = "flag")]
+    pub owner_policy: String,
+
+    /// Complément ('zero', 'space' ou 'preserve') des chaînes de caractères écrites partiellement
+    /// dans un tag défini à une adresse (hexa) (ex: '--string-padding @0100=space', option
+    /// répétable)
+    #[arg(long)]
+    pub string_padding: Vec<String>,
+
+    /// Graine des générateurs pseudo-aléatoires de la simulation (aléatoire si non spécifiée)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Fichier de l'identité du simulateur (numéro de série et adresse MAC), générée et écrite
+    /// dans ce fichier s'il n'existe pas (ex: 'identity.txt')
+    #[arg(long)]
+    pub identity: Option<String>,
+
+    /// Numéro de série du simulateur (16 caractères max., remplace celui du fichier d'identité)
+    #[arg(long)]
+    pub serial_number: Option<String>,
+
+    /// Fichier d'export de la database mappé en mémoire (65536 Bytes, mots en 'big endian')
+    #[cfg(feature = "memmap")]
+    #[arg(long)]
+    pub shm: Option<String>,
+
+    /// Script Rhai avec des 'hooks' 'on_change', 'on_frame' et 'on_timer' (ex: 'bench.rhai')
+    #[cfg(feature = "scripting")]
+    #[arg(long)]
+    pub script: Option<String>,
+}
+
+/// Commandes hors simulation
+#[derive(Subcommand)]
+pub enum Command {
+    /// Génère le fichier des vecteurs de test des trames TLV (trames et résultats attendus du
+    /// décodage) pour valider une implémentation du codage TLV
+    GenVectors {
+        /// Fichier des vecteurs de test
+        #[arg(default_value_t = String::from(DEFAULT_TEST_VECTORS_FILENAME))]
+        filename: String,
+    },
+
+    /// Génère le rapport du plan d'adressage du fichier .csv (par zone: plages, trous,
+    /// chevauchements, formats et unités) au format Markdown ou HTML
+    MapReport {
+        /// Fichier descriptif de la database au format .csv
+        #[arg(default_value_t = String::from("database.csv"))]
+        filename: String,
+
+        /// Configuration des colonnes du fichier .csv (voir l'option --csv-columns)
+        #[arg(long)]
+        csv_columns: Option<String>,
+
+        /// Format du rapport ('md' ou 'html')
+        #[arg(long, default_value_t = String::from("md"))]
+        format: String,
+
+        /// Fichier du rapport (sortie standard si non spécifié)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Établit la correspondance (tags déplacés, renommés, supprimés ou ajoutés) entre un ancien
+    /// et un nouveau fichier .csv et transforme un fichier d'état (commande 'save') ou de valeurs
+    /// (format de 'group <nom> export') de l'ancien plan des tags vers le nouveau
+    MigrateTags {
+        /// Ancien fichier descriptif de la database au format .csv
+        old_filename: String,
+
+        /// Nouveau fichier descriptif de la database au format .csv
+        new_filename: String,
+
+        /// Configuration des colonnes des fichiers .csv (voir l'option --csv-columns)
+        #[arg(long)]
+        csv_columns: Option<String>,
+
+        /// Fichier de la correspondance au format .csv (sortie standard si non spécifié)
+        #[arg(long)]
+        mapping: Option<String>,
+
+        /// Fichier d'état ou de valeurs à transformer
+        #[arg(long, requires = "output")]
+        input: Option<String>,
+
+        /// Fichier transformé
+        #[arg(short, long, requires = "input")]
+        output: Option<String>,
+    },
 }
 
 impl CommandArgs {
@@ -0,0 +1,131 @@
+//! Envoi d'une configuration de l'ICOM vers l'AFSEC+ (commande `push` de la console)
+//!
+//! Le protocole TLV ne prévoit pas de message à l'initiative de l'ICOM : une configuration est
+//! transmise à l'AFSEC+ par les conversations `AF_DATA_IN` / `IC_DATA_IN` (voir le `middleware`
+//! `MDataIn`). Les valeurs d'un fichier de configuration sont donc écrites dans la [`Database`]
+//! par lots :
+//!
+//! * Contrôle de flux : un lot de `chunk_size` tags est écrit par cycle de notification de
+//!   l'AFSEC+ pour ne pas saturer les `notification_changes` de la communication
+//! * Reprise : si la communication avec l'AFSEC+ n'est pas établie (coupure ou initialisation en
+//!   cours, voir `ID_TAG_SIM_AFSEC_STATE`), le lot en cours est différé jusqu'au rétablissement
+//!
+//! Le fichier de configuration a le format de l'export d'un groupe de tags (commande
+//! `group <nom> export <fichier>` de la console) : `id_tag;address;label;value;unity`. Seules
+//! les colonnes `address` (en hexa) et `value` sont utilisées.
+
+use std::sync::{Arc, Mutex};
+
+use crate::afsec::AFSEC_STATE_RUNNING;
+use crate::database::{WordAddress, ID_TAG_SIM_AFSEC_STATE};
+use crate::profiling::{lock_database, Subsystem};
+use crate::Database;
+
+/// Temporisation (en millisecondes) entre 2 lots (cycle de notification de l'AFSEC+)
+const CONFIG_PUSH_CYCLE_MSECS: u64 = 1000;
+
+/// Nombre de tags par lot par défaut
+pub const DEFAULT_CONFIG_PUSH_CHUNK_SIZE: usize = 10;
+
+/// Analyse d'un fichier de configuration (format de l'export d'un groupe de tags)
+/// Retourne la liste des (adresse, valeur) à transmettre
+/// # Errors
+/// Message d'erreur avec le numéro de la ligne incorrecte
+pub fn parse_config_push(content: &str) -> Result<Vec<(WordAddress, String)>, String> {
+    let mut entries = vec![];
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("id_tag;") || line.starts_with('#') {
+            // Ligne vide, entête ou commentaire
+            continue;
+        }
+        let fields: Vec<&str> = line.split(';').collect();
+        if fields.len() < 4 {
+            return Err(format!(
+                "Ligne {}: '{line}' incorrecte (attendu: id_tag;address;label;value;unity)",
+                index + 1
+            ));
+        }
+        let Ok(word_address) = WordAddress::from_str_radix(fields[1].trim(), 16) else {
+            return Err(format!(
+                "Ligne {}: adresse '{}' incorrecte",
+                index + 1,
+                fields[1]
+            ));
+        };
+        entries.push((word_address, fields[3].trim().to_string()));
+    }
+    Ok(entries)
+}
+
+/// Routine d'un thread qui transmet une configuration à l'AFSEC+ par lots de `chunk_size` tags
+pub async fn config_push_process(
+    thread_db: Arc<Mutex<Database>>,
+    entries: Vec<(WordAddress, String)>,
+    chunk_size: usize,
+) {
+    let id_user = lock_database(&thread_db, Subsystem::Console).get_id_user("Config push", false);
+    let chunk_size = chunk_size.max(1);
+    let nb_chunks = entries.len().div_ceil(chunk_size);
+    println!(
+        "PUSH: {} tag(s) en {nb_chunks} lot(s) de {chunk_size}...",
+        entries.len()
+    );
+
+    let mut nb_unknowns = 0;
+    let mut chunks = entries.chunks(chunk_size).enumerate().peekable();
+    while let Some((index, chunk)) = chunks.peek() {
+        {
+            let mut db = lock_database(&thread_db, Subsystem::Console);
+            if db.get_u16_from_id_tag(id_user, ID_TAG_SIM_AFSEC_STATE) == AFSEC_STATE_RUNNING {
+                for (word_address, value) in *chunk {
+                    match db.get_tag_from_word_address(*word_address).cloned() {
+                        Some(tag) => {
+                            if let Err(db_error) = db.set_value(id_user, &tag, value) {
+                                println!("PUSH: !!! {db_error}");
+                            }
+                        }
+                        None => nb_unknowns += 1,
+                    }
+                }
+                println!("PUSH: Lot {}/{nb_chunks} transmis", index + 1);
+                chunks.next();
+            } else {
+                println!(
+                    "PUSH: Lot {}/{nb_chunks} différé (communication AFSEC+ non établie)",
+                    index + 1
+                );
+            }
+        }
+
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(CONFIG_PUSH_CYCLE_MSECS)).await;
+    }
+
+    if nb_unknowns > 0 {
+        println!("PUSH: {nb_unknowns} adresse(s) sans tag ignorée(s)");
+    }
+    println!("PUSH: Terminé");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_push() {
+        let content = "id_tag;address;label;value;unity\n\
+                       1/0001:00:00:00;0010;Consigne;12.5;V\n\
+                       \n\
+                       1/0002:00:00:00;001A;Mode;3;\n";
+        assert_eq!(
+            parse_config_push(content),
+            Ok(vec![
+                (0x0010, "12.5".to_string()),
+                (0x001A, "3".to_string())
+            ])
+        );
+        assert!(parse_config_push("1/0001:00:00:00;0010;Consigne").is_err());
+        assert!(parse_config_push("1/0001:00:00:00;zz;Consigne;1;").is_err());
+    }
+}
@@ -0,0 +1,1224 @@
+//! Console de commandes du simulateur (lecture des commandes sur l'entrée standard)
+//!
+//! Commandes supportées :
+//!
+//! * `mark "label"`: Insère un marqueur dans toutes les traces actives
+//! * `cache`: Statistiques du cache des lectures MODBUS
+//! * `groups`: Liste des groupes de tags
+//! * `group <nom> reset|freeze|unfreeze|export [fichier]|subscribe|unsubscribe`: Opération sur
+//!   l'ensemble des tags d'un groupe (voir le module `tag_groups` de la [`Database`])
+//! * `force <adresse> <valeur>`: Force la valeur du tag défini à une adresse (hexa, `@0010` ou
+//!   `0x0010`), voir le module `forced_tags` de la [`Database`]
+//! * `unforce <adresse>`: Déforce le tag défini à une adresse
+//! * `forced`: Liste des tags forcés
+//! * `ramp <adresse> <vitesse>|off`: Définit (en unités par seconde) ou supprime la rampe du tag
+//!   défini à une adresse, voir le module `slew_rates` de la [`Database`]
+//! * `ramps`: Liste des tags avec une rampe
+//! * `history <adresse> [<nombre>|off]`: Affiche, active (nombre de valeurs conservées) ou
+//!   supprime l'historique des valeurs du tag défini à une adresse, voir le module
+//!   `tag_histories` de la [`Database`]
+//! * `histories`: Liste des tags avec un historique
+//! * `ages`: Âge et qualité des valeurs des tags avec un âge max. (option `--max-age`), voir le
+//!   module `tag_freshness` de la [`Database`]
+//! * `pulses`: Liste des tags impulsion (option `--pulse`), voir le module `pulse_tags` de la
+//!   [`Database`]
+//! * `reset [zone]`: Remise aux valeurs par défaut de tous les tags ou des tags d'une zone (voir
+//!   `Database::reset_to_defaults`)
+//! * `crc [zone]`: CRC de toutes les zones ou d'une zone (voir `Database::crc_of_zone`)
+//! * `push <fichier> [<tags par lot>]`: Transmet à l'AFSEC+ les valeurs d'un fichier de
+//!   configuration (format de `group <nom> export`), voir le module `config_push`
+//! * `panel`: Face avant virtuelle de l'ICOM (voyants et pictogrammes, voir le module
+//!   `front_panel` de la [`Database`])
+//! * `junk [clear]`: Affiche (ou efface) les dernières trames inexploitables reçues de l'AFSEC+,
+//!   voir le module `junk_captures` de la [`Database`]
+//! * `inject <trame hexa>`: Traite une trame TLV (ex: `02 00 00 00 03`) comme si elle était reçue
+//!   de l'AFSEC+ et affiche la réponse en hexa, voir `FrameInjector` du module `afsec`
+//! * `menu <id> "texte"`: Pousse un menu (message opérateur) vers l'afficheur de l'AFSEC+ au
+//!   prochain `AF_ALIVE`, voir le module `menu_pushes` de la [`Database`]
+//! * `save <fichier>`: Sauvegarde les valeurs des tags dans un fichier d'état (voir le module
+//!   `saved_state` de la [`Database`])
+//! * `restore <fichier>`: Restaure les valeurs des tags d'un fichier d'état (migration si le
+//!   fichier .csv a changé depuis la sauvegarde)
+//! * `heatmap [clear|<fichier>.csv|<fichier>.png]`: Bilan de l'activité en écriture de la
+//!   [`Database`] (tags jamais écrits), effacement des compteurs ou export de la carte (voir le
+//!   module `write_heatmap` de la [`Database`])
+//! * `powercycle [secondes]`: Simule une coupure d'alimentation de l'AFSEC+ (5 secondes par
+//!   défaut), voir le module `afsec`
+//! * `hexdump <adresse> [<nombre de mots>]`: Affichage brut (hexa) des mots de la [`Database`]
+//!   avec les limites des tags, voir le module `hexdump` de la [`Database`]
+//! * `poke <adresse> <octets hexa>`: Écriture brute d'octets (ex: `41 42 00`) à partir d'une
+//!   adresse par l'utilisateur `Maintenance`
+//! * `check`: Contrôle de cohérence des tables internes de la [`Database`] (voir le module
+//!   `invariants` de la [`Database`])
+//! * `remove <adresse>`: Supprime le tag défini à une adresse (voir le module `schema_edits` de
+//!   la [`Database`])
+//! * `format <adresse> <format>`: Change le format (code hexa du fichier .csv, ex: `04` pour
+//!   `U32`) du tag défini à une adresse
+//! * `rotate`: Rotation des fichiers de transcription et de capture (voir le module
+//!   `log_rotation`)
+//! * `stats`: Statistiques de profilage (voir le module `profiling`)
+//! * `help`: Liste des commandes
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{stdin, AsyncBufReadExt, BufReader};
+
+use crate::afsec::tlv_frame::RawFrame;
+use crate::afsec::{format_hex_frame, parse_hex_frame, FrameInjector};
+use crate::arg_parsing::{parse_word_address, unquote};
+use crate::config_push::{config_push_process, parse_config_push, DEFAULT_CONFIG_PUSH_CHUNK_SIZE};
+use crate::database::{IdUser, WordAddress, HEXDUMP_WORDS_PER_LINE, ID_TAG_SIM_POWER_CYCLE};
+use crate::log_rotation;
+use crate::profiling::{self, lock_database, Subsystem};
+use crate::t_data::TFormat;
+use crate::timeline;
+use crate::Database;
+
+/// Durée par défaut (en secondes) d'une coupure d'alimentation simulée de l'AFSEC+
+const DEFAULT_POWER_CYCLE_SECS: u16 = 5;
+
+/// Nom de l'utilisateur des écritures brutes de la commande `poke`
+const MAINTENANCE_USER_NAME: &str = "Maintenance";
+
+/// Temporisation entre chaque surveillance des modifications des groupes abonnés
+const DURATION_SUBSCRIPTIONS_MSECS: u64 = 500;
+
+/// Opération sur un groupe de tags
+#[derive(Debug, PartialEq)]
+pub enum GroupAction {
+    /// Remise des valeurs par défaut
+    Reset,
+
+    /// Gel des valeurs
+    Freeze,
+
+    /// Dégel des valeurs
+    Unfreeze,
+
+    /// Export des valeurs (dans un fichier ou à l'écran)
+    Export(Option<String>),
+
+    /// Abonnement aux modifications
+    Subscribe,
+
+    /// Désabonnement aux modifications
+    Unsubscribe,
+}
+
+impl GroupAction {
+    /// Analyse d'une opération et de son argument éventuel
+    fn parse(action: &str, arg: &str) -> Option<Self> {
+        match action.to_lowercase().as_str() {
+            "reset" => Some(GroupAction::Reset),
+            "freeze" => Some(GroupAction::Freeze),
+            "unfreeze" => Some(GroupAction::Unfreeze),
+            "export" => Some(GroupAction::Export(if arg.is_empty() {
+                None
+            } else {
+                Some(unquote(arg).to_string())
+            })),
+            "subscribe" => Some(GroupAction::Subscribe),
+            "unsubscribe" => Some(GroupAction::Unsubscribe),
+            _ => None,
+        }
+    }
+}
+
+/// Commande de la console
+#[derive(Debug, PartialEq)]
+pub enum ConsoleCommand {
+    /// Ligne vide
+    Empty,
+
+    /// Aide
+    Help,
+
+    /// Statistiques du cache des lectures MODBUS
+    Cache,
+
+    /// Statistiques de profilage
+    Stats,
+
+    /// Insertion d'un marqueur avec son label
+    Mark(String),
+
+    /// Liste des groupes de tags
+    Groups,
+
+    /// Opération sur un groupe de tags
+    Group(String, GroupAction),
+
+    /// Forçage de la valeur du tag défini à une adresse
+    Force(WordAddress, String),
+
+    /// Déforçage du tag défini à une adresse
+    Unforce(WordAddress),
+
+    /// Liste des tags forcés
+    Forced,
+
+    /// Rampe (vitesse en unités par seconde ou None pour supprimer) du tag défini à une adresse
+    Ramp(WordAddress, Option<f64>),
+
+    /// Liste des tags avec une rampe
+    Ramps,
+
+    /// Historique du tag défini à une adresse : affichage (None), activation (nombre de valeurs
+    /// conservées) ou suppression (0)
+    History(WordAddress, Option<usize>),
+
+    /// Liste des tags avec un historique
+    Histories,
+
+    /// Liste des tags avec un âge max.
+    Ages,
+
+    /// Liste des tags impulsion
+    Pulses,
+
+    /// Remise aux valeurs par défaut des tags (de toutes les zones ou d'une zone)
+    Reset(Option<u8>),
+
+    /// CRC des zones (toutes les zones définies ou une zone)
+    Crc(Option<u8>),
+
+    /// Transmission à l'AFSEC+ d'un fichier de configuration (nombre de tags par lot)
+    Push(String, usize),
+
+    /// Face avant virtuelle de l'ICOM
+    Panel,
+
+    /// Dernières trames 'junk' reçues de l'AFSEC+ (true pour les effacer)
+    Junk(bool),
+
+    /// Injection d'une trame TLV (octets) comme si elle était reçue de l'AFSEC+
+    Inject(Vec<u8>),
+
+    /// Menu (identifiant, texte) à pousser vers l'afficheur de l'AFSEC+
+    Menu(u16, String),
+
+    /// Coupure d'alimentation simulée de l'AFSEC+ (durée en secondes)
+    PowerCycle(u16),
+
+    /// Activité en écriture : bilan (None), effacement (Some("clear")) ou export dans un fichier
+    /// CSV ou PNG
+    Heatmap(Option<String>),
+
+    /// Sauvegarde de l'état de la database dans un fichier
+    Save(String),
+
+    /// Restauration de l'état de la database depuis un fichier
+    Restore(String),
+
+    /// Affichage brut des mots (nombre de mots) à partir d'une adresse
+    Hexdump(WordAddress, usize),
+
+    /// Écriture brute d'octets à partir d'une adresse
+    Poke(WordAddress, Vec<u8>),
+
+    /// Contrôle de cohérence de la database
+    Check,
+
+    /// Suppression du tag défini à une adresse
+    Remove(WordAddress),
+
+    /// Changement du format du tag défini à une adresse
+    Format(WordAddress, TFormat),
+
+    /// Rotation des fichiers de transcription et de capture
+    Rotate,
+
+    /// Commande inconnue
+    Unknown(String),
+}
+
+impl ConsoleCommand {
+    /// Analyse d'une ligne de commande
+    pub fn parse(line: &str) -> Self {
+        let line = line.trim();
+        let (command, args) = match line.split_once(char::is_whitespace) {
+            Some((command, args)) => (command, args.trim()),
+            None => (line, ""),
+        };
+        match command.to_lowercase().as_str() {
+            "" => ConsoleCommand::Empty,
+            "help" | "?" => ConsoleCommand::Help,
+            "cache" => ConsoleCommand::Cache,
+            "stats" => ConsoleCommand::Stats,
+            "mark" => ConsoleCommand::Mark(unquote(args).to_string()),
+            "groups" => ConsoleCommand::Groups,
+            "group" => {
+                let mut split = args.splitn(3, char::is_whitespace);
+                let group = split.next().unwrap_or_default();
+                let action = split.next().unwrap_or_default();
+                let arg = split.next().unwrap_or_default().trim();
+                match GroupAction::parse(action, arg) {
+                    Some(action) if !group.is_empty() => {
+                        ConsoleCommand::Group(group.to_string(), action)
+                    }
+                    _ => ConsoleCommand::Unknown(line.to_string()),
+                }
+            }
+            "force" => {
+                let (address, value) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                match parse_word_address(address) {
+                    Some(word_address) if !value.trim().is_empty() => {
+                        ConsoleCommand::Force(word_address, unquote(value).to_string())
+                    }
+                    _ => ConsoleCommand::Unknown(line.to_string()),
+                }
+            }
+            "unforce" => match parse_word_address(args) {
+                Some(word_address) => ConsoleCommand::Unforce(word_address),
+                None => ConsoleCommand::Unknown(line.to_string()),
+            },
+            "forced" => ConsoleCommand::Forced,
+            "ramp" => {
+                let (address, rate) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                let rate = rate.trim();
+                match (parse_word_address(address), rate.parse::<f64>()) {
+                    (Some(word_address), _) if rate.eq_ignore_ascii_case("off") => {
+                        ConsoleCommand::Ramp(word_address, None)
+                    }
+                    (Some(word_address), Ok(rate)) => {
+                        ConsoleCommand::Ramp(word_address, Some(rate))
+                    }
+                    _ => ConsoleCommand::Unknown(line.to_string()),
+                }
+            }
+            "ramps" => ConsoleCommand::Ramps,
+            "history" => {
+                let (address, capacity) =
+                    args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                let capacity = capacity.trim();
+                match (parse_word_address(address), capacity.parse::<usize>()) {
+                    (Some(word_address), _) if capacity.is_empty() => {
+                        ConsoleCommand::History(word_address, None)
+                    }
+                    (Some(word_address), _) if capacity.eq_ignore_ascii_case("off") => {
+                        ConsoleCommand::History(word_address, Some(0))
+                    }
+                    (Some(word_address), Ok(capacity)) if capacity > 0 => {
+                        ConsoleCommand::History(word_address, Some(capacity))
+                    }
+                    _ => ConsoleCommand::Unknown(line.to_string()),
+                }
+            }
+            "histories" => ConsoleCommand::Histories,
+            "ages" => ConsoleCommand::Ages,
+            "pulses" => ConsoleCommand::Pulses,
+            "reset" => {
+                if args.is_empty() {
+                    ConsoleCommand::Reset(None)
+                } else {
+                    match args.parse::<u8>() {
+                        Ok(zone) => ConsoleCommand::Reset(Some(zone)),
+                        _ => ConsoleCommand::Unknown(line.to_string()),
+                    }
+                }
+            }
+            "crc" => {
+                if args.is_empty() {
+                    ConsoleCommand::Crc(None)
+                } else {
+                    match args.parse::<u8>() {
+                        Ok(zone) => ConsoleCommand::Crc(Some(zone)),
+                        _ => ConsoleCommand::Unknown(line.to_string()),
+                    }
+                }
+            }
+            "push" => {
+                let (filename, chunk_size) = match args.rsplit_once(' ') {
+                    Some((filename, chunk_size)) => match chunk_size.trim().parse::<usize>() {
+                        Ok(chunk_size) => (filename, Some(chunk_size)),
+                        Err(_) => (args, None),
+                    },
+                    None => (args, None),
+                };
+                let filename = unquote(filename);
+                match chunk_size {
+                    _ if filename.is_empty() => ConsoleCommand::Unknown(line.to_string()),
+                    Some(0) => ConsoleCommand::Unknown(line.to_string()),
+                    Some(chunk_size) => ConsoleCommand::Push(filename.to_string(), chunk_size),
+                    None => {
+                        ConsoleCommand::Push(filename.to_string(), DEFAULT_CONFIG_PUSH_CHUNK_SIZE)
+                    }
+                }
+            }
+            "panel" => ConsoleCommand::Panel,
+            "check" => ConsoleCommand::Check,
+            "remove" => match parse_word_address(args) {
+                Some(word_address) => ConsoleCommand::Remove(word_address),
+                None => ConsoleCommand::Unknown(line.to_string()),
+            },
+            "format" => {
+                let (address, format) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                let format = format.trim();
+                let format = format.strip_prefix("0x").unwrap_or(format);
+                match (parse_word_address(address), u8::from_str_radix(format, 16)) {
+                    (Some(word_address), Ok(format)) => {
+                        ConsoleCommand::Format(word_address, TFormat::from(format))
+                    }
+                    _ => ConsoleCommand::Unknown(line.to_string()),
+                }
+            }
+            "rotate" => ConsoleCommand::Rotate,
+            "junk" => match args {
+                "" => ConsoleCommand::Junk(false),
+                "clear" => ConsoleCommand::Junk(true),
+                _ => ConsoleCommand::Unknown(line.to_string()),
+            },
+            "inject" => match parse_hex_frame(args) {
+                Ok(octets) => ConsoleCommand::Inject(octets),
+                Err(_) => ConsoleCommand::Unknown(line.to_string()),
+            },
+            "menu" => {
+                let (id_menu, text) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                match id_menu.parse::<u16>() {
+                    Ok(id_menu) if !text.trim().is_empty() => {
+                        ConsoleCommand::Menu(id_menu, unquote(text).to_string())
+                    }
+                    _ => ConsoleCommand::Unknown(line.to_string()),
+                }
+            }
+            "powercycle" => {
+                if args.is_empty() {
+                    ConsoleCommand::PowerCycle(DEFAULT_POWER_CYCLE_SECS)
+                } else {
+                    match args.parse::<u16>() {
+                        Ok(secs) if secs > 0 => ConsoleCommand::PowerCycle(secs),
+                        _ => ConsoleCommand::Unknown(line.to_string()),
+                    }
+                }
+            }
+            "heatmap" => match unquote(args) {
+                "" => ConsoleCommand::Heatmap(None),
+                filename => ConsoleCommand::Heatmap(Some(filename.to_string())),
+            },
+            "save" | "restore" if unquote(args).is_empty() => {
+                ConsoleCommand::Unknown(line.to_string())
+            }
+            "save" => ConsoleCommand::Save(unquote(args).to_string()),
+            "restore" => ConsoleCommand::Restore(unquote(args).to_string()),
+            "hexdump" => {
+                let (address, nb_words) =
+                    args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                let nb_words = nb_words.trim();
+                match (parse_word_address(address), nb_words.parse::<usize>()) {
+                    (Some(word_address), _) if nb_words.is_empty() => {
+                        ConsoleCommand::Hexdump(word_address, HEXDUMP_WORDS_PER_LINE)
+                    }
+                    (Some(word_address), Ok(nb_words)) if nb_words > 0 => {
+                        ConsoleCommand::Hexdump(word_address, nb_words)
+                    }
+                    _ => ConsoleCommand::Unknown(line.to_string()),
+                }
+            }
+            "poke" => {
+                let (address, octets) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                match (parse_word_address(address), parse_hex_frame(octets)) {
+                    (Some(word_address), Ok(octets)) => ConsoleCommand::Poke(word_address, octets),
+                    _ => ConsoleCommand::Unknown(line.to_string()),
+                }
+            }
+            _ => ConsoleCommand::Unknown(line.to_string()),
+        }
+    }
+}
+
+/// Nombre max. de tags jamais écrits affichés par la commande `heatmap`
+const HEATMAP_MAX_UNWRITTEN_TAGS: usize = 20;
+
+/// Exécution de la commande `heatmap`
+fn execute_heatmap(thread_db: &Arc<Mutex<Database>>, option_arg: Option<&str>) {
+    let mut db = lock_database(thread_db, Subsystem::Console);
+    match option_arg {
+        None => {
+            let unwritten_tags = db.get_unwritten_tags();
+            println!(
+                "CONSOLE: {} mots écrits, {} tags jamais écrits",
+                db.get_nb_written_words(),
+                unwritten_tags.len()
+            );
+            for tag in unwritten_tags.iter().take(HEATMAP_MAX_UNWRITTEN_TAGS) {
+                println!("CONSOLE:   {tag}");
+            }
+            if unwritten_tags.len() > HEATMAP_MAX_UNWRITTEN_TAGS {
+                println!("CONSOLE:   ...");
+            }
+        }
+        Some("clear") => {
+            db.clear_write_counts();
+            println!("CONSOLE: Compteurs des écritures effacés");
+        }
+        Some(filename) => {
+            let content = if filename.to_lowercase().ends_with(".png") {
+                db.write_heatmap_png()
+            } else {
+                db.write_heatmap_csv().into_bytes()
+            };
+            drop(db);
+            match std::fs::write(filename, content) {
+                Ok(()) => println!("CONSOLE: Carte des écritures exportée dans '{filename}'"),
+                Err(e) => println!("CONSOLE: Erreur écriture '{filename}': {e}"),
+            }
+        }
+    }
+}
+
+/// Exécution d'une commande de la console
+/// Les écritures brutes (`poke`) sont faites par l'utilisateur `id_maintenance`
+fn execute(
+    thread_db: &Arc<Mutex<Database>>,
+    id_user: IdUser,
+    id_maintenance: IdUser,
+    frame_injector: &mut FrameInjector,
+    command: &ConsoleCommand,
+) {
+    match command {
+        ConsoleCommand::Empty => (),
+        ConsoleCommand::Help => {
+            println!("CONSOLE: Commandes disponibles:");
+            println!("  mark \"label\"  Insère un marqueur dans les traces");
+            println!("  cache         Statistiques du cache des lectures MODBUS");
+            println!(
+                "  stats         Statistiques de profilage (verrou, allocations, notifications)"
+            );
+            println!("  groups        Liste des groupes de tags");
+            println!("  group <nom> reset|freeze|unfreeze|export [fichier]|subscribe|unsubscribe");
+            println!("                Opération sur tous les tags d'un groupe");
+            println!("  force <adresse> <valeur>  Force la valeur du tag à une adresse (hexa)");
+            println!("  unforce <adresse>         Déforce le tag à une adresse (hexa)");
+            println!("  forced        Liste des tags forcés");
+            println!(
+                "  ramp <adresse> <vitesse>|off  Rampe (unités/s) du tag à une adresse (hexa)"
+            );
+            println!("  ramps         Liste des tags avec une rampe");
+            println!(
+                "  history <adresse> [<nombre>|off]  Historique des valeurs du tag à une adresse"
+            );
+            println!("  histories     Liste des tags avec un historique");
+            println!("  ages          Âge et qualité des valeurs des tags avec un âge max.");
+            println!("  pulses        Liste des tags impulsion (remis à 0 automatiquement)");
+            println!("  reset [zone]  Remise aux valeurs par défaut des tags (d'une zone)");
+            println!("  crc [zone]    CRC des zones (d'une zone)");
+            println!("  push <fichier> [<tags par lot>]  Transmet une configuration à l'AFSEC+");
+            println!("  panel         Face avant virtuelle (voyants et pictogrammes)");
+            println!("  junk [clear]  Dernières trames inexploitables reçues de l'AFSEC+");
+            println!("  inject <trame hexa>       Traite une trame comme reçue de l'AFSEC+");
+            println!("  menu <id> \"texte\"         Pousse un menu vers l'afficheur de l'AFSEC+");
+            println!("  powercycle [secondes]     Simule une coupure d'alimentation de l'AFSEC+");
+            println!(
+                "  heatmap [clear|<fichier>.csv|<fichier>.png]  Activité en écriture des mots"
+            );
+            println!("  save <fichier>            Sauvegarde les valeurs des tags");
+            println!("  restore <fichier>         Restaure les valeurs des tags sauvegardées");
+            println!(
+                "  hexdump <adresse> [<nombre de mots>]  Mots bruts (hexa) et limites des tags"
+            );
+            println!(
+                "  poke <adresse> <octets hexa>          Écriture brute (utilisateur Maintenance)"
+            );
+            println!("  check         Contrôle de cohérence des tables de la database");
+            println!("  remove <adresse>          Supprime le tag à une adresse (hexa)");
+            println!("  format <adresse> <format> Change le format (hexa du .csv) du tag");
+            println!("  rotate        Rotation des fichiers de transcription et de capture");
+            println!("  help          Liste des commandes");
+        }
+        ConsoleCommand::Cache => {
+            let option_read_cache = lock_database(thread_db, Subsystem::Console).get_read_cache();
+            match option_read_cache {
+                Some(read_cache) => println!("CONSOLE: MODBUS read cache: {read_cache}"),
+                None => println!("CONSOLE: MODBUS read cache disabled (option --modbus-cache)"),
+            }
+        }
+        ConsoleCommand::Stats => {
+            let report = profiling::report(&lock_database(thread_db, Subsystem::Console));
+            for line in report.lines() {
+                println!("CONSOLE: {line}");
+            }
+        }
+        ConsoleCommand::Mark(label) => {
+            timeline::mark(label);
+        }
+        ConsoleCommand::Groups => {
+            let group_names = lock_database(thread_db, Subsystem::Console).get_group_names();
+            if group_names.is_empty() {
+                println!("CONSOLE: Aucun groupe de tags défini");
+            }
+            for group in group_names {
+                println!("CONSOLE: Groupe '{group}'");
+            }
+        }
+        ConsoleCommand::Group(group, action) => {
+            if let Err(msg) = execute_group(thread_db, id_user, group, action) {
+                println!("CONSOLE: {msg}");
+            }
+        }
+        ConsoleCommand::Force(word_address, value) => {
+            let mut db = lock_database(thread_db, Subsystem::Console);
+            match db.get_tag_from_word_address(*word_address).cloned() {
+                Some(tag) => match db.force_tag(id_user, tag.id_tag, value) {
+                    Ok(()) => println!(
+                        "CONSOLE: {tag} forcé à {}",
+                        db.get_t_value_from_tag(id_user, &tag)
+                    ),
+                    Err(msg) => println!("CONSOLE: {msg}"),
+                },
+                None => println!("CONSOLE: Pas de tag défini à l'adresse {word_address:#06X}"),
+            }
+        }
+        ConsoleCommand::Unforce(word_address) => {
+            let mut db = lock_database(thread_db, Subsystem::Console);
+            match db.get_tag_from_word_address(*word_address).cloned() {
+                Some(tag) => match db.unforce_tag(tag.id_tag) {
+                    Ok(()) => println!(
+                        "CONSOLE: {tag} déforcé = {}",
+                        db.get_t_value_from_tag(id_user, &tag)
+                    ),
+                    Err(msg) => println!("CONSOLE: {msg}"),
+                },
+                None => println!("CONSOLE: Pas de tag défini à l'adresse {word_address:#06X}"),
+            }
+        }
+        ConsoleCommand::Forced => {
+            let db = lock_database(thread_db, Subsystem::Console);
+            let tags = db.get_forced_tags();
+            if tags.is_empty() {
+                println!("CONSOLE: Aucun tag forcé");
+            }
+            for tag in tags {
+                let pending = if db.get_forced_tag_pending(tag.id_tag).is_some() {
+                    " (écriture en attente)"
+                } else {
+                    ""
+                };
+                println!(
+                    "CONSOLE: {tag} forcé à {}{pending}",
+                    db.get_t_value_from_tag(id_user, &tag)
+                );
+            }
+        }
+        ConsoleCommand::Ramp(word_address, option_rate) => {
+            let mut db = lock_database(thread_db, Subsystem::Console);
+            match db.get_tag_from_word_address(*word_address).cloned() {
+                Some(tag) => {
+                    let result = match option_rate {
+                        Some(rate) => db.set_slew_rate(id_user, tag.id_tag, *rate),
+                        None => db.remove_slew_rate(id_user, tag.id_tag),
+                    };
+                    match (result, option_rate) {
+                        (Ok(()), Some(rate)) => {
+                            println!("CONSOLE: {tag} rampe {rate} {}/s", tag.unity)
+                        }
+                        (Ok(()), None) => println!("CONSOLE: {tag} sans rampe"),
+                        (Err(msg), _) => println!("CONSOLE: {msg}"),
+                    }
+                }
+                None => println!("CONSOLE: Pas de tag défini à l'adresse {word_address:#06X}"),
+            }
+        }
+        ConsoleCommand::Ramps => {
+            let db = lock_database(thread_db, Subsystem::Console);
+            let tags = db.get_slew_rate_tags();
+            if tags.is_empty() {
+                println!("CONSOLE: Aucun tag avec une rampe");
+            }
+            for tag in tags {
+                let Some(slew_rate) = db.get_slew_rate(tag.id_tag) else {
+                    continue;
+                };
+                let target = match slew_rate.target() {
+                    Some(target) => format!(" -> {target}"),
+                    None => String::new(),
+                };
+                println!(
+                    "CONSOLE: {tag} = {}{target} (rampe {} {}/s)",
+                    db.get_t_value_from_tag(id_user, &tag),
+                    slew_rate.rate(),
+                    tag.unity
+                );
+            }
+        }
+        ConsoleCommand::History(word_address, option_capacity) => {
+            execute_history(thread_db, id_user, *word_address, *option_capacity);
+        }
+        ConsoleCommand::Histories => {
+            let db = lock_database(thread_db, Subsystem::Console);
+            let tags = db.get_tag_history_tags();
+            if tags.is_empty() {
+                println!("CONSOLE: Aucun tag avec un historique");
+            }
+            for tag in tags {
+                if let Some(tag_history) = db.get_tag_history(tag.id_tag) {
+                    println!(
+                        "CONSOLE: {tag} historique {}/{} valeur(s)",
+                        tag_history.samples().len(),
+                        tag_history.capacity()
+                    );
+                }
+            }
+        }
+        ConsoleCommand::Ages => {
+            let db = lock_database(thread_db, Subsystem::Console);
+            let tags = db.get_max_age_tags();
+            if tags.is_empty() {
+                println!("CONSOLE: Aucun tag avec un âge max.");
+            }
+            for tag in tags {
+                let Some(max_age) = db.get_max_age(tag.id_tag) else {
+                    continue;
+                };
+                let age = match db.age_of(tag.id_tag) {
+                    Some(age) => format!("{:.1}s", age.as_secs_f64()),
+                    None => "jamais écrit".to_string(),
+                };
+                println!(
+                    "CONSOLE: {tag} âge {age} (max. {:.1}s) {}",
+                    max_age.max_age().as_secs_f64(),
+                    max_age.quality()
+                );
+            }
+        }
+        ConsoleCommand::Pulses => {
+            let db = lock_database(thread_db, Subsystem::Console);
+            let tags = db.get_pulse_tags();
+            if tags.is_empty() {
+                println!("CONSOLE: Aucun tag impulsion");
+            }
+            for tag in tags {
+                let Some(pulse_tag) = db.get_pulse(tag.id_tag) else {
+                    continue;
+                };
+                let state = match pulse_tag.deadline() {
+                    Some(deadline) => format!(
+                        "remise à 0 dans {:.1}s",
+                        deadline
+                            .saturating_duration_since(std::time::Instant::now())
+                            .as_secs_f64()
+                    ),
+                    None => "au repos".to_string(),
+                };
+                println!(
+                    "CONSOLE: {tag} impulsion {:.1}s ({state})",
+                    pulse_tag.duration().as_secs_f64()
+                );
+            }
+        }
+        ConsoleCommand::Reset(option_zone) => {
+            let nb_tags = lock_database(thread_db, Subsystem::Console)
+                .reset_to_defaults(id_user, *option_zone);
+            match option_zone {
+                Some(zone) => {
+                    println!("CONSOLE: Zone {zone}: {nb_tags} tag(s) remis à la valeur par défaut");
+                }
+                None => println!("CONSOLE: {nb_tags} tag(s) remis à la valeur par défaut"),
+            }
+        }
+        ConsoleCommand::Crc(option_zone) => {
+            let db = lock_database(thread_db, Subsystem::Console);
+            let zones: BTreeSet<u8> = match option_zone {
+                Some(zone) => BTreeSet::from([*zone]),
+                None => db.get_zones(),
+            };
+            for zone in zones {
+                println!("CONSOLE: Zone {zone}: CRC 0x{:04X}", db.crc_of_zone(zone));
+            }
+        }
+        ConsoleCommand::Push(filename, chunk_size) => {
+            match std::fs::read_to_string(filename)
+                .map_err(|e| format!("Erreur lecture '{filename}': {e}"))
+                .and_then(|content| parse_config_push(&content))
+            {
+                Ok(entries) => {
+                    tokio::spawn(config_push_process(
+                        Arc::clone(thread_db),
+                        entries,
+                        *chunk_size,
+                    ));
+                }
+                Err(msg) => println!("CONSOLE: {msg}"),
+            }
+        }
+        ConsoleCommand::Panel => {
+            let front_panel = lock_database(thread_db, Subsystem::Console).get_front_panel(id_user);
+            println!("CONSOLE: Face avant: {front_panel}");
+        }
+        ConsoleCommand::Junk(clear) => {
+            let mut db = lock_database(thread_db, Subsystem::Console);
+            if *clear {
+                db.clear_junk_captures();
+                println!("CONSOLE: Trames inexploitables effacées");
+            } else {
+                let junk_captures = db.get_junk_captures();
+                println!(
+                    "CONSOLE: {} trame(s) inexploitable(s), {} dernière(s) conservée(s) (max {})",
+                    junk_captures.nb_total(),
+                    junk_captures.captures().len(),
+                    junk_captures.capacity()
+                );
+                for junk_capture in junk_captures.captures() {
+                    println!(
+                        "CONSOLE:   [{}] {junk_capture}",
+                        timeline::format_timestamp(junk_capture.timestamp)
+                    );
+                }
+            }
+        }
+        ConsoleCommand::Inject(octets) => {
+            let response = frame_injector.inject(RawFrame::new(octets));
+            println!(
+                "CONSOLE: {} -> {}",
+                format_hex_frame(octets),
+                format_hex_frame(&response.encode())
+            );
+        }
+        ConsoleCommand::Menu(id_menu, text) => {
+            match lock_database(thread_db, Subsystem::Console).push_menu(*id_menu, text) {
+                Ok(nb_menus) => println!(
+                    "CONSOLE: Menu #{id_menu} \"{text}\" poussé au prochain AF_ALIVE ({nb_menus} en attente)"
+                ),
+                Err(msg) => println!("CONSOLE: {msg}"),
+            }
+        }
+        ConsoleCommand::PowerCycle(secs) => {
+            let mut db = lock_database(thread_db, Subsystem::Console);
+            if db.get_tag_from_id_tag(ID_TAG_SIM_POWER_CYCLE).is_some() {
+                db.set_sim_tag_u16(id_user, ID_TAG_SIM_POWER_CYCLE, *secs);
+                println!("CONSOLE: Coupure d'alimentation de l'AFSEC+ pendant {secs} s demandée");
+            } else {
+                println!("CONSOLE: Tags du simulateur non définis");
+            }
+        }
+        ConsoleCommand::Heatmap(option_arg) => execute_heatmap(thread_db, option_arg.as_deref()),
+        ConsoleCommand::Save(filename) => {
+            match lock_database(thread_db, Subsystem::Console).save_state_file(id_user, filename) {
+                Ok(nb_values) => {
+                    println!("CONSOLE: {nb_values} valeurs sauvegardées dans '{filename}'");
+                }
+                Err(msg) => println!("CONSOLE: {msg}"),
+            }
+        }
+        ConsoleCommand::Restore(filename) => {
+            match lock_database(thread_db, Subsystem::Console).restore_state_file(id_user, filename)
+            {
+                Ok(state_restore) => println!("CONSOLE: '{filename}': {state_restore}"),
+                Err(msg) => println!("CONSOLE: {msg}"),
+            }
+        }
+        ConsoleCommand::Hexdump(word_address, nb_words) => {
+            match lock_database(thread_db, Subsystem::Console).hexdump(*word_address, *nb_words) {
+                Ok(lines) => {
+                    for line in lines {
+                        println!("CONSOLE: {line}");
+                    }
+                }
+                Err(msg) => println!("CONSOLE: {msg}"),
+            }
+        }
+        ConsoleCommand::Poke(word_address, octets) => {
+            let mut db = lock_database(thread_db, Subsystem::Console);
+            match db.set_vec_u8_to_word_address(id_maintenance, *word_address, octets) {
+                Ok(()) => println!(
+                    "CONSOLE: @{word_address:04X} <- {} ({})",
+                    format_hex_frame(octets),
+                    db.get_id_user_name(id_maintenance)
+                ),
+                Err(db_error) => println!("CONSOLE: {db_error}"),
+            }
+        }
+        ConsoleCommand::Check => {
+            let violations = lock_database(thread_db, Subsystem::Console).check_invariants();
+            if violations.is_empty() {
+                println!("CONSOLE: Database cohérente");
+            }
+            for violation in violations {
+                println!("CONSOLE: !!! {violation}");
+            }
+        }
+        ConsoleCommand::Remove(word_address) => {
+            let mut db = lock_database(thread_db, Subsystem::Console);
+            match db.get_tag_from_word_address(*word_address).cloned() {
+                Some(tag) => match db.remove_tag(tag.id_tag) {
+                    Ok(tag) => println!("CONSOLE: {tag} supprimé"),
+                    Err(msg) => println!("CONSOLE: {msg}"),
+                },
+                None => println!("CONSOLE: Pas de tag défini à l'adresse {word_address:#06X}"),
+            }
+        }
+        ConsoleCommand::Format(word_address, t_format) => {
+            let mut db = lock_database(thread_db, Subsystem::Console);
+            match db.get_tag_from_word_address(*word_address).cloned() {
+                Some(tag) => match db.update_tag_format(tag.id_tag, *t_format) {
+                    Ok(()) => println!("CONSOLE: {tag} au format {t_format}"),
+                    Err(msg) => println!("CONSOLE: {msg}"),
+                },
+                None => println!("CONSOLE: Pas de tag défini à l'adresse {word_address:#06X}"),
+            }
+        }
+        ConsoleCommand::Rotate => {
+            log_rotation::rotate_all();
+            println!("CONSOLE: Rotation des transcriptions et des captures demandée");
+        }
+        ConsoleCommand::Unknown(line) => {
+            println!("CONSOLE: Commande inconnue '{line}' (help pour la liste des commandes)");
+        }
+    }
+}
+
+/// Exécution d'une opération sur l'historique du tag défini à une adresse
+fn execute_history(
+    thread_db: &Arc<Mutex<Database>>,
+    id_user: IdUser,
+    word_address: WordAddress,
+    option_capacity: Option<usize>,
+) {
+    let mut db = lock_database(thread_db, Subsystem::Console);
+    let Some(tag) = db.get_tag_from_word_address(word_address).cloned() else {
+        println!("CONSOLE: Pas de tag défini à l'adresse {word_address:#06X}");
+        return;
+    };
+    match option_capacity {
+        Some(0) => match db.remove_tag_history(tag.id_tag) {
+            Ok(()) => println!("CONSOLE: {tag} sans historique"),
+            Err(msg) => println!("CONSOLE: {msg}"),
+        },
+        Some(capacity) => match db.set_tag_history(id_user, tag.id_tag, capacity) {
+            Ok(()) => println!("CONSOLE: {tag} historique de {capacity} valeur(s)"),
+            Err(msg) => println!("CONSOLE: {msg}"),
+        },
+        None => match db.get_tag_history(tag.id_tag) {
+            Some(tag_history) => {
+                println!("CONSOLE: {tag}");
+                for (date, t_value) in tag_history.samples() {
+                    println!(
+                        "CONSOLE:   [{}] {t_value} {}",
+                        timeline::format_timestamp(*date),
+                        tag.unity
+                    );
+                }
+            }
+            None => println!("CONSOLE: {tag} sans historique"),
+        },
+    }
+}
+
+/// Exécution d'une opération sur un groupe de tags
+fn execute_group(
+    thread_db: &Arc<Mutex<Database>>,
+    id_user: IdUser,
+    group: &str,
+    action: &GroupAction,
+) -> Result<(), String> {
+    let mut db = lock_database(thread_db, Subsystem::Console);
+    match action {
+        GroupAction::Reset => {
+            let nb_tags = db.reset_group_to_defaults(id_user, group)?;
+            println!("CONSOLE: Groupe '{group}': {nb_tags} tag(s) remis à leur valeur par défaut");
+        }
+        GroupAction::Freeze | GroupAction::Unfreeze => {
+            let frozen = *action == GroupAction::Freeze;
+            let nb_tags = db.freeze_group(group, frozen)?;
+            let state = if frozen { "gelé(s)" } else { "dégelé(s)" };
+            println!("CONSOLE: Groupe '{group}': {nb_tags} tag(s) {state}");
+        }
+        GroupAction::Export(option_filename) => {
+            let export = db.export_group(id_user, group)?;
+            match option_filename {
+                Some(filename) => {
+                    if let Err(e) = std::fs::write(filename, export) {
+                        return Err(format!("Erreur écriture du fichier '{filename}': {e}"));
+                    }
+                    println!("CONSOLE: Groupe '{group}' exporté dans '{filename}'");
+                }
+                None => print!("{export}"),
+            }
+        }
+        GroupAction::Subscribe | GroupAction::Unsubscribe => {
+            let subscribed = *action == GroupAction::Subscribe;
+            db.subscribe_group(group, subscribed)?;
+            let state = if subscribed { "abonné" } else { "désabonné" };
+            println!("CONSOLE: Groupe '{group}' {state}");
+        }
+    }
+    Ok(())
+}
+
+/// Routine d'un thread qui affiche les modifications des tags des groupes abonnés
+async fn subscriptions_process(thread_db: Arc<Mutex<Database>>, id_user: IdUser) {
+    loop {
+        loop {
+            // Verrouiller la database partagée
+            let mut db = lock_database(&thread_db, Subsystem::Console);
+
+            // Voir s'il y a une notification (même de la console)
+            let Some(notification_change) = db.get_change(id_user, true, true) else {
+                break;
+            };
+            let groups = db.get_subscribed_groups_of_id_tag(notification_change.id_tag);
+            if groups.is_empty() {
+                continue;
+            }
+            if let Some(tag) = db.get_tag_from_id_tag(notification_change.id_tag) {
+                println!(
+                    "GROUP {}: {} = {} ({})",
+                    groups.into_iter().collect::<Vec<_>>().join(","),
+                    tag,
+                    db.get_t_value_from_tag(id_user, tag),
+                    db.get_id_user_name(notification_change.id_user),
+                );
+            }
+        }
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(
+            DURATION_SUBSCRIPTIONS_MSECS,
+        ))
+        .await;
+    }
+}
+
+/// Routine d'un thread qui lit et exécute les commandes saisies sur l'entrée standard
+/// Les trames de la commande `inject` sont traitées par le `frame_injector`
+pub async fn console_process(thread_db: Arc<Mutex<Database>>, mut frame_injector: FrameInjector) {
+    // Obtient un id_user pour les opérations et le suivi des groupes abonnés
+    let (id_user, id_maintenance) = {
+        let mut db = lock_database(&thread_db, Subsystem::Console);
+        (
+            db.get_id_user("Console", true),
+            db.get_id_user(MAINTENANCE_USER_NAME, false),
+        )
+    };
+    let handle_subscriptions = tokio::spawn(subscriptions_process(Arc::clone(&thread_db), id_user));
+
+    let mut lines = BufReader::new(stdin()).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => execute(
+                &thread_db,
+                id_user,
+                id_maintenance,
+                &mut frame_injector,
+                &ConsoleCommand::parse(&line),
+            ),
+            Ok(None) => break, // Fin de l'entrée standard
+            Err(e) => {
+                eprintln!("CONSOLE: Erreur lecture entrée standard: {e}");
+                break;
+            }
+        }
+    }
+    handle_subscriptions.abort();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(ConsoleCommand::parse(""), ConsoleCommand::Empty);
+        assert_eq!(ConsoleCommand::parse("  help "), ConsoleCommand::Help);
+        assert_eq!(ConsoleCommand::parse("cache"), ConsoleCommand::Cache);
+        assert_eq!(ConsoleCommand::parse("stats"), ConsoleCommand::Stats);
+        assert_eq!(
+            ConsoleCommand::parse("mark \"Etape 1\""),
+            ConsoleCommand::Mark("Etape 1".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("MARK Etape 2"),
+            ConsoleCommand::Mark("Etape 2".to_string())
+        );
+        assert_eq!(ConsoleCommand::parse("groups"), ConsoleCommand::Groups);
+        assert_eq!(
+            ConsoleCommand::parse("group METERING reset"),
+            ConsoleCommand::Group("METERING".to_string(), GroupAction::Reset)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("group METERING export \"metering.csv\""),
+            ConsoleCommand::Group(
+                "METERING".to_string(),
+                GroupAction::Export(Some("metering.csv".to_string()))
+            )
+        );
+        assert_eq!(
+            ConsoleCommand::parse("group METERING"),
+            ConsoleCommand::Unknown("group METERING".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("force @0010 12.5"),
+            ConsoleCommand::Force(0x0010, "12.5".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("force 0x0010"),
+            ConsoleCommand::Unknown("force 0x0010".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("unforce 0x001A"),
+            ConsoleCommand::Unforce(0x001A)
+        );
+        assert_eq!(ConsoleCommand::parse("forced"), ConsoleCommand::Forced);
+        assert_eq!(
+            ConsoleCommand::parse("ramp @0010 2.5"),
+            ConsoleCommand::Ramp(0x0010, Some(2.5))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("ramp 0010 OFF"),
+            ConsoleCommand::Ramp(0x0010, None)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("ramp 0010"),
+            ConsoleCommand::Unknown("ramp 0010".to_string())
+        );
+        assert_eq!(ConsoleCommand::parse("ramps"), ConsoleCommand::Ramps);
+        assert_eq!(ConsoleCommand::parse("ages"), ConsoleCommand::Ages);
+        assert_eq!(ConsoleCommand::parse("pulses"), ConsoleCommand::Pulses);
+        assert_eq!(
+            ConsoleCommand::parse("history @0010"),
+            ConsoleCommand::History(0x0010, None)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("history 0010 100"),
+            ConsoleCommand::History(0x0010, Some(100))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("history 0010 off"),
+            ConsoleCommand::History(0x0010, Some(0))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("history 0010 0"),
+            ConsoleCommand::Unknown("history 0010 0".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("histories"),
+            ConsoleCommand::Histories
+        );
+        assert_eq!(ConsoleCommand::parse("reset"), ConsoleCommand::Reset(None));
+        assert_eq!(
+            ConsoleCommand::parse("reset 5"),
+            ConsoleCommand::Reset(Some(5))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("reset all"),
+            ConsoleCommand::Unknown("reset all".to_string())
+        );
+        assert_eq!(ConsoleCommand::parse("crc"), ConsoleCommand::Crc(None));
+        assert_eq!(ConsoleCommand::parse("crc 3"), ConsoleCommand::Crc(Some(3)));
+        assert_eq!(
+            ConsoleCommand::parse("crc zone"),
+            ConsoleCommand::Unknown("crc zone".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("push \"config.csv\""),
+            ConsoleCommand::Push("config.csv".to_string(), DEFAULT_CONFIG_PUSH_CHUNK_SIZE)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("push config.csv 5"),
+            ConsoleCommand::Push("config.csv".to_string(), 5)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("push"),
+            ConsoleCommand::Unknown("push".to_string())
+        );
+        assert_eq!(ConsoleCommand::parse("panel"), ConsoleCommand::Panel);
+        assert_eq!(ConsoleCommand::parse("junk"), ConsoleCommand::Junk(false));
+        assert_eq!(
+            ConsoleCommand::parse("junk clear"),
+            ConsoleCommand::Junk(true)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("junk 3"),
+            ConsoleCommand::Unknown("junk 3".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("inject 02 00 00 00 03"),
+            ConsoleCommand::Inject(vec![0x02, 0x00, 0x00, 0x00, 0x03])
+        );
+        assert_eq!(
+            ConsoleCommand::parse("inject 0"),
+            ConsoleCommand::Unknown("inject 0".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("menu 3 \"Fermer V1\""),
+            ConsoleCommand::Menu(3, "Fermer V1".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("menu 3"),
+            ConsoleCommand::Unknown("menu 3".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("powercycle"),
+            ConsoleCommand::PowerCycle(DEFAULT_POWER_CYCLE_SECS)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("powercycle 10"),
+            ConsoleCommand::PowerCycle(10)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("powercycle 0"),
+            ConsoleCommand::Unknown("powercycle 0".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("heatmap"),
+            ConsoleCommand::Heatmap(None)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("heatmap map.png"),
+            ConsoleCommand::Heatmap(Some("map.png".to_string()))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("save \"bench.state\""),
+            ConsoleCommand::Save("bench.state".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("restore bench.state"),
+            ConsoleCommand::Restore("bench.state".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("save"),
+            ConsoleCommand::Unknown("save".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("hexdump @0010"),
+            ConsoleCommand::Hexdump(0x0010, HEXDUMP_WORDS_PER_LINE)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("hexdump 0x0010 32"),
+            ConsoleCommand::Hexdump(0x0010, 32)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("hexdump 0010 0"),
+            ConsoleCommand::Unknown("hexdump 0010 0".to_string())
+        );
+        assert_eq!(
+            ConsoleCommand::parse("poke @0010 41 42 00"),
+            ConsoleCommand::Poke(0x0010, vec![0x41, 0x42, 0x00])
+        );
+        assert_eq!(
+            ConsoleCommand::parse("poke @0010"),
+            ConsoleCommand::Unknown("poke @0010".to_string())
+        );
+        assert_eq!(ConsoleCommand::parse("check"), ConsoleCommand::Check);
+        assert_eq!(
+            ConsoleCommand::parse("remove @0010"),
+            ConsoleCommand::Remove(0x0010)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("format 0x0010 04"),
+            ConsoleCommand::Format(0x0010, TFormat::U32)
+        );
+        assert_eq!(
+            ConsoleCommand::parse("format @0010 0x84"),
+            ConsoleCommand::Format(0x0010, TFormat::VecU8(4))
+        );
+        assert!(matches!(
+            ConsoleCommand::parse("format @0010"),
+            ConsoleCommand::Unknown(_)
+        ));
+        assert_eq!(ConsoleCommand::parse("rotate"), ConsoleCommand::Rotate);
+        assert_eq!(
+            ConsoleCommand::parse("foo bar"),
+            ConsoleCommand::Unknown("foo bar".to_string())
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Politique des écritures de l'utilisateur anonyme (`ID_ANONYMOUS_USER`) dans la [`Database`]
+//!
+//! Par défaut, toute écriture est acceptée quel que soit l'[`IdUser`]. Pour les besoins d'audit
+//! (aucune modification non attribuée, même en simulation), la politique `Deny` refuse les
+//! écritures de l'utilisateur anonyme : l'écriture est ignorée, comptée et tracée comme une
+//! erreur. Chaque tâche du simulateur doit alors utiliser un [`IdUser`] nommé (les [`Tag`] propres
+//! au simulateur sont écrits par l'utilisateur `SIM_USER_NAME`, voir `Database::sim_id_user`).
+//!
+//! La politique est choisie par l'option `--anonymous-writes` ou, à la compilation, par la
+//! feature `deny-anonymous-writes` qui en fait la politique par défaut. Elle n'est appliquée
+//! qu'après l'initialisation de la [`Database`] (valeurs par défaut du fichier .csv, clonage des
+//! zones, restauration de l'état des tags) : toute [`Database`] est créée avec la politique
+//! `Allow`.
+//!
+//! [`Tag`]: super::Tag
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Database, IdUser, WordAddress, ID_ANONYMOUS_USER};
+
+/// Nom de l'utilisateur des écritures internes du simulateur (`Tag` propres au simulateur)
+pub const SIM_USER_NAME: &str = "Simulator";
+
+/// Politique des écritures de l'utilisateur anonyme
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnonymousWritePolicy {
+    /// Écritures acceptées
+    Allow,
+
+    /// Écritures refusées (erreur)
+    Deny,
+}
+
+impl Default for AnonymousWritePolicy {
+    /// Politique d'une [`Database`] en cours d'initialisation
+    fn default() -> Self {
+        AnonymousWritePolicy::Allow
+    }
+}
+
+impl AnonymousWritePolicy {
+    /// Politique appliquée après l'initialisation sans option `--anonymous-writes` (selon la
+    /// feature `deny-anonymous-writes`)
+    pub fn feature_default() -> Self {
+        if cfg!(feature = "deny-anonymous-writes") {
+            AnonymousWritePolicy::Deny
+        } else {
+            AnonymousWritePolicy::Allow
+        }
+    }
+}
+
+impl fmt::Display for AnonymousWritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnonymousWritePolicy::Allow => write!(f, "allow"),
+            AnonymousWritePolicy::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+impl FromStr for AnonymousWritePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "allow" => Ok(AnonymousWritePolicy::Allow),
+            "deny" => Ok(AnonymousWritePolicy::Deny),
+            _ => Err(format!(
+                "Politique des écritures anonymes '{s}' inconnue (allow ou deny)"
+            )),
+        }
+    }
+}
+
+impl Database {
+    /// Spécifie la politique des écritures de l'utilisateur anonyme
+    pub fn set_anonymous_write_policy(&mut self, policy: AnonymousWritePolicy) {
+        self.anonymous_write_policy = policy;
+    }
+
+    /// Politique des écritures de l'utilisateur anonyme
+    pub fn get_anonymous_write_policy(&self) -> AnonymousWritePolicy {
+        self.anonymous_write_policy
+    }
+
+    /// Nombre d'écritures anonymes refusées
+    pub fn get_nb_refused_anonymous_writes(&self) -> usize {
+        self.nb_refused_anonymous_writes
+    }
+
+    /// [`IdUser`] des écritures internes du simulateur (créé à la première utilisation)
+    pub fn sim_id_user(&mut self) -> IdUser {
+        *self
+            .option_sim_id_user
+            .get_or_insert_with(|| self.id_users.get_id_user(SIM_USER_NAME, false))
+    }
+
+    /// Indique si une écriture de `nb_words` mots à partir d'une [`WordAddress`] est refusée
+    /// selon la politique des écritures anonymes (l'écriture refusée est comptée et tracée)
+    pub(super) fn is_anonymous_write_refused(
+        &mut self,
+        id_user: IdUser,
+        word_address: WordAddress,
+        nb_words: usize,
+    ) -> bool {
+        if id_user != ID_ANONYMOUS_USER
+            || self.anonymous_write_policy == AnonymousWritePolicy::Allow
+        {
+            return false;
+        }
+        self.nb_refused_anonymous_writes += 1;
+        println!(
+            "!!! Écriture anonyme refusée @{word_address:04X} ({nb_words} mots, {} refus)",
+            self.nb_refused_anonymous_writes
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{IdTag, Tag, ID_TAG_SIM_JUNK_FRAMES};
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_anonymous_write_policy() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let id_tag = IdTag::new(4, 1, [0, 0, 0]);
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag,
+            t_format: TFormat::U16,
+            ..Default::default()
+        });
+        db.set_anonymous_write_policy(AnonymousWritePolicy::Deny);
+
+        // Écriture anonyme refusée
+        db.set_u16_to_id_tag(ID_ANONYMOUS_USER, id_tag, 1).unwrap();
+        assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag), 0);
+        assert_eq!(db.get_nb_refused_anonymous_writes(), 1);
+
+        // Écriture d'un utilisateur nommé et des tags du simulateur acceptées
+        let id_user = db.get_id_user("test", false);
+        db.set_u16_to_id_tag(id_user, id_tag, 2).unwrap();
+        assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag), 2);
+        db.increment_sim_tag(ID_TAG_SIM_JUNK_FRAMES);
+        assert_eq!(db.get_u16_from_id_tag(id_user, ID_TAG_SIM_JUNK_FRAMES), 1);
+        assert_eq!(db.get_nb_refused_anonymous_writes(), 1);
+        let sim_id_user = db.sim_id_user();
+        assert_eq!(db.get_id_user_name(sim_id_user), SIM_USER_NAME);
+    }
+
+    #[test]
+    fn test_csv_load_before_deny_policy() {
+        let path = std::env::temp_dir().join("sim_icom_test_anonymous_writes.csv");
+        std::fs::write(
+            &path,
+            "00:0001:00:00:00;0000;01;;Version Metro;2000;01;;0;0;0;0;3;\n",
+        )
+        .unwrap();
+        let mut db = Database::from_file(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        // Valeur par défaut écrite pendant l'initialisation, avant la politique `Deny`
+        assert_eq!(db.get_anonymous_write_policy(), AnonymousWritePolicy::Allow);
+        db.set_anonymous_write_policy(AnonymousWritePolicy::Deny);
+        let id_tag = IdTag::new(0, 0x0001, [0, 0, 0]);
+        assert_eq!(db.get_u8_from_id_tag(ID_ANONYMOUS_USER, id_tag), 3);
+        assert_eq!(db.get_nb_refused_anonymous_writes(), 0);
+    }
+
+    #[test]
+    fn test_anonymous_write_policy_from_str() {
+        assert_eq!(
+            AnonymousWritePolicy::from_str("Deny"),
+            Ok(AnonymousWritePolicy::Deny)
+        );
+        assert_eq!(
+            AnonymousWritePolicy::from_str("allow"),
+            Ok(AnonymousWritePolicy::Allow)
+        );
+        assert!(AnonymousWritePolicy::from_str("maybe").is_err());
+    }
+}
@@ -1,68 +1,410 @@
 //! Décodage du contenu d'un fichier database*.csv
+//!
+//! Par défaut, les colonnes sont celles des fichiers database*.csv de production, séparées
+//! par des ';'. Une configuration [`CsvConfig`] permet de décoder les fichiers issus d'autres
+//! générateurs (colonnes permutées, séparateur différent, champs entre guillemets).
+//!
+//! La configuration est décrite par une spécification textuelle (voir `CsvConfig::from_spec`)
+//! de la forme `sep=comma,id=0,address=1,format=Format,...` où chaque colonne est désignée
+//! par son indice (à partir de 0) ou par son nom dans la ligne d'entête du fichier.
+//!
+//! En mode `CsvParseMode::Strict` (par défaut), la première ligne incorrecte stoppe le chargement
+//! du fichier. En mode `CsvParseMode::Lenient`, les lignes incorrectes sont ignorées et les
+//! erreurs ([`CsvError`]) sont collectées dans un rapport (voir `write_report`).
+//!
+//! Les champs entre guillemets ne sont décodés que sur demande (`quote=yes`) car les fichiers
+//! de production contiennent des '"' isolés (unité 'pouce' par exemple).
+
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
 
 use super::IdTag;
 use super::TFormat;
-use crate::database::Tag;
+use crate::database::{Database, Tag};
 
-/// Parse une ligne du fichier database*.csv et retourne
-/// `Ok(Some(u16, NumTag, Tag, String))` si la ligne contient la définition d'un [`Tag`]
-/// `Ok(None)` si la ligne ne contient pas la définition d'un [`Tag`] (commentaire)
-/// `Err(String)` pour signaler une erreur de contenu dans cette ligne
+/// Champs d'un [`Tag`] décodés depuis une ligne du fichier database*.csv
+/// (nom dans la spécification, indice de la colonne par défaut si présente en production)
+const CSV_FIELDS: [(&str, Option<usize>); 12] = [
+    ("id", Some(0)),
+    ("address", Some(1)),
+    ("format", Some(2)),
+    ("unity", Some(3)),
+    ("label", Some(4)),
+    ("rw", Some(10)),
+    ("zone", Some(11)),
+    ("default", Some(12)),
+    ("group", None),
+    ("priority", None),
+    ("min", None),
+    ("max", None),
+];
+
+/// Désignation d'une colonne du fichier .csv
+#[derive(Clone, Debug, PartialEq)]
+enum ColumnSpec {
+    /// Colonne désignée par son indice (à partir de 0)
+    Index(usize),
+
+    /// Colonne désignée par son nom dans la ligne d'entête
+    Header(String),
+
+    /// Colonne absente du fichier
+    Absent,
+}
+
+/// Mode de traitement des erreurs du fichier .csv
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CsvParseMode {
+    /// La première erreur stoppe le chargement du fichier (usage en CI)
+    #[default]
+    Strict,
+
+    /// Les lignes incorrectes sont ignorées et les erreurs collectées dans un rapport
+    Lenient,
+}
+
+/// Erreur de décodage d'une ligne du fichier .csv
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvError {
+    /// Numéro de ligne (à partir de 1) dans le fichier
+    pub line: usize,
+
+    /// Nom du champ en erreur (vide si l'erreur ne concerne pas un champ particulier)
+    pub column: String,
+
+    /// Description de l'erreur
+    pub reason: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.column.is_empty() {
+            write!(f, "line {}: {}", self.line, self.reason)
+        } else {
+            write!(
+                f,
+                "line {}, champ '{}': {}",
+                self.line, self.column, self.reason
+            )
+        }
+    }
+}
+
+impl CsvError {
+    /// Erreur d'un champ de `CSV_FIELDS` (numéro de ligne à préciser par `CsvError::at_line`)
+    fn new(n_field: usize, reason: String) -> Self {
+        Self {
+            line: 0,
+            column: CSV_FIELDS[n_field].0.to_string(),
+            reason,
+        }
+    }
+
+    /// Précise le numéro de ligne de l'erreur
+    #[must_use]
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+}
+
+/// Écriture d'un rapport d'erreurs (1 erreur par ligne: 'line;column;reason')
+/// # Errors
+/// Erreur si le fichier ne peut pas être écrit
+pub fn write_report(filename: &str, csv_errors: &[CsvError]) -> std::io::Result<()> {
+    let mut file = File::create(filename)?;
+    writeln!(file, "line;column;reason")?;
+    for csv_error in csv_errors {
+        writeln!(
+            file,
+            "{};{};{}",
+            csv_error.line,
+            csv_error.column,
+            csv_error.reason.replace(';', ",")
+        )?;
+    }
+    Ok(())
+}
+
+/// Configuration du décodage d'un fichier database*.csv
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvConfig {
+    /// Séparateur des champs d'une ligne
+    pub separator: char,
+
+    /// Décodage des champs entre guillemets
+    pub quoted: bool,
+
+    /// Mode de traitement des erreurs
+    pub mode: CsvParseMode,
+
+    /// Fichier de rapport des erreurs (en mode `CsvParseMode::Lenient`)
+    pub report_filename: Option<String>,
+
+    /// Colonne de chacun des champs de `CSV_FIELDS` (dans le même ordre)
+    columns: Vec<ColumnSpec>,
+}
+
+impl Default for CsvConfig {
+    fn default() -> Self {
+        Self {
+            separator: ';',
+            quoted: false,
+            mode: CsvParseMode::default(),
+            report_filename: None,
+            columns: CSV_FIELDS
+                .iter()
+                .map(|(_, option_index)| match option_index {
+                    Some(index) => ColumnSpec::Index(*index),
+                    None => ColumnSpec::Absent,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CsvConfig {
+    /// Configuration selon une spécification `nom=valeur` séparées par des ','
+    ///
+    /// * `sep` : Séparateur des champs (`;`, `semicolon`, `comma`, `tab` ou un caractère)
+    /// * `quote` : Décodage des champs entre guillemets (`yes` ou `no`)
+    /// * `id`, `address`, `format`, `unity`, `label`, `rw`, `zone`, `default`, `group`,
+    ///   `priority`, `min`, `max` : Colonne du champ, par son indice (à partir de 0) ou par son
+    ///   nom dans la ligne d'entête
+    ///
+    /// Les champs non spécifiés conservent la colonne des fichiers de production
+    /// (les champs `group`, `priority`, `min` et `max` sont absents des fichiers de production)
+    /// # Errors
+    /// Message d'erreur si la spécification est incorrecte
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let mut config = CsvConfig::default();
+        for item in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            let Some((name, value)) = item.split_once('=') else {
+                return Err(format!(
+                    "Spécification CSV '{item}' incorrecte (nom=valeur attendu)"
+                ));
+            };
+            let (name, value) = (name.trim().to_lowercase(), value.trim());
+            if name == "sep" {
+                config.separator = match value.to_lowercase().as_str() {
+                    "semicolon" => ';',
+                    "comma" => ',',
+                    "tab" => '\t',
+                    _ => {
+                        let mut chars = value.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(car), None) => car,
+                            _ => return Err(format!("Séparateur CSV '{value}' incorrect")),
+                        }
+                    }
+                };
+                continue;
+            }
+            if name == "quote" {
+                config.quoted = match value.to_lowercase().as_str() {
+                    "yes" | "1" | "true" => true,
+                    "no" | "0" | "false" => false,
+                    _ => return Err(format!("Option CSV quote='{value}' incorrecte")),
+                };
+                continue;
+            }
+            let Some(n_field) = CSV_FIELDS.iter().position(|(field, _)| *field == name) else {
+                return Err(format!("Champ CSV '{name}' inconnu"));
+            };
+            if value.is_empty() {
+                return Err(format!("Colonne du champ CSV '{name}' non spécifiée"));
+            }
+            config.columns[n_field] = match value.parse::<usize>() {
+                Ok(index) => ColumnSpec::Index(index),
+                Err(_) => ColumnSpec::Header(unquote_field(value).to_string()),
+            };
+        }
+        Ok(config)
+    }
+
+    /// Indique si des colonnes sont désignées par leur nom dans la ligne d'entête
+    pub fn needs_header(&self) -> bool {
+        self.columns
+            .iter()
+            .any(|column| matches!(column, ColumnSpec::Header(_)))
+    }
+
+    /// Résolution des colonnes désignées par leur nom selon la ligne d'entête du fichier
+    /// # Errors
+    /// Message d'erreur si une colonne n'est pas présente dans l'entête
+    pub fn resolve_header(&mut self, line: &str) -> Result<(), String> {
+        let headers = split_fields(line, self.separator, self.quoted);
+        for column in &mut self.columns {
+            if let ColumnSpec::Header(name) = column {
+                match headers
+                    .iter()
+                    .position(|header| header.trim() == name.as_str())
+                {
+                    Some(index) => *column = ColumnSpec::Index(index),
+                    None => return Err(format!("Colonne '{name}' absente de l'entête")),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Champ `n_field` de `CSV_FIELDS` dans les champs d'une ligne
+    /// Retourne `None` si la colonne n'est pas présente dans la ligne
+    fn get_field<'a>(&self, fields: &'a [String], n_field: usize) -> Option<&'a str> {
+        match &self.columns[n_field] {
+            ColumnSpec::Index(index) => fields.get(*index).map(|field| field.trim()),
+            ColumnSpec::Header(_) | ColumnSpec::Absent => None,
+        }
+    }
+
+    /// Idem `CsvConfig::get_field` mais retourne une erreur si la colonne est absente
+    fn get_required_field<'a>(
+        &self,
+        fields: &'a [String],
+        n_field: usize,
+    ) -> Result<&'a str, CsvError> {
+        self.get_field(fields, n_field)
+            .ok_or_else(|| CsvError::new(n_field, "Champ absent".to_string()))
+    }
+}
+
+/// Retire les guillemets autour d'un champ (s'il y en a)
+fn unquote_field(field: &str) -> &str {
+    field
+        .strip_prefix('"')
+        .and_then(|field| field.strip_suffix('"'))
+        .unwrap_or(field)
+}
+
+/// Découpe une ligne en champs selon le séparateur
+/// Si `quoted`, un champ entre guillemets peut contenir le séparateur et des guillemets doublés ("")
+fn split_fields(line: &str, separator: char, quoted: bool) -> Vec<String> {
+    if !quoted {
+        return line.split(separator).map(str::to_string).collect();
+    }
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(car) = chars.next() {
+        if in_quotes {
+            if car == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(car);
+            }
+        } else if car == '"' && field.trim().is_empty() {
+            field.clear();
+            in_quotes = true;
+        } else if car == separator {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(car);
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Parse une ligne du fichier database*.csv de production (voir `from_line_csv_with_config`)
+/// # Errors
+/// Message d'erreur si le contenu de la ligne est incorrect
+#[allow(dead_code)]
 pub fn from_line_csv(line: &str) -> Result<Option<Tag>, String> {
-    if line.is_empty() || line.starts_with("//") || line.starts_with("@@") {
+    from_line_csv_with_config(line, &CsvConfig::default()).map_err(|e| e.reason)
+}
+
+/// Parse une ligne du fichier database*.csv selon la configuration `config` et retourne
+/// `Ok(Some(Tag))` si la ligne contient la définition d'un [`Tag`]
+/// `Ok(None)` si la ligne ne contient pas la définition d'un [`Tag`] (commentaire)
+/// `Err(CsvError)` pour signaler une erreur de contenu dans cette ligne (numéro de ligne à 0)
+/// # Errors
+/// [`CsvError`] si le contenu de la ligne est incorrect
+pub fn from_line_csv_with_config(line: &str, config: &CsvConfig) -> Result<Option<Tag>, CsvError> {
+    if is_comment_line(line) {
         return Ok(None);
     }
-    let fields: Vec<&str> = line.split(';').collect();
+    let fields = split_fields(line, config.separator, config.quoted);
 
     let mut tag: Tag = Tag::default();
 
-    // println!("{} fields in '{}'", fields.len(), line);
-    // for (n, field) in fields.clone().into_iter().enumerate() {
-    //     println!("{n}: '{field}'");
-    // }
-
-    // Champ #0: 00:0000:00:00:00 -> internal + num_tag + indice 0, 1 et 3
-    let (is_internal, num_tag_u16, indice_0, indice_1, indice_2) = parse_field0(fields[0].trim())?;
+    // Champ 'id': 00:0000:00:00:00 -> internal + num_tag + indice 0, 1 et 3
+    let (is_internal, num_tag_u16, indice_0, indice_1, indice_2) =
+        parse_field0(config.get_required_field(&fields, 0)?).map_err(|e| CsvError::new(0, e))?;
     tag.is_internal = is_internal;
 
-    // Champ #1: word_address MODBUS (hexa)
-    let word_address = parse_str_hexa_to_u16(fields[1].trim())?;
+    // Champ 'address': word_address MODBUS (hexa)
+    let word_address = parse_str_hexa_to_u16(config.get_required_field(&fields, 1)?)
+        .map_err(|e| CsvError::new(1, e))?;
     tag.word_address = word_address;
 
-    // Champ #2: Format de la donnée hexa
-    let format_u8 = parse_str_hexa_to_u8(fields[2].trim())?;
+    // Champ 'format': Format de la donnée hexa
+    let format_u8 = parse_str_hexa_to_u8(config.get_required_field(&fields, 2)?)
+        .map_err(|e| CsvError::new(2, e))?;
     tag.t_format = TFormat::from(format_u8);
     if tag.t_format == TFormat::Unknown {
-        return Err(format!("Format inconnu de donnée: {format_u8:02X}"));
+        return Err(CsvError::new(
+            2,
+            format!("Format inconnu de donnée: {format_u8:02X}"),
+        ));
     }
 
-    // Champ #3: Unité (si définie)
-    tag.unity = fields[3].trim().to_string();
+    // Champ 'unity': Unité (si définie)
+    tag.unity = config.get_field(&fields, 3).unwrap_or_default().to_string();
 
-    // Champ #4: Libellé (si défini)
-    tag.label = fields[4].trim().to_string();
+    // Champ 'label': Libellé (si défini)
+    tag.label = config.get_field(&fields, 4).unwrap_or_default().to_string();
 
-    // Champs #5 (CanOpen index), #6 (CanOpen), #7 (MQTT topic), #8 (QoS), #9 (Not used)
+    // Dans les fichiers de production, champs #5 (CanOpen index), #6 (CanOpen),
+    // #7 (MQTT topic), #8 (QoS), #9 (Not used) non exploités
 
-    // Champ #10: R/W (0/1)
-    let read_write_u8 = match fields[10].trim().parse::<u8>() {
+    // Champ 'rw': R/W (0/1)
+    let read_write_u8 = match config.get_required_field(&fields, 5)?.parse::<u8>() {
         Ok(rw) => rw,
         Err(e) => {
-            return Err(format!("R/W incorrect: {e}"));
+            return Err(CsvError::new(5, format!("R/W incorrect: {e}")));
         }
     };
     tag.is_write = read_write_u8 == 1;
 
-    // Champ #11: Zone (décimal)
-    let zone = match fields[11].trim().parse::<u8>() {
+    // Champ 'zone': Zone (décimal)
+    let zone = match config.get_required_field(&fields, 6)?.parse::<u8>() {
         Ok(zone) => zone,
         Err(e) => {
-            return Err(format!("No de zone incorrect: {e}"));
+            return Err(CsvError::new(6, format!("No de zone incorrect: {e}")));
         }
     };
 
-    // Champ #12: Valeur par défaut
-    tag.default_value = fields[12].trim().to_string();
+    // Champ 'default': Valeur par défaut
+    tag.default_value = config.get_field(&fields, 7).unwrap_or_default().to_string();
+
+    // Champ 'group': Groupe du tag (si défini)
+    tag.group = config.get_field(&fields, 8).unwrap_or_default().to_string();
+
+    // Champ 'priority': Priorité des notifications du tag vers l'AFSEC+ (0 si non définie)
+    tag.priority = match config.get_field(&fields, 9).unwrap_or_default() {
+        "" => 0,
+        priority => match priority.parse::<u8>() {
+            Ok(priority) => priority,
+            Err(e) => {
+                return Err(CsvError::new(9, format!("Priorité incorrecte: {e}")));
+            }
+        },
+    };
+
+    // Champs 'min' et 'max': Plage de valeurs déclarée du tag (si définie)
+    tag.option_min = parse_limit_field(config.get_field(&fields, 10), 10, "Minimum")?;
+    tag.option_max = parse_limit_field(config.get_field(&fields, 11), 11, "Maximum")?;
 
     // Construction de l'[`IdTag`] trouvé
     tag.id_tag = IdTag::new(zone, num_tag_u16, [indice_0, indice_1, indice_2]);
@@ -71,6 +413,48 @@ pub fn from_line_csv(line: &str) -> Result<Option<Tag>, String> {
     Ok(Some(tag))
 }
 
+/// Décodage d'un champ `min` ou `max` (None si absent ou vide)
+fn parse_limit_field(
+    option_field: Option<&str>,
+    n_field: usize,
+    name: &str,
+) -> Result<Option<f64>, CsvError> {
+    match option_field.unwrap_or_default() {
+        "" => Ok(None),
+        limit => match limit.parse::<f64>() {
+            Ok(limit) if limit.is_finite() => Ok(Some(limit)),
+            _ => Err(CsvError::new(
+                n_field,
+                format!("{name} '{limit}' incorrect"),
+            )),
+        },
+    }
+}
+
+/// Erreur si le [`Tag`] est déjà défini (même [`IdTag`] ou même adresse) dans la [`Database`]
+/// # Errors
+/// [`CsvError`] si le [`Tag`] est déjà défini
+pub fn check_duplicate_tag(db: &Database, tag: &Tag) -> Result<(), CsvError> {
+    if let Some(other) = db.get_tag_from_word_address(tag.word_address) {
+        return Err(CsvError::new(
+            1,
+            format!(
+                "Adresse {:#06X} déjà attribuée à {}",
+                tag.word_address, other.id_tag
+            ),
+        ));
+    }
+    if db.get_tag_from_id_tag(tag.id_tag).is_some() {
+        return Err(CsvError::new(0, format!("Tag {} déjà défini", tag.id_tag)));
+    }
+    Ok(())
+}
+
+/// Indique si une ligne du fichier database*.csv ne contient pas de définition (vide ou commentaire)
+pub fn is_comment_line(line: &str) -> bool {
+    line.is_empty() || line.starts_with("//") || line.starts_with("@@")
+}
+
 /// Parse un champ hexadécimal de 1 caractère
 fn parse_char_hexa(car: char) -> Result<u8, String> {
     let value = match car {
@@ -131,3 +515,139 @@ fn parse_field0(field: &str) -> Result<(bool, u16, u8, u8, u8), String> {
     let indice_2 = parse_str_hexa_to_u8(split[4])?;
     Ok((is_internal, num_tag, indice_0, indice_1, indice_2))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ligne d'un fichier database*.csv de production
+    const LINE_CSV: &str = "00:0001:00:00:00;0000;01;;Version Metro;2000;01;;0;0;0;0;3;";
+
+    #[test]
+    fn test_from_line_csv() {
+        let tag = from_line_csv(LINE_CSV).unwrap().unwrap();
+        assert_eq!(tag.id_tag, IdTag::new(0, 0x0001, [0, 0, 0]));
+        assert_eq!(tag.word_address, 0x0000);
+        assert_eq!(tag.label, "Version Metro");
+        assert_eq!(tag.default_value, "3");
+
+        assert!(from_line_csv("// Commentaire").unwrap().is_none());
+        assert!(from_line_csv("00:0001:00:00:00;0000").is_err());
+    }
+
+    #[test]
+    fn test_split_fields() {
+        assert_eq!(split_fields("a;b;;c", ';', true), vec!["a", "b", "", "c"]);
+        assert_eq!(split_fields("a;\";b", ';', false), vec!["a", "\"", "b"]);
+        assert_eq!(
+            split_fields("\"a,b\",\"c \"\"d\"\"\",e", ',', true),
+            vec!["a,b", "c \"d\"", "e"]
+        );
+    }
+
+    #[test]
+    fn test_config_index() {
+        let config = CsvConfig::from_spec(
+            "sep=comma, quote=yes, address=0, id=1, group=13, priority=14, min=15, max=16",
+        )
+        .unwrap();
+        let line =
+            "0000,00:0001:00:00:00,01,,\"Version; Metro\",2000,01,,0,0,0,0,3,METRO,2,-10,10.5";
+        let tag = from_line_csv_with_config(line, &config).unwrap().unwrap();
+        assert_eq!(tag.id_tag, IdTag::new(0, 0x0001, [0, 0, 0]));
+        assert_eq!(tag.label, "Version; Metro");
+        assert_eq!(tag.group, "METRO");
+        assert_eq!(tag.priority, 2);
+        assert_eq!((tag.option_min, tag.option_max), (Some(-10.0), Some(10.5)));
+
+        assert!(CsvConfig::from_spec("unknown=1").is_err());
+        assert!(CsvConfig::from_spec("sep=;;").is_err());
+    }
+
+    #[test]
+    fn test_config_header() {
+        let mut config =
+            CsvConfig::from_spec("id=Tag,address=Adresse,zone=3,rw=4,format=2").unwrap();
+        assert!(config.needs_header());
+        assert!(config.resolve_header("Autre;Tag").is_err());
+
+        let mut config = CsvConfig::from_spec(
+            "id=Tag,address=Adresse,zone=3,rw=4,format=2,unity=9,label=9,default=9",
+        )
+        .unwrap();
+        config.resolve_header("Adresse;Tag;Format;Zone;RW").unwrap();
+        assert!(!config.needs_header());
+        let tag = from_line_csv_with_config("0010;01:0002:00:00:00;02;2;1", &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tag.id_tag, IdTag::new(2, 0x0002, [0, 0, 0]));
+        assert_eq!(tag.word_address, 0x0010);
+        assert!(tag.is_internal);
+        assert!(tag.is_write);
+        assert!(tag.label.is_empty());
+    }
+
+    #[test]
+    fn test_csv_error() {
+        let csv_error = from_line_csv_with_config(
+            "00:0001:00:00:00;0000;7F;;;;;;;;0;0;",
+            &CsvConfig::default(),
+        )
+        .unwrap_err()
+        .at_line(12);
+        assert_eq!(csv_error.line, 12);
+        assert_eq!(csv_error.column, "format");
+        assert_eq!(
+            csv_error.to_string(),
+            "line 12, champ 'format': Format inconnu de donnée: 7F"
+        );
+    }
+
+    #[test]
+    fn test_lenient_mode() {
+        let path = std::env::temp_dir().join("sim_icom_test_lenient.csv");
+        std::fs::write(
+            &path,
+            "// Test\n\
+             00:0001:00:00:00;0000;02;;;;;;;;0;0;1;\n\
+             00:0002:00:00:00;0001;7F;;;;;;;;0;0;;\n\
+             00:0003:00:00:00;0000;02;;;;;;;;0;0;;\n\
+             00:0004:00:00:00;0002;02;;;;;;;;0;0;;\n",
+        )
+        .unwrap();
+        let filename = path.to_str().unwrap();
+
+        // Mode strict: Stoppe sur la première erreur
+        let config = CsvConfig::default();
+        assert!(Database::try_from_file_with_report(filename, &config).is_err());
+
+        // Mode lenient: Les lignes incorrectes sont ignorées
+        let config = CsvConfig {
+            mode: CsvParseMode::Lenient,
+            ..Default::default()
+        };
+        let (db, csv_errors) = Database::try_from_file_with_report(filename, &config).unwrap();
+        assert_eq!(csv_errors.len(), 2);
+        assert_eq!(
+            (csv_errors[0].line, csv_errors[0].column.as_str()),
+            (3, "format")
+        );
+        assert_eq!(
+            (csv_errors[1].line, csv_errors[1].column.as_str()),
+            (4, "address")
+        );
+        assert!(db
+            .get_tag_from_id_tag(IdTag::new(0, 0x0004, [0, 0, 0]))
+            .is_some());
+
+        // Rapport d'erreurs
+        let report_path = std::env::temp_dir().join("sim_icom_test_lenient_report.csv");
+        write_report(report_path.to_str().unwrap(), &csv_errors).unwrap();
+        let report = std::fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.lines().count(), 3);
+        assert!(report.lines().nth(1).unwrap().starts_with("3;format;"));
+
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(report_path);
+    }
+}
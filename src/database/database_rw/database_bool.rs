@@ -3,7 +3,7 @@
 #[cfg(test)]
 use super::{Tag, ID_ANONYMOUS_USER};
 
-use super::{Database, IdTag, IdUser, WordAddress};
+use super::{Database, DbError, IdTag, IdUser, WordAddress};
 
 impl Database {
     /// Getter selon [`WordAddress`]
@@ -13,15 +13,17 @@ impl Database {
     }
 
     /// Setter selon [`WordAddress`]
+    /// # Errors
+    /// [`DbError`] si l'écriture est hors de la [`Database`] ou refusée
     #[allow(dead_code)]
     pub fn set_bool_to_word_address(
         &mut self,
         id_user: IdUser,
         word_address: WordAddress,
         value: bool,
-    ) {
+    ) -> Result<(), DbError> {
         let vec_u8 = vec![u8::from(value)];
-        self.set_vec_u8_to_word_address(id_user, word_address, &vec_u8);
+        self.set_vec_u8_to_word_address(id_user, word_address, &vec_u8)
     }
 
     /// Getter selon l'[`IdTag`]
@@ -34,11 +36,18 @@ impl Database {
     }
 
     /// Setter selon l'[`IdTag`]
+    /// # Errors
+    /// [`DbError`] si le tag n'est pas défini, trop court pour la valeur ou si l'écriture est
+    /// refusée
     #[allow(dead_code)]
-    pub fn set_bool_to_id_tag(&mut self, id_user: IdUser, id_tag: IdTag, value: bool) {
-        if let Some(id_tag) = self.get_tag_from_id_tag(id_tag) {
-            self.set_bool_to_word_address(id_user, id_tag.word_address, value);
-        }
+    pub fn set_bool_to_id_tag(
+        &mut self,
+        id_user: IdUser,
+        id_tag: IdTag,
+        value: bool,
+    ) -> Result<(), DbError> {
+        let word_address = self.tag_word_address(id_tag, 1, value)?;
+        self.set_bool_to_word_address(id_user, word_address, value)
     }
 }
 
@@ -68,10 +77,12 @@ mod tests {
             bool::default()
         );
 
-        db.set_bool_to_word_address(ID_ANONYMOUS_USER, addr, true);
+        db.set_bool_to_word_address(ID_ANONYMOUS_USER, addr, true)
+            .unwrap();
         assert!(db.get_bool_from_word_address(ID_ANONYMOUS_USER, addr));
 
-        db.set_bool_to_word_address(ID_ANONYMOUS_USER, addr, false);
+        db.set_bool_to_word_address(ID_ANONYMOUS_USER, addr, false)
+            .unwrap();
         assert!(!db.get_bool_from_word_address(ID_ANONYMOUS_USER, addr));
     }
 
@@ -85,10 +96,12 @@ mod tests {
             bool::default()
         );
 
-        db.set_bool_to_id_tag(ID_ANONYMOUS_USER, id_tag, true);
+        db.set_bool_to_id_tag(ID_ANONYMOUS_USER, id_tag, true)
+            .unwrap();
         assert!(db.get_bool_from_id_tag(ID_ANONYMOUS_USER, id_tag));
 
-        db.set_bool_to_id_tag(ID_ANONYMOUS_USER, id_tag, false);
+        db.set_bool_to_id_tag(ID_ANONYMOUS_USER, id_tag, false)
+            .unwrap();
         assert!(!db.get_bool_from_id_tag(ID_ANONYMOUS_USER, id_tag));
     }
 }
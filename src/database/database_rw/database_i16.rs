@@ -3,7 +3,7 @@
 #[cfg(test)]
 use super::{Tag, ID_ANONYMOUS_USER};
 
-use super::{Database, IdTag, IdUser, WordAddress};
+use super::{Database, DbError, IdTag, IdUser, WordAddress};
 
 impl Database {
     /// Getter selon [`WordAddress`]
@@ -15,15 +15,17 @@ impl Database {
     }
 
     /// Setter selon [`WordAddress`]
+    /// # Errors
+    /// [`DbError`] si l'écriture est hors de la [`Database`] ou refusée
     #[allow(dead_code)]
     pub fn set_i16_to_word_address(
         &mut self,
         id_user: IdUser,
         word_address: WordAddress,
         value: i16,
-    ) {
+    ) -> Result<(), DbError> {
         let vec_u8 = value.to_be_bytes();
-        self.set_vec_u8_to_word_address(id_user, word_address, &vec_u8);
+        self.set_vec_u8_to_word_address(id_user, word_address, &vec_u8)
     }
 
     /// Getter selon l'[`IdTag`]
@@ -36,11 +38,18 @@ impl Database {
     }
 
     /// Setter selon l'[`IdTag`]
+    /// # Errors
+    /// [`DbError`] si le tag n'est pas défini, trop court pour la valeur ou si l'écriture est
+    /// refusée
     #[allow(dead_code)]
-    pub fn set_i16_to_id_tag(&mut self, id_user: IdUser, id_tag: IdTag, value: i16) {
-        if let Some(id_tag) = self.get_tag_from_id_tag(id_tag) {
-            self.set_i16_to_word_address(id_user, id_tag.word_address, value);
-        }
+    pub fn set_i16_to_id_tag(
+        &mut self,
+        id_user: IdUser,
+        id_tag: IdTag,
+        value: i16,
+    ) -> Result<(), DbError> {
+        let word_address = self.tag_word_address(id_tag, 1, value)?;
+        self.set_i16_to_word_address(id_user, word_address, value)
     }
 }
 
@@ -71,10 +80,12 @@ mod tests {
         );
 
         for value in [-10_000_i16, -1_000_i16, 0_i16, 1_000_i16, 10_000_i16] {
-            db.set_i16_to_word_address(ID_ANONYMOUS_USER, addr, value);
+            db.set_i16_to_word_address(ID_ANONYMOUS_USER, addr, value)
+                .unwrap();
             assert_eq!(db.get_i16_from_word_address(ID_ANONYMOUS_USER, addr), value);
 
-            db.set_i16_to_word_address(ID_ANONYMOUS_USER, addr, value + 1);
+            db.set_i16_to_word_address(ID_ANONYMOUS_USER, addr, value + 1)
+                .unwrap();
             assert_eq!(
                 db.get_i16_from_word_address(ID_ANONYMOUS_USER, addr),
                 value + 1
@@ -93,10 +104,12 @@ mod tests {
         );
 
         for value in [-10_000_i16, -1_000_i16, 0_i16, 1_000_i16, 10_000_i16] {
-            db.set_i16_to_id_tag(ID_ANONYMOUS_USER, id_tag, value);
+            db.set_i16_to_id_tag(ID_ANONYMOUS_USER, id_tag, value)
+                .unwrap();
             assert_eq!(db.get_i16_from_id_tag(ID_ANONYMOUS_USER, id_tag), value);
 
-            db.set_i16_to_id_tag(ID_ANONYMOUS_USER, id_tag, value + 1);
+            db.set_i16_to_id_tag(ID_ANONYMOUS_USER, id_tag, value + 1)
+                .unwrap();
             assert_eq!(db.get_i16_from_id_tag(ID_ANONYMOUS_USER, id_tag), value + 1);
         }
     }
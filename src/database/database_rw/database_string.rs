@@ -4,7 +4,7 @@
 #[cfg(test)]
 use super::{Tag, ID_ANONYMOUS_USER};
 
-use super::{Database, IdTag, IdUser, WordAddress};
+use super::{Database, DbError, IdTag, IdUser, WordAddress};
 
 impl Database {
     /// Getter selon [`WordAddress`]
@@ -21,15 +21,17 @@ impl Database {
     }
 
     /// Setter selon [`WordAddress`]
+    /// # Errors
+    /// [`DbError`] si l'écriture est hors de la [`Database`] ou refusée
     #[allow(dead_code)]
     pub fn set_string_to_word_address(
         &mut self,
         id_user: IdUser,
         word_address: WordAddress,
         value: &str,
-    ) {
+    ) -> Result<(), DbError> {
         let vec_u8 = value.as_bytes().to_vec();
-        self.set_vec_u8_to_word_address(id_user, word_address, &vec_u8);
+        self.set_vec_u8_to_word_address(id_user, word_address, &vec_u8)
     }
 
     /// Getter selon l'[`IdTag`]
@@ -42,11 +44,18 @@ impl Database {
     }
 
     /// Setter selon l'[`IdTag`]
+    /// # Errors
+    /// [`DbError`] si le tag n'est pas défini, trop court pour la valeur ou si l'écriture est
+    /// refusée
     #[allow(dead_code)]
-    pub fn set_string_to_id_tag(&mut self, id_user: IdUser, id_tag: IdTag, value: &str) {
-        if let Some(id_tag) = self.get_tag_from_id_tag(id_tag) {
-            self.set_string_to_word_address(id_user, id_tag.word_address, value);
-        }
+    pub fn set_string_to_id_tag(
+        &mut self,
+        id_user: IdUser,
+        id_tag: IdTag,
+        value: &str,
+    ) -> Result<(), DbError> {
+        let word_address = self.tag_word_address(id_tag, value.len().div_ceil(2), value)?;
+        self.set_string_to_word_address(id_user, word_address, value)
     }
 }
 
@@ -77,7 +86,8 @@ mod tests {
         );
 
         let value = "TOTO";
-        db.set_string_to_word_address(ID_ANONYMOUS_USER, addr, value);
+        db.set_string_to_word_address(ID_ANONYMOUS_USER, addr, value)
+            .unwrap();
         assert_eq!(
             db.get_string_from_word_address(ID_ANONYMOUS_USER, addr, 6),
             "TOTO\0\0"
@@ -103,7 +113,8 @@ mod tests {
         );
 
         let value = "TOTO";
-        db.set_string_to_id_tag(ID_ANONYMOUS_USER, id_tag, value);
+        db.set_string_to_id_tag(ID_ANONYMOUS_USER, id_tag, value)
+            .unwrap();
         assert_eq!(
             db.get_string_from_id_tag(ID_ANONYMOUS_USER, id_tag, 6),
             "TOTO\0\0"
@@ -55,7 +55,8 @@ mod tests {
         );
 
         let value = vec![0x01_u8, 0x02_u8, 0x03_u8, 0x04_u8];
-        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, addr, &value);
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, addr, &value)
+            .unwrap();
         assert_eq!(
             db.get_vec_u8_from_word_address(ID_ANONYMOUS_USER, addr, 6),
             vec![0x01_u8, 0x02_u8, 0x03_u8, 0x04_u8, 0, 0]
@@ -81,7 +82,8 @@ mod tests {
         );
 
         let value = vec![0x01_u8, 0x02_u8, 0x03_u8, 0x04_u8];
-        db.set_vec_u8_to_id_tag(ID_ANONYMOUS_USER, id_tag, &value);
+        db.set_vec_u8_to_id_tag(ID_ANONYMOUS_USER, id_tag, &value)
+            .unwrap();
         assert_eq!(
             db.get_vec_u8_from_id_tag(ID_ANONYMOUS_USER, id_tag, 6),
             vec![0x01_u8, 0x02_u8, 0x03_u8, 0x04_u8, 0, 0]
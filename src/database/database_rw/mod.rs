@@ -1,11 +1,15 @@
 //! Module pour la gestion des différents formats dans la [`Database`]
 
+use std::fmt;
+use std::time::Instant;
+
+use crate::capture::{self, CaptureKind};
 use crate::t_data::string_to_vec_u8;
 
 #[cfg(test)]
 use super::ID_ANONYMOUS_USER;
 
-use super::{Database, IdTag, IdUser, TFormat, TValue, Tag, WordAddress};
+use super::{Database, DbError, IdTag, IdUser, StringPadding, TFormat, TValue, Tag, WordAddress};
 
 mod database_bool;
 mod database_f32;
@@ -23,78 +27,109 @@ mod database_vec_u8;
 
 impl Database {
     /// Ecrire la [`Database`] avec une valeur (String) par défaut
-    pub fn set_value(&mut self, id_user: IdUser, tag: &Tag, value: &str) {
+    /// # Errors
+    /// [`DbError`] si la valeur est incompatible avec le format du [`Tag`] ou si l'écriture est
+    /// refusée
+    pub fn set_value(&mut self, id_user: IdUser, tag: &Tag, value: &str) -> Result<(), DbError> {
         let word_address = tag.word_address;
+        let format_mismatch = || DbError::FormatMismatch {
+            word_address,
+            t_format: tag.t_format,
+            value: value.to_string(),
+        };
         match tag.t_format {
             TFormat::Bool => {
-                if let Ok(value) = value.parse::<bool>() {
-                    self.set_bool_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<bool>().map_err(|_| format_mismatch())?;
+                self.set_bool_to_word_address(id_user, word_address, value)
             }
             TFormat::U8 => {
-                if let Ok(value) = value.parse::<u8>() {
-                    self.set_u8_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<u8>().map_err(|_| format_mismatch())?;
+                self.set_u8_to_word_address(id_user, word_address, value)
             }
             TFormat::I8 => {
-                if let Ok(value) = value.parse::<i8>() {
-                    self.set_i8_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<i8>().map_err(|_| format_mismatch())?;
+                self.set_i8_to_word_address(id_user, word_address, value)
             }
             TFormat::U16 => {
-                if let Ok(value) = value.parse::<u16>() {
-                    self.set_u16_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<u16>().map_err(|_| format_mismatch())?;
+                self.set_u16_to_word_address(id_user, word_address, value)
             }
             TFormat::I16 => {
-                if let Ok(value) = value.parse::<i16>() {
-                    self.set_i16_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<i16>().map_err(|_| format_mismatch())?;
+                self.set_i16_to_word_address(id_user, word_address, value)
             }
             TFormat::U32 => {
-                if let Ok(value) = value.parse::<u32>() {
-                    self.set_u32_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<u32>().map_err(|_| format_mismatch())?;
+                self.set_u32_to_word_address(id_user, word_address, value)
             }
             TFormat::I32 => {
-                if let Ok(value) = value.parse::<i32>() {
-                    self.set_i32_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<i32>().map_err(|_| format_mismatch())?;
+                self.set_i32_to_word_address(id_user, word_address, value)
             }
             TFormat::U64 => {
-                if let Ok(value) = value.parse::<u64>() {
-                    self.set_u64_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<u64>().map_err(|_| format_mismatch())?;
+                self.set_u64_to_word_address(id_user, word_address, value)
             }
             TFormat::I64 => {
-                if let Ok(value) = value.parse::<i64>() {
-                    self.set_i64_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<i64>().map_err(|_| format_mismatch())?;
+                self.set_i64_to_word_address(id_user, word_address, value)
             }
             TFormat::F32 => {
-                if let Ok(value) = value.parse::<f32>() {
-                    self.set_f32_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<f32>().map_err(|_| format_mismatch())?;
+                self.set_f32_to_word_address(id_user, word_address, value)
             }
             TFormat::F64 => {
-                if let Ok(value) = value.parse::<f64>() {
-                    self.set_f64_to_word_address(id_user, word_address, value);
-                }
+                let value = value.parse::<f64>().map_err(|_| format_mismatch())?;
+                self.set_f64_to_word_address(id_user, word_address, value)
+            }
+            TFormat::VecU8(_) => {
+                // Chaîne complétée avec des NULL sauf politique spécifique du [`Tag`] (voir le
+                // module `string_paddings`)
+                let value = self.padded_string(tag, value.as_bytes(), Some(StringPadding::Zero));
+                self.set_vec_u8_to_word_address(id_user, word_address, &value)
+            }
+            TFormat::Unknown => Err(format_mismatch()),
+        }
+    }
+
+    /// Ecrire la [`Database`] avec une [`TValue`] (convertie dans le format du [`Tag`])
+    /// # Errors
+    /// [`DbError`] si la valeur ne peut pas être convertie dans le format du [`Tag`] ou si
+    /// l'écriture est refusée
+    pub fn set_t_value_to_tag(
+        &mut self,
+        id_user: IdUser,
+        tag: &Tag,
+        t_value: &TValue,
+    ) -> Result<(), DbError> {
+        let word_address = tag.word_address;
+        let option_t_value = if TFormat::from(t_value) == tag.t_format {
+            Some(t_value.clone())
+        } else {
+            t_value.to_t_format(tag.t_format)
+        };
+        match option_t_value {
+            Some(TValue::Bool(value)) => {
+                self.set_bool_to_word_address(id_user, word_address, value)
             }
-            TFormat::VecU8(len) => {
-                let value = value.as_bytes().to_vec();
-                let value = if value.len() >= len {
-                    value[..len].to_vec()
-                } else {
-                    let mut v = value.clone();
-                    while v.len() < len {
-                        v.push(0);
-                    }
-                    v
-                };
-                self.set_vec_u8_to_word_address(id_user, word_address, &value);
+            Some(TValue::U8(value)) => self.set_u8_to_word_address(id_user, word_address, value),
+            Some(TValue::I8(value)) => self.set_i8_to_word_address(id_user, word_address, value),
+            Some(TValue::U16(value)) => self.set_u16_to_word_address(id_user, word_address, value),
+            Some(TValue::I16(value)) => self.set_i16_to_word_address(id_user, word_address, value),
+            Some(TValue::U32(value)) => self.set_u32_to_word_address(id_user, word_address, value),
+            Some(TValue::I32(value)) => self.set_i32_to_word_address(id_user, word_address, value),
+            Some(TValue::U64(value)) => self.set_u64_to_word_address(id_user, word_address, value),
+            Some(TValue::I64(value)) => self.set_i64_to_word_address(id_user, word_address, value),
+            Some(TValue::F32(value)) => self.set_f32_to_word_address(id_user, word_address, value),
+            Some(TValue::F64(value)) => self.set_f64_to_word_address(id_user, word_address, value),
+            Some(TValue::VecU8(_, value)) => {
+                self.set_vec_u8_to_word_address(id_user, word_address, &value)
             }
-            TFormat::Unknown => (),
+            None => Err(DbError::FormatMismatch {
+                word_address,
+                t_format: tag.t_format,
+                value: t_value.to_string(),
+            }),
         }
     }
 
@@ -138,47 +173,147 @@ impl Database {
     }
 
     /// Copie un `&[u8]` dans la [`Database`] selon [`IdTag`]
-    /// (Helper pour le `TValue::String`)
-    pub fn set_vec_u8_to_id_tag(&mut self, id_user: IdUser, id_tag: IdTag, value: &[u8]) {
-        if let Some(tag) = self.get_tag_from_id_tag(id_tag) {
-            // S'il s'agit d'une chaîne de caractères de longueur connue, on adapte le Vec<u8> en le
-            // complétant avec des NULL ou en adaptant sa longueur...
-            let value = if let TFormat::VecU8(len) = tag.t_format {
-                let mut vec_u8 = value.to_vec();
-                while vec_u8.len() < len {
-                    vec_u8.push(0);
-                }
-                if vec_u8.len() > len {
-                    vec_u8 = vec_u8[0..len].to_vec();
+    /// (Helper pour le `TValue::VecU8`)
+    /// # Errors
+    /// [`DbError`] si le [`Tag`] n'est pas défini ou si l'écriture est refusée
+    pub fn set_vec_u8_to_id_tag(
+        &mut self,
+        id_user: IdUser,
+        id_tag: IdTag,
+        value: &[u8],
+    ) -> Result<(), DbError> {
+        let Some(tag) = self.get_tag_from_id_tag(id_tag) else {
+            return Err(DbError::UnknownTag(id_tag));
+        };
+        // S'il s'agit d'une chaîne de caractères de longueur connue, on adapte le Vec<u8> en le
+        // complétant (avec des NULL sauf politique spécifique du [`Tag`]) ou en adaptant sa
+        // longueur...
+        let value = self.padded_string(tag, value, Some(StringPadding::Zero));
+        self.set_vec_u8_to_word_address(id_user, tag.word_address, &value)
+    }
+
+    /// Erreur si l'écriture de `nb_u8` octets à partir de `word_address` dépasse la fin de la
+    /// [`Database`]
+    fn check_word_address_range(
+        &self,
+        word_address: WordAddress,
+        nb_u8: usize,
+    ) -> Result<(), DbError> {
+        if 2 * word_address as usize + nb_u8 > self.vec_u8.len() {
+            return Err(DbError::OutOfRange {
+                word_address,
+                nb_u8,
+            });
+        }
+        Ok(())
+    }
+
+    /// [`WordAddress`] d'un [`Tag`] pour y écrire une valeur de `nb_words` mots
+    /// (erreur si le [`Tag`] n'est pas défini ou si son format est trop court pour la valeur)
+    fn tag_word_address(
+        &self,
+        id_tag: IdTag,
+        nb_words: usize,
+        value: impl fmt::Display,
+    ) -> Result<WordAddress, DbError> {
+        let Some(tag) = self.get_tag_from_id_tag(id_tag) else {
+            return Err(DbError::UnknownTag(id_tag));
+        };
+        let tag_nb_words = tag.t_format.nb_words();
+        if tag_nb_words != 0 && nb_words > tag_nb_words {
+            return Err(DbError::FormatMismatch {
+                word_address: tag.word_address,
+                t_format: tag.t_format,
+                value: value.to_string(),
+            });
+        }
+        Ok(tag.word_address)
+    }
+
+    /// Retourne `vec_u8` à écrire à partir de `word_address` où le contenu des [`WordAddress`]
+    /// gelées est remplacé par le contenu actuel de la [`Database`]
+    fn without_frozen_words(&self, word_address: WordAddress, vec_u8: &[u8]) -> Vec<u8> {
+        let mut vec_u8 = vec_u8.to_vec();
+        if !self.frozen_word_addresses.is_empty() {
+            let u8_address = 2 * word_address as usize;
+            for (offset, value) in vec_u8.iter_mut().enumerate() {
+                #[allow(clippy::cast_possible_truncation)]
+                let cur_word_address = word_address.wrapping_add((offset / 2) as WordAddress);
+                if self.is_frozen_word_address(cur_word_address) {
+                    *value = self.vec_u8[u8_address + offset];
                 }
-                vec_u8
-            } else {
-                value.to_vec()
-            };
-            self.set_vec_u8_to_word_address(id_user, tag.word_address, &value);
+            }
         }
+        vec_u8
     }
 
     /// Copie un `&[u8]` dans la [`Database`] selon [`WordAddress`]
     /// Cette fonction est le seul point d'entrée pour modifier le contenu de la [`Database`]
+    /// # Errors
+    /// [`DbError`] si l'écriture est hors de la [`Database`] ou refusée
     pub fn set_vec_u8_to_word_address(
         &mut self,
         id_user: IdUser,
         word_address: WordAddress,
         vec_u8: &[u8],
-    ) {
+    ) -> Result<(), DbError> {
+        // Une chaîne de caractères écrite partiellement est complétée selon la politique de son
+        // [`Tag`] (voir le module `string_paddings`)
+        let vec_u8 = &self.with_string_padding(word_address, vec_u8);
+        self.check_word_address_range(word_address, vec_u8.len())?;
+
+        // Les écritures anonymes peuvent être refusées (voir le module `anonymous_writes`)
+        if self.is_anonymous_write_refused(id_user, word_address, vec_u8.len().div_ceil(2)) {
+            return Err(DbError::PermissionDenied {
+                word_address,
+                user_name: self.get_id_user_name(id_user),
+            });
+        }
+
+        // Les écritures dans des [`WordAddress`] gelées sont ignorées
+        // et celles dans des [`Tag`] forcés sont mémorisées sans être appliquées
+        // Celles dans des [`Tag`] avec une rampe deviennent la consigne de la rampe
+        // Celles d'une autre source que le propriétaire du [`Tag`] sont signalées ou ignorées
+        let vec_u8 = &self.without_owner_violations(id_user, word_address, vec_u8);
+        let vec_u8 = &self.without_frozen_words(word_address, vec_u8);
+        let vec_u8 = &self.without_forced_tags(id_user, word_address, vec_u8);
+        let vec_u8 = &self.without_slew_rates(word_address, vec_u8);
+
         let mut u8_address = 2 * word_address as usize;
         for value in vec_u8 {
             self.vec_u8[u8_address] = *value;
             u8_address += 1;
         }
 
+        // Mise à jour de l'export en mémoire partagée
+        #[cfg(feature = "memmap")]
+        if let Some(shared_memory) = &mut self.shared_memory {
+            shared_memory.write(2 * word_address as usize, vec_u8);
+        }
+
         // Notification de la mise à jour
         let nb_words = (vec_u8.len() + 1) / 2;
+        if let Some(read_cache) = &self.read_cache {
+            read_cache.invalidate(word_address, nb_words);
+        }
+        self.record_tag_histories(word_address, nb_words);
+        self.count_writes(word_address, nb_words);
+        self.mark_dirty_words(id_user, word_address, nb_words);
         let tags = self.get_tags_from_word_address_area(word_address, nb_words);
+        let now = Instant::now();
         for tag in tags {
+            self.record_tag_write(tag.id_tag, now);
+            self.arm_pulse(&tag, now);
             self.user_write_tag(id_user, &tag);
+            capture::record(CaptureKind::TagChange(tag.id_tag), || {
+                format!(
+                    "{tag} = {} ({})",
+                    self.get_t_value_from_tag(id_user, &tag),
+                    self.get_id_user_name(id_user)
+                )
+            });
         }
+        Ok(())
     }
 }
 
@@ -230,28 +365,29 @@ mod tests {
         db.add_tag(&tag_vec_u8);
 
         // Init de tag_u16
-        db.set_value(ID_ANONYMOUS_USER, &tag_u16, "123");
+        db.set_value(ID_ANONYMOUS_USER, &tag_u16, "123").unwrap();
         assert_eq!(
             db.get_u16_from_id_tag(ID_ANONYMOUS_USER, tag_u16.id_tag),
             123
         );
 
         // Init de tag_i16
-        db.set_value(ID_ANONYMOUS_USER, &tag_i16, "-123");
+        db.set_value(ID_ANONYMOUS_USER, &tag_i16, "-123").unwrap();
         assert_eq!(
             db.get_i16_from_id_tag(ID_ANONYMOUS_USER, tag_i16.id_tag),
             -123
         );
 
         // Init de tag_f32
-        db.set_value(ID_ANONYMOUS_USER, &tag_f32, "-123.4");
+        db.set_value(ID_ANONYMOUS_USER, &tag_f32, "-123.4").unwrap();
         assert_f32_near!(
             db.get_f32_from_id_tag(ID_ANONYMOUS_USER, tag_f32.id_tag),
             -123.4
         );
 
         // Init de tag_vec_u8
-        db.set_value(ID_ANONYMOUS_USER, &tag_vec_u8, "TOTO");
+        db.set_value(ID_ANONYMOUS_USER, &tag_vec_u8, "TOTO")
+            .unwrap();
         assert_eq!(
             db.get_vec_u8_from_id_tag(ID_ANONYMOUS_USER, tag_vec_u8.id_tag, 5),
             vec![b'T', b'O', b'T', b'O', 0x00]
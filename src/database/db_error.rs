@@ -0,0 +1,103 @@
+//! Erreurs des écritures dans la [`Database`]
+//!
+//! Les setters de la [`Database`] (`Database::set_value`, `Database::set_u16_to_word_address`,
+//! ..., `Database::set_vec_u8_to_word_address`) retournent une [`DbError`] plutôt que d'ignorer
+//! silencieusement une écriture incohérente :
+//!
+//! * [`DbError::OutOfRange`] : Écriture au delà de la fin de la [`Database`]
+//! * [`DbError::FormatMismatch`] : Valeur incompatible avec le format du [`Tag`]
+//! * [`DbError::PermissionDenied`] : Écriture refusée à l'utilisateur (voir le module
+//!   `anonymous_writes`)
+//! * [`DbError::UnknownTag`] : Écriture selon un [`IdTag`] non défini
+//!
+//! Les écritures filtrées (gels, forçages, rampes, propriétaires) ne sont pas des erreurs.
+//!
+//! [`Database`]: super::Database
+//! [`Tag`]: super::Tag
+
+use std::fmt;
+
+use super::{IdTag, TFormat, WordAddress};
+
+/// Erreur d'une écriture dans la [`Database`](super::Database)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DbError {
+    /// Écriture de `nb_u8` octets à partir de `word_address` au delà de la fin de la database
+    OutOfRange {
+        word_address: WordAddress,
+        nb_u8: usize,
+    },
+
+    /// Valeur (au format string) incompatible avec le format `t_format` du tag
+    FormatMismatch {
+        word_address: WordAddress,
+        t_format: TFormat,
+        value: String,
+    },
+
+    /// Écriture refusée à l'utilisateur
+    PermissionDenied {
+        word_address: WordAddress,
+        user_name: String,
+    },
+
+    /// Écriture selon un [`IdTag`] non défini
+    UnknownTag(IdTag),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DbError::OutOfRange {
+                word_address,
+                nb_u8,
+            } => write!(
+                f,
+                "Écriture de {nb_u8} octets @{word_address:04X} hors de la database"
+            ),
+            DbError::FormatMismatch {
+                word_address,
+                t_format,
+                value,
+            } => write!(
+                f,
+                "Valeur '{value}' incompatible avec le format {t_format} @{word_address:04X}"
+            ),
+            DbError::PermissionDenied {
+                word_address,
+                user_name,
+            } => write!(
+                f,
+                "Écriture @{word_address:04X} refusée à l'utilisateur '{user_name}'"
+            ),
+            DbError::UnknownTag(id_tag) => write!(f, "Tag {id_tag} inconnu"),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<DbError> for String {
+    fn from(db_error: DbError) -> Self {
+        db_error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let db_error = DbError::OutOfRange {
+            word_address: 0x7FFF,
+            nb_u8: 4,
+        };
+        assert_eq!(
+            db_error.to_string(),
+            "Écriture de 4 octets @7FFF hors de la database"
+        );
+        let msg: String = DbError::UnknownTag(IdTag::new(1, 2, [0, 0, 0])).into();
+        assert!(msg.starts_with("Tag "));
+    }
+}
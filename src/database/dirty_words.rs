@@ -0,0 +1,187 @@
+//! Bitmap des mots modifiés ('dirty') dans des zones surveillées de la [`Database`]
+//!
+//! Les notifications des modifications sont faites par [`Tag`] : un utilisateur qui a besoin de
+//! savoir exactement quels mots d'une zone ont été modifiés (par exemple le `middleware`
+//! `MPackIn` pour les blocs de la zone 'pack-in') surveille cette zone avec un bitmap mis à
+//! jour par `Database::set_vec_u8_to_word_address` (seul point d'entrée des modifications de la
+//! [`Database`]), y compris pour les écritures par [`WordAddress`] qui ne sont pas alignées sur
+//! les [`Tag`].
+//!
+//! Comme pour les notifications, les modifications faites par l'utilisateur lui-même ne sont
+//! pas marquées dans ses bitmaps.
+//!
+//! [`Tag`]: super::Tag
+
+use super::{Database, IdUser, WordAddress};
+
+/// Nombre de bits d'un mot du bitmap
+const BITS: usize = u64::BITS as usize;
+
+/// Zone surveillée avec son bitmap des mots modifiés
+#[derive(Clone, Debug)]
+pub struct DirtyArea {
+    /// Première [`WordAddress`] de la zone
+    word_address: WordAddress,
+
+    /// Nombre de mots de la zone
+    nb_words: usize,
+
+    /// Bitmap des mots modifiés (bit `i` pour la [`WordAddress`] `word_address + i`)
+    bits: Vec<u64>,
+}
+
+impl DirtyArea {
+    /// Constructeur
+    fn new(word_address: WordAddress, nb_words: usize) -> Self {
+        Self {
+            word_address,
+            nb_words,
+            bits: vec![0; nb_words.div_ceil(BITS)],
+        }
+    }
+
+    /// Marque les mots modifiés par une écriture de `nb_words` mots à partir de `word_address`
+    fn mark(&mut self, word_address: WordAddress, nb_words: usize) {
+        let start = usize::from(word_address).max(usize::from(self.word_address));
+        let end = (usize::from(word_address) + nb_words)
+            .min(usize::from(self.word_address) + self.nb_words);
+        for index in start..end {
+            let offset = index - usize::from(self.word_address);
+            self.bits[offset / BITS] |= 1 << (offset % BITS);
+        }
+    }
+
+    /// Indique si un mot de la zone est modifié
+    fn is_dirty(&self, offset: usize) -> bool {
+        self.bits[offset / BITS] & (1 << (offset % BITS)) != 0
+    }
+
+    /// Plages contiguës de mots modifiés (première [`WordAddress`], nombre de mots), remises à 0
+    fn take_ranges(&mut self) -> Vec<(WordAddress, usize)> {
+        let mut ranges = vec![];
+        let mut option_start = None;
+        for offset in 0..=self.nb_words {
+            let is_dirty = offset < self.nb_words && self.is_dirty(offset);
+            match (option_start, is_dirty) {
+                (None, true) => option_start = Some(offset),
+                (Some(start), false) => {
+                    #[allow(clippy::cast_possible_truncation)]
+                    ranges.push((self.word_address + start as WordAddress, offset - start));
+                    option_start = None;
+                }
+                _ => (),
+            }
+        }
+        self.bits.fill(0);
+        ranges
+    }
+}
+
+impl Database {
+    /// Surveille les modifications des `nb_words` mots à partir de `word_address` pour un
+    /// utilisateur (sans effet si cette zone est déjà surveillée)
+    pub fn watch_dirty_words(
+        &mut self,
+        id_user: IdUser,
+        word_address: WordAddress,
+        nb_words: usize,
+    ) {
+        let dirty_areas = self.dirty_words.entry(id_user).or_default();
+        if !dirty_areas
+            .iter()
+            .any(|area| area.word_address == word_address && area.nb_words == nb_words)
+        {
+            dirty_areas.push(DirtyArea::new(word_address, nb_words));
+        }
+    }
+
+    /// Plages de mots modifiés (première [`WordAddress`], nombre de mots) dans les zones
+    /// surveillées par un utilisateur depuis le dernier appel, par ordre croissant d'adresse
+    pub fn take_dirty_word_ranges(&mut self, id_user: IdUser) -> Vec<(WordAddress, usize)> {
+        let mut ranges: Vec<(WordAddress, usize)> = self
+            .dirty_words
+            .get_mut(&id_user)
+            .map(|dirty_areas| {
+                dirty_areas
+                    .iter_mut()
+                    .flat_map(DirtyArea::take_ranges)
+                    .collect()
+            })
+            .unwrap_or_default();
+        ranges.sort_unstable();
+        ranges
+    }
+
+    /// Marque dans les bitmaps des autres utilisateurs les mots modifiés par une écriture de
+    /// `nb_words` mots à partir de `word_address` par `id_user`
+    pub(super) fn mark_dirty_words(
+        &mut self,
+        id_user: IdUser,
+        word_address: WordAddress,
+        nb_words: usize,
+    ) {
+        for (watcher, dirty_areas) in &mut self.dirty_words {
+            if *watcher == id_user {
+                continue;
+            }
+            for dirty_area in dirty_areas {
+                dirty_area.mark(word_address, nb_words);
+            }
+        }
+    }
+
+    /// Fin de la surveillance des zones d'un utilisateur (utilisateur libéré)
+    pub(super) fn unwatch_dirty_words(&mut self, id_user: IdUser) {
+        self.dirty_words.remove(&id_user);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    #[test]
+    fn test_dirty_area() {
+        let mut dirty_area = DirtyArea::new(0x0100, 100);
+        dirty_area.mark(0x00FE, 4); // 0x0100 et 0x0101 dans la zone
+        dirty_area.mark(0x0140, 2);
+        dirty_area.mark(0x0142, 1); // Contigu au précédent
+        dirty_area.mark(0x0163, 10); // Seul 0x0163 dans la zone
+        dirty_area.mark(0x0200, 1); // Hors zone
+        assert_eq!(
+            dirty_area.take_ranges(),
+            vec![(0x0100, 2), (0x0140, 3), (0x0163, 1)]
+        );
+        assert!(dirty_area.take_ranges().is_empty());
+    }
+
+    #[test]
+    fn test_dirty_words() {
+        let mut db = Database::default();
+        let id_user = db.get_id_user("WATCHER", false);
+        db.watch_dirty_words(id_user, 0x0020, 32);
+        db.watch_dirty_words(id_user, 0x0020, 32);
+        db.watch_dirty_words(id_user, 0x0080, 32);
+
+        // Écritures par un autre utilisateur (sans tag défini) et par l'utilisateur lui-même
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0085, &[1, 2, 3, 4])
+            .unwrap();
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0021, &[1, 2])
+            .unwrap();
+        db.set_vec_u8_to_word_address(id_user, 0x0030, &[1, 2])
+            .unwrap();
+        assert_eq!(
+            db.take_dirty_word_ranges(id_user),
+            vec![(0x0021, 1), (0x0085, 2)]
+        );
+        assert!(db.take_dirty_word_ranges(id_user).is_empty());
+
+        // Plus de surveillance après la libération de l'utilisateur
+        db.release_id_user(id_user);
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0021, &[1, 2])
+            .unwrap();
+        assert!(db.take_dirty_word_ranges(id_user).is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+//! Forçage de la valeur de [`Tag`] (simulation de la fonction 'force' des outils automates)
+//!
+//! Un [`Tag`] forcé conserve la valeur de forçage : les écritures ultérieures, quel que soit
+//! l'utilisateur, sont mémorisées mais ne sont pas appliquées. Lors du déforçage, la dernière
+//! valeur écrite pendant le forçage (s'il y en a une) est appliquée.
+//!
+//! La table des [`Tag`] forcés est consultée par `Database::set_vec_u8_to_word_address`
+//! (seul point d'entrée des modifications de la [`Database`]).
+
+use super::{Database, IdTag, IdUser, Tag, WordAddress};
+
+/// Forçage d'un [`Tag`]
+#[derive(Clone, Debug)]
+pub struct ForcedTag {
+    /// Dernière écriture (utilisateur, contenu du [`Tag`]) reçue pendant le forçage
+    pending: Option<(IdUser, Vec<u8>)>,
+}
+
+impl Database {
+    /// Force la valeur (au format string, voir `Database::set_value`) d'un [`Tag`]
+    /// Si le [`Tag`] est déjà forcé, la valeur de forçage est modifiée
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini ou si la valeur ne peut pas être écrite
+    pub fn force_tag(&mut self, id_user: IdUser, id_tag: IdTag, value: &str) -> Result<(), String> {
+        let Some(tag) = self.get_tag_from_id_tag(id_tag).cloned() else {
+            return Err(format!("Tag {id_tag} inconnu"));
+        };
+
+        // Écriture de la valeur de forçage hors forçage
+        let option_forced_tag = self.forced_tags.remove(&id_tag);
+        if let Err(db_error) = self.set_value(id_user, &tag, value) {
+            if let Some(forced_tag) = option_forced_tag {
+                self.forced_tags.insert(id_tag, forced_tag);
+            }
+            return Err(db_error.into());
+        }
+
+        let forced_tag = option_forced_tag.unwrap_or(ForcedTag { pending: None });
+        self.forced_tags.insert(id_tag, forced_tag);
+        Ok(())
+    }
+
+    /// Déforce un [`Tag`] et applique la dernière valeur écrite pendant le forçage
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas forcé ou si la dernière valeur écrite ne peut pas
+    /// être appliquée
+    pub fn unforce_tag(&mut self, id_tag: IdTag) -> Result<(), String> {
+        let Some(forced_tag) = self.forced_tags.remove(&id_tag) else {
+            return Err(format!("Tag {id_tag} non forcé"));
+        };
+        if let (Some((id_user, vec_u8)), Some(tag)) =
+            (forced_tag.pending, self.get_tag_from_id_tag(id_tag))
+        {
+            let word_address = tag.word_address;
+            self.set_vec_u8_to_word_address(id_user, word_address, &vec_u8)?;
+        }
+        Ok(())
+    }
+
+    /// Indique si un [`Tag`] est forcé
+    pub fn is_forced_tag(&self, id_tag: IdTag) -> bool {
+        self.forced_tags.contains_key(&id_tag)
+    }
+
+    /// Liste des [`Tag`] forcés (dans l'ordre des adresses)
+    pub fn get_forced_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self
+            .forced_tags
+            .keys()
+            .filter_map(|id_tag| self.get_tag_from_id_tag(*id_tag))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        tags
+    }
+
+    /// Dernière écriture mémorisée pendant le forçage d'un [`Tag`] (contenu du [`Tag`])
+    pub fn get_forced_tag_pending(&self, id_tag: IdTag) -> Option<&[u8]> {
+        self.forced_tags
+            .get(&id_tag)
+            .and_then(|forced_tag| forced_tag.pending.as_ref())
+            .map(|(_, vec_u8)| vec_u8.as_slice())
+    }
+
+    /// Mémorise l'écriture de `vec_u8` à partir de `word_address` dans les [`Tag`] forcés
+    /// concernés et retourne `vec_u8` où le contenu des [`Tag`] forcés est remplacé par le
+    /// contenu actuel de la [`Database`]
+    pub(super) fn without_forced_tags(
+        &mut self,
+        id_user: IdUser,
+        word_address: WordAddress,
+        vec_u8: &[u8],
+    ) -> Vec<u8> {
+        let mut vec_u8 = vec_u8.to_vec();
+        if self.forced_tags.is_empty() {
+            return vec_u8;
+        }
+        let nb_words = vec_u8.len().div_ceil(2);
+        let write_start = 2 * word_address as usize;
+        let write_end = write_start + vec_u8.len();
+        for tag in self.get_tags_from_word_address_area(word_address, nb_words) {
+            let Some(forced_tag) = self.forced_tags.get_mut(&tag.id_tag) else {
+                continue;
+            };
+            let tag_start = 2 * tag.word_address as usize;
+            let tag_end = tag_start + 2 * tag.t_format.nb_words();
+            let (start, end) = (tag_start.max(write_start), tag_end.min(write_end));
+            if start >= end {
+                continue;
+            }
+
+            // Contenu du [`Tag`] après l'écriture (mémorisée)
+            let mut pending = match forced_tag.pending.take() {
+                Some((_, pending)) => pending,
+                None => self.vec_u8[tag_start..tag_end].to_vec(),
+            };
+            pending[start - tag_start..end - tag_start]
+                .copy_from_slice(&vec_u8[start - write_start..end - write_start]);
+            vec_u8[start - write_start..end - write_start]
+                .copy_from_slice(&self.vec_u8[start..end]);
+            forced_tag.pending = Some((id_user, pending));
+        }
+        vec_u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_force_tag() {
+        let mut db = Database::default();
+        let tag = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U32,
+            ..Default::default()
+        };
+        db.add_tag(&tag);
+        let id_user = db.get_id_user("TEST", false);
+
+        // Forçage
+        db.force_tag(id_user, tag.id_tag, "1234").unwrap();
+        assert!(db.is_forced_tag(tag.id_tag));
+        assert_eq!(db.get_forced_tags().len(), 1);
+        assert_eq!(
+            db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0010),
+            1234
+        );
+
+        // Les écritures sont mémorisées mais pas appliquées
+        db.set_u32_to_word_address(id_user, 0x0010, 5678).unwrap();
+        assert_eq!(
+            db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0010),
+            1234
+        );
+        assert_eq!(
+            db.get_forced_tag_pending(tag.id_tag),
+            Some(5678_u32.to_be_bytes().as_slice())
+        );
+
+        // Écriture partielle (mot de poids faible seulement) et mot voisin non forcé
+        db.set_vec_u8_to_word_address(id_user, 0x0011, &[0x00, 0x01, 0xAB, 0xCD])
+            .unwrap();
+        assert_eq!(
+            db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0012),
+            0xABCD
+        );
+        assert_eq!(
+            db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0010),
+            1234
+        );
+
+        // Déforçage: La dernière écriture est appliquée
+        db.unforce_tag(tag.id_tag).unwrap();
+        assert!(!db.is_forced_tag(tag.id_tag));
+        assert_eq!(
+            db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0010),
+            0x0000_0001
+        );
+        assert!(db.unforce_tag(tag.id_tag).is_err());
+        assert!(db
+            .force_tag(id_user, IdTag::new(9, 9, [0, 0, 0]), "0")
+            .is_err());
+    }
+
+    #[test]
+    fn test_force_tag_bad_value() {
+        let mut db = Database::default();
+        let tag = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        };
+        db.add_tag(&tag);
+        let id_user = db.get_id_user("TEST", false);
+
+        // Valeur incompatible avec le format: Le tag n'est pas forcé
+        assert!(db.force_tag(id_user, tag.id_tag, "TOTO").is_err());
+        assert!(!db.is_forced_tag(tag.id_tag));
+
+        // Un forçage existant est conservé
+        db.force_tag(id_user, tag.id_tag, "12").unwrap();
+        assert!(db.force_tag(id_user, tag.id_tag, "-1").is_err());
+        assert!(db.is_forced_tag(tag.id_tag));
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 12);
+    }
+}
@@ -0,0 +1,102 @@
+//! Face avant virtuelle de l'ICOM (voyants et pictogrammes)
+//!
+//! L'état de la face avant est publié dans des [`Tag`] propres au simulateur pour montrer, lors
+//! des démonstrations, ce que voit l'opérateur devant l'ICOM (commande `panel` de la console
+//! ou lecture MODBUS/TCP) :
+//!
+//! * `ID_TAG_SIM_FRONT_PICTOS` : Pictogrammes affichés (1 bit par pictogramme), tels que reçus de
+//!   l'AFSEC+ dans `D_MENU_PICTOS`
+//! * `ID_TAG_SIM_FRONT_LEDS` : Voyants (`FRONT_LED_COM` allumé si la communication avec
+//!   l'AFSEC+ est établie, `FRONT_LED_ALARM` allumé si au moins un pictogramme est affiché)
+//!
+//! [`Tag`]: super::Tag
+
+use std::fmt;
+
+use super::{Database, IdUser, ID_TAG_SIM_FRONT_LEDS, ID_TAG_SIM_FRONT_PICTOS};
+
+/// Voyant de la face avant : Communication avec l'AFSEC+ établie
+pub const FRONT_LED_COM: u16 = 0x0001;
+
+/// Voyant de la face avant : Alarme (au moins un pictogramme affiché)
+pub const FRONT_LED_ALARM: u16 = 0x0002;
+
+/// État de la face avant de l'ICOM
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrontPanel {
+    /// Voyants allumés (`FRONT_LED_*`)
+    pub leds: u16,
+
+    /// Pictogrammes affichés (1 bit par pictogramme)
+    pub pictos: u32,
+}
+
+impl fmt::Display for FrontPanel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let led = |mask: u16| if self.leds & mask == 0 { "off" } else { "ON" };
+        let pictos: Vec<String> = (0..u32::BITS)
+            .filter(|bit| self.pictos & (1 << bit) != 0)
+            .map(|bit| bit.to_string())
+            .collect();
+        write!(
+            f,
+            "COM {}, ALARM {}, pictos 0x{:08X} [{}]",
+            led(FRONT_LED_COM),
+            led(FRONT_LED_ALARM),
+            self.pictos,
+            pictos.join(", ")
+        )
+    }
+}
+
+impl Database {
+    /// État de la face avant de l'ICOM
+    pub fn get_front_panel(&self, id_user: IdUser) -> FrontPanel {
+        FrontPanel {
+            leds: self.get_u16_from_id_tag(id_user, ID_TAG_SIM_FRONT_LEDS),
+            pictos: self.get_u32_from_id_tag(id_user, ID_TAG_SIM_FRONT_PICTOS),
+        }
+    }
+
+    /// Mise à jour des pictogrammes de la face avant (et du voyant `FRONT_LED_ALARM`)
+    pub fn set_front_panel_pictos(&mut self, id_user: IdUser, pictos: u32) {
+        self.set_sim_tag_u32(id_user, ID_TAG_SIM_FRONT_PICTOS, pictos);
+        self.set_front_panel_led(id_user, FRONT_LED_ALARM, pictos != 0);
+    }
+
+    /// Allume ou éteint un voyant de la face avant (écriture seulement si modifié)
+    pub fn set_front_panel_led(&mut self, id_user: IdUser, led: u16, on: bool) {
+        let leds = self.get_u16_from_id_tag(id_user, ID_TAG_SIM_FRONT_LEDS);
+        let new_leds = if on { leds | led } else { leds & !led };
+        if new_leds != leds {
+            self.set_sim_tag_u16(id_user, ID_TAG_SIM_FRONT_LEDS, new_leds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    #[test]
+    fn test_front_panel() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        assert_eq!(db.get_front_panel(ID_ANONYMOUS_USER), FrontPanel::default());
+
+        db.set_front_panel_led(ID_ANONYMOUS_USER, FRONT_LED_COM, true);
+        db.set_front_panel_pictos(ID_ANONYMOUS_USER, 0x0000_0005);
+        let front_panel = db.get_front_panel(ID_ANONYMOUS_USER);
+        assert_eq!(front_panel.leds, FRONT_LED_COM | FRONT_LED_ALARM);
+        assert_eq!(
+            front_panel.to_string(),
+            "COM ON, ALARM ON, pictos 0x00000005 [0, 2]"
+        );
+
+        // Plus de pictogramme: voyant d'alarme éteint
+        db.set_front_panel_pictos(ID_ANONYMOUS_USER, 0);
+        assert_eq!(db.get_front_panel(ID_ANONYMOUS_USER).leds, FRONT_LED_COM);
+    }
+}
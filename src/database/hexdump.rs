@@ -0,0 +1,123 @@
+//! Affichage brut (hexa) d'une plage de mots de la [`Database`] avec les limites des [`Tag`]
+//!
+//! Chaque ligne affiche `HEXDUMP_WORDS_PER_LINE` mots : le début d'un [`Tag`] est marqué par
+//! un `|` devant son premier mot et les [`Tag`] qui débutent dans la ligne sont listés en fin de
+//! ligne (adresse, [`IdTag`] et format), précédés du [`Tag`] dont la ligne débute au milieu (voir
+//! `Database::id_tag_at`). Un plan d'adressage décalé se repère ainsi directement dans le
+//! contenu des mots (commande `hexdump` de la console).
+//!
+//! [`IdTag`]: super::IdTag
+
+use super::{Database, Tag, WordAddress};
+
+/// Nombre de mots affichés par ligne
+pub const HEXDUMP_WORDS_PER_LINE: usize = 8;
+
+impl Database {
+    /// Lignes de l'affichage brut de `nb_words` mots à partir d'une [`WordAddress`]
+    /// # Errors
+    /// Message d'erreur si la plage dépasse la fin de la [`Database`]
+    pub fn hexdump(
+        &self,
+        word_address: WordAddress,
+        nb_words: usize,
+    ) -> Result<Vec<String>, String> {
+        let start = word_address as usize;
+        if nb_words == 0 || 2 * (start + nb_words) > self.vec_u8.len() {
+            return Err(format!(
+                "Plage @{word_address:04X} + {nb_words} mot(s) hors de la database"
+            ));
+        }
+        let mut lines = vec![];
+        for line_start in (start..start + nb_words).step_by(HEXDUMP_WORDS_PER_LINE) {
+            let line_end = (line_start + HEXDUMP_WORDS_PER_LINE).min(start + nb_words);
+            let mut words = String::new();
+            let mut ascii = String::new();
+            let mut tags: Vec<&Tag> = vec![];
+            // [`Tag`] commencé avant la ligne
+            if let Some((id_tag, offset_in_tag)) = self.id_tag_at(line_start as WordAddress) {
+                if offset_in_tag > 0 {
+                    tags.extend(self.get_tag_from_id_tag(id_tag));
+                }
+            }
+            for address in line_start..line_end {
+                let option_tag = self.get_tag_from_word_address(address as WordAddress);
+                words.push(if option_tag.is_some() { '|' } else { ' ' });
+                for octet in &self.vec_u8[2 * address..2 * address + 2] {
+                    words += &format!("{octet:02X}");
+                    ascii.push(if octet.is_ascii_graphic() || *octet == b' ' {
+                        *octet as char
+                    } else {
+                        '.'
+                    });
+                }
+                tags.extend(option_tag);
+            }
+            // Alignement de la dernière ligne incomplète
+            for _ in line_end..line_start + HEXDUMP_WORDS_PER_LINE {
+                words += "     ";
+                ascii += "  ";
+            }
+            let tags = tags
+                .iter()
+                .map(|tag| format!("@{:04X}={} {}", tag.word_address, tag.id_tag, tag.t_format))
+                .collect::<Vec<String>>()
+                .join(", ");
+            lines.push(
+                format!("@{line_start:04X} {words}  {ascii}  {tags}")
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{IdTag, ID_ANONYMOUS_USER};
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_hexdump() {
+        let mut db = Database::default();
+        let tag_u32 = Tag {
+            word_address: 0x0011,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U32,
+            ..Default::default()
+        };
+        db.add_tag(&tag_u32);
+        let tag_string = Tag {
+            word_address: 0x0013,
+            id_tag: IdTag::new(1, 2, [0, 0, 0]),
+            t_format: TFormat::VecU8(4),
+            ..Default::default()
+        };
+        db.add_tag(&tag_string);
+        db.set_u32_to_word_address(ID_ANONYMOUS_USER, 0x0011, 0x1234_5678)
+            .unwrap();
+        db.set_value(ID_ANONYMOUS_USER, &tag_string, "AB").unwrap();
+
+        let lines = db.hexdump(0x0010, 10).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            format!(
+                "@0010  0000|1234 5678|4142 0000 0000 0000 0000  ...4VxAB........  \
+                 @0011={} U32, @0013={} VecU8(4)",
+                tag_u32.id_tag, tag_string.id_tag
+            )
+        );
+        assert!(lines[1].starts_with("@0018  0000 0000"));
+
+        // Ligne qui débute au milieu d'un tag
+        let lines = db.hexdump(0x0012, 1).unwrap();
+        assert!(lines[0].starts_with("@0012  5678 "));
+        assert!(lines[0].ends_with(&format!("Vx{}@0011={} U32", " ".repeat(16), tag_u32.id_tag)));
+        assert!(db.hexdump(0x7FFF, 2).is_err());
+        assert!(db.hexdump(0x0010, 0).is_err());
+    }
+}
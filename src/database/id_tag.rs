@@ -1,6 +1,7 @@
 //! Identificateur pour référencer un `Tag` de la database (zone + `num_tag` + indices)
 
 use std::fmt;
+use std::str::FromStr;
 
 /// Référence unique d'un `Tag` de la database (zone +  `num_tag` + indices)
 #[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -22,8 +23,31 @@ impl fmt::Display for IdTag {
     }
 }
 
+impl FromStr for IdTag {
+    type Err = String;
+
+    /// Accepte le format de l'affichage `<zone>/<tag>:<indice>:<indice>:<indice>` (zone en décimal,
+    /// tag et indices en hexa). Les indices absents sont à 0 (`5/0F45`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Tag '{s}' incorrect (ex: '5/0F45' ou '5/0F45:00:00:03')");
+        let (zone, rest) = s.trim().split_once('/').ok_or_else(err)?;
+        let mut parts = rest.split(':');
+        let zone = zone.parse::<u8>().map_err(|_| err())?;
+        let num_tag =
+            u16::from_str_radix(parts.next().unwrap_or_default(), 16).map_err(|_| err())?;
+        let mut indices = [0; 3];
+        for (index, indice) in parts.enumerate() {
+            if index >= indices.len() {
+                return Err(err());
+            }
+            indices[index] = u8::from_str_radix(indice, 16).map_err(|_| err())?;
+        }
+        Ok(IdTag::new(zone, num_tag, indices))
+    }
+}
+
 impl IdTag {
-    pub fn new(zone: u8, tag: u16, indices: [u8; 3]) -> Self {
+    pub const fn new(zone: u8, tag: u16, indices: [u8; 3]) -> Self {
         Self {
             zone,
             num_tag: tag,
@@ -33,3 +57,162 @@ impl IdTag {
         }
     }
 }
+
+/// Motif de sélection d'[`IdTag`] : chaque champ à `None` accepte n'importe quelle valeur
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IdTagPattern {
+    pub zone: Option<u8>,
+    pub num_tag: Option<u16>,
+    pub indices: [Option<u8>; 3],
+}
+
+impl fmt::Display for IdTagPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.zone {
+            Some(zone) => write!(f, "{zone}")?,
+            None => write!(f, "*")?,
+        }
+        match self.num_tag {
+            Some(num_tag) => write!(f, "/{num_tag:04X}")?,
+            None => write!(f, "/*")?,
+        }
+        for indice in self.indices {
+            match indice {
+                Some(indice) => write!(f, ":{indice:02X}")?,
+                None => write!(f, ":*")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for IdTagPattern {
+    type Err = String;
+
+    /// Accepte le format de l'affichage `<zone>/<tag>:<indice>:<indice>:<indice>` (zone en décimal,
+    /// tag et indices en hexa, `*` pour n'importe quelle valeur). Les indices absents acceptent
+    /// n'importe quelle valeur (`5/0F45` ou `5/*`) et `*` seul accepte tous les [`IdTag`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err =
+            || format!("Motif de tag '{s}' incorrect (ex: '5/0F45', '5/*' ou '5/0F45:*:*:03')");
+        let s = s.trim();
+        if s == "*" {
+            return Ok(IdTagPattern::ANY);
+        }
+        let (zone, rest) = s.split_once('/').ok_or_else(err)?;
+        let mut parts = rest.split(':');
+        let num_tag = parts.next().unwrap_or_default();
+        let mut pattern = IdTagPattern {
+            zone: match zone {
+                "*" => None,
+                zone => Some(zone.parse::<u8>().map_err(|_| err())?),
+            },
+            num_tag: match num_tag {
+                "*" => None,
+                num_tag => Some(u16::from_str_radix(num_tag, 16).map_err(|_| err())?),
+            },
+            indices: [None; 3],
+        };
+        for (index, indice) in parts.enumerate() {
+            if index >= pattern.indices.len() {
+                return Err(err());
+            }
+            pattern.indices[index] = match indice {
+                "*" => None,
+                indice => Some(u8::from_str_radix(indice, 16).map_err(|_| err())?),
+            };
+        }
+        Ok(pattern)
+    }
+}
+
+impl IdTagPattern {
+    /// Motif qui accepte tous les [`IdTag`]
+    pub const ANY: Self = Self {
+        zone: None,
+        num_tag: None,
+        indices: [None; 3],
+    };
+
+    /// Motif pour un `num_tag` d'une zone, quels que soient les indices
+    pub const fn new(zone: u8, num_tag: u16) -> Self {
+        Self {
+            zone: Some(zone),
+            num_tag: Some(num_tag),
+            indices: [None; 3],
+        }
+    }
+
+    /// Indique si un [`IdTag`] correspond au motif
+    pub fn matches(&self, id_tag: IdTag) -> bool {
+        let indices = [id_tag.indice_0, id_tag.indice_1, id_tag.indice_2];
+        self.zone.is_none_or(|zone| zone == id_tag.zone)
+            && self.num_tag.is_none_or(|num_tag| num_tag == id_tag.num_tag)
+            && self
+                .indices
+                .iter()
+                .zip(indices)
+                .all(|(pattern, indice)| pattern.is_none_or(|pattern| pattern == indice))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_tag_pattern() {
+        let id_tag = IdTag::new(5, 0x0F45, [0, 0, 3]);
+        assert!(IdTagPattern::ANY.matches(id_tag));
+        assert!(IdTagPattern::new(5, 0x0F45).matches(id_tag));
+        assert!(!IdTagPattern::new(4, 0x0F45).matches(id_tag));
+        assert!(!IdTagPattern::new(5, 0x0F46).matches(id_tag));
+
+        let pattern = IdTagPattern {
+            indices: [None, None, Some(3)],
+            ..IdTagPattern::new(5, 0x0F45)
+        };
+        assert!(pattern.matches(id_tag));
+        assert!(!pattern.matches(IdTag::new(5, 0x0F45, [0, 0, 4])));
+        assert_eq!(format!("{pattern}"), "5/0F45:*:*:03");
+        assert_eq!(format!("{}", IdTagPattern::ANY), "*/*:*:*:*");
+    }
+
+    #[test]
+    fn test_id_tag_from_str() {
+        let id_tag = IdTag::new(5, 0x0F45, [0, 0, 3]);
+        assert_eq!(IdTag::from_str(&id_tag.to_string()), Ok(id_tag));
+        assert_eq!(
+            IdTag::from_str("5/0F45"),
+            Ok(IdTag::new(5, 0x0F45, [0, 0, 0]))
+        );
+        assert!(IdTag::from_str("5/*").is_err());
+        assert!(IdTag::from_str("5").is_err());
+        assert!(IdTag::from_str("5/0F45:00:00:00:00").is_err());
+    }
+
+    #[test]
+    fn test_id_tag_pattern_from_str() {
+        assert_eq!(IdTagPattern::from_str("*"), Ok(IdTagPattern::ANY));
+        assert_eq!(
+            IdTagPattern::from_str("5/0F45"),
+            Ok(IdTagPattern::new(5, 0x0F45))
+        );
+        let pattern = IdTagPattern::from_str("5/0F45:*:*:03").unwrap();
+        assert_eq!(format!("{pattern}"), "5/0F45:*:*:03");
+        assert_eq!(
+            IdTagPattern::from_str("5/*"),
+            Ok(IdTagPattern {
+                zone: Some(5),
+                ..IdTagPattern::ANY
+            })
+        );
+        let pattern = IdTagPattern::from_str(&IdTagPattern::ANY.to_string()).unwrap();
+        assert_eq!(pattern, IdTagPattern::ANY);
+
+        assert!(IdTagPattern::from_str("5").is_err());
+        assert!(IdTagPattern::from_str("300/0001").is_err());
+        assert!(IdTagPattern::from_str("5/XYZ").is_err());
+        assert!(IdTagPattern::from_str("5/0001:00:00:00:00").is_err());
+    }
+}
@@ -25,6 +25,12 @@ pub const ID_ANONYMOUS_USER: IdUser = 0; // Doit être le 0 dans la table des ut
 /// Nom d'un utilisateur non identifié
 const ANONYMOUS_USER_NAME: &str = "Anonymous user";
 
+/// Groupe des utilisateurs clients MODBUS/TCP
+pub const GROUP_MODBUS: &str = "MODBUS";
+
+/// Groupe des utilisateurs de la communication avec l'AFSEC+
+pub const GROUP_AFSEC: &str = "AFSEC";
+
 /// Durée pendant laquelle on filtre les modifications qui semblent identiques
 const DURATION_CHANGE_FILTER_SECS: f32 = 1.0;
 
@@ -39,6 +45,17 @@ pub struct User {
 
     /// Premier index dans `vec_changes` qui n'a pas été notifié à cet utilisateur
     next_notification_index: usize,
+
+    /// Groupe de l'utilisateur (vide si aucun groupe)
+    /// Les modifications faites par les utilisateurs d'un même groupe peuvent être filtrées
+    /// ensemble (voir `IdUsers::exclude_group_changes`)
+    group: String,
+
+    /// Groupes d'utilisateurs dont les modifications ne sont pas notifiées à cet utilisateur
+    excluded_groups: Vec<String>,
+
+    /// Booléen à true si l'utilisateur a été libéré (voir `IdUsers::release_id_user`)
+    released: bool,
 }
 
 /// Structure pour mémoriser un changement dans la database
@@ -74,8 +91,7 @@ impl Default for IdUsers {
         // L'utilisateur anonyme est en 0
         let anonymous_user = User {
             name: ANONYMOUS_USER_NAME.to_string(),
-            use_notification: false,
-            next_notification_index: 0,
+            ..Default::default()
         };
         let vec_users = vec![anonymous_user];
         Self {
@@ -91,15 +107,80 @@ impl IdUsers {
     /// Un utilisateur s'identifie avec un nom et indique s'il souhaite pouvoir être notifié
     /// des changements dans la database par `get_change`
     pub fn get_id_user(&mut self, name: &str, use_notification: bool) -> IdUser {
-        let new_id_user = self.vec_users.len();
+        self.get_id_user_in_group(name, use_notification, "")
+    }
+
+    /// Retourne un nouveau [`IdUser`] membre d'un groupe d'utilisateurs
+    pub fn get_id_user_in_group(
+        &mut self,
+        name: &str,
+        use_notification: bool,
+        group: &str,
+    ) -> IdUser {
         let next_notification_index = self.vec_changes.len();
         let new_user = User {
             name: name.to_string(),
             use_notification,
             next_notification_index,
+            group: group.to_string(),
+            excluded_groups: vec![],
+            released: false,
         };
+        // Réutilisation d'un utilisateur libéré (l'utilisateur anonyme n'est jamais libéré)
+        if let Some(id_user) = self.vec_users.iter().position(|user| user.released) {
+            self.vec_users[id_user] = new_user;
+            return id_user;
+        }
         self.vec_users.push(new_user);
-        new_id_user
+        self.vec_users.len() - 1
+    }
+
+    /// Libère un [`IdUser`] qui n'est plus utilisé (par exemple, à la déconnexion d'un client
+    /// MODBUS/TCP) : il n'est plus notifié et il sera réutilisé pour un nouvel utilisateur
+    pub fn release_id_user(&mut self, id_user: IdUser) {
+        if id_user == ID_ANONYMOUS_USER {
+            return;
+        }
+        if let Some(user) = self.vec_users.get_mut(id_user) {
+            *user = User {
+                released: true,
+                ..Default::default()
+            };
+            self.purge_changes();
+        }
+    }
+
+    /// Nombre d'utilisateurs identifiés (hors utilisateur anonyme et utilisateurs libérés)
+    pub fn get_nb_users(&self) -> usize {
+        self.vec_users
+            .iter()
+            .skip(1)
+            .filter(|user| !user.released)
+            .count()
+    }
+
+    /// Les modifications faites par les utilisateurs du groupe `group` ne seront plus notifiées
+    /// à l'utilisateur `id_user`
+    pub fn exclude_group_changes(&mut self, id_user: IdUser, group: &str) {
+        if let Some(user) = self.vec_users.get_mut(id_user) {
+            if !group.is_empty() && !user.excluded_groups.iter().any(|g| g == group) {
+                user.excluded_groups.push(group.to_string());
+            }
+        }
+    }
+
+    /// Indique si les modifications faites par `id_user_change` sont filtrées pour `id_user`
+    /// selon les groupes exclus par `id_user`
+    fn is_excluded_change(&self, id_user: IdUser, id_user_change: IdUser) -> bool {
+        match (
+            self.vec_users.get(id_user),
+            self.vec_users.get(id_user_change),
+        ) {
+            (Some(user), Some(user_change)) => {
+                !user_change.group.is_empty() && user.excluded_groups.contains(&user_change.group)
+            }
+            _ => false,
+        }
     }
 
     /// Retourne le nom d'un [`IdUser`]
@@ -111,6 +192,33 @@ impl IdUsers {
         }
     }
 
+    /// Retourne le groupe d'un [`IdUser`] (vide si aucun groupe)
+    pub fn get_id_user_group(&self, id_user: IdUser) -> Option<&str> {
+        self.vec_users.get(id_user).map(|user| user.group.as_str())
+    }
+
+    /// Nombre de changements dans l'historique des changements
+    pub fn get_nb_changes(&self) -> usize {
+        self.vec_changes.len()
+    }
+
+    /// Nombre de changements restant à notifier pour chaque utilisateur du système de
+    /// notification (nom de l'utilisateur, nombre de changements)
+    pub fn get_pending_changes(&self) -> Vec<(String, usize)> {
+        self.vec_users
+            .iter()
+            .filter(|user| user.use_notification)
+            .map(|user| {
+                (
+                    user.name.clone(),
+                    self.vec_changes
+                        .len()
+                        .saturating_sub(user.next_notification_index),
+                )
+            })
+            .collect()
+    }
+
     /// Purge les nb premiers changements dans l'historique des changements
     fn do_purge_changes(&mut self, nb: usize) {
         // Supprime les nb premiers éléments de vec_changes
@@ -128,6 +236,24 @@ impl IdUsers {
         }
     }
 
+    /// Supprime de l'historique des changements ceux d'un [`IdTag`] (suppression du [`Tag`])
+    pub fn remove_changes_of_id_tag(&mut self, id_tag: IdTag) {
+        let mut index = 0;
+        while index < self.vec_changes.len() {
+            if self.vec_changes[index].id_tag != id_tag {
+                index += 1;
+                continue;
+            }
+            self.vec_changes.remove(index);
+            // Les changements suivants sont décalés d'un rang
+            for user in &mut self.vec_users {
+                if user.next_notification_index > index {
+                    user.next_notification_index -= 1;
+                }
+            }
+        }
+    }
+
     /// Purge l'historique des changements lorsque tous les utilisateurs ont été notifiés
     fn purge_changes(&mut self) {
         if self.vec_changes.is_empty() {
@@ -222,6 +348,7 @@ impl IdUsers {
             // A notifier ?
             if (include_anonymous_changes || notification.id_user != ID_ANONYMOUS_USER)
                 && (include_my_changes || notification.id_user != id_user)
+                && !self.is_excluded_change(id_user, notification.id_user)
             {
                 // Mémorisation du dernier offset non notifié à cet utilisateur
                 self.vec_users[id_user].next_notification_index = notification_offset + 1;
@@ -248,6 +375,35 @@ impl Database {
         self.id_users.get_id_user(name, use_notification)
     }
 
+    /// Retourne un [`IdUser`] membre d'un groupe d'utilisateurs (voir `Database::get_id_user`)
+    pub fn get_id_user_in_group(
+        &mut self,
+        name: &str,
+        use_notification: bool,
+        group: &str,
+    ) -> IdUser {
+        self.id_users
+            .get_id_user_in_group(name, use_notification, group)
+    }
+
+    /// Les modifications faites par les utilisateurs du groupe `group` ne seront plus notifiées
+    /// à l'utilisateur `id_user` (par exemple, pour ne pas notifier à un client MODBUS les
+    /// modifications faites par les autres clients MODBUS)
+    pub fn exclude_group_changes(&mut self, id_user: IdUser, group: &str) {
+        self.id_users.exclude_group_changes(id_user, group);
+    }
+
+    /// Libère un [`IdUser`] qui n'est plus utilisé (voir `IdUsers::release_id_user`)
+    pub fn release_id_user(&mut self, id_user: IdUser) {
+        self.id_users.release_id_user(id_user);
+        self.unwatch_dirty_words(id_user);
+    }
+
+    /// Nombre d'utilisateurs identifiés de la [`Database`] (hors utilisateur anonyme)
+    pub fn get_nb_id_users(&self) -> usize {
+        self.id_users.get_nb_users()
+    }
+
     /// Retourne le nom d'un [`IdUser`].
     /// Si [`IdUser`] n'est pas identifié, retourne `ANONYMOUS_USER_NAME`
     pub fn get_id_user_name(&self, id_user: IdUser) -> String {
@@ -257,6 +413,22 @@ impl Database {
         }
     }
 
+    /// Retourne le groupe d'un [`IdUser`] (vide si aucun groupe ou [`IdUser`] non identifié)
+    pub fn get_id_user_group(&self, id_user: IdUser) -> &str {
+        self.id_users.get_id_user_group(id_user).unwrap_or_default()
+    }
+
+    /// Nombre de changements dans l'historique des notifications
+    pub fn get_nb_notification_changes(&self) -> usize {
+        self.id_users.get_nb_changes()
+    }
+
+    /// Taille de la file des notifications en attente de chaque utilisateur du système de
+    /// notification (nom de l'utilisateur, nombre de notifications)
+    pub fn get_notification_queue_sizes(&self) -> Vec<(String, usize)> {
+        self.id_users.get_pending_changes()
+    }
+
     /// Informe qu'un utilisateur accède à la [`Database`] en ÉCRITURE
     /// (Ici database est mutable)
     pub fn user_write_tag(&mut self, id_user: IdUser, tag: &Tag) {
@@ -309,6 +481,42 @@ mod tests {
         let id_user_2 = db.get_id_user("user2", false);
         assert!(id_user_1 != id_user_2);
         assert_eq!(db.get_id_user_name(id_user_2), "user2");
+        assert_eq!(db.get_nb_id_users(), 2);
+    }
+
+    #[test]
+    fn test_release_id_user() {
+        let mut db = Database::default();
+        let tag = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        };
+        db.add_tag(&tag);
+
+        let id_user_1 = db.get_id_user("user1", true);
+        let id_user_2 = db.get_id_user("user2", true);
+        db.set_u16_to_id_tag(ID_ANONYMOUS_USER, tag.id_tag, 1)
+            .unwrap();
+        assert_eq!(db.get_nb_notification_changes(), 1);
+
+        // Un utilisateur libéré ne retient plus l'historique des modifications
+        assert!(db.get_change(id_user_2, true, true).is_some());
+        db.release_id_user(id_user_1);
+        assert_eq!(db.get_nb_id_users(), 1);
+        assert_eq!(db.get_nb_notification_changes(), 0);
+        assert!(db.get_change(id_user_1, true, true).is_none());
+
+        // L'IdUser libéré est réutilisé
+        let id_user_3 = db.get_id_user("user3", true);
+        assert_eq!(id_user_3, id_user_1);
+        assert_eq!(db.get_id_user_name(id_user_3), "user3");
+        assert_eq!(db.get_nb_id_users(), 2);
+
+        // L'utilisateur anonyme n'est jamais libéré
+        db.release_id_user(ID_ANONYMOUS_USER);
+        assert_ne!(db.get_id_user("user4", false), ID_ANONYMOUS_USER);
     }
 
     #[test]
@@ -340,7 +548,8 @@ mod tests {
         assert!(db.get_change(id_user, true, true).is_none());
 
         // Mise à jour de la database par un utilisateur anonyme
-        db.set_u16_to_id_tag(ID_ANONYMOUS_USER, tag_1.id_tag, 1);
+        db.set_u16_to_id_tag(ID_ANONYMOUS_USER, tag_1.id_tag, 1)
+            .unwrap();
 
         // Pas d'historique pour user s'il n'est pas intéressé par les modifications faites
         // par les utilisateurs anonymes
@@ -352,7 +561,8 @@ mod tests {
 
         // Nouvelle mise à jour de la database par un utilisateur anonyme
         // Autre tag que le précédent sinon filtrage
-        db.set_u16_to_id_tag(ID_ANONYMOUS_USER, tag_2.id_tag, 2);
+        db.set_u16_to_id_tag(ID_ANONYMOUS_USER, tag_2.id_tag, 2)
+            .unwrap();
 
         // Cette modification est notifiée à user s'il s'intéresse aux modifications faites
         // par les utilisateurs anonymes
@@ -395,7 +605,7 @@ mod tests {
         let id_user = db.get_id_user("user", true);
 
         // Mise à jour de la database par user
-        db.set_u16_to_id_tag(id_user, tag_1.id_tag, 1);
+        db.set_u16_to_id_tag(id_user, tag_1.id_tag, 1).unwrap();
 
         // Pas d'historique pour user s'il n'est pas intéressé par ses propres modifications
         assert!(db.get_change(id_user, false, true).is_none());
@@ -406,7 +616,7 @@ mod tests {
 
         // Nouvelle mise à jour de la database par un user
         // Autre tag sinon filtrage
-        db.set_u16_to_id_tag(id_user, tag_2.id_tag, 2);
+        db.set_u16_to_id_tag(id_user, tag_2.id_tag, 2).unwrap();
 
         // Cette modification est notifiée à user s'il s'intéresse à ses propres modifications
         let option_notification_change = db.get_change(id_user, true, true);
@@ -448,10 +658,10 @@ mod tests {
         let id_user_2 = db.get_id_user("user2", true);
 
         // Mise à jour de la database par user_1
-        db.set_u16_to_id_tag(id_user_1, tag_1.id_tag, 1);
+        db.set_u16_to_id_tag(id_user_1, tag_1.id_tag, 1).unwrap();
 
         // Mise à jour de la database par user_2
-        db.set_u16_to_id_tag(id_user_2, tag_2.id_tag, 2);
+        db.set_u16_to_id_tag(id_user_2, tag_2.id_tag, 2).unwrap();
 
         // User_1 est notifié de la modif de user_2
         let option_notification_change = db.get_change(id_user_1, false, true);
@@ -489,7 +699,7 @@ mod tests {
         let id_user = db.get_id_user("user", true);
 
         // Mise à jour de la database par user
-        db.set_u16_to_id_tag(id_user, tag.id_tag, 1);
+        db.set_u16_to_id_tag(id_user, tag.id_tag, 1).unwrap();
 
         // User qui ne s'identifie pas correctement
         let id_unknown_user = 0x1234;
@@ -504,7 +714,8 @@ mod tests {
         assert!(db.get_change(id_user, true, true).is_none());
 
         // Modif de la database pas ce user non identifié
-        db.set_u16_to_id_tag(id_unknown_user, tag.id_tag, 2);
+        db.set_u16_to_id_tag(id_unknown_user, tag.id_tag, 2)
+            .unwrap();
 
         // Toujours pas d'historique pour user non identifié
         assert!(db.get_change(id_unknown_user, true, true).is_none());
@@ -545,7 +756,8 @@ mod tests {
 
         // Mise à jour de la database par user
         // La mise à jour effectuée modifie les 2 tags tag_1 et tag_2
-        db.set_u32_to_id_tag(id_user, id_tag_1, 0x0001_0002);
+        db.set_u32_to_id_tag(id_user, id_tag_1, 0x0001_0002)
+            .unwrap();
 
         // L'utilisateur doit pouvoir retrouver les notifications pour 2 tags modifiés
         let notif_1 = db.get_change(id_user, true, true);
@@ -590,12 +802,17 @@ mod tests {
         let id_user = db.get_id_user("user", true);
 
         // Mise à jour de la database par user
-        db.set_u16_to_id_tag(id_user, tag_1.id_tag, 1);
-        db.set_u16_to_id_tag(id_user, tag_2.id_tag, 2);
+        db.set_u16_to_id_tag(id_user, tag_1.id_tag, 1).unwrap();
+        db.set_u16_to_id_tag(id_user, tag_2.id_tag, 2).unwrap();
 
         // Mesure de la taille de l'historique des changements avant les notifications
         // Ici 2 changements dans l'historique
         let start_vec_changes_len = db.id_users.vec_changes.len();
+        assert_eq!(db.get_nb_notification_changes(), 2);
+        assert_eq!(
+            db.get_notification_queue_sizes(),
+            vec![("user".to_string(), 2)]
+        );
 
         // L'utilisateur récupère toutes les notifications
         loop {
@@ -603,9 +820,13 @@ mod tests {
                 break;
             }
         }
+        assert_eq!(
+            db.get_notification_queue_sizes(),
+            vec![("user".to_string(), 0)]
+        );
 
         // La purge se fait lorsqu'un nouveau changement est fait
-        db.set_u16_to_id_tag(id_user, tag_1.id_tag, 2);
+        db.set_u16_to_id_tag(id_user, tag_1.id_tag, 2).unwrap();
 
         // La taille de l'historique des changements doit avoir diminué (plus que 1)
         assert!(db.id_users.vec_changes.len() < start_vec_changes_len);
@@ -640,10 +861,10 @@ mod tests {
         let id_user_2 = db.get_id_user("user2", true);
 
         // Mise à jour de la database par user1
-        db.set_u16_to_id_tag(id_user_1, tag_1.id_tag, 1);
-        db.set_u16_to_id_tag(id_user_2, tag_2.id_tag, 2);
-        db.set_u16_to_id_tag(id_user_1, tag_1.id_tag, 3);
-        db.set_u16_to_id_tag(id_user_2, tag_2.id_tag, 4);
+        db.set_u16_to_id_tag(id_user_1, tag_1.id_tag, 1).unwrap();
+        db.set_u16_to_id_tag(id_user_2, tag_2.id_tag, 2).unwrap();
+        db.set_u16_to_id_tag(id_user_1, tag_1.id_tag, 3).unwrap();
+        db.set_u16_to_id_tag(id_user_2, tag_2.id_tag, 4).unwrap();
 
         // Mesure de la taille de l'historique des changements avant les notifications
         let start_vec_changes_len = db.id_users.vec_changes.len();
@@ -658,9 +879,55 @@ mod tests {
         }
 
         // La purge se fait lorsqu'un nouveau changement est fait
-        db.set_u16_to_id_tag(id_user_1, tag_1.id_tag, 5);
+        db.set_u16_to_id_tag(id_user_1, tag_1.id_tag, 5).unwrap();
 
         // La taille de l'historique des changements doit avoir diminué (plus que 1)
         assert!(db.id_users.vec_changes.len() < start_vec_changes_len);
     }
+
+    #[test]
+    fn test_group_notifications() {
+        let mut db = Database::default();
+
+        // Création de tags
+        let tag_1 = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        };
+        db.add_tag(&tag_1);
+        let tag_2 = Tag {
+            word_address: 0x0020,
+            id_tag: IdTag::new(2, 2, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        };
+        db.add_tag(&tag_2);
+
+        // 2 clients MODBUS qui ignorent les modifications du groupe et un autre utilisateur
+        let id_client_1 = db.get_id_user_in_group("client1", true, GROUP_MODBUS);
+        let id_client_2 = db.get_id_user_in_group("client2", true, GROUP_MODBUS);
+        db.exclude_group_changes(id_client_1, GROUP_MODBUS);
+        db.exclude_group_changes(id_client_2, GROUP_MODBUS);
+        let id_afsec = db.get_id_user("afsec", true);
+
+        // Modification par un client MODBUS: Notifiée uniquement à l'autre utilisateur
+        db.set_u16_to_id_tag(id_client_1, tag_1.id_tag, 1).unwrap();
+        assert!(db.get_change(id_client_1, true, true).is_none());
+        assert!(db.get_change(id_client_2, true, true).is_none());
+        let notification_change = db.get_change(id_afsec, false, true).unwrap();
+        assert_eq!(notification_change.id_user, id_client_1);
+
+        // Modification par l'autre utilisateur: Notifiée aux clients MODBUS
+        db.set_u16_to_id_tag(id_afsec, tag_2.id_tag, 2).unwrap();
+        assert_eq!(
+            db.get_change(id_client_1, true, true).unwrap().id_tag,
+            tag_2.id_tag
+        );
+        assert_eq!(
+            db.get_change(id_client_2, true, true).unwrap().id_tag,
+            tag_2.id_tag
+        );
+    }
 }
@@ -0,0 +1,135 @@
+//! Contrôle de cohérence des tables internes de la [`Database`]
+//!
+//! `Database::check_invariants` vérifie à la demande (au démarrage et par la commande `check`
+//! de la console) que :
+//!
+//! * `hash_word_address` et `hash_tag` se référencent mutuellement (pas d'entrée orpheline)
+//! * Aucun [`Tag`] ne dépasse la dernière [`WordAddress`] (0x7FFF)
+//! * Le format de chaque [`Tag`] est connu et occupe au moins un mot
+//! * Les tables annexes (groupes, forçages, rampes, historiques, ...) ne référencent que des
+//!   [`Tag`] définis
+//!
+//! Une incohérence révèle une manipulation incorrecte des tables (suppression partielle d'un
+//! [`Tag`], ...) : elle est signalée sans être corrigée.
+
+use std::collections::BTreeSet;
+
+use super::{Database, IdTag, TFormat, Tag, WordAddress};
+
+/// Nombre de mots de la [`Database`] (`WordAddress` de 0x0000 à 0x7FFF)
+const NB_WORDS: usize = 0x8000;
+
+impl Database {
+    /// Contrôle de cohérence des tables internes de la [`Database`]
+    /// Retourne la liste des incohérences détectées (vide si la [`Database`] est cohérente)
+    pub fn check_invariants(&self) -> Vec<String> {
+        let mut violations = vec![];
+
+        // Correspondances WordAddress -> IdTag
+        let mut word_addresses: Vec<&WordAddress> = self.hash_word_address.keys().collect();
+        word_addresses.sort();
+        for word_address in word_addresses {
+            let id_tag = self.hash_word_address[word_address];
+            match self.hash_tag.get(&id_tag) {
+                None => violations.push(format!(
+                    "@{word_address:04X}: IdTag {id_tag} sans tag défini"
+                )),
+                Some(tag) if tag.word_address != *word_address => violations.push(format!(
+                    "@{word_address:04X}: IdTag {id_tag} défini à l'adresse @{:04X}",
+                    tag.word_address
+                )),
+                Some(_) => (),
+            }
+        }
+
+        // Correspondances IdTag -> Tag
+        let mut tags: Vec<(&IdTag, &Tag)> = self.hash_tag.iter().collect();
+        tags.sort_by_key(|(_, tag)| tag.word_address);
+        for (id_tag, tag) in tags {
+            if tag.id_tag != *id_tag {
+                violations.push(format!("{tag}: enregistré avec l'IdTag {id_tag}"));
+            }
+            if self.hash_word_address.get(&tag.word_address) != Some(id_tag) {
+                violations.push(format!(
+                    "{tag}: adresse @{:04X} non attribuée à ce tag",
+                    tag.word_address
+                ));
+            }
+            let nb_words = tag.t_format.nb_words();
+            if matches!(tag.t_format, TFormat::Unknown) || nb_words == 0 {
+                violations.push(format!("{tag}: format {} incorrect", tag.t_format));
+            }
+            if tag.word_address as usize + nb_words > NB_WORDS {
+                violations.push(format!("{tag}: dépasse l'adresse @7FFF"));
+            }
+        }
+
+        // Références des tables annexes
+        let references: [(&str, BTreeSet<IdTag>); 8] = [
+            (
+                "groupe",
+                self.tag_groups.values().flatten().copied().collect(),
+            ),
+            ("forçage", self.forced_tags.keys().copied().collect()),
+            ("rampe", self.slew_rates.keys().copied().collect()),
+            ("historique", self.tag_histories.keys().copied().collect()),
+            ("âge max.", self.max_ages.keys().copied().collect()),
+            ("impulsion", self.pulse_tags.keys().copied().collect()),
+            ("propriétaire", self.tag_owners.keys().copied().collect()),
+            ("complément", self.string_paddings.keys().copied().collect()),
+        ];
+        for (table, id_tags) in references {
+            for id_tag in id_tags {
+                if !self.hash_tag.contains_key(&id_tag) {
+                    violations.push(format!("Table '{table}': IdTag {id_tag} sans tag défini"));
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(word_address: WordAddress, index: u16, t_format: TFormat) -> Tag {
+        Tag {
+            word_address,
+            id_tag: IdTag::new(1, index, [0, 0, 0]),
+            t_format,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        db.add_tag(&tag(0x0010, 1, TFormat::U32));
+        assert!(db.check_invariants().is_empty());
+
+        // Tag dépassant la fin de la database
+        db.add_tag(&tag(0x7FFE, 2, TFormat::U64));
+        assert_eq!(db.check_invariants().len(), 1);
+        db.hash_word_address.remove(&0x7FFE);
+        db.hash_tag.remove(&IdTag::new(1, 2, [0, 0, 0]));
+
+        // Entrées orphelines
+        db.hash_word_address
+            .insert(0x0020, IdTag::new(1, 3, [0, 0, 0]));
+        db.hash_tag.remove(&IdTag::new(1, 1, [0, 0, 0]));
+        db.hash_tag.insert(
+            IdTag::new(1, 4, [0, 0, 0]),
+            tag(0x0030, 4, TFormat::Unknown),
+        );
+        db.tag_groups
+            .insert("test".to_string(), vec![IdTag::new(1, 5, [0, 0, 0])]);
+        let violations = db.check_invariants();
+        assert_eq!(violations.len(), 5, "{violations:?}");
+        assert!(violations[0].starts_with("@0010:"));
+        assert!(violations[1].starts_with("@0020:"));
+        assert!(violations[4].starts_with("Table 'groupe'"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Enregistrements des journaux produits par le simulateur
+//!
+//! Les journaux de l'ICOM (résultats de mesurage en zone 2, événements en zone 3) sont
+//! habituellement alimentés par l'AFSEC+ (`AF_DATA_OUT` avec un `TABLE_INDEX`). Les autres
+//! utilisateurs du simulateur (étape `record` d'un scénario, etc.) peuvent également y ajouter des
+//! enregistrements : ils sont déposés dans la [`Database`] partagée et le thread de communication
+//! avec l'AFSEC+ les retire périodiquement (voir `Database::take_journal_records`) pour leur
+//! attribuer le `TABLE_INDEX` suivant du journal. L'AFSEC+ les retrouve ainsi par
+//! `AF_DATA_OUT_TABLE_INDEX` comme les enregistrements qu'il a lui-même transmis.
+
+use std::fmt;
+
+use super::{Database, IdTag};
+use crate::t_data::TValue;
+
+/// Zones des journaux (résultats de mesurage et événements)
+pub const JOURNAL_ZONES: [u8; 2] = [2, 3];
+
+/// Nombre max. d'enregistrements en attente dans la [`Database`]
+const JOURNAL_RECORDS_MAX: usize = 64;
+
+/// Enregistrement d'un journal produit par le simulateur
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalRecord {
+    /// Zone du journal
+    pub zone: u8,
+
+    /// Données de l'enregistrement
+    pub datas: Vec<(IdTag, TValue)>,
+}
+
+impl fmt::Display for JournalRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "zone {}:", self.zone)?;
+        for (id_tag, t_value) in &self.datas {
+            write!(f, " {id_tag}={t_value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Database {
+    /// Dépose un enregistrement à ajouter au journal d'une zone
+    /// Retourne le nombre d'enregistrements en attente
+    /// # Errors
+    /// Zone qui n'est pas un journal, enregistrement vide, trop d'enregistrements en attente
+    pub fn append_journal_record(
+        &mut self,
+        zone: u8,
+        datas: Vec<(IdTag, TValue)>,
+    ) -> Result<usize, String> {
+        if !JOURNAL_ZONES.contains(&zone) {
+            return Err(format!("La zone {zone} n'est pas un journal"));
+        }
+        if datas.is_empty() {
+            return Err("Enregistrement vide".to_string());
+        }
+        if self.journal_records.len() >= JOURNAL_RECORDS_MAX {
+            return Err(format!(
+                "Trop d'enregistrements en attente (max {JOURNAL_RECORDS_MAX})"
+            ));
+        }
+        self.journal_records.push(JournalRecord { zone, datas });
+        Ok(self.journal_records.len())
+    }
+
+    /// Retire les enregistrements en attente (dans l'ordre de dépôt)
+    pub fn take_journal_records(&mut self) -> Vec<JournalRecord> {
+        std::mem::take(&mut self.journal_records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_records() {
+        let mut db = Database::default();
+        assert!(db.take_journal_records().is_empty());
+
+        let datas = vec![(IdTag::new(3, 0x0100, [0, 0, 0]), TValue::U16(12))];
+        assert_eq!(db.append_journal_record(3, datas.clone()), Ok(1));
+        assert!(db.append_journal_record(5, datas.clone()).is_err());
+        assert!(db.append_journal_record(2, vec![]).is_err());
+
+        let records = db.take_journal_records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].zone, 3);
+        assert!(db.take_journal_records().is_empty());
+
+        for _ in 0..JOURNAL_RECORDS_MAX {
+            db.append_journal_record(2, datas.clone()).unwrap();
+        }
+        assert!(db.append_journal_record(2, datas).is_err());
+    }
+}
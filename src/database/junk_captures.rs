@@ -0,0 +1,176 @@
+//! Capture des dernières trames inexploitables ('junk') reçues de l'AFSEC+
+//!
+//! Pour diagnostiquer après coup un problème intermittent de câblage du banc, les `capacity`
+//! dernières trames 'junk' sont conservées avec leur horodatage (horloge simulée, voir le module
+//! `sim_clock`), le port de réception et la transition de construction de la trame qui l'a
+//! rendue inexploitable (voir `RawFrame::junk_transition`).
+//!
+//! La capacité est fixée par l'option `--junk-capture <nombre>` de la ligne de commande et les
+//! captures sont affichées par la commande `junk` de la console.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+use crate::sim_clock;
+
+use super::Database;
+
+/// Nombre de trames 'junk' conservées par défaut
+pub const DEFAULT_JUNK_CAPTURE_CAPACITY: usize = 16;
+
+/// Trame 'junk' capturée
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JunkCapture {
+    /// Horodatage de la réception (depuis le 01/01/1970 UTC)
+    pub timestamp: Duration,
+
+    /// Port de réception (1 pour le port principal, 2 pour le port de secours)
+    pub port: u16,
+
+    /// Octets reçus
+    pub octets: Vec<u8>,
+
+    /// Transition qui a rendu la trame inexploitable
+    pub rejection: String,
+}
+
+impl fmt::Display for JunkCapture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hexdump: Vec<String> = self
+            .octets
+            .iter()
+            .map(|octet| format!("{octet:02X}"))
+            .collect();
+        write!(
+            f,
+            "port {} ({} octet(s)) {}: {}",
+            self.port,
+            self.octets.len(),
+            hexdump.join(" "),
+            self.rejection
+        )
+    }
+}
+
+/// Tampon circulaire des dernières trames 'junk'
+#[derive(Clone, Debug)]
+pub struct JunkCaptures {
+    /// Nombre max. de trames conservées (0 pour ne rien conserver)
+    capacity: usize,
+
+    /// Nombre total de trames capturées (y compris celles qui ne sont plus conservées)
+    nb_total: u64,
+
+    /// Trames conservées, de la plus ancienne à la plus récente
+    captures: VecDeque<JunkCapture>,
+}
+
+impl Default for JunkCaptures {
+    fn default() -> Self {
+        Self::new(DEFAULT_JUNK_CAPTURE_CAPACITY)
+    }
+}
+
+impl JunkCaptures {
+    /// Constructeur
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            nb_total: 0,
+            captures: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Nombre max. de trames conservées
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Nombre total de trames capturées depuis le démarrage (ou le dernier effacement)
+    pub fn nb_total(&self) -> u64 {
+        self.nb_total
+    }
+
+    /// Trames conservées, de la plus ancienne à la plus récente
+    pub fn captures(&self) -> &VecDeque<JunkCapture> {
+        &self.captures
+    }
+
+    /// Enregistre une trame (la plus ancienne est oubliée si le tampon est plein)
+    fn record(&mut self, capture: JunkCapture) {
+        self.nb_total += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        while self.captures.len() >= self.capacity {
+            self.captures.pop_front();
+        }
+        self.captures.push_back(capture);
+    }
+}
+
+impl Database {
+    /// Fixe le nombre de trames 'junk' conservées (les captures en cours sont effacées)
+    pub fn set_junk_capture_capacity(&mut self, capacity: usize) {
+        self.junk_captures = JunkCaptures::new(capacity);
+    }
+
+    /// Capture d'une trame 'junk' reçue de l'AFSEC+ sur un port (horodatée par l'horloge simulée)
+    pub fn record_junk_capture(&mut self, port: u16, octets: &[u8], rejection: &str) {
+        self.junk_captures.record(JunkCapture {
+            timestamp: sim_clock::now(),
+            port,
+            octets: octets.to_vec(),
+            rejection: rejection.to_string(),
+        });
+    }
+
+    /// Dernières trames 'junk' capturées
+    pub fn get_junk_captures(&self) -> &JunkCaptures {
+        &self.junk_captures
+    }
+
+    /// Efface les trames 'junk' capturées
+    pub fn clear_junk_captures(&mut self) {
+        self.set_junk_capture_capacity(self.junk_captures.capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junk_captures() {
+        let mut db = Database::default();
+        db.set_junk_capture_capacity(2);
+        db.record_junk_capture(1, &[0x01], "Empty + 01");
+        db.record_junk_capture(1, &[0x02, 0xAB], "Empty + 02");
+        db.record_junk_capture(2, &[0x03], "Empty + 03");
+
+        // Seules les 2 dernières trames sont conservées
+        let junk_captures = db.get_junk_captures();
+        assert_eq!(junk_captures.nb_total(), 3);
+        assert_eq!(junk_captures.captures().len(), 2);
+        assert_eq!(
+            junk_captures.captures()[0].to_string(),
+            "port 1 (2 octet(s)) 02 AB: Empty + 02"
+        );
+        assert_eq!(junk_captures.captures()[1].port, 2);
+
+        db.clear_junk_captures();
+        assert_eq!(db.get_junk_captures().nb_total(), 0);
+        assert!(db.get_junk_captures().captures().is_empty());
+        assert_eq!(db.get_junk_captures().capacity(), 2);
+    }
+
+    #[test]
+    fn test_junk_captures_disabled() {
+        let mut db = Database::default();
+        db.set_junk_capture_capacity(0);
+        db.record_junk_capture(1, &[0x01], "Empty + 01");
+        assert_eq!(db.get_junk_captures().nb_total(), 1);
+        assert!(db.get_junk_captures().captures().is_empty());
+    }
+}
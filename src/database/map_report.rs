@@ -0,0 +1,383 @@
+//! Rapport du plan d'adressage de la [`Database`] (sous-commande `map-report`)
+//!
+//! Le plan d'adressage du fichier .csv est présenté par zone : plages d'adresses contiguës,
+//! trous entre ces plages, chevauchements de [`Tag`] (un mot utilisé par 2 [`Tag`]), nombre de
+//! [`Tag`] de chaque format et unités utilisées, puis la liste des [`Tag`] par ordre croissant
+//! d'adresse. Le rapport est généré au format Markdown ou HTML pour être comparé au plan de
+//! référence (tableur) sans relecture manuelle du fichier .csv.
+//!
+//! Les [`Tag`] propres au simulateur (`SIM_ZONE`) ne figurent pas dans le rapport.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Database, IdTag, Tag, WordAddress, SIM_ZONE};
+
+/// Format du rapport du plan d'adressage
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MapReportFormat {
+    /// Markdown
+    #[default]
+    Markdown,
+
+    /// HTML
+    Html,
+}
+
+impl FromStr for MapReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "md" | "markdown" => Ok(MapReportFormat::Markdown),
+            "html" => Ok(MapReportFormat::Html),
+            _ => Err(format!(
+                "Format de rapport '{s}' inconnu (attendu: md ou html)"
+            )),
+        }
+    }
+}
+
+/// Plage de [`WordAddress`] (bornes incluses)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressRange {
+    /// Première [`WordAddress`]
+    pub start: WordAddress,
+
+    /// Dernière [`WordAddress`]
+    pub end: WordAddress,
+}
+
+impl fmt::Display for AddressRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "0x{:04X}", self.start)
+        } else {
+            write!(f, "0x{:04X}-0x{:04X}", self.start, self.end)
+        }
+    }
+}
+
+/// Chevauchement de 2 [`Tag`] : le [`Tag`] `id_tag` débute dans le [`Tag`] `previous`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TagOverlap {
+    /// [`Tag`] qui précède
+    pub previous: IdTag,
+
+    /// [`Tag`] qui débute dans le précédent
+    pub id_tag: IdTag,
+
+    /// [`WordAddress`] du début du [`Tag`] `id_tag`
+    pub word_address: WordAddress,
+}
+
+impl fmt::Display for TagOverlap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "0x{:04X}: {} chevauche {}",
+            self.word_address, self.id_tag, self.previous
+        )
+    }
+}
+
+/// Plan d'adressage d'une zone
+#[derive(Clone, Debug, Default)]
+pub struct ZoneMap {
+    /// Numéro de la zone
+    pub zone: u8,
+
+    /// [`Tag`] de la zone par ordre croissant de [`WordAddress`]
+    pub tags: Vec<Tag>,
+
+    /// Plages d'adresses contiguës utilisées par les [`Tag`]
+    pub ranges: Vec<AddressRange>,
+
+    /// Trous entre les plages
+    pub gaps: Vec<AddressRange>,
+
+    /// Chevauchements de [`Tag`]
+    pub overlaps: Vec<TagOverlap>,
+
+    /// Nombre de [`Tag`] de chaque format
+    pub formats: BTreeMap<String, usize>,
+
+    /// Unités utilisées
+    pub units: BTreeSet<String>,
+}
+
+impl ZoneMap {
+    /// Plan d'adressage des [`Tag`] d'une zone
+    fn new(zone: u8, mut tags: Vec<Tag>) -> Self {
+        tags.sort_by_key(|tag| (tag.word_address, tag.id_tag));
+        let mut zone_map = ZoneMap {
+            zone,
+            ..Default::default()
+        };
+        let mut option_last: Option<(IdTag, usize)> = None;
+        for tag in &tags {
+            let start = usize::from(tag.word_address);
+            let end = start + tag.t_format.nb_words().max(1) - 1;
+            match option_last {
+                Some((previous, last_end)) if start <= last_end => {
+                    zone_map.overlaps.push(TagOverlap {
+                        previous,
+                        id_tag: tag.id_tag,
+                        word_address: tag.word_address,
+                    });
+                }
+                Some((_, last_end)) if start > last_end + 1 => {
+                    zone_map.gaps.push(AddressRange {
+                        start: to_word_address(last_end + 1),
+                        end: to_word_address(start - 1),
+                    });
+                    zone_map.ranges.push(AddressRange {
+                        start: tag.word_address,
+                        end: to_word_address(end),
+                    });
+                }
+                Some(_) => (),
+                None => zone_map.ranges.push(AddressRange {
+                    start: tag.word_address,
+                    end: to_word_address(end),
+                }),
+            }
+            let last_end = option_last.map_or(end, |(_, last_end)| last_end.max(end));
+            if let Some(range) = zone_map.ranges.last_mut() {
+                range.end = to_word_address(last_end);
+            }
+            option_last = Some((tag.id_tag, last_end));
+            *zone_map
+                .formats
+                .entry(tag.t_format.to_string())
+                .or_default() += 1;
+            if !tag.unity.is_empty() {
+                zone_map.units.insert(tag.unity.clone());
+            }
+        }
+        zone_map.tags = tags;
+        zone_map
+    }
+}
+
+/// Conversion d'une adresse calculée en [`WordAddress`] (saturée à la dernière adresse)
+fn to_word_address(address: usize) -> WordAddress {
+    WordAddress::try_from(address).unwrap_or(WordAddress::MAX)
+}
+
+/// Liste d'éléments affichables séparés par une virgule ('-' si vide)
+fn join_or_dash<T: fmt::Display>(items: impl Iterator<Item = T>) -> String {
+    let items: Vec<String> = items.map(|item| item.to_string()).collect();
+    if items.is_empty() {
+        "-".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+/// Résumé (libellé et contenu) du plan d'adressage d'une zone
+fn zone_summary(zone_map: &ZoneMap) -> Vec<(&'static str, String)> {
+    vec![
+        ("Tags", zone_map.tags.len().to_string()),
+        ("Plages", join_or_dash(zone_map.ranges.iter())),
+        ("Trous", join_or_dash(zone_map.gaps.iter())),
+        ("Chevauchements", join_or_dash(zone_map.overlaps.iter())),
+        (
+            "Formats",
+            join_or_dash(
+                zone_map
+                    .formats
+                    .iter()
+                    .map(|(t_format, nb_tags)| format!("{t_format} ({nb_tags})")),
+            ),
+        ),
+        ("Unités", join_or_dash(zone_map.units.iter())),
+    ]
+}
+
+/// Colonnes d'un [`Tag`] dans la liste des [`Tag`] d'une zone
+const TAG_COLUMNS: [&str; 6] = ["Adresse", "Tag", "Format", "Mots", "Unité", "Libellé"];
+
+/// Contenu des colonnes d'un [`Tag`] dans la liste des [`Tag`] d'une zone
+fn tag_columns(tag: &Tag) -> [String; 6] {
+    [
+        format!("0x{:04X}", tag.word_address),
+        tag.id_tag.to_string(),
+        tag.t_format.to_string(),
+        tag.t_format.nb_words().to_string(),
+        tag.unity.clone(),
+        tag.label.clone(),
+    ]
+}
+
+/// Échappement d'un texte dans une cellule d'un tableau Markdown
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// Échappement d'un texte dans un document HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Rapport au format Markdown
+fn to_markdown(title: &str, zone_maps: &[ZoneMap]) -> String {
+    let mut report = format!("# {}\n", escape_markdown(title));
+    for zone_map in zone_maps {
+        report += &format!("\n## Zone {}\n\n", zone_map.zone);
+        for (label, content) in zone_summary(zone_map) {
+            report += &format!("* {label}: {}\n", escape_markdown(&content));
+        }
+        report += &format!("\n| {} |\n", TAG_COLUMNS.join(" | "));
+        report += &format!("|{}\n", "---|".repeat(TAG_COLUMNS.len()));
+        for tag in &zone_map.tags {
+            let columns = tag_columns(tag).map(|column| escape_markdown(&column));
+            report += &format!("| {} |\n", columns.join(" | "));
+        }
+    }
+    report
+}
+
+/// Rapport au format HTML
+fn to_html(title: &str, zone_maps: &[ZoneMap]) -> String {
+    let title = escape_html(title);
+    let mut report = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n"
+    );
+    for zone_map in zone_maps {
+        report += &format!("<h2>Zone {}</h2>\n<ul>\n", zone_map.zone);
+        for (label, content) in zone_summary(zone_map) {
+            report += &format!("<li>{label}: {}</li>\n", escape_html(&content));
+        }
+        report += "</ul>\n<table border=\"1\">\n<tr>";
+        for column in TAG_COLUMNS {
+            report += &format!("<th>{column}</th>");
+        }
+        report += "</tr>\n";
+        for tag in &zone_map.tags {
+            report += "<tr>";
+            for column in tag_columns(tag) {
+                report += &format!("<td>{}</td>", escape_html(&column));
+            }
+            report += "</tr>\n";
+        }
+        report += "</table>\n";
+    }
+    report += "</body>\n</html>\n";
+    report
+}
+
+impl Database {
+    /// Plan d'adressage de chaque zone (hors `SIM_ZONE`), par ordre croissant des zones
+    pub fn zone_maps(&self) -> Vec<ZoneMap> {
+        let mut zone_tags: BTreeMap<u8, Vec<Tag>> = BTreeMap::new();
+        for tag in self.hash_tag.values() {
+            if tag.id_tag.zone != SIM_ZONE {
+                zone_tags
+                    .entry(tag.id_tag.zone)
+                    .or_default()
+                    .push(tag.clone());
+            }
+        }
+        zone_tags
+            .into_iter()
+            .map(|(zone, tags)| ZoneMap::new(zone, tags))
+            .collect()
+    }
+
+    /// Rapport du plan d'adressage dans un format
+    pub fn map_report(&self, title: &str, format: MapReportFormat) -> String {
+        let zone_maps = self.zone_maps();
+        match format {
+            MapReportFormat::Markdown => to_markdown(title, &zone_maps),
+            MapReportFormat::Html => to_html(title, &zone_maps),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::t_data::TFormat;
+
+    fn add(
+        db: &mut Database,
+        zone: u8,
+        num_tag: u16,
+        word_address: WordAddress,
+        t_format: TFormat,
+    ) {
+        db.add_tag(&Tag {
+            word_address,
+            id_tag: IdTag::new(zone, num_tag, [0, 0, 0]),
+            t_format,
+            unity: if t_format == TFormat::F32 { "V" } else { "" }.to_string(),
+            label: format!("Tag <{num_tag}>"),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_zone_maps() {
+        let mut db = Database::default();
+        add(&mut db, 2, 1, 0x0100, TFormat::U16);
+        add(&mut db, 2, 2, 0x0101, TFormat::F32);
+        add(&mut db, 2, 3, 0x0110, TFormat::U16);
+        add(&mut db, 2, 4, 0x0111, TFormat::U32);
+        add(&mut db, 2, 5, 0x0112, TFormat::U16);
+        add(&mut db, 4, 1, 0x0400, TFormat::Bool);
+        db.add_sim_tags();
+
+        let zone_maps = db.zone_maps();
+        assert_eq!(zone_maps.len(), 2);
+        let zone_map = &zone_maps[0];
+        assert_eq!(zone_map.zone, 2);
+        assert_eq!(
+            zone_map.ranges,
+            [
+                AddressRange {
+                    start: 0x0100,
+                    end: 0x0102
+                },
+                AddressRange {
+                    start: 0x0110,
+                    end: 0x0112
+                }
+            ]
+        );
+        assert_eq!(
+            zone_map.gaps,
+            [AddressRange {
+                start: 0x0103,
+                end: 0x010F
+            }]
+        );
+        assert_eq!(zone_map.overlaps.len(), 1);
+        assert_eq!(zone_map.overlaps[0].id_tag.num_tag, 5);
+        assert_eq!(zone_map.formats.get("U16"), Some(&3));
+        assert!(zone_map.units.contains("V"));
+    }
+
+    #[test]
+    fn test_map_report() {
+        let mut db = Database::default();
+        add(&mut db, 2, 1, 0x0100, TFormat::U16);
+        add(&mut db, 2, 2, 0x0102, TFormat::F32);
+
+        let markdown = db.map_report("database.csv", MapReportFormat::Markdown);
+        assert!(markdown.starts_with("# database.csv\n"));
+        assert!(markdown.contains("* Trous: 0x0101\n"));
+        assert!(markdown.contains("| 0x0102 |"));
+
+        let html = db.map_report("database.csv", MapReportFormat::Html);
+        assert!(html.contains("<h2>Zone 2</h2>"));
+        assert!(html.contains("Tag &lt;2&gt;"));
+
+        assert_eq!(MapReportFormat::from_str("HTML"), Ok(MapReportFormat::Html));
+        assert!(MapReportFormat::from_str("pdf").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Menus (messages opérateur) à pousser vers l'afficheur de l'AFSEC+
+//!
+//! La commande `menu <id> "texte"` de la console dépose un menu dans la [`Database`] partagée.
+//! Le thread de communication avec l'AFSEC+ retire périodiquement les menus en attente (voir
+//! `Database::take_menu_pushes`) : le `middleware` `AF_MENU` les transmet dans l'ordre, un menu
+//! en réponse (`IC_MENU`) à chaque `AF_ALIVE` suivant.
+
+use std::fmt;
+
+use super::Database;
+
+/// Longueur max. du texte d'un menu (afficheur de l'AFSEC+)
+pub const MENU_TEXT_MAX_LEN: usize = 32;
+
+/// Nombre max. de menus en attente dans la [`Database`]
+const MENU_PUSHES_MAX: usize = 16;
+
+/// Menu à pousser vers l'afficheur de l'AFSEC+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MenuPush {
+    /// Identifiant du menu (`D_MENU_ID`)
+    pub id_menu: u16,
+
+    /// Texte affiché (`D_MENU_SHORT_DISPLAY`)
+    pub text: String,
+}
+
+impl fmt::Display for MenuPush {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "menu #{} \"{}\"", self.id_menu, self.text)
+    }
+}
+
+impl Database {
+    /// Dépose un menu à pousser vers l'afficheur de l'AFSEC+
+    /// Retourne le nombre de menus en attente
+    /// # Errors
+    /// Texte vide ou trop long, trop de menus en attente
+    pub fn push_menu(&mut self, id_menu: u16, text: &str) -> Result<usize, String> {
+        if text.is_empty() {
+            return Err("Texte du menu vide".to_string());
+        }
+        if text.len() > MENU_TEXT_MAX_LEN {
+            return Err(format!(
+                "Texte du menu trop long ({} octets, max {MENU_TEXT_MAX_LEN})",
+                text.len()
+            ));
+        }
+        if self.menu_pushes.len() >= MENU_PUSHES_MAX {
+            return Err(format!("Trop de menus en attente (max {MENU_PUSHES_MAX})"));
+        }
+        self.menu_pushes.push(MenuPush {
+            id_menu,
+            text: text.to_string(),
+        });
+        Ok(self.menu_pushes.len())
+    }
+
+    /// Retire les menus en attente (dans l'ordre de dépôt)
+    pub fn take_menu_pushes(&mut self) -> Vec<MenuPush> {
+        std::mem::take(&mut self.menu_pushes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_menu_pushes() {
+        let mut db = Database::default();
+        assert!(db.take_menu_pushes().is_empty());
+
+        assert_eq!(db.push_menu(1, "Fermer la vanne V1"), Ok(1));
+        assert_eq!(db.push_menu(2, "Valider"), Ok(2));
+        assert!(db.push_menu(3, "").is_err());
+        assert!(db.push_menu(3, &"X".repeat(MENU_TEXT_MAX_LEN + 1)).is_err());
+
+        let menu_pushes = db.take_menu_pushes();
+        assert_eq!(menu_pushes.len(), 2);
+        assert_eq!(menu_pushes[0].to_string(), "menu #1 \"Fermer la vanne V1\"");
+        assert_eq!(menu_pushes[1].id_menu, 2);
+        assert!(db.take_menu_pushes().is_empty());
+
+        for id_menu in 0..16 {
+            db.push_menu(id_menu, "Menu").unwrap();
+        }
+        assert!(db.push_menu(16, "Menu").is_err());
+    }
+}
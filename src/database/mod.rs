@@ -31,25 +31,132 @@
 //! La primitive `Database::get_id_user` permet d'obtenir un nouveau [`IdUser`]
 //!
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::fs::File;
 use std::io::Read;
+use std::sync::Arc;
+use std::time::Instant;
 
 use crate::t_data::{TFormat, TValue};
 
 mod database_csv;
+pub use database_csv::{CsvConfig, CsvError, CsvParseMode};
 
 mod id_tag;
-pub use id_tag::IdTag;
+pub use id_tag::{IdTag, IdTagPattern};
 
 mod tag;
 pub use tag::Tag;
 
 mod database_rw;
 
+mod db_error;
+pub use db_error::DbError;
+
 mod id_users;
-pub use id_users::{IdUser, IdUsers, NotificationChange, ID_ANONYMOUS_USER};
+pub use id_users::{
+    IdUser, IdUsers, NotificationChange, GROUP_AFSEC, GROUP_MODBUS, ID_ANONYMOUS_USER,
+};
+
+mod anonymous_writes;
+pub use anonymous_writes::{AnonymousWritePolicy, SIM_USER_NAME};
+
+mod sim_tags;
+pub use sim_tags::{
+    id_tag_sim_link_stat, id_tag_sim_zone_crc, id_tag_sim_zone_state, ID_TAG_SIM_AFSEC_ACTIVE_PORT,
+    ID_TAG_SIM_AFSEC_LINK, ID_TAG_SIM_AFSEC_MODE, ID_TAG_SIM_AFSEC_STATE, ID_TAG_SIM_BUILD_DATE,
+    ID_TAG_SIM_ERROR_BUDGET, ID_TAG_SIM_FRONT_LEDS, ID_TAG_SIM_FRONT_PICTOS, ID_TAG_SIM_GIT_HASH,
+    ID_TAG_SIM_HEALTH, ID_TAG_SIM_JUNK_FRAMES, ID_TAG_SIM_MAC_ADDRESS,
+    ID_TAG_SIM_MODBUS_BROADCASTS, ID_TAG_SIM_MODBUS_CONNECTIONS, ID_TAG_SIM_MODBUS_EXCEPTIONS,
+    ID_TAG_SIM_MODBUS_REJECTED_CONNECTIONS, ID_TAG_SIM_OWNER_VIOLATIONS, ID_TAG_SIM_POWER_CYCLE,
+    ID_TAG_SIM_RESET_DEFAULTS, ID_TAG_SIM_RESTARTS, ID_TAG_SIM_SERIAL_NUMBER,
+    ID_TAG_SIM_STALE_TAGS, ID_TAG_SIM_TIME_SYNC, ID_TAG_SIM_VERSION, SIM_NB_LINKS,
+    SIM_NB_LINK_STATS, SIM_NB_ZONE_CRCS, SIM_NB_ZONE_STATES, SIM_RESET_DEFAULTS_ALL_ZONES,
+    SIM_RESET_DEFAULTS_ZONE, SIM_WORD_ADDRESS_BASE, SIM_ZONE,
+};
+
+mod sim_identity;
+pub use sim_identity::{format_mac_address, parse_mac_address, SimIdentity};
+
+mod zone_crcs;
+pub(crate) use zone_crcs::crc16_modbus;
+
+mod front_panel;
+pub use front_panel::{FrontPanel, FRONT_LED_ALARM, FRONT_LED_COM};
+
+mod tag_groups;
+
+mod forced_tags;
+pub use forced_tags::ForcedTag;
+
+mod slew_rates;
+pub use slew_rates::SlewRate;
+
+mod tag_histories;
+pub use tag_histories::{parse_tag_history_spec, TagHistory};
+
+mod tag_freshness;
+pub use tag_freshness::{parse_max_age_spec, MaxAge, TagQuality};
+
+mod pulse_tags;
+pub use pulse_tags::{parse_pulse_spec, PulseTag};
+
+mod tag_owners;
+pub use tag_owners::{parse_owner_spec, OwnerPolicy, TagOwner};
+
+mod string_paddings;
+pub use string_paddings::{parse_string_padding_spec, StringPadding};
+
+mod random_values;
+pub use random_values::{random_value, RandomFilter};
+
+mod read_cache;
+pub use read_cache::ReadCache;
+
+mod dirty_words;
+
+mod undefined_words;
+pub use undefined_words::ANONYMOUS_ZONE;
+
+mod snapshot;
+pub use snapshot::DatabaseSnapshot;
+
+mod write_heatmap;
+
+mod invariants;
+
+mod schema_edits;
+
+mod zone_clones;
+pub use zone_clones::ZoneClone;
+
+mod hexdump;
+pub use hexdump::HEXDUMP_WORDS_PER_LINE;
+
+mod map_report;
+pub use map_report::{AddressRange, MapReportFormat, TagOverlap, ZoneMap};
+
+mod saved_state;
+pub(crate) use saved_state::{id_tag_bytes, t_value_bytes, StateReader};
+pub use saved_state::{SavedState, StateRestore, STATE_MAGIC, STATE_VERSION};
+
+mod tag_migration;
+pub use tag_migration::{TagChange, TagMapping, TagMigration};
+
+mod junk_captures;
+pub use junk_captures::{JunkCapture, JunkCaptures, DEFAULT_JUNK_CAPTURE_CAPACITY};
+
+mod menu_pushes;
+pub use menu_pushes::{MenuPush, MENU_TEXT_MAX_LEN};
+
+mod journal_records;
+pub use journal_records::{JournalRecord, JOURNAL_ZONES};
+
+#[cfg(feature = "memmap")]
+mod shared_memory;
+#[cfg(feature = "memmap")]
+pub use shared_memory::SharedMemory;
 
 /// Adresse MODBUS pour accéder la [`Database`]
 /// Il s'agit d'une valeur entière `u16`.
@@ -72,6 +179,88 @@ pub struct Database {
 
     /// Gestion des [`IdUsers`]
     id_users: IdUsers,
+
+    /// Groupes nommés de [`Tag`] (voir le module `tag_groups`)
+    tag_groups: BTreeMap<String, Vec<IdTag>>,
+
+    /// [`WordAddress`] gelées (les écritures y sont ignorées)
+    frozen_word_addresses: HashSet<WordAddress>,
+
+    /// Groupes dont les modifications sont suivies (voir la console)
+    subscribed_groups: BTreeSet<String>,
+
+    /// Table des [`Tag`] forcés (voir le module `forced_tags`)
+    forced_tags: HashMap<IdTag, ForcedTag>,
+
+    /// Table des rampes des [`Tag`] (voir le module `slew_rates`)
+    slew_rates: HashMap<IdTag, SlewRate>,
+
+    /// Historiques des valeurs des [`Tag`] (voir le module `tag_histories`)
+    tag_histories: HashMap<IdTag, TagHistory>,
+
+    /// Date de la dernière écriture de chaque [`Tag`] (voir le module `tag_freshness`)
+    last_writes: HashMap<IdTag, Instant>,
+
+    /// Âge max. de la valeur des [`Tag`] supervisés
+    max_ages: HashMap<IdTag, MaxAge>,
+
+    /// [`Tag`] impulsion remis à 0 automatiquement (voir le module `pulse_tags`)
+    pulse_tags: HashMap<IdTag, PulseTag>,
+
+    /// Propriétaires des [`Tag`] (voir le module `tag_owners`)
+    tag_owners: HashMap<IdTag, TagOwner>,
+
+    /// Politique des écritures d'un [`Tag`] par une autre source que son propriétaire
+    owner_policy: OwnerPolicy,
+
+    /// Nombre d'écritures de [`Tag`] par une autre source que leur propriétaire
+    nb_owner_violations: usize,
+
+    /// Compléments des chaînes de caractères écrites partiellement (voir le module
+    /// `string_paddings`)
+    string_paddings: HashMap<IdTag, StringPadding>,
+
+    /// Zones surveillées par utilisateur avec leur bitmap des mots modifiés (voir le module
+    /// `dirty_words`)
+    dirty_words: HashMap<IdUser, Vec<dirty_words::DirtyArea>>,
+
+    /// [`WordAddress`] sans [`Tag`] déjà écrites (voir le module `undefined_words`)
+    undefined_write_addresses: BTreeSet<WordAddress>,
+
+    /// Nombre d'écritures de chaque [`WordAddress`] (voir le module `write_heatmap`)
+    write_counts: Vec<u32>,
+
+    /// Politique des écritures de l'utilisateur anonyme (voir le module `anonymous_writes`)
+    anonymous_write_policy: AnonymousWritePolicy,
+
+    /// Nombre d'écritures anonymes refusées
+    nb_refused_anonymous_writes: usize,
+
+    /// [`IdUser`] des écritures internes du simulateur (voir `Database::sim_id_user`)
+    option_sim_id_user: Option<IdUser>,
+
+    /// Identité du simulateur (voir le module `sim_identity`)
+    option_sim_identity: Option<SimIdentity>,
+
+    /// Dernières trames 'junk' reçues de l'AFSEC+ (voir le module `junk_captures`)
+    junk_captures: JunkCaptures,
+
+    /// Menus en attente à pousser vers l'afficheur de l'AFSEC+ (voir le module `menu_pushes`)
+    menu_pushes: Vec<MenuPush>,
+
+    /// Enregistrements en attente à ajouter aux journaux (voir le module `journal_records`)
+    journal_records: Vec<JournalRecord>,
+
+    /// Indicateur à true lorsque l'arrêt du simulateur est demandé (voir
+    /// `Database::request_shutdown`)
+    is_shutdown_requested: bool,
+
+    /// Cache optionnel des lectures MODBUS à invalider lors des écritures
+    read_cache: Option<Arc<ReadCache>>,
+
+    /// Export optionnel de `vec_u8` dans un fichier mappé en mémoire
+    #[cfg(feature = "memmap")]
+    shared_memory: Option<SharedMemory>,
 }
 
 impl Default for Database {
@@ -81,6 +270,33 @@ impl Default for Database {
             hash_word_address: HashMap::new(),
             hash_tag: HashMap::new(),
             id_users: IdUsers::default(),
+            tag_groups: BTreeMap::new(),
+            frozen_word_addresses: HashSet::new(),
+            subscribed_groups: BTreeSet::new(),
+            forced_tags: HashMap::new(),
+            slew_rates: HashMap::new(),
+            tag_histories: HashMap::new(),
+            last_writes: HashMap::new(),
+            max_ages: HashMap::new(),
+            pulse_tags: HashMap::new(),
+            tag_owners: HashMap::new(),
+            owner_policy: OwnerPolicy::default(),
+            nb_owner_violations: 0,
+            string_paddings: HashMap::new(),
+            dirty_words: HashMap::new(),
+            undefined_write_addresses: BTreeSet::new(),
+            write_counts: write_heatmap::new_write_counts(),
+            anonymous_write_policy: AnonymousWritePolicy::default(),
+            nb_refused_anonymous_writes: 0,
+            option_sim_id_user: None,
+            option_sim_identity: None,
+            junk_captures: JunkCaptures::default(),
+            menu_pushes: Vec::new(),
+            journal_records: Vec::new(),
+            is_shutdown_requested: false,
+            read_cache: None,
+            #[cfg(feature = "memmap")]
+            shared_memory: None,
         }
     }
 }
@@ -114,29 +330,113 @@ impl Database {
     /// panic! si syntaxe incorrecte dans une ligne du fichier
     #[allow(dead_code)]
     pub fn from_file(filename: &str) -> Self {
+        Database::from_file_with_config(filename, &CsvConfig::default())
+    }
+
+    /// Idem `Database::from_file` avec une configuration du décodage du fichier .csv
+    /// (colonnes, séparateur, mode de traitement des erreurs, voir [`CsvConfig`])
+    /// En mode `CsvParseMode::Lenient`, les erreurs sont affichées et écrites dans le fichier
+    /// de rapport (si spécifié)
+    pub fn from_file_with_config(filename: &str, csv_config: &CsvConfig) -> Self {
+        match Database::try_from_file_with_report(filename, csv_config) {
+            Ok((db, csv_errors)) => {
+                if csv_errors.is_empty() {
+                    println!("Database `{filename}` loaded OK");
+                } else {
+                    eprintln!(
+                        "Database `{filename}` loaded with {} error(s):",
+                        csv_errors.len()
+                    );
+                    for csv_error in &csv_errors {
+                        eprintln!("  {csv_error}");
+                    }
+                    if let Some(report_filename) = &csv_config.report_filename {
+                        match database_csv::write_report(report_filename, &csv_errors) {
+                            Ok(()) => eprintln!("Error report written to `{report_filename}`"),
+                            Err(e) => {
+                                eprintln!("!!! Erreur écriture du rapport '{report_filename}': {e}")
+                            }
+                        }
+                    }
+                }
+                db
+            }
+            Err(msg) => {
+                eprintln!("\n{msg}\n");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Idem `Database::from_file` mais retourne un message d'erreur plutôt que de stopper
+    /// l'application (usage depuis une bibliothèque)
+    /// # Errors
+    /// Message d'erreur si le fichier ne peut pas être lu ou si une ligne du fichier est incorrecte
+    pub fn try_from_file(filename: &str) -> Result<Self, String> {
+        Database::try_from_file_with_config(filename, &CsvConfig::default())
+    }
+
+    /// Idem `Database::try_from_file` avec une configuration du décodage du fichier .csv
+    /// Les erreurs ignorées en mode `CsvParseMode::Lenient` ne sont pas signalées
+    /// # Errors
+    /// Message d'erreur si le fichier ne peut pas être lu ou si une ligne du fichier est incorrecte
+    pub fn try_from_file_with_config(
+        filename: &str,
+        csv_config: &CsvConfig,
+    ) -> Result<Self, String> {
+        Database::try_from_file_with_report(filename, csv_config).map(|(db, _)| db)
+    }
+
+    /// Chargement d'un fichier .csv selon une configuration [`CsvConfig`]
+    /// Si des colonnes sont désignées par leur nom, la première ligne qui n'est pas un
+    /// commentaire est la ligne d'entête du fichier
+    /// Retourne la [`Database`] et la liste des erreurs des lignes ignorées
+    /// (toujours vide en mode `CsvParseMode::Strict`)
+    /// # Errors
+    /// Message d'erreur si le fichier ne peut pas être lu, si l'entête est incorrecte ou si une
+    /// ligne du fichier est incorrecte en mode `CsvParseMode::Strict`
+    pub fn try_from_file_with_report(
+        filename: &str,
+        csv_config: &CsvConfig,
+    ) -> Result<(Self, Vec<CsvError>), String> {
+        let mut csv_config = csv_config.clone();
         let mut db = Database::default();
+        let mut csv_errors = vec![];
 
         // Il se peut que le fichier ne contienne pas que de l'UTF-8...
         // Aussi on le 'parse' en utf8_lossy....
         let mut file = match File::open(filename) {
             Ok(f) => f,
-            Err(e) => {
-                eprintln!("\nErreur ouverture du fichier '{filename}': {e}\n");
-                std::process::exit(1);
-            }
+            Err(e) => return Err(format!("Erreur ouverture du fichier '{filename}': {e}")),
         };
         let mut buf = vec![];
-        match file.read_to_end(&mut buf) {
-            Ok(_) => (),
-            Err(e) => {
-                eprintln!("\nErreur lecture du fichier '{filename}': {e}\n");
-                std::process::exit(1);
-            }
-        };
+        if let Err(e) = file.read_to_end(&mut buf) {
+            return Err(format!("Erreur lecture du fichier '{filename}': {e}"));
+        }
         let contents: String = String::from_utf8_lossy(&buf).into();
 
         for (n, line) in contents.lines().enumerate() {
-            match database_csv::from_line_csv(line) {
+            if csv_config.needs_header() && !database_csv::is_comment_line(line) {
+                if let Err(msg) = csv_config.resolve_header(line) {
+                    return Err(format!(
+                        "Erreur fichier '{}', line {}: {}",
+                        filename,
+                        n + 1,
+                        msg
+                    ));
+                }
+                continue;
+            }
+            let result =
+                database_csv::from_line_csv_with_config(line, &csv_config).and_then(|option_tag| {
+                    match option_tag {
+                        Some(tag) => {
+                            database_csv::check_duplicate_tag(&db, &tag).map(|()| Some(tag))
+                        }
+                        None => Ok(None),
+                    }
+                });
+            match result {
                 Ok(option_tag) => {
                     if let Some(tag) = option_tag {
                         // Ajout du [`Tag`] dans la liste des [`Tag`] connus
@@ -144,19 +444,30 @@ impl Database {
 
                         // Valeur par défaut ?
                         if !tag.default_value.is_empty() {
-                            db.set_value(ID_ANONYMOUS_USER, &tag, &tag.default_value);
+                            if let Err(db_error) =
+                                db.set_value(ID_ANONYMOUS_USER, &tag, &tag.default_value)
+                            {
+                                println!(
+                                    "!!! Fichier '{filename}', ligne {}: Valeur par défaut: {db_error}",
+                                    n + 1
+                                );
+                            }
                         }
                     }
                 }
-                Err(msg) => {
-                    eprintln!("\nErreur fichier '{}', line {}: {}\n", filename, n + 1, msg);
-                    std::process::exit(1);
+                Err(csv_error) => {
+                    let csv_error = csv_error.at_line(n + 1);
+                    match csv_config.mode {
+                        CsvParseMode::Strict => {
+                            return Err(format!("Erreur fichier '{filename}', {csv_error}"));
+                        }
+                        CsvParseMode::Lenient => csv_errors.push(csv_error),
+                    }
                 }
             }
         }
 
-        println!("Database `{filename}` loaded OK");
-        db
+        Ok((db, csv_errors))
     }
 
     /// Ajoute un [`Tag`] à une [`WordAddress`] dans la [`Database`]
@@ -179,7 +490,32 @@ impl Database {
             "Ajout {tag} avec un id_tag déjà attribué"
         );
         self.hash_word_address.insert(word_address, tag.id_tag);
+        let (id_tag, group) = (tag.id_tag, tag.group.clone());
         self.hash_tag.insert(tag.id_tag, tag);
+        if !group.is_empty() {
+            self.add_tag_to_group(&group, id_tag);
+        }
+    }
+
+    /// Active l'export de la [`Database`] dans le fichier `path` mappé en mémoire
+    /// Voir le module `shared_memory` pour le layout de ce fichier
+    /// # Errors
+    /// Erreur si le fichier ne peut pas être créé ou mappé en mémoire
+    #[cfg(feature = "memmap")]
+    pub fn export_shared_memory(&mut self, path: &std::path::Path) -> std::io::Result<()> {
+        self.shared_memory = Some(SharedMemory::new(path, &self.vec_u8)?);
+        Ok(())
+    }
+
+    /// Demande l'arrêt du simulateur : le thread de communication avec l'AFSEC+ sauvegarde
+    /// alors le contexte des conversations avant de terminer l'application
+    pub fn request_shutdown(&mut self) {
+        self.is_shutdown_requested = true;
+    }
+
+    /// Indique si l'arrêt du simulateur est demandé
+    pub fn is_shutdown_requested(&self) -> bool {
+        self.is_shutdown_requested
     }
 
     /// Extrait un [`Tag`] (non mutable) de la [`Database`] selon son [`IdTag`]
@@ -198,6 +534,20 @@ impl Database {
         }
     }
 
+    /// [`IdTag`] du [`Tag`] qui utilise une [`WordAddress`] et position (en mots) de cette
+    /// [`WordAddress`] dans ce [`Tag`] (0 pour son premier mot)
+    /// La [`WordAddress`] peut être au milieu d'un [`Tag`] sur plusieurs mots défini à une
+    /// [`WordAddress`] précédente. En cas de recouvrement, c'est le [`Tag`] qui débute au plus
+    /// près de la [`WordAddress`] qui est retenu.
+    pub fn id_tag_at(&self, word_address: WordAddress) -> Option<(IdTag, usize)> {
+        // Recherche du premier [`Tag`] défini en remontant les [`WordAddress`]
+        let tag = (0..=word_address)
+            .rev()
+            .find_map(|tag_address| self.get_tag_from_word_address(tag_address))?;
+        let offset_in_tag = usize::from(word_address - tag.word_address);
+        (offset_in_tag < tag.t_format.nb_words()).then_some((tag.id_tag, offset_in_tag))
+    }
+
     /// Extrait la liste des [`Tag`] (non mutable) de la [`Database`] selon son [`WordAddress`] et le
     /// nombre de mots à partir de cette [`WordAddress`] dans la [`Database`]
     #[allow(dead_code)]
@@ -0,0 +1,211 @@
+//! [`Tag`] 'impulsion' : bits de commande remis à 0 automatiquement
+//!
+//! Comme l'ICOM réel pour ses bits de commande, un [`Tag`] impulsion écrit à une valeur non nulle
+//! (par MODBUS, TLV ou tout autre utilisateur) est remis à 0 après une durée configurée.
+//!
+//! L'échéance est armée lors de la notification de l'écriture du [`Tag`] par
+//! `Database::set_vec_u8_to_word_address` (seul point d'entrée des modifications de la
+//! [`Database`]) : une nouvelle écriture non nulle relance la durée et une écriture à 0 désarme
+//! l'échéance. `Database::reset_expired_pulses` (voir le process `pulse_process`) remet à 0 les
+//! [`Tag`] dont l'échéance est atteinte ; la remise à 0 est notifiée aux autres utilisateurs
+//! comme toute écriture.
+
+use std::time::{Duration, Instant};
+
+use crate::arg_parsing::parse_word_address;
+
+use super::{Database, IdTag, Tag, WordAddress};
+
+/// Analyse d'une définition d'impulsion `<adresse>=<secondes>` (adresse en hexa, `@0010`,
+/// `0x0010` ou `0010`)
+/// # Errors
+/// Message d'erreur si la définition est incorrecte
+pub fn parse_pulse_spec(spec: &str) -> Result<(WordAddress, Duration), String> {
+    let Some((address, secs)) = spec.split_once('=') else {
+        return Err(format!(
+            "Impulsion '{spec}' incorrecte (attendu: <adresse>=<secondes>)"
+        ));
+    };
+    let address = address.trim();
+    let Some(word_address) = parse_word_address(address) else {
+        return Err(format!("Adresse '{address}' incorrecte"));
+    };
+    match secs.trim().parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs > 0.0 => {
+            Ok((word_address, Duration::from_secs_f64(secs)))
+        }
+        _ => Err(format!("Durée d'impulsion '{}' incorrecte", secs.trim())),
+    }
+}
+
+/// Impulsion d'un [`Tag`]
+#[derive(Clone, Debug)]
+pub struct PulseTag {
+    /// Durée de l'impulsion
+    duration: Duration,
+
+    /// Échéance de la remise à 0 (None si le [`Tag`] est à 0)
+    option_deadline: Option<Instant>,
+}
+
+impl PulseTag {
+    /// Durée de l'impulsion
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Échéance de la remise à 0 (None si le [`Tag`] est à 0)
+    pub fn deadline(&self) -> Option<Instant> {
+        self.option_deadline
+    }
+}
+
+/// Nombre d'octets du contenu d'un [`Tag`] (mots entiers)
+fn nb_tag_bytes(tag: &Tag) -> usize {
+    2 * tag.t_format.nb_bytes().div_ceil(2).max(1)
+}
+
+impl Database {
+    /// Définit la durée de l'impulsion d'un [`Tag`] (None pour un [`Tag`] ordinaire)
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini
+    pub fn set_pulse(
+        &mut self,
+        id_tag: IdTag,
+        option_duration: Option<Duration>,
+    ) -> Result<(), String> {
+        if self.get_tag_from_id_tag(id_tag).is_none() {
+            return Err(format!("Tag {id_tag} inconnu"));
+        }
+        match option_duration {
+            Some(duration) => {
+                let pulse_tag = PulseTag {
+                    duration,
+                    option_deadline: None,
+                };
+                self.pulse_tags.insert(id_tag, pulse_tag);
+            }
+            None => {
+                self.pulse_tags.remove(&id_tag);
+            }
+        }
+        Ok(())
+    }
+
+    /// Impulsion d'un [`Tag`] (None si le [`Tag`] n'est pas une impulsion)
+    pub fn get_pulse(&self, id_tag: IdTag) -> Option<&PulseTag> {
+        self.pulse_tags.get(&id_tag)
+    }
+
+    /// [`Tag`] impulsion, par ordre croissant de [`WordAddress`]
+    pub fn get_pulse_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self
+            .pulse_tags
+            .keys()
+            .filter_map(|id_tag| self.get_tag_from_id_tag(*id_tag))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        tags
+    }
+
+    /// Arme (valeur non nulle) ou désarme (valeur nulle) l'échéance de la remise à 0 d'un
+    /// [`Tag`] impulsion qui vient d'être écrit
+    pub(super) fn arm_pulse(&mut self, tag: &Tag, now: Instant) {
+        let Some(pulse_tag) = self.pulse_tags.get_mut(&tag.id_tag) else {
+            return;
+        };
+        let u8_address = 2 * tag.word_address as usize;
+        let is_set = self
+            .vec_u8
+            .iter()
+            .skip(u8_address)
+            .take(nb_tag_bytes(tag))
+            .any(|octet| *octet != 0);
+        pulse_tag.option_deadline = is_set.then(|| now + pulse_tag.duration);
+    }
+
+    /// Remet à 0 les [`Tag`] impulsion dont l'échéance est atteinte à une date
+    /// Retourne le nombre de [`Tag`] remis à 0
+    pub fn reset_expired_pulses(&mut self, now: Instant) -> usize {
+        let mut expired_tags: Vec<Tag> = self
+            .pulse_tags
+            .iter()
+            .filter(|(_, pulse_tag)| {
+                pulse_tag
+                    .option_deadline
+                    .is_some_and(|deadline| deadline <= now)
+            })
+            .filter_map(|(id_tag, _)| self.get_tag_from_id_tag(*id_tag))
+            .cloned()
+            .collect();
+        expired_tags.sort_by_key(|tag| tag.word_address);
+
+        let id_user = self.sim_id_user();
+        for tag in &expired_tags {
+            if let Some(pulse_tag) = self.pulse_tags.get_mut(&tag.id_tag) {
+                pulse_tag.option_deadline = None;
+            }
+            let vec_u8 = vec![0; nb_tag_bytes(tag)];
+            if let Err(db_error) =
+                self.set_vec_u8_to_word_address(id_user, tag.word_address, &vec_u8)
+            {
+                println!("!!! Fin de l'impulsion de {tag}: {db_error}");
+            }
+        }
+        expired_tags.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_parse_pulse_spec() {
+        assert_eq!(
+            parse_pulse_spec("@0010=0.5"),
+            Ok((0x0010, Duration::from_millis(500)))
+        );
+        assert!(parse_pulse_spec("0010").is_err());
+        assert!(parse_pulse_spec("0010=0").is_err());
+    }
+
+    #[test]
+    fn test_reset_expired_pulses() {
+        let mut db = Database::default();
+        let id_tag = IdTag::new(5, 1, [0, 0, 0]);
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag,
+            t_format: TFormat::Bool,
+            ..Default::default()
+        });
+        assert!(db.set_pulse(IdTag::new(9, 9, [0, 0, 0]), None).is_err());
+        db.set_pulse(id_tag, Some(Duration::from_secs(1))).unwrap();
+        assert_eq!(db.get_pulse_tags().len(), 1);
+
+        // Écriture à 1 : remise à 0 après la durée de l'impulsion
+        db.set_bool_to_id_tag(ID_ANONYMOUS_USER, id_tag, true)
+            .unwrap();
+        let deadline = db.get_pulse(id_tag).unwrap().deadline().unwrap();
+        assert_eq!(
+            db.reset_expired_pulses(deadline - Duration::from_millis(10)),
+            0
+        );
+        assert!(db.get_bool_from_id_tag(ID_ANONYMOUS_USER, id_tag));
+        assert_eq!(db.reset_expired_pulses(deadline), 1);
+        assert!(!db.get_bool_from_id_tag(ID_ANONYMOUS_USER, id_tag));
+        assert!(db.get_pulse(id_tag).unwrap().deadline().is_none());
+
+        // Écriture à 0 : échéance désarmée
+        db.set_bool_to_id_tag(ID_ANONYMOUS_USER, id_tag, true)
+            .unwrap();
+        db.set_bool_to_id_tag(ID_ANONYMOUS_USER, id_tag, false)
+            .unwrap();
+        assert!(db.get_pulse(id_tag).unwrap().deadline().is_none());
+    }
+}
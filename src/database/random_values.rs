@@ -0,0 +1,242 @@
+//! Valeurs aléatoires des [`Tag`] modifiables pour éprouver les clients MODBUS (SCADA)
+//!
+//! `Database::randomize_tags` écrit une valeur aléatoire dans chaque [`Tag`] modifiable (colonne
+//! `rw` du fichier .csv) retenu par un [`RandomFilter`] (groupes et zones). La valeur est tirée
+//! dans la plage déclarée du [`Tag`] (colonnes `min` et `max`, voir [`CsvConfig`]) limitée à la
+//! plage de son format :
+//!
+//! * `Bool` et formats entiers : plage du format si aucune limite n'est déclarée
+//! * `F32` et `F64` : uniquement si les 2 limites sont déclarées
+//! * Chaînes (`VecU8`) : jamais modifiées
+//!
+//! Les [`Tag`] forcés, les [`Tag`] internes et les tags du simulateur ne sont pas modifiés.
+//! Les écritures suivent le chemin commun (`Database::set_value`) : elles sont notifiées à
+//! l'AFSEC+ et soumises aux gels, rampes et propriétaires comme toute autre écriture.
+//!
+//! Voir le process `randomizer_process` (option `--randomize` de la ligne de commande).
+//!
+//! [`CsvConfig`]: super::CsvConfig
+
+use crate::sim_rng::SimRng;
+use crate::t_data::TFormat;
+
+use super::{Database, IdUser, Tag, SIM_ZONE};
+
+/// Sélection des [`Tag`] soumis aux valeurs aléatoires
+/// (une liste vide ne filtre pas)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RandomFilter {
+    /// Groupes retenus (voir le module `tag_groups`)
+    pub groups: Vec<String>,
+
+    /// Zones retenues
+    pub zones: Vec<u8>,
+}
+
+/// Plage entière d'un format (None pour les formats non entiers)
+fn integer_format_range(t_format: TFormat) -> Option<(i128, i128)> {
+    match t_format {
+        TFormat::Bool => Some((0, 1)),
+        TFormat::U8 => Some((0, i128::from(u8::MAX))),
+        TFormat::I8 => Some((i128::from(i8::MIN), i128::from(i8::MAX))),
+        TFormat::U16 => Some((0, i128::from(u16::MAX))),
+        TFormat::I16 => Some((i128::from(i16::MIN), i128::from(i16::MAX))),
+        TFormat::U32 => Some((0, i128::from(u32::MAX))),
+        TFormat::I32 => Some((i128::from(i32::MIN), i128::from(i32::MAX))),
+        TFormat::U64 => Some((0, i128::from(u64::MAX))),
+        TFormat::I64 => Some((i128::from(i64::MIN), i128::from(i64::MAX))),
+        TFormat::Unknown | TFormat::F32 | TFormat::F64 | TFormat::VecU8(_) => None,
+    }
+}
+
+/// Valeur aléatoire (au format string, voir `Database::set_value`) d'un [`Tag`]
+/// Retourne None si le [`Tag`] ne peut pas recevoir de valeur aléatoire ou si sa plage est vide
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn random_value(tag: &Tag, rng: &mut SimRng) -> Option<String> {
+    if let Some((format_min, format_max)) = integer_format_range(tag.t_format) {
+        let min = tag
+            .option_min
+            .map_or(format_min, |min| format_min.max(min.ceil() as i128));
+        let max = tag
+            .option_max
+            .map_or(format_max, |max| format_max.min(max.floor() as i128));
+        if max < min {
+            return None;
+        }
+        let span = (max - min) as u128 + 1;
+        let offset = match u64::try_from(span) {
+            Ok(span) => rng.gen_range(0, span),
+            Err(_) => rng.next_u64(),
+        };
+        let value = min + i128::from(offset);
+        return Some(if tag.t_format == TFormat::Bool {
+            format!("{}", value == 1)
+        } else {
+            format!("{value}")
+        });
+    }
+    match (tag.t_format, tag.option_min, tag.option_max) {
+        (TFormat::F32 | TFormat::F64, Some(min), Some(max)) if min <= max => {
+            let value = min + (max - min) * rng.next_f64();
+            Some(if tag.t_format == TFormat::F32 {
+                format!("{}", value as f32)
+            } else {
+                format!("{value}")
+            })
+        }
+        _ => None,
+    }
+}
+
+impl Database {
+    /// Vérifie que les groupes d'un [`RandomFilter`] existent
+    /// # Errors
+    /// Message d'erreur si un groupe n'existe pas
+    pub fn check_random_filter(&self, filter: &RandomFilter) -> Result<(), String> {
+        for group in &filter.groups {
+            self.get_group_tags(group)?;
+        }
+        Ok(())
+    }
+
+    /// Liste des [`Tag`] soumis aux valeurs aléatoires (dans l'ordre des adresses)
+    pub fn get_random_tags(&self, filter: &RandomFilter) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self
+            .hash_tag
+            .values()
+            .filter(|tag| tag.is_write && !tag.is_internal && tag.id_tag.zone != SIM_ZONE)
+            .filter(|tag| filter.zones.is_empty() || filter.zones.contains(&tag.id_tag.zone))
+            .filter(|tag| {
+                filter.groups.is_empty()
+                    || filter.groups.iter().any(|group| {
+                        self.tag_groups
+                            .get(group)
+                            .is_some_and(|id_tags| id_tags.contains(&tag.id_tag))
+                    })
+            })
+            .filter(|tag| !self.is_forced_tag(tag.id_tag))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        tags
+    }
+
+    /// Écrit une valeur aléatoire dans les [`Tag`] retenus par le filtre
+    /// Retourne le nombre de [`Tag`] modifiés
+    pub fn randomize_tags(
+        &mut self,
+        id_user: IdUser,
+        filter: &RandomFilter,
+        rng: &mut SimRng,
+    ) -> usize {
+        let mut nb_tags = 0;
+        for tag in self.get_random_tags(filter) {
+            if let Some(value) = random_value(&tag, rng) {
+                if self.set_value(id_user, &tag, &value).is_ok() {
+                    nb_tags += 1;
+                }
+            }
+        }
+        nb_tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{IdTag, ID_ANONYMOUS_USER};
+
+    #[test]
+    fn test_random_value() {
+        let mut rng = SimRng::new(0);
+        let mut tag = Tag {
+            t_format: TFormat::I16,
+            option_min: Some(-5.5),
+            option_max: Some(5.0),
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            let value = random_value(&tag, &mut rng)
+                .unwrap()
+                .parse::<i16>()
+                .unwrap();
+            assert!((-5..=5).contains(&value));
+        }
+
+        // Plage du format si aucune limite n'est déclarée
+        tag.t_format = TFormat::U64;
+        tag.option_min = None;
+        tag.option_max = None;
+        assert!(random_value(&tag, &mut rng).unwrap().parse::<u64>().is_ok());
+
+        // Flottant: limites obligatoires
+        tag.t_format = TFormat::F32;
+        assert!(random_value(&tag, &mut rng).is_none());
+        tag.option_min = Some(1.0);
+        tag.option_max = Some(2.0);
+        let value = random_value(&tag, &mut rng)
+            .unwrap()
+            .parse::<f32>()
+            .unwrap();
+        assert!((1.0..=2.0).contains(&value));
+
+        // Plage vide et chaînes
+        tag.t_format = TFormat::U8;
+        tag.option_min = Some(300.0);
+        assert!(random_value(&tag, &mut rng).is_none());
+        tag.t_format = TFormat::VecU8(4);
+        assert!(random_value(&tag, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_randomize_tags() {
+        let mut db = Database::default();
+        let id_tags = [
+            IdTag::new(1, 0x0001, [0, 0, 0]),
+            IdTag::new(1, 0x0002, [0, 0, 0]),
+            IdTag::new(2, 0x0001, [0, 0, 0]),
+            IdTag::new(1, 0x0003, [0, 0, 0]),
+        ];
+        for (word_address, id_tag) in (0..).zip(id_tags) {
+            db.add_tag(&Tag {
+                word_address,
+                id_tag,
+                t_format: TFormat::U16,
+                is_write: word_address != 3,
+                option_min: Some(100.0),
+                option_max: Some(200.0),
+                ..Default::default()
+            });
+        }
+        db.force_tag(ID_ANONYMOUS_USER, id_tags[1], "7").unwrap();
+
+        // Zone 1 seulement: tag forcé et tag non modifiable exclus
+        let filter = RandomFilter {
+            zones: vec![1],
+            ..Default::default()
+        };
+        let mut rng = SimRng::new(1234);
+        assert_eq!(db.randomize_tags(ID_ANONYMOUS_USER, &filter, &mut rng), 1);
+        let value = db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tags[0]);
+        assert!((100..=200).contains(&value));
+        assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tags[1]), 7);
+        assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tags[2]), 0);
+        assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tags[3]), 0);
+
+        // Filtre par groupe
+        assert!(db.add_tag_to_group("G", id_tags[2]));
+        let filter = RandomFilter {
+            groups: vec!["G".to_string()],
+            ..Default::default()
+        };
+        assert!(db.check_random_filter(&filter).is_ok());
+        assert_eq!(db.get_random_tags(&filter).len(), 1);
+        assert!(db
+            .check_random_filter(&RandomFilter {
+                groups: vec!["X".to_string()],
+                ..Default::default()
+            })
+            .is_err());
+    }
+}
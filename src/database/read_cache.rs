@@ -0,0 +1,174 @@
+//! Cache des lectures MODBUS de la [`Database`]
+//!
+//! Pour les bancs de test qui 'pollent' un grand nombre de registres à une fréquence élevée,
+//! ce cache mémorise les réponses des lectures selon la clé (adresse, nombre de mots).
+//! Une lecture identique à une lecture précédente est alors servie sans verrouiller ni décoder
+//! la [`Database`].
+//!
+//! Une entrée du cache est invalidée à chaque écriture dans la [`Database`] d'une zone qui
+//! recouvre la zone lue (voir `Database::set_vec_u8_to_word_address`, seul point d'entrée des
+//! modifications qui émet également les notifications des changements).
+//!
+//! Pour éviter les inter-blocages, le verrou du cache n'est jamais conservé pendant que l'on
+//! demande le verrou de la [`Database`] (ordre de verrouillage: [`Database`] puis cache).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::{Database, WordAddress};
+
+/// Nombre max. d'entrées dans le cache (le cache est vidé au delà)
+const READ_CACHE_MAX_ENTRIES: usize = 4096;
+
+/// Cache des lectures (adresse, nombre de mots) -> mots lus
+#[derive(Debug, Default)]
+pub struct ReadCache {
+    /// Lectures mémorisées
+    entries: Mutex<HashMap<(WordAddress, u16), Vec<u16>>>,
+
+    /// Nombre de lectures servies par le cache
+    nb_hits: AtomicU64,
+
+    /// Nombre de lectures non présentes dans le cache
+    nb_misses: AtomicU64,
+
+    /// Nombre d'entrées invalidées par des écritures
+    nb_invalidations: AtomicU64,
+}
+
+impl fmt::Display for ReadCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let nb_hits = self.nb_hits.load(Ordering::Relaxed);
+        let nb_misses = self.nb_misses.load(Ordering::Relaxed);
+        let nb_requests = nb_hits + nb_misses;
+        #[allow(clippy::cast_precision_loss)]
+        let hit_ratio = if nb_requests == 0 {
+            0.0
+        } else {
+            100.0 * nb_hits as f64 / nb_requests as f64
+        };
+        write!(
+            f,
+            "hits={nb_hits} misses={nb_misses} ({hit_ratio:.1}% hits), invalidations={}, entries={}",
+            self.nb_invalidations.load(Ordering::Relaxed),
+            self.entries.lock().unwrap().len()
+        )
+    }
+}
+
+impl ReadCache {
+    /// Lecture de `cnt` mots à l'adresse `word_address` si elle est dans le cache
+    /// Sinon, la lecture est à faire dans la [`Database`] puis à mémoriser par `ReadCache::insert`
+    pub fn get(&self, word_address: WordAddress, cnt: u16) -> Option<Vec<u16>> {
+        let option_values = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(&(word_address, cnt))
+            .cloned();
+        if option_values.is_some() {
+            self.nb_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.nb_misses.fetch_add(1, Ordering::Relaxed);
+        }
+        option_values
+    }
+
+    /// Mémorise le résultat d'une lecture
+    /// Cette fonction doit être appelée avec la [`Database`] verrouillée pour ne pas mémoriser
+    /// une lecture déjà invalidée par une écriture concurrente
+    pub fn insert(&self, word_address: WordAddress, cnt: u16, values: &[u16]) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= READ_CACHE_MAX_ENTRIES {
+            entries.clear();
+        }
+        entries.insert((word_address, cnt), values.to_vec());
+    }
+
+    /// Invalide les entrées qui recouvrent la zone de `nb_words` mots à partir de `word_address`
+    pub fn invalidate(&self, word_address: WordAddress, nb_words: usize) {
+        let start = usize::from(word_address);
+        let end = start + nb_words;
+        let mut entries = self.entries.lock().unwrap();
+        let nb_entries = entries.len();
+        entries.retain(|(addr, cnt), _| {
+            let entry_start = usize::from(*addr);
+            let entry_end = entry_start + usize::from(*cnt);
+            entry_end <= start || entry_start >= end
+        });
+        let nb_invalidations = (nb_entries - entries.len()) as u64;
+        self.nb_invalidations
+            .fetch_add(nb_invalidations, Ordering::Relaxed);
+    }
+
+    /// Nombre de lectures servies par le cache
+    #[allow(dead_code)]
+    pub fn get_nb_hits(&self) -> u64 {
+        self.nb_hits.load(Ordering::Relaxed)
+    }
+
+    /// Nombre de lectures non présentes dans le cache
+    #[allow(dead_code)]
+    pub fn get_nb_misses(&self) -> u64 {
+        self.nb_misses.load(Ordering::Relaxed)
+    }
+}
+
+impl Database {
+    /// Active le cache des lectures MODBUS
+    /// Retourne le cache à utiliser pour les lectures
+    pub fn enable_read_cache(&mut self) -> Arc<ReadCache> {
+        let read_cache = Arc::new(ReadCache::default());
+        self.read_cache = Some(Arc::clone(&read_cache));
+        read_cache
+    }
+
+    /// Cache des lectures MODBUS (si activé)
+    pub fn get_read_cache(&self) -> Option<Arc<ReadCache>> {
+        self.read_cache.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    #[test]
+    fn test_read_cache() {
+        let read_cache = ReadCache::default();
+
+        // Miss puis hit
+        assert_eq!(read_cache.get(0x0010, 2), None);
+        read_cache.insert(0x0010, 2, &[1, 2]);
+        assert_eq!(read_cache.get(0x0010, 2), Some(vec![1, 2]));
+        assert_eq!(read_cache.get_nb_hits(), 1);
+        assert_eq!(read_cache.get_nb_misses(), 1);
+
+        // Invalidation hors zone
+        read_cache.invalidate(0x0012, 1);
+        read_cache.invalidate(0x000F, 1);
+        assert_eq!(read_cache.get(0x0010, 2), Some(vec![1, 2]));
+
+        // Invalidation dans la zone
+        read_cache.invalidate(0x0011, 1);
+        assert_eq!(read_cache.get(0x0010, 2), None);
+        assert_eq!(read_cache.get_nb_hits(), 2);
+        assert_eq!(read_cache.get_nb_misses(), 2);
+    }
+
+    #[test]
+    fn test_database_read_cache() {
+        let mut db = Database::default();
+        let read_cache = db.enable_read_cache();
+        read_cache.insert(0x0010, 2, &[1, 2]);
+
+        // Écriture dans la zone
+        db.set_u16_to_word_address(ID_ANONYMOUS_USER, 0x0011, 0x1234)
+            .unwrap();
+        assert_eq!(read_cache.get(0x0010, 2), None);
+    }
+}
@@ -0,0 +1,437 @@
+//! Sauvegarde de l'état (valeurs des [`Tag`]) de la [`Database`] dans un fichier binaire versionné
+//!
+//! Format (entiers en 'big endian') :
+//!
+//! * `STATE_MAGIC` (4 octets) puis version du format (`u16`, `STATE_VERSION`)
+//! * Empreinte du plan des tags (`u64`, voir `Database::tag_map_hash`)
+//! * Nombre de valeurs (`u32`) puis chaque valeur : [`IdTag`] (zone `u8`, tag `u16`, 3 indices
+//!   `u8`), code du [`TFormat`] (`u8`, 0x80 pour un `TFormat::VecU8`), nombre d'octets (`u16`)
+//!   et valeur encodée (voir `be_data::encode`)
+//!
+//! Les valeurs sont associées aux [`Tag`] par leur [`IdTag`] : un état sauvegardé reste utilisable
+//! après une modification du fichier .csv (migration). Une valeur dont le [`Tag`] n'existe plus
+//! est ignorée, une valeur dont le format a changé est convertie et un [`Tag`] nouveau est remis à
+//! sa valeur par défaut. Les [`Tag`] propres au simulateur (`SIM_ZONE`) ne sont pas sauvegardés.
+
+use std::fmt;
+
+use super::{Database, IdTag, IdUser, Tag, SIM_ZONE};
+use crate::t_data::{be_data, TFormat, TValue};
+
+/// Identification d'un fichier d'état de la [`Database`]
+pub const STATE_MAGIC: [u8; 4] = *b"ICST";
+
+/// Version courante du format
+pub const STATE_VERSION: u16 = 1;
+
+/// Code du [`TFormat`] d'un `TFormat::VecU8` (longueur quelconque, voir le nombre d'octets)
+const STATE_FORMAT_VEC_U8: u8 = 0x80;
+
+/// Calcul d'un hash FNV-1a 64 bits (stable d'une version de Rust à l'autre)
+fn fnv1a_64(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+/// Code d'un [`TFormat`] dans le fichier
+fn format_code(t_format: TFormat) -> u8 {
+    match t_format {
+        TFormat::VecU8(_) => STATE_FORMAT_VEC_U8,
+        t_format => u8::from(t_format),
+    }
+}
+
+/// Octets de l'[`IdTag`] dans le fichier
+pub(crate) fn id_tag_bytes(id_tag: IdTag) -> [u8; 6] {
+    let [tag_msb, tag_lsb] = id_tag.num_tag.to_be_bytes();
+    [
+        id_tag.zone,
+        tag_msb,
+        tag_lsb,
+        id_tag.indice_0,
+        id_tag.indice_1,
+        id_tag.indice_2,
+    ]
+}
+
+/// Octets d'une [`TValue`] dans le fichier : code du [`TFormat`], nombre d'octets et valeur
+pub(crate) fn t_value_bytes(t_value: &TValue) -> Vec<u8> {
+    let value = be_data::encode(t_value);
+    let mut bytes = vec![format_code(TFormat::from(t_value))];
+    bytes.extend(u16::try_from(value.len()).unwrap().to_be_bytes());
+    bytes.extend(value);
+    bytes
+}
+
+/// Lecture séquentielle du contenu d'un fichier
+pub(crate) struct StateReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub(crate) fn take(&mut self, nb_bytes: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < nb_bytes {
+            return Err("Fichier d'état tronqué".to_string());
+        }
+        let (head, tail) = self.bytes.split_at(nb_bytes);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn take_u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn take_u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn take_u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn take_u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn take_id_tag(&mut self) -> Result<IdTag, String> {
+        Ok(IdTag::new(
+            self.take_u8()?,
+            self.take_u16()?,
+            [self.take_u8()?, self.take_u8()?, self.take_u8()?],
+        ))
+    }
+
+    /// Valeur (voir `t_value_bytes`) du [`Tag`] `id_tag`
+    pub(crate) fn take_t_value(&mut self, id_tag: IdTag) -> Result<TValue, String> {
+        let code = self.take_u8()?;
+        let nb_bytes = usize::from(self.take_u16()?);
+        let t_format = if code == STATE_FORMAT_VEC_U8 {
+            TFormat::VecU8(nb_bytes)
+        } else {
+            TFormat::from(code)
+        };
+        if t_format == TFormat::Unknown || t_format.nb_bytes() != nb_bytes {
+            return Err(format!(
+                "Format 0x{code:02X} incorrect pour le tag {id_tag}"
+            ));
+        }
+        be_data::decode(t_format, self.take(nb_bytes)?)
+            .map_err(|e| format!("Valeur incorrecte pour le tag {id_tag}: {e}"))
+    }
+}
+
+/// État (valeurs des [`Tag`]) sauvegardé de la [`Database`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SavedState {
+    /// Empreinte du plan des tags lors de la sauvegarde
+    pub tag_map_hash: u64,
+
+    /// Valeurs des [`Tag`]
+    pub values: Vec<(IdTag, TValue)>,
+}
+
+impl SavedState {
+    /// Contenu du fichier d'état (format `STATE_VERSION`)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.extend(STATE_VERSION.to_be_bytes());
+        bytes.extend(self.tag_map_hash.to_be_bytes());
+        bytes.extend(u32::try_from(self.values.len()).unwrap().to_be_bytes());
+        for (id_tag, t_value) in &self.values {
+            bytes.extend(id_tag_bytes(*id_tag));
+            bytes.extend(t_value_bytes(t_value));
+        }
+        bytes
+    }
+
+    /// Décodage d'un fichier d'état
+    /// # Errors
+    /// Fichier qui n'est pas un état de la [`Database`], version inconnue ou contenu incorrect
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = StateReader::new(bytes);
+        if reader.take(STATE_MAGIC.len()).ok() != Some(&STATE_MAGIC[..]) {
+            return Err("Fichier d'état de la database non reconnu".to_string());
+        }
+        match reader.take_u16()? {
+            1 => Self::from_v1(&mut reader),
+            version => Err(format!(
+                "Version {version} du fichier d'état non supportée (version max. {STATE_VERSION})"
+            )),
+        }
+    }
+
+    /// Décodage du contenu d'un fichier d'état en version 1 (après la version)
+    fn from_v1(reader: &mut StateReader) -> Result<Self, String> {
+        let tag_map_hash = reader.take_u64()?;
+        let nb_values = reader.take_u32()?;
+        let mut values = vec![];
+        for _ in 0..nb_values {
+            let id_tag = reader.take_id_tag()?;
+            let t_value = reader.take_t_value(id_tag)?;
+            values.push((id_tag, t_value));
+        }
+        if !reader.is_empty() {
+            return Err("Octets en trop à la fin du fichier d'état".to_string());
+        }
+        Ok(Self {
+            tag_map_hash,
+            values,
+        })
+    }
+}
+
+/// Bilan de la restauration d'un [`SavedState`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateRestore {
+    /// true si le plan des tags est celui de la sauvegarde (pas de migration)
+    pub is_same_tag_map: bool,
+
+    /// Nombre de valeurs restaurées
+    pub nb_restored: usize,
+
+    /// Nombre de valeurs converties dans le nouveau format de leur [`Tag`] (inclus dans
+    /// `nb_restored`)
+    pub nb_converted: usize,
+
+    /// Nombre de valeurs ignorées ([`Tag`] supprimés ou valeurs non convertibles dans le nouveau
+    /// format de leur [`Tag`])
+    pub nb_dropped: usize,
+
+    /// Nombre de [`Tag`] nouveaux remis à leur valeur par défaut
+    pub nb_defaulted: usize,
+}
+
+impl fmt::Display for StateRestore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} valeurs restaurées ({} converties), {} ignorées, {} tags par défaut{}",
+            self.nb_restored,
+            self.nb_converted,
+            self.nb_dropped,
+            self.nb_defaulted,
+            if self.is_same_tag_map {
+                ""
+            } else {
+                " (plan des tags modifié)"
+            }
+        )
+    }
+}
+
+impl Database {
+    /// [`Tag`] sauvegardés dans l'état de la [`Database`], par ordre croissant d'[`IdTag`]
+    fn saved_tags(&self) -> Vec<&Tag> {
+        let mut tags: Vec<&Tag> = self
+            .hash_tag
+            .values()
+            .filter(|tag| tag.id_tag.zone != SIM_ZONE && tag.t_format != TFormat::Unknown)
+            .collect();
+        tags.sort_by_key(|tag| tag.id_tag);
+        tags
+    }
+
+    /// Empreinte du plan des tags ([`IdTag`], [`WordAddress`](super::WordAddress) et [`TFormat`]
+    /// des [`Tag`] sauvegardés), identique sur toutes les plateformes (tailles en `u32`)
+    pub fn tag_map_hash(&self) -> u64 {
+        self.saved_tags()
+            .iter()
+            .fold(0xCBF2_9CE4_8422_2325, |hash, tag| {
+                let hash = fnv1a_64(hash, &id_tag_bytes(tag.id_tag));
+                let hash = fnv1a_64(hash, &tag.word_address.to_be_bytes());
+                let hash = fnv1a_64(hash, &[format_code(tag.t_format)]);
+                let nb_bytes = u32::try_from(tag.t_format.nb_bytes()).unwrap_or(u32::MAX);
+                fnv1a_64(hash, &nb_bytes.to_be_bytes())
+            })
+    }
+
+    /// État (valeurs des [`Tag`]) de la [`Database`]
+    pub fn save_state(&self, id_user: IdUser) -> SavedState {
+        SavedState {
+            tag_map_hash: self.tag_map_hash(),
+            values: self
+                .saved_tags()
+                .iter()
+                .map(|tag| (tag.id_tag, self.get_t_value_from_tag(id_user, tag)))
+                .collect(),
+        }
+    }
+
+    /// Restauration d'un état de la [`Database`] avec migration si le plan des tags a changé
+    pub fn restore_state(&mut self, id_user: IdUser, saved_state: &SavedState) -> StateRestore {
+        let mut state_restore = StateRestore {
+            is_same_tag_map: saved_state.tag_map_hash == self.tag_map_hash(),
+            ..Default::default()
+        };
+        for (id_tag, t_value) in &saved_state.values {
+            match self.get_tag_from_id_tag(*id_tag).cloned() {
+                Some(tag) if tag.id_tag.zone != SIM_ZONE => {
+                    if self.set_t_value_to_tag(id_user, &tag, t_value).is_err() {
+                        state_restore.nb_dropped += 1;
+                        continue;
+                    }
+                    if TFormat::from(t_value) != tag.t_format {
+                        state_restore.nb_converted += 1;
+                    }
+                    state_restore.nb_restored += 1;
+                }
+                _ => state_restore.nb_dropped += 1,
+            }
+        }
+        let new_tags: Vec<Tag> = self
+            .saved_tags()
+            .into_iter()
+            .filter(|tag| {
+                !saved_state
+                    .values
+                    .iter()
+                    .any(|(id_tag, _)| *id_tag == tag.id_tag)
+            })
+            .cloned()
+            .collect();
+        self.reset_tags_to_defaults(id_user, &new_tags);
+        state_restore.nb_defaulted = new_tags.len();
+        state_restore
+    }
+
+    /// Sauvegarde de l'état de la [`Database`] dans un fichier
+    /// Retourne le nombre de valeurs sauvegardées
+    /// # Errors
+    /// Erreur d'écriture du fichier
+    pub fn save_state_file(&self, id_user: IdUser, filename: &str) -> Result<usize, String> {
+        let saved_state = self.save_state(id_user);
+        std::fs::write(filename, saved_state.to_bytes())
+            .map_err(|e| format!("Erreur écriture '{filename}': {e}"))?;
+        Ok(saved_state.values.len())
+    }
+
+    /// Restauration de l'état de la [`Database`] sauvegardé dans un fichier
+    /// # Errors
+    /// Erreur de lecture ou contenu incorrect du fichier
+    pub fn restore_state_file(
+        &mut self,
+        id_user: IdUser,
+        filename: &str,
+    ) -> Result<StateRestore, String> {
+        let bytes =
+            std::fs::read(filename).map_err(|e| format!("Erreur lecture '{filename}': {e}"))?;
+        let saved_state =
+            SavedState::from_bytes(&bytes).map_err(|e| format!("'{filename}': {e}"))?;
+        Ok(self.restore_state(id_user, &saved_state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    /// Database avec des tags (`IdTag` zone 4, numéro de tag, format, valeur par défaut)
+    fn database_setup(tags: &[(u16, TFormat, &str)]) -> Database {
+        let mut db = Database::default();
+        let mut word_address = 0x0010;
+        for (num_tag, t_format, default_value) in tags {
+            let tag = Tag {
+                word_address,
+                id_tag: IdTag::new(4, *num_tag, [0, 0, 0]),
+                t_format: *t_format,
+                default_value: (*default_value).to_string(),
+                ..Default::default()
+            };
+            db.add_tag(&tag);
+            db.set_value(ID_ANONYMOUS_USER, &tag, default_value)
+                .unwrap();
+            word_address += u16::try_from(t_format.nb_words()).unwrap();
+        }
+        db
+    }
+
+    /// Écriture d'un tag de la zone 4
+    fn set(db: &mut Database, num_tag: u16, value: &str) {
+        let tag = db
+            .get_tag_from_id_tag(IdTag::new(4, num_tag, [0, 0, 0]))
+            .unwrap()
+            .clone();
+        db.set_value(ID_ANONYMOUS_USER, &tag, value).unwrap();
+    }
+
+    #[test]
+    fn test_saved_state_bytes() {
+        let db = database_setup(&[
+            (1, TFormat::U16, "1234"),
+            (2, TFormat::F64, "-1.5"),
+            (3, TFormat::VecU8(6), "abc"),
+        ]);
+        let saved_state = db.save_state(ID_ANONYMOUS_USER);
+        assert_eq!(saved_state.values.len(), 3);
+        let bytes = saved_state.to_bytes();
+        assert_eq!(&bytes[0..4], &STATE_MAGIC);
+        assert_eq!(SavedState::from_bytes(&bytes), Ok(saved_state));
+
+        // Fichier incorrect, version future ou tronqué
+        assert!(SavedState::from_bytes(b"XXXX").is_err());
+        let mut future = bytes.clone();
+        future[5] = 2;
+        assert!(SavedState::from_bytes(&future).is_err());
+        assert!(SavedState::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_tag_map_hash() {
+        // Empreinte indépendante de la plateforme (32 ou 64 bits)
+        let db = database_setup(&[(1, TFormat::U16, "0")]);
+        assert_eq!(db.tag_map_hash(), 0x5C0E_CCE4_2B89_C1E8);
+    }
+
+    #[test]
+    fn test_restore_state_migration() {
+        let mut db = database_setup(&[(1, TFormat::U16, "0"), (2, TFormat::U16, "0")]);
+        set(&mut db, 1, "7");
+        set(&mut db, 2, "8");
+        let saved_state = db.save_state(ID_ANONYMOUS_USER);
+
+        // Même plan des tags
+        let mut same_db = database_setup(&[(1, TFormat::U16, "0"), (2, TFormat::U16, "0")]);
+        let state_restore = same_db.restore_state(ID_ANONYMOUS_USER, &saved_state);
+        assert!(state_restore.is_same_tag_map);
+        assert_eq!(state_restore.nb_restored, 2);
+        assert_eq!(
+            same_db.get_u16_from_id_tag(ID_ANONYMOUS_USER, IdTag::new(4, 2, [0, 0, 0])),
+            8
+        );
+
+        // Tag 1 converti en U32, tag 2 supprimé et tag 3 nouveau
+        let mut new_db = database_setup(&[(1, TFormat::U32, "0"), (3, TFormat::U16, "99")]);
+        set(&mut new_db, 3, "5");
+        let state_restore = new_db.restore_state(ID_ANONYMOUS_USER, &saved_state);
+        assert_eq!(
+            state_restore,
+            StateRestore {
+                is_same_tag_map: false,
+                nb_restored: 1,
+                nb_converted: 1,
+                nb_dropped: 1,
+                nb_defaulted: 1,
+            }
+        );
+        assert_eq!(
+            new_db.get_u32_from_id_tag(ID_ANONYMOUS_USER, IdTag::new(4, 1, [0, 0, 0])),
+            7
+        );
+        assert_eq!(
+            new_db.get_u16_from_id_tag(ID_ANONYMOUS_USER, IdTag::new(4, 3, [0, 0, 0])),
+            99
+        );
+    }
+}
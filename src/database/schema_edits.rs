@@ -0,0 +1,176 @@
+//! Modification du plan des [`Tag`] de la [`Database`] pendant la simulation
+//!
+//! Pendant la mise au point d'un banc, un [`Tag`] peut être supprimé ou changer de format sans
+//! modifier le fichier .csv ni redémarrer le simulateur (commandes `remove` et `format` de la
+//! console) :
+//!
+//! * `Database::remove_tag` supprime le [`Tag`] des 2 tables de correspondance, de toutes les
+//!   tables annexes (groupes, forçage, rampe, historique, ...) et de l'historique des
+//!   notifications. Le contenu des mots n'est pas modifié.
+//! * `Database::update_tag_format` change le format d'un [`Tag`] si ses nouveaux mots ne
+//!   débordent ni sur le [`Tag`] suivant ni au delà de l'adresse 0x7FFF. Le forçage, la rampe et
+//!   l'historique (valeurs dans l'ancien format) du [`Tag`] sont supprimés, ainsi que son
+//!   complément de chaîne de caractères s'il n'est plus une chaîne.
+//!
+//! Le cache des lectures MODBUS est invalidé sur les mots du [`Tag`].
+
+use super::{Database, IdTag, TFormat, Tag, WordAddress};
+
+impl Database {
+    /// Invalide le cache des lectures MODBUS sur les mots d'un [`Tag`]
+    fn invalidate_tag_words(&self, word_address: WordAddress, nb_words: usize) {
+        if let Some(read_cache) = &self.read_cache {
+            read_cache.invalidate(word_address, nb_words);
+        }
+    }
+
+    /// Supprime un [`Tag`] de la [`Database`] et toutes les références à ce [`Tag`]
+    /// Retourne le [`Tag`] supprimé
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini
+    pub fn remove_tag(&mut self, id_tag: IdTag) -> Result<Tag, String> {
+        let Some(tag) = self.hash_tag.remove(&id_tag) else {
+            return Err(format!("Tag {id_tag} inconnu"));
+        };
+        if self.hash_word_address.get(&tag.word_address) == Some(&id_tag) {
+            self.hash_word_address.remove(&tag.word_address);
+        }
+
+        // Tables annexes
+        for id_tags in self.tag_groups.values_mut() {
+            id_tags.retain(|group_id_tag| *group_id_tag != id_tag);
+        }
+        self.tag_groups.retain(|_, id_tags| !id_tags.is_empty());
+        self.forced_tags.remove(&id_tag);
+        self.slew_rates.remove(&id_tag);
+        self.tag_histories.remove(&id_tag);
+        self.last_writes.remove(&id_tag);
+        self.max_ages.remove(&id_tag);
+        self.pulse_tags.remove(&id_tag);
+        self.tag_owners.remove(&id_tag);
+        self.string_paddings.remove(&id_tag);
+
+        // Historique des notifications
+        self.id_users.remove_changes_of_id_tag(id_tag);
+
+        self.invalidate_tag_words(tag.word_address, tag.t_format.nb_words());
+        Ok(tag)
+    }
+
+    /// Change le format d'un [`Tag`]
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini, si le format est incorrect ou si les
+    /// mots du [`Tag`] dans ce format débordent sur un autre [`Tag`] ou au delà de l'adresse
+    /// 0x7FFF
+    pub fn update_tag_format(&mut self, id_tag: IdTag, t_format: TFormat) -> Result<(), String> {
+        let Some(tag) = self.get_tag_from_id_tag(id_tag) else {
+            return Err(format!("Tag {id_tag} inconnu"));
+        };
+        let nb_words = t_format.nb_words();
+        if matches!(t_format, TFormat::Unknown) || nb_words == 0 {
+            return Err(format!("Format {t_format} incorrect"));
+        }
+        let word_address = tag.word_address;
+        let end = usize::from(word_address) + nb_words;
+        if end > 0x8000 {
+            return Err(format!(
+                "{tag}: le format {t_format} dépasse l'adresse @7FFF"
+            ));
+        }
+        let option_overlap = (usize::from(word_address) + 1..end).find_map(|address| {
+            WordAddress::try_from(address)
+                .ok()
+                .and_then(|address| self.get_tag_from_word_address(address))
+        });
+        if let Some(other_tag) = option_overlap {
+            return Err(format!(
+                "{tag}: le format {t_format} déborde sur le tag {other_tag}"
+            ));
+        }
+        let old_nb_words = tag.t_format.nb_words();
+
+        // Réglages dans l'ancien format
+        self.forced_tags.remove(&id_tag);
+        self.slew_rates.remove(&id_tag);
+        self.tag_histories.remove(&id_tag);
+        if !matches!(t_format, TFormat::VecU8(_)) {
+            self.string_paddings.remove(&id_tag);
+        }
+
+        if let Some(tag) = self.hash_tag.get_mut(&id_tag) {
+            tag.t_format = t_format;
+        }
+        self.invalidate_tag_words(word_address, nb_words.max(old_nb_words));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    fn db_with_tags() -> Database {
+        let mut db = Database::default();
+        for (word_address, index, t_format) in [
+            (0x0010, 1, TFormat::U16),
+            (0x0012, 2, TFormat::U32),
+            (0x7FFE, 3, TFormat::U16),
+        ] {
+            db.add_tag(&Tag {
+                word_address,
+                id_tag: IdTag::new(1, index, [0, 0, 0]),
+                t_format,
+                group: "test".to_string(),
+                ..Default::default()
+            });
+        }
+        db
+    }
+
+    #[test]
+    fn test_remove_tag() {
+        let mut db = db_with_tags();
+        let id_tag = IdTag::new(1, 2, [0, 0, 0]);
+        let id_user = db.get_id_user("Test", true);
+        db.set_slew_rate(ID_ANONYMOUS_USER, id_tag, 1.0).unwrap();
+        db.set_u32_to_word_address(ID_ANONYMOUS_USER, 0x0012, 5)
+            .unwrap();
+        db.force_tag(ID_ANONYMOUS_USER, id_tag, "7").unwrap();
+
+        let tag = db.remove_tag(id_tag).unwrap();
+        assert_eq!(tag.word_address, 0x0012);
+        assert!(db.get_tag_from_id_tag(id_tag).is_none());
+        assert!(db.get_tag_from_word_address(0x0012).is_none());
+        assert!(!db.is_forced_tag(id_tag));
+        assert!(db.get_slew_rate(id_tag).is_none());
+        assert_eq!(db.get_group_tags("test").unwrap().len(), 2);
+        // Plus de notification du tag supprimé
+        assert!(db.get_change(id_user, true, true).is_none());
+        assert!(db.check_invariants().is_empty());
+        assert!(db.remove_tag(id_tag).is_err());
+    }
+
+    #[test]
+    fn test_update_tag_format() {
+        let mut db = db_with_tags();
+        let id_tag = IdTag::new(1, 1, [0, 0, 0]);
+        db.update_tag_format(id_tag, TFormat::U32).unwrap_err();
+        db.update_tag_format(id_tag, TFormat::I16).unwrap();
+        assert_eq!(
+            db.get_tag_from_id_tag(id_tag).unwrap().t_format,
+            TFormat::I16
+        );
+        db.update_tag_format(IdTag::new(1, 2, [0, 0, 0]), TFormat::VecU8(8))
+            .unwrap();
+        assert!(db
+            .update_tag_format(IdTag::new(1, 3, [0, 0, 0]), TFormat::U32)
+            .is_err());
+        assert!(db.update_tag_format(id_tag, TFormat::Unknown).is_err());
+        assert!(db
+            .update_tag_format(IdTag::new(9, 9, [0, 0, 0]), TFormat::U16)
+            .is_err());
+        assert!(db.check_invariants().is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+//! Export optionnel de la [`Database`] dans un fichier 'mappé' en mémoire (feature `memmap`)
+//!
+//! Ce fichier permet à des outils de test natifs (legacy) de consulter l'état du simulateur
+//! sans passer par un protocole réseau.
+//!
+//! Layout du fichier (65536 Bytes, identique à la table interne de la [`Database`]) :
+//!
+//! * Offset `2 * addr` et `2 * addr + 1` : contenu de la [`WordAddress`] `addr` (0x0000-0x7FFF)
+//! * Encodage 'big endian' : l'offset `2 * addr` contient le MSB du mot
+//! * Les données des [`Tag`] sur plusieurs mots sont consécutives (même encodage que MODBUS)
+//!
+//! Le contenu est mis à jour à chaque écriture dans la [`Database`]. Il n'y a pas de mécanisme
+//! de verrouillage : un outil externe peut lire une valeur multi-mots en cours de mise à jour.
+//!
+//! [`Database`]: super::Database
+//! [`WordAddress`]: super::WordAddress
+//! [`Tag`]: super::Tag
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+
+use memmap2::MmapMut;
+
+/// Taille du fichier partagé en Bytes (0x8000 mots de 2 Bytes)
+pub const SHARED_MEMORY_LEN: usize = 2 * 0x8000;
+
+/// Vue 'mappée' en mémoire de la table `u8` de la [`Database`]
+///
+/// [`Database`]: super::Database
+#[derive(Debug)]
+pub struct SharedMemory {
+    /// Fichier mappé en mémoire
+    mmap: MmapMut,
+}
+
+impl SharedMemory {
+    /// Création (ou réutilisation) du fichier `path` et mapping en mémoire
+    /// Le fichier est dimensionné à [`SHARED_MEMORY_LEN`] et initialisé avec `vec_u8`
+    pub fn new(path: &Path, vec_u8: &[u8]) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.set_len(SHARED_MEMORY_LEN as u64)?;
+
+        // SAFETY: Le fichier est dédié à cet export. Un outil externe peut y accéder en
+        // lecture mais sa modification hors de ce processus n'est pas supportée
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        let mut shared_memory = Self { mmap };
+        shared_memory.write(0, vec_u8);
+        Ok(shared_memory)
+    }
+
+    /// Recopie de `vec_u8` à l'offset `u8_address` du fichier partagé
+    /// Les données hors de la zone [`SHARED_MEMORY_LEN`] sont ignorées
+    pub fn write(&mut self, u8_address: usize, vec_u8: &[u8]) {
+        if u8_address >= SHARED_MEMORY_LEN {
+            return;
+        }
+        let len = vec_u8.len().min(SHARED_MEMORY_LEN - u8_address);
+        self.mmap[u8_address..u8_address + len].copy_from_slice(&vec_u8[..len]);
+    }
+
+    /// Contenu courant du fichier partagé
+    #[allow(dead_code)]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.mmap
+    }
+}
+
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        let _ = self.mmap.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_memory() {
+        let path = std::env::temp_dir().join("sim_icom_test_shared_memory.bin");
+        let mut vec_u8 = vec![0_u8; SHARED_MEMORY_LEN];
+        vec_u8[0] = 0x12;
+        vec_u8[1] = 0x34;
+
+        let mut shared_memory = SharedMemory::new(&path, &vec_u8).unwrap();
+        assert_eq!(shared_memory.as_slice().len(), SHARED_MEMORY_LEN);
+        assert_eq!(shared_memory.as_slice()[0..2], [0x12, 0x34]);
+
+        shared_memory.write(0x20, &[0xAB, 0xCD]);
+        assert_eq!(shared_memory.as_slice()[0x20..0x22], [0xAB, 0xCD]);
+
+        // Écriture tronquée en fin de zone
+        shared_memory.write(SHARED_MEMORY_LEN - 1, &[0x55, 0x66]);
+        assert_eq!(shared_memory.as_slice()[SHARED_MEMORY_LEN - 1], 0x55);
+
+        drop(shared_memory);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_database_shared_memory() {
+        let path = std::env::temp_dir().join("sim_icom_test_database_shared_memory.bin");
+        let mut db = super::super::Database::default();
+        db.export_shared_memory(&path).unwrap();
+
+        db.set_u16_to_word_address(super::super::ID_ANONYMOUS_USER, 0x0010, 0x1234)
+            .unwrap();
+        let shared_memory = db.shared_memory.as_ref().unwrap();
+        assert_eq!(shared_memory.as_slice()[0x20..0x22], [0x12, 0x34]);
+
+        drop(db);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,293 @@
+//! Identité unique d'une instance du simulateur (numéro de série et adresse MAC)
+//!
+//! Pour distinguer plusieurs simulateurs sur un même banc (suivi des équipements), chaque
+//! instance peut avoir un numéro de série et une adresse 'MAC' stables (option
+//! `--identity <fichier>`) : l'identité est lue dans le fichier, ou générée puis écrite dans le
+//! fichier au premier démarrage. Le numéro de série peut aussi être imposé (option
+//! `--serial-number`).
+//!
+//! L'identité est publiée dans des [`Tag`] propres au simulateur (voir le module `sim_tags`),
+//! dans l'identification MODBUS de l'équipement (fonction 43/14) et dans la réponse `IC_INIT` à
+//! l'AFSEC+.
+//!
+//! Le fichier est un texte `<clé>=<valeur>` (lignes `#` de commentaire) :
+//!
+//! ```text
+//! serial_number=SIM-1A2B3C4D
+//! mac_address=02:1A:2B:3C:4D:5E
+//! ```
+//!
+//! [`Tag`]: super::Tag
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::sim_rng::SimRng;
+
+use super::sim_tags::{trace_sim_tag_error, SIM_SERIAL_NUMBER_LEN};
+use super::Database;
+use super::{ID_TAG_SIM_MAC_ADDRESS, ID_TAG_SIM_SERIAL_NUMBER};
+
+/// Identité d'une instance du simulateur
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimIdentity {
+    /// Numéro de série (`SIM_SERIAL_NUMBER_LEN` caractères max.)
+    pub serial_number: String,
+
+    /// Adresse 'MAC' (adresse unicast administrée localement)
+    pub mac_address: [u8; 6],
+}
+
+impl fmt::Display for SimIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "serial_number={} mac_address={}",
+            self.serial_number,
+            format_mac_address(&self.mac_address)
+        )
+    }
+}
+
+/// Adresse 'MAC' au format `02:1A:2B:3C:4D:5E`
+pub fn format_mac_address(mac_address: &[u8; 6]) -> String {
+    mac_address
+        .iter()
+        .map(|octet| format!("{octet:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Analyse d'une adresse 'MAC' au format `02:1A:2B:3C:4D:5E` (ou `02-1A-2B-3C-4D-5E`)
+/// # Errors
+/// Message d'erreur si l'adresse est incorrecte
+pub fn parse_mac_address(s: &str) -> Result<[u8; 6], String> {
+    let err = || format!("Adresse MAC '{s}' incorrecte (ex: '02:1A:2B:3C:4D:5E')");
+    let octets = s
+        .trim()
+        .split([':', '-'])
+        .map(|octet| u8::from_str_radix(octet, 16).map_err(|_| err()))
+        .collect::<Result<Vec<u8>, String>>()?;
+    octets.try_into().map_err(|_| err())
+}
+
+/// Contrôle d'un numéro de série
+fn check_serial_number(serial_number: &str) -> Result<(), String> {
+    if serial_number.is_empty()
+        || serial_number.len() > SIM_SERIAL_NUMBER_LEN
+        || !serial_number.bytes().all(|b| b.is_ascii_graphic())
+    {
+        return Err(format!(
+            "Numéro de série '{serial_number}' incorrect ({SIM_SERIAL_NUMBER_LEN} caractères ASCII max., sans espace)"
+        ));
+    }
+    Ok(())
+}
+
+impl SimIdentity {
+    /// Identité générée à partir d'une graine : le numéro de série est `SIM-` suivi de 8
+    /// chiffres hexa et l'adresse 'MAC' est unicast et administrée localement
+    pub fn generate(seed: u64) -> Self {
+        let mut rng = SimRng::new(seed).fork("identity");
+        let serial = rng.next_u64();
+        let mut mac_address = [0_u8; 6];
+        mac_address.copy_from_slice(&rng.next_u64().to_be_bytes()[..6]);
+        mac_address[0] = (mac_address[0] & 0xFC) | 0x02;
+        Self {
+            serial_number: format!("SIM-{:08X}", serial & 0xFFFF_FFFF),
+            mac_address,
+        }
+    }
+
+    /// Identité avec un numéro de série imposé (adresse 'MAC' dérivée du numéro de série pour
+    /// rester stable d'un démarrage à l'autre)
+    /// # Errors
+    /// Message d'erreur si le numéro de série est incorrect
+    pub fn with_serial_number(serial_number: &str) -> Result<Self, String> {
+        check_serial_number(serial_number)?;
+        let seed = serial_number
+            .bytes()
+            .fold(0_u64, |seed, b| seed.rotate_left(8) ^ u64::from(b));
+        Ok(Self {
+            serial_number: serial_number.to_string(),
+            ..Self::generate(seed)
+        })
+    }
+
+    /// Contenu du fichier d'identité
+    pub fn to_text(&self) -> String {
+        format!(
+            "# Identité du simulateur\nserial_number={}\nmac_address={}\n",
+            self.serial_number,
+            format_mac_address(&self.mac_address)
+        )
+    }
+
+    /// Identité depuis le contenu d'un fichier
+    /// # Errors
+    /// Message d'erreur si une ligne est incorrecte ou si une clé est absente
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut option_serial_number = None;
+        let mut option_mac_address = None;
+        for (num_line, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("Ligne {} incorrecte: '{line}'", num_line + 1));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "serial_number" => {
+                    check_serial_number(value)?;
+                    option_serial_number = Some(value.to_string());
+                }
+                "mac_address" => option_mac_address = Some(parse_mac_address(value)?),
+                _ => return Err(format!("Ligne {} incorrecte: '{line}'", num_line + 1)),
+            }
+        }
+        match (option_serial_number, option_mac_address) {
+            (Some(serial_number), Some(mac_address)) => Ok(Self {
+                serial_number,
+                mac_address,
+            }),
+            _ => Err("Identité incomplète (serial_number et mac_address attendus)".to_string()),
+        }
+    }
+
+    /// Identité lue dans un fichier, ou générée (selon `seed`) et écrite dans ce fichier s'il
+    /// n'existe pas. Un numéro de série imposé remplace celui du fichier (qui est mis à jour)
+    /// # Errors
+    /// Message d'erreur si le fichier est incorrect ou ne peut pas être lu ou écrit
+    pub fn load_or_create(
+        filename: &str,
+        option_serial_number: Option<&str>,
+        seed: u64,
+    ) -> Result<Self, String> {
+        let stored = match std::fs::read_to_string(filename) {
+            Ok(text) => Some(Self::from_text(&text).map_err(|msg| format!("'{filename}': {msg}"))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("Erreur lecture '{filename}': {e}")),
+        };
+        let identity = match (&stored, option_serial_number) {
+            (Some(stored), None) => stored.clone(),
+            (Some(stored), Some(serial_number)) => {
+                check_serial_number(serial_number)?;
+                Self {
+                    serial_number: serial_number.to_string(),
+                    ..stored.clone()
+                }
+            }
+            (None, Some(serial_number)) => Self::with_serial_number(serial_number)?,
+            (None, None) => Self::generate(seed),
+        };
+        if stored.as_ref() != Some(&identity) {
+            std::fs::write(filename, identity.to_text())
+                .map_err(|e| format!("Erreur écriture '{filename}': {e}"))?;
+        }
+        Ok(identity)
+    }
+}
+
+impl FromStr for SimIdentity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_text(s)
+    }
+}
+
+impl Database {
+    /// Définit l'identité du simulateur et la publie dans les [`Tag`] propres au simulateur
+    ///
+    /// [`Tag`]: super::Tag
+    pub fn set_sim_identity(&mut self, identity: SimIdentity) {
+        let id_user = self.sim_id_user();
+        for (id_tag, value) in [
+            (ID_TAG_SIM_SERIAL_NUMBER, identity.serial_number.as_bytes()),
+            (ID_TAG_SIM_MAC_ADDRESS, &identity.mac_address[..]),
+        ] {
+            trace_sim_tag_error(self.set_vec_u8_to_id_tag(id_user, id_tag, value));
+        }
+        self.option_sim_identity = Some(identity);
+    }
+
+    /// Identité du simulateur (None si elle n'est pas définie)
+    pub fn get_sim_identity(&self) -> Option<&SimIdentity> {
+        self.option_sim_identity.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    #[test]
+    fn test_identity_text() {
+        let identity = SimIdentity::generate(42);
+        assert_eq!(identity, SimIdentity::generate(42));
+        assert_ne!(identity, SimIdentity::generate(43));
+        assert!(identity.serial_number.starts_with("SIM-"));
+        assert_eq!(identity.mac_address[0] & 0x03, 0x02);
+        assert_eq!(SimIdentity::from_text(&identity.to_text()), Ok(identity));
+
+        assert_eq!(
+            parse_mac_address("02-1a-2B-3C-4D-5E"),
+            Ok([0x02, 0x1A, 0x2B, 0x3C, 0x4D, 0x5E])
+        );
+        assert!(parse_mac_address("02:1A:2B:3C:4D").is_err());
+        assert!(SimIdentity::from_text("serial_number=ABC").is_err());
+        assert!(SimIdentity::with_serial_number("SERIAL NUMBER").is_err());
+        assert!(SimIdentity::with_serial_number("0123456789ABCDEFG").is_err());
+        assert_eq!(
+            SimIdentity::with_serial_number("BENCH-01"),
+            SimIdentity::with_serial_number("BENCH-01")
+        );
+    }
+
+    #[test]
+    fn test_load_or_create() {
+        let filename = std::env::temp_dir()
+            .join(format!("sim_icom_identity_{}.txt", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let _ = std::fs::remove_file(&filename);
+
+        // Identité générée au premier démarrage puis reprise du fichier
+        let identity = SimIdentity::load_or_create(&filename, None, 1).unwrap();
+        assert_eq!(
+            SimIdentity::load_or_create(&filename, None, 2),
+            Ok(identity.clone())
+        );
+
+        // Numéro de série imposé: l'adresse MAC est conservée
+        let renamed = SimIdentity::load_or_create(&filename, Some("BENCH-02"), 2).unwrap();
+        assert_eq!(renamed.serial_number, "BENCH-02");
+        assert_eq!(renamed.mac_address, identity.mac_address);
+        assert_eq!(SimIdentity::load_or_create(&filename, None, 2), Ok(renamed));
+        std::fs::remove_file(&filename).unwrap();
+    }
+
+    #[test]
+    fn test_sim_identity_tags() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        assert!(db.get_sim_identity().is_none());
+        let identity = SimIdentity::with_serial_number("BENCH-01").unwrap();
+        db.set_sim_identity(identity.clone());
+        assert_eq!(db.get_sim_identity(), Some(&identity));
+        let tag = db.get_tag_from_id_tag(ID_TAG_SIM_SERIAL_NUMBER).unwrap();
+        assert_eq!(
+            db.get_t_value_from_tag(ID_ANONYMOUS_USER, tag).as_text(),
+            Some("BENCH-01".to_string())
+        );
+        let tag = db.get_tag_from_id_tag(ID_TAG_SIM_MAC_ADDRESS).unwrap();
+        assert_eq!(
+            db.get_vec_u8_from_word_address(ID_ANONYMOUS_USER, tag.word_address, 6),
+            identity.mac_address
+        );
+    }
+}
@@ -0,0 +1,627 @@
+//! [`Tag`] propres au simulateur (état de fonctionnement, diagnostics, ...)
+//!
+//! Ces [`Tag`] ne sont pas définis dans le fichier database*.csv de production. Ils sont ajoutés
+//! par `Database::add_sim_tags` dans une zone réservée au simulateur (`SIM_ZONE`) et à des
+//! [`WordAddress`] en fin de table MODBUS (à partir de `SIM_WORD_ADDRESS_BASE`) pour être lus
+//! par un client MODBUS/TCP.
+
+use crate::build_info;
+use crate::t_data::TFormat;
+
+use super::{Database, DbError, IdTag, IdUser, Tag, WordAddress, ID_ANONYMOUS_USER};
+
+/// Zone des [`IdTag`] propres au simulateur
+pub const SIM_ZONE: u8 = 0xFF;
+
+/// Première [`WordAddress`] des [`Tag`] propres au simulateur
+pub const SIM_WORD_ADDRESS_BASE: WordAddress = 0x7F00;
+
+/// État de santé des tâches du simulateur (1 bit par tâche, voir le module `supervisor`)
+pub const ID_TAG_SIM_HEALTH: IdTag = IdTag::new(SIM_ZONE, 0x0001, [0, 0, 0]);
+
+/// Nombre de redémarrages de tâches du simulateur après un crash
+pub const ID_TAG_SIM_RESTARTS: IdTag = IdTag::new(SIM_ZONE, 0x0002, [0, 0, 0]);
+
+/// Demande de coupure d'alimentation simulée de l'AFSEC+ (durée de la coupure en secondes,
+/// remis à 0 lors de la prise en compte de la demande, voir le module `afsec`)
+pub const ID_TAG_SIM_POWER_CYCLE: IdTag = IdTag::new(SIM_ZONE, 0x0003, [0, 0, 0]);
+
+/// État de la communication avec l'AFSEC+ (voir les constantes `AFSEC_STATE_*` du module `afsec`)
+pub const ID_TAG_SIM_AFSEC_STATE: IdTag = IdTag::new(SIM_ZONE, 0x0004, [0, 0, 0]);
+
+/// Demande de remise aux valeurs par défaut des [`Tag`] (`SIM_RESET_DEFAULTS_ALL_ZONES` pour
+/// toutes les zones ou `SIM_RESET_DEFAULTS_ZONE | zone` pour une zone, remis à 0 lors de la prise
+/// en compte de la demande, voir `Database::take_reset_defaults_request`)
+pub const ID_TAG_SIM_RESET_DEFAULTS: IdTag = IdTag::new(SIM_ZONE, 0x0005, [0, 0, 0]);
+
+/// Valeur de `ID_TAG_SIM_RESET_DEFAULTS` pour une remise aux valeurs par défaut de toutes les zones
+pub const SIM_RESET_DEFAULTS_ALL_ZONES: u16 = 0xFFFF;
+
+/// Valeur de `ID_TAG_SIM_RESET_DEFAULTS` (avec le numéro de la zone dans l'octet de poids faible)
+/// pour une remise aux valeurs par défaut d'une zone
+pub const SIM_RESET_DEFAULTS_ZONE: u16 = 0x0100;
+
+/// Nombre de connexions MODBUS/TCP en cours (voir le module `server_modbus_tcp`)
+pub const ID_TAG_SIM_MODBUS_CONNECTIONS: IdTag = IdTag::new(SIM_ZONE, 0x0006, [0, 0, 0]);
+
+/// Nombre de trames inexploitables ('junk') reçues de l'AFSEC+ (voir le module `afsec`)
+pub const ID_TAG_SIM_JUNK_FRAMES: IdTag = IdTag::new(SIM_ZONE, 0x0007, [0, 0, 0]);
+
+/// Port série actif pour la communication avec l'AFSEC+ (1: port principal, 2: port de secours)
+pub const ID_TAG_SIM_AFSEC_ACTIVE_PORT: IdTag = IdTag::new(SIM_ZONE, 0x0008, [0, 0, 0]);
+
+/// Date (en secondes depuis le 01/01/1970 UTC) de la dernière synchronisation de l'horloge
+/// simulée : l'écriture d'une nouvelle date (par exemple par l'AFSEC+ dans un `AF_DATA_OUT`)
+/// synchronise l'horloge simulée (voir les modules `sim_clock` et `afsec`)
+pub const ID_TAG_SIM_TIME_SYNC: IdTag = IdTag::new(SIM_ZONE, 0x0009, [0, 0, 0]);
+
+/// Mode de fonctionnement de l'AFSEC+ (`D_MODE_AFSEC` reçu dans `AF_INIT` ou lors d'un changement
+/// de mode, voir le module `afsec`)
+pub const ID_TAG_SIM_AFSEC_MODE: IdTag = IdTag::new(SIM_ZONE, 0x000A, [0, 0, 0]);
+
+/// Pictogrammes de la face avant de l'ICOM (1 bit par pictogramme, `D_MENU_PICTOS` reçu de
+/// l'AFSEC+, voir le module `front_panel`)
+pub const ID_TAG_SIM_FRONT_PICTOS: IdTag = IdTag::new(SIM_ZONE, 0x000B, [0, 0, 0]);
+
+/// Voyants de la face avant de l'ICOM (voir les constantes `FRONT_LED_*` du module `front_panel`)
+pub const ID_TAG_SIM_FRONT_LEDS: IdTag = IdTag::new(SIM_ZONE, 0x000C, [0, 0, 0]);
+
+/// Version du simulateur (chaîne de 16 caractères max., voir le module `build_info`)
+pub const ID_TAG_SIM_VERSION: IdTag = IdTag::new(SIM_ZONE, 0x000D, [0, 0, 0]);
+
+/// Hash git du build du simulateur (chaîne de 16 caractères max., voir le module `build_info`)
+pub const ID_TAG_SIM_GIT_HASH: IdTag = IdTag::new(SIM_ZONE, 0x000E, [0, 0, 0]);
+
+/// Date du build du simulateur (chaîne de 16 caractères max., voir le module `build_info`)
+pub const ID_TAG_SIM_BUILD_DATE: IdTag = IdTag::new(SIM_ZONE, 0x000F, [0, 0, 0]);
+
+/// Nombre de réponses d'exception MODBUS/TCP émises (voir le module `server_modbus_tcp`)
+pub const ID_TAG_SIM_MODBUS_EXCEPTIONS: IdTag = IdTag::new(SIM_ZONE, 0x0011, [0, 0, 0]);
+
+/// Budgets d'erreurs dépassés (1 bit par budget avec l'action `tag`, voir le module
+/// `error_budget`), remis à 0 uniquement par une écriture de ce [`Tag`]
+pub const ID_TAG_SIM_ERROR_BUDGET: IdTag = IdTag::new(SIM_ZONE, 0x0012, [0, 0, 0]);
+
+/// Nombre de [`Tag`] dont la valeur est plus ancienne que leur âge max. (voir le module
+/// `tag_freshness`)
+pub const ID_TAG_SIM_STALE_TAGS: IdTag = IdTag::new(SIM_ZONE, 0x0013, [0, 0, 0]);
+
+/// État du lien avec l'AFSEC+ (0: établi, 1: retransmissions, 2: coupé, voir le module
+/// `link_watchdog` de `afsec`)
+pub const ID_TAG_SIM_AFSEC_LINK: IdTag = IdTag::new(SIM_ZONE, 0x0014, [0, 0, 0]);
+
+/// Nombre d'écritures de tags par une autre source que leur propriétaire (voir le module
+/// `tag_owners`)
+pub const ID_TAG_SIM_OWNER_VIOLATIONS: IdTag = IdTag::new(SIM_ZONE, 0x0015, [0, 0, 0]);
+
+/// Nombre de requêtes MODBUS/TCP diffusées (unité 0) reçues (voir le module `broadcast` de
+/// `server_modbus_tcp`)
+pub const ID_TAG_SIM_MODBUS_BROADCASTS: IdTag = IdTag::new(SIM_ZONE, 0x0016, [0, 0, 0]);
+
+/// Nombre de connexions MODBUS/TCP rejetées selon l'adresse IP du client (voir le module
+/// `client_filter` de `server_modbus_tcp`)
+pub const ID_TAG_SIM_MODBUS_REJECTED_CONNECTIONS: IdTag = IdTag::new(SIM_ZONE, 0x0017, [0, 0, 0]);
+
+/// Numéro de série du simulateur (voir le module `sim_identity`)
+pub const ID_TAG_SIM_SERIAL_NUMBER: IdTag = IdTag::new(SIM_ZONE, 0x0018, [0, 0, 0]);
+
+/// Adresse 'MAC' du simulateur (voir le module `sim_identity`)
+pub const ID_TAG_SIM_MAC_ADDRESS: IdTag = IdTag::new(SIM_ZONE, 0x0019, [0, 0, 0]);
+
+/// Nombre de caractères max. du numéro de série du simulateur
+pub(super) const SIM_SERIAL_NUMBER_LEN: usize = 16;
+
+/// Nombre de caractères des [`Tag`] d'identification du build
+const SIM_BUILD_INFO_LEN: usize = 16;
+
+/// Nombre de zones (0 à `SIM_NB_ZONE_CRCS - 1`) dont le CRC est publié (voir le module `zone_crcs`)
+pub const SIM_NB_ZONE_CRCS: u8 = 16;
+
+/// Offset de la [`WordAddress`] (depuis `SIM_WORD_ADDRESS_BASE`) du CRC de la zone 0
+const SIM_ZONE_CRC_WORD_OFFSET: WordAddress = 0x0010;
+
+/// CRC d'une zone de la [`Database`] (voir `Database::crc_of_zone`)
+pub const fn id_tag_sim_zone_crc(zone: u8) -> IdTag {
+    IdTag::new(SIM_ZONE, 0x0010, [0, 0, zone])
+}
+
+/// Nombre de zones (0 à `SIM_NB_ZONE_STATES - 1`) dont l'état vis-à-vis de l'AFSEC+ est publié
+pub const SIM_NB_ZONE_STATES: u8 = 16;
+
+/// Offset de la [`WordAddress`] (depuis `SIM_WORD_ADDRESS_BASE`) de l'état de la zone 0
+const SIM_ZONE_STATE_WORD_OFFSET: WordAddress = 0x0060;
+
+/// État d'une zone de la [`Database`] vis-à-vis de l'AFSEC+ (voir les constantes `AFSEC_STATE_*`
+/// du module `afsec`) : les zones de la [`Database`] sont en initialisation après une coupure
+/// d'alimentation simulée de l'AFSEC+ jusqu'à la réinitialisation des communications par `AF_INIT`
+pub const fn id_tag_sim_zone_state(zone: u8) -> IdTag {
+    IdTag::new(SIM_ZONE, 0x0030, [0, 0, zone])
+}
+
+/// Nombre de liens série avec l'AFSEC+ (1: principal, 2: secours) dont les statistiques sont
+/// publiées (voir le module `link_stats` de `afsec`)
+pub const SIM_NB_LINKS: u8 = 2;
+
+/// Nombre de statistiques (compteurs `u32`) publiées par lien avec l'AFSEC+
+pub const SIM_NB_LINK_STATS: usize = 6;
+
+/// Libellés des statistiques d'un lien avec l'AFSEC+ (dans l'ordre de publication)
+const SIM_LINK_STAT_LABELS: [&str; SIM_NB_LINK_STATS] = [
+    "trames reçues",
+    "trames émises",
+    "ACK",
+    "NACK",
+    "trames inexploitables",
+    "date dernier AF_INIT",
+];
+
+/// Offset de la [`WordAddress`] (depuis `SIM_WORD_ADDRESS_BASE`) des statistiques du lien 1
+const SIM_LINK_STATS_WORD_OFFSET: WordAddress = 0x0040;
+
+/// Nombre de [`WordAddress`] réservées aux statistiques de chaque lien
+const SIM_LINK_STATS_WORDS: WordAddress = 0x0010;
+
+/// [`IdTag`] d'une statistique (rang dans `SIM_LINK_STAT_LABELS`) d'un lien (1 ou 2) avec l'AFSEC+
+pub const fn id_tag_sim_link_stat(link: u8, stat: u8) -> IdTag {
+    IdTag::new(SIM_ZONE, 0x0020 + stat as u16, [0, 0, link])
+}
+
+/// Définition des [`Tag`] propres au simulateur :
+/// ([`IdTag`], offset de la [`WordAddress`] depuis `SIM_WORD_ADDRESS_BASE`, format, libellé)
+const SIM_TAGS: &[(IdTag, WordAddress, TFormat, &str)] = &[
+    (
+        ID_TAG_SIM_HEALTH,
+        0x0000,
+        TFormat::U16,
+        "Simulateur: Santé des tâches",
+    ),
+    (
+        ID_TAG_SIM_RESTARTS,
+        0x0001,
+        TFormat::U16,
+        "Simulateur: Redémarrages",
+    ),
+    (
+        ID_TAG_SIM_POWER_CYCLE,
+        0x0002,
+        TFormat::U16,
+        "Simulateur: Coupure AFSEC+ (s)",
+    ),
+    (
+        ID_TAG_SIM_AFSEC_STATE,
+        0x0003,
+        TFormat::U16,
+        "Simulateur: État AFSEC+",
+    ),
+    (
+        ID_TAG_SIM_RESET_DEFAULTS,
+        0x0004,
+        TFormat::U16,
+        "Simulateur: Valeurs par défaut",
+    ),
+    (
+        ID_TAG_SIM_MODBUS_CONNECTIONS,
+        0x0005,
+        TFormat::U16,
+        "Simulateur: Connexions MODBUS/TCP",
+    ),
+    (
+        ID_TAG_SIM_JUNK_FRAMES,
+        0x0006,
+        TFormat::U16,
+        "Simulateur: Trames AFSEC+ inexploitables",
+    ),
+    (
+        ID_TAG_SIM_AFSEC_ACTIVE_PORT,
+        0x0007,
+        TFormat::U16,
+        "Simulateur: Port AFSEC+ actif",
+    ),
+    (
+        ID_TAG_SIM_TIME_SYNC,
+        0x0008,
+        TFormat::U32,
+        "Simulateur: Synchronisation horloge",
+    ),
+    (
+        ID_TAG_SIM_AFSEC_MODE,
+        0x0009,
+        TFormat::U16,
+        "Simulateur: Mode AFSEC+",
+    ),
+    (
+        ID_TAG_SIM_FRONT_PICTOS,
+        0x000A,
+        TFormat::U32,
+        "Simulateur: Pictogrammes face avant",
+    ),
+    (
+        ID_TAG_SIM_FRONT_LEDS,
+        0x000C,
+        TFormat::U16,
+        "Simulateur: Voyants face avant",
+    ),
+    (
+        ID_TAG_SIM_MODBUS_EXCEPTIONS,
+        0x000D,
+        TFormat::U16,
+        "Simulateur: Exceptions MODBUS/TCP",
+    ),
+    (
+        ID_TAG_SIM_ERROR_BUDGET,
+        0x000E,
+        TFormat::U16,
+        "Simulateur: Budgets d'erreurs dépassés",
+    ),
+    (
+        ID_TAG_SIM_STALE_TAGS,
+        0x000F,
+        TFormat::U16,
+        "Simulateur: Tags périmés",
+    ),
+    (
+        ID_TAG_SIM_AFSEC_LINK,
+        0x0038,
+        TFormat::U16,
+        "Simulateur: Lien AFSEC+",
+    ),
+    (
+        ID_TAG_SIM_OWNER_VIOLATIONS,
+        0x0039,
+        TFormat::U16,
+        "Simulateur: Écritures hors propriétaire",
+    ),
+    (
+        ID_TAG_SIM_MODBUS_BROADCASTS,
+        0x003A,
+        TFormat::U16,
+        "Simulateur: Requêtes MODBUS diffusées",
+    ),
+    (
+        ID_TAG_SIM_MODBUS_REJECTED_CONNECTIONS,
+        0x003B,
+        TFormat::U16,
+        "Simulateur: Connexions MODBUS rejetées",
+    ),
+    (
+        ID_TAG_SIM_VERSION,
+        0x0020,
+        TFormat::VecU8(SIM_BUILD_INFO_LEN),
+        "Simulateur: Version",
+    ),
+    (
+        ID_TAG_SIM_GIT_HASH,
+        0x0028,
+        TFormat::VecU8(SIM_BUILD_INFO_LEN),
+        "Simulateur: Hash git",
+    ),
+    (
+        ID_TAG_SIM_BUILD_DATE,
+        0x0030,
+        TFormat::VecU8(SIM_BUILD_INFO_LEN),
+        "Simulateur: Date du build",
+    ),
+    (
+        ID_TAG_SIM_SERIAL_NUMBER,
+        0x0070,
+        TFormat::VecU8(SIM_SERIAL_NUMBER_LEN),
+        "Simulateur: Numéro de série",
+    ),
+    (
+        ID_TAG_SIM_MAC_ADDRESS,
+        0x0078,
+        TFormat::VecU8(6),
+        "Simulateur: Adresse MAC",
+    ),
+];
+
+/// Trace de l'erreur d'écriture d'un [`Tag`] propre au simulateur
+/// (les tags du simulateur ne sont pas définis dans toutes les [`Database`] : tests, FFI, ...)
+pub(super) fn trace_sim_tag_error(result: Result<(), DbError>) {
+    match result {
+        Ok(()) | Err(DbError::UnknownTag(_)) => (),
+        Err(db_error) => println!("!!! Tag du simulateur: {db_error}"),
+    }
+}
+
+impl Database {
+    /// Ajoute les [`Tag`] propres au simulateur dans la [`Database`]
+    /// Un [`Tag`] n'est pas ajouté si son [`IdTag`] ou sa [`WordAddress`] est déjà attribué
+    pub fn add_sim_tags(&mut self) {
+        let zone_crc_tags = (0..SIM_NB_ZONE_CRCS).map(|zone| {
+            (
+                id_tag_sim_zone_crc(zone),
+                SIM_ZONE_CRC_WORD_OFFSET + WordAddress::from(zone),
+                TFormat::U16,
+                format!("Simulateur: CRC zone {zone}"),
+            )
+        });
+        let zone_state_tags = (0..SIM_NB_ZONE_STATES).map(|zone| {
+            (
+                id_tag_sim_zone_state(zone),
+                SIM_ZONE_STATE_WORD_OFFSET + WordAddress::from(zone),
+                TFormat::U16,
+                format!("Simulateur: État zone {zone}"),
+            )
+        });
+        let link_stat_tags = (1..=SIM_NB_LINKS).flat_map(|link| {
+            SIM_LINK_STAT_LABELS
+                .iter()
+                .enumerate()
+                .map(move |(stat, label)| {
+                    let stat = u8::try_from(stat).unwrap_or(u8::MAX);
+                    (
+                        id_tag_sim_link_stat(link, stat),
+                        SIM_LINK_STATS_WORD_OFFSET
+                            + WordAddress::from(link - 1) * SIM_LINK_STATS_WORDS
+                            + 2 * WordAddress::from(stat),
+                        TFormat::U32,
+                        format!("Simulateur: Lien AFSEC+ {link} {label}"),
+                    )
+                })
+        });
+        let sim_tags = SIM_TAGS
+            .iter()
+            .map(|(id_tag, word_offset, t_format, label)| {
+                (*id_tag, *word_offset, *t_format, (*label).to_string())
+            })
+            .chain(zone_crc_tags)
+            .chain(zone_state_tags)
+            .chain(link_stat_tags);
+        for (id_tag, word_offset, t_format, label) in sim_tags {
+            let word_address = SIM_WORD_ADDRESS_BASE + word_offset;
+            if self.get_tag_from_id_tag(id_tag).is_some()
+                || self.get_tag_from_word_address(word_address).is_some()
+            {
+                continue;
+            }
+            let tag = Tag {
+                word_address,
+                id_tag,
+                is_internal: true,
+                t_format,
+                label,
+                ..Default::default()
+            };
+            self.add_tag(&tag);
+        }
+
+        // Identification du build du simulateur
+        for (id_tag, value) in [
+            (ID_TAG_SIM_VERSION, build_info::VERSION),
+            (ID_TAG_SIM_GIT_HASH, build_info::GIT_HASH),
+            (ID_TAG_SIM_BUILD_DATE, build_info::BUILD_DATE),
+        ] {
+            let id_user = self.sim_id_user();
+            trace_sim_tag_error(self.set_vec_u8_to_id_tag(id_user, id_tag, value.as_bytes()));
+        }
+    }
+
+    /// Écriture d'un [`Tag`] `u16` propre au simulateur
+    /// (sans effet si les tags du simulateur ne sont pas définis dans la [`Database`])
+    pub fn set_sim_tag_u16(&mut self, id_user: IdUser, id_tag: IdTag, value: u16) {
+        trace_sim_tag_error(self.set_u16_to_id_tag(id_user, id_tag, value));
+    }
+
+    /// Écriture d'un [`Tag`] `u32` propre au simulateur
+    /// (sans effet si les tags du simulateur ne sont pas définis dans la [`Database`])
+    pub fn set_sim_tag_u32(&mut self, id_user: IdUser, id_tag: IdTag, value: u32) {
+        trace_sim_tag_error(self.set_u32_to_id_tag(id_user, id_tag, value));
+    }
+
+    /// Mise à jour de bits d'un [`Tag`] `u16` propre au simulateur
+    /// Les bits de `mask` sont mis à 1 si `value` est vrai, sinon à 0
+    pub fn set_sim_tag_bits(&mut self, id_tag: IdTag, mask: u16, value: bool) {
+        let bits = self.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag);
+        let bits = if value { bits | mask } else { bits & !mask };
+        let id_user = self.sim_id_user();
+        self.set_sim_tag_u16(id_user, id_tag, bits);
+    }
+
+    /// Incrémente un compteur `u16` propre au simulateur
+    pub fn increment_sim_tag(&mut self, id_tag: IdTag) {
+        let counter = self.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag);
+        let id_user = self.sim_id_user();
+        self.set_sim_tag_u16(id_user, id_tag, counter.wrapping_add(1));
+    }
+
+    /// Décrémente (jusqu'à 0) un compteur `u16` propre au simulateur
+    pub fn decrement_sim_tag(&mut self, id_tag: IdTag) {
+        let counter = self.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag);
+        let id_user = self.sim_id_user();
+        self.set_sim_tag_u16(id_user, id_tag, counter.saturating_sub(1));
+    }
+
+    /// Publication des statistiques d'un lien (1 ou 2) avec l'AFSEC+ (seules les valeurs modifiées
+    /// sont écrites)
+    pub fn publish_link_stats(&mut self, link: u8, values: &[u32; SIM_NB_LINK_STATS]) {
+        let id_user = self.sim_id_user();
+        for (stat, value) in values.iter().enumerate() {
+            let id_tag = id_tag_sim_link_stat(link, u8::try_from(stat).unwrap_or(u8::MAX));
+            if self.get_u32_from_id_tag(id_user, id_tag) != *value {
+                self.set_sim_tag_u32(id_user, id_tag, *value);
+            }
+        }
+    }
+
+    /// Publication de l'état vis-à-vis de l'AFSEC+ (voir les constantes `AFSEC_STATE_*` du module
+    /// `afsec`) des zones de la [`Database`] (hors zone du simulateur)
+    pub fn set_zone_states(&mut self, id_user: IdUser, state: u16) {
+        for zone in self.get_zones() {
+            if zone < SIM_NB_ZONE_STATES {
+                self.set_sim_tag_u16(id_user, id_tag_sim_zone_state(zone), state);
+            }
+        }
+    }
+
+    /// Prise en compte (et remise à 0) d'une demande de remise aux valeurs par défaut dans le
+    /// [`Tag`] `ID_TAG_SIM_RESET_DEFAULTS` (voir `Database::reset_to_defaults`)
+    /// Retourne la zone concernée (None pour toutes les zones) et le nombre de [`Tag`] remis à
+    /// leur valeur par défaut, ou None si pas de demande (ou demande incorrecte)
+    pub fn take_reset_defaults_request(&mut self, id_user: IdUser) -> Option<(Option<u8>, usize)> {
+        let request = self.get_u16_from_id_tag(id_user, ID_TAG_SIM_RESET_DEFAULTS);
+        if request == 0 {
+            return None;
+        }
+        self.set_sim_tag_u16(id_user, ID_TAG_SIM_RESET_DEFAULTS, 0);
+        let option_zone = match request {
+            SIM_RESET_DEFAULTS_ALL_ZONES => None,
+            _ if request & 0xFF00 == SIM_RESET_DEFAULTS_ZONE => Some(request.to_be_bytes()[1]),
+            _ => return None,
+        };
+        Some((option_zone, self.reset_to_defaults(id_user, option_zone)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_sim_tags() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let tag = db.get_tag_from_id_tag(ID_TAG_SIM_HEALTH).unwrap();
+        assert_eq!(tag.word_address, SIM_WORD_ADDRESS_BASE);
+        assert_eq!(tag.t_format, TFormat::U16);
+
+        // Pas de doublon si appelé plusieurs fois
+        db.add_sim_tags();
+    }
+
+    #[test]
+    fn test_build_info_tags() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let tag = db.get_tag_from_id_tag(ID_TAG_SIM_VERSION).unwrap();
+        let t_value = db.get_t_value_from_tag(ID_ANONYMOUS_USER, tag);
+        assert_eq!(t_value.as_text(), Some(build_info::VERSION.to_string()));
+    }
+
+    #[test]
+    fn test_sim_tag_bits() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        db.set_sim_tag_bits(ID_TAG_SIM_HEALTH, 0x0001, true);
+        db.set_sim_tag_bits(ID_TAG_SIM_HEALTH, 0x0004, true);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_HEALTH),
+            0x0005
+        );
+        db.set_sim_tag_bits(ID_TAG_SIM_HEALTH, 0x0001, false);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_HEALTH),
+            0x0004
+        );
+
+        db.increment_sim_tag(ID_TAG_SIM_RESTARTS);
+        db.increment_sim_tag(ID_TAG_SIM_RESTARTS);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_RESTARTS),
+            2
+        );
+        db.decrement_sim_tag(ID_TAG_SIM_RESTARTS);
+        db.decrement_sim_tag(ID_TAG_SIM_RESTARTS);
+        db.decrement_sim_tag(ID_TAG_SIM_RESTARTS);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_RESTARTS),
+            0
+        );
+    }
+
+    #[test]
+    fn test_set_zone_states() {
+        let mut db = Database::default();
+        db.add_tag(&Tag {
+            word_address: 0x0000,
+            id_tag: IdTag::new(2, 0x0001, [0, 0, 0]),
+            ..Default::default()
+        });
+        db.add_sim_tags();
+        let id_user = db.sim_id_user();
+        db.set_zone_states(id_user, 2);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag_sim_zone_state(2)),
+            2
+        );
+        // Zone sans tag
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag_sim_zone_state(1)),
+            0
+        );
+        db.set_zone_states(id_user, 0);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag_sim_zone_state(2)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_publish_link_stats() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let tag = db.get_tag_from_id_tag(id_tag_sim_link_stat(2, 5)).unwrap();
+        assert_eq!(tag.word_address, SIM_WORD_ADDRESS_BASE + 0x005A);
+        assert_eq!(tag.t_format, TFormat::U32);
+
+        db.publish_link_stats(2, &[10, 9, 1, 2, 3, 0x1234_5678]);
+        assert_eq!(
+            db.get_u32_from_id_tag(ID_ANONYMOUS_USER, id_tag_sim_link_stat(2, 0)),
+            10
+        );
+        assert_eq!(
+            db.get_u32_from_word_address(ID_ANONYMOUS_USER, SIM_WORD_ADDRESS_BASE + 0x005A),
+            0x1234_5678
+        );
+        assert_eq!(
+            db.get_u32_from_id_tag(ID_ANONYMOUS_USER, id_tag_sim_link_stat(1, 0)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_reset_defaults_request() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(5, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            default_value: "3".to_string(),
+            ..Default::default()
+        });
+        db.set_u16_to_word_address(ID_ANONYMOUS_USER, 0x0010, 100)
+            .unwrap();
+        assert_eq!(db.take_reset_defaults_request(ID_ANONYMOUS_USER), None);
+
+        // Zone 4 seulement
+        db.set_u16_to_id_tag(
+            ID_ANONYMOUS_USER,
+            ID_TAG_SIM_RESET_DEFAULTS,
+            SIM_RESET_DEFAULTS_ZONE | 4,
+        )
+        .unwrap();
+        assert_eq!(
+            db.take_reset_defaults_request(ID_ANONYMOUS_USER),
+            Some((Some(4), 0))
+        );
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 100);
+
+        // Toutes les zones
+        db.set_u16_to_id_tag(
+            ID_ANONYMOUS_USER,
+            ID_TAG_SIM_RESET_DEFAULTS,
+            SIM_RESET_DEFAULTS_ALL_ZONES,
+        )
+        .unwrap();
+        assert_eq!(
+            db.take_reset_defaults_request(ID_ANONYMOUS_USER),
+            Some((None, 1))
+        );
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 3);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_RESET_DEFAULTS),
+            0
+        );
+    }
+}
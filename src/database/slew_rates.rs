@@ -0,0 +1,315 @@
+//! Limitation de la vitesse de variation (rampe) de la valeur de [`Tag`] numériques
+//!
+//! Pour un [`Tag`] avec une rampe, une écriture (quel que soit l'utilisateur) n'est pas appliquée
+//! immédiatement : la valeur écrite devient la consigne et la valeur du [`Tag`] évolue vers cette
+//! consigne à la vitesse configurée (en unités par seconde) à chaque appel de
+//! `Database::step_slew_rates` (voir le process `slew_rate_process`).
+//!
+//! La table des rampes est consultée par `Database::set_vec_u8_to_word_address`
+//! (seul point d'entrée des modifications de la [`Database`]).
+
+use crate::t_data::{be_data, TFormat};
+
+use super::{Database, IdTag, IdUser, Tag, WordAddress};
+
+/// Rampe d'un [`Tag`]
+#[derive(Clone, Debug)]
+pub struct SlewRate {
+    /// Vitesse de variation (unités par seconde)
+    rate: f64,
+
+    /// Position courante de la rampe (non arrondie pour les formats entiers)
+    position: f64,
+
+    /// Consigne à atteindre (None si la consigne est atteinte)
+    target: Option<f64>,
+}
+
+impl SlewRate {
+    /// Vitesse de variation (unités par seconde)
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Consigne en cours (None si la consigne est atteinte)
+    pub fn target(&self) -> Option<f64> {
+        self.target
+    }
+}
+
+/// Indique si une rampe est possible pour un [`TFormat`] (formats numériques seulement)
+fn is_slew_rate_format(t_format: TFormat) -> bool {
+    !matches!(
+        t_format,
+        TFormat::Unknown | TFormat::Bool | TFormat::VecU8(_)
+    )
+}
+
+/// Valeur numérique du contenu d'un [`Tag`] numérique dans la [`Database`]
+/// (les `u8` et `i8` sont dans l'octet de poids faible du mot)
+fn tag_vec_u8_to_f64(t_format: TFormat, vec_u8: &[u8]) -> Option<f64> {
+    let vec_u8 = match t_format {
+        TFormat::U8 | TFormat::I8 => vec_u8.get(1..)?,
+        _ => vec_u8,
+    };
+    be_data::decode(t_format, vec_u8)
+        .ok()
+        .map(|t_value| f64::from(&t_value))
+}
+
+/// Valeur (au format string, voir `Database::set_value`) d'une position de rampe
+fn position_to_string(t_format: TFormat, position: f64) -> String {
+    match t_format {
+        TFormat::F32 | TFormat::F64 => format!("{position}"),
+        _ => format!("{}", position.round()),
+    }
+}
+
+impl Database {
+    /// Définit la vitesse de variation (unités par seconde) d'un [`Tag`] numérique
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini, n'est pas numérique ou si la vitesse
+    /// n'est pas strictement positive
+    pub fn set_slew_rate(
+        &mut self,
+        id_user: IdUser,
+        id_tag: IdTag,
+        rate: f64,
+    ) -> Result<(), String> {
+        let Some(tag) = self.get_tag_from_id_tag(id_tag) else {
+            return Err(format!("Tag {id_tag} inconnu"));
+        };
+        if !is_slew_rate_format(tag.t_format) {
+            return Err(format!("Tag {id_tag} non numérique ({})", tag.t_format));
+        }
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(format!("Vitesse de variation '{rate}' incorrecte"));
+        }
+        let tag = tag.clone();
+        match self.slew_rates.get_mut(&id_tag) {
+            Some(slew_rate) => slew_rate.rate = rate,
+            None => {
+                let position = f64::from(&self.get_t_value_from_tag(id_user, &tag));
+                self.slew_rates.insert(
+                    id_tag,
+                    SlewRate {
+                        rate,
+                        position,
+                        target: None,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Supprime la rampe d'un [`Tag`] et applique immédiatement la consigne en cours
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'a pas de rampe ou si la consigne ne peut pas être écrite
+    pub fn remove_slew_rate(&mut self, id_user: IdUser, id_tag: IdTag) -> Result<(), String> {
+        let Some(slew_rate) = self.slew_rates.remove(&id_tag) else {
+            return Err(format!("Tag {id_tag} sans rampe"));
+        };
+        if let (Some(target), Some(tag)) =
+            (slew_rate.target, self.get_tag_from_id_tag(id_tag).cloned())
+        {
+            self.set_value(id_user, &tag, &position_to_string(tag.t_format, target))?;
+        }
+        Ok(())
+    }
+
+    /// Rampe d'un [`Tag`]
+    pub fn get_slew_rate(&self, id_tag: IdTag) -> Option<&SlewRate> {
+        self.slew_rates.get(&id_tag)
+    }
+
+    /// Liste des [`Tag`] avec une rampe (dans l'ordre des adresses)
+    pub fn get_slew_rate_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self
+            .slew_rates
+            .keys()
+            .filter_map(|id_tag| self.get_tag_from_id_tag(*id_tag))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        tags
+    }
+
+    /// Fait évoluer les [`Tag`] avec une rampe vers leur consigne pendant `elapsed_secs` secondes
+    /// Retourne le nombre de [`Tag`] modifiés
+    pub fn step_slew_rates(&mut self, id_user: IdUser, elapsed_secs: f64) -> usize {
+        let id_tags: Vec<IdTag> = self
+            .slew_rates
+            .iter()
+            .filter(|(_, slew_rate)| slew_rate.target.is_some())
+            .map(|(id_tag, _)| *id_tag)
+            .collect();
+        let mut nb_tags = 0;
+        for id_tag in id_tags {
+            let Some(tag) = self.get_tag_from_id_tag(id_tag).cloned() else {
+                continue;
+            };
+
+            // Écriture de la nouvelle position hors rampe
+            let Some(mut slew_rate) = self.slew_rates.remove(&id_tag) else {
+                continue;
+            };
+            if let Some(target) = slew_rate.target {
+                let step = slew_rate.rate * elapsed_secs;
+                if (target - slew_rate.position).abs() <= step {
+                    slew_rate.position = target;
+                    slew_rate.target = None;
+                } else {
+                    slew_rate.position += step.copysign(target - slew_rate.position);
+                }
+                let value = position_to_string(tag.t_format, slew_rate.position);
+                match self.set_value(id_user, &tag, &value) {
+                    Ok(()) => nb_tags += 1,
+                    Err(db_error) => println!("!!! Rampe de {tag}: {db_error}"),
+                }
+            }
+            self.slew_rates.insert(id_tag, slew_rate);
+        }
+        nb_tags
+    }
+
+    /// Mémorise comme consigne l'écriture de `vec_u8` à partir de `word_address` dans les [`Tag`]
+    /// avec une rampe et retourne `vec_u8` où le contenu de ces [`Tag`] est remplacé par le
+    /// contenu actuel de la [`Database`]
+    pub(super) fn without_slew_rates(
+        &mut self,
+        word_address: WordAddress,
+        vec_u8: &[u8],
+    ) -> Vec<u8> {
+        let mut vec_u8 = vec_u8.to_vec();
+        if self.slew_rates.is_empty() {
+            return vec_u8;
+        }
+        let nb_words = vec_u8.len().div_ceil(2);
+        let write_start = 2 * word_address as usize;
+        let write_end = write_start + vec_u8.len();
+        for tag in self.get_tags_from_word_address_area(word_address, nb_words) {
+            let Some(slew_rate) = self.slew_rates.get_mut(&tag.id_tag) else {
+                continue;
+            };
+            let tag_start = 2 * tag.word_address as usize;
+            let tag_end = tag_start + 2 * tag.t_format.nb_words();
+            let (start, end) = (tag_start.max(write_start), tag_end.min(write_end));
+            if start >= end {
+                continue;
+            }
+
+            // Contenu du [`Tag`] après l'écriture (consigne)
+            let mut target = self.vec_u8[tag_start..tag_end].to_vec();
+            target[start - tag_start..end - tag_start]
+                .copy_from_slice(&vec_u8[start - write_start..end - write_start]);
+            if let Some(target) = tag_vec_u8_to_f64(tag.t_format, &target) {
+                if slew_rate.target.is_none() {
+                    slew_rate.position =
+                        tag_vec_u8_to_f64(tag.t_format, &self.vec_u8[tag_start..tag_end])
+                            .unwrap_or(slew_rate.position);
+                }
+                slew_rate.target = Some(target);
+            }
+            vec_u8[start - write_start..end - write_start]
+                .copy_from_slice(&self.vec_u8[start..end]);
+        }
+        vec_u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    fn database_setup() -> Database {
+        let mut db = Database::default();
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        });
+        db.add_tag(&Tag {
+            word_address: 0x0020,
+            id_tag: IdTag::new(1, 2, [0, 0, 0]),
+            t_format: TFormat::F32,
+            ..Default::default()
+        });
+        db.add_tag(&Tag {
+            word_address: 0x0030,
+            id_tag: IdTag::new(1, 3, [0, 0, 0]),
+            t_format: TFormat::Bool,
+            ..Default::default()
+        });
+        db
+    }
+
+    #[test]
+    fn test_slew_rate_u16() {
+        let mut db = database_setup();
+        let id_tag = IdTag::new(1, 1, [0, 0, 0]);
+        let id_user = db.get_id_user("TEST", false);
+        db.set_u16_to_word_address(id_user, 0x0010, 100).unwrap();
+        db.set_slew_rate(id_user, id_tag, 10.0).unwrap();
+
+        // L'écriture devient la consigne
+        db.set_u16_to_word_address(id_user, 0x0010, 120).unwrap();
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 100);
+        assert_eq!(db.get_slew_rate(id_tag).unwrap().target(), Some(120.0));
+
+        // Évolution à 10 unités par seconde
+        assert_eq!(db.step_slew_rates(id_user, 0.5), 1);
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 105);
+        db.step_slew_rates(id_user, 0.1);
+        db.step_slew_rates(id_user, 0.1);
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 107);
+        db.step_slew_rates(id_user, 10.0);
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 120);
+        assert_eq!(db.get_slew_rate(id_tag).unwrap().target(), None);
+        assert_eq!(db.step_slew_rates(id_user, 1.0), 0);
+
+        // Descente
+        db.set_u16_to_word_address(id_user, 0x0010, 110).unwrap();
+        db.step_slew_rates(id_user, 0.5);
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 115);
+
+        // Suppression de la rampe: la consigne est appliquée
+        db.remove_slew_rate(id_user, id_tag).unwrap();
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 110);
+        assert!(db.remove_slew_rate(id_user, id_tag).is_err());
+        db.set_u16_to_word_address(id_user, 0x0010, 50).unwrap();
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 50);
+    }
+
+    #[test]
+    fn test_slew_rate_f32() {
+        let mut db = database_setup();
+        let id_tag = IdTag::new(1, 2, [0, 0, 0]);
+        db.set_slew_rate(ID_ANONYMOUS_USER, id_tag, 0.5).unwrap();
+        assert_eq!(db.get_slew_rate_tags().len(), 1);
+
+        db.set_f32_to_word_address(ID_ANONYMOUS_USER, 0x0020, -1.0)
+            .unwrap();
+        db.step_slew_rates(ID_ANONYMOUS_USER, 1.0);
+        assert!((db.get_f32_from_word_address(ID_ANONYMOUS_USER, 0x0020) + 0.5).abs() < 1e-6);
+        db.step_slew_rates(ID_ANONYMOUS_USER, 1.0);
+        assert!((db.get_f32_from_word_address(ID_ANONYMOUS_USER, 0x0020) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_slew_rate_errors() {
+        let mut db = database_setup();
+        assert!(db
+            .set_slew_rate(ID_ANONYMOUS_USER, IdTag::new(1, 3, [0, 0, 0]), 1.0)
+            .is_err());
+        assert!(db
+            .set_slew_rate(ID_ANONYMOUS_USER, IdTag::new(1, 1, [0, 0, 0]), 0.0)
+            .is_err());
+        assert!(db
+            .set_slew_rate(ID_ANONYMOUS_USER, IdTag::new(9, 9, [0, 0, 0]), 1.0)
+            .is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Copie instantanée ('snapshot') de zones de la [`Database`]
+//!
+//! Un `middleware` de la communication avec l'AFSEC+ qui assemble une longue réponse (par
+//! exemple les blocs d'une transaction `pack-in`) ne doit pas garder le verrou de la [`Database`]
+//! partagée pendant cet assemblage au détriment des clients MODBUS/TCP : il copie rapidement
+//! les zones de mots dont il a besoin sous le verrou, libère le verrou puis construit sa réponse
+//! à partir de cette copie.
+
+use super::{Database, WordAddress};
+
+/// Copie de zones de mots de la [`Database`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DatabaseSnapshot {
+    /// Zones copiées (première [`WordAddress`], octets de la zone)
+    areas: Vec<(WordAddress, Vec<u8>)>,
+}
+
+impl DatabaseSnapshot {
+    /// Extrait `nb_u8` octets de la copie à partir d'une [`WordAddress`]
+    /// (None si ces octets ne sont pas entièrement dans une des zones copiées)
+    pub fn get_vec_u8_from_word_address(
+        &self,
+        word_address: WordAddress,
+        nb_u8: usize,
+    ) -> Option<Vec<u8>> {
+        self.areas.iter().find_map(|(area_word_address, octets)| {
+            let offset = 2 * usize::from(word_address.checked_sub(*area_word_address)?);
+            octets.get(offset..offset + nb_u8).map(<[u8]>::to_vec)
+        })
+    }
+}
+
+impl Database {
+    /// Copie des zones de `nb_words` mots à partir de chaque [`WordAddress`] de `areas`
+    /// (les zones sont tronquées à la fin de la [`Database`])
+    pub fn snapshot(&self, areas: &[(WordAddress, usize)]) -> DatabaseSnapshot {
+        DatabaseSnapshot {
+            areas: areas
+                .iter()
+                .map(|(word_address, nb_words)| {
+                    let start = (2 * usize::from(*word_address)).min(self.vec_u8.len());
+                    let end = (start + 2 * nb_words).min(self.vec_u8.len());
+                    (*word_address, self.vec_u8[start..end].to_vec())
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    #[test]
+    fn test_snapshot() {
+        let mut db = Database::default();
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0010, &[1, 2, 3, 4])
+            .unwrap();
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0100, &[5, 6])
+            .unwrap();
+        let snapshot = db.snapshot(&[(0x0010, 2), (0x0100, 1), (0x7FFF, 10)]);
+
+        // La copie n'est pas affectée par les modifications suivantes de la database
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0010, &[0, 0, 0, 0])
+            .unwrap();
+        assert_eq!(
+            snapshot.get_vec_u8_from_word_address(0x0010, 4),
+            Some(vec![1, 2, 3, 4])
+        );
+        assert_eq!(
+            snapshot.get_vec_u8_from_word_address(0x0011, 2),
+            Some(vec![3, 4])
+        );
+        assert_eq!(
+            snapshot.get_vec_u8_from_word_address(0x0100, 2),
+            Some(vec![5, 6])
+        );
+
+        // Hors des zones copiées
+        assert_eq!(snapshot.get_vec_u8_from_word_address(0x0011, 4), None);
+        assert_eq!(snapshot.get_vec_u8_from_word_address(0x0000, 2), None);
+        assert_eq!(snapshot.get_vec_u8_from_word_address(0x7FFF, 4), None);
+    }
+}
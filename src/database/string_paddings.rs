@@ -0,0 +1,265 @@
+//! Complément des chaînes de caractères écrites partiellement dans un [`Tag`] `VecU8`
+//!
+//! Une chaîne plus courte que la largeur de son [`Tag`] est historiquement complétée avec des
+//! NULL par `Database::set_value` et `Database::set_vec_u8_to_id_tag` (AFSEC+, console,
+//! scénarios, ...) alors qu'une écriture MODBUS ne modifie que les mots écrits. Certains clients
+//! attendent des espaces ou la conservation de la fin de la chaîne : une politique de complément
+//! peut être attribuée à un [`Tag`] (option `--string-padding`).
+//!
+//! Avec une politique, toute écriture partielle d'une chaîne à partir du début de son [`Tag`]
+//! est complétée selon cette politique, y compris par `Database::set_vec_u8_to_word_address`
+//! (écritures MODBUS).
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::arg_parsing::parse_word_address;
+
+use super::{Database, IdTag, TFormat, Tag, WordAddress};
+
+/// Complément d'une chaîne de caractères plus courte que son [`Tag`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringPadding {
+    /// Complément avec des NULL
+    Zero,
+
+    /// Complément avec des espaces
+    Space,
+
+    /// Fin de la chaîne actuelle conservée
+    Preserve,
+}
+
+impl fmt::Display for StringPadding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StringPadding::Zero => write!(f, "zero"),
+            StringPadding::Space => write!(f, "space"),
+            StringPadding::Preserve => write!(f, "preserve"),
+        }
+    }
+}
+
+impl FromStr for StringPadding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "zero" => Ok(StringPadding::Zero),
+            "space" => Ok(StringPadding::Space),
+            "preserve" => Ok(StringPadding::Preserve),
+            _ => Err(format!(
+                "Complément '{s}' inconnu (zero, space ou preserve)"
+            )),
+        }
+    }
+}
+
+/// Analyse d'une définition de complément `<adresse>=<complément>` (adresse en hexa, `@0010`,
+/// `0x0010` ou `0010`)
+/// # Errors
+/// Message d'erreur si la définition est incorrecte
+pub fn parse_string_padding_spec(spec: &str) -> Result<(WordAddress, StringPadding), String> {
+    let Some((address, padding)) = spec.split_once('=') else {
+        return Err(format!(
+            "Complément '{spec}' incorrect (attendu: <adresse>=<zero|space|preserve>)"
+        ));
+    };
+    let address = address.trim();
+    let Some(word_address) = parse_word_address(address) else {
+        return Err(format!("Adresse '{address}' incorrecte"));
+    };
+    Ok((word_address, StringPadding::from_str(padding)?))
+}
+
+impl Database {
+    /// Définit le complément des écritures partielles d'un [`Tag`] chaîne de caractères (None
+    /// pour le comportement historique)
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini ou n'est pas une chaîne de caractères
+    pub fn set_string_padding(
+        &mut self,
+        id_tag: IdTag,
+        option_padding: Option<StringPadding>,
+    ) -> Result<(), String> {
+        let Some(tag) = self.get_tag_from_id_tag(id_tag) else {
+            return Err(format!("Tag {id_tag} inconnu"));
+        };
+        if !matches!(tag.t_format, TFormat::VecU8(_)) {
+            return Err(format!("Tag {id_tag} n'est pas une chaîne de caractères"));
+        }
+        match option_padding {
+            Some(padding) => {
+                self.string_paddings.insert(id_tag, padding);
+            }
+            None => {
+                self.string_paddings.remove(&id_tag);
+            }
+        }
+        Ok(())
+    }
+
+    /// Complément des écritures partielles d'un [`Tag`] (None pour le comportement historique)
+    pub fn get_string_padding(&self, id_tag: IdTag) -> Option<StringPadding> {
+        self.string_paddings.get(&id_tag).copied()
+    }
+
+    /// Adapte une chaîne de caractères à la largeur d'un [`Tag`] `VecU8` : tronquée si elle est
+    /// trop longue, complétée selon la politique du [`Tag`] (ou `default_padding` s'il n'en a
+    /// pas) si elle est trop courte
+    pub(super) fn padded_string(
+        &self,
+        tag: &Tag,
+        value: &[u8],
+        default_padding: Option<StringPadding>,
+    ) -> Vec<u8> {
+        let TFormat::VecU8(len) = tag.t_format else {
+            return value.to_vec();
+        };
+        let mut vec_u8 = value[..value.len().min(len)].to_vec();
+        let option_padding = self.get_string_padding(tag.id_tag).or(default_padding);
+        match option_padding {
+            Some(StringPadding::Zero) => vec_u8.resize(len, 0),
+            Some(StringPadding::Space) => vec_u8.resize(len, b' '),
+            Some(StringPadding::Preserve) => {
+                let u8_address = 2 * tag.word_address as usize;
+                vec_u8.extend_from_slice(&self.vec_u8[u8_address + vec_u8.len()..u8_address + len]);
+            }
+            None => (),
+        }
+        vec_u8
+    }
+
+    /// Retourne le contenu à écrire à partir d'une [`WordAddress`] complété selon la politique
+    /// du [`Tag`] chaîne de caractères défini à cette [`WordAddress`] si l'écriture est plus
+    /// courte que ce [`Tag`]
+    pub(super) fn with_string_padding(&self, word_address: WordAddress, vec_u8: &[u8]) -> Vec<u8> {
+        if self.string_paddings.is_empty() {
+            return vec_u8.to_vec();
+        }
+        match self.get_tag_from_word_address(word_address) {
+            Some(tag) if matches!(tag.t_format, TFormat::VecU8(len) if vec_u8.len() < len) => {
+                self.padded_string(tag, vec_u8, None)
+            }
+            _ => vec_u8.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    fn db_with_string_tag() -> (Database, Tag) {
+        let mut db = Database::default();
+        let tag = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::VecU8(8),
+            ..Default::default()
+        };
+        db.add_tag(&tag);
+        db.set_value(ID_ANONYMOUS_USER, &tag, "ABCDEFGH").unwrap();
+        (db, tag)
+    }
+
+    fn string_bytes(db: &Database, tag: &Tag) -> Vec<u8> {
+        db.get_vec_u8_from_word_address(ID_ANONYMOUS_USER, tag.word_address, 8)
+    }
+
+    #[test]
+    fn test_parse_string_padding_spec() {
+        assert_eq!(
+            parse_string_padding_spec("@0010=space"),
+            Ok((0x0010, StringPadding::Space))
+        );
+        assert_eq!(
+            parse_string_padding_spec("0x0A00= PRESERVE"),
+            Ok((0x0A00, StringPadding::Preserve))
+        );
+        assert!(parse_string_padding_spec("@0010").is_err());
+        assert!(parse_string_padding_spec("@XYZ=zero").is_err());
+        assert!(parse_string_padding_spec("@0010=tab").is_err());
+    }
+
+    #[test]
+    fn test_set_string_padding() {
+        let (mut db, tag) = db_with_string_tag();
+        assert_eq!(db.get_string_padding(tag.id_tag), None);
+        db.set_string_padding(tag.id_tag, Some(StringPadding::Space))
+            .unwrap();
+        assert_eq!(
+            db.get_string_padding(tag.id_tag),
+            Some(StringPadding::Space)
+        );
+        db.set_string_padding(tag.id_tag, None).unwrap();
+        assert_eq!(db.get_string_padding(tag.id_tag), None);
+
+        let tag_u16 = Tag {
+            word_address: 0x0020,
+            id_tag: IdTag::new(1, 2, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        };
+        db.add_tag(&tag_u16);
+        assert!(db
+            .set_string_padding(tag_u16.id_tag, Some(StringPadding::Zero))
+            .is_err());
+        assert!(db
+            .set_string_padding(IdTag::new(9, 9, [0, 0, 0]), Some(StringPadding::Zero))
+            .is_err());
+    }
+
+    #[test]
+    fn test_string_padding_tlv() {
+        // Écritures par `set_value` et `set_vec_u8_to_id_tag` (AFSEC+, console, scénarios)
+        let (mut db, tag) = db_with_string_tag();
+        db.set_vec_u8_to_id_tag(ID_ANONYMOUS_USER, tag.id_tag, b"xy")
+            .unwrap();
+        assert_eq!(string_bytes(&db, &tag), b"xy\0\0\0\0\0\0");
+
+        db.set_string_padding(tag.id_tag, Some(StringPadding::Space))
+            .unwrap();
+        db.set_value(ID_ANONYMOUS_USER, &tag, "abc").unwrap();
+        assert_eq!(string_bytes(&db, &tag), b"abc     ");
+
+        db.set_value(ID_ANONYMOUS_USER, &tag, "ABCDEFGH").unwrap();
+        db.set_string_padding(tag.id_tag, Some(StringPadding::Preserve))
+            .unwrap();
+        db.set_vec_u8_to_id_tag(ID_ANONYMOUS_USER, tag.id_tag, b"xyz")
+            .unwrap();
+        assert_eq!(string_bytes(&db, &tag), b"xyzDEFGH");
+
+        // Chaîne trop longue tronquée quelle que soit la politique
+        db.set_value(ID_ANONYMOUS_USER, &tag, "0123456789").unwrap();
+        assert_eq!(string_bytes(&db, &tag), b"01234567");
+    }
+
+    #[test]
+    fn test_string_padding_modbus() {
+        // Écritures par `set_vec_u8_to_word_address` (MODBUS)
+        let (mut db, tag) = db_with_string_tag();
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0010, b"xy")
+            .unwrap();
+        assert_eq!(string_bytes(&db, &tag), b"xyCDEFGH");
+
+        db.set_string_padding(tag.id_tag, Some(StringPadding::Zero))
+            .unwrap();
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0010, b"ab")
+            .unwrap();
+        assert_eq!(string_bytes(&db, &tag), b"ab\0\0\0\0\0\0");
+
+        db.set_string_padding(tag.id_tag, Some(StringPadding::Space))
+            .unwrap();
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0010, b"abcd")
+            .unwrap();
+        assert_eq!(string_bytes(&db, &tag), b"abcd    ");
+
+        // Écriture au milieu du tag: pas de complément
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0011, b"XY")
+            .unwrap();
+        assert_eq!(string_bytes(&db, &tag), b"abXY    ");
+    }
+}
@@ -32,6 +32,19 @@ pub struct Tag {
 
     /// Valeur par défaut (au format string)
     pub default_value: String,
+
+    /// Groupe du [`Tag`] (si défini, voir le module `tag_groups`)
+    pub group: String,
+
+    /// Priorité des notifications du [`Tag`] vers l'AFSEC+ (0: la plus basse, voir le
+    /// `middleware` `MDataIn`)
+    pub priority: u8,
+
+    /// Valeur minimale déclarée (si définie, voir le module `random_values`)
+    pub option_min: Option<f64>,
+
+    /// Valeur maximale déclarée (si définie, voir le module `random_values`)
+    pub option_max: Option<f64>,
 }
 
 impl fmt::Display for Tag {
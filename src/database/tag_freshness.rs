@@ -0,0 +1,250 @@
+//! Fraîcheur des valeurs des [`Tag`] de la [`Database`]
+//!
+//! La date de la dernière écriture de chaque [`Tag`] est mémorisée par
+//! `Database::set_vec_u8_to_word_address` (seul point d'entrée des modifications de la
+//! [`Database`]), quel que soit l'utilisateur. `Database::age_of` donne l'âge de la valeur d'un
+//! [`Tag`].
+//!
+//! Un âge max. peut être défini pour les [`Tag`] mis à jour périodiquement (capteurs) : à chaque
+//! appel de `Database::check_tag_freshness` (voir le process `freshness_process`), la qualité
+//! d'un [`Tag`] dont la valeur est plus ancienne que son âge max. devient [`TagQuality::Stale`]
+//! et le nombre de [`Tag`] périmés est publié dans le tag `ID_TAG_SIM_STALE_TAGS`.
+//! Un [`Tag`] jamais écrit est périmé si l'âge max. s'est écoulé depuis sa définition.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::arg_parsing::parse_word_address;
+
+use super::{Database, IdTag, Tag, WordAddress, ID_TAG_SIM_STALE_TAGS};
+
+/// Analyse d'une définition d'âge max. `<adresse>=<secondes>` (adresse en hexa, `@0010`,
+/// `0x0010` ou `0010`)
+/// # Errors
+/// Message d'erreur si la définition est incorrecte
+pub fn parse_max_age_spec(spec: &str) -> Result<(WordAddress, Duration), String> {
+    let Some((address, secs)) = spec.split_once('=') else {
+        return Err(format!(
+            "Âge max. '{spec}' incorrect (attendu: <adresse>=<secondes>)"
+        ));
+    };
+    let address = address.trim();
+    let Some(word_address) = parse_word_address(address) else {
+        return Err(format!("Adresse '{address}' incorrecte"));
+    };
+    match secs.trim().parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs > 0.0 => {
+            Ok((word_address, Duration::from_secs_f64(secs)))
+        }
+        _ => Err(format!("Âge max. '{}' incorrect", secs.trim())),
+    }
+}
+
+/// Qualité de la valeur d'un [`Tag`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagQuality {
+    /// Valeur à jour (ou [`Tag`] sans âge max.)
+    Good,
+
+    /// Valeur plus ancienne que l'âge max. du [`Tag`]
+    Stale,
+}
+
+impl fmt::Display for TagQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TagQuality::Good => write!(f, "OK"),
+            TagQuality::Stale => write!(f, "Périmé"),
+        }
+    }
+}
+
+/// Âge max. de la valeur d'un [`Tag`]
+#[derive(Clone, Debug)]
+pub struct MaxAge {
+    /// Âge max. de la valeur
+    max_age: Duration,
+
+    /// Date de la définition de l'âge max. (référence d'un [`Tag`] jamais écrit)
+    since: Instant,
+
+    /// Qualité lors du dernier contrôle
+    quality: TagQuality,
+}
+
+impl MaxAge {
+    /// Âge max. de la valeur
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Qualité lors du dernier contrôle
+    pub fn quality(&self) -> TagQuality {
+        self.quality
+    }
+}
+
+impl Database {
+    /// Mémorise la date de l'écriture d'un [`Tag`]
+    pub(super) fn record_tag_write(&mut self, id_tag: IdTag, now: Instant) {
+        self.last_writes.insert(id_tag, now);
+    }
+
+    /// Âge de la valeur d'un [`Tag`] (None si le [`Tag`] n'a jamais été écrit)
+    pub fn age_of(&self, id_tag: IdTag) -> Option<Duration> {
+        self.last_writes
+            .get(&id_tag)
+            .map(|last_write| last_write.elapsed())
+    }
+
+    /// Définit l'âge max. de la valeur d'un [`Tag`] (None pour supprimer la supervision)
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini
+    pub fn set_max_age(
+        &mut self,
+        id_tag: IdTag,
+        option_max_age: Option<Duration>,
+    ) -> Result<(), String> {
+        if self.get_tag_from_id_tag(id_tag).is_none() {
+            return Err(format!("Tag {id_tag} inconnu"));
+        }
+        match option_max_age {
+            Some(max_age) => {
+                let max_age = MaxAge {
+                    max_age,
+                    since: Instant::now(),
+                    quality: TagQuality::Good,
+                };
+                self.max_ages.insert(id_tag, max_age);
+            }
+            None => {
+                self.max_ages.remove(&id_tag);
+            }
+        }
+        Ok(())
+    }
+
+    /// Âge max. de la valeur d'un [`Tag`] (None si le [`Tag`] n'est pas supervisé)
+    pub fn get_max_age(&self, id_tag: IdTag) -> Option<&MaxAge> {
+        self.max_ages.get(&id_tag)
+    }
+
+    /// [`Tag`] avec un âge max., par ordre croissant de [`WordAddress`]
+    pub fn get_max_age_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self
+            .max_ages
+            .keys()
+            .filter_map(|id_tag| self.get_tag_from_id_tag(*id_tag))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        tags
+    }
+
+    /// Qualité de la valeur d'un [`Tag`] lors du dernier contrôle de fraîcheur
+    pub fn get_tag_quality(&self, id_tag: IdTag) -> TagQuality {
+        self.max_ages
+            .get(&id_tag)
+            .map_or(TagQuality::Good, MaxAge::quality)
+    }
+
+    /// Contrôle la fraîcheur des [`Tag`] avec un âge max. à une date, met à jour leur qualité et
+    /// publie le nombre de [`Tag`] périmés dans `ID_TAG_SIM_STALE_TAGS`
+    /// Retourne le nombre de [`Tag`] périmés
+    pub fn check_tag_freshness(&mut self, now: Instant) -> usize {
+        let mut nb_stale_tags = 0;
+        for (id_tag, max_age) in &mut self.max_ages {
+            let last_write = self.last_writes.get(id_tag).copied();
+            let age = now.saturating_duration_since(last_write.unwrap_or(max_age.since));
+            let quality = if age > max_age.max_age {
+                TagQuality::Stale
+            } else {
+                TagQuality::Good
+            };
+            if quality != max_age.quality {
+                match quality {
+                    TagQuality::Stale => println!(
+                        "Tag {id_tag} périmé (âge {:.1}s > {:.1}s)",
+                        age.as_secs_f64(),
+                        max_age.max_age.as_secs_f64()
+                    ),
+                    TagQuality::Good => println!("Tag {id_tag} de nouveau à jour"),
+                }
+                max_age.quality = quality;
+            }
+            if quality == TagQuality::Stale {
+                nb_stale_tags += 1;
+            }
+        }
+
+        let nb_stale_tags_u16 = u16::try_from(nb_stale_tags).unwrap_or(u16::MAX);
+        let id_user = self.sim_id_user();
+        if self.get_u16_from_id_tag(id_user, ID_TAG_SIM_STALE_TAGS) != nb_stale_tags_u16 {
+            self.set_sim_tag_u16(id_user, ID_TAG_SIM_STALE_TAGS, nb_stale_tags_u16);
+        }
+        nb_stale_tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_parse_max_age_spec() {
+        assert_eq!(
+            parse_max_age_spec("@0010=5"),
+            Ok((0x0010, Duration::from_secs(5)))
+        );
+        assert_eq!(
+            parse_max_age_spec("0x001A = 0.5"),
+            Ok((0x001A, Duration::from_millis(500)))
+        );
+        assert!(parse_max_age_spec("0010").is_err());
+        assert!(parse_max_age_spec("zz=1").is_err());
+        assert!(parse_max_age_spec("0010=0").is_err());
+    }
+
+    #[test]
+    fn test_check_tag_freshness() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let id_tag = IdTag::new(4, 1, [0, 0, 0]);
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag,
+            t_format: TFormat::U16,
+            ..Default::default()
+        });
+        assert!(db.age_of(id_tag).is_none());
+        assert!(db.set_max_age(IdTag::new(9, 9, [0, 0, 0]), None).is_err());
+        db.set_max_age(id_tag, Some(Duration::from_secs(10)))
+            .unwrap();
+
+        // Tag jamais écrit : périmé 10s après la définition de l'âge max.
+        let since = db.get_max_age(id_tag).unwrap().since;
+        assert_eq!(db.check_tag_freshness(since + Duration::from_secs(5)), 0);
+        assert_eq!(db.check_tag_freshness(since + Duration::from_secs(11)), 1);
+        assert_eq!(db.get_tag_quality(id_tag), TagQuality::Stale);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_STALE_TAGS),
+            1
+        );
+
+        // Une écriture rafraîchit le tag
+        db.set_u16_to_id_tag(ID_ANONYMOUS_USER, id_tag, 1).unwrap();
+        assert!(db.age_of(id_tag).unwrap() < Duration::from_secs(1));
+        assert_eq!(db.check_tag_freshness(Instant::now()), 0);
+        assert_eq!(db.get_tag_quality(id_tag), TagQuality::Good);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_STALE_TAGS),
+            0
+        );
+
+        db.set_max_age(id_tag, None).unwrap();
+        assert!(db.get_max_age_tags().is_empty());
+    }
+}
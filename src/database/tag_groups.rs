@@ -0,0 +1,289 @@
+//! Groupes nommés de [`Tag`] et opérations sur l'ensemble des [`Tag`] d'un groupe
+//!
+//! Un groupe est défini par la colonne `group` du fichier .csv (voir [`CsvConfig`]) ou par
+//! `Database::add_tag_to_group`. Les opérations sur un groupe sont :
+//!
+//! * `Database::reset_group_to_defaults` : Remise des valeurs par défaut
+//! * `Database::freeze_group` : Gel des valeurs (les écritures sont ignorées)
+//! * `Database::export_group` : Export des valeurs au format .csv
+//! * `Database::subscribe_group` : Abonnement aux modifications (voir la console)
+//!
+//! La remise des valeurs par défaut est également possible pour tous les [`Tag`] de la
+//! [`Database`] ou ceux d'une zone (`Database::reset_to_defaults`).
+//!
+//! [`CsvConfig`]: super::CsvConfig
+
+use std::collections::BTreeSet;
+
+use super::{Database, DbError, IdTag, IdUser, Tag, WordAddress};
+
+impl Database {
+    /// Ajoute un [`Tag`] à un groupe (le groupe est créé s'il n'existe pas)
+    /// Retourne false si le [`Tag`] n'est pas défini
+    pub fn add_tag_to_group(&mut self, group: &str, id_tag: IdTag) -> bool {
+        if self.get_tag_from_id_tag(id_tag).is_none() {
+            return false;
+        }
+        let id_tags = self.tag_groups.entry(group.to_string()).or_default();
+        if !id_tags.contains(&id_tag) {
+            id_tags.push(id_tag);
+        }
+        true
+    }
+
+    /// Liste des noms des groupes (par ordre alphabétique)
+    pub fn get_group_names(&self) -> Vec<String> {
+        self.tag_groups.keys().cloned().collect()
+    }
+
+    /// Liste des [`Tag`] d'un groupe (dans l'ordre des adresses)
+    /// # Errors
+    /// Message d'erreur si le groupe n'existe pas
+    pub fn get_group_tags(&self, group: &str) -> Result<Vec<Tag>, String> {
+        let Some(id_tags) = self.tag_groups.get(group) else {
+            return Err(format!("Groupe '{group}' inconnu"));
+        };
+        let mut tags: Vec<Tag> = id_tags
+            .iter()
+            .filter_map(|id_tag| self.get_tag_from_id_tag(*id_tag))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        Ok(tags)
+    }
+
+    /// Remise à la valeur par défaut d'un [`Tag`] (0 si pas de valeur par défaut)
+    /// # Errors
+    /// [`DbError`] si la valeur par défaut ne peut pas être écrite
+    pub fn reset_tag_to_default(&mut self, id_user: IdUser, tag: &Tag) -> Result<(), DbError> {
+        if tag.default_value.is_empty() {
+            let vec_u8 = vec![0; 2 * tag.t_format.nb_words()];
+            self.set_vec_u8_to_word_address(id_user, tag.word_address, &vec_u8)
+        } else {
+            self.set_value(id_user, tag, &tag.default_value)
+        }
+    }
+
+    /// Remise à la valeur par défaut d'une liste de [`Tag`] (les erreurs sont tracées)
+    pub(super) fn reset_tags_to_defaults(&mut self, id_user: IdUser, tags: &[Tag]) {
+        for tag in tags {
+            if let Err(db_error) = self.reset_tag_to_default(id_user, tag) {
+                println!("!!! Remise à la valeur par défaut de {tag}: {db_error}");
+            }
+        }
+    }
+
+    /// Remise à la valeur par défaut des [`Tag`] d'un groupe
+    /// Retourne le nombre de [`Tag`] du groupe
+    /// # Errors
+    /// Message d'erreur si le groupe n'existe pas
+    pub fn reset_group_to_defaults(
+        &mut self,
+        id_user: IdUser,
+        group: &str,
+    ) -> Result<usize, String> {
+        let tags = self.get_group_tags(group)?;
+        self.reset_tags_to_defaults(id_user, &tags);
+        Ok(tags.len())
+    }
+
+    /// Remise à la valeur par défaut (valeur du fichier .csv ou 0) de tous les [`Tag`] ou des
+    /// [`Tag`] d'une zone (`option_zone`), hors [`Tag`] internes (propres au simulateur)
+    /// Les modifications sont notifiées comme toute écriture de `id_user`
+    /// Retourne le nombre de [`Tag`] remis à leur valeur par défaut
+    pub fn reset_to_defaults(&mut self, id_user: IdUser, option_zone: Option<u8>) -> usize {
+        let mut tags: Vec<Tag> = self
+            .hash_tag
+            .values()
+            .filter(|tag| !tag.is_internal)
+            .filter(|tag| option_zone.is_none_or(|zone| tag.id_tag.zone == zone))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        self.reset_tags_to_defaults(id_user, &tags);
+        tags.len()
+    }
+
+    /// Gel (`frozen` = true) ou dégel des valeurs des [`Tag`] d'un groupe
+    /// Les écritures dans un [`Tag`] gelé sont ignorées, quel que soit l'utilisateur
+    /// Retourne le nombre de [`Tag`] du groupe
+    /// # Errors
+    /// Message d'erreur si le groupe n'existe pas
+    pub fn freeze_group(&mut self, group: &str, frozen: bool) -> Result<usize, String> {
+        let tags = self.get_group_tags(group)?;
+        for tag in &tags {
+            for offset in 0..tag.t_format.nb_words() {
+                #[allow(clippy::cast_possible_truncation)]
+                let word_address = tag.word_address.wrapping_add(offset as WordAddress);
+                if frozen {
+                    self.frozen_word_addresses.insert(word_address);
+                } else {
+                    self.frozen_word_addresses.remove(&word_address);
+                }
+            }
+        }
+        Ok(tags.len())
+    }
+
+    /// Indique si une [`WordAddress`] est gelée
+    pub fn is_frozen_word_address(&self, word_address: WordAddress) -> bool {
+        self.frozen_word_addresses.contains(&word_address)
+    }
+
+    /// Export des valeurs des [`Tag`] d'un groupe au format .csv
+    /// (1 ligne par [`Tag`]: 'id_tag;address;label;value;unity')
+    /// # Errors
+    /// Message d'erreur si le groupe n'existe pas
+    pub fn export_group(&self, id_user: IdUser, group: &str) -> Result<String, String> {
+        let mut ret = String::from("id_tag;address;label;value;unity\n");
+        for tag in self.get_group_tags(group)? {
+            let t_value = self.get_t_value_from_tag(id_user, &tag);
+            ret += &format!(
+                "{};{:04X};{};{};{}\n",
+                tag.id_tag, tag.word_address, tag.label, t_value, tag.unity
+            );
+        }
+        Ok(ret)
+    }
+
+    /// Abonnement (`subscribed` = true) ou désabonnement aux modifications d'un groupe
+    /// # Errors
+    /// Message d'erreur si le groupe n'existe pas
+    pub fn subscribe_group(&mut self, group: &str, subscribed: bool) -> Result<(), String> {
+        if !self.tag_groups.contains_key(group) {
+            return Err(format!("Groupe '{group}' inconnu"));
+        }
+        if subscribed {
+            self.subscribed_groups.insert(group.to_string());
+        } else {
+            self.subscribed_groups.remove(group);
+        }
+        Ok(())
+    }
+
+    /// Liste des groupes abonnés qui contiennent un [`Tag`]
+    pub fn get_subscribed_groups_of_id_tag(&self, id_tag: IdTag) -> BTreeSet<String> {
+        self.subscribed_groups
+            .iter()
+            .filter(|group| {
+                self.tag_groups
+                    .get(*group)
+                    .is_some_and(|id_tags| id_tags.contains(&id_tag))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{ID_ANONYMOUS_USER, ID_TAG_SIM_RESTARTS};
+    use crate::t_data::TFormat;
+
+    fn database_setup() -> Database {
+        let mut db = Database::default();
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            default_value: "12".to_string(),
+            group: "METERING".to_string(),
+            ..Default::default()
+        });
+        db.add_tag(&Tag {
+            word_address: 0x0020,
+            id_tag: IdTag::new(1, 2, [0, 0, 0]),
+            t_format: TFormat::U32,
+            ..Default::default()
+        });
+        db.add_tag_to_group("METERING", IdTag::new(1, 2, [0, 0, 0]));
+        db
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut db = database_setup();
+        assert_eq!(db.get_group_names(), vec!["METERING".to_string()]);
+        assert_eq!(db.get_group_tags("METERING").unwrap().len(), 2);
+        assert!(db.get_group_tags("UNKNOWN").is_err());
+        assert!(!db.add_tag_to_group("METERING", IdTag::new(9, 9, [0, 0, 0])));
+    }
+
+    #[test]
+    fn test_reset_and_freeze() {
+        let mut db = database_setup();
+        db.set_u16_to_word_address(ID_ANONYMOUS_USER, 0x0010, 100)
+            .unwrap();
+        db.set_u32_to_word_address(ID_ANONYMOUS_USER, 0x0020, 200)
+            .unwrap();
+
+        assert_eq!(
+            db.reset_group_to_defaults(ID_ANONYMOUS_USER, "METERING"),
+            Ok(2)
+        );
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 12);
+        assert_eq!(db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0020), 0);
+
+        assert_eq!(db.freeze_group("METERING", true), Ok(2));
+        assert!(db.is_frozen_word_address(0x0021));
+        db.set_u32_to_word_address(ID_ANONYMOUS_USER, 0x0020, 300)
+            .unwrap();
+        assert_eq!(db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0020), 0);
+
+        assert_eq!(db.freeze_group("METERING", false), Ok(2));
+        db.set_u32_to_word_address(ID_ANONYMOUS_USER, 0x0020, 300)
+            .unwrap();
+        assert_eq!(db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0020), 300);
+    }
+
+    #[test]
+    fn test_export_and_subscribe() {
+        let mut db = database_setup();
+        let export = db.export_group(ID_ANONYMOUS_USER, "METERING").unwrap();
+        assert_eq!(export.lines().count(), 3);
+
+        let id_tag = IdTag::new(1, 2, [0, 0, 0]);
+        assert!(db.get_subscribed_groups_of_id_tag(id_tag).is_empty());
+        db.subscribe_group("METERING", true).unwrap();
+        assert!(db
+            .get_subscribed_groups_of_id_tag(id_tag)
+            .contains("METERING"));
+        assert!(db.subscribe_group("UNKNOWN", true).is_err());
+    }
+
+    #[test]
+    fn test_reset_to_defaults() {
+        let mut db = database_setup();
+        db.add_tag(&Tag {
+            word_address: 0x0030,
+            id_tag: IdTag::new(2, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            default_value: "7".to_string(),
+            ..Default::default()
+        });
+        db.add_sim_tags();
+        let id_user = db.get_id_user("TEST", true);
+        db.set_u16_to_word_address(ID_ANONYMOUS_USER, 0x0010, 100)
+            .unwrap();
+        db.set_u16_to_word_address(ID_ANONYMOUS_USER, 0x0030, 300)
+            .unwrap();
+        db.increment_sim_tag(ID_TAG_SIM_RESTARTS);
+
+        // Zone 2 seulement
+        assert_eq!(db.reset_to_defaults(ID_ANONYMOUS_USER, Some(2)), 1);
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 100);
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0030), 7);
+
+        // Toutes les zones, hors tags du simulateur
+        while db.get_change(id_user, false, true).is_some() {}
+        assert_eq!(db.reset_to_defaults(ID_ANONYMOUS_USER, None), 3);
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 12);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_RESTARTS),
+            1
+        );
+        assert!(db.get_change(id_user, false, true).is_some());
+    }
+}
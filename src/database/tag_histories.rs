@@ -0,0 +1,218 @@
+//! Historique circulaire des valeurs de [`Tag`] sélectionnés (tendances)
+//!
+//! Pour un [`Tag`] avec un historique, les `capacity` dernières valeurs sont conservées avec leur
+//! horodatage (horloge simulée, voir le module `sim_clock`) afin de vérifier l'allure d'un signal
+//! lors de la mise en service sans raccorder un historien externe.
+//!
+//! Un historique est activé par l'option `--history <adresse>=<nombre>` de la ligne de commande
+//! ou par la commande `history` de la console.
+//!
+//! Une valeur n'est enregistrée que si elle diffère de la dernière valeur de l'historique (les
+//! écritures périodiques d'une même valeur ne saturent pas l'historique). L'historique est mis à
+//! jour par `Database::set_vec_u8_to_word_address` (seul point d'entrée des modifications de la
+//! [`Database`]).
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::arg_parsing::parse_word_address;
+use crate::sim_clock;
+use crate::t_data::TValue;
+
+use super::{Database, IdTag, IdUser, Tag, WordAddress};
+
+/// Analyse d'une définition d'historique `<adresse>=<nombre>` (adresse en hexa, `@0010`,
+/// `0x0010` ou `0010`, et nombre de valeurs conservées)
+/// # Errors
+/// Message d'erreur si la définition est incorrecte
+pub fn parse_tag_history_spec(spec: &str) -> Result<(WordAddress, usize), String> {
+    let Some((address, capacity)) = spec.split_once('=') else {
+        return Err(format!(
+            "Historique '{spec}' incorrect (attendu: <adresse>=<nombre>)"
+        ));
+    };
+    let address = address.trim();
+    let Some(word_address) = parse_word_address(address) else {
+        return Err(format!("Adresse '{address}' incorrecte"));
+    };
+    match capacity.trim().parse::<usize>() {
+        Ok(capacity) if capacity > 0 => Ok((word_address, capacity)),
+        _ => Err(format!("Nombre de valeurs '{}' incorrect", capacity.trim())),
+    }
+}
+
+/// Historique des valeurs d'un [`Tag`]
+#[derive(Clone, Debug)]
+pub struct TagHistory {
+    /// Nombre max. de valeurs conservées
+    capacity: usize,
+
+    /// Valeurs conservées (horodatage depuis le 01/01/1970 UTC, valeur), de la plus ancienne
+    /// à la plus récente
+    samples: VecDeque<(Duration, TValue)>,
+}
+
+impl TagHistory {
+    /// Constructeur
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Nombre max. de valeurs conservées
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Valeurs conservées (horodatage, valeur), de la plus ancienne à la plus récente
+    pub fn samples(&self) -> &VecDeque<(Duration, TValue)> {
+        &self.samples
+    }
+
+    /// Enregistre une valeur si elle diffère de la dernière valeur de l'historique
+    fn record(&mut self, timestamp: Duration, t_value: TValue) {
+        if self
+            .samples
+            .back()
+            .is_some_and(|(_, last_value)| *last_value == t_value)
+        {
+            return;
+        }
+        while self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((timestamp, t_value));
+    }
+}
+
+impl Database {
+    /// Active (ou redimensionne) l'historique des `capacity` dernières valeurs d'un [`Tag`]
+    /// La valeur courante du [`Tag`] est la première valeur d'un nouvel historique
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini ou si `capacity` est nul
+    pub fn set_tag_history(
+        &mut self,
+        id_user: IdUser,
+        id_tag: IdTag,
+        capacity: usize,
+    ) -> Result<(), String> {
+        let Some(tag) = self.get_tag_from_id_tag(id_tag) else {
+            return Err(format!("Tag {id_tag} inconnu"));
+        };
+        if capacity == 0 {
+            return Err("Nombre de valeurs de l'historique nul".to_string());
+        }
+        let t_value = self.get_t_value_from_tag(id_user, tag);
+        let tag_history = self
+            .tag_histories
+            .entry(id_tag)
+            .or_insert_with(|| TagHistory::new(capacity));
+        tag_history.capacity = capacity;
+        while tag_history.samples.len() > capacity {
+            tag_history.samples.pop_front();
+        }
+        tag_history.record(sim_clock::now(), t_value);
+        Ok(())
+    }
+
+    /// Supprime l'historique d'un [`Tag`]
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'a pas d'historique
+    pub fn remove_tag_history(&mut self, id_tag: IdTag) -> Result<(), String> {
+        match self.tag_histories.remove(&id_tag) {
+            Some(_) => Ok(()),
+            None => Err(format!("Tag {id_tag} sans historique")),
+        }
+    }
+
+    /// Historique d'un [`Tag`]
+    pub fn get_tag_history(&self, id_tag: IdTag) -> Option<&TagHistory> {
+        self.tag_histories.get(&id_tag)
+    }
+
+    /// Liste des [`Tag`] avec un historique (dans l'ordre des adresses)
+    pub fn get_tag_history_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self
+            .tag_histories
+            .keys()
+            .filter_map(|id_tag| self.get_tag_from_id_tag(*id_tag))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        tags
+    }
+
+    /// Enregistre dans leur historique la valeur des [`Tag`] modifiés par une écriture de
+    /// `nb_words` mots à partir de `word_address`
+    pub(super) fn record_tag_histories(&mut self, word_address: WordAddress, nb_words: usize) {
+        if self.tag_histories.is_empty() {
+            return;
+        }
+        let timestamp = sim_clock::now();
+        for tag in self.get_tags_from_word_address_area(word_address, nb_words) {
+            if !self.tag_histories.contains_key(&tag.id_tag) {
+                continue;
+            }
+            let t_value = self.get_t_value_from_tag(super::ID_ANONYMOUS_USER, &tag);
+            if let Some(tag_history) = self.tag_histories.get_mut(&tag.id_tag) {
+                tag_history.record(timestamp, t_value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_parse_tag_history_spec() {
+        assert_eq!(parse_tag_history_spec("@0010=100"), Ok((0x0010, 100)));
+        assert_eq!(parse_tag_history_spec("0x001A = 5"), Ok((0x001A, 5)));
+        assert!(parse_tag_history_spec("0010").is_err());
+        assert!(parse_tag_history_spec("zz=1").is_err());
+        assert!(parse_tag_history_spec("0010=0").is_err());
+    }
+
+    #[test]
+    fn test_tag_history() {
+        let mut db = Database::default();
+        let id_tag = IdTag::new(1, 1, [0, 0, 0]);
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag,
+            t_format: TFormat::U16,
+            ..Default::default()
+        });
+        assert!(db.set_tag_history(ID_ANONYMOUS_USER, id_tag, 0).is_err());
+        db.set_tag_history(ID_ANONYMOUS_USER, id_tag, 3).unwrap();
+        assert_eq!(db.get_tag_history_tags().len(), 1);
+
+        // Valeurs enregistrées sur modification seulement
+        for value in [1, 1, 2, 3, 3, 4] {
+            db.set_u16_to_word_address(ID_ANONYMOUS_USER, 0x0010, value)
+                .unwrap();
+        }
+        let tag_history = db.get_tag_history(id_tag).unwrap();
+        assert_eq!(tag_history.capacity(), 3);
+        let values: Vec<TValue> = tag_history
+            .samples()
+            .iter()
+            .map(|(_, t_value)| t_value.clone())
+            .collect();
+        assert_eq!(values, [TValue::U16(2), TValue::U16(3), TValue::U16(4)]);
+
+        // Réduction de la taille de l'historique
+        db.set_tag_history(ID_ANONYMOUS_USER, id_tag, 1).unwrap();
+        assert_eq!(db.get_tag_history(id_tag).unwrap().samples().len(), 1);
+
+        db.remove_tag_history(id_tag).unwrap();
+        assert!(db.get_tag_history(id_tag).is_none());
+        assert!(db.remove_tag_history(id_tag).is_err());
+    }
+}
@@ -0,0 +1,383 @@
+//! Migration des états et des valeurs sauvegardés lors d'une réorganisation du plan des tags
+//!
+//! La correspondance entre l'ancien et le nouveau fichier .csv est établie par [`IdTag`] (un
+//! [`Tag`] qui change d'adresse est déplacé) puis, pour les [`Tag`] restants, par libellé unique
+//! dans chacun des fichiers (un [`Tag`] qui change d'[`IdTag`] est renommé). Les autres [`Tag`]
+//! de l'ancien fichier sont supprimés et ceux du nouveau fichier sont ajoutés.
+//!
+//! Cette correspondance transforme un fichier d'état (commande `save` de la console, voir le
+//! module `saved_state`) ou un fichier de valeurs (format de `group <nom> export`) de l'ancien
+//! plan vers le nouveau (sous-commande `migrate-tags`) : l'état d'un banc de test survit ainsi à
+//! une réorganisation du plan des tags. Les [`Tag`] propres au simulateur (`SIM_ZONE`) ne sont
+//! pas concernés.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Database, IdTag, SavedState, Tag, WordAddress, SIM_ZONE};
+
+/// Modification d'un [`Tag`] entre l'ancien et le nouveau plan
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagChange {
+    /// Même [`IdTag`] à une autre adresse
+    Moved,
+
+    /// Même libellé avec un autre [`IdTag`]
+    Renamed,
+
+    /// [`Tag`] absent du nouveau plan
+    Removed,
+
+    /// [`Tag`] absent de l'ancien plan
+    Added,
+}
+
+impl fmt::Display for TagChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TagChange::Moved => write!(f, "moved"),
+            TagChange::Renamed => write!(f, "renamed"),
+            TagChange::Removed => write!(f, "removed"),
+            TagChange::Added => write!(f, "added"),
+        }
+    }
+}
+
+/// Correspondance d'un [`Tag`] modifié ([`IdTag`] et adresse dans l'ancien et le nouveau plan)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagMapping {
+    /// Modification du [`Tag`]
+    pub change: TagChange,
+
+    /// [`IdTag`] et adresse dans l'ancien plan (None pour un [`Tag`] ajouté)
+    pub old: Option<(IdTag, WordAddress)>,
+
+    /// [`IdTag`] et adresse dans le nouveau plan (None pour un [`Tag`] supprimé)
+    pub new: Option<(IdTag, WordAddress)>,
+
+    /// Libellé du [`Tag`]
+    pub label: String,
+}
+
+/// Correspondance entre l'ancien et le nouveau plan des tags
+#[derive(Clone, Debug, Default)]
+pub struct TagMigration {
+    /// [`Tag`] modifiés (déplacés, renommés, supprimés ou ajoutés)
+    mappings: Vec<TagMapping>,
+
+    /// Nouveau [`Tag`] de chaque [`IdTag`] conservé de l'ancien plan
+    targets: HashMap<IdTag, Tag>,
+
+    /// Nombre de [`Tag`] inchangés
+    nb_unchanged: usize,
+
+    /// Empreinte du nouveau plan des tags (voir `Database::tag_map_hash`)
+    tag_map_hash: u64,
+}
+
+impl fmt::Display for TagMigration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let count = |change| self.mappings.iter().filter(|m| m.change == change).count();
+        write!(
+            f,
+            "{} tags inchangés, {} déplacés, {} renommés, {} supprimés, {} ajoutés",
+            self.nb_unchanged,
+            count(TagChange::Moved),
+            count(TagChange::Renamed),
+            count(TagChange::Removed),
+            count(TagChange::Added)
+        )
+    }
+}
+
+/// [`Tag`] migrables d'une [`Database`], par ordre croissant d'[`IdTag`]
+fn migrated_tags(db: &Database) -> BTreeMap<IdTag, &Tag> {
+    db.hash_tag
+        .values()
+        .filter(|tag| tag.id_tag.zone != SIM_ZONE)
+        .map(|tag| (tag.id_tag, tag))
+        .collect()
+}
+
+/// [`Tag`] de libellé unique (non vide) parmi des [`Tag`]
+fn unique_labels<'a>(tags: &[&'a Tag]) -> HashMap<&'a str, &'a Tag> {
+    let mut labels: HashMap<&str, Vec<&Tag>> = HashMap::new();
+    for &tag in tags {
+        if !tag.label.trim().is_empty() {
+            labels.entry(tag.label.trim()).or_default().push(tag);
+        }
+    }
+    labels
+        .into_iter()
+        .filter_map(|(label, tags)| match tags[..] {
+            [tag] => Some((label, tag)),
+            _ => None,
+        })
+        .collect()
+}
+
+impl TagMigration {
+    /// Correspondance entre l'ancien et le nouveau plan des tags
+    pub fn new(old_db: &Database, new_db: &Database) -> Self {
+        let old_tags = migrated_tags(old_db);
+        let new_tags = migrated_tags(new_db);
+        let mut migration = TagMigration {
+            tag_map_hash: new_db.tag_map_hash(),
+            ..Default::default()
+        };
+
+        // Correspondance par [`IdTag`]
+        let mut old_remaining = vec![];
+        for (id_tag, old_tag) in &old_tags {
+            match new_tags.get(id_tag) {
+                Some(new_tag) => migration.add(TagChange::Moved, old_tag, new_tag),
+                None => old_remaining.push(*old_tag),
+            }
+        }
+        let new_remaining: Vec<&Tag> = new_tags
+            .values()
+            .filter(|tag| !old_tags.contains_key(&tag.id_tag))
+            .copied()
+            .collect();
+
+        // Correspondance par libellé unique des [`Tag`] restants
+        let new_labels = unique_labels(&new_remaining);
+        let old_labels = unique_labels(&old_remaining);
+        let mut renamed = vec![];
+        for old_tag in &old_remaining {
+            let label = old_tag.label.trim();
+            match (old_labels.get(label), new_labels.get(label)) {
+                (Some(_), Some(new_tag)) => {
+                    migration.add(TagChange::Renamed, old_tag, new_tag);
+                    renamed.push(new_tag.id_tag);
+                }
+                _ => migration.mappings.push(TagMapping {
+                    change: TagChange::Removed,
+                    old: Some((old_tag.id_tag, old_tag.word_address)),
+                    new: None,
+                    label: old_tag.label.clone(),
+                }),
+            }
+        }
+        for new_tag in new_remaining {
+            if !renamed.contains(&new_tag.id_tag) {
+                migration.mappings.push(TagMapping {
+                    change: TagChange::Added,
+                    old: None,
+                    new: Some((new_tag.id_tag, new_tag.word_address)),
+                    label: new_tag.label.clone(),
+                });
+            }
+        }
+        migration
+    }
+
+    /// Ajoute la correspondance d'un [`Tag`] conservé (inchangé s'il a le même [`IdTag`] à la
+    /// même adresse)
+    fn add(&mut self, change: TagChange, old_tag: &Tag, new_tag: &Tag) {
+        self.targets.insert(old_tag.id_tag, new_tag.clone());
+        if change == TagChange::Moved && old_tag.word_address == new_tag.word_address {
+            self.nb_unchanged += 1;
+            return;
+        }
+        self.mappings.push(TagMapping {
+            change,
+            old: Some((old_tag.id_tag, old_tag.word_address)),
+            new: Some((new_tag.id_tag, new_tag.word_address)),
+            label: new_tag.label.clone(),
+        });
+    }
+
+    /// [`Tag`] modifiés (déplacés, renommés, supprimés ou ajoutés)
+    pub fn mappings(&self) -> &[TagMapping] {
+        &self.mappings
+    }
+
+    /// Nouveau [`Tag`] d'un [`IdTag`] de l'ancien plan (None si le [`Tag`] est supprimé)
+    pub fn target(&self, old_id_tag: IdTag) -> Option<&Tag> {
+        self.targets.get(&old_id_tag)
+    }
+
+    /// Correspondance au format .csv (`change;old_id_tag;old_address;new_id_tag;new_address;label`)
+    pub fn to_csv(&self) -> String {
+        let fields = |option: Option<(IdTag, WordAddress)>| match option {
+            Some((id_tag, word_address)) => format!("{id_tag};{word_address:04X}"),
+            None => ";".to_string(),
+        };
+        let mut ret = String::from("change;old_id_tag;old_address;new_id_tag;new_address;label\n");
+        for mapping in &self.mappings {
+            ret += &format!(
+                "{};{};{};{}\n",
+                mapping.change,
+                fields(mapping.old),
+                fields(mapping.new),
+                mapping.label
+            );
+        }
+        ret
+    }
+
+    /// Transformation d'un état sauvegardé vers le nouveau plan : les valeurs des [`Tag`]
+    /// supprimés sont ignorées
+    /// Retourne le nouvel état et le nombre de valeurs ignorées
+    pub fn migrate_saved_state(&self, saved_state: &SavedState) -> (SavedState, usize) {
+        let values: Vec<_> = saved_state
+            .values
+            .iter()
+            .filter_map(|(id_tag, t_value)| {
+                self.target(*id_tag)
+                    .map(|tag| (tag.id_tag, t_value.clone()))
+            })
+            .collect();
+        let nb_dropped = saved_state.values.len() - values.len();
+        (
+            SavedState {
+                tag_map_hash: self.tag_map_hash,
+                values,
+            },
+            nb_dropped,
+        )
+    }
+
+    /// Transformation d'un fichier de valeurs (format de `group <nom> export`) vers le nouveau
+    /// plan : l'[`IdTag`] et l'adresse de chaque ligne sont remplacés et les lignes des [`Tag`]
+    /// supprimés sont ignorées
+    /// Retourne le nouveau contenu et le nombre de lignes ignorées
+    /// # Errors
+    /// Message d'erreur si une ligne est incorrecte
+    pub fn migrate_values(&self, content: &str) -> Result<(String, usize), String> {
+        let mut ret = String::new();
+        let mut nb_dropped = 0;
+        for (index, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("id_tag;") || trimmed.starts_with('#') {
+                // Ligne vide, entête ou commentaire
+                ret += line;
+                ret.push('\n');
+                continue;
+            }
+            let fields: Vec<&str> = line.split(';').collect();
+            if fields.len() < 4 {
+                return Err(format!(
+                    "Ligne {}: '{trimmed}' incorrecte (attendu: id_tag;address;label;value;unity)",
+                    index + 1
+                ));
+            }
+            let id_tag =
+                IdTag::from_str(fields[0]).map_err(|e| format!("Ligne {}: {e}", index + 1))?;
+            let Some(tag) = self.target(id_tag) else {
+                nb_dropped += 1;
+                continue;
+            };
+            let mut fields: Vec<String> = fields.iter().map(ToString::to_string).collect();
+            fields[0] = tag.id_tag.to_string();
+            fields[1] = format!("{:04X}", tag.word_address);
+            ret += &fields.join(";");
+            ret.push('\n');
+        }
+        Ok((ret, nb_dropped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+    use crate::t_data::{TFormat, TValue};
+
+    /// Database avec des tags (`IdTag` zone 4, numéro de tag, adresse, libellé)
+    fn database_setup(tags: &[(u16, WordAddress, &str)]) -> Database {
+        let mut db = Database::default();
+        for (num_tag, word_address, label) in tags {
+            db.add_tag(&Tag {
+                word_address: *word_address,
+                id_tag: IdTag::new(4, *num_tag, [0, 0, 0]),
+                t_format: TFormat::U16,
+                label: (*label).to_string(),
+                ..Default::default()
+            });
+        }
+        db
+    }
+
+    fn id(num_tag: u16) -> IdTag {
+        IdTag::new(4, num_tag, [0, 0, 0])
+    }
+
+    #[test]
+    fn test_tag_migration() {
+        let old_db = database_setup(&[
+            (1, 0x0010, "Tension"),
+            (2, 0x0011, "Courant"),
+            (3, 0x0012, "Mode"),
+            (4, 0x0013, "Obsolète"),
+        ]);
+        let new_db = database_setup(&[
+            (1, 0x0010, "Tension"),
+            (2, 0x0020, "Courant"),
+            (9, 0x0021, "Mode"),
+            (5, 0x0022, "Nouveau"),
+        ]);
+        let migration = TagMigration::new(&old_db, &new_db);
+        assert_eq!(
+            migration.to_string(),
+            "1 tags inchangés, 1 déplacés, 1 renommés, 1 supprimés, 1 ajoutés"
+        );
+        assert_eq!(migration.target(id(2)).unwrap().word_address, 0x0020);
+        assert_eq!(migration.target(id(3)).unwrap().id_tag, id(9));
+        assert!(migration.target(id(4)).is_none());
+        let csv = migration.to_csv();
+        assert!(csv.contains("moved;4/0002:00:00:00;0011;4/0002:00:00:00;0020;Courant\n"));
+        assert!(csv.contains("removed;4/0004:00:00:00;0013;;;Obsolète\n"));
+        assert!(csv.contains("added;;;4/0005:00:00:00;0022;Nouveau\n"));
+
+        // Migration d'un état sauvegardé
+        let saved_state = SavedState {
+            tag_map_hash: old_db.tag_map_hash(),
+            values: vec![
+                (id(1), TValue::U16(1)),
+                (id(3), TValue::U16(3)),
+                (id(4), TValue::U16(4)),
+            ],
+        };
+        let (new_state, nb_dropped) = migration.migrate_saved_state(&saved_state);
+        assert_eq!(nb_dropped, 1);
+        assert_eq!(
+            new_state.values,
+            vec![(id(1), TValue::U16(1)), (id(9), TValue::U16(3))]
+        );
+        let mut db = database_setup(&[(9, 0x0021, "Mode")]);
+        db.restore_state(ID_ANONYMOUS_USER, &new_state);
+        assert_eq!(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id(9)), 3);
+
+        // Migration d'un fichier de valeurs
+        let content = "id_tag;address;label;value;unity\n\
+                       4/0002:00:00:00;0011;Courant;12.5;A\n\
+                       4/0004:00:00:00;0013;Obsolète;1;\n";
+        assert_eq!(
+            migration.migrate_values(content),
+            Ok((
+                "id_tag;address;label;value;unity\n\
+                 4/0002:00:00:00;0020;Courant;12.5;A\n"
+                    .to_string(),
+                1
+            ))
+        );
+        assert!(migration.migrate_values("4/0002;0011").is_err());
+        assert!(migration.migrate_values("x;0011;Courant;1;").is_err());
+    }
+
+    #[test]
+    fn test_ambiguous_labels() {
+        // Libellés en double: pas de renommage
+        let old_db = database_setup(&[(1, 0x0010, "Réserve"), (2, 0x0011, "Réserve")]);
+        let new_db = database_setup(&[(3, 0x0010, "Réserve"), (4, 0x0011, "Réserve")]);
+        let migration = TagMigration::new(&old_db, &new_db);
+        assert_eq!(
+            migration.to_string(),
+            "0 tags inchangés, 0 déplacés, 0 renommés, 2 supprimés, 2 ajoutés"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! Propriétaire des [`Tag`] : source autorisée à les écrire (AFSEC+, MODBUS ou simulateur)
+//!
+//! Un [`Tag`] de mesure ne doit être écrit que par la liaison série (AFSEC+) : un script de test
+//! qui l'écrit par MODBUS fausse la simulation sans que rien ne le signale. Un propriétaire peut
+//! être attribué à un [`Tag`] (option `--owner`) ; la source d'une écriture est déduite du groupe
+//! de l'[`IdUser`] (`GROUP_AFSEC`, `GROUP_MODBUS`, le simulateur pour les autres utilisateurs).
+//!
+//! Une écriture d'un [`Tag`] par une autre source que son propriétaire est comptée et tracée
+//! (`OwnerPolicy::Flag`, par défaut) ou, de plus, ignorée pour ce [`Tag`] (`OwnerPolicy::Reject`)
+//! par `Database::set_vec_u8_to_word_address`. Le nombre de ces écritures est publié dans le
+//! [`Tag`] propre au simulateur `ID_TAG_SIM_OWNER_VIOLATIONS`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::arg_parsing::parse_word_address;
+
+use super::{
+    Database, IdTag, IdUser, WordAddress, GROUP_AFSEC, GROUP_MODBUS, ID_TAG_SIM_OWNER_VIOLATIONS,
+    SIM_ZONE,
+};
+
+/// Source autorisée à écrire un [`Tag`]
+///
+/// [`Tag`]: super::Tag
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagOwner {
+    /// Communication avec l'AFSEC+ (liaison série)
+    Afsec,
+
+    /// Clients MODBUS/TCP
+    Modbus,
+
+    /// Simulateur (console, scénarios, scripts, ...)
+    Sim,
+}
+
+impl fmt::Display for TagOwner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TagOwner::Afsec => write!(f, "afsec"),
+            TagOwner::Modbus => write!(f, "modbus"),
+            TagOwner::Sim => write!(f, "sim"),
+        }
+    }
+}
+
+impl FromStr for TagOwner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "afsec" => Ok(TagOwner::Afsec),
+            "modbus" => Ok(TagOwner::Modbus),
+            "sim" => Ok(TagOwner::Sim),
+            _ => Err(format!("Propriétaire '{s}' inconnu (afsec, modbus ou sim)")),
+        }
+    }
+}
+
+/// Politique des écritures d'un [`Tag`] par une autre source que son propriétaire
+///
+/// [`Tag`]: super::Tag
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OwnerPolicy {
+    /// Écritures appliquées, comptées et tracées
+    #[default]
+    Flag,
+
+    /// Écritures ignorées, comptées et tracées
+    Reject,
+}
+
+impl fmt::Display for OwnerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OwnerPolicy::Flag => write!(f, "flag"),
+            OwnerPolicy::Reject => write!(f, "reject"),
+        }
+    }
+}
+
+impl FromStr for OwnerPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "flag" => Ok(OwnerPolicy::Flag),
+            "reject" => Ok(OwnerPolicy::Reject),
+            _ => Err(format!(
+                "Politique des propriétaires '{s}' inconnue (flag ou reject)"
+            )),
+        }
+    }
+}
+
+/// Analyse d'une définition de propriétaire `<adresse>=<propriétaire>` (adresse en hexa, `@0010`,
+/// `0x0010` ou `0010`)
+/// # Errors
+/// Message d'erreur si la définition est incorrecte
+pub fn parse_owner_spec(spec: &str) -> Result<(WordAddress, TagOwner), String> {
+    let Some((address, owner)) = spec.split_once('=') else {
+        return Err(format!(
+            "Propriétaire '{spec}' incorrect (attendu: <adresse>=<afsec|modbus|sim>)"
+        ));
+    };
+    let address = address.trim();
+    let Some(word_address) = parse_word_address(address) else {
+        return Err(format!("Adresse '{address}' incorrecte"));
+    };
+    Ok((word_address, TagOwner::from_str(owner)?))
+}
+
+impl Database {
+    /// Définit le propriétaire d'un [`Tag`] (None pour un [`Tag`] écrit par toute source)
+    /// # Errors
+    /// Message d'erreur si le [`Tag`] n'est pas défini ou est propre au simulateur
+    ///
+    /// [`Tag`]: super::Tag
+    pub fn set_tag_owner(
+        &mut self,
+        id_tag: IdTag,
+        option_owner: Option<TagOwner>,
+    ) -> Result<(), String> {
+        if self.get_tag_from_id_tag(id_tag).is_none() {
+            return Err(format!("Tag {id_tag} inconnu"));
+        }
+        // Les [`Tag`] propres au simulateur (dont le compteur des écritures hors propriétaire)
+        // sont toujours écrits par le simulateur
+        if id_tag.zone == SIM_ZONE {
+            return Err(format!("Tag {id_tag} propre au simulateur"));
+        }
+        match option_owner {
+            Some(owner) => {
+                self.tag_owners.insert(id_tag, owner);
+            }
+            None => {
+                self.tag_owners.remove(&id_tag);
+            }
+        }
+        Ok(())
+    }
+
+    /// Propriétaire d'un [`Tag`] (None si le [`Tag`] peut être écrit par toute source)
+    ///
+    /// [`Tag`]: super::Tag
+    pub fn get_tag_owner(&self, id_tag: IdTag) -> Option<TagOwner> {
+        self.tag_owners.get(&id_tag).copied()
+    }
+
+    /// Spécifie la politique des écritures par une autre source que le propriétaire
+    pub fn set_owner_policy(&mut self, policy: OwnerPolicy) {
+        self.owner_policy = policy;
+    }
+
+    /// Politique des écritures par une autre source que le propriétaire
+    pub fn get_owner_policy(&self) -> OwnerPolicy {
+        self.owner_policy
+    }
+
+    /// Nombre d'écritures de [`Tag`] par une autre source que leur propriétaire
+    ///
+    /// [`Tag`]: super::Tag
+    pub fn get_nb_owner_violations(&self) -> usize {
+        self.nb_owner_violations
+    }
+
+    /// Source des écritures d'un [`IdUser`] (selon son groupe)
+    pub fn write_source(&self, id_user: IdUser) -> TagOwner {
+        match self.get_id_user_group(id_user) {
+            GROUP_AFSEC => TagOwner::Afsec,
+            GROUP_MODBUS => TagOwner::Modbus,
+            _ => TagOwner::Sim,
+        }
+    }
+
+    /// Retourne le contenu à écrire à partir d'une [`WordAddress`] après le contrôle des
+    /// propriétaires : chaque [`Tag`] écrit par une autre source que son propriétaire est compté
+    /// et tracé et, avec la politique `OwnerPolicy::Reject`, conserve son contenu actuel
+    ///
+    /// [`Tag`]: super::Tag
+    pub(super) fn without_owner_violations(
+        &mut self,
+        id_user: IdUser,
+        word_address: WordAddress,
+        vec_u8: &[u8],
+    ) -> Vec<u8> {
+        let mut vec_u8 = vec_u8.to_vec();
+        if self.tag_owners.is_empty() {
+            return vec_u8;
+        }
+        let source = self.write_source(id_user);
+        let nb_words = vec_u8.len().div_ceil(2);
+        let write_start = 2 * word_address as usize;
+        let write_end = write_start + vec_u8.len();
+        let mut nb_violations = 0;
+        for tag in self.get_tags_from_word_address_area(word_address, nb_words) {
+            let Some(owner) = self.tag_owners.get(&tag.id_tag).copied() else {
+                continue;
+            };
+            let tag_start = 2 * tag.word_address as usize;
+            let tag_end = tag_start + 2 * tag.t_format.nb_words();
+            let (start, end) = (tag_start.max(write_start), tag_end.min(write_end));
+            if owner == source || start >= end {
+                continue;
+            }
+            nb_violations += 1;
+            self.nb_owner_violations += 1;
+            println!(
+                "!!! Écriture {} du tag {} @{:04X} par {source} ({}, propriétaire {owner})",
+                if self.owner_policy == OwnerPolicy::Reject {
+                    "refusée"
+                } else {
+                    "signalée"
+                },
+                tag.id_tag,
+                tag.word_address,
+                self.get_id_user_name(id_user),
+            );
+            if self.owner_policy == OwnerPolicy::Reject {
+                vec_u8[start - write_start..end - write_start]
+                    .copy_from_slice(&self.vec_u8[start..end]);
+            }
+        }
+        for _ in 0..nb_violations {
+            self.increment_sim_tag(ID_TAG_SIM_OWNER_VIOLATIONS);
+        }
+        vec_u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{Tag, ID_ANONYMOUS_USER};
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_parse_owner_spec() {
+        assert_eq!(
+            parse_owner_spec("@0010=afsec"),
+            Ok((0x0010, TagOwner::Afsec))
+        );
+        assert_eq!(
+            parse_owner_spec("0x0A00= MODBUS"),
+            Ok((0x0A00, TagOwner::Modbus))
+        );
+        assert!(parse_owner_spec("@0010").is_err());
+        assert!(parse_owner_spec("@XYZ=sim").is_err());
+        assert!(parse_owner_spec("@0010=serial").is_err());
+        assert_eq!(OwnerPolicy::from_str("reject"), Ok(OwnerPolicy::Reject));
+        assert!(OwnerPolicy::from_str("deny").is_err());
+    }
+
+    #[test]
+    fn test_owner_violations() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let tag = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U32,
+            ..Default::default()
+        };
+        db.add_tag(&tag);
+        let id_afsec = db.get_id_user_in_group("AFSEC", false, GROUP_AFSEC);
+        let id_modbus = db.get_id_user_in_group("MODBUS", false, GROUP_MODBUS);
+        assert_eq!(db.write_source(id_afsec), TagOwner::Afsec);
+        assert_eq!(db.write_source(id_modbus), TagOwner::Modbus);
+        assert_eq!(db.write_source(ID_ANONYMOUS_USER), TagOwner::Sim);
+
+        db.set_tag_owner(tag.id_tag, Some(TagOwner::Afsec)).unwrap();
+        assert_eq!(db.get_tag_owner(tag.id_tag), Some(TagOwner::Afsec));
+        assert!(db
+            .set_tag_owner(ID_TAG_SIM_OWNER_VIOLATIONS, Some(TagOwner::Sim))
+            .is_err());
+
+        // Écriture par le propriétaire
+        db.set_u32_to_word_address(id_afsec, 0x0010, 1234).unwrap();
+        assert_eq!(db.get_nb_owner_violations(), 0);
+
+        // Politique par défaut: écriture signalée et appliquée
+        db.set_u32_to_word_address(id_modbus, 0x0010, 5678).unwrap();
+        assert_eq!(
+            db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0010),
+            5678
+        );
+        assert_eq!(db.get_nb_owner_violations(), 1);
+
+        // Politique `Reject`: le tag conserve sa valeur, le mot voisin est écrit
+        db.set_owner_policy(OwnerPolicy::Reject);
+        db.set_vec_u8_to_word_address(id_modbus, 0x0011, &[0x00, 0x01, 0xAB, 0xCD])
+            .unwrap();
+        assert_eq!(
+            db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0010),
+            5678
+        );
+        assert_eq!(
+            db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0012),
+            0xABCD
+        );
+        assert_eq!(db.get_nb_owner_violations(), 2);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_OWNER_VIOLATIONS),
+            2
+        );
+
+        // Sans propriétaire, toute source écrit le tag
+        db.set_tag_owner(tag.id_tag, None).unwrap();
+        db.set_u32_to_word_address(id_modbus, 0x0010, 1).unwrap();
+        assert_eq!(db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0010), 1);
+        assert_eq!(db.get_nb_owner_violations(), 2);
+    }
+}
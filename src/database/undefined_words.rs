@@ -0,0 +1,109 @@
+//! Écritures à des [`WordAddress`] sans [`Tag`] défini
+//!
+//! Une écriture MODBUS/TCP à une [`WordAddress`] qui n'est couverte par aucun [`Tag`] modifie
+//! la table MODBUS sans aucune notification. Selon la politique du serveur MODBUS/TCP (voir
+//! `UndefinedWritePolicy` du module `server_modbus_tcp`), une telle écriture est refusée ou un
+//! [`Tag`] anonyme `U16` est créé à cette adresse dans la zone `ANONYMOUS_ZONE` pour que
+//! l'écriture soit notifiée comme toute autre modification.
+//!
+//! Les [`WordAddress`] sans [`Tag`] déjà écrites sont mémorisées pour ne tracer que la première
+//! écriture à chacune de ces adresses.
+
+use crate::t_data::TFormat;
+
+use super::{Database, IdTag, Tag, WordAddress};
+
+/// Zone des [`Tag`] anonymes créés lors d'une écriture à une [`WordAddress`] sans [`Tag`]
+/// (le numéro du [`Tag`] est sa [`WordAddress`])
+pub const ANONYMOUS_ZONE: u8 = 0xFE;
+
+impl Database {
+    /// Indique si une [`WordAddress`] est couverte par un [`Tag`] (y compris par un [`Tag`] sur
+    /// plusieurs mots défini à une [`WordAddress`] précédente)
+    pub fn is_word_address_defined(&self, word_address: WordAddress) -> bool {
+        self.id_tag_at(word_address).is_some()
+    }
+
+    /// Mémorise une écriture à une [`WordAddress`] sans [`Tag`]
+    /// Retourne true s'il s'agit de la première écriture à cette [`WordAddress`]
+    pub fn note_undefined_write(&mut self, word_address: WordAddress) -> bool {
+        self.undefined_write_addresses.insert(word_address)
+    }
+
+    /// [`WordAddress`] sans [`Tag`] déjà écrites (par ordre croissant)
+    pub fn get_undefined_write_addresses(&self) -> Vec<WordAddress> {
+        self.undefined_write_addresses.iter().copied().collect()
+    }
+
+    /// Ajoute un [`Tag`] anonyme `U16` à une [`WordAddress`] sans [`Tag`]
+    /// Retourne l'[`IdTag`] du [`Tag`] ajouté (None si la [`WordAddress`] est déjà couverte)
+    pub fn add_anonymous_tag(&mut self, word_address: WordAddress) -> Option<IdTag> {
+        if self.is_word_address_defined(word_address) {
+            return None;
+        }
+        let id_tag = IdTag::new(ANONYMOUS_ZONE, word_address, [0, 0, 0]);
+        if self.get_tag_from_id_tag(id_tag).is_some() {
+            return None;
+        }
+        self.add_tag(&Tag {
+            word_address,
+            id_tag,
+            t_format: TFormat::U16,
+            label: format!("Anonyme @{word_address:04X}"),
+            is_write: true,
+            ..Default::default()
+        });
+        Some(id_tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_word_address_defined() {
+        let mut db = Database::default();
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U32,
+            ..Default::default()
+        });
+        assert!(!db.is_word_address_defined(0x000F));
+        assert!(db.is_word_address_defined(0x0010));
+        assert!(db.is_word_address_defined(0x0011));
+        assert!(!db.is_word_address_defined(0x0012));
+    }
+
+    #[test]
+    fn test_id_tag_at() {
+        let mut db = Database::default();
+        let id_tag = IdTag::new(1, 1, [0, 0, 0]);
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag,
+            t_format: TFormat::VecU8(6),
+            ..Default::default()
+        });
+        assert_eq!(db.id_tag_at(0x0000), None);
+        assert_eq!(db.id_tag_at(0x000F), None);
+        assert_eq!(db.id_tag_at(0x0010), Some((id_tag, 0)));
+        assert_eq!(db.id_tag_at(0x0012), Some((id_tag, 2)));
+        assert_eq!(db.id_tag_at(0x0013), None);
+        assert_eq!(db.id_tag_at(0x7FFF), None);
+    }
+
+    #[test]
+    fn test_anonymous_tag() {
+        let mut db = Database::default();
+        assert!(db.note_undefined_write(0x0020));
+        assert!(!db.note_undefined_write(0x0020));
+        assert_eq!(db.get_undefined_write_addresses(), vec![0x0020]);
+
+        let id_tag = db.add_anonymous_tag(0x0020).unwrap();
+        assert_eq!(id_tag, IdTag::new(ANONYMOUS_ZONE, 0x0020, [0, 0, 0]));
+        assert!(db.is_word_address_defined(0x0020));
+        assert_eq!(db.add_anonymous_tag(0x0020), None);
+    }
+}
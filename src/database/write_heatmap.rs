@@ -0,0 +1,246 @@
+//! Carte de l'activité en écriture ('heatmap') de la [`Database`]
+//!
+//! Le nombre d'écritures de chaque [`WordAddress`] est compté par
+//! `Database::set_vec_u8_to_word_address` (seul point d'entrée des modifications de la
+//! [`Database`]), quel que soit l'utilisateur. À la fin d'une campagne de tests, la carte montre
+//! les zones de la table MODBUS réellement sollicitées et les [`Tag`] jamais écrits du fichier
+//! .csv.
+//!
+//! La carte est exportée en CSV (une ligne par bloc de `HEATMAP_WIDTH` mots, séparateur `;`) ou
+//! en image PNG en niveaux de gris (un pixel par mot, noir pour un mot jamais écrit puis du gris
+//! au blanc selon le logarithme du nombre d'écritures).
+
+use super::{Database, Tag, WordAddress, SIM_ZONE};
+
+/// Nombre de [`WordAddress`] de la table MODBUS
+const NB_WORD_ADDRESSES: usize = 0x8000;
+
+/// Nombre de mots par ligne de la carte (largeur de l'image)
+const HEATMAP_WIDTH: usize = 0x100;
+
+/// Nombre de lignes de la carte (hauteur de l'image)
+const HEATMAP_HEIGHT: usize = NB_WORD_ADDRESSES / HEATMAP_WIDTH;
+
+/// Niveau de gris d'un mot écrit une seule fois
+const HEATMAP_MIN_GRAY: f64 = 64.0;
+
+/// Compteurs des écritures de chaque [`WordAddress`]
+pub(super) fn new_write_counts() -> Vec<u32> {
+    vec![0; NB_WORD_ADDRESSES]
+}
+
+/// Calcul d'un CRC-32 (polynôme 0xEDB88320 réfléchi) d'un 'chunk' PNG
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(0xFFFF_FFFF_u32, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0xEDB8_8320
+            }
+        })
+    })
+}
+
+/// Calcul d'un Adler-32 (contrôle du flux 'zlib')
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1_u32, 0_u32), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+/// Ajoute un 'chunk' PNG (longueur, type, données et CRC)
+fn push_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend(u32::try_from(data.len()).unwrap().to_be_bytes());
+    let start = png.len();
+    png.extend(chunk_type);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// Image PNG en niveaux de gris (8 bits) avec des données 'zlib' non compressées
+fn encode_png_gray(width: usize, pixels: &[u8]) -> Vec<u8> {
+    let height = pixels.len() / width;
+    let mut ihdr = vec![];
+    ihdr.extend(u32::try_from(width).unwrap().to_be_bytes());
+    ihdr.extend(u32::try_from(height).unwrap().to_be_bytes());
+    ihdr.extend([8, 0, 0, 0, 0]); // 8 bits, niveaux de gris, pas d'entrelacement
+
+    // Lignes de l'image précédées du filtre 0 (aucun)
+    let mut raw = vec![];
+    for row in pixels.chunks(width) {
+        raw.push(0);
+        raw.extend(row);
+    }
+
+    // Flux 'zlib' en blocs 'deflate' non compressés (65535 octets max. par bloc)
+    let mut zlib = vec![0x78, 0x01];
+    let nb_blocks = raw.len().div_ceil(0xFFFF);
+    for (index, block) in raw.chunks(0xFFFF).enumerate() {
+        zlib.push(u8::from(index + 1 == nb_blocks));
+        let len = u16::try_from(block.len()).unwrap();
+        zlib.extend(len.to_le_bytes());
+        zlib.extend((!len).to_le_bytes());
+        zlib.extend(block);
+    }
+    zlib.extend(adler32(&raw).to_be_bytes());
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    push_png_chunk(&mut png, b"IHDR", &ihdr);
+    push_png_chunk(&mut png, b"IDAT", &zlib);
+    push_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+impl Database {
+    /// Compte une écriture de `nb_words` mots à partir d'une [`WordAddress`]
+    pub(super) fn count_writes(&mut self, word_address: WordAddress, nb_words: usize) {
+        let start = usize::from(word_address);
+        let end = (start + nb_words).min(NB_WORD_ADDRESSES);
+        for count in &mut self.write_counts[start..end] {
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// Nombre d'écritures d'une [`WordAddress`]
+    pub fn get_write_count(&self, word_address: WordAddress) -> u32 {
+        self.write_counts
+            .get(usize::from(word_address))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Remise à 0 des compteurs des écritures (début d'une campagne de tests)
+    pub fn clear_write_counts(&mut self) {
+        self.write_counts.fill(0);
+    }
+
+    /// Nombre de [`WordAddress`] écrites au moins une fois
+    pub fn get_nb_written_words(&self) -> usize {
+        self.write_counts.iter().filter(|count| **count > 0).count()
+    }
+
+    /// [`Tag`] jamais écrits (hors [`Tag`] propres au simulateur), par ordre croissant de
+    /// [`WordAddress`]
+    pub fn get_unwritten_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self
+            .hash_tag
+            .values()
+            .filter(|tag| tag.id_tag.zone != SIM_ZONE)
+            .filter(|tag| {
+                let start = usize::from(tag.word_address);
+                let end = (start + tag.t_format.nb_words()).min(NB_WORD_ADDRESSES);
+                self.write_counts[start..end]
+                    .iter()
+                    .all(|count| *count == 0)
+            })
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        tags
+    }
+
+    /// Carte des écritures au format CSV (première colonne : [`WordAddress`] du bloc en hexa)
+    pub fn write_heatmap_csv(&self) -> String {
+        let mut csv = String::from("address");
+        for offset in 0..HEATMAP_WIDTH {
+            csv.push_str(&format!(";+{offset:02X}"));
+        }
+        csv.push('\n');
+        for (row, counts) in self.write_counts.chunks(HEATMAP_WIDTH).enumerate() {
+            csv.push_str(&format!("{:04X}", row * HEATMAP_WIDTH));
+            for count in counts {
+                csv.push_str(&format!(";{count}"));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Carte des écritures au format PNG (`HEATMAP_WIDTH` x 128 pixels)
+    pub fn write_heatmap_png(&self) -> Vec<u8> {
+        let max_ln = f64::from(self.write_counts.iter().copied().max().unwrap_or_default()).ln();
+        let pixels: Vec<u8> = self
+            .write_counts
+            .iter()
+            .map(|count| {
+                if *count == 0 {
+                    return 0;
+                }
+                let ratio = if max_ln > 0.0 {
+                    f64::from(*count).ln() / max_ln
+                } else {
+                    1.0
+                };
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let gray = (HEATMAP_MIN_GRAY + ratio * (255.0 - HEATMAP_MIN_GRAY)).round() as u8;
+                gray
+            })
+            .collect();
+        debug_assert_eq!(pixels.len(), HEATMAP_WIDTH * HEATMAP_HEIGHT);
+        encode_png_gray(HEATMAP_WIDTH, &pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{IdTag, ID_ANONYMOUS_USER};
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_write_counts() {
+        let mut db = Database::default();
+        for (word_address, num_tag) in [(0x0010, 1), (0x0012, 2)] {
+            db.add_tag(&Tag {
+                word_address,
+                id_tag: IdTag::new(4, num_tag, [0, 0, 0]),
+                t_format: TFormat::U32,
+                ..Default::default()
+            });
+        }
+        db.set_u32_to_id_tag(ID_ANONYMOUS_USER, IdTag::new(4, 1, [0, 0, 0]), 1)
+            .unwrap();
+        db.set_u32_to_id_tag(ID_ANONYMOUS_USER, IdTag::new(4, 1, [0, 0, 0]), 2)
+            .unwrap();
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x7FFF, &[1, 2])
+            .unwrap();
+        assert_eq!(db.get_write_count(0x0010), 2);
+        assert_eq!(db.get_write_count(0x0011), 2);
+        assert_eq!(db.get_write_count(0x0012), 0);
+        assert_eq!(db.get_write_count(0x7FFF), 1);
+        assert_eq!(db.get_nb_written_words(), 3);
+        let unwritten_tags = db.get_unwritten_tags();
+        assert_eq!(unwritten_tags.len(), 1);
+        assert_eq!(unwritten_tags[0].word_address, 0x0012);
+
+        let csv = db.write_heatmap_csv();
+        assert_eq!(csv.lines().count(), 1 + HEATMAP_HEIGHT);
+        assert!(csv.lines().nth(1).unwrap().starts_with("0000;0;"));
+        assert!(csv.lines().nth(1).unwrap().contains(";0;2;2;0;"));
+
+        db.clear_write_counts();
+        assert_eq!(db.get_nb_written_words(), 0);
+    }
+
+    #[test]
+    fn test_write_heatmap_png() {
+        let mut db = Database::default();
+        db.set_vec_u8_to_word_address(ID_ANONYMOUS_USER, 0x0000, &[0, 1])
+            .unwrap();
+        let png = db.write_heatmap_png();
+        assert_eq!(&png[0..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 256);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 128);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+        // Valeurs de référence
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
@@ -0,0 +1,260 @@
+//! Clonage des [`Tag`] d'une zone (équipements répétés)
+//!
+//! Pour simuler une seconde ligne de comptage identique à une première, les [`Tag`] d'une zone
+//! sont dupliqués (option `--clone-zone`) plutôt que de recopier des centaines de lignes du
+//! fichier .csv. Chaque [`Tag`] cloné :
+//!
+//! * est défini dans la zone cible, à l'adresse du [`Tag`] source décalée d'un nombre de mots
+//! * a le même numéro de tag et un premier indice (`indice_0`) incrémenté
+//! * reprend le format, l'unité, le libellé, les droits, le groupe, ... et le contenu actuel des
+//!   mots du [`Tag`] source (écrit par l'utilisateur du simulateur, même si les écritures anonymes
+//!   sont refusées)
+//!
+//! Le clonage est refusé (sans aucun [`Tag`] ajouté) si un [`Tag`] cloné dépasse l'adresse
+//! 0x7FFF, recouvre un [`Tag`] existant ou reprend un [`IdTag`] déjà défini.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Database, IdTag, Tag, WordAddress, ID_ANONYMOUS_USER};
+
+/// Nombre de mots de la [`Database`] (`WordAddress` de 0x0000 à 0x7FFF)
+const NB_WORDS: usize = 0x8000;
+
+/// Définition du clonage des [`Tag`] d'une zone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ZoneClone {
+    /// Zone des [`Tag`] à cloner
+    pub from_zone: u8,
+
+    /// Zone des [`Tag`] clonés
+    pub to_zone: u8,
+
+    /// Décalage (en mots) des adresses des [`Tag`] clonés
+    pub address_offset: i32,
+
+    /// Incrément du premier indice des [`Tag`] clonés
+    pub indice_increment: u8,
+}
+
+impl fmt::Display for ZoneClone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.address_offset < 0 { "-" } else { "+" };
+        write!(
+            f,
+            "zone {} -> zone {} (adresses {sign}0x{:04X}, indice +{})",
+            self.from_zone,
+            self.to_zone,
+            self.address_offset.unsigned_abs(),
+            self.indice_increment
+        )
+    }
+}
+
+/// Analyse d'un décalage d'adresses signé (décimal ou hexa `0x0100`)
+fn parse_offset(offset: &str) -> Option<i32> {
+    let offset = offset.trim();
+    let (negative, offset) = match offset.strip_prefix('-') {
+        Some(offset) => (true, offset),
+        None => (false, offset.strip_prefix('+').unwrap_or(offset)),
+    };
+    let value = match offset
+        .strip_prefix("0x")
+        .or_else(|| offset.strip_prefix("0X"))
+    {
+        Some(hexa) => i32::from_str_radix(hexa, 16).ok()?,
+        None => offset.parse::<i32>().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+impl FromStr for ZoneClone {
+    type Err = String;
+
+    /// Accepte `<zone source>:<zone cible>:<décalage>[:<incrément d'indice>]` (zones en décimal,
+    /// décalage signé en décimal ou hexa, incrément de 0 par défaut), ex: `1:2:0x0100:1`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err =
+            || format!("Clonage '{s}' incorrect (attendu: <zone>:<zone>:<décalage>[:<incrément>])");
+        let fields: Vec<&str> = s.split(':').map(str::trim).collect();
+        if !(3..=4).contains(&fields.len()) {
+            return Err(err());
+        }
+        let from_zone = fields[0].parse::<u8>().map_err(|_| err())?;
+        let to_zone = fields[1].parse::<u8>().map_err(|_| err())?;
+        let address_offset = parse_offset(fields[2]).ok_or_else(err)?;
+        let indice_increment = match fields.get(3) {
+            Some(increment) => increment.parse::<u8>().map_err(|_| err())?,
+            None => 0,
+        };
+        Ok(ZoneClone {
+            from_zone,
+            to_zone,
+            address_offset,
+            indice_increment,
+        })
+    }
+}
+
+impl Database {
+    /// Clone les [`Tag`] d'une zone (voir [`ZoneClone`])
+    /// Retourne le nombre de [`Tag`] clonés
+    /// # Errors
+    /// Message d'erreur si la zone source n'a pas de [`Tag`] ou si un [`Tag`] cloné dépasse
+    /// l'adresse 0x7FFF, recouvre un [`Tag`] existant ou reprend un [`IdTag`] déjà défini
+    pub fn clone_zone(&mut self, zone_clone: &ZoneClone) -> Result<usize, String> {
+        let mut tags: Vec<Tag> = self
+            .hash_tag
+            .values()
+            .filter(|tag| tag.id_tag.zone == zone_clone.from_zone && !tag.is_internal)
+            .cloned()
+            .collect();
+        if tags.is_empty() {
+            return Err(format!("Zone {} sans tag à cloner", zone_clone.from_zone));
+        }
+        tags.sort_by_key(|tag| tag.word_address);
+
+        // Contrôle de tous les tags clonés avant le moindre ajout
+        let mut clones = Vec::with_capacity(tags.len());
+        for tag in &tags {
+            let nb_words = tag.t_format.nb_words();
+            let word_address = i64::from(tag.word_address) + i64::from(zone_clone.address_offset);
+            let Some(word_address) = WordAddress::try_from(word_address)
+                .ok()
+                .filter(|word_address| usize::from(*word_address) + nb_words <= NB_WORDS)
+            else {
+                return Err(format!("{tag}: clone hors de la database"));
+            };
+            let Some(indice_0) = tag.id_tag.indice_0.checked_add(zone_clone.indice_increment)
+            else {
+                return Err(format!("{tag}: indice du clone supérieur à 0xFF"));
+            };
+            let id_tag = IdTag {
+                zone: zone_clone.to_zone,
+                indice_0,
+                ..tag.id_tag
+            };
+            if let Some(other_tag) = self.get_tag_from_id_tag(id_tag) {
+                return Err(format!("{tag}: IdTag du clone déjà défini par {other_tag}"));
+            }
+            let option_overlap = (word_address..)
+                .take(nb_words)
+                .find_map(|address| self.id_tag_at(address));
+            if let Some((other_id_tag, _)) = option_overlap {
+                return Err(format!("{tag}: clone à l'adresse du tag {other_id_tag}"));
+            }
+            clones.push((
+                Tag {
+                    word_address,
+                    id_tag,
+                    ..tag.clone()
+                },
+                self.get_vec_u8_from_word_address(
+                    ID_ANONYMOUS_USER,
+                    tag.word_address,
+                    2 * nb_words,
+                ),
+            ));
+        }
+
+        // Ajout des tags clonés puis écriture du contenu actuel des tags source, une fois tous les
+        // contrôles passés (adresses dans la database et utilisateur non anonyme)
+        let id_user = self.sim_id_user();
+        let nb_clones = clones.len();
+        for (clone, _) in &clones {
+            self.add_tag(clone);
+        }
+        for (clone, vec_u8) in clones {
+            self.set_vec_u8_to_word_address(id_user, clone.word_address, &vec_u8)
+                .map_err(|db_error| format!("{clone}: {db_error}"))?;
+        }
+        Ok(nb_clones)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::AnonymousWritePolicy;
+    use crate::t_data::TFormat;
+
+    fn tag(word_address: WordAddress, zone: u8, num_tag: u16, t_format: TFormat) -> Tag {
+        Tag {
+            word_address,
+            id_tag: IdTag::new(zone, num_tag, [0, 0, 0]),
+            t_format,
+            label: format!("Tag {num_tag}"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_zone_clone_from_str() {
+        assert_eq!(
+            ZoneClone::from_str("1:2:0x0100:1"),
+            Ok(ZoneClone {
+                from_zone: 1,
+                to_zone: 2,
+                address_offset: 0x0100,
+                indice_increment: 1,
+            })
+        );
+        assert_eq!(
+            ZoneClone::from_str("1:1:-16").map(|zone_clone| zone_clone.address_offset),
+            Ok(-16)
+        );
+        assert!(ZoneClone::from_str("1:2").is_err());
+        assert!(ZoneClone::from_str("1:2:0x10:256").is_err());
+    }
+
+    #[test]
+    fn test_clone_zone() {
+        let mut db = Database::default();
+        db.add_tag(&tag(0x0010, 1, 0x0001, TFormat::U16));
+        db.add_tag(&tag(0x0011, 1, 0x0002, TFormat::U32));
+        db.add_tag(&tag(0x0020, 2, 0x0001, TFormat::U16));
+        db.set_u32_to_word_address(ID_ANONYMOUS_USER, 0x0011, 1234)
+            .unwrap();
+
+        let zone_clone = ZoneClone::from_str("1:1:0x0100:1").unwrap();
+        assert_eq!(db.clone_zone(&zone_clone), Ok(2));
+        let clone = db.get_tag_from_word_address(0x0111).unwrap().clone();
+        assert_eq!(clone.id_tag, IdTag::new(1, 0x0002, [1, 0, 0]));
+        assert_eq!(clone.label, "Tag 2");
+        assert_eq!(
+            db.get_u32_from_word_address(ID_ANONYMOUS_USER, 0x0111),
+            1234
+        );
+        assert!(db.check_invariants().is_empty());
+
+        // Clones en conflit: aucun tag ajouté
+        let zone_clone = ZoneClone::from_str("2:2:-0x0010:1").unwrap();
+        assert!(db.clone_zone(&zone_clone).is_err());
+        assert!(db.get_tag_from_word_address(0x0010).is_some());
+        let zone_clone = ZoneClone::from_str("1:3:0x7FF0").unwrap();
+        assert!(db.clone_zone(&zone_clone).is_err());
+        assert!(db
+            .get_tag_from_id_tag(IdTag::new(3, 0x0001, [0, 0, 0]))
+            .is_none());
+        let zone_clone = ZoneClone::from_str("5:6:0x0100").unwrap();
+        assert!(db.clone_zone(&zone_clone).is_err());
+    }
+
+    #[test]
+    fn test_clone_zone_deny_anonymous_writes() {
+        let mut db = Database::default();
+        db.add_tag(&tag(0x0010, 1, 0x0001, TFormat::U16));
+        db.set_u16_to_word_address(ID_ANONYMOUS_USER, 0x0010, 4321)
+            .unwrap();
+        db.set_anonymous_write_policy(AnonymousWritePolicy::Deny);
+
+        let zone_clone = ZoneClone::from_str("1:2:0x0100").unwrap();
+        assert_eq!(db.clone_zone(&zone_clone), Ok(1));
+        assert_eq!(
+            db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0110),
+            4321
+        );
+        assert_eq!(db.get_nb_refused_anonymous_writes(), 0);
+    }
+}
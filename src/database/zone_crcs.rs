@@ -0,0 +1,121 @@
+//! CRC des zones de la [`Database`] pour la vérification d'intégrité
+//!
+//! Le CRC d'une zone (CRC-16/MODBUS) est calculé sur l'ensemble des [`Tag`] de la zone, par
+//! ordre croissant de [`WordAddress`] (adresse en 'big endian' suivie du contenu des mots du
+//! [`Tag`]). Il change dès qu'une valeur de la zone est modifiée.
+//!
+//! Les CRC des zones 0 à `SIM_NB_ZONE_CRCS - 1` sont publiés périodiquement dans des [`Tag`]
+//! propres au simulateur (voir `id_tag_sim_zone_crc` et le module `zone_crc`) pour qu'un outil
+//! externe détecte à moindre coût une modification inattendue lors d'un test d'endurance.
+
+use std::collections::BTreeSet;
+
+use super::{id_tag_sim_zone_crc, Database, Tag, ID_ANONYMOUS_USER, SIM_NB_ZONE_CRCS};
+
+/// Calcul d'un CRC-16/MODBUS (polynôme 0xA001 réfléchi, valeur initiale 0xFFFF)
+pub(crate) fn crc16_modbus(crc: u16, bytes: &[u8]) -> u16 {
+    let mut crc = crc;
+    for byte in bytes {
+        crc ^= u16::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 0x0001 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0xA001
+            };
+        }
+    }
+    crc
+}
+
+impl Database {
+    /// Ensemble des zones des [`Tag`] de la [`Database`]
+    pub fn get_zones(&self) -> BTreeSet<u8> {
+        self.hash_tag.keys().map(|id_tag| id_tag.zone).collect()
+    }
+
+    /// CRC (CRC-16/MODBUS) du contenu des [`Tag`] d'une zone
+    pub fn crc_of_zone(&self, zone: u8) -> u16 {
+        let mut tags: Vec<&Tag> = self
+            .hash_tag
+            .values()
+            .filter(|tag| tag.id_tag.zone == zone)
+            .collect();
+        tags.sort_by_key(|tag| tag.word_address);
+        tags.iter().fold(0xFFFF, |crc, tag| {
+            let u8_address = 2 * tag.word_address as usize;
+            let u8_end = (u8_address + 2 * tag.t_format.nb_words()).min(self.vec_u8.len());
+            let crc = crc16_modbus(crc, &tag.word_address.to_be_bytes());
+            crc16_modbus(crc, &self.vec_u8[u8_address..u8_end])
+        })
+    }
+
+    /// Publication des CRC des zones dans les [`Tag`] propres au simulateur
+    /// Seuls les CRC modifiés sont écrits (pas de notification inutile)
+    /// Retourne le nombre de CRC modifiés
+    pub fn publish_zone_crcs(&mut self) -> usize {
+        let mut nb_changes = 0;
+        for zone in 0..SIM_NB_ZONE_CRCS {
+            let id_tag = id_tag_sim_zone_crc(zone);
+            if self.get_tag_from_id_tag(id_tag).is_none() {
+                continue;
+            }
+            let crc = self.crc_of_zone(zone);
+            if self.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag) != crc {
+                let id_user = self.sim_id_user();
+                self.set_sim_tag_u16(id_user, id_tag, crc);
+                nb_changes += 1;
+            }
+        }
+        nb_changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::IdTag;
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_crc16_modbus() {
+        // Valeur de contrôle du CRC-16/MODBUS
+        assert_eq!(crc16_modbus(0xFFFF, b"123456789"), 0x4B37);
+    }
+
+    #[test]
+    fn test_crc_of_zone() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let id_tag = IdTag::new(3, 0x0010, [0, 0, 0]);
+        db.add_tag(&Tag {
+            word_address: 0x0100,
+            id_tag,
+            t_format: TFormat::U32,
+            ..Default::default()
+        });
+
+        assert!(db.get_zones().contains(&3));
+        let crc = db.crc_of_zone(3);
+        assert_eq!(db.crc_of_zone(4), 0xFFFF);
+
+        // Une modification de la zone modifie le CRC
+        db.set_u32_to_id_tag(ID_ANONYMOUS_USER, id_tag, 0x1234_5678)
+            .unwrap();
+        let new_crc = db.crc_of_zone(3);
+        assert_ne!(new_crc, crc);
+
+        // Publication des CRC modifiés seulement
+        assert!(db.publish_zone_crcs() > 0);
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag_sim_zone_crc(3)),
+            new_crc
+        );
+        assert_eq!(db.publish_zone_crcs(), 0);
+
+        // Retour à la valeur initiale: CRC initial
+        db.set_u32_to_id_tag(ID_ANONYMOUS_USER, id_tag, 0).unwrap();
+        assert_eq!(db.crc_of_zone(3), crc);
+    }
+}
@@ -0,0 +1,350 @@
+//! Budget d'erreurs des tests de longue durée (option `--error-budget`)
+//!
+//! Un budget fixe le nombre max. d'erreurs d'un compteur du simulateur sur une minute glissante
+//! et l'action déclenchée lorsqu'il est dépassé, pour qu'un test de nuit sans surveillance échoue
+//! rapidement et de manière visible :
+//!
+//! * `<compteur>:<max>:tag` : Le bit du budget (rang de l'option `--error-budget`) est mis à 1
+//!   dans le tag `ID_TAG_SIM_ERROR_BUDGET` (remis à 0 uniquement par une écriture de ce tag)
+//! * `<compteur>:<max>:webhook=<url>` : Notification JSON vers l'URL (voir le module `webhook`),
+//!   envoyée par une tâche séparée pour ne pas retarder la surveillance
+//! * `<compteur>:<max>:exit` : Arrêt du simulateur avec le code de sortie
+//!   `ERROR_BUDGET_EXIT_CODE`
+//!
+//! Les compteurs surveillés sont les tags du simulateur `ID_TAG_SIM_JUNK_FRAMES` (`junk`, trames
+//! inexploitables reçues de l'AFSEC+) et `ID_TAG_SIM_MODBUS_EXCEPTIONS` (`modbus-exceptions`,
+//! réponses d'exception MODBUS/TCP). Un budget dépassé se réarme lorsque le nombre d'erreurs de
+//! la dernière minute repasse sous le max.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::database::{
+    IdTag, ID_ANONYMOUS_USER, ID_TAG_SIM_ERROR_BUDGET, ID_TAG_SIM_JUNK_FRAMES,
+    ID_TAG_SIM_MODBUS_EXCEPTIONS,
+};
+use crate::profiling::{lock_database, Subsystem};
+use crate::test_summary;
+use crate::timeline;
+use crate::webhook::{self, escape_json, WebhookUrl};
+use crate::Database;
+
+/// Code de sortie du simulateur arrêté par l'action `exit` d'un budget
+pub const ERROR_BUDGET_EXIT_CODE: i32 = 3;
+
+/// Nombre max. de budgets (1 bit par budget dans `ID_TAG_SIM_ERROR_BUDGET`)
+pub const ERROR_BUDGETS_MAX: usize = 16;
+
+/// Fenêtre glissante des erreurs comptées
+const ERROR_BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+/// Temps de cycle (en millisecondes) de la surveillance des compteurs
+const ERROR_BUDGET_CYCLE_MSECS: u64 = 1000;
+
+/// Compteur d'erreurs surveillé
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetCounter {
+    /// Trames inexploitables reçues de l'AFSEC+
+    Junk,
+
+    /// Réponses d'exception MODBUS/TCP
+    ModbusExceptions,
+}
+
+impl BudgetCounter {
+    /// Tag du simulateur du compteur
+    fn id_tag(self) -> IdTag {
+        match self {
+            BudgetCounter::Junk => ID_TAG_SIM_JUNK_FRAMES,
+            BudgetCounter::ModbusExceptions => ID_TAG_SIM_MODBUS_EXCEPTIONS,
+        }
+    }
+}
+
+impl fmt::Display for BudgetCounter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BudgetCounter::Junk => write!(f, "junk"),
+            BudgetCounter::ModbusExceptions => write!(f, "modbus-exceptions"),
+        }
+    }
+}
+
+/// Action déclenchée par le dépassement d'un budget
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BudgetAction {
+    /// Bit du budget dans le tag `ID_TAG_SIM_ERROR_BUDGET`
+    Tag,
+
+    /// Notification JSON
+    Webhook(WebhookUrl),
+
+    /// Arrêt du simulateur
+    Exit,
+}
+
+impl fmt::Display for BudgetAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BudgetAction::Tag => write!(f, "tag"),
+            BudgetAction::Webhook(url) => write!(f, "webhook={url}"),
+            BudgetAction::Exit => write!(f, "exit"),
+        }
+    }
+}
+
+/// Budget d'erreurs d'un compteur
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorBudget {
+    /// Compteur surveillé
+    pub counter: BudgetCounter,
+
+    /// Nombre max. d'erreurs par minute
+    pub max_per_minute: u32,
+
+    /// Action en cas de dépassement
+    pub action: BudgetAction,
+}
+
+impl fmt::Display for ErrorBudget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} > {}/min -> {}",
+            self.counter, self.max_per_minute, self.action
+        )
+    }
+}
+
+impl FromStr for ErrorBudget {
+    type Err = String;
+
+    /// Accepte '<compteur>:<max>:<action>' avec `junk` ou `modbus-exceptions` comme compteur et
+    /// `tag`, `webhook=<url>` ou `exit` comme action
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err =
+            || format!("Budget d'erreurs '{s}' incorrect (attendu: <compteur>:<max>:<action>)");
+        let mut parts = s.trim().splitn(3, ':');
+        let (Some(counter), Some(max), Some(action)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(err());
+        };
+        let counter = match counter.trim().to_lowercase().as_str() {
+            "junk" => BudgetCounter::Junk,
+            "modbus-exceptions" => BudgetCounter::ModbusExceptions,
+            _ => {
+                return Err(format!(
+                    "Compteur '{counter}' inconnu dans le budget d'erreurs '{s}' (junk ou modbus-exceptions)"
+                ))
+            }
+        };
+        let max_per_minute = max.trim().parse::<u32>().map_err(|_| err())?;
+        let action = match action.trim() {
+            "tag" => BudgetAction::Tag,
+            "exit" => BudgetAction::Exit,
+            action => match action.strip_prefix("webhook=") {
+                Some(url) => BudgetAction::Webhook(WebhookUrl::from_str(url)?),
+                None => {
+                    return Err(format!(
+                        "Action '{action}' inconnue dans le budget d'erreurs '{s}' (tag, webhook=<url> ou exit)"
+                    ))
+                }
+            },
+        };
+        Ok(ErrorBudget {
+            counter,
+            max_per_minute,
+            action,
+        })
+    }
+}
+
+/// Suivi du nombre d'erreurs d'un budget sur la fenêtre glissante
+#[derive(Debug)]
+struct BudgetTracker {
+    /// Valeur précédente du compteur (None avant la première lecture)
+    option_last_counter: Option<u16>,
+
+    /// Erreurs comptées dans la fenêtre (date, nombre)
+    events: VecDeque<(Instant, u32)>,
+
+    /// Budget dépassé (pas de nouveau déclenchement avant de repasser sous le max.)
+    exceeded: bool,
+}
+
+impl BudgetTracker {
+    /// Constructeur
+    fn new() -> Self {
+        Self {
+            option_last_counter: None,
+            events: VecDeque::new(),
+            exceeded: false,
+        }
+    }
+
+    /// Prise en compte de la valeur courante du compteur (compteur `u16` qui reboucle)
+    /// Retourne le nombre d'erreurs de la dernière minute si le budget vient d'être dépassé
+    fn update(&mut self, counter: u16, max_per_minute: u32, now: Instant) -> Option<u32> {
+        if let Some(last_counter) = self.option_last_counter {
+            let nb_errors = u32::from(counter.wrapping_sub(last_counter));
+            if nb_errors > 0 {
+                self.events.push_back((now, nb_errors));
+            }
+        }
+        self.option_last_counter = Some(counter);
+
+        while let Some((date, _)) = self.events.front() {
+            if now.duration_since(*date) < ERROR_BUDGET_WINDOW {
+                break;
+            }
+            self.events.pop_front();
+        }
+        let nb_errors: u32 = self.events.iter().map(|(_, nb_errors)| nb_errors).sum();
+        if nb_errors <= max_per_minute {
+            self.exceeded = false;
+            return None;
+        }
+        if self.exceeded {
+            return None;
+        }
+        self.exceeded = true;
+        Some(nb_errors)
+    }
+}
+
+/// Document JSON de la notification du dépassement d'un budget
+fn webhook_body(error_budget: &ErrorBudget, nb_errors: u32, timestamp: &str) -> String {
+    format!(
+        "{{\"event\":\"error_budget\",\"counter\":\"{}\",\"errors_per_minute\":{nb_errors},\"max_per_minute\":{},\"timestamp\":\"{}\"}}",
+        error_budget.counter,
+        error_budget.max_per_minute,
+        escape_json(timestamp)
+    )
+}
+
+/// Routine d'un thread qui surveille les budgets d'erreurs
+pub async fn error_budget_process(thread_db: Arc<Mutex<Database>>, budgets: Vec<ErrorBudget>) {
+    if budgets.is_empty() {
+        return;
+    }
+    for error_budget in &budgets {
+        println!("ERROR BUDGET: {error_budget}");
+    }
+    let mut trackers: Vec<BudgetTracker> = budgets.iter().map(|_| BudgetTracker::new()).collect();
+
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(ERROR_BUDGET_CYCLE_MSECS)).await;
+
+        // Budgets dépassés (indice, nombre d'erreurs de la dernière minute)
+        let mut exceeded = vec![];
+        {
+            let mut db = lock_database(&thread_db, Subsystem::ErrorBudget);
+            let now = Instant::now();
+            for (index, (error_budget, tracker)) in budgets.iter().zip(&mut trackers).enumerate() {
+                let counter =
+                    db.get_u16_from_id_tag(ID_ANONYMOUS_USER, error_budget.counter.id_tag());
+                if let Some(nb_errors) = tracker.update(counter, error_budget.max_per_minute, now) {
+                    if error_budget.action == BudgetAction::Tag {
+                        db.set_sim_tag_bits(ID_TAG_SIM_ERROR_BUDGET, 1 << index, true);
+                    }
+                    exceeded.push((index, nb_errors));
+                }
+            }
+        }
+
+        for (index, nb_errors) in exceeded {
+            let error_budget = &budgets[index];
+            let timestamp = timeline::timestamp();
+            eprintln!(
+                "!!! ERROR BUDGET [{timestamp}]: {nb_errors} {} dans la dernière minute (max {}) !!!",
+                error_budget.counter, error_budget.max_per_minute
+            );
+            match &error_budget.action {
+                BudgetAction::Tag => (),
+                BudgetAction::Webhook(url) => {
+                    // Notification sans bloquer la surveillance des autres budgets
+                    let url = url.clone();
+                    let body = webhook_body(error_budget, nb_errors, &timestamp);
+                    tokio::spawn(async move { webhook::notify(&url, &body).await });
+                }
+                BudgetAction::Exit => {
+                    eprintln!(
+                        "!!! ERROR BUDGET: Arrêt du simulateur (code {ERROR_BUDGET_EXIT_CODE})"
+                    );
+                    test_summary::write_summary(&lock_database(&thread_db, Subsystem::ErrorBudget));
+                    std::process::exit(ERROR_BUDGET_EXIT_CODE);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_budget_from_str() {
+        assert_eq!(
+            ErrorBudget::from_str("junk:10:tag"),
+            Ok(ErrorBudget {
+                counter: BudgetCounter::Junk,
+                max_per_minute: 10,
+                action: BudgetAction::Tag
+            })
+        );
+        let error_budget =
+            ErrorBudget::from_str("modbus-exceptions:5:webhook=http://jenkins:8080/hook").unwrap();
+        assert_eq!(error_budget.counter, BudgetCounter::ModbusExceptions);
+        assert_eq!(
+            error_budget.to_string(),
+            "modbus-exceptions > 5/min -> webhook=http://jenkins:8080/hook"
+        );
+        assert_eq!(
+            ErrorBudget::from_str("junk:0:exit").unwrap().action,
+            BudgetAction::Exit
+        );
+        assert!(ErrorBudget::from_str("junk:10").is_err());
+        assert!(ErrorBudget::from_str("crc:10:tag").is_err());
+        assert!(ErrorBudget::from_str("junk:-1:tag").is_err());
+        assert!(ErrorBudget::from_str("junk:10:mail").is_err());
+        assert!(ErrorBudget::from_str("junk:10:webhook=ftp://x").is_err());
+    }
+
+    #[test]
+    fn test_budget_tracker() {
+        let mut tracker = BudgetTracker::new();
+        let t0 = Instant::now();
+        let at = |secs| t0 + Duration::from_secs(secs);
+
+        // Première lecture : référence du compteur (pas d'erreur comptée)
+        assert_eq!(tracker.update(500, 10, at(0)), None);
+        assert_eq!(tracker.update(508, 10, at(10)), None);
+
+        // Dépassement : un seul déclenchement tant que le budget reste dépassé
+        assert_eq!(tracker.update(511, 10, at(20)), Some(11));
+        assert_eq!(tracker.update(512, 10, at(30)), None);
+
+        // Les 8 premières erreurs sortent de la fenêtre : réarmement puis nouveau dépassement
+        assert_eq!(tracker.update(512, 10, at(75)), None);
+        assert_eq!(tracker.update(520, 10, at(78)), Some(12));
+
+        // Compteur qui reboucle
+        let mut tracker = BudgetTracker::new();
+        assert_eq!(tracker.update(u16::MAX - 1, 2, at(0)), None);
+        assert_eq!(tracker.update(1, 2, at(1)), Some(3));
+    }
+
+    #[test]
+    fn test_webhook_body() {
+        let error_budget = ErrorBudget::from_str("junk:10:webhook=http://jenkins/hook").unwrap();
+        assert_eq!(
+            webhook_body(&error_budget, 12, "01:02:03.004"),
+            "{\"event\":\"error_budget\",\"counter\":\"junk\",\"errors_per_minute\":12,\"max_per_minute\":10,\"timestamp\":\"01:02:03.004\"}"
+        );
+    }
+}
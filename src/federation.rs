@@ -0,0 +1,539 @@
+//! Partage de plages de registres entre simulateurs (option `--federation <groupe:port>`)
+//!
+//! Comme les ICOM d'un site réel qui échangent les données inter-stations via le réseau ICOM,
+//! plusieurs instances du simulateur (une par armoire simulée) partagent des plages de registres
+//! par un protocole léger en UDP multicast :
+//!
+//! * Chaque instance publie ses plages (option répétable `--federation-range <début>-<fin>`,
+//!   adresses en hexa) sur le groupe multicast : les blocs modifiés toutes les
+//!   `--federation-period` millisecondes et tous les blocs tous les `FEDERATION_REFRESH_CYCLES`
+//!   cycles (pour une instance qui rejoint le site en cours de test)
+//! * Chaque instance écrit dans sa database les blocs publiés par les autres instances (utilisateur
+//!   `Federation`), seulement s'ils diffèrent de son contenu actuel
+//!
+//! Une plage ne doit être publiée que par une seule instance du site : un bloc reçu qui recouvre
+//! une plage publiée par l'instance locale est ignoré. Avec l'option répétable
+//! `--federation-accept <début>-<fin>`, seuls les blocs contenus dans une de ces plages sont
+//! acceptés. Un bloc au delà de la fin de la database est toujours refusé.
+//!
+//! Format d'un datagramme (entiers en 'big endian') : `FEDERATION_MAGIC` (4 octets), version
+//! (`u8`, `FEDERATION_VERSION`), identifiant de l'instance émettrice (`u32`, tiré au hasard au
+//! démarrage), numéro de séquence (`u32`), adresse du premier registre (`u16`), nombre de
+//! registres (`u16`) puis les registres (`u16`). Un datagramme reçu d'une instance avec un numéro
+//! de séquence qui n'est pas postérieur au dernier reçu de cette instance est ignoré.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::{Arc, Mutex};
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::UdpSocket;
+
+use crate::database::{IdUser, WordAddress};
+use crate::profiling::{lock_database, Subsystem};
+use crate::server_modbus_tcp::MODBUS_TOP_WORD_ADDRESS;
+use crate::shadow::ShadowRange;
+use crate::sim_rng::SimRng;
+use crate::Database;
+
+/// Période (en millisecondes) par défaut de la publication des plages de registres
+pub const DEFAULT_FEDERATION_PERIOD_MSECS: u64 = 500;
+
+/// Identification d'un datagramme de partage de registres
+pub const FEDERATION_MAGIC: [u8; 4] = *b"ICFD";
+
+/// Version courante du format des datagrammes
+pub const FEDERATION_VERSION: u8 = 1;
+
+/// Taille de l'entête d'un datagramme (magic, version, instance, séquence, adresse, nombre)
+const HEADER_LEN: usize = 4 + 1 + 4 + 4 + 2 + 2;
+
+/// Nombre max. de registres par datagramme (datagramme de moins de 1472 octets, sans
+/// fragmentation IP sur Ethernet)
+const MAX_WORDS_PER_DATAGRAM: u16 = 600;
+
+/// Nombre de cycles de publication entre 2 publications de tous les blocs
+const FEDERATION_REFRESH_CYCLES: u32 = 10;
+
+/// Bloc de registres consécutifs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FederationBlock {
+    /// Adresse du premier registre
+    pub word_address: WordAddress,
+
+    /// Valeurs des registres
+    pub words: Vec<u16>,
+}
+
+/// Datagramme de partage d'un bloc de registres
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FederationDatagram {
+    /// Identifiant de l'instance émettrice
+    pub instance_id: u32,
+
+    /// Numéro de séquence des datagrammes de l'instance émettrice
+    pub sequence: u32,
+
+    /// Bloc de registres
+    pub block: FederationBlock,
+}
+
+impl FederationDatagram {
+    /// Contenu du datagramme
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = FEDERATION_MAGIC.to_vec();
+        bytes.push(FEDERATION_VERSION);
+        bytes.extend(self.instance_id.to_be_bytes());
+        bytes.extend(self.sequence.to_be_bytes());
+        bytes.extend(self.block.word_address.to_be_bytes());
+        bytes.extend(
+            u16::try_from(self.block.words.len())
+                .unwrap_or(u16::MAX)
+                .to_be_bytes(),
+        );
+        for word in &self.block.words {
+            bytes.extend(word.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Décodage d'un datagramme reçu
+    /// # Errors
+    /// Datagramme qui n'est pas un partage de registres, version inconnue, taille incorrecte ou
+    /// bloc au delà de la fin de la database
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < HEADER_LEN || bytes[0..4] != FEDERATION_MAGIC {
+            return Err("Datagramme non reconnu".to_string());
+        }
+        if bytes[4] != FEDERATION_VERSION {
+            return Err(format!(
+                "Version {} du datagramme non supportée (version {FEDERATION_VERSION})",
+                bytes[4]
+            ));
+        }
+        let u16_at = |offset: usize| u16::from_be_bytes([bytes[offset], bytes[offset + 1]]);
+        let u32_at = |offset: usize| {
+            u32::from_be_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        let nb_words = usize::from(u16_at(15));
+        if bytes.len() != HEADER_LEN + 2 * nb_words {
+            return Err(format!(
+                "Taille du datagramme incorrecte ({} octets pour {nb_words} registres)",
+                bytes.len()
+            ));
+        }
+        let word_address = u16_at(13);
+        if usize::from(word_address) + nb_words > usize::from(MODBUS_TOP_WORD_ADDRESS) {
+            return Err(format!(
+                "Bloc @{word_address:04X} de {nb_words} registres hors de la database"
+            ));
+        }
+        Ok(Self {
+            instance_id: u32_at(5),
+            sequence: u32_at(9),
+            block: FederationBlock {
+                word_address,
+                words: bytes[HEADER_LEN..]
+                    .chunks(2)
+                    .map(|word| u16::from_be_bytes([word[0], word[1]]))
+                    .collect(),
+            },
+        })
+    }
+}
+
+/// Analyse du groupe multicast `<adresse IPv4>:<port>` (ex: `239.255.0.1:5021`)
+/// # Errors
+/// Message d'erreur si l'adresse n'est pas une adresse multicast IPv4 avec son port
+pub fn parse_federation_group(spec: &str) -> Result<SocketAddrV4, String> {
+    match spec.trim().parse::<SocketAddrV4>() {
+        Ok(group) if group.ip().is_multicast() => Ok(group),
+        _ => Err(format!(
+            "Groupe de partage '{spec}' incorrect (attendu: <adresse multicast IPv4>:<port>, ex: 239.255.0.1:5021)"
+        )),
+    }
+}
+
+/// Découpe des plages de registres en blocs (adresse, nombre de registres) d'un datagramme
+fn blocks_of(ranges: &[ShadowRange]) -> Vec<(WordAddress, u16)> {
+    let mut blocks = vec![];
+    for range in ranges {
+        let mut word_address = range.start;
+        loop {
+            let nb_words = (range.end - word_address).min(MAX_WORDS_PER_DATAGRAM - 1) + 1;
+            blocks.push((word_address, nb_words));
+            match word_address.checked_add(nb_words) {
+                Some(next) if next <= range.end => word_address = next,
+                _ => break,
+            }
+        }
+    }
+    blocks
+}
+
+/// Sélection des blocs à publier à chaque cycle
+#[derive(Clone, Debug, Default)]
+pub struct FederationPublisher {
+    /// Derniers blocs publiés par adresse
+    last_blocks: HashMap<WordAddress, Vec<u16>>,
+
+    /// Nombre de cycles de publication
+    nb_cycles: u32,
+}
+
+impl FederationPublisher {
+    /// Blocs à publier parmi le contenu actuel des blocs : les blocs modifiés depuis leur
+    /// dernière publication, ou tous les blocs tous les `FEDERATION_REFRESH_CYCLES` cycles
+    pub fn select(&mut self, blocks: Vec<FederationBlock>) -> Vec<FederationBlock> {
+        let is_refresh = self.nb_cycles.is_multiple_of(FEDERATION_REFRESH_CYCLES);
+        self.nb_cycles = self.nb_cycles.wrapping_add(1);
+        blocks
+            .into_iter()
+            .filter(|block| {
+                let is_changed = self.last_blocks.get(&block.word_address) != Some(&block.words);
+                if is_changed {
+                    self.last_blocks
+                        .insert(block.word_address, block.words.clone());
+                }
+                is_changed || is_refresh
+            })
+            .collect()
+    }
+}
+
+/// Filtrage des datagrammes reçus des autres instances
+#[derive(Clone, Debug)]
+pub struct FederationReceiver {
+    /// Identifiant de l'instance locale (ses propres datagrammes sont ignorés)
+    instance_id: u32,
+
+    /// Plages publiées par l'instance locale (refusées des autres instances)
+    own_ranges: Vec<ShadowRange>,
+
+    /// Plages acceptées des autres instances (toutes si vide)
+    accept_ranges: Vec<ShadowRange>,
+
+    /// Dernier numéro de séquence reçu de chaque instance
+    last_sequences: HashMap<u32, u32>,
+}
+
+impl FederationReceiver {
+    /// Constructeur
+    pub fn new(
+        instance_id: u32,
+        own_ranges: &[ShadowRange],
+        accept_ranges: &[ShadowRange],
+    ) -> Self {
+        Self {
+            instance_id,
+            own_ranges: own_ranges.to_vec(),
+            accept_ranges: accept_ranges.to_vec(),
+            last_sequences: HashMap::new(),
+        }
+    }
+
+    /// Indique si un bloc peut être écrit dans la database : hors des plages publiées par
+    /// l'instance locale et dans une des plages acceptées (s'il y en a)
+    fn is_block_accepted(&self, block: &FederationBlock) -> bool {
+        let Ok(nb_words) = u16::try_from(block.words.len()) else {
+            return false;
+        };
+        if nb_words == 0 {
+            return true;
+        }
+        let Some(end) = block.word_address.checked_add(nb_words - 1) else {
+            return false;
+        };
+        let block_range = ShadowRange {
+            start: block.word_address,
+            end,
+        };
+        !self
+            .own_ranges
+            .iter()
+            .any(|range| range.overlaps(&block_range))
+            && (self.accept_ranges.is_empty()
+                || self
+                    .accept_ranges
+                    .iter()
+                    .any(|range| range.contains(&block_range)))
+    }
+
+    /// Indique si un datagramme reçu est à appliquer (datagramme d'une autre instance, plus
+    /// récent que le dernier reçu de cette instance, pour un bloc accepté)
+    pub fn accept(&mut self, datagram: &FederationDatagram) -> bool {
+        if datagram.instance_id == self.instance_id || !self.is_block_accepted(&datagram.block) {
+            return false;
+        }
+        if let Some(last_sequence) = self.last_sequences.get(&datagram.instance_id) {
+            // Comparaison modulo 2^32 (la séquence reboucle)
+            #[allow(clippy::cast_possible_wrap)]
+            if datagram.sequence.wrapping_sub(*last_sequence) as i32 <= 0 {
+                return false;
+            }
+        }
+        self.last_sequences
+            .insert(datagram.instance_id, datagram.sequence);
+        true
+    }
+}
+
+/// Socket UDP abonnée au groupe multicast sur l'interface `interface` (plusieurs instances du
+/// simulateur peuvent l'ouvrir sur le même poste)
+fn open_socket(group: SocketAddrV4, interface: Ipv4Addr) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, group.port())).into())?;
+    socket.join_multicast_v4(group.ip(), &interface)?;
+    socket.set_multicast_if_v4(&interface)?;
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::from_std(socket.into())
+}
+
+/// Contenu actuel des blocs de registres publiés
+fn read_blocks(
+    thread_db: &Arc<Mutex<Database>>,
+    id_user: IdUser,
+    blocks: &[(WordAddress, u16)],
+) -> Vec<FederationBlock> {
+    let db = lock_database(thread_db, Subsystem::Federation);
+    blocks
+        .iter()
+        .map(|(word_address, nb_words)| FederationBlock {
+            word_address: *word_address,
+            words: (0..*nb_words)
+                .map(|offset| db.get_u16_from_word_address(id_user, word_address + offset))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Écriture dans la database d'un bloc publié par une autre instance (si différent)
+fn write_block(thread_db: &Arc<Mutex<Database>>, id_user: IdUser, block: &FederationBlock) {
+    let mut db = lock_database(thread_db, Subsystem::Federation);
+    let is_changed = block.words.iter().enumerate().any(|(offset, word)| {
+        #[allow(clippy::cast_possible_truncation)]
+        let word_address = block.word_address.wrapping_add(offset as WordAddress);
+        db.get_u16_from_word_address(id_user, word_address) != *word
+    });
+    if is_changed {
+        let vec_u8: Vec<u8> = block
+            .words
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect();
+        if let Err(db_error) = db.set_vec_u8_to_word_address(id_user, block.word_address, &vec_u8) {
+            println!("FEDERATION: !!! {db_error}");
+        }
+    }
+}
+
+/// Routine d'un thread qui applique les blocs de registres publiés par les autres instances
+async fn receive_process(
+    thread_db: Arc<Mutex<Database>>,
+    socket: Arc<UdpSocket>,
+    id_user: IdUser,
+    mut receiver: FederationReceiver,
+) {
+    let mut buffer = vec![0_u8; HEADER_LEN + 2 * usize::from(MAX_WORDS_PER_DATAGRAM)];
+    loop {
+        match socket.recv_from(&mut buffer).await {
+            Ok((len, from)) => match FederationDatagram::decode(&buffer[..len]) {
+                Ok(datagram) => {
+                    if receiver.accept(&datagram) {
+                        write_block(&thread_db, id_user, &datagram.block);
+                    }
+                }
+                Err(e) => println!("FEDERATION: !!! Datagramme de {from} ignoré: {e}"),
+            },
+            Err(e) => {
+                println!("FEDERATION: !!! Erreur réception: {e}");
+                // Laisse la main...
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+/// Routine d'un thread qui publie des plages de registres sur un groupe multicast et applique
+/// les plages publiées par les autres instances du simulateur (dans les plages `accept_ranges`
+/// si spécifiées)
+pub async fn federation_process(
+    thread_db: Arc<Mutex<Database>>,
+    group: SocketAddrV4,
+    interface: Ipv4Addr,
+    ranges: Vec<ShadowRange>,
+    accept_ranges: Vec<ShadowRange>,
+    period_msecs: u64,
+) {
+    let socket = match open_socket(group, interface) {
+        Ok(socket) => Arc::new(socket),
+        Err(e) => {
+            println!("FEDERATION: !!! Abonnement au groupe {group} impossible: {e}");
+            return;
+        }
+    };
+    let id_user = lock_database(&thread_db, Subsystem::Federation).get_id_user("Federation", false);
+    #[allow(clippy::cast_possible_truncation)]
+    let instance_id = SimRng::random_seed() as u32;
+    println!(
+        "FEDERATION: Instance {instance_id:08X}, groupe {group}, {} plage(s) publiée(s)",
+        ranges.len()
+    );
+    tokio::spawn(receive_process(
+        Arc::clone(&thread_db),
+        Arc::clone(&socket),
+        id_user,
+        FederationReceiver::new(instance_id, &ranges, &accept_ranges),
+    ));
+
+    let blocks = blocks_of(&ranges);
+    let mut publisher = FederationPublisher::default();
+    let mut sequence: u32 = 0;
+    loop {
+        let current_blocks = read_blocks(&thread_db, id_user, &blocks);
+        for block in publisher.select(current_blocks) {
+            sequence = sequence.wrapping_add(1);
+            let datagram = FederationDatagram {
+                instance_id,
+                sequence,
+                block,
+            };
+            if let Err(e) = socket
+                .send_to(&datagram.encode(), SocketAddr::V4(group))
+                .await
+            {
+                println!("FEDERATION: !!! Erreur émission vers {group}: {e}");
+            }
+        }
+
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(period_msecs.max(1))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_federation_datagram() {
+        let datagram = FederationDatagram {
+            instance_id: 0x1234_5678,
+            sequence: 42,
+            block: FederationBlock {
+                word_address: 0x0010,
+                words: vec![0x0102, 0xFFFF],
+            },
+        };
+        let bytes = datagram.encode();
+        assert_eq!(bytes.len(), HEADER_LEN + 4);
+        assert_eq!(&bytes[..5], b"ICFD\x01");
+        assert_eq!(FederationDatagram::decode(&bytes), Ok(datagram));
+        assert!(FederationDatagram::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(FederationDatagram::decode(b"ICST").is_err());
+
+        // Bloc au delà de la fin de la database
+        for (word_address, nb_words) in [(0x8000, 1), (0x7FFF, 2), (0xFFFF, 600)] {
+            let datagram = FederationDatagram {
+                instance_id: 1,
+                sequence: 1,
+                block: FederationBlock {
+                    word_address,
+                    words: vec![0; nb_words],
+                },
+            };
+            assert!(FederationDatagram::decode(&datagram.encode()).is_err());
+        }
+
+        assert!(parse_federation_group("239.255.0.1:5021").is_ok());
+        assert!(parse_federation_group("192.168.1.10:5021").is_err());
+        assert!(parse_federation_group("239.255.0.1").is_err());
+    }
+
+    #[test]
+    fn test_federation_publisher() {
+        let ranges = [ShadowRange {
+            start: 0x0010,
+            end: 0x0010 + 700,
+        }];
+        assert_eq!(blocks_of(&ranges), [(0x0010, 600), (0x0010 + 600, 101)]);
+
+        let block = |word| FederationBlock {
+            word_address: 0x0010,
+            words: vec![word],
+        };
+        let mut publisher = FederationPublisher::default();
+        assert_eq!(publisher.select(vec![block(1)]), [block(1)]);
+        assert!(publisher.select(vec![block(1)]).is_empty());
+        assert_eq!(publisher.select(vec![block(2)]), [block(2)]);
+        for _ in 3..FEDERATION_REFRESH_CYCLES {
+            assert!(publisher.select(vec![block(2)]).is_empty());
+        }
+        // Publication de tous les blocs
+        assert_eq!(publisher.select(vec![block(2)]), [block(2)]);
+    }
+
+    #[test]
+    fn test_federation_receiver() {
+        let datagram = |instance_id, sequence| FederationDatagram {
+            instance_id,
+            sequence,
+            block: FederationBlock {
+                word_address: 0,
+                words: vec![],
+            },
+        };
+        let mut receiver = FederationReceiver::new(1, &[], &[]);
+        assert!(!receiver.accept(&datagram(1, 1)));
+        assert!(receiver.accept(&datagram(2, 10)));
+        assert!(!receiver.accept(&datagram(2, 10)));
+        assert!(!receiver.accept(&datagram(2, 9)));
+        assert!(receiver.accept(&datagram(3, 5)));
+        assert!(receiver.accept(&datagram(2, 11)));
+
+        // Rebouclage de la séquence
+        assert!(receiver.accept(&datagram(4, u32::MAX)));
+        assert!(receiver.accept(&datagram(4, 0)));
+        assert!(!receiver.accept(&datagram(4, u32::MAX)));
+    }
+
+    #[test]
+    fn test_federation_receiver_ranges() {
+        let datagram = |sequence, word_address, nb_words| FederationDatagram {
+            instance_id: 2,
+            sequence,
+            block: FederationBlock {
+                word_address,
+                words: vec![0; nb_words],
+            },
+        };
+        let own_ranges = [ShadowRange {
+            start: 0x0100,
+            end: 0x01FF,
+        }];
+        let accept_ranges = [ShadowRange {
+            start: 0x0200,
+            end: 0x02FF,
+        }];
+
+        // Plages publiées par l'instance locale refusées
+        let mut receiver = FederationReceiver::new(1, &own_ranges, &[]);
+        assert!(receiver.accept(&datagram(1, 0x0010, 16)));
+        assert!(!receiver.accept(&datagram(2, 0x01F0, 32)));
+        assert!(receiver.accept(&datagram(3, 0x0200, 16)));
+
+        // Seules les plages acceptées
+        let mut receiver = FederationReceiver::new(1, &own_ranges, &accept_ranges);
+        assert!(!receiver.accept(&datagram(1, 0x0010, 16)));
+        assert!(receiver.accept(&datagram(2, 0x0200, 16)));
+        assert!(!receiver.accept(&datagram(3, 0x02F0, 17)));
+    }
+}
@@ -0,0 +1,286 @@
+//! Interface C (ABI) pour l'encodage et le décodage des trames TLV
+//!
+//! Cette interface permet de tester une pile TLV écrite en C (résident de l'AFSEC+) contre
+//! l'implémentation Rust de référence, octet par octet.
+//!
+//! Le header C correspondant est `include/sim_icom_tlv.h` (généré par `cbindgen` avec la
+//! configuration `cbindgen.toml`).
+//!
+//! Les données d'un message sont échangées sous la forme de la suite des `DataItem` encodés
+//! (tag + format + valeur 'big endian'), c'est à dire le contenu 'Value' de la trame TLV.
+//!
+//! Les fonctions retournent le nombre d'octets écrits dans le buffer de sortie si OK,
+//! sinon un code d'erreur négatif `SIM_ICOM_TLV_ERR_*`.
+
+use std::slice;
+
+use crate::afsec::tlv_frame::{DataItem, FrameError, RawFrame, ACK, NACK};
+
+/// Erreur: Pointeur NULL en paramètre
+pub const SIM_ICOM_TLV_ERR_NULL_POINTER: i32 = -1;
+
+/// Erreur: Buffer de sortie trop petit
+pub const SIM_ICOM_TLV_ERR_BUFFER_TOO_SMALL: i32 = -2;
+
+/// Erreur: La trame est vide
+pub const SIM_ICOM_TLV_ERR_IS_EMPTY: i32 = -3;
+
+/// Erreur: La trame n'est pas correcte
+pub const SIM_ICOM_TLV_ERR_IS_JUNK: i32 = -4;
+
+/// Erreur: La trame n'est pas complètement construite
+pub const SIM_ICOM_TLV_ERR_IS_BUILDING: i32 = -5;
+
+/// Erreur: La trame n'est pas un message correct
+pub const SIM_ICOM_TLV_ERR_IS_NOT_OK: i32 = -6;
+
+/// Erreur: Inconsistance longueur des `DataItem`
+pub const SIM_ICOM_TLV_ERR_BAD_DATA_LENGTH: i32 = -7;
+
+/// Erreur: Inconsistance décodage des `DataItem`
+pub const SIM_ICOM_TLV_ERR_BAD_DATA_ITEM: i32 = -8;
+
+/// Erreur: Overflow de la longueur max. d'une trame
+pub const SIM_ICOM_TLV_ERR_MAX_LENGTH_OVERFLOW: i32 = -9;
+
+/// Code d'erreur C d'un [`FrameError`]
+pub(crate) fn frame_error_to_code(frame_error: &FrameError) -> i32 {
+    match frame_error {
+        FrameError::IsEmpty => SIM_ICOM_TLV_ERR_IS_EMPTY,
+        FrameError::IsJunk => SIM_ICOM_TLV_ERR_IS_JUNK,
+        FrameError::IsBuilding => SIM_ICOM_TLV_ERR_IS_BUILDING,
+        FrameError::IsNotOk => SIM_ICOM_TLV_ERR_IS_NOT_OK,
+        FrameError::BadDataLength => SIM_ICOM_TLV_ERR_BAD_DATA_LENGTH,
+        FrameError::BadDataItem => SIM_ICOM_TLV_ERR_BAD_DATA_ITEM,
+        FrameError::MaxLengthOverflow => SIM_ICOM_TLV_ERR_MAX_LENGTH_OVERFLOW,
+    }
+}
+
+/// Construit un `&[u8]` depuis un pointeur C (NULL accepté si `len` == 0)
+///
+/// # Safety
+/// `ptr` doit pointer sur au moins `len` octets valides
+unsafe fn slice_from_c<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if ptr.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(ptr, len))
+    }
+}
+
+/// Recopie `vec_u8` dans le buffer C de sortie et retourne le nombre d'octets copiés
+///
+/// # Safety
+/// `out` doit pointer sur au moins `out_len` octets modifiables
+unsafe fn copy_to_c(vec_u8: &[u8], out: *mut u8, out_len: usize) -> i32 {
+    if vec_u8.len() > out_len {
+        return SIM_ICOM_TLV_ERR_BUFFER_TOO_SMALL;
+    }
+    if !vec_u8.is_empty() {
+        if out.is_null() {
+            return SIM_ICOM_TLV_ERR_NULL_POINTER;
+        }
+        slice::from_raw_parts_mut(out, vec_u8.len()).copy_from_slice(vec_u8);
+    }
+    i32::try_from(vec_u8.len()).unwrap_or(SIM_ICOM_TLV_ERR_BUFFER_TOO_SMALL)
+}
+
+/// Encode une trame TLV complète (STX + tag + len + données + XOR + ETX)
+///
+/// * `tag`: Tag du message
+/// * `data`, `data_len`: Suite des `DataItem` encodés (tag + format + valeur)
+/// * `out`, `out_len`: Buffer pour la trame encodée
+///
+/// Retourne la longueur de la trame encodée ou un code d'erreur négatif
+///
+/// # Safety
+/// `data` doit pointer sur `data_len` octets valides et `out` sur `out_len` octets modifiables
+#[no_mangle]
+pub unsafe extern "C" fn sim_icom_tlv_encode(
+    tag: u8,
+    data: *const u8,
+    data_len: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> i32 {
+    let Some(data) = slice_from_c(data, data_len) else {
+        return SIM_ICOM_TLV_ERR_NULL_POINTER;
+    };
+    let data_items = match DataItem::decode_all(data) {
+        Ok(data_items) => data_items,
+        Err(e) => return frame_error_to_code(&e),
+    };
+    let mut raw_frame = RawFrame::new_message(tag);
+    for data_item in &data_items {
+        if let Err(e) = raw_frame.try_extend_data_item(data_item) {
+            return frame_error_to_code(&e);
+        }
+    }
+    copy_to_c(&raw_frame.encode(), out, out_len)
+}
+
+/// Décode une trame TLV complète
+///
+/// * `frame`, `frame_len`: Octets de la trame reçue
+/// * `tag`: Tag du message décodé (`ACK` ou `NACK` pour une trame simple ACK ou NACK)
+/// * `out`, `out_len`: Buffer pour la suite des `DataItem` encodés (tag + format + valeur)
+///
+/// Retourne la longueur des données du message ou un code d'erreur négatif
+///
+/// # Safety
+/// `frame` doit pointer sur `frame_len` octets valides, `tag` sur un octet modifiable
+/// et `out` sur `out_len` octets modifiables
+#[no_mangle]
+pub unsafe extern "C" fn sim_icom_tlv_decode(
+    frame: *const u8,
+    frame_len: usize,
+    tag: *mut u8,
+    out: *mut u8,
+    out_len: usize,
+) -> i32 {
+    let Some(frame) = slice_from_c(frame, frame_len) else {
+        return SIM_ICOM_TLV_ERR_NULL_POINTER;
+    };
+    if tag.is_null() {
+        return SIM_ICOM_TLV_ERR_NULL_POINTER;
+    }
+    match RawFrame::new(frame) {
+        RawFrame::Empty => SIM_ICOM_TLV_ERR_IS_EMPTY,
+        RawFrame::Ack => {
+            *tag = ACK;
+            0
+        }
+        RawFrame::Nack => {
+            *tag = NACK;
+            0
+        }
+        RawFrame::Ok(frame_tag, _, values, _) => {
+            if let Err(e) = DataItem::decode_all(&values) {
+                return frame_error_to_code(&e);
+            }
+            *tag = frame_tag;
+            copy_to_c(&values, out, out_len)
+        }
+        RawFrame::AckAndJunk(_)
+        | RawFrame::NackAndJunk(_)
+        | RawFrame::OkAndJunk(_, _, _, _, _)
+        | RawFrame::Junk(_) => SIM_ICOM_TLV_ERR_IS_JUNK,
+        RawFrame::Stx
+        | RawFrame::Tag(_)
+        | RawFrame::TagLen(_, _)
+        | RawFrame::TagLenValue(_, _, _)
+        | RawFrame::Xor(_, _, _, _) => SIM_ICOM_TLV_ERR_IS_BUILDING,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::t_data::TValue;
+
+    /// Encode via l'interface C
+    fn encode(tag: u8, data: &[u8]) -> Result<Vec<u8>, i32> {
+        let mut out = [0_u8; 300];
+        let n = unsafe {
+            sim_icom_tlv_encode(tag, data.as_ptr(), data.len(), out.as_mut_ptr(), out.len())
+        };
+        if n < 0 {
+            Err(n)
+        } else {
+            Ok(out[..usize::try_from(n).unwrap()].to_vec())
+        }
+    }
+
+    /// Décode via l'interface C
+    fn decode(frame: &[u8]) -> Result<(u8, Vec<u8>), i32> {
+        let mut tag = 0_u8;
+        let mut out = [0_u8; 300];
+        let n = unsafe {
+            sim_icom_tlv_decode(
+                frame.as_ptr(),
+                frame.len(),
+                &mut tag,
+                out.as_mut_ptr(),
+                out.len(),
+            )
+        };
+        if n < 0 {
+            Err(n)
+        } else {
+            Ok((tag, out[..usize::try_from(n).unwrap()].to_vec()))
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        // AF_ALIVE
+        assert_eq!(encode(0, &[]).unwrap(), vec![2, 0, 0, 0, 3]);
+
+        // Un booléen
+        assert_eq!(
+            encode(0x23, &[0x45, 0x11, 0x01]).unwrap(),
+            vec![0x02, 0x23, 0x03, 0x45, 0x11, 0x01, 0x75, 0x03]
+        );
+
+        // Données incomplètes
+        assert_eq!(
+            encode(0x23, &[0x45, 0x02, 0x00]),
+            Err(SIM_ICOM_TLV_ERR_BAD_DATA_LENGTH)
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(&[ACK]).unwrap(), (ACK, vec![]));
+        assert_eq!(decode(&[NACK]).unwrap(), (NACK, vec![]));
+        assert_eq!(
+            decode(&[0x02, 0x23, 0x03, 0x45, 0x11, 0x01, 0x75, 0x03]).unwrap(),
+            (0x23, vec![0x45, 0x11, 0x01])
+        );
+        assert_eq!(decode(&[]), Err(SIM_ICOM_TLV_ERR_IS_EMPTY));
+        assert_eq!(decode(&[0x02, 0x23]), Err(SIM_ICOM_TLV_ERR_IS_BUILDING));
+        assert_eq!(
+            decode(&[0x02, 0x23, 0x03, 0x45, 0x11, 0x01, 0x00, 0x03]),
+            Err(SIM_ICOM_TLV_ERR_IS_JUNK)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for t_value in [
+            TValue::Bool(true),
+            TValue::U8(123),
+            TValue::I8(-123),
+            TValue::U16(1234),
+            TValue::I16(-1234),
+            TValue::U32(123_456),
+            TValue::I32(-123_456),
+            TValue::U64(123_456_789),
+            TValue::F32(-123.0),
+            TValue::F64(-123.0),
+            TValue::VecU8(5, vec![b'A', b'B', b'C', b'D', b'E']),
+        ] {
+            let data = DataItem::new(0x45, t_value).encode();
+            let frame = encode(0x23, &data).unwrap();
+
+            // Identique à l'encodage Rust
+            let mut raw_frame = RawFrame::new_message(0x23);
+            raw_frame
+                .try_extend_data_item(&DataItem::decode(&data).unwrap().0)
+                .unwrap();
+            assert_eq!(frame, raw_frame.encode());
+
+            assert_eq!(decode(&frame).unwrap(), (0x23, data));
+        }
+    }
+
+    #[test]
+    fn test_buffer_too_small() {
+        let mut out = [0_u8; 4];
+        let n = unsafe { sim_icom_tlv_encode(0, std::ptr::null(), 0, out.as_mut_ptr(), out.len()) };
+        assert_eq!(n, SIM_ICOM_TLV_ERR_BUFFER_TOO_SMALL);
+    }
+}
@@ -0,0 +1,26 @@
+//! Process de supervision de la fraîcheur des tags de la [`Database`]
+//!
+//! Les âges max. des tags mis à jour périodiquement (capteurs) sont définis par l'option
+//! `--max-age <adresse>=<secondes>` de la ligne de commande. Ce process contrôle périodiquement
+//! la fraîcheur de ces tags (voir le module `tag_freshness` de la [`Database`]) pour signaler les
+//! capteurs figés.
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::profiling::{lock_database, Subsystem};
+use crate::Database;
+
+/// Temps de cycle (en millisecondes) du contrôle de la fraîcheur des tags
+pub const FRESHNESS_CYCLE_MSECS: u64 = 500;
+
+/// Routine d'un thread qui contrôle périodiquement la fraîcheur des tags avec un âge max.
+/// En paramètre, le temps de cycle entre chaque contrôle (en millisecondes)
+pub async fn freshness_process(thread_db: Arc<Mutex<Database>>, cycle_in_msecs: u64) {
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(cycle_in_msecs)).await;
+
+        lock_database(&thread_db, Subsystem::Freshness).check_tag_freshness(Instant::now());
+    }
+}
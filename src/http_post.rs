@@ -0,0 +1,126 @@
+//! Requêtes HTTP `POST` minimales vers InfluxDB et les 'webhooks'
+//!
+//! Seules les URL `http://<hôte>[:<port>][/<chemin>]` sont supportées ([`HttpUrl`]). Chaque
+//! requête ouvre une connexion (`Connection: close`) et ne lit que le statut de la réponse.
+//!
+//! La connexion, l'envoi de la requête et la lecture du statut sont limités à `HTTP_TIMEOUT` :
+//! un serveur qui ne répond plus ne bloque pas la tâche qui l'utilise.
+
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Délai max. d'une requête (connexion, envoi et statut de la réponse)
+pub const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// URL `http://` d'un serveur
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpUrl {
+    /// Adresse `<hôte>:<port>` du serveur
+    pub host_port: String,
+
+    /// Chemin et paramètres de la requête (ex: `/write?db=bench`)
+    pub path: String,
+}
+
+impl HttpUrl {
+    /// Analyse de 'http://<hôte>[:<port>][/<chemin>]' avec le port `default_port` et le chemin
+    /// `default_path` par défaut (un chemin qui commence par `?` complète `default_path`)
+    /// `kind` désigne le serveur dans les messages d'erreur
+    /// # Errors
+    /// Message d'erreur si l'URL n'est pas une URL `http://` avec un hôte
+    pub fn parse(
+        s: &str,
+        kind: &str,
+        default_port: u16,
+        default_path: &str,
+    ) -> Result<Self, String> {
+        let Some(url) = s.trim().strip_prefix("http://") else {
+            return Err(format!("URL {kind} '{s}' incorrecte (attendu: http://...)"));
+        };
+        let (host_port, path) = match url.find(['/', '?']) {
+            Some(pos) => url.split_at(pos),
+            None => (url, ""),
+        };
+        if host_port.is_empty() {
+            return Err(format!("URL {kind} '{s}' sans hôte"));
+        }
+        let host_port = if host_port.contains(':') {
+            host_port.to_string()
+        } else {
+            format!("{host_port}:{default_port}")
+        };
+        let path = match path {
+            "" | "/" => default_path.to_string(),
+            path if path.starts_with('?') => format!("{default_path}{path}"),
+            path => path.to_string(),
+        };
+        Ok(HttpUrl { host_port, path })
+    }
+}
+
+impl fmt::Display for HttpUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "http://{}{}", self.host_port, self.path)
+    }
+}
+
+/// Requête HTTP `POST` sans limite de durée
+async fn post_unbounded(url: &HttpUrl, content_type: &str, body: &str) -> io::Result<u16> {
+    let mut stream = TcpStream::connect(&url.host_port).await?;
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        url.path,
+        url.host_port,
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    // Statut de la réponse (`HTTP/1.1 204 No Content`)
+    let mut buffer = [0_u8; 64];
+    let nb_bytes = stream.read(&mut buffer).await?;
+    let response = String::from_utf8_lossy(&buffer[..nb_bytes]);
+    response
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| io::Error::other(format!("Réponse HTTP incorrecte '{response}'")))
+}
+
+/// Envoi d'un document (requête HTTP `POST`) en moins de `HTTP_TIMEOUT`
+/// Retourne le code de statut HTTP de la réponse
+/// # Errors
+/// Erreur de communication avec le serveur, délai dépassé ou réponse HTTP incorrecte
+pub async fn post(url: &HttpUrl, content_type: &str, body: &str) -> io::Result<u16> {
+    match tokio::time::timeout(HTTP_TIMEOUT, post_unbounded(url, content_type, body)).await {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("Pas de réponse en {}s", HTTP_TIMEOUT.as_secs()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_url() {
+        let url = HttpUrl::parse("http://bench:8086/write?db=sim", "test", 80, "/").unwrap();
+        assert_eq!(url.host_port, "bench:8086");
+        assert_eq!(url.path, "/write?db=sim");
+        assert_eq!(url.to_string(), "http://bench:8086/write?db=sim");
+
+        let url = HttpUrl::parse("http://bench?db=sim", "test", 8086, "/write").unwrap();
+        assert_eq!(url.to_string(), "http://bench:8086/write?db=sim");
+        let url = HttpUrl::parse("http://bench/", "test", 80, "/hook").unwrap();
+        assert_eq!(url.path, "/hook");
+
+        assert!(HttpUrl::parse("https://bench", "test", 80, "/").is_err());
+        assert!(HttpUrl::parse("http:///write", "test", 80, "/").is_err());
+    }
+}
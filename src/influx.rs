@@ -0,0 +1,291 @@
+//! Export des valeurs des tags vers InfluxDB (protocole 'line protocol')
+//!
+//! Les valeurs des tags de groupes sélectionnés (option `--influx-group` de la ligne de
+//! commande) sont poussées vers un serveur InfluxDB (option `--influx-url`, requête HTTP `POST`
+//! sur l'API `/write`) pour être exploitées par les tableaux de bord Grafana du banc :
+//!
+//! * `<groupe>` : Chaque modification d'un tag du groupe est exportée
+//! * `<groupe>=<secondes>` : Tous les tags du groupe sont échantillonnés périodiquement
+//!
+//! Chaque valeur est une ligne `sim_icom,group=<groupe>,id=<id_tag>,address=<adresse>
+//! value=<valeur> <horodatage>` (horodatage en nanosecondes de l'horloge simulée).
+//! En cas d'erreur de communication avec le serveur ou sans réponse dans le délai
+//! `HTTP_TIMEOUT` (voir le module `http_post`), les valeurs sont perdues (trace `INFLUX:`).
+
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::database::{IdTag, Tag};
+use crate::http_post::{self, HttpUrl};
+use crate::profiling::{lock_database, Subsystem};
+use crate::sim_clock;
+use crate::t_data::TValue;
+use crate::Database;
+
+/// Nom de la 'measurement' InfluxDB des valeurs exportées
+const MEASUREMENT: &str = "sim_icom";
+
+/// Temps de cycle (en millisecondes) de l'export des modifications
+const INFLUX_CYCLE_MSECS: u64 = 1000;
+
+/// Serveur InfluxDB destinataire des valeurs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InfluxEndpoint {
+    /// URL de l'API `/write` (ex: `http://bench:8086/write?db=bench`)
+    url: HttpUrl,
+}
+
+impl FromStr for InfluxEndpoint {
+    type Err = String;
+
+    /// Accepte 'http://<hôte>[:<port>][/<chemin>]' (port 8086 et chemin `/write` par défaut)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(InfluxEndpoint {
+            url: HttpUrl::parse(s, "InfluxDB", 8086, "/write")?,
+        })
+    }
+}
+
+/// Groupe de tags exporté vers InfluxDB
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InfluxGroup {
+    /// Nom du groupe
+    pub group: String,
+
+    /// Période d'échantillonnage des tags du groupe (None pour exporter chaque modification)
+    pub option_period: Option<Duration>,
+}
+
+impl FromStr for InfluxGroup {
+    type Err = String;
+
+    /// Accepte '<groupe>' ou '<groupe>=<secondes>'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (group, option_period) = match s.split_once('=') {
+            Some((group, period)) => match period.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => (group.trim(), Some(Duration::from_secs(secs))),
+                _ => {
+                    return Err(format!(
+                        "Période '{period}' incorrecte pour le groupe '{s}'"
+                    ))
+                }
+            },
+            None => (s.trim(), None),
+        };
+        if group.is_empty() {
+            return Err(format!(
+                "Groupe '{s}' incorrect (attendu: <groupe>[=<secondes>])"
+            ));
+        }
+        Ok(InfluxGroup {
+            group: group.to_string(),
+            option_period,
+        })
+    }
+}
+
+/// Échappement d'une valeur de 'tag' InfluxDB (virgules, espaces et signes égal)
+fn escape_tag_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, ',' | ' ' | '=') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Valeur d'un 'field' InfluxDB (None si la valeur n'est pas exportable, NaN par exemple)
+fn field_value(t_value: &TValue) -> Option<String> {
+    match t_value {
+        TValue::Bool(value) => Some(value.to_string()),
+        TValue::U8(_)
+        | TValue::I8(_)
+        | TValue::U16(_)
+        | TValue::I16(_)
+        | TValue::U32(_)
+        | TValue::I32(_)
+        | TValue::U64(_)
+        | TValue::I64(_) => Some(format!("{}i", String::from(t_value))),
+        TValue::F32(value) => value.is_finite().then(|| format!("{value}")),
+        TValue::F64(value) => value.is_finite().then(|| format!("{value}")),
+        TValue::VecU8(..) => {
+            let value = String::from(t_value);
+            Some(format!(
+                "\"{}\"",
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            ))
+        }
+    }
+}
+
+/// Ligne au format 'line protocol' de la valeur d'un [`Tag`] d'un groupe
+pub fn line_protocol(group: &str, tag: &Tag, t_value: &TValue, date: Duration) -> Option<String> {
+    let value = field_value(t_value)?;
+    Some(format!(
+        "{MEASUREMENT},group={},id={},address=0x{:04X} value={value} {}",
+        escape_tag_value(group),
+        escape_tag_value(&tag.id_tag.to_string()),
+        tag.word_address,
+        date.as_nanos()
+    ))
+}
+
+/// Envoi de lignes au serveur InfluxDB (requête HTTP `POST` limitée à `HTTP_TIMEOUT`)
+/// Retourne le code de statut HTTP de la réponse
+async fn post_lines(endpoint: &InfluxEndpoint, body: &str) -> std::io::Result<u16> {
+    http_post::post(&endpoint.url, "text/plain; charset=utf-8", body).await
+}
+
+/// Routine d'un thread qui exporte les valeurs des tags de groupes vers InfluxDB
+pub async fn influx_process(
+    thread_db: Arc<Mutex<Database>>,
+    endpoint: InfluxEndpoint,
+    groups: Vec<InfluxGroup>,
+) {
+    if groups.is_empty() {
+        return;
+    }
+    println!("INFLUX: Starting ({})...", endpoint.url);
+
+    // Tags de chaque groupe et date du prochain échantillonnage
+    let (id_user, mut group_tags) = {
+        let mut db = lock_database(&thread_db, Subsystem::Influx);
+        let id_user = db.get_id_user("InfluxDB", true);
+        let mut group_tags = vec![];
+        for influx_group in groups {
+            match db.get_group_tags(&influx_group.group) {
+                Ok(tags) => {
+                    let id_tags: HashSet<IdTag> = tags.iter().map(|tag| tag.id_tag).collect();
+                    group_tags.push((influx_group, id_tags, Instant::now()));
+                }
+                Err(msg) => println!("INFLUX: {msg}"),
+            }
+        }
+        (id_user, group_tags)
+    };
+
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(INFLUX_CYCLE_MSECS)).await;
+
+        let mut lines = vec![];
+        {
+            let mut db = lock_database(&thread_db, Subsystem::Influx);
+            let date = sim_clock::now();
+
+            // Modifications des tags des groupes exportés à chaque modification
+            while let Some(notification_change) = db.get_change(id_user, false, true) {
+                let Some(tag) = db.get_tag_from_id_tag(notification_change.id_tag) else {
+                    continue;
+                };
+                let t_value = db.get_t_value_from_tag(id_user, tag);
+                for (influx_group, id_tags, _) in &group_tags {
+                    if influx_group.option_period.is_none() && id_tags.contains(&tag.id_tag) {
+                        lines.extend(line_protocol(&influx_group.group, tag, &t_value, date));
+                    }
+                }
+            }
+
+            // Échantillonnage des groupes exportés périodiquement
+            let now = Instant::now();
+            for (influx_group, id_tags, next_sample) in &mut group_tags {
+                let Some(period) = influx_group.option_period else {
+                    continue;
+                };
+                if now < *next_sample {
+                    continue;
+                }
+                *next_sample = now + period;
+                for id_tag in id_tags.iter() {
+                    if let Some(tag) = db.get_tag_from_id_tag(*id_tag) {
+                        let t_value = db.get_t_value_from_tag(id_user, tag);
+                        lines.extend(line_protocol(&influx_group.group, tag, &t_value, date));
+                    }
+                }
+            }
+        }
+
+        if lines.is_empty() {
+            continue;
+        }
+        match post_lines(&endpoint, &lines.join("\n")).await {
+            Ok(status) if (200..300).contains(&status) => (),
+            Ok(status) => println!(
+                "INFLUX: {} valeur(s) refusée(s) (HTTP {status})",
+                lines.len()
+            ),
+            Err(e) => println!("INFLUX: {} valeur(s) perdue(s): {e}", lines.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_influx_endpoint() {
+        let endpoint = InfluxEndpoint::from_str("http://bench:8086/write?db=sim").unwrap();
+        assert_eq!(endpoint.url.host_port, "bench:8086");
+        assert_eq!(endpoint.url.path, "/write?db=sim");
+
+        let endpoint = InfluxEndpoint::from_str("http://bench?db=sim").unwrap();
+        assert_eq!(endpoint.url.host_port, "bench:8086");
+        assert_eq!(endpoint.url.path, "/write?db=sim");
+
+        assert!(InfluxEndpoint::from_str("https://bench").is_err());
+        assert!(InfluxEndpoint::from_str("http:///write").is_err());
+    }
+
+    #[test]
+    fn test_influx_group() {
+        assert_eq!(
+            InfluxGroup::from_str("METERING"),
+            Ok(InfluxGroup {
+                group: "METERING".to_string(),
+                option_period: None
+            })
+        );
+        assert_eq!(
+            InfluxGroup::from_str("METERING=10"),
+            Ok(InfluxGroup {
+                group: "METERING".to_string(),
+                option_period: Some(Duration::from_secs(10))
+            })
+        );
+        assert!(InfluxGroup::from_str("METERING=0").is_err());
+        assert!(InfluxGroup::from_str("=10").is_err());
+    }
+
+    #[test]
+    fn test_line_protocol() {
+        let tag = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 0x0020, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        };
+        let date = Duration::from_millis(1_500);
+        assert_eq!(
+            line_protocol("MY GROUP", &tag, &TValue::U16(12), date).unwrap(),
+            "sim_icom,group=MY\\ GROUP,id=1/0020:00:00:00,address=0x0010 value=12i 1500000000"
+        );
+        assert_eq!(
+            line_protocol("G", &tag, &TValue::F32(2.5), date).unwrap(),
+            "sim_icom,group=G,id=1/0020:00:00:00,address=0x0010 value=2.5 1500000000"
+        );
+        assert!(line_protocol("G", &tag, &TValue::F64(f64::NAN), date).is_none());
+        assert_eq!(
+            field_value(&TValue::VecU8(3, b"a\"b".to_vec())),
+            Some("\"a\\\"b\"".to_string())
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Histogramme des latences (type HDR) en microsecondes
+//!
+//! Les valeurs sont regroupées par puissance de 2, chaque puissance de 2 étant découpée en
+//! `SUB_BUCKET_HALF_COUNT` intervalles de même largeur : les valeurs inférieures à
+//! 2 x `SUB_BUCKET_HALF_COUNT` µs sont exactes et la précision relative est ensuite meilleure que
+//! 1 / `SUB_BUCKET_HALF_COUNT` (soit 1,6%), avec une mémoire fixe quel que soit le nombre de
+//! valeurs enregistrées. Les valeurs au delà de `LATENCY_MAX_MICROS` sont comptées dans le
+//! dernier intervalle.
+//!
+//! Utilisé pour les temps de réponse de la liaison série avec l'AFSEC+ (voir
+//! `profiling::record_round_trip`), à comparer aux mesures à l'oscilloscope de l'ICOM réel.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Nombre de bits des intervalles d'une puissance de 2
+const SUB_BUCKET_BITS: u32 = 6;
+
+/// Nombre d'intervalles d'une puissance de 2
+const SUB_BUCKET_HALF_COUNT: usize = 1 << SUB_BUCKET_BITS;
+
+/// Latence max. (en microsecondes) distinguée dans l'histogramme (60 secondes)
+pub const LATENCY_MAX_MICROS: u64 = 60_000_000;
+
+/// Indice de l'intervalle d'une valeur
+fn index_of(value: u64) -> usize {
+    let value = value.min(LATENCY_MAX_MICROS);
+    let magnitude = u64::BITS - value.leading_zeros();
+    let bucket = magnitude.saturating_sub(SUB_BUCKET_BITS + 1);
+    // `value >> bucket` < 2 x `SUB_BUCKET_HALF_COUNT` (conversion sans perte)
+    bucket as usize * SUB_BUCKET_HALF_COUNT + usize::try_from(value >> bucket).unwrap()
+}
+
+/// Plus grande valeur de l'intervalle d'indice `index`
+fn highest_value_of(index: usize) -> u64 {
+    if index < 2 * SUB_BUCKET_HALF_COUNT {
+        return index as u64;
+    }
+    let bucket = index / SUB_BUCKET_HALF_COUNT - 1;
+    let sub_bucket = (index - bucket * SUB_BUCKET_HALF_COUNT) as u64;
+    ((sub_bucket + 1) << bucket) - 1
+}
+
+/// Histogramme des latences
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// Nombre de valeurs de chaque intervalle
+    counts: Vec<u64>,
+
+    /// Nombre de valeurs enregistrées
+    nb_values: u64,
+
+    /// Somme des valeurs (en microsecondes)
+    sum_micros: u64,
+
+    /// Plus petite valeur (en microsecondes)
+    min_micros: u64,
+
+    /// Plus grande valeur (en microsecondes)
+    max_micros: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for LatencyHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.nb_values == 0 {
+            return write!(f, "count=0");
+        }
+        write!(
+            f,
+            "count={} min={:?} mean={:?} p50={:?} p90={:?} p99={:?} p99.9={:?} max={:?}",
+            self.nb_values,
+            self.min(),
+            self.mean(),
+            self.percentile(50.0),
+            self.percentile(90.0),
+            self.percentile(99.0),
+            self.percentile(99.9),
+            self.max()
+        )
+    }
+}
+
+impl LatencyHistogram {
+    /// Constructeur (histogramme vide)
+    pub const fn new() -> Self {
+        Self {
+            counts: Vec::new(),
+            nb_values: 0,
+            sum_micros: 0,
+            min_micros: u64::MAX,
+            max_micros: 0,
+        }
+    }
+
+    /// Enregistre une latence
+    pub fn record(&mut self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        let index = index_of(micros);
+        if self.counts.len() <= index {
+            self.counts.resize(index_of(LATENCY_MAX_MICROS) + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.nb_values += 1;
+        self.sum_micros = self.sum_micros.saturating_add(micros);
+        self.min_micros = self.min_micros.min(micros);
+        self.max_micros = self.max_micros.max(micros);
+    }
+
+    /// Nombre de latences enregistrées
+    pub fn count(&self) -> u64 {
+        self.nb_values
+    }
+
+    /// Plus petite latence (0 si aucune)
+    pub fn min(&self) -> Duration {
+        if self.nb_values == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_micros(self.min_micros)
+        }
+    }
+
+    /// Plus grande latence (0 si aucune)
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.max_micros)
+    }
+
+    /// Latence moyenne (0 si aucune)
+    pub fn mean(&self) -> Duration {
+        Duration::from_micros(self.sum_micros.checked_div(self.nb_values).unwrap_or(0))
+    }
+
+    /// Latence au percentile `percentile` (0 à 100) : plus grande valeur de l'intervalle qui
+    /// contient ce percentile (bornée par la plus grande latence)
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        if self.nb_values == 0 {
+            return Duration::ZERO;
+        }
+        // Rang (arrondi supérieur) de la valeur du percentile parmi les valeurs enregistrées
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let rank =
+            ((percentile.clamp(0.0, 100.0) / 100.0 * self.nb_values as f64).ceil() as u64).max(1);
+        let mut nb_values = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            nb_values += count;
+            if nb_values >= rank {
+                let micros = highest_value_of(index).min(self.max_micros);
+                return Duration::from_micros(micros.max(self.min_micros));
+            }
+        }
+        self.max()
+    }
+
+    /// Intervalles non vides de l'histogramme (plus grande valeur de l'intervalle et nombre
+    /// de latences)
+    pub fn buckets(&self) -> Vec<(Duration, u64)> {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(index, count)| (Duration::from_micros(highest_value_of(index)), *count))
+            .collect()
+    }
+
+    /// Remise à 0 de l'histogramme
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_of() {
+        for value in [
+            0,
+            1,
+            127,
+            128,
+            129,
+            255,
+            256,
+            1000,
+            123_456,
+            LATENCY_MAX_MICROS,
+        ] {
+            let index = index_of(value);
+            assert!(highest_value_of(index) >= value);
+            if index > 0 {
+                assert!(highest_value_of(index - 1) < value);
+            }
+        }
+        assert_eq!(index_of(127), 127);
+        assert_eq!(highest_value_of(index_of(129)), 129);
+        assert_eq!(
+            index_of(LATENCY_MAX_MICROS + 1),
+            index_of(LATENCY_MAX_MICROS)
+        );
+    }
+
+    #[test]
+    fn test_latency_histogram() {
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.to_string(), "count=0");
+        assert_eq!(histogram.percentile(99.0), Duration::ZERO);
+
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+        assert_eq!(histogram.count(), 1000);
+        assert_eq!(histogram.min(), Duration::from_micros(1));
+        assert_eq!(histogram.max(), Duration::from_micros(1000));
+        assert_eq!(histogram.mean(), Duration::from_micros(500));
+
+        // Précision relative meilleure que 1/64
+        for (percentile, expected) in [(50.0, 500.0), (90.0, 900.0), (99.0, 990.0)] {
+            let micros = histogram.percentile(percentile).as_secs_f64() * 1e6;
+            assert!((micros - expected).abs() <= expected / 64.0);
+        }
+        assert_eq!(histogram.percentile(100.0), Duration::from_micros(1000));
+        assert_eq!(
+            histogram.buckets().iter().map(|(_, n)| n).sum::<u64>(),
+            1000
+        );
+        assert!(histogram.to_string().starts_with("count=1000 min=1µs"));
+
+        histogram.clear();
+        assert_eq!(histogram.count(), 0);
+    }
+}
@@ -0,0 +1,121 @@
+//! Simulateur logiciel de l'ICOM d'une solution AFSEC+ ALMA
+//!
+//! Bibliothèque commune à l'exécutable `sim_icom` et aux bindings éventuels (Python, ...)
+//!
+//! * `t_data`: Formats et types de données génériques
+//! * `database`: Database de l'ICOM
+//! * `arg_parsing`: Analyse des arguments communs (adresses de registres, textes entre guillemets)
+//! * `afsec`: Communication avec l'AFSEC+ et codage des trames TLV
+//! * `server_modbus_tcp`: Serveur MODBUS/TCP pour accéder à la database
+//! * `watcher`: Surveillance des changements dans la database
+//! * `ffi`: Interface C pour l'encodage et le décodage des trames TLV
+//! * `timeline`: Marqueurs temporels dans les traces
+//! * `sim_clock`: Horloge simulée (synchronisée par l'AFSEC+)
+//! * `console`: Console de commandes sur l'entrée standard
+//! * `supervisor`: Supervision et redémarrage des tâches du simulateur
+//! * `sim_rng`: Générateur pseudo-aléatoire déterministe (option `--seed`)
+//! * `profiling`: Profilage (verrou de la database, allocations, notifications, option `--stats`)
+//! * `latency_histogram`: Histogramme des latences (temps de réponse de la liaison série)
+//! * `slew_rate`: Évolution des tags avec une rampe vers leur consigne (option `--ramp`)
+//! * `http_post`: Requêtes HTTP `POST` (avec délai max.) vers InfluxDB et les 'webhooks'
+//! * `influx`: Export des valeurs des tags vers InfluxDB (option `--influx-url`)
+//! * `webhook`: Notifications HTTP (JSON) vers l'orchestration des tests (option `--webhook`)
+//! * `error_budget`: Budget d'erreurs des tests de longue durée (option `--error-budget`)
+//! * `scripting`: Scripts Rhai des comportements du simulateur (feature `scripting`, option `--script`)
+//! * `freshness`: Supervision de la fraîcheur des tags (option `--max-age`)
+//! * `pulse`: Remise à 0 automatique des tags impulsion (option `--pulse`)
+//! * `load_profile`: Profils journaliers ou hebdomadaires des valeurs des tags (option `--profile`)
+//! * `zone_crc`: Publication périodique des CRC des zones de la database (option `--crc-period`)
+//! * `scenario`: Scénarios de test avec des séquences en parallèle (option `--scenario`)
+//! * `shadow`: Comparaison avec un ICOM de référence (option `--shadow`)
+//! * `federation`: Partage de plages de registres entre simulateurs en UDP multicast (option `--federation`)
+//! * `snmp`: Agent SNMP en lecture seule des diagnostics du simulateur (option `--snmp`)
+//! * `rt_thread`: Thread dédié (temps réel) à la communication avec l'AFSEC+ (option `--afsec-thread`)
+//! * `capture`: Capture déclenchée du trafic autour d'un événement (option `--capture-trigger`)
+//! * `log_rotation`: Rotation des transcriptions et des captures (signaux `SIGUSR1`/`SIGUSR2`)
+//! * `shutdown`: Arrêt sur ctrl+C avec sauvegarde du contexte AFSEC (option `--context-state`)
+//! * `test_summary`: Rapport de synthèse de fin de test en JSON ou Markdown (option `--summary`)
+//! * `config_push`: Transmission d'une configuration à l'AFSEC+ (commande `push` de la console)
+//! * `build_info`: Identification du build du simulateur (version, hash git, date du build)
+//! * `sim_handle`: Simulateur dans le processus courant pour les tests d'intégration (`SimIcom::spawn`)
+//!
+
+pub mod t_data;
+
+pub mod build_info;
+
+pub mod sim_rng;
+
+pub mod sim_clock;
+
+pub mod timeline;
+
+pub mod console;
+
+pub mod supervisor;
+
+pub mod profiling;
+
+pub mod latency_histogram;
+
+pub mod database;
+pub use database::Database;
+
+pub mod arg_parsing;
+
+pub mod watcher;
+
+pub mod slew_rate;
+
+pub mod zone_crc;
+
+pub mod freshness;
+
+pub mod pulse;
+
+pub mod randomizer;
+
+pub mod load_profile;
+
+pub mod http_post;
+
+pub mod influx;
+
+pub mod webhook;
+
+pub mod error_budget;
+
+#[cfg(feature = "scripting")]
+pub mod scripting;
+
+pub mod scenario;
+
+pub mod shadow;
+
+pub mod federation;
+
+pub mod snmp;
+
+pub mod rt_thread;
+
+pub mod shutdown;
+
+pub mod test_summary;
+
+pub mod capture;
+
+pub mod log_rotation;
+
+pub mod config_push;
+
+pub mod afsec;
+
+pub mod server_modbus_tcp;
+
+pub mod ffi;
+
+pub mod sim_handle;
+pub use sim_handle::{SimConfig, SimHandle, SimIcom};
+
+#[cfg(feature = "python")]
+mod python;
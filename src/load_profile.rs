@@ -0,0 +1,311 @@
+//! Process des profils journaliers ou hebdomadaires des valeurs de tags de la [`Database`]
+//!
+//! Pour reproduire sur une simulation de longue durée les variations réalistes d'une journée
+//! (consommations, températures, ...), un tag numérique peut suivre un profil défini par un
+//! fichier .csv (option `--profile <adresse>=<fichier>`). Chaque ligne du fichier associe une
+//! heure (`HH:MM` ou `HH:MM:SS`), éventuellement précédée d'un jour de la semaine (`lun` à `dim`)
+//! pour un profil hebdomadaire, à une valeur :
+//!
+//! ```text
+//! # Consommation journalière
+//! 00:00;120
+//! 07:30;450
+//! 19:00;600
+//! 23:00;150
+//! ```
+//!
+//! La valeur est interpolée linéairement entre 2 points (la période boucle du dernier point au
+//! premier) et écrite à chaque cycle selon l'horloge simulée (voir le module `sim_clock`),
+//! décalée de `--profile-utc-offset` minutes pour suivre l'heure locale.
+
+use std::sync::{Arc, Mutex};
+
+use crate::arg_parsing::parse_word_address;
+use crate::database::{IdUser, WordAddress};
+use crate::profiling::{lock_database, Subsystem};
+use crate::sim_clock;
+use crate::t_data::TFormat;
+use crate::Database;
+
+/// Temps de cycle (en millisecondes) de l'écriture des valeurs des profils
+pub const LOAD_PROFILE_CYCLE_MSECS: u64 = 1000;
+
+/// Nombre de secondes d'une journée
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Jours de la semaine (du lundi au dimanche) d'un profil hebdomadaire
+const WEEK_DAYS: [&str; 7] = ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"];
+
+/// Profil des valeurs d'un tag sur une journée ou une semaine
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadProfile {
+    /// Points du profil (secondes depuis le début de la période, valeur) triés par date
+    points: Vec<(u64, f64)>,
+
+    /// Indicateur d'un profil hebdomadaire (période d'une semaine débutant le lundi à 00:00)
+    is_weekly: bool,
+}
+
+/// Analyse d'une heure `HH:MM` ou `HH:MM:SS` (en secondes depuis 00:00)
+fn parse_time_of_day(s: &str) -> Option<u64> {
+    let fields: Vec<u64> = s
+        .split(':')
+        .map(|field| field.trim().parse::<u64>().ok())
+        .collect::<Option<_>>()?;
+    match fields[..] {
+        [hours, minutes] if hours < 24 && minutes < 60 => Some(3600 * hours + 60 * minutes),
+        [hours, minutes, secs] if hours < 24 && minutes < 60 && secs < 60 => {
+            Some(3600 * hours + 60 * minutes + secs)
+        }
+        _ => None,
+    }
+}
+
+impl LoadProfile {
+    /// Durée (en secondes) de la période du profil
+    fn period_secs(&self) -> u64 {
+        if self.is_weekly {
+            7 * SECS_PER_DAY
+        } else {
+            SECS_PER_DAY
+        }
+    }
+
+    /// Indicateur d'un profil hebdomadaire
+    pub fn is_weekly(&self) -> bool {
+        self.is_weekly
+    }
+
+    /// Nombre de points du profil
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Indique si le profil n'a aucun point
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Profil depuis le contenu d'un fichier .csv (lignes `[jour ]HH:MM[:SS];valeur`, lignes
+    /// vides et commentaires `#` ignorés)
+    /// # Errors
+    /// Message d'erreur si une ligne est incorrecte, si le profil n'a aucun point ou mélange
+    /// des lignes avec et sans jour
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut points = vec![];
+        let mut option_is_weekly = None;
+        for (num_line, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = || format!("Ligne {} incorrecte: '{line}'", num_line + 1);
+            let Some((date, value)) = line.split_once(';') else {
+                return Err(err());
+            };
+            let value = value.trim().parse::<f64>().map_err(|_| err())?;
+            let (option_day, time) = match date.trim().split_once(' ') {
+                Some((day, time)) => (Some(day), time),
+                None => (None, date),
+            };
+            let is_weekly = option_day.is_some();
+            if *option_is_weekly.get_or_insert(is_weekly) != is_weekly {
+                return Err(format!(
+                    "Ligne {}: profil journalier et hebdomadaire mélangés",
+                    num_line + 1
+                ));
+            }
+            let day_secs = match option_day {
+                Some(day) => {
+                    let day = day.trim().to_lowercase();
+                    let Some(num_day) = WEEK_DAYS.iter().position(|week_day| *week_day == day)
+                    else {
+                        return Err(err());
+                    };
+                    u64::try_from(num_day).unwrap_or(0) * SECS_PER_DAY
+                }
+                None => 0,
+            };
+            let time_secs = parse_time_of_day(time).ok_or_else(err)?;
+            points.push((day_secs + time_secs, value));
+        }
+        if points.is_empty() {
+            return Err("Profil sans aucun point".to_string());
+        }
+        points.sort_by_key(|(secs, _)| *secs);
+        Ok(Self {
+            points,
+            is_weekly: option_is_weekly.unwrap_or(false),
+        })
+    }
+
+    /// Profil depuis un fichier .csv
+    /// # Errors
+    /// Message d'erreur si le fichier ne peut pas être lu ou est incorrect
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(filename)
+            .map_err(|e| format!("Erreur lecture '{filename}': {e}"))?;
+        Self::from_text(&text).map_err(|msg| format!("'{filename}': {msg}"))
+    }
+
+    /// Valeur du profil à une date (en secondes depuis le 01/01/1970, heure locale)
+    #[allow(clippy::cast_precision_loss)]
+    pub fn value_at(&self, unix_secs: u64) -> f64 {
+        let period = self.period_secs();
+        // Le 01/01/1970 est un jeudi: décalage pour débuter la semaine le lundi
+        let secs = (unix_secs + 3 * SECS_PER_DAY) % period;
+        let next = self
+            .points
+            .partition_point(|(point_secs, _)| *point_secs <= secs);
+        let (prev_secs, prev_value) =
+            self.points[(next + self.points.len() - 1) % self.points.len()];
+        let (next_secs, next_value) = self.points[next % self.points.len()];
+        // Écarts modulo la période (bouclage du dernier point au premier)
+        let elapsed = (secs + period - prev_secs) % period;
+        let span = (next_secs + period - prev_secs) % period;
+        if span == 0 {
+            return prev_value;
+        }
+        prev_value + (elapsed as f64 / span as f64) * (next_value - prev_value)
+    }
+}
+
+/// Analyse d'une définition de profil `<adresse>=<fichier>` (adresse en hexa, `@0010`,
+/// `0x0010` ou `0010`) et lecture du fichier du profil
+/// # Errors
+/// Message d'erreur si la définition ou le fichier est incorrect
+pub fn parse_load_profile_spec(spec: &str) -> Result<(WordAddress, LoadProfile), String> {
+    let Some((address, filename)) = spec.split_once('=') else {
+        return Err(format!(
+            "Profil '{spec}' incorrect (attendu: <adresse>=<fichier>)"
+        ));
+    };
+    let address = address.trim();
+    let Some(word_address) = parse_word_address(address) else {
+        return Err(format!("Adresse du profil '{spec}' incorrecte"));
+    };
+    Ok((word_address, LoadProfile::from_file(filename.trim())?))
+}
+
+/// Écrit dans la [`Database`] les valeurs des profils à une date (en secondes depuis le
+/// 01/01/1970, heure locale)
+/// Retourne le nombre de tags modifiés
+pub fn apply_load_profiles(
+    db: &mut Database,
+    id_user: IdUser,
+    profiles: &[(WordAddress, LoadProfile)],
+    unix_secs: u64,
+) -> usize {
+    let mut nb_tags = 0;
+    for (word_address, profile) in profiles {
+        let Some(tag) = db.get_tag_from_word_address(*word_address).cloned() else {
+            continue;
+        };
+        let value = profile.value_at(unix_secs);
+        let value = match tag.t_format {
+            TFormat::F32 | TFormat::F64 => format!("{value}"),
+            _ => format!("{}", value.round()),
+        };
+        match db.set_value(id_user, &tag, &value) {
+            Ok(()) => nb_tags += 1,
+            Err(db_error) => println!("!!! Profil de {tag}: {db_error}"),
+        }
+    }
+    nb_tags
+}
+
+/// Routine d'un thread qui écrit périodiquement la valeur des tags avec un profil
+/// En paramètre, le temps de cycle entre chaque écriture (en millisecondes) et le décalage (en
+/// minutes) de l'heure locale par rapport à l'heure UTC
+pub async fn load_profile_process(
+    thread_db: Arc<Mutex<Database>>,
+    profiles: Vec<(WordAddress, LoadProfile)>,
+    cycle_in_msecs: u64,
+    utc_offset_minutes: i64,
+) {
+    // Obtient un id_user pour les opérations
+    let id_user =
+        lock_database(&thread_db, Subsystem::LoadProfile).get_id_user("Load profile", false);
+
+    loop {
+        let unix_secs = sim_clock::now()
+            .as_secs()
+            .saturating_add_signed(60 * utc_offset_minutes);
+        apply_load_profiles(
+            &mut lock_database(&thread_db, Subsystem::LoadProfile),
+            id_user,
+            &profiles,
+            unix_secs,
+        );
+
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(cycle_in_msecs)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{IdTag, Tag, ID_ANONYMOUS_USER};
+
+    /// Lundi 01/01/2024 00:00:00 UTC
+    const MONDAY: u64 = 1_704_067_200;
+
+    #[test]
+    fn test_daily_profile() {
+        let profile = LoadProfile::from_text("# Test\n06:00;100\n\n18:00;300\n").unwrap();
+        assert!(!profile.is_weekly());
+        assert_eq!(profile.len(), 2);
+        assert_eq!(profile.value_at(MONDAY + 6 * 3600), 100.0);
+        assert_eq!(profile.value_at(MONDAY + 12 * 3600), 200.0);
+        assert_eq!(profile.value_at(MONDAY + 18 * 3600), 300.0);
+        // Bouclage de 18:00 à 06:00 le lendemain
+        assert_eq!(profile.value_at(MONDAY), 200.0);
+        assert_eq!(profile.value_at(MONDAY + 3 * SECS_PER_DAY), 200.0);
+
+        assert_eq!(
+            LoadProfile::from_text("12:00:30;5")
+                .unwrap()
+                .value_at(MONDAY),
+            5.0
+        );
+        assert!(LoadProfile::from_text("").is_err());
+        assert!(LoadProfile::from_text("24:00;1").is_err());
+        assert!(LoadProfile::from_text("12:00").is_err());
+        assert!(LoadProfile::from_text("12:00;x").is_err());
+    }
+
+    #[test]
+    fn test_weekly_profile() {
+        let profile = LoadProfile::from_text("lun 00:00;10\nsam 00:00;10\nsam 12:00;0").unwrap();
+        assert!(profile.is_weekly());
+        assert_eq!(profile.value_at(MONDAY + 2 * SECS_PER_DAY), 10.0);
+        assert_eq!(profile.value_at(MONDAY + 5 * SECS_PER_DAY + 6 * 3600), 5.0);
+        // Bouclage du samedi 12:00 au lundi 00:00
+        assert_eq!(profile.value_at(MONDAY + 6 * SECS_PER_DAY + 6 * 3600), 5.0);
+        assert!(LoadProfile::from_text("lun 00:00;1\n12:00;2").is_err());
+        assert!(LoadProfile::from_text("xyz 00:00;1").is_err());
+    }
+
+    #[test]
+    fn test_apply_load_profiles() {
+        let mut db = Database::default();
+        let tag = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        };
+        db.add_tag(&tag);
+        let profile = LoadProfile::from_text("00:00;0\n12:00;101").unwrap();
+        let profiles = vec![(0x0010, profile.clone()), (0x0020, profile)];
+        assert_eq!(
+            apply_load_profiles(&mut db, ID_ANONYMOUS_USER, &profiles, MONDAY + 3 * 3600),
+            1
+        );
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 25);
+        assert!(parse_load_profile_spec("@0010").is_err());
+        assert!(parse_load_profile_spec("@0010=/fichier/absent.csv").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+//! Rotation des fichiers de transcription et de capture sans redémarrage du simulateur
+//!
+//! Un essai de plusieurs jours produit des transcriptions des échanges avec l'AFSEC+ (option
+//! `--transcript`) de plusieurs Go. Pour les archiver au fil de l'eau, la rotation est demandée
+//! par un signal :
+//!
+//! * `SIGUSR1` : chaque transcription en cours se poursuit dans un nouveau fichier
+//!   `afsec_session_<secs>.txt` (à la trame ou au cycle de surveillance suivant, voir
+//!   `SessionTranscript`)
+//! * `SIGUSR2` : la capture déclenchée en cours (option `--capture-trigger`) est écrite sans
+//!   attendre la fin de sa fenêtre (voir le module `capture`)
+//!
+//! Après la rotation, les anciens fichiers ne sont plus modifiés et peuvent être archivés ou
+//! supprimés. Les signaux n'existent que sous Unix : la commande `rotate` de la console fait les
+//! 2 rotations sur toutes les plateformes.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::capture;
+
+/// Numéro de la dernière rotation des transcriptions demandée
+static TRANSCRIPT_ROTATION: AtomicU32 = AtomicU32::new(0);
+
+/// Demande la rotation des transcriptions en cours
+pub fn request_transcript_rotation() {
+    TRANSCRIPT_ROTATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Numéro de la dernière rotation des transcriptions demandée
+pub fn transcript_rotation() -> u32 {
+    TRANSCRIPT_ROTATION.load(Ordering::Relaxed)
+}
+
+/// Rotation des transcriptions et des captures (commande `rotate` de la console)
+pub fn rotate_all() {
+    request_transcript_rotation();
+    capture::rotate();
+}
+
+/// Routine d'un thread qui attend les signaux `SIGUSR1` (rotation des transcriptions) et
+/// `SIGUSR2` (rotation des captures)
+#[cfg(unix)]
+pub async fn log_rotation_process() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (mut sigusr1, mut sigusr2) = match (
+        signal(SignalKind::user_defined1()),
+        signal(SignalKind::user_defined2()),
+    ) {
+        (Ok(sigusr1), Ok(sigusr2)) => (sigusr1, sigusr2),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("Rotation: !!! SIGUSR1/SIGUSR2 non interceptés: {e}");
+            return;
+        }
+    };
+    loop {
+        tokio::select! {
+            Some(()) = sigusr1.recv() => {
+                println!("Rotation: SIGUSR1: Nouveaux fichiers de transcription");
+                request_transcript_rotation();
+            }
+            Some(()) = sigusr2.recv() => {
+                println!("Rotation: SIGUSR2: Écriture de la capture en cours");
+                capture::rotate();
+            }
+            else => return,
+        }
+    }
+}
+
+/// Pas de signaux `SIGUSR1` et `SIGUSR2` hors Unix (voir la commande `rotate` de la console)
+#[cfg(not(unix))]
+pub async fn log_rotation_process() {}
@@ -1,38 +1,378 @@
 //! Simulateur logiciel de l'ICOM d'une solution AFSEC+ ALMA
 //!
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-
-use tokio::net::TcpListener;
-use tokio_modbus::server::tcp::{accept_tcp_connection, Server};
+use std::time::Duration;
 
 mod command_args;
-use command_args::CommandArgs;
-
-mod t_data;
-
-mod database;
-use database::Database;
+use command_args::{Command, CommandArgs};
 
-mod watcher;
-use watcher::database_watcher_process;
+use sim_icom::afsec::{
+    database_afsec_process, write_test_vectors, AliveAnswer, ContextState, DatabaseAfsecComm,
+    FirmwareProfile, FrameInjector, JunkGuard, LinkWatchdog, MalformedAnswer, ModePolicy,
+    ResponseDelay, StatsStore,
+};
+use sim_icom::build_info;
+use sim_icom::capture::{capture_process, CaptureBuffer, CaptureTrigger};
+use sim_icom::console::console_process;
+use sim_icom::database::{
+    parse_max_age_spec, parse_owner_spec, parse_pulse_spec, parse_string_padding_spec,
+    parse_tag_history_spec, AnonymousWritePolicy, CsvConfig, CsvParseMode, IdTag, MapReportFormat,
+    OwnerPolicy, RandomFilter, SavedState, SimIdentity, TagMigration, WordAddress, ZoneClone,
+    ID_ANONYMOUS_USER, STATE_MAGIC,
+};
+use sim_icom::error_budget::{error_budget_process, ErrorBudget, ERROR_BUDGETS_MAX};
+use sim_icom::federation::{federation_process, parse_federation_group};
+use sim_icom::freshness::{freshness_process, FRESHNESS_CYCLE_MSECS};
+use sim_icom::influx::{influx_process, InfluxEndpoint, InfluxGroup};
+use sim_icom::load_profile::{
+    load_profile_process, parse_load_profile_spec, LOAD_PROFILE_CYCLE_MSECS,
+};
+use sim_icom::log_rotation::log_rotation_process;
+use sim_icom::profiling::stats_process;
+#[cfg(feature = "profiling")]
+use sim_icom::profiling::CountingAllocator;
+use sim_icom::pulse::{pulse_process, PULSE_CYCLE_MSECS};
+use sim_icom::randomizer::randomizer_process;
+use sim_icom::rt_thread::{spawn_dedicated, RtThreadConfig};
+use sim_icom::scenario::{scenario_process, Scenario};
+use sim_icom::server_modbus_tcp::{
+    modbus_server_process, modbus_udp_server_process, parse_bind_address, parse_bind_addresses,
+    ClientFilter, ModbusServerConfig, UdpFraming, UndefinedWritePolicy,
+};
+use sim_icom::shadow::{parse_shadow_range, shadow_process, ShadowRange};
+use sim_icom::shutdown::shutdown_process;
+use sim_icom::sim_rng::SimRng;
+use sim_icom::slew_rate::{parse_slew_rate_spec, slew_rate_process, SLEW_RATE_CYCLE_MSECS};
+use sim_icom::snmp::{snmp_agent_process, DEFAULT_SNMP_PORT};
+use sim_icom::supervisor::{
+    supervise, HEALTH_AFSEC, HEALTH_MODBUS, HEALTH_MODBUS_UDP, HEALTH_WATCHER,
+};
+use sim_icom::test_summary::{enable_summary, is_summary_enabled, SummaryFormat};
+use sim_icom::watcher::database_watcher_process;
+use sim_icom::webhook::{webhook_process, WebhookRule};
+use sim_icom::zone_crc::zone_crc_process;
+use sim_icom::Database;
 
-mod afsec;
-use afsec::{database_afsec_process, DatabaseAfsecComm};
-
-mod server_modbus_tcp;
-use server_modbus_tcp::DatabaseService;
+/// Allocateur global pour le décompte des allocations par sous-système (feature `profiling`,
+/// option `--stats`)
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static GLOBAL_ALLOCATOR: CountingAllocator = CountingAllocator;
 
 /// Point d'entrée du simulateur ICOM
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let command_args = CommandArgs::new();
 
+    // Rapport du plan d'adressage (avant la bannière: le rapport peut être écrit sur la sortie
+    // standard)
+    if let Some(Command::MapReport {
+        filename,
+        csv_columns,
+        format,
+        output,
+    }) = &command_args.command
+    {
+        if let Err(msg) =
+            write_map_report(filename, csv_columns.as_deref(), format, output.as_deref())
+        {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Migration vers un nouveau plan des tags (la correspondance peut être écrite sur la sortie
+    // standard)
+    if let Some(Command::MigrateTags {
+        old_filename,
+        new_filename,
+        csv_columns,
+        mapping,
+        input,
+        output,
+    }) = &command_args.command
+    {
+        if let Err(msg) = migrate_tags(
+            old_filename,
+            new_filename,
+            csv_columns.as_deref(),
+            mapping.as_deref(),
+            input.as_deref().zip(output.as_deref()),
+        ) {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    println!("{}", build_info::banner());
+
+    // Commande hors simulation
+    if let Some(Command::GenVectors { filename }) = &command_args.command {
+        match write_test_vectors(Path::new(filename)) {
+            Ok(nb_vectors) => {
+                println!("{nb_vectors} vecteurs de test écrits dans '{filename}'");
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("!!! Erreur écriture '{filename}': {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
     // Initialisation de la database
-    let mut db: Database = Database::from_file(&command_args.filename);
+    let mut csv_config = match &command_args.csv_columns {
+        Some(spec) => match CsvConfig::from_spec(spec) {
+            Ok(csv_config) => csv_config,
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        },
+        None => CsvConfig::default(),
+    };
+    if command_args.csv_lenient {
+        csv_config.mode = CsvParseMode::Lenient;
+        csv_config.report_filename = command_args.csv_report.clone();
+    }
+    let mut db: Database = Database::from_file_with_config(&command_args.filename, &csv_config);
+
+    // Clonage optionnel des tags de zones (équipements répétés)
+    for spec in &command_args.clone_zone {
+        match ZoneClone::from_str(spec).and_then(|zone_clone| {
+            db.clone_zone(&zone_clone)
+                .map(|nb_clones| (zone_clone, nb_clones))
+        }) {
+            Ok((zone_clone, nb_clones)) => println!("Clone {zone_clone}: {nb_clones} tag(s)"),
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        }
+    }
+    db.add_sim_tags();
+
+    // Contrôle de cohérence des tables de la database
+    for violation in db.check_invariants() {
+        eprintln!("!!! Database: {violation}");
+    }
+
+    // Restauration optionnelle d'un état sauvegardé des valeurs des tags
+    if let Some(state) = &command_args.state {
+        match db.restore_state_file(ID_ANONYMOUS_USER, state) {
+            Ok(state_restore) => println!("State '{state}': {state_restore}"),
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Identité optionnelle du simulateur (numéro de série et adresse MAC)
+    let identity = match (&command_args.identity, &command_args.serial_number) {
+        (Some(identity), option_serial_number) => Some(SimIdentity::load_or_create(
+            identity,
+            option_serial_number.as_deref(),
+            command_args.seed.unwrap_or_else(SimRng::random_seed),
+        )),
+        (None, Some(serial_number)) => Some(SimIdentity::with_serial_number(serial_number)),
+        (None, None) => None,
+    };
+    match identity {
+        Some(Ok(identity)) => {
+            println!("Simulator identity: {identity}");
+            db.set_sim_identity(identity);
+        }
+        Some(Err(msg)) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+        None => (),
+    }
+
+    // La carte de l'activité en écriture ne compte que les écritures de la campagne de tests
+    db.clear_write_counts();
+
+    // Politique des écritures anonymes (appliquée après l'initialisation de la database : les
+    // valeurs par défaut, les clones des zones et l'état restauré sont des écritures anonymes)
+    let anonymous_write_policy = match &command_args.anonymous_writes {
+        Some(spec) => match AnonymousWritePolicy::from_str(spec) {
+            Ok(policy) => policy,
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        },
+        None => AnonymousWritePolicy::feature_default(),
+    };
+    db.set_anonymous_write_policy(anonymous_write_policy);
+
+    // Export optionnel de la database en mémoire partagée
+    #[cfg(feature = "memmap")]
+    if let Some(shm) = &command_args.shm {
+        db.export_shared_memory(std::path::Path::new(shm))?;
+        println!("Database exported to shared memory file `{shm}`");
+    }
+
+    // Cache optionnel des lectures MODBUS/TCP
+    if command_args.modbus_cache {
+        db.enable_read_cache();
+        println!("MODBUS/TCP read cache enabled");
+    }
+
+    // Rampes des tags numériques
+    apply_tag_specs(
+        &mut db,
+        &command_args.ramp,
+        parse_slew_rate_spec,
+        |db, id_tag, rate| db.set_slew_rate(ID_ANONYMOUS_USER, id_tag, rate),
+    );
+
+    // Historiques des valeurs des tags
+    apply_tag_specs(
+        &mut db,
+        &command_args.history,
+        parse_tag_history_spec,
+        |db, id_tag, capacity| db.set_tag_history(ID_ANONYMOUS_USER, id_tag, capacity),
+    );
+
+    // Âges max. des valeurs des tags supervisés
+    apply_tag_specs(
+        &mut db,
+        &command_args.max_age,
+        parse_max_age_spec,
+        |db, id_tag, max_age| db.set_max_age(id_tag, Some(max_age)),
+    );
+
+    // Profils journaliers ou hebdomadaires des tags
+    let mut load_profiles = vec![];
+    for spec in &command_args.profile {
+        let result = parse_load_profile_spec(spec).and_then(|(word_address, profile)| {
+            match db.get_tag_from_word_address(word_address) {
+                Some(_) => Ok((word_address, profile)),
+                None => Err(format!("Pas de tag défini à l'adresse {word_address:#06X}")),
+            }
+        });
+        match result {
+            Ok(load_profile) => load_profiles.push(load_profile),
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Scénario de test
+    let option_scenario = command_args
+        .scenario
+        .as_ref()
+        .map(|filename| match Scenario::from_file(filename) {
+            Ok(scenario) => scenario,
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        });
+
+    // Tags impulsion remis à 0 automatiquement
+    apply_tag_specs(
+        &mut db,
+        &command_args.pulse,
+        parse_pulse_spec,
+        |db, id_tag, duration| db.set_pulse(id_tag, Some(duration)),
+    );
+
+    // Propriétaires des tags et politique des écritures par une autre source
+    match OwnerPolicy::from_str(&command_args.owner_policy) {
+        Ok(policy) => db.set_owner_policy(policy),
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    }
+    apply_tag_specs(
+        &mut db,
+        &command_args.owner,
+        parse_owner_spec,
+        |db, id_tag, owner| db.set_tag_owner(id_tag, Some(owner)),
+    );
+
+    // Compléments des chaînes de caractères écrites partiellement
+    apply_tag_specs(
+        &mut db,
+        &command_args.string_padding,
+        parse_string_padding_spec,
+        |db, id_tag, padding| db.set_string_padding(id_tag, Some(padding)),
+    );
 
-    // Extrait un id_user pour le serveur MODBUS/TCP
-    let id_user_tcp_server = db.get_id_user("Server MODBUS/TCP", false);
+    // Sélection des tags soumis aux valeurs aléatoires
+    let random_filter = RandomFilter {
+        groups: command_args.randomize_group.clone(),
+        zones: command_args.randomize_zone.clone(),
+    };
+    if let Err(msg) = db.check_random_filter(&random_filter) {
+        eprintln!("!!! {msg}");
+        std::process::exit(1);
+    }
+
+    // Plages de registres comparées avec un ICOM de référence
+    let shadow_ranges = match command_args
+        .shadow_range
+        .iter()
+        .map(|spec| parse_shadow_range(spec))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(shadow_ranges) => shadow_ranges,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+    if command_args.shadow.is_some() && shadow_ranges.is_empty() {
+        eprintln!("!!! Option '--shadow' sans plage de registres '--shadow-range'");
+        std::process::exit(1);
+    }
+
+    // Partage de plages de registres avec les autres instances du simulateur
+    let federation = match parse_federation_args(&command_args) {
+        Ok(federation) => federation,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+
+    // Rapport de fin de test
+    if let Some(filename) = &command_args.summary {
+        match SummaryFormat::from_str(&command_args.summary_format) {
+            Ok(format) => enable_summary(filename, format),
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Agent SNMP des diagnostics du simulateur
+    let snmp_addr =
+        command_args
+            .snmp
+            .as_ref()
+            .map(|spec| match parse_bind_address(spec, DEFAULT_SNMP_PORT) {
+                Ok(socket_addr) => socket_addr,
+                Err(msg) => {
+                    eprintln!("!!! {msg}");
+                    std::process::exit(1);
+                }
+            });
+
+    // Capture des dernières trames inexploitables reçues de l'AFSEC+
+    db.set_junk_capture_capacity(command_args.junk_capture);
 
     // Niveau de debug pour les traces
     let debug_level = match command_args.debug {
@@ -47,53 +387,503 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    // Profil du firmware ICOM émulé
+    let firmware_profile = match command_args
+        .firmware
+        .as_deref()
+        .map(FirmwareProfile::from_file)
+        .transpose()
+    {
+        Ok(option_firmware_profile) => option_firmware_profile.unwrap_or_default(),
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+    println!("Firmware profile: {firmware_profile}");
+
+    // Réponse à AF_ALIVE imposée
+    let option_alive_answer = match command_args
+        .alive_answer
+        .as_deref()
+        .map(AliveAnswer::from_str)
+    {
+        None => None,
+        Some(Ok(alive_answer)) => {
+            println!("AF_ALIVE answer: {alive_answer}");
+            Some(alive_answer)
+        }
+        Some(Err(msg)) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+
+    // Réponse imposée aux trames inexploitables
+    let option_malformed_answer = match command_args
+        .malformed_answer
+        .as_deref()
+        .map(MalformedAnswer::from_str)
+    {
+        None => None,
+        Some(Ok(malformed_answer)) => {
+            println!("Malformed frame answer: {malformed_answer}");
+            Some(malformed_answer)
+        }
+        Some(Err(msg)) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+
+    // Délai de réponse aux trames de l'AFSEC+
+    let response_delay = match ResponseDelay::from_str(&command_args.response_delay) {
+        Ok(response_delay) => response_delay,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+    println!("AFSEC response delay: {response_delay}");
+
+    // Refus de conversations selon le mode de fonctionnement de l'AFSEC+
+    let mode_policy = match ModePolicy::from_rules(&command_args.mode_refuse) {
+        Ok(mode_policy) => mode_policy,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+    for refusal in mode_policy.refusals() {
+        println!("Mode refusal: {refusal}");
+    }
+
+    // Export vers InfluxDB
+    let influx = match parse_influx_args(&command_args) {
+        Ok(influx) => influx,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+
+    // Notifications HTTP des modifications des tags
+    let webhook_rules = match command_args
+        .webhook
+        .iter()
+        .map(|spec| WebhookRule::from_str(spec))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(webhook_rules) => webhook_rules,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+
+    // Budgets d'erreurs
+    let error_budgets = match parse_error_budget_args(&command_args) {
+        Ok(error_budgets) => error_budgets,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+
+    // Capture déclenchée du trafic
+    let capture_triggers = match command_args
+        .capture_trigger
+        .iter()
+        .map(|spec| CaptureTrigger::from_str(spec))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(capture_triggers) => capture_triggers,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+    if !capture_triggers.is_empty() {
+        for capture_trigger in &capture_triggers {
+            println!(
+                "Capture trigger: {capture_trigger} (window {} secs)",
+                command_args.capture_window
+            );
+        }
+        sim_icom::capture::configure(CaptureBuffer::new(
+            capture_triggers,
+            Duration::from_secs(command_args.capture_window),
+            Path::new(&command_args.capture_dir),
+        ));
+        tokio::spawn(capture_process());
+    }
+
+    // Graine pour toutes les sources aléatoires de la simulation
+    let seed = command_args.seed.unwrap_or_else(SimRng::random_seed);
+    println!("Simulation seed: {seed} (rejouer avec --seed {seed})");
+    let rng = SimRng::new(seed);
+
     // Créer la database partagée mutable
     let shared_db = Arc::new(Mutex::new(db));
 
-    // Cloner la référence à la database partagée le `watcher`
-    let db_watcher = Arc::clone(&shared_db);
+    // Script Rhai optionnel
+    #[cfg(feature = "scripting")]
+    let option_script = match command_args.script.as_deref().map(|path| {
+        sim_icom::scripting::ScriptHost::load(path, Arc::clone(&shared_db)).map(Arc::new)
+    }) {
+        None => None,
+        Some(Ok(script)) => {
+            tokio::spawn(sim_icom::scripting::script_process(Arc::clone(&script)));
+            Some(script)
+        }
+        Some(Err(msg)) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    };
+
+    // Créer le watcher (supervisé)
+    let watcher_cycle = command_args.watcher;
+    let handle_watcher = tokio::spawn(supervise(
+        "Watcher",
+        HEALTH_WATCHER,
+        Arc::clone(&shared_db),
+        {
+            // Cloner la référence à la database partagée le `watcher`
+            let db_watcher = Arc::clone(&shared_db);
+            move || {
+                let db_watcher = Arc::clone(&db_watcher);
+                async move {
+                    database_watcher_process(db_watcher, watcher_cycle, true).await;
+                    Ok(())
+                }
+            }
+        },
+    ));
+
+    // Process communication avec l'AFSEC+ sur le port série (supervisé)
+    let port_name = command_args.port_name.unwrap_or_default(); // Need 'copy'
+    let standby_port = command_args.standby_port;
+    let rng_afsec = rng.fork("afsec");
+    let junk_guard = JunkGuard::new(
+        command_args.junk_max_rate,
+        Duration::from_millis(command_args.junk_silence),
+    );
+    let conversation_timeout = Duration::from_millis(command_args.conversation_timeout);
+    let link_watchdog = LinkWatchdog::new(
+        command_args.max_retries,
+        Duration::from_millis(command_args.retry_timeout),
+        Duration::from_secs(command_args.probe_period),
+    );
+    let option_transcript_dir = command_args.transcript.map(PathBuf::from);
+
+    // Contexte des conversations avec l'AFSEC+ sauvegardé lors de l'arrêt précédent (restauré
+    // une seule fois, pas lors d'un redémarrage par la supervision)
+    let option_context_file = command_args.context_state.clone();
+    let option_context_state = match &option_context_file {
+        Some(context_file) if Path::new(context_file).exists() => {
+            match ContextState::from_file(context_file) {
+                Ok(context_state) => Some(context_state),
+                Err(msg) => {
+                    eprintln!("!!! {msg}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+    let context_restore = Arc::new(Mutex::new(option_context_state));
 
-    // Créer le watcher
-    let handle_watcher = tokio::spawn(async move {
-        database_watcher_process(db_watcher, command_args.watcher, true).await;
+    // Compteurs des conversations avec l'AFSEC+ conservés d'un démarrage à l'autre (fichier lu
+    // une seule fois, les redémarrages par la supervision reprennent les compteurs à jour)
+    let option_stats_store = command_args.protocol_stats.as_deref().map(|filename| {
+        let period = Duration::from_secs(command_args.protocol_stats_period.max(1));
+        match StatsStore::load(filename, period, std::time::Instant::now()) {
+            Ok(stats_store) => {
+                println!(
+                    "Protocol stats loaded from '{filename}' ({})",
+                    stats_store.stats()
+                );
+                stats_store
+            }
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        }
+    });
+    let afsec_thread = (command_args.afsec_thread
+        || command_args.afsec_priority.is_some()
+        || command_args.afsec_core.is_some())
+    .then(|| {
+        RtThreadConfig::new(command_args.afsec_priority, command_args.afsec_core).unwrap_or_else(
+            |msg| {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            },
+        )
+    });
+    let afsec_task = supervise("AFSEC Comm", HEALTH_AFSEC, Arc::clone(&shared_db), {
+        // Cloner la référence à la database partagée pour la communication avec l'AFSEC+
+        let db_afsec = Arc::clone(&shared_db);
+        move || {
+            let db_afsec = Arc::clone(&db_afsec);
+            let port_name = port_name.clone();
+            let standby_port = standby_port.clone();
+            let rng_afsec = rng_afsec.clone();
+            let junk_guard = junk_guard.clone();
+            let link_watchdog = link_watchdog.clone();
+            let mode_policy = mode_policy.clone();
+            let option_transcript_dir = option_transcript_dir.clone();
+            let option_context_file = option_context_file.clone();
+            let option_context_state = context_restore.lock().unwrap().take();
+            let option_stats_store = option_stats_store.clone();
+            #[cfg(feature = "scripting")]
+            let option_script = option_script.clone();
+            async move {
+                let mut afsec_service = DatabaseAfsecComm::new(db_afsec, port_name, debug_level)
+                    .with_rng(rng_afsec)
+                    .with_standby_port(standby_port)
+                    .with_firmware_profile(firmware_profile)
+                    .with_alive_answer(option_alive_answer)
+                    .with_malformed_answer(option_malformed_answer)
+                    .with_junk_guard(junk_guard)
+                    .with_mode_policy(mode_policy)
+                    .with_conversation_timeout(conversation_timeout)
+                    .with_response_delay(response_delay)
+                    .with_link_watchdog(link_watchdog)
+                    .with_transcript_dir(option_transcript_dir)
+                    .with_context_file(option_context_file)
+                    .with_context_state(option_context_state)
+                    .with_stats_store(option_stats_store);
+                #[cfg(feature = "scripting")]
+                {
+                    afsec_service = afsec_service.with_script(option_script);
+                }
+                database_afsec_process(&mut afsec_service).await;
+                Ok(())
+            }
+        }
     });
+    let handle_afsec = match afsec_thread {
+        Some(rt_thread_config) => spawn_dedicated("AFSEC Comm", rt_thread_config, afsec_task),
+        None => tokio::spawn(afsec_task),
+    };
+
+    // Évolution des tags avec une rampe vers leur consigne
+    tokio::spawn(slew_rate_process(
+        Arc::clone(&shared_db),
+        SLEW_RATE_CYCLE_MSECS,
+    ));
 
-    // Cloner la référence à la database partagée pour la communication avec l'AFSEC+
-    let db_afsec = Arc::clone(&shared_db);
+    // Supervision de la fraîcheur des tags
+    tokio::spawn(freshness_process(
+        Arc::clone(&shared_db),
+        FRESHNESS_CYCLE_MSECS,
+    ));
 
-    // Process communication avec l'AFSEC+ sur le port série
-    let port_name = command_args.port_name; // Need 'copy'
-    let handle_afsec = tokio::spawn(async move {
-        database_afsec_process(&mut DatabaseAfsecComm::new(
-            db_afsec,
-            port_name,
+    // Remise à 0 des tags impulsion
+    tokio::spawn(pulse_process(Arc::clone(&shared_db), PULSE_CYCLE_MSECS));
+
+    // Valeurs aléatoires des tags modifiables
+    if let Some(period) = command_args.randomize {
+        tokio::spawn(randomizer_process(
+            Arc::clone(&shared_db),
+            period.max(1),
+            random_filter,
+            rng.fork("randomizer"),
             debug_level,
-        ))
-        .await;
-    });
+        ));
+    }
 
-    // Serveur MODBUS
-    let socket_addr: SocketAddr = format!("0.0.0.0:{}", command_args.port).parse().unwrap();
-
-    println!("Starting up server on {socket_addr}");
-    let listener = TcpListener::bind(socket_addr).await?;
-    let server = Server::new(listener);
-    let new_service = |_socket_addr| {
-        let thread_db = Arc::clone(&shared_db);
-        Ok(Some(DatabaseService::new(
-            thread_db,
-            id_user_tcp_server,
+    // Profils journaliers ou hebdomadaires des tags
+    if !load_profiles.is_empty() {
+        tokio::spawn(load_profile_process(
+            Arc::clone(&shared_db),
+            load_profiles,
+            LOAD_PROFILE_CYCLE_MSECS,
+            command_args.profile_utc_offset,
+        ));
+    }
+
+    // Exécution du scénario de test
+    if let Some(scenario) = option_scenario {
+        tokio::spawn(scenario_process(
+            Arc::clone(&shared_db),
+            scenario,
+            command_args.scenario_report.clone(),
+            command_args.scenario_exit,
+        ));
+    }
+
+    // Comparaison avec un ICOM de référence
+    if let Some(reference) = command_args.shadow.clone() {
+        tokio::spawn(shadow_process(
+            Arc::clone(&shared_db),
+            reference,
+            shadow_ranges,
+            command_args.shadow_period,
+        ));
+    }
+
+    // Partage de plages de registres avec les autres instances du simulateur
+    if let Some((group, interface, federation_ranges, accept_ranges)) = federation {
+        tokio::spawn(federation_process(
+            Arc::clone(&shared_db),
+            group,
+            interface,
+            federation_ranges,
+            accept_ranges,
+            command_args.federation_period,
+        ));
+    }
+
+    // Agent SNMP des diagnostics du simulateur
+    if let Some(socket_addr) = snmp_addr {
+        tokio::spawn(snmp_agent_process(
+            Arc::clone(&shared_db),
+            socket_addr,
+            command_args.snmp_community.clone(),
             debug_level,
-        )))
-    };
-    let on_connected = |stream, socket_addr| async move {
-        accept_tcp_connection(stream, socket_addr, new_service)
+        ));
+    }
+
+    // Publication périodique des CRC des zones
+    tokio::spawn(zone_crc_process(
+        Arc::clone(&shared_db),
+        command_args.crc_period,
+    ));
+
+    // Export vers InfluxDB
+    if let Some((endpoint, groups)) = influx {
+        tokio::spawn(influx_process(Arc::clone(&shared_db), endpoint, groups));
+    }
+
+    // Notifications HTTP des modifications des tags
+    tokio::spawn(webhook_process(Arc::clone(&shared_db), webhook_rules));
+
+    // Surveillance des budgets d'erreurs
+    tokio::spawn(error_budget_process(Arc::clone(&shared_db), error_budgets));
+
+    // Trace périodique des statistiques de profilage
+    tokio::spawn(stats_process(Arc::clone(&shared_db), command_args.stats));
+
+    // Arrêt sur ctrl+C (ou SIGTERM) avec sauvegarde du contexte et des compteurs des conversations avec
+    // l'AFSEC+ et écriture du rapport de fin de test
+    let is_context_saved =
+        command_args.context_state.is_some() || command_args.protocol_stats.is_some();
+    if is_context_saved || is_summary_enabled() {
+        tokio::spawn(shutdown_process(Arc::clone(&shared_db), is_context_saved));
+    }
+
+    // Rotation des fichiers de transcription et de capture sur SIGUSR1/SIGUSR2
+    tokio::spawn(log_rotation_process());
+
+    // Console de commandes sur l'entrée standard
+    let frame_injector = FrameInjector::new(Arc::clone(&shared_db), debug_level, firmware_profile)
+        .with_alive_answer(option_alive_answer)
+        .with_malformed_answer(option_malformed_answer);
+    tokio::spawn(console_process(Arc::clone(&shared_db), frame_injector));
+
+    // Serveur MODBUS (supervisé)
+    let socket_addrs = match u16::try_from(command_args.port)
+        .map_err(|_| format!("Port MODBUS/TCP {} invalide", command_args.port))
+        .and_then(|port| parse_bind_addresses(&command_args.bind, port))
+    {
+        Ok(socket_addrs) => socket_addrs,
+        Err(msg) => {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
     };
-    let on_process_error = |err| {
-        eprintln!("{err}");
+    let undefined_write_policy =
+        match UndefinedWritePolicy::from_str(&command_args.modbus_undefined_writes) {
+            Ok(undefined_write_policy) => undefined_write_policy,
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        };
+    println!("MODBUS/TCP writes to undefined addresses: {undefined_write_policy}");
+    let client_filter =
+        match ClientFilter::from_specs(&command_args.modbus_allow, &command_args.modbus_deny) {
+            Ok(client_filter) => client_filter,
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        };
+    if !client_filter.is_empty() {
+        println!("MODBUS/TCP clients: {client_filter}");
+    }
+    let modbus_server_config = ModbusServerConfig {
+        socket_addrs,
+        debug_level,
+        max_pipelining: command_args.modbus_max_pipelining,
+        keepalive_secs: command_args.modbus_keepalive,
+        undefined_write_policy,
+        max_concurrency: command_args.modbus_max_concurrency,
+        is_broadcast: command_args.modbus_broadcast,
+        client_filter,
     };
+
+    // Serveur MODBUS/UDP optionnel (supervisé) avec les réglages du serveur MODBUS/TCP
+    if let Some(spec) = &command_args.modbus_udp {
+        let result_udp = u16::try_from(command_args.port)
+            .map_err(|_| format!("Port MODBUS/UDP {} invalide", command_args.port))
+            .and_then(|port| parse_bind_address(spec, port))
+            .and_then(|socket_addr| {
+                UdpFraming::from_str(&command_args.modbus_udp_framing)
+                    .map(|framing| (socket_addr, framing))
+            });
+        let (socket_addr, framing) = match result_udp {
+            Ok(udp) => udp,
+            Err(msg) => {
+                eprintln!("!!! {msg}");
+                std::process::exit(1);
+            }
+        };
+        let db_modbus_udp = Arc::clone(&shared_db);
+        let modbus_server_config = modbus_server_config.clone();
+        tokio::spawn(supervise(
+            "Server MODBUS/UDP",
+            HEALTH_MODBUS_UDP,
+            Arc::clone(&shared_db),
+            move || {
+                let db_modbus_udp = Arc::clone(&db_modbus_udp);
+                let modbus_server_config = modbus_server_config.clone();
+                async move {
+                    modbus_udp_server_process(
+                        db_modbus_udp,
+                        socket_addr,
+                        framing,
+                        &modbus_server_config,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())
+                }
+            },
+        ));
+    }
     println!("[Note: Entrer ctrl+C pour stopper l'application]");
-    server.serve(&on_connected, on_process_error).await?;
+    supervise(
+        "Server MODBUS/TCP",
+        HEALTH_MODBUS,
+        Arc::clone(&shared_db),
+        {
+            let db_modbus = Arc::clone(&shared_db);
+            move || {
+                let db_modbus = Arc::clone(&db_modbus);
+                let modbus_server_config = modbus_server_config.clone();
+                async move {
+                    modbus_server_process(db_modbus, &modbus_server_config)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+            }
+        },
+    )
+    .await;
 
     // Attendre que les threads se terminent
     handle_watcher.await.unwrap();
@@ -101,3 +891,169 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Application aux tags des définitions `<adresse>=<paramètre>` d'une option répétable de la
+/// ligne de commande (`--ramp`, `--history`, ...)
+/// Arrêt du simulateur si une définition est incorrecte ou désigne une adresse sans tag
+fn apply_tag_specs<T>(
+    db: &mut Database,
+    specs: &[String],
+    parse_spec: fn(&str) -> Result<(WordAddress, T), String>,
+    apply: impl Fn(&mut Database, IdTag, T) -> Result<(), String>,
+) {
+    for spec in specs {
+        let result = parse_spec(spec).and_then(|(word_address, param)| {
+            match db.get_tag_from_word_address(word_address) {
+                Some(tag) => {
+                    let id_tag = tag.id_tag;
+                    apply(db, id_tag, param)
+                }
+                None => Err(format!("Pas de tag défini à l'adresse {word_address:#06X}")),
+            }
+        });
+        if let Err(msg) = result {
+            eprintln!("!!! {msg}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Rapport du plan d'adressage d'un fichier .csv (sous-commande `map-report`) écrit dans un
+/// fichier ou sur la sortie standard
+fn write_map_report(
+    filename: &str,
+    option_csv_columns: Option<&str>,
+    format: &str,
+    option_output: Option<&str>,
+) -> Result<(), String> {
+    let format = MapReportFormat::from_str(format)?;
+    let csv_config = match option_csv_columns {
+        Some(spec) => CsvConfig::from_spec(spec)?,
+        None => CsvConfig::default(),
+    };
+    let db = Database::try_from_file_with_config(filename, &csv_config)?;
+    let report = db.map_report(filename, format);
+    match option_output {
+        Some(output) => {
+            std::fs::write(output, report)
+                .map_err(|e| format!("Erreur écriture '{output}': {e}"))?;
+            println!("Rapport du plan d'adressage de '{filename}' écrit dans '{output}'");
+        }
+        None => print!("{report}"),
+    }
+    Ok(())
+}
+
+/// Correspondance entre un ancien et un nouveau fichier .csv et transformation éventuelle d'un
+/// fichier d'état ou de valeurs (`option_files`: fichier à transformer et fichier transformé)
+fn migrate_tags(
+    old_filename: &str,
+    new_filename: &str,
+    option_csv_columns: Option<&str>,
+    option_mapping: Option<&str>,
+    option_files: Option<(&str, &str)>,
+) -> Result<(), String> {
+    let csv_config = match option_csv_columns {
+        Some(spec) => CsvConfig::from_spec(spec)?,
+        None => CsvConfig::default(),
+    };
+    let old_db = Database::try_from_file_with_config(old_filename, &csv_config)?;
+    let new_db = Database::try_from_file_with_config(new_filename, &csv_config)?;
+    let migration = TagMigration::new(&old_db, &new_db);
+    match option_mapping {
+        Some(mapping) => {
+            std::fs::write(mapping, migration.to_csv())
+                .map_err(|e| format!("Erreur écriture '{mapping}': {e}"))?;
+            println!("'{old_filename}' -> '{new_filename}': {migration}");
+            println!("Correspondance écrite dans '{mapping}'");
+        }
+        None => print!("{}", migration.to_csv()),
+    }
+    let Some((input, output)) = option_files else {
+        return Ok(());
+    };
+    let bytes = std::fs::read(input).map_err(|e| format!("Erreur lecture '{input}': {e}"))?;
+    let (content, nb_dropped) = if bytes.starts_with(&STATE_MAGIC) {
+        let saved_state = SavedState::from_bytes(&bytes).map_err(|e| format!("'{input}': {e}"))?;
+        let (saved_state, nb_dropped) = migration.migrate_saved_state(&saved_state);
+        (saved_state.to_bytes(), nb_dropped)
+    } else {
+        let content = String::from_utf8(bytes).map_err(|e| format!("'{input}': {e}"))?;
+        let (content, nb_dropped) = migration
+            .migrate_values(&content)
+            .map_err(|e| format!("'{input}': {e}"))?;
+        (content.into_bytes(), nb_dropped)
+    };
+    std::fs::write(output, content).map_err(|e| format!("Erreur écriture '{output}': {e}"))?;
+    eprintln!("'{input}' transformé dans '{output}' ({nb_dropped} valeur(s) de tags supprimés ignorée(s))");
+    Ok(())
+}
+
+/// Configuration de l'export vers InfluxDB selon la ligne de commande
+/// Retourne None si aucun groupe n'est exporté
+fn parse_influx_args(
+    command_args: &CommandArgs,
+) -> Result<Option<(InfluxEndpoint, Vec<InfluxGroup>)>, String> {
+    if command_args.influx_group.is_empty() {
+        return Ok(None);
+    }
+    let Some(url) = &command_args.influx_url else {
+        return Err("Option --influx-url requise avec --influx-group".to_string());
+    };
+    let endpoint = InfluxEndpoint::from_str(url)?;
+    let groups = command_args
+        .influx_group
+        .iter()
+        .map(|spec| InfluxGroup::from_str(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some((endpoint, groups)))
+}
+
+/// Configuration du partage de plages de registres selon la ligne de commande (groupe
+/// multicast, interface, plages publiées et plages acceptées)
+/// Retourne None si le partage n'est pas activé
+#[allow(clippy::type_complexity)]
+fn parse_federation_args(
+    command_args: &CommandArgs,
+) -> Result<Option<(SocketAddrV4, Ipv4Addr, Vec<ShadowRange>, Vec<ShadowRange>)>, String> {
+    let Some(spec) = &command_args.federation else {
+        if command_args.federation_range.is_empty() && command_args.federation_accept.is_empty() {
+            return Ok(None);
+        }
+        return Err(
+            "Option --federation requise avec --federation-range et --federation-accept"
+                .to_string(),
+        );
+    };
+    let group = parse_federation_group(spec)?;
+    let interface = match &command_args.federation_interface {
+        Some(interface) => interface
+            .trim()
+            .parse::<Ipv4Addr>()
+            .map_err(|_| format!("Interface '{interface}' incorrecte (adresse IPv4 attendue)"))?,
+        None => Ipv4Addr::UNSPECIFIED,
+    };
+    let parse_ranges = |specs: &[String]| {
+        specs
+            .iter()
+            .map(|spec| parse_shadow_range(spec))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let ranges = parse_ranges(&command_args.federation_range)?;
+    let accept_ranges = parse_ranges(&command_args.federation_accept)?;
+    Ok(Some((group, interface, ranges, accept_ranges)))
+}
+
+/// Budgets d'erreurs selon la ligne de commande
+fn parse_error_budget_args(command_args: &CommandArgs) -> Result<Vec<ErrorBudget>, String> {
+    if command_args.error_budget.len() > ERROR_BUDGETS_MAX {
+        return Err(format!(
+            "Trop d'options --error-budget (max {ERROR_BUDGETS_MAX})"
+        ));
+    }
+    command_args
+        .error_budget
+        .iter()
+        .map(|spec| ErrorBudget::from_str(spec))
+        .collect()
+}
@@ -0,0 +1,513 @@
+//! Profilage du simulateur : contention du verrou de la [`Database`], allocations mémoire et
+//! files de notification
+//!
+//! * `lock_database`: Verrouillage instrumenté de la [`Database`] partagée qui mesure le temps
+//!   d'attente du verrou pour chaque [`Subsystem`]
+//! * `CountingAllocator`: Allocateur global (feature `profiling`, à déclarer avec
+//!   `#[global_allocator]`) qui décompte les allocations selon le [`Subsystem`] qui détient le
+//!   verrou de la [`Database`] (les allocations hors verrou sont attribuées à `Subsystem::Other`)
+//! * `record_middleware_time`: Temps de traitement des requêtes de l'AFSEC+ (`get_conversation`)
+//!   cumulé pour chaque `middleware` (voir [`MiddlewareStats`])
+//! * `record_round_trip`: Temps de réponse de la liaison série avec l'AFSEC+ (du dernier octet
+//!   d'une requête reçue au premier octet de la réponse transmise) dans un [`LatencyHistogram`]
+//! * `stats_process`: Trace périodique des statistiques (option `--stats`, voir également la
+//!   commande `stats` de la console)
+
+#[cfg(feature = "profiling")]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::latency_histogram::LatencyHistogram;
+use crate::Database;
+
+/// Sous-système du simulateur qui accède à la [`Database`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subsystem {
+    /// Communication avec l'AFSEC+
+    Afsec,
+
+    /// Serveur MODBUS/TCP
+    Modbus,
+
+    /// Watcher
+    Watcher,
+
+    /// Console de commandes
+    Console,
+
+    /// Évolution des rampes
+    SlewRate,
+
+    /// Trace des statistiques
+    Stats,
+
+    /// Publication des CRC des zones
+    ZoneCrc,
+
+    /// Supervision de la fraîcheur des tags
+    Freshness,
+
+    /// Remise à 0 des tags impulsion
+    Pulse,
+
+    /// Valeurs aléatoires des tags modifiables
+    Randomizer,
+
+    /// Profils journaliers ou hebdomadaires des valeurs des tags
+    LoadProfile,
+
+    /// Export vers InfluxDB
+    Influx,
+
+    /// Surveillance du budget d'erreurs
+    ErrorBudget,
+
+    /// Notifications HTTP des modifications de tags
+    Webhook,
+
+    /// Script Rhai (feature `scripting`)
+    Script,
+
+    /// Exécution d'un scénario
+    Scenario,
+
+    /// Comparaison avec un ICOM de référence
+    Shadow,
+
+    /// Partage de tags entre simulateurs
+    Federation,
+
+    /// Agent SNMP des diagnostics
+    Snmp,
+
+    /// Autres accès (ou hors verrou de la [`Database`])
+    Other,
+}
+
+/// Nombre de [`Subsystem`]
+const NB_SUBSYSTEMS: usize = 20;
+
+impl Subsystem {
+    /// Liste de tous les [`Subsystem`]
+    pub const ALL: [Subsystem; NB_SUBSYSTEMS] = [
+        Subsystem::Afsec,
+        Subsystem::Modbus,
+        Subsystem::Watcher,
+        Subsystem::Console,
+        Subsystem::SlewRate,
+        Subsystem::Stats,
+        Subsystem::ZoneCrc,
+        Subsystem::Freshness,
+        Subsystem::Pulse,
+        Subsystem::Randomizer,
+        Subsystem::LoadProfile,
+        Subsystem::Influx,
+        Subsystem::ErrorBudget,
+        Subsystem::Webhook,
+        Subsystem::Script,
+        Subsystem::Scenario,
+        Subsystem::Shadow,
+        Subsystem::Federation,
+        Subsystem::Snmp,
+        Subsystem::Other,
+    ];
+}
+
+impl fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Subsystem::Afsec => "AFSEC Comm",
+            Subsystem::Modbus => "Server MODBUS/TCP",
+            Subsystem::Watcher => "Watcher",
+            Subsystem::Console => "Console",
+            Subsystem::SlewRate => "Slew rate",
+            Subsystem::Stats => "Stats",
+            Subsystem::ZoneCrc => "Zone CRC",
+            Subsystem::Freshness => "Freshness",
+            Subsystem::Pulse => "Pulse",
+            Subsystem::Randomizer => "Randomizer",
+            Subsystem::LoadProfile => "Load profile",
+            Subsystem::Influx => "InfluxDB",
+            Subsystem::ErrorBudget => "Error budget",
+            Subsystem::Webhook => "Webhook",
+            Subsystem::Script => "Script",
+            Subsystem::Scenario => "Scenario",
+            Subsystem::Shadow => "Shadow",
+            Subsystem::Federation => "Federation",
+            Subsystem::Snmp => "SNMP",
+            Subsystem::Other => "Other",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Compteurs d'un [`Subsystem`]
+struct Counters {
+    /// Nombre de verrouillages de la [`Database`]
+    nb_locks: AtomicU64,
+
+    /// Temps d'attente total du verrou (en nanosecondes)
+    lock_wait_nanos: AtomicU64,
+
+    /// Temps d'attente max. du verrou (en nanosecondes)
+    lock_wait_max_nanos: AtomicU64,
+
+    /// Nombre d'allocations mémoire
+    nb_allocs: AtomicU64,
+
+    /// Nombre d'octets alloués
+    alloc_bytes: AtomicU64,
+}
+
+impl Counters {
+    /// Compteurs à 0
+    const fn new() -> Self {
+        Self {
+            nb_locks: AtomicU64::new(0),
+            lock_wait_nanos: AtomicU64::new(0),
+            lock_wait_max_nanos: AtomicU64::new(0),
+            nb_allocs: AtomicU64::new(0),
+            alloc_bytes: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Compteurs de tous les [`Subsystem`] (dans l'ordre de `Subsystem::ALL`)
+static COUNTERS: [Counters; NB_SUBSYSTEMS] = [const { Counters::new() }; NB_SUBSYSTEMS];
+
+thread_local! {
+    /// [`Subsystem`] qui détient le verrou de la [`Database`] dans ce thread
+    static CURRENT_SUBSYSTEM: Cell<Option<Subsystem>> = const { Cell::new(None) };
+}
+
+/// Compteurs d'un [`Subsystem`]
+fn counters(subsystem: Subsystem) -> &'static Counters {
+    &COUNTERS[subsystem as usize]
+}
+
+/// Verrou instrumenté de la [`Database`] (voir `lock_database`)
+pub struct ProfiledGuard<'a> {
+    /// Verrou de la [`Database`]
+    guard: MutexGuard<'a, Database>,
+
+    /// [`Subsystem`] qui détenait le verrou avant celui-ci dans ce thread
+    previous: Option<Subsystem>,
+}
+
+impl Deref for ProfiledGuard<'_> {
+    type Target = Database;
+
+    fn deref(&self) -> &Database {
+        &self.guard
+    }
+}
+
+impl DerefMut for ProfiledGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Database {
+        &mut self.guard
+    }
+}
+
+impl Drop for ProfiledGuard<'_> {
+    fn drop(&mut self) {
+        let _ = CURRENT_SUBSYSTEM.try_with(|current| current.set(self.previous));
+    }
+}
+
+/// Verrouille la [`Database`] partagée pour un [`Subsystem`] en mesurant le temps d'attente
+/// du verrou
+/// # Panics
+/// Panic si le verrou de la [`Database`] est empoisonné (comme `Mutex::lock().unwrap()`)
+pub fn lock_database(thread_db: &Mutex<Database>, subsystem: Subsystem) -> ProfiledGuard<'_> {
+    let start = Instant::now();
+    let guard = thread_db.lock().unwrap();
+    let wait_nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+
+    let counters = counters(subsystem);
+    counters.nb_locks.fetch_add(1, Ordering::Relaxed);
+    counters
+        .lock_wait_nanos
+        .fetch_add(wait_nanos, Ordering::Relaxed);
+    counters
+        .lock_wait_max_nanos
+        .fetch_max(wait_nanos, Ordering::Relaxed);
+
+    let previous = CURRENT_SUBSYSTEM
+        .try_with(|current| current.replace(Some(subsystem)))
+        .unwrap_or_default();
+    ProfiledGuard { guard, previous }
+}
+
+/// Allocateur global qui décompte les allocations selon le [`Subsystem`] qui détient le verrou
+/// de la [`Database`] dans le thread de l'allocation
+#[cfg(feature = "profiling")]
+pub struct CountingAllocator;
+
+#[cfg(feature = "profiling")]
+impl CountingAllocator {
+    /// Décompte d'une allocation de `size` octets
+    fn count(size: usize) {
+        let subsystem = CURRENT_SUBSYSTEM
+            .try_with(Cell::get)
+            .ok()
+            .flatten()
+            .unwrap_or(Subsystem::Other);
+        let counters = counters(subsystem);
+        counters.nb_allocs.fetch_add(1, Ordering::Relaxed);
+        counters
+            .alloc_bytes
+            .fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "profiling")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Statistiques d'un [`Subsystem`]
+#[derive(Clone, Debug)]
+pub struct SubsystemStats {
+    /// [`Subsystem`]
+    pub subsystem: Subsystem,
+
+    /// Nombre de verrouillages de la [`Database`]
+    pub nb_locks: u64,
+
+    /// Temps d'attente total du verrou
+    pub lock_wait: Duration,
+
+    /// Temps d'attente max. du verrou
+    pub lock_wait_max: Duration,
+
+    /// Nombre d'allocations mémoire
+    pub nb_allocs: u64,
+
+    /// Nombre d'octets alloués
+    pub alloc_bytes: u64,
+}
+
+impl fmt::Display for SubsystemStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lock_wait_mean = if self.nb_locks == 0 {
+            Duration::ZERO
+        } else {
+            self.lock_wait / u32::try_from(self.nb_locks).unwrap_or(u32::MAX)
+        };
+        write!(
+            f,
+            "{}: locks={} wait={:?} (mean={:?}, max={:?}), allocs={} ({} bytes)",
+            self.subsystem,
+            self.nb_locks,
+            self.lock_wait,
+            lock_wait_mean,
+            self.lock_wait_max,
+            self.nb_allocs,
+            self.alloc_bytes
+        )
+    }
+}
+
+/// Statistiques (depuis le lancement) de tous les [`Subsystem`]
+pub fn get_stats() -> Vec<SubsystemStats> {
+    Subsystem::ALL
+        .iter()
+        .map(|subsystem| {
+            let counters = counters(*subsystem);
+            SubsystemStats {
+                subsystem: *subsystem,
+                nb_locks: counters.nb_locks.load(Ordering::Relaxed),
+                lock_wait: Duration::from_nanos(counters.lock_wait_nanos.load(Ordering::Relaxed)),
+                lock_wait_max: Duration::from_nanos(
+                    counters.lock_wait_max_nanos.load(Ordering::Relaxed),
+                ),
+                nb_allocs: counters.nb_allocs.load(Ordering::Relaxed),
+                alloc_bytes: counters.alloc_bytes.load(Ordering::Relaxed),
+            }
+        })
+        .collect()
+}
+
+/// Statistiques des temps de traitement des requêtes de l'AFSEC+ par un `middleware`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MiddlewareStats {
+    /// Nom du `middleware`
+    pub name: &'static str,
+
+    /// Nombre de requêtes présentées au `middleware`
+    pub nb_calls: u64,
+
+    /// Temps de traitement total
+    pub time: Duration,
+
+    /// Temps de traitement max.
+    pub time_max: Duration,
+}
+
+impl fmt::Display for MiddlewareStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time_mean = if self.nb_calls == 0 {
+            Duration::ZERO
+        } else {
+            self.time / u32::try_from(self.nb_calls).unwrap_or(u32::MAX)
+        };
+        write!(
+            f,
+            "Middleware {}: calls={} time={:?} (mean={:?}, max={:?})",
+            self.name, self.nb_calls, self.time, time_mean, self.time_max
+        )
+    }
+}
+
+/// Statistiques de tous les `middlewares` (dans l'ordre du premier traitement)
+static MIDDLEWARE_STATS: Mutex<Vec<MiddlewareStats>> = Mutex::new(Vec::new());
+
+/// Enregistre le temps de traitement d'une requête de l'AFSEC+ par un `middleware`
+/// (y compris l'attente du verrou de la [`Database`] pendant ce traitement)
+/// # Panics
+/// Panic si le verrou des statistiques est empoisonné
+pub fn record_middleware_time(name: &'static str, elapsed: Duration) {
+    let mut middleware_stats = MIDDLEWARE_STATS.lock().unwrap();
+    let index = match middleware_stats.iter().position(|stats| stats.name == name) {
+        Some(index) => index,
+        None => {
+            middleware_stats.push(MiddlewareStats {
+                name,
+                nb_calls: 0,
+                time: Duration::ZERO,
+                time_max: Duration::ZERO,
+            });
+            middleware_stats.len() - 1
+        }
+    };
+    let stats = &mut middleware_stats[index];
+    stats.nb_calls += 1;
+    stats.time += elapsed;
+    stats.time_max = stats.time_max.max(elapsed);
+}
+
+/// Statistiques (depuis le lancement) des `middlewares` qui ont traité au moins une requête
+/// # Panics
+/// Panic si le verrou des statistiques est empoisonné
+pub fn get_middleware_stats() -> Vec<MiddlewareStats> {
+    MIDDLEWARE_STATS.lock().unwrap().clone()
+}
+
+/// Histogramme des temps de réponse de la liaison série avec l'AFSEC+
+static ROUND_TRIP_HISTOGRAM: Mutex<LatencyHistogram> = Mutex::new(LatencyHistogram::new());
+
+/// Enregistre le temps de réponse à une requête de l'AFSEC+ (du dernier octet de la requête
+/// reçue au premier octet de la réponse transmise sur la liaison série)
+/// # Panics
+/// Panic si le verrou de l'histogramme est empoisonné
+pub fn record_round_trip(elapsed: Duration) {
+    ROUND_TRIP_HISTOGRAM.lock().unwrap().record(elapsed);
+}
+
+/// Histogramme (depuis le lancement) des temps de réponse de la liaison série avec l'AFSEC+
+/// # Panics
+/// Panic si le verrou de l'histogramme est empoisonné
+pub fn get_round_trip_histogram() -> LatencyHistogram {
+    ROUND_TRIP_HISTOGRAM.lock().unwrap().clone()
+}
+
+/// Rapport des statistiques (1 ligne par [`Subsystem`], 1 ligne par `middleware`, 1 ligne pour
+/// les temps de réponse de la liaison série puis 1 ligne par file de notification)
+pub fn report(db: &Database) -> String {
+    let mut ret = String::new();
+    for subsystem_stats in get_stats() {
+        ret += &format!("{subsystem_stats}\n");
+    }
+    for middleware_stats in get_middleware_stats() {
+        ret += &format!("{middleware_stats}\n");
+    }
+    ret += &format!("Round trip: {}\n", get_round_trip_histogram());
+    ret += &format!(
+        "Notifications: {} change(s) in history\n",
+        db.get_nb_notification_changes()
+    );
+    for (name, size) in db.get_notification_queue_sizes() {
+        ret += &format!("Notifications: {size} pending for '{name}'\n");
+    }
+    ret
+}
+
+/// Routine d'un thread qui trace les statistiques du simulateur
+/// En paramètre, la période de trace (en secondes)
+pub async fn stats_process(thread_db: Arc<Mutex<Database>>, period_in_secs: u64) {
+    if period_in_secs == 0 {
+        return;
+    }
+    println!("STATS: Starting (period={period_in_secs} secs)...");
+
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_secs(period_in_secs)).await;
+
+        let report = report(&lock_database(&thread_db, Subsystem::Stats));
+        for line in report.lines() {
+            println!("STATS: {line}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_database() {
+        let thread_db = Arc::new(Mutex::new(Database::default()));
+        let nb_locks = get_stats()[Subsystem::Console as usize].nb_locks;
+        {
+            let mut db = lock_database(&thread_db, Subsystem::Console);
+            db.get_id_user("TEST", true);
+            assert_eq!(CURRENT_SUBSYSTEM.with(Cell::get), Some(Subsystem::Console));
+        }
+        assert_eq!(CURRENT_SUBSYSTEM.with(Cell::get), None);
+        assert!(get_stats()[Subsystem::Console as usize].nb_locks > nb_locks);
+
+        let report = report(&lock_database(&thread_db, Subsystem::Stats));
+        assert!(report.contains("Console: locks="));
+        assert!(report.contains("Round trip: count="));
+        assert!(report.contains("pending for 'TEST'"));
+    }
+
+    #[test]
+    fn test_record_middleware_time() {
+        record_middleware_time("MTest", Duration::from_millis(3));
+        record_middleware_time("MTest", Duration::from_millis(1));
+        let stats = get_middleware_stats()
+            .into_iter()
+            .find(|stats| stats.name == "MTest")
+            .unwrap();
+        assert_eq!(stats.nb_calls, 2);
+        assert_eq!(stats.time, Duration::from_millis(4));
+        assert_eq!(stats.time_max, Duration::from_millis(3));
+        assert_eq!(
+            stats.to_string(),
+            "Middleware MTest: calls=2 time=4ms (mean=2ms, max=3ms)"
+        );
+    }
+}
@@ -0,0 +1,25 @@
+//! Process de remise à 0 des tags impulsion de la [`Database`]
+//!
+//! Les tags impulsion (bits de commande) sont définis par l'option `--pulse <adresse>=<secondes>`
+//! de la ligne de commande. Ce process remet périodiquement à 0 les tags dont la durée de
+//! l'impulsion est écoulée (voir le module `pulse_tags` de la [`Database`]).
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::profiling::{lock_database, Subsystem};
+use crate::Database;
+
+/// Temps de cycle (en millisecondes) de la remise à 0 des tags impulsion
+pub const PULSE_CYCLE_MSECS: u64 = 50;
+
+/// Routine d'un thread qui remet périodiquement à 0 les tags impulsion échus
+/// En paramètre, le temps de cycle entre chaque contrôle (en millisecondes)
+pub async fn pulse_process(thread_db: Arc<Mutex<Database>>, cycle_in_msecs: u64) {
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(cycle_in_msecs)).await;
+
+        lock_database(&thread_db, Subsystem::Pulse).reset_expired_pulses(Instant::now());
+    }
+}
@@ -0,0 +1,245 @@
+//! Bindings Python (feature `python`) du module `sim_icom_py`
+//!
+//! Ce module expose aux scripts de validation le codage/décodage des trames TLV et une
+//! [`Database`] en mémoire, afin d'utiliser l'implémentation Rust de référence plutôt
+//! qu'une ré-implémentation en Python.
+//!
+//! Une donnée d'un message TLV est représentée en Python par un tuple `(tag, format, value)` où:
+//!
+//! * `tag`: Tag de la donnée (`int`)
+//! * `format`: Format de la donnée (`int`, même codage que dans la trame TLV)
+//! * `value`: Valeur de la donnée encodée en 'big endian' (`bytes`)
+//!
+//! Exemple d'utilisation depuis Python (module construit avec `maturin develop --features python`) :
+//!
+//! ```python
+//! import sim_icom_py
+//! frame = sim_icom_py.encode_message(0x23, [(0x45, 0x01, bytes([123]))])
+//! (kind, tag, items) = sim_icom_py.decode_frame(frame)
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::afsec::tlv_frame::{DataFrame, DataItem, FrameState, RawFrame};
+use crate::database::{Tag, WordAddress, ID_ANONYMOUS_USER};
+use crate::server_modbus_tcp::MODBUS_TOP_WORD_ADDRESS;
+use crate::t_data::{be_data, TFormat};
+use crate::Database;
+
+/// Représentation Python d'un [`DataItem`]: (tag, format, valeur 'big endian')
+type PyDataItem = (u8, u8, Vec<u8>);
+
+/// Conversion d'une donnée Python en [`DataItem`]
+fn data_item_from_py(py_data_item: &PyDataItem) -> PyResult<DataItem> {
+    let (tag, format, value) = py_data_item;
+    let t_format = TFormat::from(*format);
+    if t_format == TFormat::Unknown {
+        return Err(PyValueError::new_err(format!(
+            "Format {format:#04X} inconnu pour le tag {tag:#04X}"
+        )));
+    }
+    match be_data::decode(t_format, value) {
+        Ok(t_value) => Ok(DataItem::new(*tag, t_value)),
+        Err(e) => Err(PyValueError::new_err(format!("Tag {tag:#04X}: {e}"))),
+    }
+}
+
+/// Conversion d'un [`DataItem`] en donnée Python
+fn data_item_to_py(data_item: &DataItem) -> PyDataItem {
+    (
+        data_item.tag,
+        u8::from(data_item.t_format),
+        be_data::encode(&data_item.t_value),
+    )
+}
+
+/// Encode une trame simple ACK
+#[pyfunction]
+fn encode_ack() -> Vec<u8> {
+    RawFrame::new_ack().encode()
+}
+
+/// Encode une trame simple NACK
+#[pyfunction]
+fn encode_nack() -> Vec<u8> {
+    RawFrame::new_nack().encode()
+}
+
+/// Encode un message TLV avec son tag et la liste de ses données
+#[pyfunction]
+fn encode_message(tag: u8, data_items: Vec<PyDataItem>) -> PyResult<Vec<u8>> {
+    let mut raw_frame = RawFrame::new_message(tag);
+    for py_data_item in &data_items {
+        let data_item = data_item_from_py(py_data_item)?;
+        if let Err(e) = raw_frame.try_extend_data_item(&data_item) {
+            return Err(PyValueError::new_err(e.to_string()));
+        }
+    }
+    Ok(raw_frame.encode())
+}
+
+/// Décode une trame TLV complète
+/// Retourne un tuple `(kind, tag, data_items)` où `kind` est 'ACK', 'NACK' ou 'MESSAGE'
+#[pyfunction]
+fn decode_frame(octets: Vec<u8>) -> PyResult<(String, u8, Vec<PyDataItem>)> {
+    let raw_frame = RawFrame::new(&octets);
+    if raw_frame.get_state() != FrameState::Ok {
+        return Err(PyValueError::new_err(format!(
+            "Trame incorrecte ({})",
+            raw_frame.get_state()
+        )));
+    }
+    match DataFrame::try_from(raw_frame) {
+        Ok(data_frame) => {
+            let kind = if data_frame.is_simple_ack() {
+                "ACK"
+            } else if data_frame.is_simple_nack() {
+                "NACK"
+            } else {
+                "MESSAGE"
+            };
+            let data_items = data_frame
+                .get_data_items()
+                .iter()
+                .map(data_item_to_py)
+                .collect();
+            Ok((kind.to_string(), data_frame.get_tag(), data_items))
+        }
+        Err(e) => Err(PyValueError::new_err(e.to_string())),
+    }
+}
+
+/// Contrôle que `cnt` mots à partir de l'adresse `word_address` sont dans la [`Database`]
+fn check_words(word_address: WordAddress, cnt: usize) -> PyResult<()> {
+    if usize::from(word_address) + cnt > usize::from(MODBUS_TOP_WORD_ADDRESS) {
+        return Err(PyValueError::new_err(format!(
+            "{cnt} mot(s) à partir de l'adresse {word_address:#06X}: au delà de l'adresse 0x7FFF"
+        )));
+    }
+    Ok(())
+}
+
+/// [`Database`] en mémoire accessible depuis Python
+#[pyclass(name = "Database")]
+struct PyDatabase {
+    db: Database,
+}
+
+impl PyDatabase {
+    /// Extrait le [`Tag`] défini à une [`WordAddress`]
+    fn get_tag(&self, word_address: WordAddress) -> PyResult<Tag> {
+        match self.db.get_tag_from_word_address(word_address) {
+            Some(tag) => Ok(tag.clone()),
+            None => Err(PyValueError::new_err(format!(
+                "Pas de tag défini à l'adresse {word_address:#06X}"
+            ))),
+        }
+    }
+}
+
+#[pymethods]
+impl PyDatabase {
+    /// Database vide (aucun tag défini)
+    #[new]
+    fn new() -> Self {
+        Self {
+            db: Database::default(),
+        }
+    }
+
+    /// Database depuis un fichier database*.csv
+    #[staticmethod]
+    fn from_file(filename: &str) -> PyResult<Self> {
+        match Database::try_from_file(filename) {
+            Ok(db) => Ok(Self { db }),
+            Err(msg) => Err(PyValueError::new_err(msg)),
+        }
+    }
+
+    /// Lecture de `cnt` mots à partir de l'adresse `word_address`
+    fn get_words(&self, word_address: WordAddress, cnt: u16) -> PyResult<Vec<u16>> {
+        check_words(word_address, usize::from(cnt))?;
+        Ok((word_address..word_address + cnt)
+            .map(|addr| self.db.get_u16_from_word_address(ID_ANONYMOUS_USER, addr))
+            .collect())
+    }
+
+    /// Écriture de mots à partir de l'adresse `word_address`
+    fn set_words(&mut self, word_address: WordAddress, values: Vec<u16>) -> PyResult<()> {
+        check_words(word_address, values.len())?;
+        for (addr, value) in (word_address..).zip(values) {
+            self.db
+                .set_u16_to_word_address(ID_ANONYMOUS_USER, addr, value)
+                .map_err(|db_error| PyValueError::new_err(db_error.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Valeur (en texte) du tag défini à l'adresse `word_address`
+    fn get_value(&self, word_address: WordAddress) -> PyResult<String> {
+        let tag = self.get_tag(word_address)?;
+        Ok(self
+            .db
+            .get_t_value_from_tag(ID_ANONYMOUS_USER, &tag)
+            .to_string())
+    }
+
+    /// Modification (depuis un texte) du tag défini à l'adresse `word_address`
+    fn set_value(&mut self, word_address: WordAddress, value: &str) -> PyResult<()> {
+        let tag = self.get_tag(word_address)?;
+        self.db
+            .set_value(ID_ANONYMOUS_USER, &tag, value)
+            .map_err(|db_error| PyValueError::new_err(db_error.to_string()))
+    }
+
+    fn __str__(&self) -> String {
+        self.db.to_string()
+    }
+}
+
+/// Module Python `sim_icom_py`
+#[pymodule]
+fn sim_icom_py(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(encode_ack, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_nack, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_message, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_frame, m)?)?;
+    m.add_class::<PyDatabase>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Message `AF_DATA_OUT` et donnée `D_DATA_TAG`
+    const AF_DATA_OUT: u8 = 0x03;
+    const D_DATA_TAG: u8 = 0x33;
+
+    #[test]
+    fn test_encode_decode() {
+        let frame =
+            encode_message(AF_DATA_OUT, vec![(D_DATA_TAG, 0x02, vec![0x00, 0x04])]).unwrap();
+        let (kind, tag, data_items) = decode_frame(frame).unwrap();
+        assert_eq!((kind.as_str(), tag), ("MESSAGE", AF_DATA_OUT));
+        assert_eq!(data_items, [(D_DATA_TAG, 0x02, vec![0x00, 0x04])]);
+        assert_eq!(decode_frame(encode_ack()).unwrap().0, "ACK");
+
+        // Format inconnu ou valeur de longueur incorrecte
+        assert!(encode_message(AF_DATA_OUT, vec![(D_DATA_TAG, 0x7F, vec![0])]).is_err());
+        assert!(encode_message(AF_DATA_OUT, vec![(D_DATA_TAG, 0x02, vec![0])]).is_err());
+        assert!(decode_frame(vec![0x02, 0x00]).is_err());
+    }
+
+    #[test]
+    fn test_database_words() {
+        let mut py_db = PyDatabase::new();
+        py_db.set_words(0x7FFE, vec![1, 2]).unwrap();
+        assert_eq!(py_db.get_words(0x7FFE, 2).unwrap(), [1, 2]);
+        assert!(py_db.get_words(0x7FFF, 2).is_err());
+        assert!(py_db.set_words(0x7FFF, vec![1, 2]).is_err());
+        assert!(py_db.get_words(0xFFFF, 1).is_err());
+        assert!(py_db.get_value(0x0010).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+//! Process des valeurs aléatoires des tags modifiables de la [`Database`]
+//!
+//! Avec l'option `--randomize <secondes>` de la ligne de commande, les tags modifiables reçoivent
+//! une valeur aléatoire dans leur plage déclarée à chaque période (voir le module `random_values`
+//! de la [`Database`]) pour éprouver les courbes et les alarmes du SCADA. Les options
+//! `--randomize-group` et `--randomize-zone` limitent les tags concernés.
+//!
+//! Les tirages utilisent un [`SimRng`] dérivé de la graine globale : une exécution peut être
+//! rejouée à l'identique avec l'option `--seed`.
+
+use std::sync::{Arc, Mutex};
+
+use crate::database::RandomFilter;
+use crate::profiling::{lock_database, Subsystem};
+use crate::sim_rng::SimRng;
+use crate::Database;
+
+/// Routine d'un thread qui écrit périodiquement des valeurs aléatoires dans les tags modifiables
+/// En paramètre, la période entre chaque tirage (en secondes)
+pub async fn randomizer_process(
+    thread_db: Arc<Mutex<Database>>,
+    period_in_secs: u64,
+    filter: RandomFilter,
+    mut rng: SimRng,
+    debug_level: u8,
+) {
+    // Obtient un id_user pour les opérations
+    let id_user = lock_database(&thread_db, Subsystem::Randomizer).get_id_user("Randomizer", false);
+
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_secs(period_in_secs)).await;
+
+        let nb_tags = lock_database(&thread_db, Subsystem::Randomizer)
+            .randomize_tags(id_user, &filter, &mut rng);
+        if debug_level > 1 {
+            println!("Randomizer: {nb_tags} tags modifiés");
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Thread dédié à la communication avec l'AFSEC+ (options `--afsec-thread`, `--afsec-priority`
+//! et `--afsec-core`)
+//!
+//! Pour les bancs qui valident les temps de réponse de l'ICOM, la tâche de communication avec
+//! l'AFSEC+ peut être exécutée sur un thread du système dédié, avec son propre runtime `tokio`
+//! séparé du pool des autres tâches du simulateur (MODBUS/TCP, console, etc.). Ce thread peut
+//! optionnellement être ordonnancé en temps réel (`SCHED_FIFO`, priorité 1 à 99) et être
+//! attaché à un cœur (affinité) pour réduire la gigue des réponses sous la milliseconde.
+//!
+//! La priorité temps réel et l'affinité ne sont supportées que sous Linux (la priorité
+//! nécessite en général les droits root ou la capacité `CAP_SYS_NICE`). Un échec est tracé et le
+//! thread dédié s'exécute alors avec l'ordonnancement par défaut.
+
+use std::fmt;
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+
+/// Priorité temps réel min. (`SCHED_FIFO`)
+pub const RT_PRIORITY_MIN: i32 = 1;
+
+/// Priorité temps réel max. (`SCHED_FIFO`)
+pub const RT_PRIORITY_MAX: i32 = 99;
+
+/// Configuration d'un thread dédié
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RtThreadConfig {
+    /// Priorité temps réel (`SCHED_FIFO`) du thread (None pour l'ordonnancement par défaut)
+    pub option_priority: Option<i32>,
+
+    /// Cœur auquel le thread est attaché (None pour tous les cœurs)
+    pub option_core: Option<usize>,
+}
+
+impl fmt::Display for RtThreadConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.option_priority {
+            Some(priority) => write!(f, "SCHED_FIFO priority {priority}")?,
+            None => write!(f, "default scheduling")?,
+        }
+        match self.option_core {
+            Some(core) => write!(f, ", core #{core}"),
+            None => write!(f, ", all cores"),
+        }
+    }
+}
+
+impl RtThreadConfig {
+    /// Constructeur
+    /// # Errors
+    /// Message d'erreur si la priorité n'est pas entre `RT_PRIORITY_MIN` et `RT_PRIORITY_MAX`
+    pub fn new(option_priority: Option<i32>, option_core: Option<usize>) -> Result<Self, String> {
+        if let Some(priority) = option_priority {
+            if !(RT_PRIORITY_MIN..=RT_PRIORITY_MAX).contains(&priority) {
+                return Err(format!(
+                    "Priorité temps réel {priority} incorrecte (attendu: {RT_PRIORITY_MIN} à {RT_PRIORITY_MAX})"
+                ));
+            }
+        }
+        Ok(Self {
+            option_priority,
+            option_core,
+        })
+    }
+
+    /// Applique la configuration au thread courant
+    /// # Errors
+    /// Message d'erreur si la priorité ou l'affinité ne peut pas être appliquée
+    pub fn apply_to_current_thread(&self) -> Result<(), String> {
+        if let Some(core) = self.option_core {
+            set_current_thread_core(core)
+                .map_err(|e| format!("Affinité au cœur #{core} impossible: {e}"))?;
+        }
+        if let Some(priority) = self.option_priority {
+            set_current_thread_priority(priority)
+                .map_err(|e| format!("Priorité temps réel {priority} impossible: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Attache le thread courant à un cœur
+#[cfg(target_os = "linux")]
+fn set_current_thread_core(core: usize) -> Result<(), String> {
+    let nb_cores = std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+    if core >= nb_cores {
+        return Err(format!("{nb_cores} cœur(s) disponible(s)"));
+    }
+    // SAFETY: `cpu_set` est initialisé à 0 avant d'y ajouter le cœur (indice vérifié) et la
+    // taille transmise est celle de la structure
+    let ret = unsafe {
+        let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut cpu_set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set)
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
+}
+
+/// Attache le thread courant à un cœur (non supporté sur cette plateforme)
+#[cfg(not(target_os = "linux"))]
+fn set_current_thread_core(_core: usize) -> Result<(), String> {
+    Err("Non supporté sur cette plateforme".to_string())
+}
+
+/// Ordonnancement temps réel (`SCHED_FIFO`) du thread courant
+#[cfg(target_os = "linux")]
+fn set_current_thread_priority(priority: i32) -> Result<(), String> {
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    // SAFETY: Sous Linux, le pid 0 désigne le thread appelant et `param` est valide pendant
+    // l'appel
+    let ret = unsafe { libc::sched_setscheduler(0, libc::SCHED_FIFO, &param) };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
+}
+
+/// Ordonnancement temps réel du thread courant (non supporté sur cette plateforme)
+#[cfg(not(target_os = "linux"))]
+fn set_current_thread_priority(_priority: i32) -> Result<(), String> {
+    Err("Non supporté sur cette plateforme".to_string())
+}
+
+/// Exécute une tâche sur un thread dédié avec son propre runtime `tokio` (`current_thread`),
+/// après application de la configuration au thread
+/// # Panics
+/// Création du runtime `tokio` du thread impossible
+pub fn spawn_dedicated<F>(
+    name: &'static str,
+    config: RtThreadConfig,
+    future: F,
+) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        match config.apply_to_current_thread() {
+            Ok(()) => println!("{name}: Dedicated thread ({config})"),
+            Err(msg) => println!("{name}: Dedicated thread, !!! {msg}"),
+        }
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Création du runtime du thread dédié");
+        runtime.block_on(future)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rt_thread_config() {
+        let config = RtThreadConfig::new(Some(80), Some(2)).unwrap();
+        assert_eq!(config.to_string(), "SCHED_FIFO priority 80, core #2");
+        assert_eq!(
+            RtThreadConfig::default().to_string(),
+            "default scheduling, all cores"
+        );
+        assert!(RtThreadConfig::new(Some(0), None).is_err());
+        assert!(RtThreadConfig::new(Some(100), None).is_err());
+        assert_eq!(RtThreadConfig::default().apply_to_current_thread(), Ok(()));
+    }
+}
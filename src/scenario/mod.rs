@@ -0,0 +1,915 @@
+//! Exécution de scénarios de test (option `--scenario <fichier>`)
+//!
+//! Un scénario est composé de plusieurs séquences ('timelines') exécutées en parallèle, par
+//! exemple le comptage et les alarmes d'un même essai. Chaque séquence commence par son nom
+//! entre crochets (les étapes avant la première séquence forment la séquence `main`), suivi
+//! d'une étape par ligne :
+//!
+//! * `set <adresse> <valeur>` : Écrit la valeur du tag défini à une adresse (hexa, `@0010`)
+//! * `sleep <secondes>` : Attente
+//! * `wait <adresse> <op> <valeur> [timeout <secondes>]` : Attente conditionnelle sur la valeur
+//!   d'un tag (`==`, `!=`, `<`, `<=`, `>`, `>=` ou `in <min>..<max>`), en échec si la condition
+//!   n'est pas remplie
+//!   avant le timeout
+//! * `expect <adresse> <op> <valeur> [within <secondes>]` : Vérification de la valeur d'un tag,
+//!   immédiate ou dans un délai ; le résultat est enregistré dans le rapport du scénario et la
+//!   séquence continue même en cas d'échec
+//! * `sync <nom>` : Point de synchronisation ('barrière') : la séquence attend que toutes les
+//!   séquences qui contiennent ce point l'aient atteint (ou soient terminées)
+//! * `mark "label"` : Insère un marqueur dans les traces (voir le module `timeline`)
+//! * `record <zone> <adresse> [<adresse>...]` : Ajoute au journal d'une zone (2 ou 3) un
+//!   enregistrement des valeurs des tags définis aux adresses (voir le module `journal_records`
+//!   de la [`Database`])
+//!
+//! Les lignes vides et les lignes commençant par `#` sont ignorées. Exemple :
+//!
+//! ```text
+//! [comptage]
+//! set @0010 100
+//! sync depart
+//! wait @0020 == 1 timeout 10
+//!
+//! [alarmes]
+//! sync depart
+//! sleep 2.5
+//! set @0020 1
+//! ```
+//!
+//! Les écritures du scénario sont faites par l'utilisateur `SCENARIO_USER_NAME`.
+//!
+//! À la fin du scénario, le rapport des vérifications (étapes `expect`, timeouts des `wait` et
+//! erreurs) est tracé et éventuellement exporté en JSON (voir le module `report`).
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::arg_parsing::{self, unquote};
+use crate::database::{IdUser, WordAddress};
+use crate::profiling::{lock_database, Subsystem};
+use crate::t_data::TValue;
+use crate::test_summary;
+use crate::timeline;
+use crate::Database;
+
+mod report;
+pub use report::{ScenarioReport, StepResult, SCENARIO_FAILED_EXIT_CODE};
+
+/// Temps de cycle (en millisecondes) de l'exécution d'un scénario
+pub const SCENARIO_CYCLE_MSECS: u64 = 50;
+
+/// Nom de l'utilisateur des écritures d'un scénario
+pub const SCENARIO_USER_NAME: &str = "Scenario";
+
+/// Nom de la séquence des étapes avant la première séquence nommée
+const MAIN_TIMELINE_NAME: &str = "main";
+
+/// Opérateur de comparaison d'une attente conditionnelle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `==`
+    Eq,
+
+    /// `!=`
+    Ne,
+
+    /// `<`
+    Lt,
+
+    /// `<=`
+    Le,
+
+    /// `>`
+    Gt,
+
+    /// `>=`
+    Ge,
+
+    /// `in` : valeur dans l'intervalle `<min>..<max>` (bornes incluses)
+    In,
+}
+
+impl FromStr for CompareOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "==" => Ok(CompareOp::Eq),
+            "!=" => Ok(CompareOp::Ne),
+            "<" => Ok(CompareOp::Lt),
+            "<=" => Ok(CompareOp::Le),
+            ">" => Ok(CompareOp::Gt),
+            ">=" => Ok(CompareOp::Ge),
+            "in" => Ok(CompareOp::In),
+            _ => Err(format!("Opérateur '{s}' inconnu")),
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::In => "in",
+        };
+        write!(f, "{op}")
+    }
+}
+
+/// Condition sur la valeur du tag défini à une adresse
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    /// Adresse du tag
+    pub word_address: WordAddress,
+
+    /// Opérateur de comparaison
+    pub op: CompareOp,
+
+    /// Valeur de comparaison (texte pour les tags `VecU8`, sinon numérique ou intervalle
+    /// `<min>..<max>` pour `CompareOp::In`)
+    pub value: String,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "@{:04X} {} {}", self.word_address, self.op, self.value)
+    }
+}
+
+impl Condition {
+    /// Indique si la condition est remplie par une valeur
+    /// Les valeurs texte (`VecU8`) ne sont comparées que par `==` et `!=`
+    pub fn is_met_by(&self, t_value: &TValue) -> bool {
+        if let Some(text) = t_value.as_text() {
+            let text = text.trim_end_matches('\0');
+            return match self.op {
+                CompareOp::Eq => text == self.value,
+                CompareOp::Ne => text != self.value,
+                _ => false,
+            };
+        }
+        let TValue::F64(value) = t_value.to_t_value_f64() else {
+            return false;
+        };
+        if self.op == CompareOp::In {
+            return match self
+                .value
+                .split_once("..")
+                .map(|(min, max)| (parse_number(min), parse_number(max)))
+            {
+                Some((Some(min), Some(max))) => min <= value && value <= max,
+                _ => false,
+            };
+        }
+        let Some(expected) = parse_number(&self.value) else {
+            return false;
+        };
+        match self.op {
+            CompareOp::Eq => (value - expected).abs() < f64::EPSILON,
+            CompareOp::Ne => (value - expected).abs() >= f64::EPSILON,
+            CompareOp::Lt => value < expected,
+            CompareOp::Le => value <= expected,
+            CompareOp::Gt => value > expected,
+            CompareOp::Ge => value >= expected,
+            CompareOp::In => false,
+        }
+    }
+}
+
+/// Analyse d'une valeur numérique d'une condition (`true` et `false` acceptés)
+fn parse_number(value: &str) -> Option<f64> {
+    match value.trim() {
+        "true" => Some(1.0),
+        "false" => Some(0.0),
+        value => value.parse::<f64>().ok(),
+    }
+}
+
+/// Étape d'une séquence
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    /// Écriture de la valeur du tag défini à une adresse
+    Set(WordAddress, String),
+
+    /// Attente
+    Sleep(Duration),
+
+    /// Attente conditionnelle (avec un timeout éventuel)
+    Wait(Condition, Option<Duration>),
+
+    /// Vérification d'une condition dans un délai (0 pour une vérification immédiate)
+    Expect(Condition, Duration),
+
+    /// Point de synchronisation
+    Sync(String),
+
+    /// Marqueur dans les traces
+    Mark(String),
+
+    /// Enregistrement des valeurs de tags dans le journal d'une zone
+    Record(u8, Vec<WordAddress>),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Set(word_address, value) => write!(f, "set @{word_address:04X} {value}"),
+            Step::Sleep(duration) => write!(f, "sleep {}", duration.as_secs_f64()),
+            Step::Wait(condition, None) => write!(f, "wait {condition}"),
+            Step::Wait(condition, Some(timeout)) => {
+                write!(f, "wait {condition} timeout {}", timeout.as_secs_f64())
+            }
+            Step::Expect(condition, within) if within.is_zero() => write!(f, "expect {condition}"),
+            Step::Expect(condition, within) => {
+                write!(f, "expect {condition} within {}", within.as_secs_f64())
+            }
+            Step::Sync(name) => write!(f, "sync {name}"),
+            Step::Mark(label) => write!(f, "mark \"{label}\""),
+            Step::Record(zone, word_addresses) => {
+                write!(f, "record {zone}")?;
+                for word_address in word_addresses {
+                    write!(f, " @{word_address:04X}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Analyse d'une adresse hexa (`@0010`, `0x0010` ou `0010`)
+fn parse_word_address(arg: &str) -> Result<WordAddress, String> {
+    arg_parsing::parse_word_address(arg).ok_or_else(|| format!("Adresse '{arg}' incorrecte"))
+}
+
+/// Analyse d'une durée en secondes
+fn parse_secs(arg: &str) -> Result<Duration, String> {
+    match arg.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs >= 0.0 => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(format!("Durée '{arg}' incorrecte")),
+    }
+}
+
+/// Analyse d'une condition `<adresse> <op> <valeur>`
+fn parse_condition(address: &str, op: &str, value: &str) -> Result<Condition, String> {
+    let op = CompareOp::from_str(op)?;
+    let value = unquote(value).to_string();
+    if op == CompareOp::In
+        && value
+            .split_once("..")
+            .and_then(|(min, max)| parse_number(min).zip(parse_number(max)))
+            .is_none()
+    {
+        return Err(format!(
+            "Intervalle '{value}' incorrect (attendu: <min>..<max>)"
+        ));
+    }
+    Ok(Condition {
+        word_address: parse_word_address(address)?,
+        op,
+        value,
+    })
+}
+
+impl FromStr for Step {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (command, args) = match s.trim().split_once(char::is_whitespace) {
+            Some((command, args)) => (command, args.trim()),
+            None => (s.trim(), ""),
+        };
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match command.to_lowercase().as_str() {
+            "set" => match args.split_once(char::is_whitespace) {
+                Some((address, value)) => Ok(Step::Set(
+                    parse_word_address(address)?,
+                    unquote(value.trim()).to_string(),
+                )),
+                None => Err("Attendu: set <adresse> <valeur>".to_string()),
+            },
+            "sleep" => match words.as_slice() {
+                [secs] => Ok(Step::Sleep(parse_secs(secs)?)),
+                _ => Err("Attendu: sleep <secondes>".to_string()),
+            },
+            "wait" => match words.as_slice() {
+                [address, op, value] => Ok(Step::Wait(parse_condition(address, op, value)?, None)),
+                [address, op, value, "timeout", secs] => Ok(Step::Wait(
+                    parse_condition(address, op, value)?,
+                    Some(parse_secs(secs)?),
+                )),
+                _ => Err("Attendu: wait <adresse> <op> <valeur> [timeout <secondes>]".to_string()),
+            },
+            "expect" => match words.as_slice() {
+                [address, op, value] => Ok(Step::Expect(
+                    parse_condition(address, op, value)?,
+                    Duration::ZERO,
+                )),
+                [address, op, value, "within", secs] => Ok(Step::Expect(
+                    parse_condition(address, op, value)?,
+                    parse_secs(secs)?,
+                )),
+                _ => Err("Attendu: expect <adresse> <op> <valeur> [within <secondes>]".to_string()),
+            },
+            "sync" => match words.as_slice() {
+                [name] => Ok(Step::Sync((*name).to_string())),
+                _ => Err("Attendu: sync <nom>".to_string()),
+            },
+            "mark" if !args.is_empty() => Ok(Step::Mark(unquote(args).to_string())),
+            "mark" => Err("Attendu: mark \"label\"".to_string()),
+            "record" => match words.split_first() {
+                Some((zone, addresses)) if !addresses.is_empty() => {
+                    let zone = zone
+                        .parse::<u8>()
+                        .map_err(|_| format!("Zone '{zone}' incorrecte"))?;
+                    let word_addresses = addresses
+                        .iter()
+                        .map(|address| parse_word_address(address))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(Step::Record(zone, word_addresses))
+                }
+                _ => Err("Attendu: record <zone> <adresse> [<adresse>...]".to_string()),
+            },
+            _ => Err(format!("Étape '{command}' inconnue")),
+        }
+    }
+}
+
+/// État d'une séquence
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimelineState {
+    /// Prête à exécuter l'étape courante
+    Ready,
+
+    /// Attente jusqu'à une date
+    Sleeping(Instant),
+
+    /// Attente conditionnelle de l'étape courante (date limite éventuelle)
+    Waiting(Option<Instant>),
+
+    /// Attente des autres séquences au point de synchronisation de l'étape courante
+    AtSync,
+
+    /// Toutes les étapes sont exécutées
+    Done,
+
+    /// Séquence interrompue par une erreur
+    Failed(String),
+}
+
+/// Séquence d'étapes d'un scénario
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    /// Nom de la séquence
+    name: String,
+
+    /// Étapes de la séquence
+    steps: Vec<Step>,
+
+    /// Index de l'étape courante
+    index: usize,
+
+    /// État de la séquence
+    state: TimelineState,
+
+    /// Résultats des vérifications de la séquence
+    results: Vec<StepResult>,
+}
+
+impl Timeline {
+    /// Constructeur
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            steps: vec![],
+            index: 0,
+            state: TimelineState::Ready,
+            results: vec![],
+        }
+    }
+
+    /// Nom de la séquence
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// État de la séquence
+    pub fn state(&self) -> &TimelineState {
+        &self.state
+    }
+
+    /// Indique si la séquence est terminée (toutes les étapes exécutées ou en erreur)
+    pub fn is_ended(&self) -> bool {
+        matches!(self.state, TimelineState::Done | TimelineState::Failed(_))
+    }
+
+    /// Nom du point de synchronisation atteint par la séquence
+    fn sync_name(&self) -> Option<&str> {
+        match (&self.state, self.steps.get(self.index)) {
+            (TimelineState::AtSync, Some(Step::Sync(name))) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Indique si la séquence contient un point de synchronisation
+    fn has_sync(&self, name: &str) -> bool {
+        self.steps
+            .iter()
+            .any(|step| matches!(step, Step::Sync(sync_name) if sync_name == name))
+    }
+
+    /// Passe à l'étape suivante
+    fn next_step(&mut self) {
+        self.index += 1;
+        self.state = if self.index < self.steps.len() {
+            TimelineState::Ready
+        } else {
+            println!("SCENARIO: [{}] Terminé", self.name);
+            TimelineState::Done
+        };
+    }
+
+    /// Enregistre le résultat de la vérification de l'étape courante
+    fn record_result(&mut self, passed: bool, detail: String) {
+        let step = self
+            .steps
+            .get(self.index)
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        if !passed {
+            println!("SCENARIO: [{}] !!! {step}: {detail}", self.name);
+        }
+        self.results.push(StepResult {
+            timeline: self.name.clone(),
+            step,
+            passed,
+            detail,
+        });
+    }
+
+    /// Interrompt la séquence en erreur
+    fn fail(&mut self, msg: String) {
+        self.record_result(false, msg.clone());
+        self.state = TimelineState::Failed(msg);
+    }
+
+    /// Exécute les étapes de la séquence jusqu'à la prochaine attente
+    /// Retourne true si au moins une étape a progressé
+    fn run(&mut self, db: &mut Database, id_user: IdUser, now: Instant) -> bool {
+        let mut progress = false;
+        loop {
+            let Some(step) = self.steps.get(self.index).cloned() else {
+                if !self.is_ended() {
+                    self.state = TimelineState::Done;
+                    progress = true;
+                }
+                return progress;
+            };
+            match (&self.state, step) {
+                (TimelineState::Ready, Step::Set(word_address, value)) => {
+                    match db.get_tag_from_word_address(word_address).cloned() {
+                        Some(tag) => match db.set_value(id_user, &tag, &value) {
+                            Ok(()) => self.next_step(),
+                            Err(db_error) => self.fail(db_error.to_string()),
+                        },
+                        None => {
+                            self.fail(format!("Pas de tag défini à l'adresse @{word_address:04X}"))
+                        }
+                    }
+                }
+                (TimelineState::Ready, Step::Sleep(duration)) => {
+                    self.state = TimelineState::Sleeping(now + duration);
+                    continue;
+                }
+                (TimelineState::Sleeping(until), _) => {
+                    if now < *until {
+                        return progress;
+                    }
+                    self.next_step();
+                }
+                (TimelineState::Ready, Step::Wait(_, option_timeout)) => {
+                    self.state =
+                        TimelineState::Waiting(option_timeout.map(|timeout| now + timeout));
+                    continue;
+                }
+                (TimelineState::Ready, Step::Expect(_, within)) => {
+                    self.state = TimelineState::Waiting(Some(now + within));
+                    continue;
+                }
+                (
+                    TimelineState::Waiting(option_deadline),
+                    Step::Wait(condition, _) | Step::Expect(condition, _),
+                ) => {
+                    let option_deadline = *option_deadline;
+                    let is_expect = matches!(self.steps[self.index], Step::Expect(..));
+                    let Some(tag) = db
+                        .get_tag_from_word_address(condition.word_address)
+                        .cloned()
+                    else {
+                        self.fail(format!(
+                            "Pas de tag défini à l'adresse @{:04X}",
+                            condition.word_address
+                        ));
+                        return true;
+                    };
+                    let t_value = db.get_t_value_from_tag(id_user, &tag);
+                    if condition.is_met_by(&t_value) {
+                        if is_expect {
+                            self.record_result(true, format!("valeur {t_value}"));
+                        }
+                        self.next_step();
+                    } else {
+                        match option_deadline {
+                            Some(deadline) if now >= deadline && is_expect => {
+                                self.record_result(false, format!("valeur {t_value}"));
+                                self.next_step();
+                            }
+                            Some(deadline) if now >= deadline => {
+                                self.fail(format!("Timeout (valeur {t_value})"));
+                            }
+                            _ => return progress,
+                        }
+                    }
+                }
+                (TimelineState::Ready, Step::Sync(_)) => {
+                    self.state = TimelineState::AtSync;
+                    return true;
+                }
+                (TimelineState::Ready, Step::Mark(label)) => {
+                    timeline::mark(&format!("[{}] {label}", self.name));
+                    self.next_step();
+                }
+                (TimelineState::Ready, Step::Record(zone, word_addresses)) => {
+                    let mut datas = vec![];
+                    for word_address in word_addresses {
+                        let Some(tag) = db.get_tag_from_word_address(word_address).cloned() else {
+                            self.fail(format!("Pas de tag défini à l'adresse @{word_address:04X}"));
+                            return true;
+                        };
+                        datas.push((tag.id_tag, db.get_t_value_from_tag(id_user, &tag)));
+                    }
+                    match db.append_journal_record(zone, datas) {
+                        Ok(_) => self.next_step(),
+                        Err(msg) => self.fail(msg),
+                    }
+                }
+                _ => return progress,
+            }
+            progress = true;
+        }
+    }
+}
+
+/// Scénario : séquences exécutées en parallèle
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    /// Séquences du scénario
+    timelines: Vec<Timeline>,
+}
+
+impl FromStr for Scenario {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut timelines: Vec<Timeline> = vec![];
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let name = name.trim();
+                if name.is_empty() || timelines.iter().any(|timeline| timeline.name == name) {
+                    return Err(format!("Ligne {}: séquence '{name}' incorrecte", index + 1));
+                }
+                timelines.push(Timeline::new(name));
+                continue;
+            }
+            let step = Step::from_str(line).map_err(|msg| format!("Ligne {}: {msg}", index + 1))?;
+            if timelines.is_empty() {
+                timelines.push(Timeline::new(MAIN_TIMELINE_NAME));
+            }
+            timelines.last_mut().unwrap().steps.push(step);
+        }
+        if timelines.is_empty() {
+            return Err("Scénario vide".to_string());
+        }
+        Ok(Scenario { timelines })
+    }
+}
+
+impl Scenario {
+    /// Lecture d'un scénario dans un fichier
+    /// # Errors
+    /// Message d'erreur si le fichier est illisible ou incorrect
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(filename)
+            .map_err(|e| format!("Erreur lecture du scénario '{filename}': {e}"))?;
+        Scenario::from_str(&content).map_err(|msg| format!("Scénario '{filename}': {msg}"))
+    }
+
+    /// Séquences du scénario
+    pub fn timelines(&self) -> &[Timeline] {
+        &self.timelines
+    }
+
+    /// Rapport des vérifications des séquences
+    pub fn report(&self) -> ScenarioReport {
+        let mut results: Vec<StepResult> = self
+            .timelines
+            .iter()
+            .flat_map(|timeline| timeline.results.iter().cloned())
+            .collect();
+        for timeline in &self.timelines {
+            if !timeline.is_ended() {
+                results.push(StepResult {
+                    timeline: timeline.name.clone(),
+                    step: timeline
+                        .steps
+                        .get(timeline.index)
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                    passed: false,
+                    detail: "Séquence non terminée".to_string(),
+                });
+            }
+        }
+        ScenarioReport::new(results)
+    }
+
+    /// Avancement du scénario (nombre de vérifications faites, nombre de séquences terminées)
+    fn progress(&self) -> (usize, usize) {
+        (
+            self.timelines
+                .iter()
+                .map(|timeline| timeline.results.len())
+                .sum(),
+            self.timelines
+                .iter()
+                .filter(|timeline| timeline.is_ended())
+                .count(),
+        )
+    }
+
+    /// Indique si toutes les séquences sont terminées
+    pub fn is_ended(&self) -> bool {
+        self.timelines.iter().all(Timeline::is_ended)
+    }
+
+    /// Libère les séquences des points de synchronisation atteints par toutes les séquences
+    /// concernées (les séquences terminées ne bloquent pas la synchronisation)
+    /// Retourne true si au moins une séquence est libérée
+    fn release_syncs(&mut self) -> bool {
+        let names: HashSet<String> = self
+            .timelines
+            .iter()
+            .filter_map(|timeline| timeline.sync_name().map(str::to_string))
+            .collect();
+        let mut progress = false;
+        for name in names {
+            let is_complete = self.timelines.iter().all(|timeline| {
+                !timeline.has_sync(&name)
+                    || timeline.is_ended()
+                    || timeline.sync_name() == Some(name.as_str())
+            });
+            if is_complete {
+                println!("SCENARIO: Synchronisation '{name}'");
+                for timeline in &mut self.timelines {
+                    if timeline.sync_name() == Some(name.as_str()) {
+                        timeline.next_step();
+                    }
+                }
+                progress = true;
+            }
+        }
+        progress
+    }
+
+    /// Exécute les séquences à une date jusqu'à ce qu'elles soient toutes en attente ou
+    /// terminées
+    /// Retourne true si le scénario est terminé
+    pub fn tick(&mut self, db: &mut Database, id_user: IdUser, now: Instant) -> bool {
+        loop {
+            let mut progress = false;
+            for timeline in &mut self.timelines {
+                progress |= timeline.run(db, id_user, now);
+            }
+            progress |= self.release_syncs();
+            if !progress {
+                return self.is_ended();
+            }
+        }
+    }
+}
+
+/// Routine d'un thread qui exécute un scénario jusqu'à la fin de toutes ses séquences
+/// À la fin du scénario, le rapport est tracé, exporté au format JSON dans un fichier (si
+/// `option_report_filename`) et, si `exit_at_end`, le simulateur est arrêté avec le code de
+/// sortie 0 si toutes les vérifications sont OK, sinon `SCENARIO_FAILED_EXIT_CODE`
+pub async fn scenario_process(
+    thread_db: Arc<Mutex<Database>>,
+    mut scenario: Scenario,
+    option_report_filename: Option<String>,
+    exit_at_end: bool,
+) {
+    let id_user =
+        lock_database(&thread_db, Subsystem::Scenario).get_id_user(SCENARIO_USER_NAME, false);
+    println!(
+        "SCENARIO: Démarrage de {} séquence(s)",
+        scenario.timelines.len()
+    );
+    let mut last_progress = None;
+    loop {
+        let is_ended = {
+            let mut db = lock_database(&thread_db, Subsystem::Scenario);
+            scenario.tick(&mut db, id_user, Instant::now())
+        };
+        if is_ended {
+            break;
+        }
+
+        // Rapport partiel pour le rapport de fin de test (arrêt en cours de scénario)
+        let progress = scenario.progress();
+        if last_progress != Some(progress) {
+            test_summary::record_scenario_report(scenario.report());
+            last_progress = Some(progress);
+        }
+
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(SCENARIO_CYCLE_MSECS)).await;
+    }
+
+    let report = scenario.report();
+    print!("{report}");
+    test_summary::record_scenario_report(report.clone());
+    if let Some(filename) = option_report_filename {
+        match std::fs::write(&filename, report.to_json()) {
+            Ok(()) => println!("SCENARIO: Rapport exporté dans '{filename}'"),
+            Err(e) => println!("SCENARIO: !!! Erreur écriture du rapport '{filename}': {e}"),
+        }
+    }
+    if exit_at_end {
+        test_summary::write_summary(&lock_database(&thread_db, Subsystem::Scenario));
+        std::process::exit(if report.is_passed() {
+            0
+        } else {
+            SCENARIO_FAILED_EXIT_CODE
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{IdTag, Tag, ID_ANONYMOUS_USER};
+    use crate::t_data::TFormat;
+
+    fn database_setup() -> Database {
+        let mut db = Database::default();
+        for (word_address, num) in [(0x0010, 1), (0x0020, 2)] {
+            db.add_tag(&Tag {
+                word_address,
+                id_tag: IdTag::new(5, num, [0, 0, 0]),
+                t_format: TFormat::U16,
+                ..Default::default()
+            });
+        }
+        db
+    }
+
+    #[test]
+    fn test_parse_step() {
+        assert_eq!(
+            Step::from_str("set @0010 12"),
+            Ok(Step::Set(0x0010, "12".to_string()))
+        );
+        assert_eq!(
+            Step::from_str("wait 0x0020 >= 1.5 timeout 2"),
+            Ok(Step::Wait(
+                Condition {
+                    word_address: 0x0020,
+                    op: CompareOp::Ge,
+                    value: "1.5".to_string()
+                },
+                Some(Duration::from_secs(2))
+            ))
+        );
+        assert_eq!(
+            Step::from_str("mark \"Étape 1\""),
+            Ok(Step::Mark("Étape 1".to_string()))
+        );
+        assert_eq!(
+            Step::from_str("record 3 @0010 0020"),
+            Ok(Step::Record(3, vec![0x0010, 0x0020]))
+        );
+        assert!(Step::from_str("record 3").is_err());
+        assert!(Step::from_str("wait @0020 = 1").is_err());
+        assert!(Step::from_str("sleep -1").is_err());
+        assert!(Step::from_str("jump 3").is_err());
+        assert!(Scenario::from_str("[a]\nsleep 1\n[a]\n").is_err());
+        assert!(Scenario::from_str("# vide\n").is_err());
+    }
+
+    #[test]
+    fn test_scenario_sync_and_wait() {
+        let mut db = database_setup();
+        let mut scenario = Scenario::from_str(
+            "[comptage]\n\
+             set @0010 100\n\
+             sync depart\n\
+             wait @0020 == 1 timeout 10\n\
+             set @0010 200\n\
+             \n\
+             [alarmes]\n\
+             sleep 1\n\
+             sync depart\n\
+             sleep 2\n\
+             set @0020 1\n",
+        )
+        .unwrap();
+        assert_eq!(scenario.timelines().len(), 2);
+
+        // 'comptage' attend 'alarmes' au point de synchronisation
+        let t0 = Instant::now();
+        assert!(!scenario.tick(&mut db, ID_ANONYMOUS_USER, t0));
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 100);
+        assert_eq!(scenario.timelines()[0].state(), &TimelineState::AtSync);
+
+        // Synchronisation puis attente de la condition
+        assert!(!scenario.tick(&mut db, ID_ANONYMOUS_USER, t0 + Duration::from_secs(1)));
+        assert!(matches!(
+            scenario.timelines()[0].state(),
+            TimelineState::Waiting(Some(_))
+        ));
+        assert!(!scenario.tick(&mut db, ID_ANONYMOUS_USER, t0 + Duration::from_secs(2)));
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 100);
+
+        // Condition remplie par 'alarmes'
+        assert!(scenario.tick(&mut db, ID_ANONYMOUS_USER, t0 + Duration::from_secs(3)));
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 200);
+        assert_eq!(scenario.timelines()[1].state(), &TimelineState::Done);
+    }
+
+    #[test]
+    fn test_scenario_expect() {
+        let mut db = database_setup();
+        let mut scenario = Scenario::from_str(
+            "set @0010 12\n\
+             expect @0010 in 10..15\n\
+             expect @0010 == 13\n\
+             expect @0020 == 1 within 1\n",
+        )
+        .unwrap();
+        let t0 = Instant::now();
+        assert!(!scenario.tick(&mut db, ID_ANONYMOUS_USER, t0));
+        db.set_u16_to_word_address(ID_ANONYMOUS_USER, 0x0020, 1)
+            .unwrap();
+        assert!(scenario.tick(&mut db, ID_ANONYMOUS_USER, t0));
+
+        // Une vérification en échec n'interrompt pas la séquence
+        let report = scenario.report();
+        assert_eq!(report.nb_passed(), 2);
+        assert_eq!(report.nb_failed(), 1);
+        assert_eq!(report.results()[1].step, "expect @0010 == 13");
+        assert_eq!(scenario.timelines()[0].state(), &TimelineState::Done);
+        assert!(Step::from_str("expect @0010 in 10").is_err());
+    }
+
+    #[test]
+    fn test_scenario_record() {
+        let mut db = database_setup();
+        let mut scenario = Scenario::from_str("set @0010 7\nrecord 3 @0010 @0020\n").unwrap();
+        assert!(scenario.tick(&mut db, ID_ANONYMOUS_USER, Instant::now()));
+        assert!(scenario.is_ended());
+        let journal_records = db.take_journal_records();
+        assert_eq!(journal_records.len(), 1);
+        assert_eq!(journal_records[0].zone, 3);
+        assert_eq!(
+            journal_records[0].datas[0],
+            (IdTag::new(5, 1, [0, 0, 0]), TValue::U16(7))
+        );
+    }
+
+    #[test]
+    fn test_scenario_timeout() {
+        let mut db = database_setup();
+        let mut scenario = Scenario::from_str("wait @0020 > 5 timeout 1\nset @0010 1\n").unwrap();
+        let t0 = Instant::now();
+        assert!(!scenario.tick(&mut db, ID_ANONYMOUS_USER, t0));
+        assert!(scenario.tick(&mut db, ID_ANONYMOUS_USER, t0 + Duration::from_secs(2)));
+        assert_eq!(scenario.timelines()[0].name(), MAIN_TIMELINE_NAME);
+        assert!(matches!(
+            scenario.timelines()[0].state(),
+            TimelineState::Failed(_)
+        ));
+        assert_eq!(db.get_u16_from_word_address(ID_ANONYMOUS_USER, 0x0010), 0);
+        assert!(!scenario.report().is_passed());
+    }
+}
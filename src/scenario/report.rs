@@ -0,0 +1,151 @@
+//! Rapport des vérifications d'un [`Scenario`]
+//!
+//! Chaque étape `expect` donne un résultat (OK ou échec) ; un timeout d'une étape `wait` ou une
+//! erreur d'exécution interrompt la séquence et donne un résultat en échec. Le rapport est tracé
+//! à la fin du scénario et peut être exporté au format JSON pour l'orchestration des tests :
+//!
+//! ```text
+//! {"passed":false,"nb_passed":1,"nb_failed":1,"results":[
+//!  {"timeline":"alarmes","step":"expect @0020 == 1","passed":true,"detail":"valeur 1"}, ...]}
+//! ```
+//!
+//! [`Scenario`]: super::Scenario
+
+use std::fmt;
+
+use crate::webhook::escape_json;
+
+/// Code de sortie du simulateur arrêté à la fin d'un scénario avec au moins un échec
+pub const SCENARIO_FAILED_EXIT_CODE: i32 = 4;
+
+/// Résultat de la vérification d'une étape
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepResult {
+    /// Nom de la séquence
+    pub timeline: String,
+
+    /// Étape vérifiée
+    pub step: String,
+
+    /// Vérification OK
+    pub passed: bool,
+
+    /// Détail du résultat (valeur constatée ou erreur)
+    pub detail: String,
+}
+
+/// Rapport des vérifications d'un scénario
+#[derive(Clone, Debug, Default)]
+pub struct ScenarioReport {
+    /// Résultats (dans l'ordre des séquences)
+    results: Vec<StepResult>,
+}
+
+impl ScenarioReport {
+    /// Constructeur
+    pub fn new(results: Vec<StepResult>) -> Self {
+        Self { results }
+    }
+
+    /// Résultats des vérifications
+    pub fn results(&self) -> &[StepResult] {
+        &self.results
+    }
+
+    /// Nombre de vérifications OK
+    pub fn nb_passed(&self) -> usize {
+        self.results.iter().filter(|result| result.passed).count()
+    }
+
+    /// Nombre de vérifications en échec
+    pub fn nb_failed(&self) -> usize {
+        self.results.len() - self.nb_passed()
+    }
+
+    /// Indique si toutes les vérifications sont OK
+    pub fn is_passed(&self) -> bool {
+        self.nb_failed() == 0
+    }
+
+    /// Export du rapport au format JSON
+    pub fn to_json(&self) -> String {
+        let results: Vec<String> = self
+            .results
+            .iter()
+            .map(|result| {
+                format!(
+                    "{{\"timeline\":\"{}\",\"step\":\"{}\",\"passed\":{},\"detail\":\"{}\"}}",
+                    escape_json(&result.timeline),
+                    escape_json(&result.step),
+                    result.passed,
+                    escape_json(&result.detail)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"passed\":{},\"nb_passed\":{},\"nb_failed\":{},\"results\":[{}]}}\n",
+            self.is_passed(),
+            self.nb_passed(),
+            self.nb_failed(),
+            results.join(",")
+        )
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "SCENARIO: Rapport")?;
+        for result in &self.results {
+            writeln!(
+                f,
+                "  {:<6} [{}] {} ({})",
+                if result.passed { "OK" } else { "ECHEC" },
+                result.timeline,
+                result.step,
+                result.detail
+            )?;
+        }
+        writeln!(
+            f,
+            "SCENARIO: {} ({} OK, {} en échec)",
+            if self.is_passed() {
+                "SUCCÈS"
+            } else {
+                "ÉCHEC"
+            },
+            self.nb_passed(),
+            self.nb_failed()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario_report() {
+        let report = ScenarioReport::new(vec![
+            StepResult {
+                timeline: "a".to_string(),
+                step: "expect @0010 == 1".to_string(),
+                passed: true,
+                detail: "valeur 1".to_string(),
+            },
+            StepResult {
+                timeline: "b".to_string(),
+                step: "mark \"x\"".to_string(),
+                passed: false,
+                detail: "erreur".to_string(),
+            },
+        ]);
+        assert_eq!(report.nb_passed(), 1);
+        assert_eq!(report.nb_failed(), 1);
+        assert!(!report.is_passed());
+        let json = report.to_json();
+        assert!(json.starts_with("{\"passed\":false,\"nb_passed\":1,\"nb_failed\":1,"));
+        assert!(json.contains("\"step\":\"mark \\\"x\\\"\""));
+        assert!(report.to_string().contains("ECHEC  [b]"));
+        assert!(ScenarioReport::default().is_passed());
+    }
+}
@@ -0,0 +1,533 @@
+//! Scripts Rhai dans le simulateur (feature `scripting`, option `--script <fichier>`)
+//!
+//! Un script [Rhai](https://rhai.rs) permet de prototyper un comportement du firmware résident
+//! sans recompiler le simulateur. Le code 'top-level' du script est exécuté au chargement et
+//! enregistre des 'hooks' (fonctions ou closures Rhai) :
+//!
+//! * `on_change(motif, |id_tag, value| ...)` : Modification d'un tag du motif (voir
+//!   [`IdTagPattern`]), hors modifications faites par le script lui-même
+//! * `on_frame(message, |items| ...)` : Requête `message` (ex: `0x03` pour `AF_DATA_OUT`) reçue de
+//!   l'AFSEC+. `items` est la liste des données `#{tag, format, value}` de la requête. Le 'hook'
+//!   retourne `()` pour laisser les `middlewares` répondre, `"ACK"`, `"NACK"` ou une réponse
+//!   `#{tag: 0x83, items: [#{tag: 0x33, format: "U16", value: 4}]}`
+//! * `on_timer(période_ms, || ...)` : Appel périodique
+//!
+//! Les fonctions `get(id_tag)` et `set(id_tag, value)` lisent et écrivent la [`Database`]
+//! (`id_tag` au format `5/0F45:00:00:03`, indices à 0 si absents) et `print` trace un message
+//! préfixé par `SCRIPT:`. Exemple :
+//!
+//! ```text
+//! on_change("5/0F45", |id_tag, value| { if value > 100 { set("5/0F46", 1); } });
+//! on_timer(1000, || set("4/0001", get("4/0001") + 1));
+//! on_frame(0x7F, |items| "NACK");
+//! ```
+//!
+//! Une erreur dans un 'hook' est tracée et sans effet sur le simulateur (un `on_frame` en erreur
+//! laisse répondre les `middlewares`). Le nombre d'opérations d'un appel est limité
+//! (`SCRIPT_MAX_OPERATIONS`) pour qu'une boucle infinie ne bloque pas la communication.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FnPtr, Map, Scope, AST};
+
+use crate::afsec::tlv_frame::{DataFrame, DataItem, RawFrame};
+use crate::database::{IdTag, IdTagPattern, IdUser};
+use crate::profiling::{lock_database, Subsystem};
+use crate::t_data::{TFormat, TValue};
+use crate::Database;
+
+/// Nombre max. d'opérations d'un appel au script
+const SCRIPT_MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Temps de cycle (en millisecondes) des 'hooks' `on_change` et `on_timer`
+const SCRIPT_CYCLE_MSECS: u64 = 10;
+
+/// Appel périodique enregistré par `on_timer`
+struct ScriptTimer {
+    /// Période des appels
+    period: Duration,
+
+    /// Date du prochain appel
+    next_date: Instant,
+
+    /// Fonction appelée
+    callback: FnPtr,
+}
+
+/// 'Hooks' enregistrés par le script
+#[derive(Default)]
+struct Hooks {
+    /// Motif des tags et fonction appelée à chaque modification
+    on_change: Vec<(IdTagPattern, FnPtr)>,
+
+    /// Message et fonction appelée à chaque requête de l'AFSEC+
+    on_frame: Vec<(u8, FnPtr)>,
+
+    /// Appels périodiques
+    on_timer: Vec<ScriptTimer>,
+}
+
+/// Erreur d'une fonction appelée par le script
+fn script_error(msg: impl fmt::Display) -> Box<EvalAltResult> {
+    msg.to_string().into()
+}
+
+/// Format d'une donnée désigné par son nom (`Bool`, `U8`, ..., `F64`, `VecU8(<len>)`)
+fn parse_t_format(name: &str) -> Option<TFormat> {
+    let name = name.trim().to_uppercase();
+    if let Some(len) = name
+        .strip_prefix("VECU8(")
+        .and_then(|len| len.strip_suffix(')'))
+    {
+        return len.trim().parse::<usize>().ok().map(TFormat::VecU8);
+    }
+    [
+        TFormat::Bool,
+        TFormat::U8,
+        TFormat::I8,
+        TFormat::U16,
+        TFormat::I16,
+        TFormat::U32,
+        TFormat::I32,
+        TFormat::U64,
+        TFormat::I64,
+        TFormat::F32,
+        TFormat::F64,
+    ]
+    .into_iter()
+    .find(|t_format| t_format.to_string().to_uppercase() == name)
+}
+
+/// Valeur Rhai d'une [`TValue`] (texte pour une `TValue::VecU8`)
+fn to_dynamic(t_value: &TValue) -> Dynamic {
+    match t_value {
+        TValue::Bool(value) => Dynamic::from(*value),
+        TValue::F32(_) | TValue::F64(_) => Dynamic::from(f64::from(t_value)),
+        TValue::VecU8(..) => Dynamic::from(t_value.as_text().unwrap_or_default()),
+        _ => Dynamic::from(i64::from(t_value)),
+    }
+}
+
+/// [`TValue`] d'une valeur Rhai dans le format `option_t_format` (format selon le type de la
+/// valeur si None : `Bool` ou `VecU8` seulement, un nombre doit préciser son format)
+fn from_dynamic(value: &Dynamic, option_t_format: Option<TFormat>) -> Result<TValue, String> {
+    let t_value = if let Ok(value) = value.as_bool() {
+        TValue::Bool(value)
+    } else if let Ok(value) = value.as_int() {
+        TValue::I64(value)
+    } else if let Ok(value) = value.as_float() {
+        TValue::F64(value)
+    } else if value.is_string() {
+        let text = value.clone().into_string().unwrap_or_default();
+        TValue::new_vec_u8(text.len(), text.as_bytes())
+    } else {
+        return Err(format!(
+            "Valeur de type '{}' non supportée",
+            value.type_name()
+        ));
+    };
+    match option_t_format {
+        Some(t_format) => t_value
+            .to_t_format(t_format)
+            .ok_or_else(|| format!("Format {t_format} non supporté")),
+        None if matches!(t_value, TValue::Bool(_) | TValue::VecU8(..)) => Ok(t_value),
+        None => Err(format!("Format requis pour la valeur {value}")),
+    }
+}
+
+/// Donnée Rhai `#{tag, format, value}` d'un [`DataItem`]
+fn data_item_to_map(data_item: &DataItem) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("tag".into(), Dynamic::from(i64::from(data_item.tag)));
+    map.insert(
+        "format".into(),
+        Dynamic::from(data_item.t_format.to_string()),
+    );
+    map.insert("value".into(), to_dynamic(&data_item.t_value));
+    Dynamic::from_map(map)
+}
+
+/// Tag (`u8`) d'un message ou d'une donnée
+fn tag_of(map: &Map) -> Result<u8, String> {
+    map.get("tag")
+        .and_then(|tag| tag.as_int().ok())
+        .and_then(|tag| u8::try_from(tag).ok())
+        .ok_or_else(|| "Tag absent ou incorrect (0 à 255)".to_string())
+}
+
+/// [`DataItem`] d'une donnée Rhai `#{tag, format, value}`
+fn data_item_from_map(map: &Map) -> Result<DataItem, String> {
+    let tag = tag_of(map)?;
+    let option_t_format = match map.get("format") {
+        Some(format) => {
+            let format = format.clone().into_string().unwrap_or_default();
+            Some(parse_t_format(&format).ok_or_else(|| format!("Format '{format}' inconnu"))?)
+        }
+        None => None,
+    };
+    let value = map
+        .get("value")
+        .ok_or_else(|| format!("Valeur absente pour la donnée 0x{tag:02X}"))?;
+    let t_value =
+        from_dynamic(value, option_t_format).map_err(|e| format!("Donnée 0x{tag:02X}: {e}"))?;
+    Ok(DataItem::new(tag, t_value))
+}
+
+/// Réponse à l'AFSEC+ retournée par un 'hook' `on_frame` (None pour laisser répondre les
+/// `middlewares`)
+fn response_from_dynamic(response: Dynamic) -> Result<Option<RawFrame>, String> {
+    if response.is_unit() {
+        return Ok(None);
+    }
+    if response.is_string() {
+        return match response.into_string().unwrap_or_default().as_str() {
+            "ACK" => Ok(Some(RawFrame::new_ack())),
+            "NACK" => Ok(Some(RawFrame::new_nack())),
+            other => Err(format!("Réponse '{other}' incorrecte (ACK ou NACK)")),
+        };
+    }
+    let Some(map) = response.try_cast::<Map>() else {
+        return Err(
+            "Réponse incorrecte (attendu: (), \"ACK\", \"NACK\" ou #{tag, items})".to_string(),
+        );
+    };
+    let mut raw_frame = RawFrame::new_message(tag_of(&map)?);
+    let items = match map.get("items") {
+        Some(items) => items
+            .clone()
+            .try_cast::<Array>()
+            .ok_or("Liste 'items' incorrecte")?,
+        None => Array::new(),
+    };
+    for item in items {
+        let item = item
+            .try_cast::<Map>()
+            .ok_or("Donnée incorrecte (attendu: #{tag, format, value})")?;
+        raw_frame
+            .try_extend_data_item(&data_item_from_map(&item)?)
+            .map_err(|e| format!("{e:?}"))?;
+    }
+    Ok(Some(raw_frame))
+}
+
+/// Script Rhai chargé dans le simulateur
+pub struct ScriptHost {
+    /// Moteur Rhai (avec les fonctions d'accès à la [`Database`])
+    engine: Engine,
+
+    /// Script compilé
+    ast: AST,
+
+    /// 'Hooks' enregistrés par le script
+    hooks: Arc<Mutex<Hooks>>,
+
+    /// Accès à la [`Database`]
+    thread_db: Arc<Mutex<Database>>,
+
+    /// Utilisateur de la [`Database`] pour les modifications faites par le script
+    id_user: IdUser,
+}
+
+impl ScriptHost {
+    /// Chargement d'un script : compilation et exécution du code 'top-level' qui enregistre les
+    /// 'hooks'
+    /// # Errors
+    /// Erreur de lecture, de compilation ou d'exécution du script
+    pub fn load(path: &str, thread_db: Arc<Mutex<Database>>) -> Result<Self, String> {
+        let script =
+            std::fs::read_to_string(path).map_err(|e| format!("Script '{path}' illisible: {e}"))?;
+        Self::from_source(&script, thread_db).map_err(|e| format!("Script '{path}': {e}"))
+    }
+
+    /// Chargement d'un script depuis son texte
+    /// # Errors
+    /// Erreur de compilation ou d'exécution du script
+    pub fn from_source(script: &str, thread_db: Arc<Mutex<Database>>) -> Result<Self, String> {
+        let id_user = lock_database(&thread_db, Subsystem::Script).get_id_user("Script", true);
+        let hooks = Arc::new(Mutex::new(Hooks::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(SCRIPT_MAX_OPERATIONS);
+        engine.on_print(|text| println!("SCRIPT: {text}"));
+        Self::register_database_fns(&mut engine, &thread_db, id_user);
+        Self::register_hook_fns(&mut engine, &hooks);
+
+        let ast = engine.compile(script).map_err(|e| e.to_string())?;
+        engine
+            .run_ast_with_scope(&mut Scope::new(), &ast)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            engine,
+            ast,
+            hooks,
+            thread_db,
+            id_user,
+        })
+    }
+
+    /// Fonctions `get` et `set` d'accès à la [`Database`]
+    fn register_database_fns(
+        engine: &mut Engine,
+        thread_db: &Arc<Mutex<Database>>,
+        id_user: IdUser,
+    ) {
+        let get_db = Arc::clone(thread_db);
+        engine.register_fn(
+            "get",
+            move |id_tag: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+                let id_tag = IdTag::from_str(id_tag).map_err(script_error)?;
+                let db = lock_database(&get_db, Subsystem::Script);
+                let tag = db
+                    .get_tag_from_id_tag(id_tag)
+                    .ok_or_else(|| script_error(format!("Tag {id_tag} inconnu")))?;
+                Ok(to_dynamic(&db.get_t_value_from_tag(id_user, tag)))
+            },
+        );
+        let set_db = Arc::clone(thread_db);
+        engine.register_fn(
+            "set",
+            move |id_tag: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+                let id_tag = IdTag::from_str(id_tag).map_err(script_error)?;
+                let mut db = lock_database(&set_db, Subsystem::Script);
+                let tag = db
+                    .get_tag_from_id_tag(id_tag)
+                    .cloned()
+                    .ok_or_else(|| script_error(format!("Tag {id_tag} inconnu")))?;
+                let t_value = from_dynamic(&value, Some(tag.t_format))
+                    .map_err(|e| script_error(format!("Tag {id_tag}: {e}")))?;
+                db.set_t_value_to_tag(id_user, &tag, &t_value)
+                    .map_err(script_error)
+            },
+        );
+    }
+
+    /// Fonctions `on_change`, `on_frame` et `on_timer` d'enregistrement des 'hooks'
+    fn register_hook_fns(engine: &mut Engine, hooks: &Arc<Mutex<Hooks>>) {
+        let change_hooks = Arc::clone(hooks);
+        engine.register_fn(
+            "on_change",
+            move |pattern: &str, callback: FnPtr| -> Result<(), Box<EvalAltResult>> {
+                let pattern = IdTagPattern::from_str(pattern).map_err(script_error)?;
+                change_hooks
+                    .lock()
+                    .unwrap()
+                    .on_change
+                    .push((pattern, callback));
+                Ok(())
+            },
+        );
+        let frame_hooks = Arc::clone(hooks);
+        engine.register_fn(
+            "on_frame",
+            move |message: i64, callback: FnPtr| -> Result<(), Box<EvalAltResult>> {
+                let message = u8::try_from(message)
+                    .map_err(|_| script_error(format!("Message {message} incorrect (0 à 255)")))?;
+                frame_hooks
+                    .lock()
+                    .unwrap()
+                    .on_frame
+                    .push((message, callback));
+                Ok(())
+            },
+        );
+        let timer_hooks = Arc::clone(hooks);
+        engine.register_fn(
+            "on_timer",
+            move |period_ms: i64, callback: FnPtr| -> Result<(), Box<EvalAltResult>> {
+                let period = u64::try_from(period_ms)
+                    .ok()
+                    .filter(|period_ms| *period_ms > 0)
+                    .map(Duration::from_millis)
+                    .ok_or_else(|| script_error(format!("Période {period_ms} ms incorrecte")))?;
+                timer_hooks.lock().unwrap().on_timer.push(ScriptTimer {
+                    period,
+                    next_date: Instant::now() + period,
+                    callback,
+                });
+                Ok(())
+            },
+        );
+    }
+
+    /// Nombre de 'hooks' enregistrés (`on_change`, `on_frame`, `on_timer`)
+    pub fn nb_hooks(&self) -> (usize, usize, usize) {
+        let hooks = self.hooks.lock().unwrap();
+        (
+            hooks.on_change.len(),
+            hooks.on_frame.len(),
+            hooks.on_timer.len(),
+        )
+    }
+
+    /// Réponse du script à une requête de l'AFSEC+ (None si aucun 'hook' `on_frame` ne répond)
+    pub fn on_frame(&self, request_data_frame: &DataFrame) -> Option<RawFrame> {
+        let DataFrame::Message(message, data_items) = request_data_frame else {
+            return None;
+        };
+        let callbacks: Vec<FnPtr> = self
+            .hooks
+            .lock()
+            .unwrap()
+            .on_frame
+            .iter()
+            .filter(|(hook_message, _)| hook_message == message)
+            .map(|(_, callback)| callback.clone())
+            .collect();
+        if callbacks.is_empty() {
+            return None;
+        }
+        let items: Array = data_items.iter().map(data_item_to_map).collect();
+        for callback in callbacks {
+            let result = callback
+                .call::<Dynamic>(&self.engine, &self.ast, (items.clone(),))
+                .map_err(|e| e.to_string())
+                .and_then(response_from_dynamic);
+            match result {
+                Ok(Some(response_raw_frame)) => return Some(response_raw_frame),
+                Ok(None) => (),
+                Err(e) => println!("SCRIPT: Erreur on_frame(0x{message:02X}): {e}"),
+            }
+        }
+        None
+    }
+
+    /// Appel des 'hooks' `on_change` des tags modifiés et des 'hooks' `on_timer` échus
+    pub fn run_cycle(&self, now: Instant) {
+        // Modifications des tags (valeur lue lors de la prise en compte de la modification)
+        let mut changes = vec![];
+        {
+            let change_hooks: Vec<(IdTagPattern, FnPtr)> =
+                self.hooks.lock().unwrap().on_change.clone();
+            let mut db = lock_database(&self.thread_db, Subsystem::Script);
+            while let Some(notification_change) = db.get_change(self.id_user, false, true) {
+                let id_tag = notification_change.id_tag;
+                let Some(tag) = db.get_tag_from_id_tag(id_tag) else {
+                    continue;
+                };
+                let value = to_dynamic(&db.get_t_value_from_tag(self.id_user, tag));
+                for (pattern, callback) in &change_hooks {
+                    if pattern.matches(id_tag) {
+                        changes.push((callback.clone(), id_tag, value.clone()));
+                    }
+                }
+            }
+        }
+        for (callback, id_tag, value) in changes {
+            if let Err(e) =
+                callback.call::<Dynamic>(&self.engine, &self.ast, (id_tag.to_string(), value))
+            {
+                println!("SCRIPT: Erreur on_change({id_tag}): {e}");
+            }
+        }
+
+        // Appels périodiques échus
+        let mut callbacks = vec![];
+        for timer in &mut self.hooks.lock().unwrap().on_timer {
+            if now >= timer.next_date {
+                timer.next_date = now + timer.period;
+                callbacks.push((timer.callback.clone(), timer.period));
+            }
+        }
+        for (callback, period) in callbacks {
+            if let Err(e) = callback.call::<Dynamic>(&self.engine, &self.ast, ()) {
+                println!("SCRIPT: Erreur on_timer({} ms): {e}", period.as_millis());
+            }
+        }
+    }
+}
+
+/// Routine d'un thread qui appelle les 'hooks' `on_change` et `on_timer` du script
+pub async fn script_process(script: Arc<ScriptHost>) {
+    let (nb_on_change, nb_on_frame, nb_on_timer) = script.nb_hooks();
+    println!("SCRIPT: {nb_on_change} on_change, {nb_on_frame} on_frame, {nb_on_timer} on_timer");
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(SCRIPT_CYCLE_MSECS)).await;
+        script.run_cycle(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::Tag;
+
+    /// Messages et tag de donnée des tests (`AF_DATA_OUT`, `IC_DATA_OUT`, `AF_TEST`, `D_DATA_TAG`)
+    const AF_DATA_OUT: u8 = 0x03;
+    const IC_DATA_OUT: u8 = 0x83;
+    const AF_TEST: u8 = 0x7F;
+    const D_DATA_TAG: u8 = 0x33;
+
+    /// Database avec 2 tags `u16` (adresses 0x0010 et 0x0011)
+    fn database_setup() -> Arc<Mutex<Database>> {
+        let mut db = Database::default();
+        for (word_address, num_tag) in [(0x0010, 1), (0x0011, 2)] {
+            db.add_tag(&Tag {
+                word_address,
+                id_tag: IdTag::new(4, num_tag, [0, 0, 0]),
+                t_format: TFormat::U16,
+                ..Default::default()
+            });
+        }
+        Arc::new(Mutex::new(db))
+    }
+
+    #[test]
+    fn test_parse_t_format() {
+        assert_eq!(parse_t_format("u16"), Some(TFormat::U16));
+        assert_eq!(parse_t_format("F64"), Some(TFormat::F64));
+        assert_eq!(parse_t_format("VecU8(12)"), Some(TFormat::VecU8(12)));
+        assert_eq!(parse_t_format("U24"), None);
+    }
+
+    #[test]
+    fn test_script_hooks() {
+        let shared_db = database_setup();
+        let script = r#"
+            on_change("4/0001", |id_tag, value| set("4/0002", value * 2));
+            on_frame(0x03, |items| #{tag: 0x83, items: [#{tag: 0x33, format: "U16", value: items[0].value + 1}]});
+            on_frame(0x7F, |items| "NACK");
+        "#;
+        let script_host = ScriptHost::from_source(script, Arc::clone(&shared_db)).unwrap();
+        assert_eq!(script_host.nb_hooks(), (1, 2, 0));
+
+        // on_change
+        shared_db
+            .lock()
+            .unwrap()
+            .set_u16_to_id_tag(0, IdTag::new(4, 1, [0, 0, 0]), 21)
+            .unwrap();
+        script_host.run_cycle(Instant::now());
+        assert_eq!(
+            shared_db
+                .lock()
+                .unwrap()
+                .get_u16_from_id_tag(0, IdTag::new(4, 2, [0, 0, 0])),
+            42
+        );
+
+        // on_frame
+        let mut request = RawFrame::new_message(AF_DATA_OUT);
+        request
+            .try_extend_data_item(&DataItem::new(D_DATA_TAG, TValue::U16(4)))
+            .unwrap();
+        let response = script_host
+            .on_frame(&DataFrame::try_from(request).unwrap())
+            .unwrap();
+        let response = DataFrame::try_from(response).unwrap();
+        assert_eq!(response.get_tag(), IC_DATA_OUT);
+        assert_eq!(response.get_data_items()[0].t_value, TValue::U16(5));
+        let request = DataFrame::try_from(RawFrame::new_message(AF_TEST)).unwrap();
+        assert_eq!(script_host.on_frame(&request), Some(RawFrame::new_nack()));
+    }
+
+    #[test]
+    fn test_script_errors() {
+        assert!(ScriptHost::from_source("on_timer(0, || 1);", database_setup()).is_err());
+        assert!(ScriptHost::from_source("on_change(\"4\", |a, b| 1);", database_setup()).is_err());
+        assert!(ScriptHost::from_source("let x = ;", database_setup()).is_err());
+    }
+}
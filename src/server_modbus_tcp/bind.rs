@@ -0,0 +1,91 @@
+//! Adresses d'écoute du serveur MODBUS/TCP
+//!
+//! Sur un PC de banc avec plusieurs interfaces réseau, le serveur MODBUS/TCP ne doit être
+//! accessible que sur certaines interfaces (option `--bind` répétable). Une adresse d'écoute est
+//! une adresse IPv4 ou IPv6 (le port est alors celui de l'option `--port`) ou une adresse avec
+//! son port (`192.168.1.10:5020` ou `[fe80::1]:5020`).
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+/// Adresse d'écoute par défaut (toutes les interfaces IPv4)
+pub const DEFAULT_BIND_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+/// Décode une adresse d'écoute (adresse IP seule avec le port `port` ou adresse avec son port)
+/// # Errors
+/// Erreur si l'adresse n'est pas une adresse IPv4 ou IPv6 valide
+pub fn parse_bind_address(spec: &str, port: u16) -> Result<SocketAddr, String> {
+    let spec = spec.trim();
+    if let Ok(socket_addr) = spec.parse::<SocketAddr>() {
+        return Ok(socket_addr);
+    }
+    let ip_addr = spec
+        .strip_prefix('[')
+        .and_then(|spec| spec.strip_suffix(']'))
+        .unwrap_or(spec);
+    match ip_addr.parse::<IpAddr>() {
+        Ok(ip_addr) => Ok(SocketAddr::new(ip_addr, port)),
+        Err(_) => Err(format!(
+            "Adresse d'écoute '{spec}' invalide (attendu: adresse IPv4 ou IPv6 avec ou sans port)"
+        )),
+    }
+}
+
+/// Adresses d'écoute du serveur MODBUS/TCP selon les options `--bind` (toutes les interfaces
+/// IPv4 si aucune adresse n'est spécifiée) et `--port`
+/// # Errors
+/// Erreur si une des adresses est invalide
+pub fn parse_bind_addresses(specs: &[String], port: u16) -> Result<Vec<SocketAddr>, String> {
+    if specs.is_empty() {
+        return Ok(vec![SocketAddr::new(DEFAULT_BIND_ADDRESS, port)]);
+    }
+    specs
+        .iter()
+        .map(|spec| parse_bind_address(spec, port))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bind_address() {
+        assert_eq!(
+            parse_bind_address("192.168.1.10", 502),
+            Ok("192.168.1.10:502".parse().unwrap())
+        );
+        assert_eq!(
+            parse_bind_address("192.168.1.10:5020", 502),
+            Ok("192.168.1.10:5020".parse().unwrap())
+        );
+        assert_eq!(
+            parse_bind_address("::1", 502),
+            Ok("[::1]:502".parse().unwrap())
+        );
+        assert_eq!(
+            parse_bind_address("[fe80::1]", 502),
+            Ok("[fe80::1]:502".parse().unwrap())
+        );
+        assert_eq!(
+            parse_bind_address("[fe80::1]:5020", 502),
+            Ok("[fe80::1]:5020".parse().unwrap())
+        );
+        assert!(parse_bind_address("localhost", 502).is_err());
+    }
+
+    #[test]
+    fn test_parse_bind_addresses() {
+        assert_eq!(
+            parse_bind_addresses(&[], 502),
+            Ok(vec!["0.0.0.0:502".parse().unwrap()])
+        );
+        let specs = vec!["10.0.0.1".to_string(), "::".to_string()];
+        assert_eq!(
+            parse_bind_addresses(&specs, 5020),
+            Ok(vec![
+                "10.0.0.1:5020".parse().unwrap(),
+                "[::]:5020".parse().unwrap()
+            ])
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Écritures MODBUS diffusées ('broadcast', unité 0)
+//!
+//! Le maître MODBUS émet parfois des écritures diffusées à tous les esclaves (unité 0) : chaque
+//! esclave applique l'écriture sans répondre. Ce comportement est celui d'une liaison série
+//! (MODBUS RTU) ; il est activé sur MODBUS/TCP par l'option `--modbus-broadcast` pour les tests
+//! du maître.
+//!
+//! Une requête diffusée est reconnue par [`PipelinedStream`] (voir le module `pipelining`) : une
+//! écriture est transmise au serveur et sa réponse n'est pas émise vers le client, les autres
+//! requêtes sont ignorées. Chaque requête diffusée reçue est comptée dans le tag
+//! `ID_TAG_SIM_MODBUS_BROADCASTS`.
+//!
+//! [`PipelinedStream`]: super::PipelinedStream
+
+use std::sync::{Arc, Mutex};
+
+use crate::afsec::format_hex_frame;
+use crate::database::{Database, ID_TAG_SIM_MODBUS_BROADCASTS};
+use crate::profiling::{lock_database, Subsystem};
+
+/// Unité des requêtes MODBUS diffusées
+pub const MODBUS_BROADCAST_UNIT_ID: u8 = 0;
+
+/// Codes fonction MODBUS des écritures qui peuvent être diffusées (write single coil, write
+/// single register, write multiple coils, write multiple registers)
+const BROADCAST_WRITE_FUNCTION_CODES: [u8; 4] = [0x05, 0x06, 0x0F, 0x10];
+
+/// Position de l'unité et du code fonction dans une trame MODBUS/TCP (après l'entête MBAP)
+const UNIT_ID_OFFSET: usize = 6;
+const FUNCTION_CODE_OFFSET: usize = 7;
+
+/// Signalement d'une requête diffusée reçue (trame MODBUS/TCP complète)
+pub type BroadcastHook = Box<dyn FnMut(&[u8]) + Send>;
+
+/// Indique si une requête (trame MODBUS/TCP complète) est diffusée (unité 0)
+pub fn is_broadcast(request: &[u8]) -> bool {
+    request.get(UNIT_ID_OFFSET) == Some(&MODBUS_BROADCAST_UNIT_ID)
+}
+
+/// Indique si une requête diffusée est une écriture (appliquée sans réponse)
+pub fn is_broadcast_write(request: &[u8]) -> bool {
+    request
+        .get(FUNCTION_CODE_OFFSET)
+        .is_some_and(|function_code| BROADCAST_WRITE_FUNCTION_CODES.contains(function_code))
+}
+
+/// Comptage des requêtes diffusées d'une connexion dans le tag `ID_TAG_SIM_MODBUS_BROADCASTS`
+pub fn broadcast_counter(thread_db: Arc<Mutex<Database>>, debug_level: u8) -> BroadcastHook {
+    Box::new(move |request| {
+        lock_database(&thread_db, Subsystem::Modbus)
+            .increment_sim_tag(ID_TAG_SIM_MODBUS_BROADCASTS);
+        if debug_level > 1 {
+            println!(
+                "Server MODBUS/TCP: Broadcast request {}",
+                format_hex_frame(request)
+            );
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_broadcast_write() {
+        let write = [0, 1, 0, 0, 0, 6, 0, 0x06, 0, 0x10, 0x12, 0x34];
+        assert!(is_broadcast(&write));
+        assert!(is_broadcast_write(&write));
+
+        let read = [0, 1, 0, 0, 0, 6, 0, 0x03, 0, 0x10, 0, 1];
+        assert!(is_broadcast(&read));
+        assert!(!is_broadcast_write(&read));
+
+        let unicast = [0, 1, 0, 0, 0, 6, 1, 0x06, 0, 0x10, 0x12, 0x34];
+        assert!(!is_broadcast(&unicast));
+    }
+}
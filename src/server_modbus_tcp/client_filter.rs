@@ -0,0 +1,247 @@
+//! Filtrage des clients MODBUS/TCP selon leur adresse IP
+//!
+//! Sur un réseau de laboratoire, un scanner égaré peut se connecter au simulateur et écrire
+//! n'importe quoi dans la table MODBUS. Les connexions sont filtrées à l'acceptation
+//! (`on_connected`) selon des listes de réseaux autorisés (option `--modbus-allow`) et refusés
+//! (option `--modbus-deny`) :
+//!
+//! * Un client d'un réseau refusé est toujours rejeté
+//! * Si des réseaux sont autorisés, un client hors de ces réseaux est rejeté
+//! * Sans aucun réseau défini, tous les clients sont acceptés (comportement historique)
+//!
+//! Chaque connexion rejetée est tracée et comptée dans le tag
+//! `ID_TAG_SIM_MODBUS_REJECTED_CONNECTIONS`.
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::database::{Database, ID_TAG_SIM_MODBUS_REJECTED_CONNECTIONS};
+use crate::profiling::{lock_database, Subsystem};
+
+/// Réseau IPv4 ou IPv6 (adresse et longueur du préfixe)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpNetwork {
+    /// Adresse du réseau
+    addr: IpAddr,
+
+    /// Nombre de bits du préfixe
+    prefix_len: u8,
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    /// Accepte une adresse (`192.168.1.10`, `::1`) ou un réseau (`192.168.1.0/24`, `fd00::/8`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Réseau '{s}' incorrect (ex: '192.168.1.0/24' ou '10.0.0.5')");
+        let (addr, option_prefix_len) = match s.trim().split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s.trim(), None),
+        };
+        let addr = IpAddr::from_str(addr).map_err(|_| err())?;
+        let max_prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match option_prefix_len {
+            Some(prefix_len) => prefix_len.trim().parse::<u8>().map_err(|_| err())?,
+            None => max_prefix_len,
+        };
+        if prefix_len > max_prefix_len {
+            return Err(err());
+        }
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl IpNetwork {
+    /// Indique si une adresse IP appartient au réseau (une adresse IPv4 est reconnue sous sa
+    /// forme IPv6 'mappée' d'un serveur à l'écoute en IPv6)
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Listes des réseaux des clients autorisés et refusés
+#[derive(Clone, Debug, Default)]
+pub struct ClientFilter {
+    /// Réseaux autorisés (tous les clients si vide)
+    allowed: Vec<IpNetwork>,
+
+    /// Réseaux refusés
+    denied: Vec<IpNetwork>,
+}
+
+impl ClientFilter {
+    /// Filtre selon les définitions des réseaux autorisés et refusés
+    /// # Errors
+    /// Message d'erreur si une définition de réseau est incorrecte
+    pub fn from_specs(allowed: &[String], denied: &[String]) -> Result<Self, String> {
+        let parse = |specs: &[String]| {
+            specs
+                .iter()
+                .map(|spec| IpNetwork::from_str(spec))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            allowed: parse(allowed)?,
+            denied: parse(denied)?,
+        })
+    }
+
+    /// Indique si aucun réseau n'est défini (tous les clients sont acceptés)
+    pub fn is_empty(&self) -> bool {
+        self.allowed.is_empty() && self.denied.is_empty()
+    }
+
+    /// Indique si un client est accepté
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        if self.denied.iter().any(|network| network.contains(ip)) {
+            return false;
+        }
+        self.allowed.is_empty() || self.allowed.iter().any(|network| network.contains(ip))
+    }
+}
+
+impl fmt::Display for ClientFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |networks: &[IpNetwork]| {
+            networks
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match (self.allowed.is_empty(), self.denied.is_empty()) {
+            (true, true) => write!(f, "tous les clients acceptés"),
+            (false, true) => write!(f, "autorisés {}", join(&self.allowed)),
+            (true, false) => write!(f, "refusés {}", join(&self.denied)),
+            (false, false) => write!(
+                f,
+                "autorisés {}, refusés {}",
+                join(&self.allowed),
+                join(&self.denied)
+            ),
+        }
+    }
+}
+
+/// Contrôle d'un client à l'acceptation de sa connexion : une connexion rejetée est tracée et
+/// comptée dans le tag `ID_TAG_SIM_MODBUS_REJECTED_CONNECTIONS`
+/// Retourne true si la connexion est acceptée
+pub fn accept_client(
+    thread_db: &Arc<Mutex<Database>>,
+    client_filter: &ClientFilter,
+    peer_addr: SocketAddr,
+) -> bool {
+    if client_filter.is_allowed(peer_addr.ip()) {
+        return true;
+    }
+    lock_database(thread_db, Subsystem::Modbus)
+        .increment_sim_tag(ID_TAG_SIM_MODBUS_REJECTED_CONNECTIONS);
+    println!("Server MODBUS/TCP: Connection from {peer_addr} rejected ({client_filter})");
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    fn ip(s: &str) -> IpAddr {
+        IpAddr::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_ip_network() {
+        let network = IpNetwork::from_str("192.168.1.0/24").unwrap();
+        assert!(network.contains(ip("192.168.1.200")));
+        assert!(!network.contains(ip("192.168.2.1")));
+        assert!(network.contains(ip("::ffff:192.168.1.7")));
+        assert!(!network.contains(ip("fd00::1")));
+
+        let host = IpNetwork::from_str(" 10.0.0.5 ").unwrap();
+        assert_eq!(host.to_string(), "10.0.0.5/32");
+        assert!(host.contains(ip("10.0.0.5")));
+        assert!(!host.contains(ip("10.0.0.6")));
+
+        assert!(IpNetwork::from_str("0.0.0.0/0")
+            .unwrap()
+            .contains(ip("8.8.8.8")));
+        assert!(IpNetwork::from_str("fd00::/8")
+            .unwrap()
+            .contains(ip("fd12::1")));
+
+        assert!(IpNetwork::from_str("192.168.1.0/33").is_err());
+        assert!(IpNetwork::from_str("192.168.1/24").is_err());
+        assert!(IpNetwork::from_str("lab").is_err());
+    }
+
+    #[test]
+    fn test_client_filter() {
+        let client_filter = ClientFilter::default();
+        assert!(client_filter.is_empty());
+        assert!(client_filter.is_allowed(ip("1.2.3.4")));
+
+        let client_filter = ClientFilter::from_specs(
+            &["192.168.1.0/24".to_string(), "::1".to_string()],
+            &["192.168.1.66".to_string()],
+        )
+        .unwrap();
+        assert!(client_filter.is_allowed(ip("192.168.1.10")));
+        assert!(client_filter.is_allowed(ip("::1")));
+        assert!(!client_filter.is_allowed(ip("192.168.1.66")));
+        assert!(!client_filter.is_allowed(ip("10.0.0.1")));
+
+        // Liste des réseaux refusés seule
+        let client_filter = ClientFilter::from_specs(&[], &["10.0.0.0/8".to_string()]).unwrap();
+        assert!(!client_filter.is_allowed(ip("10.1.2.3")));
+        assert!(client_filter.is_allowed(ip("192.168.1.10")));
+
+        assert!(ClientFilter::from_specs(&["x".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn test_accept_client() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let thread_db = Arc::new(Mutex::new(db));
+        let client_filter = ClientFilter::from_specs(&["127.0.0.1".to_string()], &[]).unwrap();
+        assert!(accept_client(
+            &thread_db,
+            &client_filter,
+            "127.0.0.1:5000".parse().unwrap()
+        ));
+        assert!(!accept_client(
+            &thread_db,
+            &client_filter,
+            "192.168.1.10:5000".parse().unwrap()
+        ));
+        let db = thread_db.lock().unwrap();
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_MODBUS_REJECTED_CONNECTIONS),
+            1
+        );
+    }
+}
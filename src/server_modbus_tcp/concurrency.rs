@@ -0,0 +1,74 @@
+//! Traitement concurrent des requêtes MODBUS/TCP
+//!
+//! Les accès à la [`Database`] d'une requête sont exécutés dans une tâche du 'pool' bloquant de
+//! `tokio` (`spawn_blocking`) et non plus dans la tâche de la connexion : une longue écriture en
+//! masse (qui détient le verrou de la [`Database`]) ne bloque pas la réception des requêtes des
+//! autres connexions.
+//!
+//! Le nombre de requêtes en cours de traitement (toutes connexions confondues) est limité par un
+//! [`RequestLimiter`] partagé. Les requêtes d'une même connexion restent traitées dans l'ordre de
+//! réception (la réponse d'une requête est attendue avant le traitement de la suivante).
+//!
+//! [`Database`]: crate::database::Database
+
+use std::io;
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+
+/// Nombre max. par défaut de requêtes MODBUS/TCP traitées simultanément
+pub const DEFAULT_MODBUS_MAX_CONCURRENCY: usize = 4;
+
+/// Nombre de requêtes traitées simultanément pour un max. (0 pour un nombre illimité)
+fn nb_permits(max_concurrency: usize) -> usize {
+    match max_concurrency {
+        0 => Semaphore::MAX_PERMITS,
+        max_concurrency => max_concurrency.min(Semaphore::MAX_PERMITS),
+    }
+}
+
+/// Limitation du nombre de requêtes MODBUS/TCP traitées simultanément (partagé par toutes les
+/// connexions)
+#[derive(Clone)]
+pub struct RequestLimiter {
+    semaphore: Arc<Semaphore>,
+}
+
+impl RequestLimiter {
+    /// Constructeur pour `max_concurrency` requêtes simultanées (0 pour un nombre illimité)
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(nb_permits(max_concurrency))),
+        }
+    }
+
+    /// Exécute un traitement dans le 'pool' bloquant de `tokio` dès qu'une place se libère
+    /// # Errors
+    /// Erreur si le traitement n'a pas pu aboutir (tâche interrompue)
+    pub async fn run_blocking<F, R>(&self, f: F) -> io::Result<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let _permit = Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .await
+            .map_err(io::Error::other)?;
+        tokio::task::spawn_blocking(f)
+            .await
+            .map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nb_permits() {
+        assert_eq!(nb_permits(DEFAULT_MODBUS_MAX_CONCURRENCY), 4);
+        assert_eq!(nb_permits(1), 1);
+        assert_eq!(nb_permits(0), Semaphore::MAX_PERMITS);
+        assert_eq!(nb_permits(usize::MAX), Semaphore::MAX_PERMITS);
+    }
+}
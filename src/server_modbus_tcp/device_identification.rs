@@ -0,0 +1,150 @@
+//! Identification de l'équipement MODBUS (fonction 43 / MEI 14 `Read Device Identification`)
+//!
+//! Les objets publiés sont le fabricant (0x00), le code produit (0x01), la version (0x02) et le
+//! nom du produit (0x04). Si l'identité du simulateur est définie (voir le module
+//! `sim_identity` de la `Database`), le numéro de série (0x80) et l'adresse 'MAC' (0x81) sont
+//! publiés en objets étendus.
+//!
+//! Tous les objets tiennent dans une seule réponse (pas de suite `more follows`).
+
+use crate::build_info;
+use crate::database::{format_mac_address, Database};
+
+use super::MODBUS_EXCEPTION_ILLEGAL_DATA_ADDRESS;
+
+/// Code fonction MODBUS `Encapsulated Interface Transport`
+pub const MODBUS_FUNCTION_MEI: u8 = 0x2B;
+
+/// Type MEI `Read Device Identification`
+const MEI_READ_DEVICE_ID: u8 = 0x0E;
+
+/// Code d'exception MODBUS `ILLEGAL DATA VALUE`
+const MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE: u8 = 0x03;
+
+/// Code d'exception MODBUS `ILLEGAL FUNCTION`
+const MODBUS_EXCEPTION_ILLEGAL_FUNCTION: u8 = 0x01;
+
+/// Niveau de conformité : identifications basique, régulière et étendue, accès individuel
+const CONFORMITY_LEVEL: u8 = 0x83;
+
+/// Nom du fabricant publié
+const VENDOR_NAME: &str = "ddurler";
+
+/// Nom du produit publié
+const PRODUCT_NAME: &str = "Simulateur ICOM";
+
+/// Objets d'identification de l'équipement (identifiant et valeur, dans l'ordre des
+/// identifiants)
+fn device_objects(db: &Database) -> Vec<(u8, String)> {
+    let mut objects = vec![
+        (0x00, VENDOR_NAME.to_string()),
+        (0x01, build_info::NAME.to_string()),
+        (0x02, build_info::VERSION.to_string()),
+        (0x04, PRODUCT_NAME.to_string()),
+    ];
+    if let Some(identity) = db.get_sim_identity() {
+        objects.push((0x80, identity.serial_number.clone()));
+        objects.push((0x81, format_mac_address(&identity.mac_address)));
+    }
+    objects
+}
+
+/// Données de la réponse à une requête `Read Device Identification` (`data` et réponse sans le
+/// code fonction)
+/// # Errors
+/// Code de l'exception MODBUS à retourner si la requête est incorrecte
+pub fn device_identification_response(db: &Database, data: &[u8]) -> Result<Vec<u8>, u8> {
+    let [mei_type, read_code, object_id] = data else {
+        return Err(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE);
+    };
+    if *mei_type != MEI_READ_DEVICE_ID {
+        return Err(MODBUS_EXCEPTION_ILLEGAL_FUNCTION);
+    }
+    let objects = device_objects(db);
+    let selected: Vec<&(u8, String)> = match read_code {
+        // Accès par catégorie : basique, régulière et étendue
+        1..=3 => {
+            let last_id = match read_code {
+                1 => 0x02,
+                2 => 0x7F,
+                _ => 0xFF,
+            };
+            let first_id = if *object_id <= last_id { *object_id } else { 0 };
+            objects
+                .iter()
+                .filter(|(id, _)| (first_id..=last_id).contains(id))
+                .collect()
+        }
+        // Accès individuel
+        4 => {
+            let selected: Vec<_> = objects.iter().filter(|(id, _)| id == object_id).collect();
+            if selected.is_empty() {
+                return Err(MODBUS_EXCEPTION_ILLEGAL_DATA_ADDRESS);
+            }
+            selected
+        }
+        _ => return Err(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE),
+    };
+    let nb_objects = u8::try_from(selected.len()).unwrap_or(u8::MAX);
+    let mut response = vec![
+        MEI_READ_DEVICE_ID,
+        *read_code,
+        CONFORMITY_LEVEL,
+        0x00,
+        0x00,
+        nb_objects,
+    ];
+    for (id, value) in selected {
+        response.push(*id);
+        response.push(u8::try_from(value.len()).unwrap_or(u8::MAX));
+        response.extend_from_slice(value.as_bytes());
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::SimIdentity;
+
+    #[test]
+    fn test_device_identification() {
+        let mut db = Database::default();
+
+        // Identification basique
+        let response = device_identification_response(&db, &[0x0E, 1, 0]).unwrap();
+        assert_eq!(response[..6], [0x0E, 1, CONFORMITY_LEVEL, 0, 0, 3]);
+        assert_eq!(
+            response[6..8],
+            [0x00, u8::try_from(VENDOR_NAME.len()).unwrap()]
+        );
+
+        // Pas d'objets étendus sans identité
+        let response = device_identification_response(&db, &[0x0E, 3, 0]).unwrap();
+        assert_eq!(response[5], 4);
+        assert_eq!(
+            device_identification_response(&db, &[0x0E, 4, 0x80]),
+            Err(MODBUS_EXCEPTION_ILLEGAL_DATA_ADDRESS)
+        );
+
+        db.set_sim_identity(SimIdentity::with_serial_number("BENCH-01").unwrap());
+        let response = device_identification_response(&db, &[0x0E, 3, 0x80]).unwrap();
+        assert_eq!(response[5], 2);
+        let response = device_identification_response(&db, &[0x0E, 4, 0x80]).unwrap();
+        assert_eq!(
+            response[5..],
+            [[1, 0x80, 8].as_slice(), b"BENCH-01"].concat()
+        );
+
+        assert_eq!(
+            device_identification_response(&db, &[0x0E, 5, 0]),
+            Err(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE)
+        );
+        assert_eq!(
+            device_identification_response(&db, &[0x0D, 1, 0]),
+            Err(MODBUS_EXCEPTION_ILLEGAL_FUNCTION)
+        );
+        assert!(device_identification_response(&db, &[0x0E, 1]).is_err());
+    }
+}
@@ -0,0 +1,626 @@
+//! Serveur TCP pour les requêtes MODBUS/TCP dans la [`Database`]
+//!
+//! Les requêtes 'pipelinées' d'une connexion sont traitées dans l'ordre de réception avec une
+//! profondeur éventuellement limitée (voir le module `pipelining`)
+//!
+//! Les accès à la [`Database`] sont exécutés hors de la tâche de la connexion avec un nombre
+//! limité de requêtes traitées simultanément (voir le module `concurrency`)
+//!
+//! Chaque connexion dispose de son propre [`IdUser`] (groupe `GROUP_MODBUS`), libéré à la fin de
+//! la connexion et de ses requêtes en cours de traitement avec la mise à jour du nombre de
+//! connexions (tag `ID_TAG_SIM_MODBUS_CONNECTIONS`).
+//! Le 'keepalive' TCP des connexions permet de détecter les clients déconnectés brutalement.
+//!
+//! Le serveur écoute sur une ou plusieurs adresses IPv4 ou IPv6 (voir le module `bind`).
+//!
+//! Les écritures à des adresses sans [`Tag`] sont traitées selon une [`UndefinedWritePolicy`]
+//! (voir le module `undefined_writes`).
+//!
+//! Les requêtes diffusées (unité 0) sont optionnellement traitées sans réponse (voir le module
+//! `broadcast`).
+//!
+//! Les connexions des clients sont filtrées selon leur adresse IP (voir le module
+//! `client_filter`).
+//!
+//! L'identification de l'équipement (fonction 43 / MEI 14) publie la version et l'identité du
+//! simulateur (voir le module `device_identification`).
+//!
+//! Les réponses d'exception émises sont comptées dans le tag `ID_TAG_SIM_MODBUS_EXCEPTIONS` (voir
+//! le module `error_budget`).
+//!
+//! Un serveur MODBUS/UDP optionnel traite les requêtes des testeurs MODBUS sur UDP avec le même
+//! [`DatabaseService`] (voir le module `udp`).
+//!
+//! [`Tag`]: crate::database::Tag
+
+//Le code ci-dessous est très largement inspiré de
+//(ce dépôt)[https://github.com/slowtec/tokio-modbus/blob/main/examples/tcp-server.rs]
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{future, FutureExt};
+
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_modbus::prelude::*;
+use tokio_modbus::server::tcp::{accept_tcp_connection, Server};
+
+use crate::database::{
+    Database, IdUser, ReadCache, GROUP_MODBUS, ID_TAG_SIM_MODBUS_CONNECTIONS,
+    ID_TAG_SIM_MODBUS_EXCEPTIONS,
+};
+
+mod pipelining;
+use crate::profiling::{lock_database, Subsystem};
+use crate::test_summary;
+pub use pipelining::{
+    ExceptionHook, PipelinedStream, RequestFilter, MODBUS_EXCEPTION_SERVER_DEVICE_BUSY,
+};
+
+mod broadcast;
+pub use broadcast::{broadcast_counter, BroadcastHook, MODBUS_BROADCAST_UNIT_ID};
+
+mod bind;
+pub use bind::{parse_bind_address, parse_bind_addresses, DEFAULT_BIND_ADDRESS};
+
+mod concurrency;
+pub use concurrency::{RequestLimiter, DEFAULT_MODBUS_MAX_CONCURRENCY};
+
+mod client_filter;
+pub use client_filter::{accept_client, ClientFilter, IpNetwork};
+
+mod device_identification;
+pub use device_identification::{device_identification_response, MODBUS_FUNCTION_MEI};
+
+mod undefined_writes;
+pub use undefined_writes::{
+    strict_request_filter, UndefinedWritePolicy, MODBUS_EXCEPTION_ILLEGAL_DATA_ADDRESS,
+};
+
+mod udp;
+pub use udp::{modbus_udp_server_process, UdpFraming};
+
+/// Adresse MODBUS max: Sans effet pour toutes les actions après cette adresse mots
+pub const MODBUS_TOP_WORD_ADDRESS: u16 = 0x8000;
+
+/// Délai (en secondes) entre 2 sondes 'keepalive' TCP sans réponse (voir `set_keepalive`)
+const KEEPALIVE_INTERVAL_SECS: u64 = 10;
+
+/// Active le 'keepalive' TCP d'une connexion acceptée : des sondes sont émises après `time_secs`
+/// secondes sans échange pour détecter un client déconnecté brutalement (0 pour ne pas activer)
+/// # Errors
+/// Erreur si le 'keepalive' ne peut être configuré sur la connexion
+pub fn set_keepalive(stream: &TcpStream, time_secs: u64) -> std::io::Result<()> {
+    if time_secs == 0 {
+        return Ok(());
+    }
+    let keepalive = TcpKeepalive::new()
+        .with_time(Duration::from_secs(time_secs))
+        .with_interval(Duration::from_secs(KEEPALIVE_INTERVAL_SECS));
+    SockRef::from(stream).set_tcp_keepalive(&keepalive)
+}
+
+/// Comptage des réponses d'exception d'une connexion dans le tag `ID_TAG_SIM_MODBUS_EXCEPTIONS`
+pub fn exception_counter(thread_db: Arc<Mutex<Database>>, debug_level: u8) -> ExceptionHook {
+    Box::new(move |exception_code| {
+        lock_database(&thread_db, Subsystem::Modbus)
+            .increment_sim_tag(ID_TAG_SIM_MODBUS_EXCEPTIONS);
+        if debug_level > 1 {
+            println!("Server MODBUS/TCP: Exception response (code 0x{exception_code:02X})");
+        }
+    })
+}
+
+/// Configuration du serveur MODBUS/TCP (voir `modbus_server_process`)
+#[derive(Clone, Debug)]
+pub struct ModbusServerConfig {
+    /// Adresses d'écoute (voir le module `bind`)
+    pub socket_addrs: Vec<SocketAddr>,
+
+    /// Niveau de debug
+    pub debug_level: u8,
+
+    /// Nombre max. de requêtes 'pipelinées' en attente de réponse sur une connexion (0 pour une
+    /// profondeur illimitée, voir le module `pipelining`)
+    pub max_pipelining: usize,
+
+    /// Délai (en secondes) sans échange avant les sondes 'keepalive' TCP (0 pour désactiver,
+    /// voir `set_keepalive`)
+    pub keepalive_secs: u64,
+
+    /// Traitement des écritures à des adresses sans tag (voir le module `undefined_writes`)
+    pub undefined_write_policy: UndefinedWritePolicy,
+
+    /// Nombre max. de requêtes traitées simultanément, toutes connexions confondues (0 pour un
+    /// nombre illimité, voir le module `concurrency`)
+    pub max_concurrency: usize,
+
+    /// Décompte des requêtes diffusées (voir le module `broadcast`)
+    pub is_broadcast: bool,
+
+    /// Réseaux des clients autorisés et refusés (voir le module `client_filter`)
+    pub client_filter: ClientFilter,
+}
+
+impl ModbusServerConfig {
+    /// Configuration par défaut du serveur à l'écoute sur les adresses `socket_addrs`
+    pub fn new(socket_addrs: Vec<SocketAddr>, debug_level: u8) -> Self {
+        Self {
+            socket_addrs,
+            debug_level,
+            max_pipelining: 0,
+            keepalive_secs: 0,
+            undefined_write_policy: UndefinedWritePolicy::default(),
+            max_concurrency: DEFAULT_MODBUS_MAX_CONCURRENCY,
+            is_broadcast: false,
+            client_filter: ClientFilter::default(),
+        }
+    }
+}
+
+/// Serveur MODBUS/TCP à l'écoute sur chacune des adresses de la configuration
+/// # Errors
+/// Erreur si le serveur ne peut pas être démarré sur une des adresses d'écoute
+pub async fn modbus_server_process(
+    shared_db: Arc<Mutex<Database>>,
+    config: &ModbusServerConfig,
+) -> anyhow::Result<()> {
+    // Limitation partagée par toutes les connexions de toutes les adresses d'écoute
+    let request_limiter = RequestLimiter::new(config.max_concurrency);
+    let listeners: Vec<_> = config
+        .socket_addrs
+        .iter()
+        .map(|socket_addr| {
+            modbus_server_listener(
+                Arc::clone(&shared_db),
+                *socket_addr,
+                config,
+                request_limiter.clone(),
+            )
+        })
+        .collect();
+    future::try_join_all(listeners).await?;
+    Ok(())
+}
+
+/// Serveur MODBUS/TCP à l'écoute sur `socket_addr`
+/// # Errors
+/// Erreur si le serveur ne peut pas être démarré sur `socket_addr`
+async fn modbus_server_listener(
+    shared_db: Arc<Mutex<Database>>,
+    socket_addr: SocketAddr,
+    config: &ModbusServerConfig,
+    request_limiter: RequestLimiter,
+) -> anyhow::Result<()> {
+    let ModbusServerConfig {
+        debug_level,
+        max_pipelining,
+        keepalive_secs,
+        undefined_write_policy,
+        is_broadcast,
+        ..
+    } = *config;
+    let client_filter = &config.client_filter;
+    println!("Starting up server on {socket_addr}");
+    let listener = TcpListener::bind(socket_addr).await?;
+    let server = Server::new(listener);
+    // Un service (et un id_user) par connexion, libéré à la fin de la connexion
+    let new_service = |peer_addr| {
+        let thread_db = Arc::clone(&shared_db);
+        Ok(Some(
+            DatabaseService::new(thread_db, peer_addr, debug_level)
+                .with_undefined_write_policy(undefined_write_policy)
+                .with_request_limiter(request_limiter.clone()),
+        ))
+    };
+    // Chaque connexion d'un client accepté (voir le module `client_filter`) détecte les clients
+    // déconnectés brutalement ('keepalive' TCP), limite la profondeur des requêtes 'pipelinées'
+    // et refuse éventuellement les écritures à des adresses sans tag. Les réponses d'exception
+    // et les requêtes diffusées sont comptées
+    let on_connected = |stream, socket_addr| {
+        let is_accepted = accept_client(&shared_db, client_filter, socket_addr);
+        let option_request_filter = (undefined_write_policy == UndefinedWritePolicy::Strict)
+            .then(|| strict_request_filter(Arc::clone(&shared_db), debug_level));
+        let exception_hook = exception_counter(Arc::clone(&shared_db), debug_level);
+        let option_broadcast_hook =
+            is_broadcast.then(|| broadcast_counter(Arc::clone(&shared_db), debug_level));
+        async move {
+            if !is_accepted {
+                // La connexion est fermée à la libération du `stream`
+                return Ok(None);
+            }
+            if let Err(e) = set_keepalive(&stream, keepalive_secs) {
+                eprintln!("Server MODBUS/TCP: TCP keepalive not set for {socket_addr}: {e}");
+            }
+            accept_tcp_connection(stream, socket_addr, new_service).map(|option_connection| {
+                option_connection.map(|(service, stream)| {
+                    (
+                        service,
+                        PipelinedStream::new(stream, max_pipelining, debug_level)
+                            .with_request_filter(option_request_filter)
+                            .with_exception_hook(Some(exception_hook))
+                            .with_broadcast_hook(option_broadcast_hook),
+                    )
+                })
+            })
+        }
+    };
+    let on_process_error = |err| {
+        eprintln!("{err}");
+    };
+    server.serve(&on_connected, on_process_error).await?;
+    Ok(())
+}
+
+/// Wrapper de [`Database`] pour le serveur MODBUS/TCP (un par connexion)
+pub struct DatabaseService {
+    context: RequestContext,
+    request_limiter: RequestLimiter,
+}
+
+/// Contexte du traitement des requêtes d'une connexion (copié dans les tâches du 'pool' bloquant,
+/// voir le module `concurrency`)
+#[derive(Clone)]
+struct RequestContext {
+    thread_db: Arc<Mutex<Database>>,
+    id_user: IdUser,
+    debug_level: u8,
+    read_cache: Option<Arc<ReadCache>>,
+    undefined_write_policy: UndefinedWritePolicy,
+
+    /// Utilisateur de la connexion, libéré à la fin de la connexion et de toutes ses requêtes
+    /// en cours de traitement
+    _connection_user: Arc<ConnectionUser>,
+}
+
+/// [`IdUser`] d'une connexion (ou du serveur MODBUS/UDP)
+/// L'[`IdUser`] n'est libéré (et peut être réattribué à une nouvelle connexion) qu'à la
+/// libération de la dernière copie du [`RequestContext`] : les écritures des requêtes encore en
+/// cours de traitement après la fin de la connexion restent attribuées à ce client
+struct ConnectionUser {
+    thread_db: Arc<Mutex<Database>>,
+    id_user: IdUser,
+    peer_addr: SocketAddr,
+    debug_level: u8,
+
+    /// Connexion comptée dans `ID_TAG_SIM_MODBUS_CONNECTIONS` (MODBUS/TCP)
+    is_connection: bool,
+}
+
+impl DatabaseService {
+    /// Constructeur pour une nouvelle connexion d'un client `peer_addr`
+    /// Les modifications faites par les clients MODBUS/TCP sont regroupées dans le groupe
+    /// `GROUP_MODBUS` et ne sont pas re-notifiées côté MODBUS (elles restent notifiées à l'AFSEC+)
+    pub fn new(thread_db: Arc<Mutex<Database>>, peer_addr: SocketAddr, debug_level: u8) -> Self {
+        Self::with_user(
+            thread_db,
+            &format!("Server MODBUS/TCP {peer_addr}"),
+            peer_addr,
+            true,
+            debug_level,
+        )
+    }
+
+    /// Constructeur du service unique du serveur MODBUS/UDP à l'écoute sur `socket_addr` (voir
+    /// le module `udp`) : pas de connexion, son utilisateur n'est pas compté dans
+    /// `ID_TAG_SIM_MODBUS_CONNECTIONS`
+    pub fn new_udp(
+        thread_db: Arc<Mutex<Database>>,
+        socket_addr: SocketAddr,
+        debug_level: u8,
+    ) -> Self {
+        Self::with_user(
+            thread_db,
+            &format!("Server MODBUS/UDP {socket_addr}"),
+            socket_addr,
+            false,
+            debug_level,
+        )
+    }
+
+    /// Constructeur avec un nouvel utilisateur `user_name` du groupe `GROUP_MODBUS`
+    fn with_user(
+        thread_db: Arc<Mutex<Database>>,
+        user_name: &str,
+        peer_addr: SocketAddr,
+        is_connection: bool,
+        debug_level: u8,
+    ) -> Self {
+        let mut db = lock_database(&thread_db, Subsystem::Modbus);
+        let id_user = db.get_id_user_in_group(user_name, false, GROUP_MODBUS);
+        db.exclude_group_changes(id_user, GROUP_MODBUS);
+        if is_connection {
+            db.increment_sim_tag(ID_TAG_SIM_MODBUS_CONNECTIONS);
+            if debug_level > 0 {
+                println!(
+                    "Server MODBUS/TCP: Connection from {peer_addr} ({} connection(s))",
+                    db.get_u16_from_id_tag(id_user, ID_TAG_SIM_MODBUS_CONNECTIONS)
+                );
+            }
+        }
+        let read_cache = db.get_read_cache();
+        drop(db);
+        let connection_user = ConnectionUser {
+            thread_db: Arc::clone(&thread_db),
+            id_user,
+            peer_addr,
+            debug_level,
+            is_connection,
+        };
+        Self {
+            context: RequestContext {
+                thread_db,
+                id_user,
+                debug_level,
+                read_cache,
+                undefined_write_policy: UndefinedWritePolicy::default(),
+                _connection_user: Arc::new(connection_user),
+            },
+            request_limiter: RequestLimiter::new(DEFAULT_MODBUS_MAX_CONCURRENCY),
+        }
+    }
+
+    /// Politique des écritures à des adresses sans [`Tag`]
+    ///
+    /// [`Tag`]: crate::database::Tag
+    #[must_use]
+    pub fn with_undefined_write_policy(
+        mut self,
+        undefined_write_policy: UndefinedWritePolicy,
+    ) -> Self {
+        self.context.undefined_write_policy = undefined_write_policy;
+        self
+    }
+
+    /// Limitation du nombre de requêtes traitées simultanément (partagée avec les autres
+    /// connexions, voir le module `concurrency`)
+    #[must_use]
+    pub fn with_request_limiter(mut self, request_limiter: RequestLimiter) -> Self {
+        self.request_limiter = request_limiter;
+        self
+    }
+}
+
+impl RequestContext {
+    /// Lecture des registres pour une requête `ReadHoldingRegisters`
+    /// Utilise le cache des lectures s'il est activé
+    fn holding_registers_read(&self, addr: u16, cnt: u16) -> Vec<u16> {
+        let Some(read_cache) = &self.read_cache else {
+            return register_read(
+                &lock_database(&self.thread_db, Subsystem::Modbus),
+                self.id_user,
+                self.debug_level,
+                addr,
+                cnt,
+            );
+        };
+        if let Some(values) = read_cache.get(addr, cnt) {
+            if self.debug_level > 1 {
+                println!("Server MODBUS/TCP: Read {cnt} words @{addr:04X} (cache): {values:?}");
+            }
+            return values;
+        }
+        // Lecture et mise en cache avec la database verrouillée
+        let db = lock_database(&self.thread_db, Subsystem::Modbus);
+        let values = register_read(&db, self.id_user, self.debug_level, addr, cnt);
+        read_cache.insert(addr, cnt, &values);
+        values
+    }
+
+    /// Traitement (bloquant) d'une requête MODBUS/TCP
+    fn process(&self, req: Request<'static>) -> Result<Response, std::io::Error> {
+        match req {
+            Request::ReadInputRegisters(addr, cnt) => {
+                test_summary::record_modbus_request("ReadInputRegisters");
+                let values = register_read(
+                    &lock_database(&self.thread_db, Subsystem::Modbus),
+                    self.id_user,
+                    self.debug_level,
+                    addr,
+                    cnt,
+                );
+                Ok(Response::ReadInputRegisters(values))
+            }
+            Request::ReadHoldingRegisters(addr, cnt) => {
+                test_summary::record_modbus_request("ReadHoldingRegisters");
+                let values = self.holding_registers_read(addr, cnt);
+                Ok(Response::ReadHoldingRegisters(values))
+            }
+            Request::WriteMultipleRegisters(addr, values) => {
+                test_summary::record_modbus_request("WriteMultipleRegisters");
+                register_write(
+                    &mut lock_database(&self.thread_db, Subsystem::Modbus),
+                    self.id_user,
+                    self.debug_level,
+                    self.undefined_write_policy,
+                    addr,
+                    &values,
+                );
+                #[allow(clippy::cast_possible_truncation)]
+                Ok(Response::WriteMultipleRegisters(addr, values.len() as u16))
+            }
+            Request::WriteSingleRegister(addr, value) => {
+                test_summary::record_modbus_request("WriteSingleRegister");
+                register_write(
+                    &mut lock_database(&self.thread_db, Subsystem::Modbus),
+                    self.id_user,
+                    self.debug_level,
+                    self.undefined_write_policy,
+                    addr,
+                    std::slice::from_ref(&value),
+                );
+                Ok(Response::WriteSingleRegister(addr, value))
+            }
+            Request::Custom(MODBUS_FUNCTION_MEI, data) => {
+                test_summary::record_modbus_request("ReadDeviceIdentification");
+                let db = lock_database(&self.thread_db, Subsystem::Modbus);
+                match device_identification_response(&db, &data) {
+                    Ok(response) => Ok(Response::Custom(MODBUS_FUNCTION_MEI, response)),
+                    // Réponse d'exception (code fonction avec le bit 0x80)
+                    Err(exception_code) => Ok(Response::Custom(
+                        MODBUS_FUNCTION_MEI | 0x80,
+                        vec![exception_code],
+                    )),
+                }
+            }
+            _ => {
+                eprintln!("Server MODBUS/TCP: Unimplemented function code in request: {req:?} !!!");
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "Unimplemented function code in request".to_string(),
+                ))
+            }
+        }
+    }
+}
+
+impl Drop for ConnectionUser {
+    /// Fin de la connexion (y compris rompue) et de ses requêtes en cours : libération de
+    /// l'[`IdUser`]
+    fn drop(&mut self) {
+        // Pas de panic dans un `drop` si le verrou de la [`Database`] est empoisonné
+        let Ok(mut db) = self.thread_db.lock() else {
+            return;
+        };
+        db.release_id_user(self.id_user);
+        if !self.is_connection {
+            return;
+        }
+        db.decrement_sim_tag(ID_TAG_SIM_MODBUS_CONNECTIONS);
+        if self.debug_level > 0 {
+            println!(
+                "Server MODBUS/TCP: Disconnection of {} ({} connection(s))",
+                self.peer_addr,
+                db.get_u16_from_id_tag(self.id_user, ID_TAG_SIM_MODBUS_CONNECTIONS)
+            );
+        }
+    }
+}
+
+impl tokio_modbus::server::Service for DatabaseService {
+    type Request = Request<'static>;
+    type Response = Response;
+    type Error = std::io::Error;
+    type Future = future::Map<
+        tokio::task::JoinHandle<Result<Self::Response, Self::Error>>,
+        fn(ServiceTaskResult) -> Result<Self::Response, Self::Error>,
+    >;
+
+    /// La requête est traitée dans le 'pool' bloquant de `tokio` (voir le module `concurrency`)
+    /// Le traitement est une tâche `tokio` : `tokio_modbus` exige une `Future` `Sync`, ce qu'est
+    /// le `JoinHandle` de la tâche
+    fn call(&self, req: Self::Request) -> Self::Future {
+        let context = self.context.clone();
+        let request_limiter = self.request_limiter.clone();
+        tokio::spawn(async move {
+            request_limiter
+                .run_blocking(move || context.process(req))
+                .await?
+        })
+        .map(join_service_task)
+    }
+}
+
+/// Résultat de la tâche de traitement d'une requête
+type ServiceTaskResult = Result<Result<Response, std::io::Error>, tokio::task::JoinError>;
+
+/// Réponse d'une requête selon le résultat de sa tâche de traitement
+fn join_service_task(result: ServiceTaskResult) -> Result<Response, std::io::Error> {
+    result.map_err(std::io::Error::other)?
+}
+
+/// Helper function implementing reading registers from [`Database`].
+/// Used by both the input registers reading and the holding registers reading
+fn register_read(db: &Database, id_user: IdUser, debug_level: u8, addr: u16, cnt: u16) -> Vec<u16> {
+    let mut response_values = vec![0; cnt.into()];
+    for i in 0..cnt {
+        let reg_addr = addr + i;
+        if reg_addr < MODBUS_TOP_WORD_ADDRESS {
+            response_values[i as usize] = db.get_u16_from_word_address(id_user, reg_addr);
+        } else {
+            eprintln!("Server MODBUS/TCP: Read out of database {addr:04X} !!!");
+        }
+    }
+    if debug_level > 1 {
+        println!("Server MODBUS/TCP: Read {cnt} words @{addr:04X}: {response_values:?}");
+    }
+    response_values
+}
+
+/// Write a holding register. Used by both the write single register
+/// and write multiple registers requests.
+/// Writes to addresses without a tag follow `undefined_write_policy` (the first write to each of
+/// these addresses is logged)
+fn register_write(
+    db: &mut Database,
+    id_user: IdUser,
+    debug_level: u8,
+    undefined_write_policy: UndefinedWritePolicy,
+    addr: u16,
+    values: &[u16],
+) {
+    if debug_level > 1 {
+        println!(
+            "Server MODBUS/TCP: Write {} words @{:04X}: {:?}",
+            values.len(),
+            addr,
+            values
+        );
+    }
+    for (i, value) in values.iter().enumerate() {
+        #[allow(clippy::cast_possible_truncation)]
+        let reg_addr = addr + i as u16;
+        if reg_addr < MODBUS_TOP_WORD_ADDRESS {
+            if !db.is_word_address_defined(reg_addr) {
+                if db.note_undefined_write(reg_addr) {
+                    println!(
+                        "Server MODBUS/TCP: First write to undefined address {reg_addr:04X} ({undefined_write_policy})"
+                    );
+                }
+                if undefined_write_policy == UndefinedWritePolicy::Permissive {
+                    db.add_anonymous_tag(reg_addr);
+                }
+            }
+            // Pas d'exception MODBUS possible depuis le service : l'erreur est tracée
+            if let Err(db_error) = db.set_u16_to_word_address(id_user, reg_addr, *value) {
+                eprintln!("Server MODBUS/TCP: {db_error} !!!");
+            }
+        } else {
+            eprintln!("Server MODBUS/TCP: Write out of database {reg_addr:04X} !!!");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    #[test]
+    fn test_connection_user_release() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let shared_db = Arc::new(Mutex::new(db));
+        let connections = || {
+            lock_database(&shared_db, Subsystem::Other)
+                .get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_MODBUS_CONNECTIONS)
+        };
+        let peer_addr: SocketAddr = "127.0.0.1:5020".parse().unwrap();
+
+        // Requête encore en cours de traitement à la fin de la connexion
+        let service = DatabaseService::new(Arc::clone(&shared_db), peer_addr, 0);
+        assert_eq!(connections(), 1);
+        let in_flight = service.context.clone();
+        drop(service);
+        assert_eq!(connections(), 1);
+        drop(in_flight);
+        assert_eq!(connections(), 0);
+
+        // Le service MODBUS/UDP n'est pas une connexion
+        let _udp_service = DatabaseService::new_udp(Arc::clone(&shared_db), peer_addr, 0);
+        assert_eq!(connections(), 0);
+    }
+}
@@ -0,0 +1,613 @@
+//! Limitation de la profondeur de 'pipelining' des requêtes MODBUS/TCP
+//!
+//! Un client MODBUS/TCP peut envoyer plusieurs requêtes (ADU) sur une même connexion sans
+//! attendre les réponses (c'est le cas des enregistreurs rapides qui envoient jusqu'à 8 requêtes
+//! à la suite). Le serveur `tokio_modbus` traite les requêtes d'une connexion dans l'ordre de
+//! réception et répond dans ce même ordre.
+//!
+//! [`PipelinedStream`] s'intercale entre la connexion TCP et le serveur `tokio_modbus` pour
+//! limiter le nombre de requêtes en attente de réponse : au delà de la profondeur max., une
+//! requête n'est pas transmise au serveur et le client reçoit l'exception
+//! `SERVER DEVICE BUSY` (0x06), à sa place dans l'ordre des réponses.
+//!
+//! Un filtre optionnel des requêtes ([`RequestFilter`]) permet également de refuser une requête
+//! avec une exception, sans la transmettre au serveur (voir le module `undefined_writes`).
+//!
+//! Chaque réponse d'exception émise vers le client (refus ou réponse du serveur) est signalée à
+//! un [`ExceptionHook`] optionnel (comptage pour le budget d'erreurs, voir `exception_counter`).
+//! Les requêtes et les réponses sont également mémorisées pour la capture déclenchée (voir le
+//! module `capture`).
+//!
+//! Les requêtes diffusées (unité 0) sont optionnellement traitées sans réponse (voir le module
+//! `broadcast`).
+//!
+//! Les trames sont délimitées selon l'entête MBAP (7 octets: transaction, protocole, longueur
+//! et unité) des requêtes reçues et des réponses émises par le serveur.
+
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::afsec::format_hex_frame;
+use crate::capture::{self, CaptureKind};
+
+use super::broadcast::{is_broadcast, is_broadcast_write, BroadcastHook};
+
+/// Code d'exception MODBUS `SERVER DEVICE BUSY`
+pub const MODBUS_EXCEPTION_SERVER_DEVICE_BUSY: u8 = 0x06;
+
+/// Taille de l'entête MBAP (transaction: 2, protocole: 2, longueur: 2, unité: 1)
+const MBAP_HEADER_LEN: usize = 7;
+
+/// Taille du buffer de lecture de la connexion
+const READ_BUFFER_LEN: usize = 1024;
+
+/// Filtre des requêtes d'une connexion : Retourne le code d'exception de la réponse si la
+/// requête (trame MODBUS/TCP complète) est refusée
+pub type RequestFilter = Box<dyn FnMut(&[u8]) -> Option<u8> + Send>;
+
+/// Signalement d'une réponse d'exception émise vers le client (code d'exception)
+pub type ExceptionHook = Box<dyn FnMut(u8) + Send>;
+
+/// Requête en attente de réponse
+#[derive(Debug)]
+enum Slot {
+    /// Requête transmise au serveur
+    Forwarded,
+
+    /// Requête refusée (réponse d'exception à émettre à son tour)
+    Rejected(Vec<u8>),
+
+    /// Écriture diffusée transmise au serveur (réponse non émise vers le client)
+    Broadcast,
+}
+
+/// Extrait la prochaine trame complète (selon l'entête MBAP) en tête de `vec_u8`
+/// Les trames incorrectes (sans unité ni code fonction) sont ignorées
+fn take_frame(vec_u8: &mut Vec<u8>) -> Option<Vec<u8>> {
+    loop {
+        if vec_u8.len() < MBAP_HEADER_LEN {
+            return None;
+        }
+        // La longueur de l'entête MBAP compte l'unité et le PDU
+        let frame_len = 6 + usize::from(u16::from_be_bytes([vec_u8[4], vec_u8[5]]));
+        if vec_u8.len() < frame_len {
+            return None;
+        }
+        let frame: Vec<u8> = vec_u8.drain(..frame_len).collect();
+        if frame.len() > MBAP_HEADER_LEN {
+            return Some(frame);
+        }
+    }
+}
+
+/// Code d'exception d'une réponse (None si ce n'est pas une réponse d'exception)
+fn exception_code(response: &[u8]) -> Option<u8> {
+    let function_code = *response.get(MBAP_HEADER_LEN)?;
+    if function_code & 0x80 == 0 {
+        return None;
+    }
+    response.get(MBAP_HEADER_LEN + 1).copied()
+}
+
+/// Réponse d'exception `exception_code` à une requête
+fn exception_response(request: &[u8], exception_code: u8) -> Vec<u8> {
+    let byte_at = |index: usize| request.get(index).copied().unwrap_or_default();
+    // Transaction et protocole
+    let mut response = vec![byte_at(0), byte_at(1), byte_at(2), byte_at(3)];
+    response.extend([0x00, 0x03]); // Longueur: unité + code fonction + code d'exception
+    response.push(byte_at(6)); // Unité
+    response.push(byte_at(MBAP_HEADER_LEN) | 0x80);
+    response.push(exception_code);
+    response
+}
+
+/// Réponse d'exception `SERVER DEVICE BUSY` à une requête
+fn busy_exception(request: &[u8]) -> Vec<u8> {
+    exception_response(request, MODBUS_EXCEPTION_SERVER_DEVICE_BUSY)
+}
+
+/// Suivi des requêtes et des réponses d'une connexion MODBUS/TCP
+struct Pipeline {
+    /// Nombre max. de requêtes transmises au serveur en attente de réponse
+    max_depth: usize,
+
+    /// Niveau de debug pour les affichages (0: None, 1: Some, 2: All)
+    debug_level: u8,
+
+    /// Octets reçus du client et pas encore analysés (trame incomplète)
+    rx_pending: Vec<u8>,
+
+    /// Octets des requêtes acceptées à transmettre au serveur
+    rx_ready: VecDeque<u8>,
+
+    /// Requêtes en attente de réponse (dans l'ordre de réception)
+    slots: VecDeque<Slot>,
+
+    /// Octets émis par le serveur et pas encore analysés (trame incomplète)
+    tx_pending: Vec<u8>,
+
+    /// Octets à émettre vers le client (dans l'ordre des requêtes)
+    tx_ready: VecDeque<u8>,
+
+    /// Filtre optionnel des requêtes
+    option_request_filter: Option<RequestFilter>,
+
+    /// Signalement optionnel des réponses d'exception
+    option_exception_hook: Option<ExceptionHook>,
+
+    /// Traitement des requêtes diffusées (unité 0) avec leur signalement (None pour les traiter
+    /// comme les autres requêtes)
+    option_broadcast_hook: Option<BroadcastHook>,
+}
+
+impl Pipeline {
+    /// Constructeur (`max_depth` = 0 pour une profondeur illimitée)
+    fn new(max_depth: usize, debug_level: u8) -> Self {
+        Self {
+            max_depth: if max_depth == 0 {
+                usize::MAX
+            } else {
+                max_depth
+            },
+            debug_level,
+            rx_pending: vec![],
+            rx_ready: VecDeque::new(),
+            slots: VecDeque::new(),
+            tx_pending: vec![],
+            tx_ready: VecDeque::new(),
+            option_request_filter: None,
+            option_exception_hook: None,
+            option_broadcast_hook: None,
+        }
+    }
+
+    /// Nombre de requêtes transmises au serveur en attente de réponse
+    fn nb_forwarded(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| matches!(slot, Slot::Forwarded | Slot::Broadcast))
+            .count()
+    }
+
+    /// Octets reçus du client
+    fn receive(&mut self, vec_u8: &[u8]) {
+        self.rx_pending.extend_from_slice(vec_u8);
+        while let Some(request) = take_frame(&mut self.rx_pending) {
+            capture::record(CaptureKind::ModbusFrame, || {
+                format!("-> {}", format_hex_frame(&request))
+            });
+            if let Some(broadcast_hook) = self.option_broadcast_hook.as_mut() {
+                if is_broadcast(&request) {
+                    broadcast_hook(&request);
+                    self.receive_broadcast(request);
+                    continue;
+                }
+            }
+            let option_exception_code = self
+                .option_request_filter
+                .as_mut()
+                .and_then(|request_filter| request_filter(&request));
+            if let Some(exception_code) = option_exception_code {
+                self.slots
+                    .push_back(Slot::Rejected(exception_response(&request, exception_code)));
+            } else if self.nb_forwarded() < self.max_depth {
+                self.rx_ready.extend(request);
+                self.slots.push_back(Slot::Forwarded);
+            } else {
+                if self.debug_level > 0 {
+                    println!(
+                        "Server MODBUS/TCP: Pipelining depth overflow (max {}), request {request:02X?} rejected",
+                        self.max_depth
+                    );
+                }
+                self.slots
+                    .push_back(Slot::Rejected(busy_exception(&request)));
+            }
+        }
+        self.release_rejected();
+    }
+
+    /// Requête diffusée reçue du client : Une écriture est transmise au serveur (sauf si elle est
+    /// refusée par le filtre ou au delà de la profondeur max.), aucune réponse n'est émise
+    fn receive_broadcast(&mut self, request: Vec<u8>) {
+        let is_refused = !is_broadcast_write(&request)
+            || self.nb_forwarded() >= self.max_depth
+            || self
+                .option_request_filter
+                .as_mut()
+                .and_then(|request_filter| request_filter(&request))
+                .is_some();
+        if is_refused {
+            if self.debug_level > 0 {
+                println!("Server MODBUS/TCP: Broadcast request {request:02X?} ignored");
+            }
+            return;
+        }
+        self.rx_ready.extend(request);
+        self.slots.push_back(Slot::Broadcast);
+    }
+
+    /// Octets émis par le serveur
+    fn send(&mut self, vec_u8: &[u8]) {
+        self.tx_pending.extend_from_slice(vec_u8);
+        while let Some(response) = take_frame(&mut self.tx_pending) {
+            // Pas de réponse à une écriture diffusée
+            if !matches!(self.slots.pop_front(), Some(Slot::Broadcast)) {
+                self.emit(response);
+            }
+            self.release_rejected();
+        }
+    }
+
+    /// Émission d'une réponse vers le client (signalée si c'est une réponse d'exception)
+    fn emit(&mut self, response: Vec<u8>) {
+        let option_exception_code = exception_code(&response);
+        let kind = match option_exception_code {
+            Some(_) => CaptureKind::ModbusException,
+            None => CaptureKind::ModbusFrame,
+        };
+        capture::record(kind, || format!("<- {}", format_hex_frame(&response)));
+        if let (Some(exception_hook), Some(exception_code)) =
+            (self.option_exception_hook.as_mut(), option_exception_code)
+        {
+            exception_hook(exception_code);
+        }
+        self.tx_ready.extend(response);
+    }
+
+    /// Les réponses d'exception en tête des requêtes en attente sont émises
+    fn release_rejected(&mut self) {
+        while let Some(Slot::Rejected(_)) = self.slots.front() {
+            if let Some(Slot::Rejected(response)) = self.slots.pop_front() {
+                self.emit(response);
+            }
+        }
+    }
+}
+
+/// Connexion MODBUS/TCP avec une profondeur de 'pipelining' limitée
+pub struct PipelinedStream<T> {
+    /// Connexion TCP
+    inner: T,
+
+    /// Suivi des requêtes et des réponses
+    pipeline: Pipeline,
+}
+
+impl<T> PipelinedStream<T> {
+    /// Constructeur (`max_depth` = 0 pour une profondeur illimitée)
+    pub fn new(inner: T, max_depth: usize, debug_level: u8) -> Self {
+        Self {
+            inner,
+            pipeline: Pipeline::new(max_depth, debug_level),
+        }
+    }
+
+    /// Filtre des requêtes : Une requête refusée par le filtre n'est pas transmise au serveur et
+    /// le client reçoit l'exception retournée par le filtre, à sa place dans l'ordre des réponses
+    #[must_use]
+    pub fn with_request_filter(mut self, option_request_filter: Option<RequestFilter>) -> Self {
+        self.pipeline.option_request_filter = option_request_filter;
+        self
+    }
+
+    /// Signalement des réponses d'exception émises vers le client
+    #[must_use]
+    pub fn with_exception_hook(mut self, option_exception_hook: Option<ExceptionHook>) -> Self {
+        self.pipeline.option_exception_hook = option_exception_hook;
+        self
+    }
+
+    /// Traitement des requêtes diffusées (unité 0) sans réponse, avec leur signalement (None
+    /// pour les traiter comme les autres requêtes)
+    #[must_use]
+    pub fn with_broadcast_hook(mut self, option_broadcast_hook: Option<BroadcastHook>) -> Self {
+        self.pipeline.option_broadcast_hook = option_broadcast_hook;
+        self
+    }
+}
+
+impl<T: AsyncWrite + Unpin> PipelinedStream<T> {
+    /// Émission vers le client des octets en attente
+    fn poll_write_ready(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pipeline.tx_ready.is_empty() {
+            let (front, _) = self.pipeline.tx_ready.as_slices();
+            match Pin::new(&mut self.inner).poll_write(cx, front) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(n)) => {
+                    self.pipeline.tx_ready.drain(..n);
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncRead for PipelinedStream<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            // Les réponses d'exception sont émises sans attendre une écriture du serveur
+            if let Poll::Ready(Err(e)) = this.poll_write_ready(cx) {
+                return Poll::Ready(Err(e));
+            }
+
+            if !this.pipeline.rx_ready.is_empty() {
+                let n = buf.remaining().min(this.pipeline.rx_ready.len());
+                let vec_u8: Vec<u8> = this.pipeline.rx_ready.drain(..n).collect();
+                buf.put_slice(&vec_u8);
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut read_buffer = [0_u8; READ_BUFFER_LEN];
+            let mut read_buf = ReadBuf::new(&mut read_buffer);
+            match Pin::new(&mut this.inner).poll_read(cx, &mut read_buf) {
+                Poll::Ready(Ok(())) => {
+                    if read_buf.filled().is_empty() {
+                        // Fin de la connexion
+                        return Poll::Ready(Ok(()));
+                    }
+                    this.pipeline.receive(read_buf.filled());
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> AsyncWrite for PipelinedStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.pipeline.send(buf);
+        if let Poll::Ready(Err(e)) = this.poll_write_ready(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_write_ready(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+            other => other,
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_write_ready(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_shutdown(cx),
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Requête `ReadHoldingRegisters` (transaction `tid`, 1 registre à l'adresse 0)
+    fn request(tid: u8) -> Vec<u8> {
+        vec![0, tid, 0, 0, 0, 6, 1, 0x03, 0, 0, 0, 1]
+    }
+
+    /// Réponse `ReadHoldingRegisters` (transaction `tid`, 1 registre)
+    fn response(tid: u8) -> Vec<u8> {
+        vec![0, tid, 0, 0, 0, 5, 1, 0x03, 2, 0, tid]
+    }
+
+    /// Octets à transmettre au serveur
+    fn take_rx(pipeline: &mut Pipeline) -> Vec<u8> {
+        pipeline.rx_ready.drain(..).collect()
+    }
+
+    /// Octets à émettre vers le client
+    fn take_tx(pipeline: &mut Pipeline) -> Vec<u8> {
+        pipeline.tx_ready.drain(..).collect()
+    }
+
+    #[test]
+    fn test_take_frame() {
+        let mut vec_u8 = request(1);
+        vec_u8.extend(&request(2)[..5]);
+        assert_eq!(take_frame(&mut vec_u8), Some(request(1)));
+        assert_eq!(take_frame(&mut vec_u8), None);
+        assert_eq!(vec_u8.len(), 5);
+
+        // Trames sans code fonction (longueur MBAP 0 ou 1) ignorées
+        let mut vec_u8 = vec![0, 1, 0, 0, 0, 0];
+        vec_u8.extend([0, 2, 0, 0, 0, 1, 1]);
+        vec_u8.extend(request(3));
+        assert_eq!(take_frame(&mut vec_u8), Some(request(3)));
+        assert!(vec_u8.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_request() {
+        let mut pipeline = Pipeline::new(1, 0);
+        pipeline.option_request_filter = Some(Box::new(|_: &[u8]| Some(0x02)));
+
+        // Une trame sans code fonction ne bloque pas la connexion
+        pipeline.receive(&[0, 1, 0, 0, 0, 0]);
+        pipeline.receive(&request(2));
+        assert!(take_rx(&mut pipeline).is_empty());
+        assert_eq!(
+            take_tx(&mut pipeline),
+            vec![0, 2, 0, 0, 0, 3, 1, 0x83, 0x02]
+        );
+        assert_eq!(
+            exception_response(&[0, 3], 0x04),
+            vec![0, 3, 0, 0, 0, 3, 0, 0x80, 0x04]
+        );
+    }
+
+    #[test]
+    fn test_busy_exception() {
+        assert_eq!(
+            busy_exception(&request(7)),
+            vec![
+                0,
+                7,
+                0,
+                0,
+                0,
+                3,
+                1,
+                0x83,
+                MODBUS_EXCEPTION_SERVER_DEVICE_BUSY
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pipelining_in_order() {
+        let mut pipeline = Pipeline::new(0, 0);
+
+        // 8 requêtes à la suite (dont une découpée en 2 segments TCP)
+        let mut requests = vec![];
+        for tid in 1..=8 {
+            requests.extend(request(tid));
+        }
+        pipeline.receive(&requests[..30]);
+        pipeline.receive(&requests[30..]);
+        assert_eq!(take_rx(&mut pipeline), requests);
+
+        // Les réponses sont émises dans l'ordre
+        let mut responses = vec![];
+        for tid in 1..=8 {
+            responses.extend(response(tid));
+        }
+        pipeline.send(&responses[..5]);
+        pipeline.send(&responses[5..]);
+        assert_eq!(take_tx(&mut pipeline), responses);
+        assert!(pipeline.slots.is_empty());
+    }
+
+    #[test]
+    fn test_pipelining_depth() {
+        let mut pipeline = Pipeline::new(2, 0);
+
+        // Le client envoie 3 requêtes à la suite: seules les 2 premières sont transmises
+        let mut requests = request(1);
+        requests.extend(request(2));
+        requests.extend(request(3));
+        pipeline.receive(&requests);
+        assert_eq!(take_rx(&mut pipeline), requests[..24]);
+        assert!(take_tx(&mut pipeline).is_empty());
+
+        // L'exception est émise après les réponses aux 2 premières requêtes
+        pipeline.send(&response(1));
+        pipeline.send(&response(2));
+        let mut expected = response(1);
+        expected.extend(response(2));
+        expected.extend(busy_exception(&request(3)));
+        assert_eq!(take_tx(&mut pipeline), expected);
+
+        // Une nouvelle requête est à nouveau transmise
+        pipeline.receive(&request(4));
+        assert_eq!(take_rx(&mut pipeline), request(4));
+    }
+
+    #[test]
+    fn test_request_filter() {
+        let mut pipeline = Pipeline::new(0, 0);
+        pipeline.option_request_filter =
+            Some(Box::new(|request: &[u8]| (request[1] == 2).then_some(0x02)));
+
+        // La requête 2 est refusée: son exception est émise après la réponse à la requête 1
+        let mut requests = request(1);
+        requests.extend(request(2));
+        requests.extend(request(3));
+        pipeline.receive(&requests);
+        let mut expected = request(1);
+        expected.extend(request(3));
+        assert_eq!(take_rx(&mut pipeline), expected);
+
+        pipeline.send(&response(1));
+        pipeline.send(&response(3));
+        let mut expected = response(1);
+        expected.extend(exception_response(&request(2), 0x02));
+        expected.extend(response(3));
+        assert_eq!(take_tx(&mut pipeline), expected);
+    }
+
+    #[test]
+    fn test_exception_hook() {
+        use std::sync::{Arc, Mutex};
+
+        let exception_codes = Arc::new(Mutex::new(vec![]));
+        let mut pipeline = Pipeline::new(1, 0);
+        let hook_codes = Arc::clone(&exception_codes);
+        pipeline.option_exception_hook = Some(Box::new(move |exception_code| {
+            hook_codes.lock().unwrap().push(exception_code);
+        }));
+
+        // Requête 2 refusée (profondeur) puis réponse d'exception du serveur à la requête 1
+        let mut requests = request(1);
+        requests.extend(request(2));
+        pipeline.receive(&requests);
+        assert!(exception_codes.lock().unwrap().is_empty());
+        pipeline.send(&[0, 1, 0, 0, 0, 3, 1, 0x83, 0x02]);
+        assert_eq!(
+            *exception_codes.lock().unwrap(),
+            vec![0x02, MODBUS_EXCEPTION_SERVER_DEVICE_BUSY]
+        );
+
+        // Réponse normale : pas de signalement
+        pipeline.receive(&request(3));
+        pipeline.send(&response(3));
+        assert_eq!(exception_codes.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_broadcast() {
+        use std::sync::{Arc, Mutex};
+
+        let nb_broadcasts = Arc::new(Mutex::new(0));
+        let mut pipeline = Pipeline::new(0, 0);
+        let hook_broadcasts = Arc::clone(&nb_broadcasts);
+        pipeline.option_broadcast_hook = Some(Box::new(move |_| {
+            *hook_broadcasts.lock().unwrap() += 1;
+        }));
+
+        // Écriture diffusée entre 2 lectures, puis lecture diffusée
+        let write = vec![0, 2, 0, 0, 0, 6, 0, 0x06, 0, 0x10, 0x12, 0x34];
+        let read = vec![0, 4, 0, 0, 0, 6, 0, 0x03, 0, 0, 0, 1];
+        let mut requests = request(1);
+        requests.extend(&write);
+        requests.extend(request(3));
+        requests.extend(&read);
+        pipeline.receive(&requests);
+        assert_eq!(*nb_broadcasts.lock().unwrap(), 2);
+
+        // L'écriture est transmise au serveur, pas la lecture
+        let mut expected = request(1);
+        expected.extend(&write);
+        expected.extend(request(3));
+        assert_eq!(take_rx(&mut pipeline), expected);
+
+        // La réponse du serveur à l'écriture n'est pas émise vers le client
+        pipeline.send(&response(1));
+        pipeline.send(&write);
+        pipeline.send(&response(3));
+        let mut expected = response(1);
+        expected.extend(response(3));
+        assert_eq!(take_tx(&mut pipeline), expected);
+        assert!(pipeline.slots.is_empty());
+    }
+}
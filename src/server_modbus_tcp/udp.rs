@@ -0,0 +1,429 @@
+//! Serveur MODBUS/UDP optionnel (option `--modbus-udp`)
+//!
+//! Certains testeurs anciens du banc interrogent l'ICOM en MODBUS sur UDP. Chaque datagramme
+//! reçu contient une requête complète, selon l'un des 2 tramages ([`UdpFraming`]) :
+//!
+//! * `rtu` : Trame MODBUS RTU (unité, PDU et CRC-16/MODBUS, octet de poids faible en tête). Un
+//!   datagramme dont le CRC est incorrect est ignoré
+//! * `mbap` : Trame MODBUS/TCP (entête MBAP et PDU), la transaction est reprise dans la réponse
+//!
+//! Les requêtes sont traitées par un [`DatabaseService`] unique pour tous les clients (il n'y a
+//! pas de connexion en UDP, voir `DatabaseService::new_udp`), comme celles du serveur
+//! MODBUS/TCP : mêmes fonctions supportées, même politique des écritures à des adresses sans
+//! tag et même filtrage des clients (les datagrammes des clients refusés sont ignorés). Les
+//! requêtes sont traitées une par une, dans l'ordre de réception des datagrammes. Les requêtes
+//! diffusées (unité 0) et les réponses d'exception sont comptées comme en MODBUS/TCP.
+//!
+//! Les écritures sont attribuées à l'utilisateur `Server MODBUS/UDP <adresse d'écoute>` (groupe
+//! des clients MODBUS), qui n'est pas compté dans le nombre de connexions MODBUS/TCP.
+
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use tokio::net::UdpSocket;
+use tokio_modbus::prelude::*;
+use tokio_modbus::server::Service as _;
+
+use crate::afsec::format_hex_frame;
+use crate::capture::{self, CaptureKind};
+use crate::database::{crc16_modbus, Database};
+
+use super::broadcast::{is_broadcast, is_broadcast_write};
+use super::{
+    broadcast_counter, exception_counter, strict_request_filter, DatabaseService,
+    ModbusServerConfig, UndefinedWritePolicy, MODBUS_FUNCTION_MEI,
+};
+
+/// Code d'exception MODBUS `ILLEGAL FUNCTION`
+const MODBUS_EXCEPTION_ILLEGAL_FUNCTION: u8 = 0x01;
+
+/// Code d'exception MODBUS `ILLEGAL DATA VALUE`
+const MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE: u8 = 0x03;
+
+/// Code d'exception MODBUS `SERVER DEVICE FAILURE`
+const MODBUS_EXCEPTION_SERVER_DEVICE_FAILURE: u8 = 0x04;
+
+/// Nombre max. de registres lus par une requête
+const MAX_READ_REGISTERS: u16 = 125;
+
+/// Nombre max. de registres écrits par une requête
+const MAX_WRITE_REGISTERS: u16 = 123;
+
+/// Taille max. d'un datagramme reçu (une trame MODBUS fait au plus 260 octets)
+const DATAGRAM_MAX_LEN: usize = 512;
+
+/// Tramage des requêtes et des réponses MODBUS/UDP
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UdpFraming {
+    /// Trame MODBUS RTU (unité, PDU et CRC)
+    #[default]
+    Rtu,
+
+    /// Trame MODBUS/TCP (entête MBAP et PDU)
+    Mbap,
+}
+
+impl fmt::Display for UdpFraming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UdpFraming::Rtu => write!(f, "rtu"),
+            UdpFraming::Mbap => write!(f, "mbap"),
+        }
+    }
+}
+
+impl FromStr for UdpFraming {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "rtu" => Ok(UdpFraming::Rtu),
+            "mbap" => Ok(UdpFraming::Mbap),
+            _ => Err(format!("Tramage MODBUS/UDP '{s}' inconnu (rtu ou mbap)")),
+        }
+    }
+}
+
+/// Requête extraite d'un datagramme
+#[derive(Debug, PartialEq)]
+struct UdpRequest {
+    /// Transaction (entête MBAP, 0 en RTU)
+    transaction_id: u16,
+
+    /// Unité destinataire
+    unit_id: u8,
+
+    /// PDU (code fonction et données)
+    pdu: Vec<u8>,
+}
+
+/// Extrait la requête d'un datagramme selon le tramage (None si le datagramme est incorrect)
+fn parse_datagram(framing: UdpFraming, datagram: &[u8]) -> Option<UdpRequest> {
+    match framing {
+        UdpFraming::Rtu => {
+            // Unité, code fonction et CRC au minimum
+            if datagram.len() < 4 {
+                return None;
+            }
+            let (frame, crc) = datagram.split_at(datagram.len() - 2);
+            if crc16_modbus(0xFFFF, frame) != u16::from_le_bytes([crc[0], crc[1]]) {
+                return None;
+            }
+            Some(UdpRequest {
+                transaction_id: 0,
+                unit_id: frame[0],
+                pdu: frame[1..].to_vec(),
+            })
+        }
+        UdpFraming::Mbap => {
+            // Entête MBAP et code fonction au minimum
+            if datagram.len() < 8 {
+                return None;
+            }
+            let length = usize::from(u16::from_be_bytes([datagram[4], datagram[5]]));
+            if datagram[2..4] != [0, 0] || length != datagram.len() - 6 {
+                return None;
+            }
+            Some(UdpRequest {
+                transaction_id: u16::from_be_bytes([datagram[0], datagram[1]]),
+                unit_id: datagram[6],
+                pdu: datagram[7..].to_vec(),
+            })
+        }
+    }
+}
+
+/// Datagramme de la réponse (PDU) à une requête selon le tramage
+fn build_datagram(framing: UdpFraming, request: &UdpRequest, pdu: &[u8]) -> Vec<u8> {
+    match framing {
+        UdpFraming::Rtu => {
+            let mut datagram = Vec::with_capacity(pdu.len() + 3);
+            datagram.push(request.unit_id);
+            datagram.extend_from_slice(pdu);
+            let crc = crc16_modbus(0xFFFF, &datagram);
+            datagram.extend_from_slice(&crc.to_le_bytes());
+            datagram
+        }
+        UdpFraming::Mbap => {
+            let length = u16::try_from(pdu.len() + 1).unwrap_or(u16::MAX);
+            let mut datagram = Vec::with_capacity(pdu.len() + 7);
+            datagram.extend_from_slice(&request.transaction_id.to_be_bytes());
+            datagram.extend_from_slice(&[0, 0]);
+            datagram.extend_from_slice(&length.to_be_bytes());
+            datagram.push(request.unit_id);
+            datagram.extend_from_slice(pdu);
+            datagram
+        }
+    }
+}
+
+/// PDU d'une réponse d'exception
+fn exception_pdu(function_code: u8, exception_code: u8) -> Vec<u8> {
+    vec![function_code | 0x80, exception_code]
+}
+
+/// Décodage du PDU d'une requête
+/// # Errors
+/// Code d'exception de la réponse si la fonction n'est pas supportée ou si la requête est
+/// incorrecte
+fn decode_request(pdu: &[u8]) -> Result<Request<'static>, u8> {
+    let Some((&function_code, data)) = pdu.split_first() else {
+        return Err(MODBUS_EXCEPTION_ILLEGAL_FUNCTION);
+    };
+    let word = |index: usize| {
+        data.get(2 * index..2 * index + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .ok_or(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE)
+    };
+    match function_code {
+        0x03 | 0x04 => {
+            let (addr, cnt) = (word(0)?, word(1)?);
+            if data.len() != 4 || !(1..=MAX_READ_REGISTERS).contains(&cnt) {
+                return Err(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE);
+            }
+            Ok(if function_code == 0x03 {
+                Request::ReadHoldingRegisters(addr, cnt)
+            } else {
+                Request::ReadInputRegisters(addr, cnt)
+            })
+        }
+        0x06 => {
+            if data.len() != 4 {
+                return Err(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE);
+            }
+            Ok(Request::WriteSingleRegister(word(0)?, word(1)?))
+        }
+        0x10 => {
+            let (addr, cnt) = (word(0)?, word(1)?);
+            let nb_bytes = 2 * usize::from(cnt);
+            if !(1..=MAX_WRITE_REGISTERS).contains(&cnt)
+                || data.len() != 5 + nb_bytes
+                || usize::from(data[4]) != nb_bytes
+            {
+                return Err(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE);
+            }
+            let values: Vec<u16> = data[5..]
+                .chunks_exact(2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+                .collect();
+            Ok(Request::WriteMultipleRegisters(addr, values.into()))
+        }
+        MODBUS_FUNCTION_MEI => Ok(Request::Custom(function_code, data.to_vec().into())),
+        _ => Err(MODBUS_EXCEPTION_ILLEGAL_FUNCTION),
+    }
+}
+
+/// PDU d'une réponse du [`DatabaseService`] (None si la réponse n'est pas supportée)
+fn encode_response(response: Response) -> Option<Vec<u8>> {
+    let registers = |function_code: u8, values: &[u16]| {
+        let mut pdu = vec![function_code, u8::try_from(2 * values.len()).ok()?];
+        for value in values {
+            pdu.extend_from_slice(&value.to_be_bytes());
+        }
+        Some(pdu)
+    };
+    match response {
+        Response::ReadHoldingRegisters(values) => registers(0x03, &values),
+        Response::ReadInputRegisters(values) => registers(0x04, &values),
+        Response::WriteSingleRegister(addr, value) => {
+            let mut pdu = vec![0x06];
+            pdu.extend_from_slice(&addr.to_be_bytes());
+            pdu.extend_from_slice(&value.to_be_bytes());
+            Some(pdu)
+        }
+        Response::WriteMultipleRegisters(addr, cnt) => {
+            let mut pdu = vec![0x10];
+            pdu.extend_from_slice(&addr.to_be_bytes());
+            pdu.extend_from_slice(&cnt.to_be_bytes());
+            Some(pdu)
+        }
+        Response::Custom(function_code, data) => {
+            let mut pdu = vec![function_code];
+            pdu.extend_from_slice(&data);
+            Some(pdu)
+        }
+        _ => None,
+    }
+}
+
+/// Traitement du PDU d'une requête par le [`DatabaseService`]
+/// Retourne le PDU de la réponse (éventuellement d'exception)
+async fn process_pdu(service: &DatabaseService, pdu: &[u8]) -> Vec<u8> {
+    let function_code = pdu.first().copied().unwrap_or_default();
+    let request = match decode_request(pdu) {
+        Ok(request) => request,
+        Err(exception_code) => return exception_pdu(function_code, exception_code),
+    };
+    match service.call(request).await {
+        Ok(response) => encode_response(response).unwrap_or_else(|| {
+            exception_pdu(function_code, MODBUS_EXCEPTION_SERVER_DEVICE_FAILURE)
+        }),
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            exception_pdu(function_code, MODBUS_EXCEPTION_ILLEGAL_FUNCTION)
+        }
+        Err(e) => {
+            eprintln!("Server MODBUS/UDP: {e}");
+            exception_pdu(function_code, MODBUS_EXCEPTION_SERVER_DEVICE_FAILURE)
+        }
+    }
+}
+
+/// Serveur MODBUS/UDP à l'écoute sur `socket_addr` (avec les réglages du serveur MODBUS/TCP)
+/// # Errors
+/// Erreur si le serveur ne peut pas être démarré sur `socket_addr` ou si une réponse ne peut
+/// pas être émise
+pub async fn modbus_udp_server_process(
+    shared_db: Arc<Mutex<Database>>,
+    socket_addr: SocketAddr,
+    framing: UdpFraming,
+    config: &ModbusServerConfig,
+) -> anyhow::Result<()> {
+    let debug_level = config.debug_level;
+    println!("Starting up MODBUS/UDP server ({framing}) on {socket_addr}");
+    let socket = UdpSocket::bind(socket_addr).await?;
+    let service = DatabaseService::new_udp(Arc::clone(&shared_db), socket_addr, debug_level)
+        .with_undefined_write_policy(config.undefined_write_policy);
+    let mut option_request_filter = (config.undefined_write_policy == UndefinedWritePolicy::Strict)
+        .then(|| strict_request_filter(Arc::clone(&shared_db), debug_level));
+    let mut exception_hook = exception_counter(Arc::clone(&shared_db), debug_level);
+    let mut option_broadcast_hook = config
+        .is_broadcast
+        .then(|| broadcast_counter(Arc::clone(&shared_db), debug_level));
+
+    let mut buffer = vec![0; DATAGRAM_MAX_LEN];
+    loop {
+        let (len, peer_addr) = socket.recv_from(&mut buffer).await?;
+        let datagram = &buffer[..len];
+        if !config.client_filter.is_allowed(peer_addr.ip()) {
+            if debug_level > 1 {
+                println!("Server MODBUS/UDP: Datagram from {peer_addr} rejected");
+            }
+            continue;
+        }
+        let Some(request) = parse_datagram(framing, datagram) else {
+            if debug_level > 0 {
+                println!(
+                    "Server MODBUS/UDP: Incorrect datagram from {peer_addr}: {}",
+                    format_hex_frame(datagram)
+                );
+            }
+            continue;
+        };
+        capture::record(CaptureKind::ModbusFrame, || {
+            format!("-> {}", format_hex_frame(datagram))
+        });
+
+        // Trame MODBUS/TCP équivalente pour les filtres communs avec le serveur MODBUS/TCP
+        let frame = build_datagram(UdpFraming::Mbap, &request, &request.pdu);
+        let mut is_broadcast_request = false;
+        if let Some(broadcast_hook) = option_broadcast_hook.as_mut() {
+            if is_broadcast(&frame) {
+                broadcast_hook(&frame);
+                if !is_broadcast_write(&frame) {
+                    continue;
+                }
+                is_broadcast_request = true;
+            }
+        }
+        let function_code = request.pdu[0];
+        let response = match option_request_filter
+            .as_mut()
+            .and_then(|request_filter| request_filter(&frame))
+        {
+            Some(exception_code) => exception_pdu(function_code, exception_code),
+            None => process_pdu(&service, &request.pdu).await,
+        };
+        if is_broadcast_request {
+            // Écriture diffusée appliquée sans réponse
+            continue;
+        }
+
+        let is_exception = response[0] & 0x80 != 0;
+        if is_exception {
+            exception_hook(response[1]);
+        }
+        let datagram = build_datagram(framing, &request, &response);
+        let kind = if is_exception {
+            CaptureKind::ModbusException
+        } else {
+            CaptureKind::ModbusFrame
+        };
+        capture::record(kind, || format!("<- {}", format_hex_frame(&datagram)));
+        socket.send_to(&datagram, peer_addr).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_datagram() {
+        // Lecture de 10 registres à partir de l'adresse 0 de l'unité 1
+        let rtu = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A, 0xC5, 0xCD];
+        let request = parse_datagram(UdpFraming::Rtu, &rtu).unwrap();
+        assert_eq!(request.unit_id, 1);
+        assert_eq!(request.pdu, [0x03, 0x00, 0x00, 0x00, 0x0A]);
+        assert_eq!(build_datagram(UdpFraming::Rtu, &request, &request.pdu), rtu);
+        assert!(parse_datagram(UdpFraming::Rtu, &rtu[..7]).is_none());
+
+        let mbap = [0x12, 0x34, 0, 0, 0, 6, 0x01, 0x03, 0x00, 0x00, 0x00, 0x0A];
+        let request = parse_datagram(UdpFraming::Mbap, &mbap).unwrap();
+        assert_eq!(request.transaction_id, 0x1234);
+        assert_eq!(
+            build_datagram(UdpFraming::Mbap, &request, &request.pdu),
+            mbap
+        );
+        assert!(parse_datagram(UdpFraming::Mbap, &mbap[..11]).is_none());
+    }
+
+    #[test]
+    fn test_decode_request() {
+        assert_eq!(
+            decode_request(&[0x03, 0x00, 0x10, 0x00, 0x02]),
+            Ok(Request::ReadHoldingRegisters(0x0010, 2))
+        );
+        assert_eq!(
+            decode_request(&[0x10, 0x00, 0x10, 0x00, 0x02, 0x04, 0x12, 0x34, 0x56, 0x78]),
+            Ok(Request::WriteMultipleRegisters(
+                0x0010,
+                vec![0x1234, 0x5678].into()
+            ))
+        );
+        assert_eq!(
+            decode_request(&[0x10, 0x00, 0x10, 0x00, 0x02, 0x02, 0x12, 0x34]),
+            Err(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE)
+        );
+        assert_eq!(
+            decode_request(&[0x03, 0x00, 0x10, 0x00, 0x00]),
+            Err(MODBUS_EXCEPTION_ILLEGAL_DATA_VALUE)
+        );
+        assert_eq!(
+            decode_request(&[0x01, 0x00, 0x10, 0x00, 0x08]),
+            Err(MODBUS_EXCEPTION_ILLEGAL_FUNCTION)
+        );
+    }
+
+    #[test]
+    fn test_encode_response() {
+        assert_eq!(
+            encode_response(Response::ReadHoldingRegisters(vec![0x1234, 0x0001])),
+            Some(vec![0x03, 0x04, 0x12, 0x34, 0x00, 0x01])
+        );
+        assert_eq!(
+            encode_response(Response::WriteMultipleRegisters(0x0010, 2)),
+            Some(vec![0x10, 0x00, 0x10, 0x00, 0x02])
+        );
+        assert_eq!(exception_pdu(0x2B, 0x02), [0xAB, 0x02]);
+    }
+
+    #[test]
+    fn test_udp_framing_from_str() {
+        assert_eq!(UdpFraming::from_str("RTU"), Ok(UdpFraming::Rtu));
+        assert_eq!(UdpFraming::from_str("mbap"), Ok(UdpFraming::Mbap));
+        assert!(UdpFraming::from_str("ascii").is_err());
+    }
+}
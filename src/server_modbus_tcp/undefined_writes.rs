@@ -0,0 +1,173 @@
+//! Politique des écritures MODBUS/TCP à des adresses sans [`Tag`] défini
+//!
+//! Selon le déploiement (option `--modbus-undefined-writes`) :
+//!
+//! * `raw` (par défaut) : L'écriture modifie la table MODBUS sans notification
+//! * `strict` : La requête d'écriture est refusée avec l'exception `ILLEGAL DATA ADDRESS` (0x02)
+//!   si une des adresses écrites n'est couverte par aucun [`Tag`] (rien n'est écrit)
+//! * `permissive` : Un [`Tag`] anonyme `U16` est créé à l'adresse écrite (voir le module
+//!   `undefined_words` de la [`Database`]) et l'écriture est notifiée comme toute autre
+//!
+//! Dans tous les cas, la première écriture à chaque adresse sans [`Tag`] est tracée.
+//!
+//! Le refus d'une requête en mode `strict` est fait par un filtre des requêtes de la connexion
+//! (voir [`PipelinedStream::with_request_filter`]) car le service MODBUS/TCP ne peut pas
+//! répondre par une exception.
+//!
+//! [`Tag`]: crate::database::Tag
+//! [`PipelinedStream::with_request_filter`]: super::PipelinedStream::with_request_filter
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::database::{Database, WordAddress};
+use crate::profiling::{lock_database, Subsystem};
+
+use super::{RequestFilter, MODBUS_TOP_WORD_ADDRESS};
+
+/// Code d'exception MODBUS `ILLEGAL DATA ADDRESS`
+pub const MODBUS_EXCEPTION_ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+
+/// Offset du code fonction dans une trame MODBUS/TCP (après l'entête MBAP)
+const FUNCTION_CODE_OFFSET: usize = 7;
+
+/// Code fonction `WriteSingleRegister`
+const FC_WRITE_SINGLE_REGISTER: u8 = 0x06;
+
+/// Code fonction `WriteMultipleRegisters`
+const FC_WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+
+/// Politique des écritures à des adresses sans [`Tag`]
+///
+/// [`Tag`]: crate::database::Tag
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UndefinedWritePolicy {
+    /// Écriture sans notification
+    #[default]
+    Raw,
+
+    /// Requête refusée (exception `ILLEGAL DATA ADDRESS`)
+    Strict,
+
+    /// Création d'un [`Tag`] anonyme `U16` à l'adresse écrite
+    ///
+    /// [`Tag`]: crate::database::Tag
+    Permissive,
+}
+
+impl FromStr for UndefinedWritePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "raw" => Ok(UndefinedWritePolicy::Raw),
+            "strict" => Ok(UndefinedWritePolicy::Strict),
+            "permissive" => Ok(UndefinedWritePolicy::Permissive),
+            _ => Err(format!(
+                "Politique '{s}' inconnue (attendu: raw, strict ou permissive)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for UndefinedWritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UndefinedWritePolicy::Raw => write!(f, "raw"),
+            UndefinedWritePolicy::Strict => write!(f, "strict"),
+            UndefinedWritePolicy::Permissive => write!(f, "permissive"),
+        }
+    }
+}
+
+/// Adresses écrites par une requête MODBUS/TCP (première adresse, nombre de mots)
+/// (None si ce n'est pas une requête d'écriture de registres)
+fn written_area(request: &[u8]) -> Option<(WordAddress, u16)> {
+    let pdu = request.get(FUNCTION_CODE_OFFSET..)?;
+    let word_address = u16::from_be_bytes([*pdu.get(1)?, *pdu.get(2)?]);
+    match pdu[0] {
+        FC_WRITE_SINGLE_REGISTER => Some((word_address, 1)),
+        FC_WRITE_MULTIPLE_REGISTERS => Some((
+            word_address,
+            u16::from_be_bytes([*pdu.get(3)?, *pdu.get(4)?]),
+        )),
+        _ => None,
+    }
+}
+
+/// Première adresse écrite par une requête qui n'est couverte par aucun [`Tag`]
+///
+/// [`Tag`]: crate::database::Tag
+fn first_undefined_address(db: &Database, request: &[u8]) -> Option<WordAddress> {
+    let (word_address, nb_words) = written_area(request)?;
+    (word_address..word_address.saturating_add(nb_words))
+        .filter(|word_address| *word_address < MODBUS_TOP_WORD_ADDRESS)
+        .find(|word_address| !db.is_word_address_defined(*word_address))
+}
+
+/// Filtre des requêtes d'une connexion en mode `strict` : Les requêtes d'écriture à une adresse
+/// sans [`Tag`] sont refusées avec l'exception `ILLEGAL DATA ADDRESS`
+///
+/// [`Tag`]: crate::database::Tag
+pub fn strict_request_filter(thread_db: Arc<Mutex<Database>>, debug_level: u8) -> RequestFilter {
+    Box::new(move |request: &[u8]| {
+        let mut db = lock_database(&thread_db, Subsystem::Modbus);
+        let word_address = first_undefined_address(&db, request)?;
+        if db.note_undefined_write(word_address) || debug_level > 1 {
+            println!(
+                "Server MODBUS/TCP: Write to undefined address {word_address:04X} rejected (strict)"
+            );
+        }
+        Some(MODBUS_EXCEPTION_ILLEGAL_DATA_ADDRESS)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::{IdTag, Tag};
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_undefined_write_policy() {
+        assert_eq!(
+            "strict".parse::<UndefinedWritePolicy>(),
+            Ok(UndefinedWritePolicy::Strict)
+        );
+        assert_eq!(
+            "Permissive".parse::<UndefinedWritePolicy>(),
+            Ok(UndefinedWritePolicy::Permissive)
+        );
+        assert!("lenient".parse::<UndefinedWritePolicy>().is_err());
+        assert_eq!(UndefinedWritePolicy::default().to_string(), "raw");
+    }
+
+    #[test]
+    fn test_first_undefined_address() {
+        let mut db = Database::default();
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U32,
+            ..Default::default()
+        });
+
+        // WriteSingleRegister @0010 et @0012
+        let request = [0, 1, 0, 0, 0, 6, 1, 0x06, 0x00, 0x10, 0x12, 0x34];
+        assert_eq!(first_undefined_address(&db, &request), None);
+        let request = [0, 1, 0, 0, 0, 6, 1, 0x06, 0x00, 0x12, 0x12, 0x34];
+        assert_eq!(first_undefined_address(&db, &request), Some(0x0012));
+
+        // WriteMultipleRegisters de 3 mots @0010
+        let request = [
+            0, 1, 0, 0, 0, 13, 1, 0x10, 0x00, 0x10, 0x00, 0x03, 6, 0, 1, 0, 2, 0, 3,
+        ];
+        assert_eq!(first_undefined_address(&db, &request), Some(0x0012));
+
+        // ReadHoldingRegisters: Pas une écriture
+        let request = [0, 1, 0, 0, 0, 6, 1, 0x03, 0x00, 0x12, 0x00, 0x01];
+        assert_eq!(first_undefined_address(&db, &request), None);
+    }
+}
@@ -0,0 +1,300 @@
+//! Comparaison avec un ICOM de référence (option `--shadow <hôte:port>`)
+//!
+//! Pour valider que le simulateur reproduit fidèlement le comportement du firmware, le simulateur
+//! se connecte comme client MODBUS/TCP à un ICOM réel et compare périodiquement des plages de
+//! registres (option répétable `--shadow-range <début>-<fin>`, adresses en hexa) avec son propre
+//! état.
+//!
+//! Seules les évolutions sont tracées (`SHADOW:`) : une nouvelle divergence (ou une divergence
+//! dont les valeurs changent) et le retour à l'identique d'un registre. Les registres sont lus
+//! par la fonction MODBUS `Read Holding Registers` (0x03) du client `tokio_modbus`, par lots de
+//! `MAX_REGISTERS_PER_REQUEST` registres.
+//!
+//! En cas d'erreur de communication, la connexion est rétablie à la période suivante.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio_modbus::client::{tcp, Context};
+use tokio_modbus::prelude::*;
+
+use crate::arg_parsing::parse_word_address;
+use crate::database::WordAddress;
+use crate::profiling::{lock_database, Subsystem};
+use crate::server_modbus_tcp::MODBUS_TOP_WORD_ADDRESS;
+use crate::Database;
+
+/// Période (en secondes) par défaut de la comparaison avec l'ICOM de référence
+pub const DEFAULT_SHADOW_PERIOD_SECS: u64 = 1;
+
+/// Identifiant d'unité MODBUS des requêtes vers l'ICOM de référence
+const SHADOW_UNIT_ID: u8 = 1;
+
+/// Nombre max. de registres par requête `Read Holding Registers`
+const MAX_REGISTERS_PER_REQUEST: u16 = 125;
+
+/// Timeout de la connexion et des réponses de l'ICOM de référence
+const SHADOW_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Plage de registres comparés (`début` et `fin` inclus)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShadowRange {
+    /// Premier registre
+    pub start: WordAddress,
+
+    /// Dernier registre
+    pub end: WordAddress,
+}
+
+impl ShadowRange {
+    /// Indique si la plage contient entièrement une autre plage
+    pub fn contains(&self, other: &ShadowRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Indique si la plage a au moins un registre commun avec une autre plage
+    pub fn overlaps(&self, other: &ShadowRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+/// Analyse d'une plage de registres `<début>-<fin>` (adresses en hexa, ex: `@0010-@001F`)
+/// # Errors
+/// Message d'erreur si la plage est incorrecte ou dépasse la fin de la database
+pub fn parse_shadow_range(spec: &str) -> Result<ShadowRange, String> {
+    let range = spec
+        .split_once('-')
+        .and_then(|(start, end)| parse_word_address(start).zip(parse_word_address(end)));
+    match range {
+        Some((start, end)) if start <= end && end < MODBUS_TOP_WORD_ADDRESS => {
+            Ok(ShadowRange { start, end })
+        }
+        _ => Err(format!(
+            "Plage de registres '{spec}' incorrecte (attendu: <début>-<fin>)"
+        )),
+    }
+}
+
+/// Découpe des plages de registres en requêtes (adresse, nombre de registres)
+fn requests_of(ranges: &[ShadowRange]) -> Vec<(WordAddress, u16)> {
+    let mut requests = vec![];
+    for range in ranges {
+        let mut word_address = range.start;
+        loop {
+            let nb_words = (range.end - word_address).min(MAX_REGISTERS_PER_REQUEST - 1) + 1;
+            requests.push((word_address, nb_words));
+            match word_address.checked_add(nb_words) {
+                Some(next) if next <= range.end => word_address = next,
+                _ => break,
+            }
+        }
+    }
+    requests
+}
+
+/// Évolution de la comparaison d'un registre
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShadowEvent {
+    /// Divergence (adresse, valeur du simulateur, valeur de l'ICOM de référence)
+    Diverged(WordAddress, u16, u16),
+
+    /// Retour à l'identique (adresse)
+    Resolved(WordAddress),
+}
+
+/// Comparaison de l'état du simulateur avec l'ICOM de référence
+#[derive(Clone, Debug, Default)]
+pub struct ShadowComparator {
+    /// Divergences en cours (valeurs du simulateur et de l'ICOM de référence) par adresse
+    divergences: BTreeMap<WordAddress, (u16, u16)>,
+}
+
+impl ShadowComparator {
+    /// Nombre de divergences en cours
+    pub fn nb_divergences(&self) -> usize {
+        self.divergences.len()
+    }
+
+    /// Compare les registres à partir d'une adresse et retourne les évolutions par rapport à la
+    /// comparaison précédente
+    pub fn compare(
+        &mut self,
+        word_address: WordAddress,
+        sim_words: &[u16],
+        reference_words: &[u16],
+    ) -> Vec<ShadowEvent> {
+        let mut events = vec![];
+        for (offset, (sim, reference)) in sim_words.iter().zip(reference_words).enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let cur_word_address = word_address.wrapping_add(offset as WordAddress);
+            if sim == reference {
+                if self.divergences.remove(&cur_word_address).is_some() {
+                    events.push(ShadowEvent::Resolved(cur_word_address));
+                }
+            } else if self.divergences.get(&cur_word_address) != Some(&(*sim, *reference)) {
+                self.divergences
+                    .insert(cur_word_address, (*sim, *reference));
+                events.push(ShadowEvent::Diverged(cur_word_address, *sim, *reference));
+            }
+        }
+        events
+    }
+}
+
+/// Connexion (client MODBUS/TCP) à l'ICOM de référence (`hôte:port`)
+async fn connect(reference: &str) -> io::Result<Context> {
+    let socket_addr: SocketAddr = tokio::net::lookup_host(reference)
+        .await?
+        .next()
+        .ok_or_else(|| io::Error::other("Adresse introuvable"))?;
+    tcp::connect_slave(socket_addr, Slave(SHADOW_UNIT_ID)).await
+}
+
+/// Routine d'un thread qui compare périodiquement des plages de registres avec un ICOM de
+/// référence (`hôte:port`)
+pub async fn shadow_process(
+    thread_db: Arc<Mutex<Database>>,
+    reference: String,
+    ranges: Vec<ShadowRange>,
+    period_secs: u64,
+) {
+    let requests = requests_of(&ranges);
+    let id_user = lock_database(&thread_db, Subsystem::Shadow).get_id_user("Shadow", false);
+    println!(
+        "SHADOW: Comparaison de {} plage(s) de registres avec {reference}",
+        ranges.len()
+    );
+
+    let mut comparator = ShadowComparator::default();
+    let mut option_context: Option<Context> = None;
+    loop {
+        // Connexion à l'ICOM de référence
+        if option_context.is_none() {
+            match tokio::time::timeout(SHADOW_TIMEOUT, connect(&reference)).await {
+                Ok(Ok(context)) => {
+                    println!("SHADOW: Connecté à {reference}");
+                    option_context = Some(context);
+                }
+                Ok(Err(e)) => println!("SHADOW: !!! Connexion à {reference} impossible: {e}"),
+                Err(_) => println!("SHADOW: !!! Timeout de la connexion à {reference}"),
+            }
+        }
+
+        if let Some(context) = &mut option_context {
+            for (word_address, nb_words) in &requests {
+                let result = tokio::time::timeout(
+                    SHADOW_TIMEOUT,
+                    context.read_holding_registers(*word_address, *nb_words),
+                )
+                .await
+                .unwrap_or_else(|_| Err(io::Error::other("Timeout")));
+                let reference_words = match result {
+                    Ok(reference_words) if reference_words.len() == usize::from(*nb_words) => {
+                        reference_words
+                    }
+                    Ok(reference_words) => {
+                        println!(
+                            "SHADOW: !!! Lecture @{word_address:04X}: {} registres reçus ({nb_words} attendus)",
+                            reference_words.len()
+                        );
+                        option_context = None;
+                        break;
+                    }
+                    Err(e) => {
+                        println!("SHADOW: !!! Erreur lecture @{word_address:04X}: {e}");
+                        option_context = None;
+                        break;
+                    }
+                };
+
+                let db = lock_database(&thread_db, Subsystem::Shadow);
+                let sim_words: Vec<u16> = (0..*nb_words)
+                    .map(|offset| {
+                        db.get_u16_from_word_address(id_user, word_address.wrapping_add(offset))
+                    })
+                    .collect();
+                for event in comparator.compare(*word_address, &sim_words, &reference_words) {
+                    match event {
+                        ShadowEvent::Diverged(word_address, sim, reference) => {
+                            let label = db
+                                .get_tag_from_word_address(word_address)
+                                .map(|tag| format!(" {tag}"))
+                                .unwrap_or_default();
+                            println!(
+                                "SHADOW: Divergence @{word_address:04X}{label}: simulateur {sim:#06X}, ICOM {reference:#06X}"
+                            );
+                        }
+                        ShadowEvent::Resolved(word_address) => {
+                            println!("SHADOW: @{word_address:04X} de nouveau identique");
+                        }
+                    }
+                }
+            }
+        }
+
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_secs(period_secs.max(1))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shadow_range() {
+        assert_eq!(
+            parse_shadow_range("@0010-@001F"),
+            Ok(ShadowRange {
+                start: 0x0010,
+                end: 0x001F
+            })
+        );
+        assert!(parse_shadow_range("0010").is_err());
+        assert!(parse_shadow_range("0020-0010").is_err());
+        assert!(parse_shadow_range("7FF0-7FFF").is_ok());
+        assert!(parse_shadow_range("7FF0-8000").is_err());
+    }
+
+    #[test]
+    fn test_requests_of() {
+        let ranges = [
+            ShadowRange {
+                start: 0x0000,
+                end: 0x00FF,
+            },
+            ShadowRange {
+                start: 0xFFFF,
+                end: 0xFFFF,
+            },
+        ];
+        assert_eq!(
+            requests_of(&ranges),
+            vec![(0x0000, 125), (0x007D, 125), (0x00FA, 6), (0xFFFF, 1)]
+        );
+    }
+
+    #[test]
+    fn test_shadow_comparator() {
+        let mut comparator = ShadowComparator::default();
+        assert_eq!(
+            comparator.compare(0x0010, &[1, 2, 3], &[1, 5, 3]),
+            vec![ShadowEvent::Diverged(0x0011, 2, 5)]
+        );
+
+        // Divergence inchangée : pas de nouvel événement
+        assert!(comparator
+            .compare(0x0010, &[1, 2, 3], &[1, 5, 3])
+            .is_empty());
+        assert_eq!(comparator.nb_divergences(), 1);
+
+        assert_eq!(
+            comparator.compare(0x0010, &[1, 5, 3], &[1, 5, 3]),
+            vec![ShadowEvent::Resolved(0x0011)]
+        );
+        assert_eq!(comparator.nb_divergences(), 0);
+    }
+}
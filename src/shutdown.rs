@@ -0,0 +1,64 @@
+//! Arrêt du simulateur sur ctrl+C avec sauvegarde du contexte des conversations avec l'AFSEC+
+//! (option `--context-state`) et écriture du rapport de fin de test (option `--summary`)
+//!
+//! Sous Unix, le signal `SIGTERM` (arrêt d'un job CI, `docker stop`, `systemctl stop`, ...) est
+//! traité comme ctrl+C.
+//!
+//! Sur ctrl+C, le rapport de fin de test est écrit (voir le module `test_summary`). Si le
+//! contexte ou les compteurs des conversations sont à sauvegarder, l'arrêt est ensuite demandé
+//! à la [`Database`] (voir `Database::request_shutdown`) : le thread de communication avec
+//! l'AFSEC+ sauvegarde le contexte des conversations au cycle de surveillance suivant puis
+//! termine l'application. Si le thread de communication ne répond pas dans le délai
+//! `SHUTDOWN_TIMEOUT_SECS`, l'application est terminée sans sauvegarde.
+
+use std::sync::{Arc, Mutex};
+
+use crate::profiling::{lock_database, Subsystem};
+use crate::test_summary;
+use crate::Database;
+
+/// Délai (en secondes) max. de la sauvegarde du contexte avant l'arrêt sans sauvegarde
+pub const SHUTDOWN_TIMEOUT_SECS: u64 = 5;
+
+/// Attente de ctrl+C ou du signal `SIGTERM`
+#[cfg(unix)]
+async fn wait_for_shutdown_signal() -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = sigterm.recv() => {
+            println!("Shutdown: SIGTERM");
+            Ok(())
+        }
+    }
+}
+
+/// Attente de ctrl+C (pas de signal `SIGTERM` hors Unix)
+#[cfg(not(unix))]
+async fn wait_for_shutdown_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
+/// Routine d'un thread qui attend ctrl+C (ou `SIGTERM`) pour demander l'arrêt du simulateur
+/// Sans contexte à sauvegarder (`is_context_saved` à false), le simulateur est arrêté dès
+/// l'écriture du rapport de fin de test
+pub async fn shutdown_process(thread_db: Arc<Mutex<Database>>, is_context_saved: bool) {
+    if let Err(e) = wait_for_shutdown_signal().await {
+        println!("Shutdown: !!! ctrl+C/SIGTERM non intercepté: {e}");
+        return;
+    }
+    test_summary::write_summary(&lock_database(&thread_db, Subsystem::Other));
+    if !is_context_saved {
+        std::process::exit(0);
+    }
+    println!("Shutdown: Sauvegarde du contexte AFSEC...");
+    lock_database(&thread_db, Subsystem::Other).request_shutdown();
+
+    tokio::time::sleep(tokio::time::Duration::from_secs(SHUTDOWN_TIMEOUT_SECS)).await;
+    eprintln!(
+        "!!! Contexte AFSEC non sauvegardé (pas de réponse de la communication avec l'AFSEC+)"
+    );
+    std::process::exit(1);
+}
@@ -0,0 +1,50 @@
+//! Horloge simulée de l'ICOM
+//!
+//! L'horloge simulée est l'horloge système décalée d'un offset fixé lors de la dernière
+//! synchronisation (écriture par l'AFSEC+ de la date dans le tag `ID_TAG_SIM_TIME_SYNC`, voir
+//! le module `afsec`). Elle est utilisée pour horodater les traces (voir le module `timeline`).
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Décalage (en millisecondes) de l'horloge simulée par rapport à l'horloge système
+static OFFSET_MILLIS: AtomicI64 = AtomicI64::new(0);
+
+/// Date courante de l'horloge système (en millisecondes depuis le 01/01/1970 UTC)
+fn system_millis() -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    i64::try_from(now.as_millis()).unwrap_or(i64::MAX)
+}
+
+/// Date courante de l'horloge simulée (depuis le 01/01/1970 UTC)
+pub fn now() -> Duration {
+    let millis = system_millis().saturating_add(OFFSET_MILLIS.load(Ordering::Relaxed));
+    Duration::from_millis(u64::try_from(millis).unwrap_or(0))
+}
+
+/// Synchronise l'horloge simulée sur une date (en secondes depuis le 01/01/1970 UTC)
+pub fn synchronize(unix_secs: u64) {
+    let millis = i64::try_from(unix_secs)
+        .unwrap_or(i64::MAX)
+        .saturating_mul(1000);
+    OFFSET_MILLIS.store(millis.saturating_sub(system_millis()), Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synchronize() {
+        // Synchronisation sur le 01/01/2000 00:00:00 UTC
+        synchronize(946_684_800);
+        let secs = now().as_secs();
+        assert!((946_684_800..946_684_810).contains(&secs));
+
+        // Retour à l'horloge système
+        synchronize(system_millis().unsigned_abs() / 1000);
+        assert!(OFFSET_MILLIS.load(Ordering::Relaxed).abs() < 1000);
+    }
+}
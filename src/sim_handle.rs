@@ -0,0 +1,255 @@
+//! Simulateur dans le processus courant (tests d'intégration)
+//!
+//! `SimIcom::spawn` démarre les tâches du simulateur (watcher, communication avec l'AFSEC+,
+//! serveur MODBUS/TCP, rampes, CRC des zones) sur le runtime `tokio` courant et retourne un
+//! [`SimHandle`] pour agir sur le simulateur sans lancer l'exécutable ni analyser ses traces :
+//!
+//! * Lecture et écriture des tags de la [`Database`]
+//! * Injection de trames TLV comme si elles étaient reçues de l'AFSEC+
+//! * Statistiques de profilage
+//! * Arrêt des tâches
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::task::JoinHandle;
+
+use crate::afsec::tlv_frame::RawFrame;
+use crate::afsec::{database_afsec_process, DatabaseAfsecComm, FirmwareProfile, FrameInjector};
+use crate::database::{IdUser, WordAddress};
+use crate::profiling::{self, lock_database, Subsystem};
+use crate::server_modbus_tcp::{modbus_server_process, ModbusServerConfig};
+use crate::sim_rng::SimRng;
+use crate::slew_rate::{slew_rate_process, SLEW_RATE_CYCLE_MSECS};
+use crate::t_data::TValue;
+use crate::watcher::database_watcher_process;
+use crate::zone_crc::zone_crc_process;
+use crate::Database;
+
+/// Configuration du simulateur démarré par `SimIcom::spawn`
+#[derive(Debug)]
+pub struct SimConfig {
+    /// [`Database`] du simulateur (les tags du simulateur y sont ajoutés)
+    pub database: Database,
+
+    /// Nom du port série pour communiquer avec l'AFSEC+ ('fake' pour aucune communication, les
+    /// trames sont alors injectées par `SimHandle::inject_frame`)
+    pub port_name: String,
+
+    /// Adresse du serveur MODBUS/TCP (None pour ne pas démarrer le serveur)
+    pub option_modbus_addr: Option<SocketAddr>,
+
+    /// Timer (en millisecondes) pour le watcher (0 pour inhiber le watcher)
+    pub watcher_cycle_msecs: u64,
+
+    /// Période (en secondes) de publication des CRC des zones (0 pour inhiber la publication)
+    pub crc_period_secs: u64,
+
+    /// Niveau de debug pour les traces (0: None, 1: Some, 2: All)
+    pub debug_level: u8,
+
+    /// Profil du firmware ICOM émulé
+    pub firmware_profile: FirmwareProfile,
+
+    /// Graine des générateurs pseudo-aléatoires de la simulation
+    pub seed: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            database: Database::default(),
+            port_name: "fake".to_string(),
+            option_modbus_addr: None,
+            watcher_cycle_msecs: 0,
+            crc_period_secs: 0,
+            debug_level: 0,
+            firmware_profile: FirmwareProfile::default(),
+            seed: 0,
+        }
+    }
+}
+
+/// Simulateur ICOM dans le processus courant
+pub struct SimIcom;
+
+impl SimIcom {
+    /// Démarre les tâches du simulateur sur le runtime `tokio` courant
+    /// # Panics
+    /// panic! si appelé hors d'un runtime `tokio`
+    pub fn spawn(config: SimConfig) -> SimHandle {
+        let SimConfig {
+            mut database,
+            port_name,
+            option_modbus_addr,
+            watcher_cycle_msecs,
+            crc_period_secs,
+            debug_level,
+            firmware_profile,
+            seed,
+        } = config;
+        database.add_sim_tags();
+        let thread_db = Arc::new(Mutex::new(database));
+        let rng = SimRng::new(seed);
+        let mut sim_handle = SimHandle::new(Arc::clone(&thread_db), debug_level, firmware_profile);
+
+        // Tâches lancées directement (sans `supervisor`) pour être toutes arrêtées par
+        // `SimHandle::shutdown`
+        sim_handle.tasks.push(tokio::spawn(database_watcher_process(
+            Arc::clone(&thread_db),
+            watcher_cycle_msecs,
+            true,
+        )));
+
+        let db_afsec = Arc::clone(&thread_db);
+        let rng_afsec = rng.fork("afsec");
+        sim_handle.tasks.push(tokio::spawn(async move {
+            database_afsec_process(
+                &mut DatabaseAfsecComm::new(db_afsec, port_name, debug_level)
+                    .with_rng(rng_afsec)
+                    .with_firmware_profile(firmware_profile),
+            )
+            .await;
+        }));
+
+        sim_handle.tasks.push(tokio::spawn(slew_rate_process(
+            Arc::clone(&thread_db),
+            SLEW_RATE_CYCLE_MSECS,
+        )));
+        sim_handle.tasks.push(tokio::spawn(zone_crc_process(
+            Arc::clone(&thread_db),
+            crc_period_secs,
+        )));
+
+        if let Some(modbus_addr) = option_modbus_addr {
+            let db_modbus = Arc::clone(&thread_db);
+            sim_handle.tasks.push(tokio::spawn(async move {
+                let config = ModbusServerConfig::new(vec![modbus_addr], debug_level);
+                if let Err(e) = modbus_server_process(db_modbus, &config).await {
+                    eprintln!("SIM_HANDLE: Server MODBUS/TCP failed: {e}");
+                }
+            }));
+        }
+
+        sim_handle
+    }
+}
+
+/// Accès au simulateur démarré par `SimIcom::spawn`
+pub struct SimHandle {
+    /// Database partagée du simulateur
+    thread_db: Arc<Mutex<Database>>,
+
+    /// [`IdUser`] pour les lectures et écritures via le `SimHandle`
+    id_user: IdUser,
+
+    /// Injecteur des trames TLV
+    frame_injector: FrameInjector,
+
+    /// Tâches du simulateur
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl SimHandle {
+    /// Constructeur (sans tâche)
+    fn new(
+        thread_db: Arc<Mutex<Database>>,
+        debug_level: u8,
+        firmware_profile: FirmwareProfile,
+    ) -> Self {
+        let id_user = lock_database(&thread_db, Subsystem::Other).get_id_user("SimHandle", false);
+        let frame_injector =
+            FrameInjector::new(Arc::clone(&thread_db), debug_level, firmware_profile);
+        Self {
+            thread_db,
+            id_user,
+            frame_injector,
+            tasks: vec![],
+        }
+    }
+
+    /// Database partagée du simulateur
+    pub fn database(&self) -> Arc<Mutex<Database>> {
+        Arc::clone(&self.thread_db)
+    }
+
+    /// Valeur du tag défini à une [`WordAddress`] (None si pas de tag à cette adresse)
+    pub fn read_tag(&self, word_address: WordAddress) -> Option<TValue> {
+        let db = lock_database(&self.thread_db, Subsystem::Other);
+        let tag = db.get_tag_from_word_address(word_address)?;
+        Some(db.get_t_value_from_tag(self.id_user, tag))
+    }
+
+    /// Écriture d'une valeur (au format string) dans le tag défini à une [`WordAddress`]
+    /// # Errors
+    /// Message d'erreur si pas de tag à cette adresse ou si la valeur ne peut pas être écrite
+    pub fn write_tag(&self, word_address: WordAddress, value: &str) -> Result<(), String> {
+        let mut db = lock_database(&self.thread_db, Subsystem::Other);
+        let Some(tag) = db.get_tag_from_word_address(word_address).cloned() else {
+            return Err(format!("Pas de tag défini à l'adresse {word_address:#06X}"));
+        };
+        db.set_value(self.id_user, &tag, value)?;
+        Ok(())
+    }
+
+    /// Traite une trame TLV comme si elle était reçue de l'AFSEC+ et retourne la réponse (voir
+    /// [`FrameInjector`])
+    pub fn inject_frame(&mut self, request_raw_frame: RawFrame) -> RawFrame {
+        self.frame_injector.inject(request_raw_frame)
+    }
+
+    /// Statistiques de profilage (voir le module `profiling`)
+    pub fn stats(&self) -> String {
+        profiling::report(&lock_database(&self.thread_db, Subsystem::Other))
+    }
+
+    /// Arrêt des tâches du simulateur
+    pub fn shutdown(self) {
+        for task in self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::afsec::tlv_frame::DataFrame;
+    use crate::database::{IdTag, Tag};
+    use crate::t_data::TFormat;
+
+    fn sim_handle_setup() -> SimHandle {
+        let mut db = Database::default();
+        db.add_tag(&Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(1, 1, [0, 0, 0]),
+            t_format: TFormat::U16,
+            ..Default::default()
+        });
+        SimHandle::new(Arc::new(Mutex::new(db)), 0, FirmwareProfile::default())
+    }
+
+    #[test]
+    fn test_read_write_tag() {
+        let sim_handle = sim_handle_setup();
+        sim_handle.write_tag(0x0010, "1234").unwrap();
+        assert_eq!(sim_handle.read_tag(0x0010), Some(TValue::U16(1234)));
+        assert_eq!(sim_handle.read_tag(0x0020), None);
+        assert!(sim_handle.write_tag(0x0020, "1").is_err());
+        assert!(sim_handle.stats().contains("Other"));
+    }
+
+    #[test]
+    fn test_inject_frame() {
+        let mut sim_handle = sim_handle_setup();
+
+        // Avant AF_INIT, un AF_ALIVE est acquitté
+        let response = sim_handle.inject_frame(RawFrame::new_message(0x00));
+        assert!(DataFrame::try_from(response).unwrap().is_simple_ack());
+
+        // Trame incorrecte: pas de réponse
+        let response = sim_handle.inject_frame(RawFrame::new(&[0x00]));
+        assert!(response.encode().is_empty());
+    }
+}
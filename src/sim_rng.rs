@@ -0,0 +1,122 @@
+//! Générateur pseudo-aléatoire déterministe du simulateur
+//!
+//! Toutes les sources aléatoires de la simulation (bruit, injection de défauts, ...) doivent
+//! utiliser un [`SimRng`] dérivé de la graine globale (option `--seed`) afin qu'une exécution
+//! puisse être rejouée à l'identique.
+//!
+//! Chaque sous-système obtient son propre générateur par `SimRng::fork` avec un nom dédié.
+//! Ainsi, la séquence d'un sous-système ne dépend pas du nombre de tirages effectués par
+//! les autres sous-systèmes.
+//!
+//! L'algorithme est `SplitMix64` (simple, rapide et suffisant pour de la simulation).
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Générateur pseudo-aléatoire déterministe
+#[derive(Clone, Debug)]
+pub struct SimRng {
+    /// État courant du générateur
+    state: u64,
+}
+
+impl SimRng {
+    /// Constructeur selon une graine
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Graine 'aléatoire' (selon l'heure courante) si l'utilisateur n'en spécifie pas
+    pub fn random_seed() -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        #[allow(clippy::cast_possible_truncation)]
+        let nanos = now.as_nanos() as u64;
+        SimRng::new(nanos).next_u64()
+    }
+
+    /// Nouveau générateur indépendant pour le sous-système `name`
+    /// (ne modifie pas l'état du générateur courant)
+    pub fn fork(&self, name: &str) -> Self {
+        // Hash FNV-1a du nom
+        let hash = name.bytes().fold(0xCBF2_9CE4_8422_2325_u64, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01B3)
+        });
+        let mut rng = SimRng::new(self.state ^ hash);
+        SimRng::new(rng.next_u64())
+    }
+
+    /// Tirage d'un `u64`
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Tirage d'un `f64` dans [0.0, 1.0[
+    #[allow(clippy::cast_precision_loss)]
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Tirage d'un `u64` dans [min, max[ (retourne `min` si la plage est vide)
+    pub fn gen_range(&mut self, min: u64, max: u64) -> u64 {
+        if max <= min {
+            min
+        } else {
+            min + self.next_u64() % (max - min)
+        }
+    }
+
+    /// Tirage d'un booléen vrai avec la probabilité `probability` (entre 0.0 et 1.0)
+    pub fn gen_bool(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic() {
+        let mut rng1 = SimRng::new(1234);
+        let mut rng2 = SimRng::new(1234);
+        for _ in 0..100 {
+            assert_eq!(rng1.next_u64(), rng2.next_u64());
+        }
+
+        let mut rng3 = SimRng::new(1235);
+        assert_ne!(SimRng::new(1234).next_u64(), rng3.next_u64());
+    }
+
+    #[test]
+    fn test_fork() {
+        let rng = SimRng::new(1234);
+        let mut rng_a = rng.fork("a");
+        let mut rng_b = rng.fork("b");
+        assert_ne!(rng_a.next_u64(), rng_b.next_u64());
+
+        // Le fork ne dépend que de la graine et du nom
+        assert_eq!(
+            rng.fork("a").next_u64(),
+            SimRng::new(1234).fork("a").next_u64()
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = SimRng::new(0);
+        for _ in 0..1000 {
+            let value = rng.gen_range(10, 20);
+            assert!((10..20).contains(&value));
+            let value = rng.next_f64();
+            assert!((0.0..1.0).contains(&value));
+        }
+        assert_eq!(rng.gen_range(10, 10), 10);
+        assert!(!rng.gen_bool(0.0));
+        assert!(rng.gen_bool(1.0));
+    }
+}
@@ -0,0 +1,72 @@
+//! Process d'intégration des rampes des tags de la [`Database`]
+//!
+//! Les rampes (vitesse de variation max. d'un tag numérique) sont définies par l'option
+//! `--ramp <adresse>=<vitesse>` de la ligne de commande ou par la commande `ramp` de la console.
+//! Ce process fait évoluer périodiquement la valeur de ces tags vers leur consigne.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::time::Instant;
+
+use crate::arg_parsing::parse_word_address;
+use crate::database::WordAddress;
+use crate::profiling::{lock_database, Subsystem};
+use crate::Database;
+
+/// Temps de cycle (en millisecondes) de l'évolution des tags avec une rampe
+pub const SLEW_RATE_CYCLE_MSECS: u64 = 100;
+
+/// Analyse d'une définition de rampe `<adresse>=<vitesse>` (adresse en hexa, `@0010`, `0x0010`
+/// ou `0010`, et vitesse en unités par seconde)
+/// # Errors
+/// Message d'erreur si la définition est incorrecte
+pub fn parse_slew_rate_spec(spec: &str) -> Result<(WordAddress, f64), String> {
+    let Some((address, rate)) = spec.split_once('=') else {
+        return Err(format!(
+            "Rampe '{spec}' incorrecte (attendu: <adresse>=<vitesse>)"
+        ));
+    };
+    let address = address.trim();
+    let Some(word_address) = parse_word_address(address) else {
+        return Err(format!("Adresse de la rampe '{spec}' incorrecte"));
+    };
+    let Ok(rate) = rate.trim().parse::<f64>() else {
+        return Err(format!("Vitesse de la rampe '{spec}' incorrecte"));
+    };
+    Ok((word_address, rate))
+}
+
+/// Routine d'un thread qui fait évoluer les tags avec une rampe vers leur consigne
+/// En paramètre, le temps de cycle entre chaque évolution (en millisecondes)
+pub async fn slew_rate_process(thread_db: Arc<Mutex<Database>>, cycle_in_msecs: u64) {
+    // Obtient un id_user pour les opérations
+    let id_user = lock_database(&thread_db, Subsystem::SlewRate).get_id_user("Slew rate", false);
+
+    let mut last_instant = Instant::now();
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(cycle_in_msecs)).await;
+
+        let now = Instant::now();
+        let elapsed_secs = now.duration_since(last_instant).as_secs_f64();
+        last_instant = now;
+
+        // Verrouiller la database partagée
+        let mut db = lock_database(&thread_db, Subsystem::SlewRate);
+        db.step_slew_rates(id_user, elapsed_secs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_slew_rate_spec() {
+        assert_eq!(parse_slew_rate_spec("@0010=2.5"), Ok((0x0010, 2.5)));
+        assert_eq!(parse_slew_rate_spec("0x001A = 10"), Ok((0x001A, 10.0)));
+        assert!(parse_slew_rate_spec("0010").is_err());
+        assert!(parse_slew_rate_spec("zz=1").is_err());
+        assert!(parse_slew_rate_spec("0010=fast").is_err());
+    }
+}
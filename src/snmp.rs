@@ -0,0 +1,598 @@
+//! Agent SNMP en lecture seule des diagnostics du simulateur (option `--snmp <adresse>`)
+//!
+//! Le superviseur (NMS) du laboratoire surveille tous les équipements du banc en SNMP : l'agent
+//! répond aux requêtes SNMPv1 et SNMPv2c (`GetRequest`, `GetNextRequest` et `GetBulkRequest`)
+//! dont la communauté est celle de l'option `--snmp-community` (`public` par défaut). Les
+//! requêtes d'une autre communauté sont ignorées et les écritures (`SetRequest`) sont refusées.
+//!
+//! Objets publiés (valeurs lues dans les tags propres au simulateur à chaque requête) :
+//!
+//! * Groupe `system` standard : `sysDescr`, `sysObjectID` (`SIM_ICOM_MIB`), `sysUpTime` (durée
+//!   depuis le démarrage de l'agent) et `sysName`
+//! * MIB propre au simulateur, sous `SIM_ICOM_MIB` (arc expérimental `1.3.6.1.3`, faute de numéro
+//!   d'entreprise enregistré) :
+//!   * `.1.1.0` à `.1.8.0` : santé des tâches, redémarrages, état du lien et de la
+//!     communication avec l'AFSEC+, port actif, connexions MODBUS/TCP, exceptions MODBUS/TCP
+//!     et trames inexploitables (voir le module `sim_tags` de la [`Database`])
+//!   * `.2.1.<stat>.<lien>` : table des statistiques des liens (1: principal, 2: secours) avec
+//!     l'AFSEC+ : trames reçues (1), trames émises (2), ACK (3), NACK (4), trames
+//!     inexploitables (5) et date du dernier `AF_INIT` (6)
+//!
+//! Le codage BER des messages SNMP est limité aux types utilisés par l'agent.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tokio::net::UdpSocket;
+
+use crate::build_info;
+use crate::database::{
+    id_tag_sim_link_stat, IdTag, ID_ANONYMOUS_USER, ID_TAG_SIM_AFSEC_ACTIVE_PORT,
+    ID_TAG_SIM_AFSEC_LINK, ID_TAG_SIM_AFSEC_STATE, ID_TAG_SIM_HEALTH, ID_TAG_SIM_JUNK_FRAMES,
+    ID_TAG_SIM_MODBUS_CONNECTIONS, ID_TAG_SIM_MODBUS_EXCEPTIONS, ID_TAG_SIM_RESTARTS, SIM_NB_LINKS,
+    SIM_NB_LINK_STATS,
+};
+use crate::profiling::{lock_database, Subsystem};
+use crate::Database;
+
+/// Port UDP par défaut de l'agent SNMP
+pub const DEFAULT_SNMP_PORT: u16 = 161;
+
+/// Communauté par défaut des requêtes SNMP
+pub const DEFAULT_SNMP_COMMUNITY: &str = "public";
+
+/// OID de la MIB propre au simulateur
+pub const SIM_ICOM_MIB: [u32; 6] = [1, 3, 6, 1, 3, 2023];
+
+/// OID du groupe `system` (MIB-II)
+const MIB_SYSTEM: [u32; 7] = [1, 3, 6, 1, 2, 1, 1];
+
+/// Versions SNMP supportées (valeur du champ `version`)
+const SNMP_VERSION_1: i64 = 0;
+const SNMP_VERSION_2C: i64 = 1;
+
+/// Types BER universels
+const BER_INTEGER: u8 = 0x02;
+const BER_OCTET_STRING: u8 = 0x04;
+const BER_NULL: u8 = 0x05;
+const BER_OBJECT_ID: u8 = 0x06;
+const BER_SEQUENCE: u8 = 0x30;
+
+/// Types BER de l'application SNMP
+const BER_COUNTER32: u8 = 0x41;
+const BER_GAUGE32: u8 = 0x42;
+const BER_TIMETICKS: u8 = 0x43;
+
+/// Exceptions SNMPv2c d'une variable
+const BER_NO_SUCH_OBJECT: u8 = 0x80;
+const BER_END_OF_MIB_VIEW: u8 = 0x82;
+
+/// Types des PDU SNMP
+const PDU_GET_REQUEST: u8 = 0xA0;
+const PDU_GET_NEXT_REQUEST: u8 = 0xA1;
+const PDU_GET_RESPONSE: u8 = 0xA2;
+const PDU_SET_REQUEST: u8 = 0xA3;
+const PDU_GET_BULK_REQUEST: u8 = 0xA5;
+
+/// Codes d'erreur SNMP (`error-status`)
+const SNMP_ERROR_NO_SUCH_NAME: i64 = 2;
+const SNMP_ERROR_NOT_WRITABLE: i64 = 17;
+
+/// Nombre max. de variables dans une réponse à un `GetBulkRequest`
+const MAX_BULK_VARBINDS: usize = 64;
+
+/// Taille max. d'un datagramme reçu
+const DATAGRAM_MAX_LEN: usize = 1500;
+
+/// Valeur d'un objet de la MIB
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnmpValue {
+    Integer(i64),
+    OctetString(String),
+    ObjectId(Vec<u32>),
+    Counter32(u32),
+    Gauge32(u32),
+    TimeTicks(u32),
+}
+
+/// Objets de la MIB (OID et valeur) triés par OID
+pub type MibEntries = Vec<(Vec<u32>, SnmpValue)>;
+
+/// OID d'un objet de la MIB propre au simulateur
+fn sim_oid(suffix: &[u32]) -> Vec<u32> {
+    SIM_ICOM_MIB.iter().chain(suffix).copied().collect()
+}
+
+/// Objets de la MIB selon le contenu de la [`Database`] et la durée (en centièmes de seconde)
+/// depuis le démarrage de l'agent
+pub fn mib_entries(db: &Database, uptime_ticks: u32) -> MibEntries {
+    let system =
+        |index: u32| -> Vec<u32> { MIB_SYSTEM.iter().chain(&[index, 0]).copied().collect() };
+    let u16_of = |id_tag: IdTag| u32::from(db.get_u16_from_id_tag(ID_ANONYMOUS_USER, id_tag));
+    let mut entries: MibEntries = vec![
+        (
+            system(1),
+            SnmpValue::OctetString(format!(
+                "Simulateur ICOM {} {} ({})",
+                build_info::NAME,
+                build_info::VERSION,
+                build_info::GIT_HASH
+            )),
+        ),
+        (system(2), SnmpValue::ObjectId(SIM_ICOM_MIB.to_vec())),
+        (system(3), SnmpValue::TimeTicks(uptime_ticks)),
+        (
+            system(5),
+            SnmpValue::OctetString(build_info::NAME.to_string()),
+        ),
+    ];
+    let diagnostics = [
+        (1, SnmpValue::Integer(i64::from(u16_of(ID_TAG_SIM_HEALTH)))),
+        (2, SnmpValue::Counter32(u16_of(ID_TAG_SIM_RESTARTS))),
+        (
+            3,
+            SnmpValue::Integer(i64::from(u16_of(ID_TAG_SIM_AFSEC_LINK))),
+        ),
+        (
+            4,
+            SnmpValue::Integer(i64::from(u16_of(ID_TAG_SIM_AFSEC_STATE))),
+        ),
+        (
+            5,
+            SnmpValue::Integer(i64::from(u16_of(ID_TAG_SIM_AFSEC_ACTIVE_PORT))),
+        ),
+        (6, SnmpValue::Gauge32(u16_of(ID_TAG_SIM_MODBUS_CONNECTIONS))),
+        (
+            7,
+            SnmpValue::Counter32(u16_of(ID_TAG_SIM_MODBUS_EXCEPTIONS)),
+        ),
+        (8, SnmpValue::Counter32(u16_of(ID_TAG_SIM_JUNK_FRAMES))),
+    ];
+    for (index, value) in diagnostics {
+        entries.push((sim_oid(&[1, index, 0]), value));
+    }
+    for stat in 0..SIM_NB_LINK_STATS {
+        #[allow(clippy::cast_possible_truncation)]
+        let stat = stat as u8;
+        for link in 1..=SIM_NB_LINKS {
+            let value = db.get_u32_from_id_tag(ID_ANONYMOUS_USER, id_tag_sim_link_stat(link, stat));
+            let value = if usize::from(stat) + 1 == SIM_NB_LINK_STATS {
+                SnmpValue::Gauge32(value) // Date du dernier AF_INIT
+            } else {
+                SnmpValue::Counter32(value)
+            };
+            entries.push((
+                sim_oid(&[2, 1, u32::from(stat) + 1, u32::from(link)]),
+                value,
+            ));
+        }
+    }
+    entries.sort_by(|(oid_a, _), (oid_b, _)| oid_a.cmp(oid_b));
+    entries
+}
+
+/// Encodage BER de la longueur d'un contenu
+fn encode_length(len: usize, ber: &mut Vec<u8>) {
+    if len < 0x80 {
+        #[allow(clippy::cast_possible_truncation)]
+        ber.push(len as u8);
+    } else if len <= 0xFF {
+        #[allow(clippy::cast_possible_truncation)]
+        ber.extend_from_slice(&[0x81, len as u8]);
+    } else {
+        let len = u16::try_from(len).unwrap_or(u16::MAX);
+        ber.push(0x82);
+        ber.extend_from_slice(&len.to_be_bytes());
+    }
+}
+
+/// Encodage BER d'un élément (type et contenu)
+fn encode_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut ber = vec![tag];
+    encode_length(content.len(), &mut ber);
+    ber.extend_from_slice(content);
+    ber
+}
+
+/// Contenu BER d'un entier (complément à 2, nombre minimal d'octets)
+fn integer_content(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+/// Contenu BER d'un OID
+fn oid_content(oid: &[u32]) -> Vec<u8> {
+    let mut content = vec![];
+    let (first, rest) = match oid {
+        [x, y, rest @ ..] => (40 * x + y, rest),
+        [x] => (40 * x, &[][..]),
+        [] => (0, &[][..]),
+    };
+    for sub_id in std::iter::once(&first).chain(rest) {
+        let mut sub_bytes = vec![];
+        let mut value = *sub_id;
+        loop {
+            #[allow(clippy::cast_possible_truncation)]
+            sub_bytes.push((value & 0x7F) as u8);
+            value >>= 7;
+            if value == 0 {
+                break;
+            }
+        }
+        sub_bytes.reverse();
+        let last = sub_bytes.len() - 1;
+        for (index, byte) in sub_bytes.iter().enumerate() {
+            content.push(if index < last { byte | 0x80 } else { *byte });
+        }
+    }
+    content
+}
+
+/// Encodage BER d'une valeur
+fn encode_value(value: &SnmpValue) -> Vec<u8> {
+    match value {
+        SnmpValue::Integer(value) => encode_tlv(BER_INTEGER, &integer_content(*value)),
+        SnmpValue::OctetString(text) => encode_tlv(BER_OCTET_STRING, text.as_bytes()),
+        SnmpValue::ObjectId(oid) => encode_tlv(BER_OBJECT_ID, &oid_content(oid)),
+        SnmpValue::Counter32(value) => {
+            encode_tlv(BER_COUNTER32, &integer_content(i64::from(*value)))
+        }
+        SnmpValue::Gauge32(value) => encode_tlv(BER_GAUGE32, &integer_content(i64::from(*value))),
+        SnmpValue::TimeTicks(value) => {
+            encode_tlv(BER_TIMETICKS, &integer_content(i64::from(*value)))
+        }
+    }
+}
+
+/// Lecture d'un élément BER en tête de `ber`
+/// Retourne le type, le contenu et la suite de `ber`
+fn read_tlv(ber: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = ber.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = match first {
+        0x00..=0x7F => (usize::from(first), rest),
+        0x81 => (usize::from(*rest.first()?), &rest[1..]),
+        0x82 => (
+            usize::from(u16::from_be_bytes([*rest.first()?, *rest.get(1)?])),
+            rest.get(2..)?,
+        ),
+        _ => return None,
+    };
+    if rest.len() < len {
+        return None;
+    }
+    let (content, rest) = rest.split_at(len);
+    Some((tag, content, rest))
+}
+
+/// Lecture d'un élément BER d'un type attendu
+fn read_expected(ber: &[u8], expected_tag: u8) -> Option<(&[u8], &[u8])> {
+    let (tag, content, rest) = read_tlv(ber)?;
+    (tag == expected_tag).then_some((content, rest))
+}
+
+/// Décodage d'un entier BER
+fn decode_integer(content: &[u8]) -> Option<i64> {
+    if content.is_empty() || content.len() > 8 {
+        return None;
+    }
+    let initial = if content[0] & 0x80 == 0 { 0 } else { -1 };
+    Some(
+        content
+            .iter()
+            .fold(initial, |value, byte| (value << 8) | i64::from(*byte)),
+    )
+}
+
+/// Décodage d'un OID BER
+fn decode_oid(content: &[u8]) -> Option<Vec<u32>> {
+    let mut sub_ids = vec![];
+    let mut value: u32 = 0;
+    for byte in content {
+        value = value.checked_mul(128)? | u32::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            sub_ids.push(value);
+            value = 0;
+        }
+    }
+    let (&first, rest) = sub_ids.split_first()?;
+    let mut oid = vec![(first / 40).min(2), first - 40 * (first / 40).min(2)];
+    oid.extend_from_slice(rest);
+    Some(oid)
+}
+
+/// Requête SNMP décodée
+#[derive(Debug, PartialEq)]
+struct SnmpRequest {
+    version: i64,
+    community: Vec<u8>,
+    pdu_type: u8,
+    request_id: i64,
+
+    /// `non-repeaters` d'un `GetBulkRequest` (`error-status` des autres requêtes)
+    non_repeaters: i64,
+
+    /// `max-repetitions` d'un `GetBulkRequest` (`error-index` des autres requêtes)
+    max_repetitions: i64,
+
+    oids: Vec<Vec<u32>>,
+}
+
+/// Décodage d'un message SNMP (None si le message est incorrect)
+fn decode_request(datagram: &[u8]) -> Option<SnmpRequest> {
+    let (message, _) = read_expected(datagram, BER_SEQUENCE)?;
+    let (version, rest) = read_expected(message, BER_INTEGER)?;
+    let (community, rest) = read_expected(rest, BER_OCTET_STRING)?;
+    let (pdu_type, pdu, _) = read_tlv(rest)?;
+    let (request_id, rest) = read_expected(pdu, BER_INTEGER)?;
+    let (non_repeaters, rest) = read_expected(rest, BER_INTEGER)?;
+    let (max_repetitions, rest) = read_expected(rest, BER_INTEGER)?;
+    let (mut varbinds, _) = read_expected(rest, BER_SEQUENCE)?;
+    let mut oids = vec![];
+    while !varbinds.is_empty() {
+        let (varbind, rest) = read_expected(varbinds, BER_SEQUENCE)?;
+        let (oid, _) = read_expected(varbind, BER_OBJECT_ID)?;
+        oids.push(decode_oid(oid)?);
+        varbinds = rest;
+    }
+    Some(SnmpRequest {
+        version: decode_integer(version)?,
+        community: community.to_vec(),
+        pdu_type,
+        request_id: decode_integer(request_id)?,
+        non_repeaters: decode_integer(non_repeaters)?,
+        max_repetitions: decode_integer(max_repetitions)?,
+        oids,
+    })
+}
+
+/// Variable d'une réponse : valeur ou exception SNMPv2c (type BER sans contenu)
+enum VarBind<'a> {
+    Value(&'a SnmpValue),
+    Exception(u8),
+}
+
+/// Encodage d'un message `GetResponse`
+fn encode_response(
+    request: &SnmpRequest,
+    error_status: i64,
+    error_index: i64,
+    varbinds: &[(Vec<u32>, VarBind<'_>)],
+) -> Vec<u8> {
+    let mut varbinds_ber = vec![];
+    for (oid, varbind) in varbinds {
+        let mut varbind_ber = encode_tlv(BER_OBJECT_ID, &oid_content(oid));
+        varbind_ber.extend(match varbind {
+            VarBind::Value(value) => encode_value(value),
+            VarBind::Exception(tag) => encode_tlv(*tag, &[]),
+        });
+        varbinds_ber.extend(encode_tlv(BER_SEQUENCE, &varbind_ber));
+    }
+    let mut pdu = encode_tlv(BER_INTEGER, &integer_content(request.request_id));
+    pdu.extend(encode_tlv(BER_INTEGER, &integer_content(error_status)));
+    pdu.extend(encode_tlv(BER_INTEGER, &integer_content(error_index)));
+    pdu.extend(encode_tlv(BER_SEQUENCE, &varbinds_ber));
+    let mut message = encode_tlv(BER_INTEGER, &integer_content(request.version));
+    message.extend(encode_tlv(BER_OCTET_STRING, &request.community));
+    message.extend(encode_tlv(PDU_GET_RESPONSE, &pdu));
+    encode_tlv(BER_SEQUENCE, &message)
+}
+
+/// Objet suivant un OID dans l'ordre de la MIB
+fn next_entry<'a>(entries: &'a MibEntries, oid: &[u32]) -> Option<&'a (Vec<u32>, SnmpValue)> {
+    entries
+        .iter()
+        .find(|(entry_oid, _)| entry_oid.as_slice() > oid)
+}
+
+/// Ajoute aux variables d'une réponse l'objet suivant un OID (ou l'exception `endOfMibView`)
+/// Retourne l'OID de l'objet ajouté
+fn push_next<'a>(
+    entries: &'a MibEntries,
+    varbinds: &mut Vec<(Vec<u32>, VarBind<'a>)>,
+    oid: &[u32],
+) -> Option<Vec<u32>> {
+    if let Some((entry_oid, value)) = next_entry(entries, oid) {
+        varbinds.push((entry_oid.clone(), VarBind::Value(value)));
+        Some(entry_oid.clone())
+    } else {
+        varbinds.push((oid.to_vec(), VarBind::Exception(BER_END_OF_MIB_VIEW)));
+        None
+    }
+}
+
+/// Réponse à un message SNMP (None si le message est ignoré: message incorrect, version non
+/// supportée ou communauté différente)
+fn process_request(datagram: &[u8], community: &str, entries: &MibEntries) -> Option<Vec<u8>> {
+    let request = decode_request(datagram)?;
+    if !matches!(request.version, SNMP_VERSION_1 | SNMP_VERSION_2C)
+        || request.community != community.as_bytes()
+    {
+        return None;
+    }
+    let is_v1 = request.version == SNMP_VERSION_1;
+    let null_varbinds = || {
+        request
+            .oids
+            .iter()
+            .map(|oid| (oid.clone(), VarBind::Exception(BER_NULL)))
+            .collect::<Vec<_>>()
+    };
+    let mut varbinds = vec![];
+    match request.pdu_type {
+        PDU_GET_REQUEST | PDU_GET_NEXT_REQUEST => {
+            for (index, oid) in request.oids.iter().enumerate() {
+                let option_entry = if request.pdu_type == PDU_GET_REQUEST {
+                    entries.iter().find(|(entry_oid, _)| entry_oid == oid)
+                } else {
+                    next_entry(entries, oid)
+                };
+                match option_entry {
+                    Some((entry_oid, value)) => {
+                        varbinds.push((entry_oid.clone(), VarBind::Value(value)));
+                    }
+                    None if is_v1 => {
+                        let error_index = i64::try_from(index + 1).unwrap_or(i64::MAX);
+                        return Some(encode_response(
+                            &request,
+                            SNMP_ERROR_NO_SUCH_NAME,
+                            error_index,
+                            &null_varbinds(),
+                        ));
+                    }
+                    None if request.pdu_type == PDU_GET_REQUEST => {
+                        varbinds.push((oid.clone(), VarBind::Exception(BER_NO_SUCH_OBJECT)));
+                    }
+                    None => varbinds.push((oid.clone(), VarBind::Exception(BER_END_OF_MIB_VIEW))),
+                }
+            }
+        }
+        PDU_GET_BULK_REQUEST if !is_v1 => {
+            let non_repeaters = usize::try_from(request.non_repeaters.max(0)).unwrap_or(0);
+            let max_repetitions = usize::try_from(request.max_repetitions.max(0))
+                .unwrap_or(0)
+                .min(MAX_BULK_VARBINDS);
+            let (singles, repeaters) = request.oids.split_at(non_repeaters.min(request.oids.len()));
+            for oid in singles {
+                push_next(entries, &mut varbinds, oid);
+            }
+            let mut cursors: Vec<Vec<u32>> = repeaters.to_vec();
+            for _ in 0..max_repetitions {
+                if cursors.is_empty() || varbinds.len() + cursors.len() > MAX_BULK_VARBINDS {
+                    break;
+                }
+                for cursor in &mut cursors {
+                    if let Some(next_oid) = push_next(entries, &mut varbinds, cursor) {
+                        *cursor = next_oid;
+                    }
+                }
+            }
+        }
+        PDU_SET_REQUEST => {
+            let error_status = if is_v1 {
+                SNMP_ERROR_NO_SUCH_NAME
+            } else {
+                SNMP_ERROR_NOT_WRITABLE
+            };
+            return Some(encode_response(&request, error_status, 1, &null_varbinds()));
+        }
+        _ => return None,
+    }
+    Some(encode_response(&request, 0, 0, &varbinds))
+}
+
+/// Agent SNMP à l'écoute sur `socket_addr`
+pub async fn snmp_agent_process(
+    thread_db: Arc<Mutex<Database>>,
+    socket_addr: SocketAddr,
+    community: String,
+    debug_level: u8,
+) {
+    let socket = match UdpSocket::bind(socket_addr).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            println!("SNMP: !!! Écoute sur {socket_addr} impossible: {e}");
+            return;
+        }
+    };
+    println!("SNMP: Agent à l'écoute sur {socket_addr}");
+    let start = Instant::now();
+    let mut buffer = vec![0; DATAGRAM_MAX_LEN];
+    loop {
+        let (len, peer_addr) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            Err(e) => {
+                println!("SNMP: !!! Erreur de réception: {e}");
+                continue;
+            }
+        };
+        // Durée en centièmes de seconde, modulo 2^32 (`TimeTicks`)
+        #[allow(clippy::cast_possible_truncation)]
+        let uptime_ticks = (start.elapsed().as_millis() / 10) as u32;
+        let entries = mib_entries(&lock_database(&thread_db, Subsystem::Snmp), uptime_ticks);
+        if let Some(response) = process_request(&buffer[..len], &community, &entries) {
+            if let Err(e) = socket.send_to(&response, peer_addr).await {
+                println!("SNMP: !!! Erreur d'émission vers {peer_addr}: {e}");
+            }
+        } else if debug_level > 1 {
+            println!("SNMP: Requête de {peer_addr} ignorée");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `GetRequest` SNMPv2c (communauté `public`, request-id 1) de `sysUpTime.0`
+    const GET_SYS_UPTIME: [u8; 40] = [
+        0x30, 0x26, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xA0, 0x19,
+        0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0E, 0x30, 0x0C, 0x06, 0x08,
+        0x2B, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00, 0x05, 0x00,
+    ];
+
+    #[test]
+    fn test_ber() {
+        assert_eq!(integer_content(0), [0x00]);
+        assert_eq!(integer_content(128), [0x00, 0x80]);
+        assert_eq!(integer_content(-1), [0xFF]);
+        assert_eq!(decode_integer(&[0x00, 0x80]), Some(128));
+        assert_eq!(decode_integer(&[0xFF, 0x7F]), Some(-129));
+        let oid = sim_oid(&[2, 1, 300, 1]);
+        assert_eq!(decode_oid(&oid_content(&oid)), Some(oid));
+    }
+
+    #[test]
+    fn test_process_request() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let entries = mib_entries(&db, 4200);
+
+        let request = decode_request(&GET_SYS_UPTIME).unwrap();
+        assert_eq!(request.oids, [vec![1, 3, 6, 1, 2, 1, 1, 3, 0]]);
+        let response = process_request(&GET_SYS_UPTIME, "public", &entries).unwrap();
+        assert_eq!(response[13], PDU_GET_RESPONSE);
+        // TimeTicks 4200 en fin de réponse
+        assert_eq!(
+            response[response.len() - 4..],
+            [BER_TIMETICKS, 0x02, 0x10, 0x68]
+        );
+        assert!(process_request(&GET_SYS_UPTIME, "private", &entries).is_none());
+
+        // GetNext après le groupe `system` (`sysLocation.0`) : premier objet de la MIB du
+        // simulateur
+        let mut get_next = GET_SYS_UPTIME;
+        get_next[13] = PDU_GET_NEXT_REQUEST;
+        get_next[36] = 0x06;
+        let response = process_request(&get_next, "public", &entries).unwrap();
+        let response = decode_request(&response).unwrap();
+        assert_eq!(response.oids, [sim_oid(&[1, 1, 0])]);
+
+        // GetBulk sans OID répété (`non-repeaters` = 1) et `max-repetitions` = 0x7FFFFFFF :
+        // un seul objet en réponse
+        let mut get_bulk = GET_SYS_UPTIME.to_vec();
+        get_bulk[1] += 3;
+        get_bulk[13] = PDU_GET_BULK_REQUEST;
+        get_bulk[14] += 3;
+        get_bulk[20] = 0x01;
+        get_bulk.splice(22..24, [0x04, 0x7F, 0xFF, 0xFF, 0xFF]);
+        let request = decode_request(&get_bulk).unwrap();
+        assert_eq!(
+            (request.non_repeaters, request.max_repetitions),
+            (1, 0x7FFF_FFFF)
+        );
+        let response = process_request(&get_bulk, "public", &entries).unwrap();
+        let response = decode_request(&response).unwrap();
+        assert_eq!(response.oids.len(), 1);
+    }
+}
@@ -0,0 +1,174 @@
+//! Supervision des tâches `tokio` du simulateur
+//!
+//! Chaque tâche (watcher, communication AFSEC+, serveurs MODBUS/TCP et MODBUS/UDP) est exécutée
+//! sous le contrôle d'un superviseur qui :
+//!
+//! * trace la fin anormale de la tâche (panic ou erreur)
+//! * redémarre la tâche après une temporisation croissante (backoff)
+//! * met à jour le [`Tag`] de santé `ID_TAG_SIM_HEALTH` (1 bit par tâche) et le compteur de
+//!   redémarrages `ID_TAG_SIM_RESTARTS` de la [`Database`]
+//!
+//! Une tâche qui se termine normalement (`Ok(())`) n'est pas redémarrée.
+//!
+//! Si la tâche a paniqué en détenant le verrou de la [`Database`], ce verrou est 'empoisonné'.
+//! Le superviseur lève cet empoisonnement avant de redémarrer la tâche.
+//!
+//! [`Tag`]: crate::database::Tag
+
+use std::any::Any;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::database::{ID_TAG_SIM_HEALTH, ID_TAG_SIM_RESTARTS};
+use crate::Database;
+
+/// Bit de santé de la tâche watcher
+pub const HEALTH_WATCHER: u16 = 0x0001;
+
+/// Bit de santé de la tâche de communication avec l'AFSEC+
+pub const HEALTH_AFSEC: u16 = 0x0002;
+
+/// Bit de santé de la tâche serveur MODBUS/TCP
+pub const HEALTH_MODBUS: u16 = 0x0004;
+
+/// Bit de santé de la tâche serveur MODBUS/UDP (optionnelle)
+pub const HEALTH_MODBUS_UDP: u16 = 0x0008;
+
+/// Temporisation initiale avant redémarrage d'une tâche
+const BACKOFF_MIN: Duration = Duration::from_millis(500);
+
+/// Temporisation max. avant redémarrage d'une tâche
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// Durée de fonctionnement au delà de laquelle la temporisation de redémarrage est réinitialisée
+const BACKOFF_RESET: Duration = Duration::from_secs(60);
+
+/// Message d'un panic
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        (*s).to_string()
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "panic".to_string()
+    }
+}
+
+/// Mise à jour du bit de santé d'une tâche dans la [`Database`]
+fn set_health(thread_db: &Arc<Mutex<Database>>, health_bit: u16, value: bool) {
+    if thread_db.is_poisoned() {
+        thread_db.clear_poison();
+    }
+    let mut db = thread_db.lock().unwrap();
+    db.set_sim_tag_bits(ID_TAG_SIM_HEALTH, health_bit, value);
+}
+
+/// Temporisation suivante (doublée jusqu'à `BACKOFF_MAX`)
+fn next_backoff(backoff: Duration) -> Duration {
+    (backoff * 2).min(BACKOFF_MAX)
+}
+
+/// Exécute et supervise une tâche
+/// `new_task` est appelé pour créer la tâche à chaque (re)démarrage
+pub async fn supervise<F, Fut>(
+    name: &str,
+    health_bit: u16,
+    thread_db: Arc<Mutex<Database>>,
+    mut new_task: F,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let mut backoff = BACKOFF_MIN;
+    loop {
+        set_health(&thread_db, health_bit, true);
+        let start = Instant::now();
+        let result = tokio::spawn(new_task()).await;
+        set_health(&thread_db, health_bit, false);
+
+        match result {
+            Ok(Ok(())) => {
+                println!("SUPERVISOR: Task {name} terminated");
+                return;
+            }
+            Ok(Err(msg)) => {
+                eprintln!("SUPERVISOR: Task {name} failed: {msg}");
+            }
+            Err(e) if e.is_panic() => {
+                eprintln!(
+                    "SUPERVISOR: Task {name} panicked: {}",
+                    panic_message(e.into_panic().as_ref())
+                );
+            }
+            Err(_) => {
+                println!("SUPERVISOR: Task {name} cancelled");
+                return;
+            }
+        }
+
+        if start.elapsed() > BACKOFF_RESET {
+            backoff = BACKOFF_MIN;
+        }
+        println!(
+            "SUPERVISOR: Restarting task {name} in {} ms...",
+            backoff.as_millis()
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = next_backoff(backoff);
+
+        thread_db
+            .lock()
+            .unwrap()
+            .increment_sim_tag(ID_TAG_SIM_RESTARTS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::ID_ANONYMOUS_USER;
+
+    #[test]
+    fn test_panic_message() {
+        let panic: Box<dyn Any + Send> = Box::new("Oops");
+        assert_eq!(panic_message(panic.as_ref()), "Oops");
+        let panic: Box<dyn Any + Send> = Box::new(String::from("Oops"));
+        assert_eq!(panic_message(panic.as_ref()), "Oops");
+        let panic: Box<dyn Any + Send> = Box::new(0);
+        assert_eq!(panic_message(panic.as_ref()), "panic");
+    }
+
+    #[test]
+    fn test_next_backoff() {
+        assert_eq!(next_backoff(BACKOFF_MIN), 2 * BACKOFF_MIN);
+        assert_eq!(next_backoff(BACKOFF_MAX), BACKOFF_MAX);
+    }
+
+    #[test]
+    fn test_set_health_poisoned() {
+        let mut db = Database::default();
+        db.add_sim_tags();
+        let thread_db = Arc::new(Mutex::new(db));
+
+        // Empoisonne le verrou de la database
+        let thread_db_panic = Arc::clone(&thread_db);
+        let _ = std::thread::spawn(move || {
+            let _db = thread_db_panic.lock().unwrap();
+            panic!("Oops");
+        })
+        .join();
+        assert!(thread_db.is_poisoned());
+
+        set_health(&thread_db, HEALTH_AFSEC, true);
+        assert!(!thread_db.is_poisoned());
+        let db = thread_db.lock().unwrap();
+        assert_eq!(
+            db.get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_HEALTH),
+            HEALTH_AFSEC
+        );
+    }
+}
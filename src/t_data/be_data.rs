@@ -3,7 +3,7 @@
 
 use std::vec;
 
-use super::{TFormat, TValue};
+use super::{t_value::fit_vec_u8, TFormat, TValue};
 
 /// Extraction d'une donnée: `TFormat` + `Vec<u8>` -> `TValue`
 #[allow(clippy::cast_possible_wrap)]
@@ -86,7 +86,8 @@ pub fn encode(t_value: &TValue) -> Vec<u8> {
         TValue::I64(value) => value.to_be_bytes().to_vec(),
         TValue::F32(value) => value.to_be_bytes().to_vec(),
         TValue::F64(value) => value.to_be_bytes().to_vec(),
-        TValue::VecU8(_, value) => value.clone(),
+        // Le contenu est ajusté à la longueur annoncée par le format
+        TValue::VecU8(len, value) => fit_vec_u8(value, *len),
     }
 }
 
@@ -112,9 +113,11 @@ mod tests {
             TValue::F64(-1.23),
             TValue::VecU8(3, string_to_vec_u8("ABC")),
             TValue::VecU8(3, vec![0xFF, 0xFF, 0xFF]),
+            TValue::VecU8(3, string_to_vec_u8("A")),
         ] {
             let t_format = TFormat::from(&t_value);
             let vec_u8 = encode(&t_value);
+            assert_eq!(vec_u8.len(), t_format.nb_bytes());
             let t_value_decode_vec_u8 = decode(t_format, &vec_u8).unwrap();
             let encode_decode_vec_u8 = encode(&t_value_decode_vec_u8);
             assert_eq!(vec_u8, encode_decode_vec_u8);
@@ -5,7 +5,7 @@ use std::fmt;
 use super::{string_to_vec_u8, vec_u8_to_string, TFormat};
 
 /// Format et conteneur d'une valeur atomique
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TValue {
     Bool(bool),
     U8(u8),
@@ -196,23 +196,63 @@ impl From<&TValue> for String {
             TValue::I64(value) => format!("{value}"),
             TValue::F32(value) => format!("{value}"),
             TValue::F64(value) => format!("{value}"),
-            TValue::VecU8(len, value) => {
-                let vec_u8 = if value.len() > *len {
-                    value[..*len].to_vec()
-                } else {
-                    let mut v = value.clone();
-                    while v.len() < *len {
-                        v.push(0);
-                    }
-                    v
-                };
-                vec_u8_to_string(&vec_u8)
-            }
+            TValue::VecU8(len, value) => vec_u8_to_string(&fit_vec_u8(value, *len)),
         }
     }
 }
 
+/// Ajuste un `Vec<u8>` à la longueur `len` (contenu tronqué ou complété par des 0)
+pub(super) fn fit_vec_u8(vec_u8: &[u8], len: usize) -> Vec<u8> {
+    let mut vec_u8 = vec_u8[..len.min(vec_u8.len())].to_vec();
+    vec_u8.resize(len, 0);
+    vec_u8
+}
+
 impl TValue {
+    /// Constructeur d'une `TValue::VecU8` de longueur `len` : le contenu `vec_u8` est tronqué
+    /// ou complété par des 0 pour respecter cette longueur
+    pub fn new_vec_u8(len: usize, vec_u8: &[u8]) -> Self {
+        TValue::VecU8(len, fit_vec_u8(vec_u8, len))
+    }
+
+    /// Indique si la longueur d'une `TValue::VecU8` correspond à la longueur de son contenu
+    /// (toujours vrai pour les autres `TValue`)
+    pub fn is_valid(&self) -> bool {
+        match self {
+            TValue::VecU8(len, value) => value.len() == *len,
+            _ => true,
+        }
+    }
+
+    /// Retourne la `TValue` avec le contenu d'une `TValue::VecU8` ajusté à sa longueur
+    #[must_use]
+    pub fn normalized(self) -> Self {
+        match self {
+            TValue::VecU8(len, value) if value.len() != len => TValue::new_vec_u8(len, &value),
+            t_value => t_value,
+        }
+    }
+
+    /// Contenu brut (octets) d'une `TValue::VecU8` (`None` pour les autres `TValue`)
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            TValue::VecU8(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Contenu d'une `TValue::VecU8` interprété comme une chaîne de caractères, sans les NULL de
+    /// complément (`None` pour les autres `TValue`)
+    pub fn as_text(&self) -> Option<String> {
+        self.as_bytes().map(|value| {
+            let end = value
+                .iter()
+                .position(|byte| *byte == 0)
+                .unwrap_or(value.len());
+            vec_u8_to_string(&value[..end])
+        })
+    }
+
     #[allow(dead_code)]
     pub fn to_t_value_bool(&self) -> Self {
         TValue::Bool(bool::from(self))
@@ -272,17 +312,26 @@ impl TValue {
     pub fn to_t_value_vec_u8(&self, len: usize) -> Self {
         let value = String::from(self);
         let value = value.trim();
-        let value = string_to_vec_u8(value);
-        let value = if value.len() >= len {
-            value[..len].to_vec()
-        } else {
-            let mut v = value.clone();
-            while v.len() < len {
-                v.push(0);
-            }
-            v
-        };
-        TValue::VecU8(len, value)
+        TValue::new_vec_u8(len, &string_to_vec_u8(value))
+    }
+
+    /// Conversion dans un [`TFormat`] (None pour `TFormat::Unknown`)
+    pub fn to_t_format(&self, t_format: TFormat) -> Option<Self> {
+        Some(match t_format {
+            TFormat::Bool => self.to_t_value_bool(),
+            TFormat::U8 => self.to_t_value_u8(),
+            TFormat::I8 => self.to_t_value_i8(),
+            TFormat::U16 => self.to_t_value_u16(),
+            TFormat::I16 => self.to_t_value_i16(),
+            TFormat::U32 => self.to_t_value_u32(),
+            TFormat::I32 => self.to_t_value_i32(),
+            TFormat::U64 => self.to_t_value_u64(),
+            TFormat::I64 => self.to_t_value_i64(),
+            TFormat::F32 => self.to_t_value_f32(),
+            TFormat::F64 => self.to_t_value_f64(),
+            TFormat::VecU8(len) => self.to_t_value_vec_u8(len),
+            TFormat::Unknown => return None,
+        })
     }
 
     #[allow(dead_code)]
@@ -305,7 +354,7 @@ impl TValue {
             TValue::I64(value) => value.to_be_bytes().to_vec(),
             TValue::F32(value) => value.to_be_bytes().to_vec(),
             TValue::F64(value) => value.to_be_bytes().to_vec(),
-            TValue::VecU8(_, value) => value.clone(),
+            TValue::VecU8(len, value) => fit_vec_u8(value, *len),
         }
     }
 }
@@ -445,6 +494,15 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_as_bytes_as_text() {
+        let value = TValue::new_vec_u8(6, b"ABC");
+        assert_eq!(value.as_bytes(), Some(&b"ABC\0\0\0"[..]));
+        assert_eq!(value.as_text(), Some("ABC".to_string()));
+        assert_eq!(TValue::U16(1).as_bytes(), None);
+        assert_eq!(TValue::U16(1).as_text(), None);
+    }
+
     #[test]
     fn test_to_t_i8() {
         let value = TValue::I32(-1);
@@ -569,4 +627,26 @@ mod tests {
             assert_eq!(value.to_vec_u8(), vec_u8);
         }
     }
+
+    #[test]
+    fn test_new_vec_u8() {
+        // Contenu tronqué
+        let t_value = TValue::new_vec_u8(2, &[0x01, 0x02, 0x03]);
+        assert!(t_value.is_valid());
+        assert_eq!(t_value.to_vec_u8(), vec![0x01, 0x02]);
+
+        // Contenu complété par des 0
+        let t_value = TValue::new_vec_u8(4, &[0x01]);
+        assert!(t_value.is_valid());
+        assert_eq!(t_value.to_vec_u8(), vec![0x01, 0x00, 0x00, 0x00]);
+
+        // Longueur incohérente
+        let t_value = TValue::VecU8(5, string_to_vec_u8("AB"));
+        assert!(!t_value.is_valid());
+        assert_eq!(t_value.to_vec_u8().len(), 5);
+        let t_value = t_value.normalized();
+        assert!(t_value.is_valid());
+        assert_eq!(String::from(&t_value), "AB\0\0\0");
+        assert!(TValue::U16(123).normalized().is_valid());
+    }
 }
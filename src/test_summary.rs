@@ -0,0 +1,440 @@
+//! Rapport de synthèse de fin de test (option `--summary <fichier>`)
+//!
+//! Pour qu'un job d'intégration continue joigne le bilan d'un test à ses artefacts, le
+//! simulateur écrit à son arrêt (ctrl+C ou `SIGTERM`, fin d'un scénario avec `--scenario-exit`
+//! ou action `exit` d'un budget d'erreurs) un rapport au format JSON ou Markdown (option
+//! `--summary-format`) :
+//!
+//! * Durée du test
+//! * Trames reçues de l'AFSEC+ par type de message et statistiques des liens série
+//! * Requêtes MODBUS (TCP et UDP) par fonction et réponses d'exception émises
+//! * Notifications : modifications dans l'historique et notifications en attente par utilisateur
+//! * Fautes injectées : coupures d'alimentation simulées (`powercycle`) et trames injectées
+//!   (`inject`)
+//! * Résultats des vérifications du scénario (option `--scenario`)
+//!
+//! Comme les statistiques du module `profiling`, les compteurs sont globaux au processus et
+//! alimentés par les fonctions `record_*`. Le rapport n'est écrit qu'une seule fois.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::build_info;
+use crate::database::{
+    id_tag_sim_link_stat, ID_ANONYMOUS_USER, ID_TAG_SIM_MODBUS_EXCEPTIONS, SIM_NB_LINKS,
+    SIM_NB_LINK_STATS,
+};
+use crate::scenario::ScenarioReport;
+use crate::webhook::escape_json;
+use crate::Database;
+
+/// Nom des statistiques d'un lien avec l'AFSEC+ dans le rapport JSON (dans l'ordre de
+/// publication des tags du simulateur)
+const LINK_STAT_KEYS: [&str; SIM_NB_LINK_STATS] = [
+    "rx_frames",
+    "tx_frames",
+    "ack",
+    "nack",
+    "junk_frames",
+    "last_af_init",
+];
+
+/// Libellés des statistiques d'un lien avec l'AFSEC+ dans le rapport Markdown
+const LINK_STAT_LABELS: [&str; SIM_NB_LINK_STATS] = [
+    "Reçues",
+    "Émises",
+    "ACK",
+    "NACK",
+    "Inexploitables",
+    "Dernier AF_INIT",
+];
+
+/// Format du rapport de fin de test
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// JSON
+    #[default]
+    Json,
+
+    /// Markdown
+    Markdown,
+}
+
+impl FromStr for SummaryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(SummaryFormat::Json),
+            "md" | "markdown" => Ok(SummaryFormat::Markdown),
+            _ => Err(format!(
+                "Format de rapport '{s}' inconnu (attendu: json ou md)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SummaryFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SummaryFormat::Json => write!(f, "json"),
+            SummaryFormat::Markdown => write!(f, "md"),
+        }
+    }
+}
+
+/// Compteurs nommés (dans l'ordre du premier comptage)
+type Counts = Vec<(&'static str, u64)>;
+
+/// Trames reçues de l'AFSEC+ par type de message
+static FRAME_COUNTS: Mutex<Counts> = Mutex::new(Vec::new());
+
+/// Requêtes MODBUS par fonction
+static MODBUS_COUNTS: Mutex<Counts> = Mutex::new(Vec::new());
+
+/// Fautes injectées par type
+static FAULT_COUNTS: Mutex<Counts> = Mutex::new(Vec::new());
+
+/// Dernier rapport des vérifications du scénario
+static SCENARIO_REPORT: Mutex<Option<ScenarioReport>> = Mutex::new(None);
+
+/// Fichier, format et date de début du rapport (None si pas de rapport ou rapport déjà écrit)
+static SUMMARY_TARGET: Mutex<Option<(String, SummaryFormat, Instant)>> = Mutex::new(None);
+
+/// Incrémente un compteur nommé
+fn increment(counts: &Mutex<Counts>, name: &'static str) {
+    let mut counts = counts.lock().unwrap();
+    match counts
+        .iter_mut()
+        .find(|(count_name, _)| *count_name == name)
+    {
+        Some((_, count)) => *count += 1,
+        None => counts.push((name, 1)),
+    }
+}
+
+/// Enregistre une trame reçue de l'AFSEC+ (nom du type de message)
+/// # Panics
+/// Panic si le verrou des compteurs est empoisonné
+pub fn record_frame(name: &'static str) {
+    increment(&FRAME_COUNTS, name);
+}
+
+/// Enregistre une requête MODBUS (nom de la fonction)
+/// # Panics
+/// Panic si le verrou des compteurs est empoisonné
+pub fn record_modbus_request(name: &'static str) {
+    increment(&MODBUS_COUNTS, name);
+}
+
+/// Enregistre une faute injectée (nom de la faute)
+/// # Panics
+/// Panic si le verrou des compteurs est empoisonné
+pub fn record_fault(name: &'static str) {
+    increment(&FAULT_COUNTS, name);
+}
+
+/// Enregistre le rapport (éventuellement partiel) des vérifications du scénario
+/// # Panics
+/// Panic si le verrou du rapport est empoisonné
+pub fn record_scenario_report(report: ScenarioReport) {
+    *SCENARIO_REPORT.lock().unwrap() = Some(report);
+}
+
+/// Active le rapport de fin de test dans le fichier `filename` (la durée du test est comptée à
+/// partir de cette activation)
+/// # Panics
+/// Panic si le verrou du rapport est empoisonné
+pub fn enable_summary(filename: &str, format: SummaryFormat) {
+    *SUMMARY_TARGET.lock().unwrap() = Some((filename.to_string(), format, Instant::now()));
+}
+
+/// Indique si le rapport de fin de test est activé (et pas encore écrit)
+/// # Panics
+/// Panic si le verrou du rapport est empoisonné
+pub fn is_summary_enabled() -> bool {
+    SUMMARY_TARGET.lock().unwrap().is_some()
+}
+
+/// Écrit le rapport de fin de test s'il est activé et pas encore écrit
+/// # Panics
+/// Panic si le verrou du rapport est empoisonné
+pub fn write_summary(db: &Database) {
+    let Some((filename, format, start)) = SUMMARY_TARGET.lock().unwrap().take() else {
+        return;
+    };
+    let summary = TestSummary::collect(db, start.elapsed());
+    let content = match format {
+        SummaryFormat::Json => summary.to_json(),
+        SummaryFormat::Markdown => summary.to_markdown(),
+    };
+    match std::fs::write(&filename, content) {
+        Ok(()) => println!("SUMMARY: Rapport de fin de test écrit dans '{filename}'"),
+        Err(e) => println!("SUMMARY: !!! Erreur écriture du rapport '{filename}': {e}"),
+    }
+}
+
+/// Synthèse d'un test
+#[derive(Clone, Debug, Default)]
+pub struct TestSummary {
+    /// Durée du test
+    pub duration: Duration,
+
+    /// Trames reçues de l'AFSEC+ par type de message
+    pub frames: Counts,
+
+    /// Statistiques de chaque lien avec l'AFSEC+ (voir `LINK_STAT_KEYS`)
+    pub link_stats: Vec<[u32; SIM_NB_LINK_STATS]>,
+
+    /// Requêtes MODBUS par fonction
+    pub modbus_requests: Counts,
+
+    /// Nombre de réponses d'exception MODBUS émises
+    pub modbus_exceptions: u16,
+
+    /// Nombre de modifications dans l'historique des notifications
+    pub nb_notification_changes: usize,
+
+    /// Notifications en attente de chaque utilisateur
+    pub notification_queues: Vec<(String, usize)>,
+
+    /// Fautes injectées par type
+    pub faults: Counts,
+
+    /// Rapport des vérifications du scénario
+    pub option_scenario_report: Option<ScenarioReport>,
+}
+
+impl TestSummary {
+    /// Synthèse selon les compteurs enregistrés et le contenu de la [`Database`]
+    /// # Panics
+    /// Panic si le verrou des compteurs est empoisonné
+    pub fn collect(db: &Database, duration: Duration) -> Self {
+        let link_stats = (1..=SIM_NB_LINKS)
+            .map(|link| {
+                let mut values = [0; SIM_NB_LINK_STATS];
+                for (stat, value) in values.iter_mut().enumerate() {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let id_tag = id_tag_sim_link_stat(link, stat as u8);
+                    *value = db.get_u32_from_id_tag(ID_ANONYMOUS_USER, id_tag);
+                }
+                values
+            })
+            .collect();
+        Self {
+            duration,
+            frames: FRAME_COUNTS.lock().unwrap().clone(),
+            link_stats,
+            modbus_requests: MODBUS_COUNTS.lock().unwrap().clone(),
+            modbus_exceptions: db
+                .get_u16_from_id_tag(ID_ANONYMOUS_USER, ID_TAG_SIM_MODBUS_EXCEPTIONS),
+            nb_notification_changes: db.get_nb_notification_changes(),
+            notification_queues: db.get_notification_queue_sizes(),
+            faults: FAULT_COUNTS.lock().unwrap().clone(),
+            option_scenario_report: SCENARIO_REPORT.lock().unwrap().clone(),
+        }
+    }
+
+    /// Export de la synthèse au format JSON
+    pub fn to_json(&self) -> String {
+        let counts_json = |counts: &Counts| {
+            let counts: Vec<String> = counts
+                .iter()
+                .map(|(name, count)| format!("\"{}\":{count}", escape_json(name)))
+                .collect();
+            format!("{{{}}}", counts.join(","))
+        };
+        let links: Vec<String> = self
+            .link_stats
+            .iter()
+            .enumerate()
+            .map(|(index, values)| {
+                let stats: Vec<String> = LINK_STAT_KEYS
+                    .iter()
+                    .zip(values)
+                    .map(|(key, value)| format!("\"{key}\":{value}"))
+                    .collect();
+                format!("{{\"link\":{},{}}}", index + 1, stats.join(","))
+            })
+            .collect();
+        let queues: Vec<String> = self
+            .notification_queues
+            .iter()
+            .map(|(name, size)| format!("\"{}\":{size}", escape_json(name)))
+            .collect();
+        let scenario = match &self.option_scenario_report {
+            Some(report) => report.to_json().trim_end().to_string(),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"simulator\":\"{} {} ({})\",\"duration_secs\":{:.3},\"frames\":{},\"links\":[{}],\
+             \"modbus\":{{\"requests\":{},\"exceptions\":{}}},\
+             \"notifications\":{{\"changes\":{},\"pending\":{{{}}}}},\"faults\":{},\"scenario\":{}}}\n",
+            build_info::NAME,
+            build_info::VERSION,
+            build_info::GIT_HASH,
+            self.duration.as_secs_f64(),
+            counts_json(&self.frames),
+            links.join(","),
+            counts_json(&self.modbus_requests),
+            self.modbus_exceptions,
+            self.nb_notification_changes,
+            queues.join(","),
+            counts_json(&self.faults),
+            scenario
+        )
+    }
+
+    /// Export de la synthèse au format Markdown
+    pub fn to_markdown(&self) -> String {
+        let counts_table = |title: &str, counts: &Counts| {
+            if counts.is_empty() {
+                return "Aucune\n".to_string();
+            }
+            let mut table = format!("| {title} | Nombre |\n|---|---:|\n");
+            for (name, count) in counts {
+                table += &format!("| {} | {count} |\n", escape_markdown(name));
+            }
+            table
+        };
+
+        let mut report = String::from("# Rapport de fin de test\n\n");
+        report += &format!(
+            "* Simulateur : {} {} ({})\n",
+            build_info::NAME,
+            build_info::VERSION,
+            build_info::GIT_HASH
+        );
+        report += &format!("* Durée : {:.1} s\n", self.duration.as_secs_f64());
+
+        report += "\n## Trames reçues de l'AFSEC+\n\n";
+        report += &counts_table("Type", &self.frames);
+        report += &format!("\n| Lien | {} |\n", LINK_STAT_LABELS.join(" | "));
+        report += &format!("|---|{}\n", "---:|".repeat(SIM_NB_LINK_STATS));
+        for (index, values) in self.link_stats.iter().enumerate() {
+            let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+            report += &format!("| {} | {} |\n", index + 1, values.join(" | "));
+        }
+
+        report += "\n## Requêtes MODBUS\n\n";
+        report += &counts_table("Fonction", &self.modbus_requests);
+        report += &format!("\nRéponses d'exception : {}\n", self.modbus_exceptions);
+
+        report += "\n## Notifications\n\n";
+        report += &format!(
+            "Modifications dans l'historique : {}\n",
+            self.nb_notification_changes
+        );
+        if !self.notification_queues.is_empty() {
+            report += "\n| Utilisateur | En attente |\n|---|---:|\n";
+            for (name, size) in &self.notification_queues {
+                report += &format!("| {} | {size} |\n", escape_markdown(name));
+            }
+        }
+
+        report += "\n## Fautes injectées\n\n";
+        report += &counts_table("Faute", &self.faults);
+
+        report += "\n## Scénario\n\n";
+        match &self.option_scenario_report {
+            Some(scenario_report) => {
+                report += &format!(
+                    "**{}** ({} OK, {} en échec)\n",
+                    if scenario_report.is_passed() {
+                        "SUCCÈS"
+                    } else {
+                        "ÉCHEC"
+                    },
+                    scenario_report.nb_passed(),
+                    scenario_report.nb_failed()
+                );
+                if !scenario_report.results().is_empty() {
+                    report += "\n| Séquence | Étape | Résultat | Détail |\n|---|---|---|---|\n";
+                    for result in scenario_report.results() {
+                        report += &format!(
+                            "| {} | {} | {} | {} |\n",
+                            escape_markdown(&result.timeline),
+                            escape_markdown(&result.step),
+                            if result.passed { "OK" } else { "ECHEC" },
+                            escape_markdown(&result.detail)
+                        );
+                    }
+                }
+            }
+            None => report += "Pas de scénario\n",
+        }
+        report
+    }
+}
+
+/// Échappement d'un texte dans un tableau Markdown
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::scenario::StepResult;
+
+    fn summary() -> TestSummary {
+        TestSummary {
+            duration: Duration::from_millis(12_345),
+            frames: vec![("AF_INIT", 1), ("AF_ALIVE", 10)],
+            link_stats: vec![[11, 11, 10, 1, 0, 1_700_000_000], [0; SIM_NB_LINK_STATS]],
+            modbus_requests: vec![("ReadHoldingRegisters", 5)],
+            modbus_exceptions: 2,
+            nb_notification_changes: 3,
+            notification_queues: vec![("AFSEC".to_string(), 1)],
+            faults: vec![("powercycle", 1)],
+            option_scenario_report: Some(ScenarioReport::new(vec![StepResult {
+                timeline: "a".to_string(),
+                step: "expect @0010 == 1".to_string(),
+                passed: true,
+                detail: "valeur 1".to_string(),
+            }])),
+        }
+    }
+
+    #[test]
+    fn test_summary_format() {
+        assert_eq!(SummaryFormat::from_str("JSON"), Ok(SummaryFormat::Json));
+        assert_eq!(SummaryFormat::from_str("md"), Ok(SummaryFormat::Markdown));
+        assert!(SummaryFormat::from_str("html").is_err());
+    }
+
+    #[test]
+    fn test_summary_to_json() {
+        let json = summary().to_json();
+        assert!(json.contains("\"duration_secs\":12.345,"));
+        assert!(json.contains("\"frames\":{\"AF_INIT\":1,\"AF_ALIVE\":10}"));
+        assert!(json.contains("{\"link\":1,\"rx_frames\":11,\"tx_frames\":11,\"ack\":10,"));
+        assert!(json
+            .contains("\"modbus\":{\"requests\":{\"ReadHoldingRegisters\":5},\"exceptions\":2}"));
+        assert!(json.contains("\"notifications\":{\"changes\":3,\"pending\":{\"AFSEC\":1}}"));
+        assert!(json.contains("\"faults\":{\"powercycle\":1}"));
+        assert!(json.contains("\"scenario\":{\"passed\":true,\"nb_passed\":1,"));
+        assert!(json.ends_with("}}\n"));
+
+        let json = TestSummary::default().to_json();
+        assert!(json.contains("\"frames\":{},\"links\":[],"));
+        assert!(json.ends_with("\"scenario\":null}\n"));
+    }
+
+    #[test]
+    fn test_summary_to_markdown() {
+        let markdown = summary().to_markdown();
+        assert!(markdown.starts_with("# Rapport de fin de test\n"));
+        assert!(markdown.contains("* Durée : 12.3 s\n"));
+        assert!(markdown.contains("| AF_ALIVE | 10 |\n"));
+        assert!(markdown.contains("| 1 | 11 | 11 | 10 | 1 | 0 | 1700000000 |\n"));
+        assert!(markdown.contains("Réponses d'exception : 2\n"));
+        assert!(markdown.contains("**SUCCÈS** (1 OK, 0 en échec)\n"));
+        assert!(TestSummary::default()
+            .to_markdown()
+            .contains("## Fautes injectées\n\nAucune\n"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Marqueurs temporels dans les traces du simulateur
+//!
+//! Un marqueur (commande `mark "label"` de la console) est inséré simultanément dans toutes
+//! les traces actives afin de faciliter la corrélation des étapes d'un test entre les
+//! différentes sources (MODBUS, TLV, watcher, ...).
+//!
+//! Chaque marqueur est numéroté et horodaté (heure UTC de l'horloge simulée, voir le module
+//! `sim_clock`). Il est affiché sur la sortie standard, enregistré dans la capture déclenchée
+//! (module `capture`) et conservé pour les transcriptions des sessions AFSEC+ qui relèvent les
+//! derniers marqueurs à chaque cycle (voir [`marks_after`]).
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::capture::{self, CaptureKind};
+use crate::sim_clock;
+
+/// Nombre max. de marqueurs conservés pour les traces qui les relèvent périodiquement
+const RECENT_MARKS_MAX: usize = 64;
+
+/// Compteur des marqueurs insérés depuis le démarrage
+static MARK_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Derniers marqueurs insérés (numéro, texte)
+static RECENT_MARKS: Mutex<VecDeque<(u32, String)>> = Mutex::new(VecDeque::new());
+
+/// Horodatage courant au format `HH:MM:SS.mmm` (UTC de l'horloge simulée)
+pub fn timestamp() -> String {
+    format_timestamp(sim_clock::now())
+}
+
+/// Horodatage (depuis le 01/01/1970 UTC) au format `HH:MM:SS.mmm`
+pub fn format_timestamp(date: Duration) -> String {
+    let secs = date.as_secs() % 86400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60,
+        date.subsec_millis()
+    )
+}
+
+/// Texte d'un marqueur
+fn mark_line(num: u32, timestamp: &str, label: &str) -> String {
+    format!("==== MARK #{num} [{timestamp}] {label} ====")
+}
+
+/// Insère un marqueur `label` dans les traces actives
+/// Retourne le texte du marqueur inséré
+pub fn mark(label: &str) -> String {
+    let mut recent_marks = RECENT_MARKS.lock().unwrap();
+    let num = MARK_COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+    let line = mark_line(num, &timestamp(), label);
+    println!("{line}");
+    capture::record(CaptureKind::Mark, || line.clone());
+    if recent_marks.len() >= RECENT_MARKS_MAX {
+        recent_marks.pop_front();
+    }
+    recent_marks.push_back((num, line.clone()));
+    line
+}
+
+/// Numéro du dernier marqueur inséré (0 si aucun)
+pub fn last_mark_num() -> u32 {
+    MARK_COUNTER.load(Ordering::Relaxed)
+}
+
+/// Marqueurs (numéro, texte) insérés après le marqueur numéro `after`, parmi les
+/// `RECENT_MARKS_MAX` derniers
+pub fn marks_after(after: u32) -> Vec<(u32, String)> {
+    RECENT_MARKS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(num, _)| *num > after)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_line() {
+        assert_eq!(
+            mark_line(3, "12:34:56.789", "Etape 1"),
+            "==== MARK #3 [12:34:56.789] Etape 1 ===="
+        );
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(
+            format_timestamp(Duration::from_millis(86_400_000 + 45_296_789)),
+            "12:34:56.789"
+        );
+    }
+
+    #[test]
+    fn test_mark() {
+        let line1 = mark("A");
+        let line2 = mark("B");
+        assert!(line1.ends_with("] A ===="));
+        assert!(line2.ends_with("] B ===="));
+        assert_ne!(line1, line2);
+
+        // Marqueurs relevés par les transcriptions
+        let num = last_mark_num();
+        assert!(marks_after(num).is_empty());
+        let line3 = mark("C");
+        assert!(marks_after(num).iter().any(|(_, line)| *line == line3));
+    }
+}
@@ -3,6 +3,7 @@
 
 use std::sync::{Arc, Mutex};
 
+use crate::profiling::{lock_database, Subsystem};
 use crate::Database;
 
 /// Routine d'un thread qui trace les modifications effectuées dans la [`Database`]
@@ -24,7 +25,7 @@ pub async fn database_watcher_process(
     let id_user;
     {
         // Verrouiller la database partagée
-        let mut db = thread_db.lock().unwrap();
+        let mut db = lock_database(&thread_db, Subsystem::Watcher);
 
         // Obtient un id_user pour les opérations
         id_user = db.get_id_user("Watcher", true);
@@ -33,7 +34,7 @@ pub async fn database_watcher_process(
     loop {
         loop {
             // Verrouiller la database partagée
-            let mut db = thread_db.lock().unwrap();
+            let mut db = lock_database(&thread_db, Subsystem::Watcher);
 
             // Voir s'il y a une notification d'un autre utilisateur
             if let Some(notification_change) =
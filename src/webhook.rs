@@ -0,0 +1,274 @@
+//! Notifications HTTP ('webhooks') vers l'orchestration des tests
+//!
+//! Un événement du simulateur est notifié par une requête HTTP `POST` d'un document JSON vers une
+//! URL `http://<hôte>[:<port>][/<chemin>]` (port 80 et chemin `/` par défaut), par exemple un job
+//! Jenkins qui réagit sans interroger périodiquement le simulateur.
+//!
+//! Les modifications des tags sélectionnés par un motif (option répétable
+//! `--webhook <motif>=<url>`, voir [`IdTagPattern`]) sont notifiées par un document JSON :
+//!
+//! ```text
+//! {"event":"tag_change","id_tag":"5/0F45:00:00:03","address":"0x0010","label":"Alarme",
+//!  "value":1,"user":"Server MODBUS/TCP ...","timestamp":"10:15:42.120"}
+//! ```
+//!
+//! Les modifications sont relevées périodiquement (`WEBHOOK_CYCLE_MSECS`) : plusieurs
+//! modifications d'un même tag dans un cycle ne donnent qu'une notification avec la dernière
+//! valeur. Chaque notification est envoyée par une tâche séparée.
+//!
+//! En cas d'erreur de communication ou sans réponse dans le délai `HTTP_TIMEOUT` (voir le module
+//! `http_post`), la notification est perdue (trace `WEBHOOK:`).
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::database::{IdTag, IdTagPattern, Tag};
+use crate::http_post::{self, HttpUrl};
+use crate::profiling::{lock_database, Subsystem};
+use crate::t_data::TValue;
+use crate::timeline;
+use crate::Database;
+
+/// Temps de cycle (en millisecondes) du relevé des modifications des tags
+const WEBHOOK_CYCLE_MSECS: u64 = 200;
+
+/// Destinataire d'une notification
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookUrl {
+    /// URL de la notification (ex: `http://jenkins:8080/job/bench/build?token=sim`)
+    url: HttpUrl,
+}
+
+impl FromStr for WebhookUrl {
+    type Err = String;
+
+    /// Accepte 'http://<hôte>[:<port>][/<chemin>]'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(WebhookUrl {
+            url: HttpUrl::parse(s, "webhook", 80, "/")?,
+        })
+    }
+}
+
+impl fmt::Display for WebhookUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+
+/// Notification des modifications des tags d'un motif vers une URL
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookRule {
+    /// Motif des tags notifiés
+    pub pattern: IdTagPattern,
+
+    /// Destinataire des notifications
+    pub url: WebhookUrl,
+}
+
+impl FromStr for WebhookRule {
+    type Err = String;
+
+    /// Accepte '<motif>=<url>' (ex: '5/*=http://jenkins:8080/hook')
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((pattern, url)) = s.split_once('=') else {
+            return Err(format!("Webhook '{s}' incorrect (attendu: <motif>=<url>)"));
+        };
+        Ok(WebhookRule {
+            pattern: IdTagPattern::from_str(pattern)?,
+            url: WebhookUrl::from_str(url)?,
+        })
+    }
+}
+
+impl fmt::Display for WebhookRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.pattern, self.url)
+    }
+}
+
+/// Échappement d'une chaîne dans un document JSON (sans les guillemets)
+pub fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Valeur JSON d'une [`TValue`] (`null` si la valeur n'est pas représentable, NaN par exemple)
+pub fn json_value(t_value: &TValue) -> String {
+    match t_value {
+        TValue::Bool(value) => value.to_string(),
+        TValue::U8(_)
+        | TValue::I8(_)
+        | TValue::U16(_)
+        | TValue::I16(_)
+        | TValue::U32(_)
+        | TValue::I32(_)
+        | TValue::U64(_)
+        | TValue::I64(_) => String::from(t_value),
+        TValue::F32(value) if value.is_finite() => format!("{value}"),
+        TValue::F64(value) if value.is_finite() => format!("{value}"),
+        TValue::F32(_) | TValue::F64(_) => "null".to_string(),
+        TValue::VecU8(..) => format!("\"{}\"", escape_json(&String::from(t_value))),
+    }
+}
+
+/// Document JSON de la notification de la modification d'un [`Tag`]
+pub fn tag_change_body(tag: &Tag, t_value: &TValue, user: &str, timestamp: &str) -> String {
+    format!(
+        "{{\"event\":\"tag_change\",\"id_tag\":\"{}\",\"address\":\"0x{:04X}\",\"label\":\"{}\",\"value\":{},\"user\":\"{}\",\"timestamp\":\"{}\"}}",
+        tag.id_tag,
+        tag.word_address,
+        escape_json(&tag.label),
+        json_value(t_value),
+        escape_json(user),
+        escape_json(timestamp)
+    )
+}
+
+/// Envoi d'un document JSON (requête HTTP `POST` limitée à `HTTP_TIMEOUT`)
+/// Retourne le code de statut HTTP de la réponse
+/// # Errors
+/// Erreur de communication avec le serveur, délai dépassé ou réponse HTTP incorrecte
+pub async fn post_json(url: &WebhookUrl, body: &str) -> std::io::Result<u16> {
+    http_post::post(&url.url, "application/json", body).await
+}
+
+/// Envoi d'une notification avec trace du résultat en cas d'échec
+pub async fn notify(url: &WebhookUrl, body: &str) {
+    match post_json(url, body).await {
+        Ok(status) if (200..300).contains(&status) => (),
+        Ok(status) => println!("WEBHOOK: Notification refusée par {url} (HTTP {status})"),
+        Err(e) => println!("WEBHOOK: Notification perdue pour {url}: {e}"),
+    }
+}
+
+/// Routine d'un thread qui notifie les modifications des tags sélectionnés
+pub async fn webhook_process(thread_db: Arc<Mutex<Database>>, rules: Vec<WebhookRule>) {
+    if rules.is_empty() {
+        return;
+    }
+    for rule in &rules {
+        println!("WEBHOOK: {rule}");
+    }
+    let id_user = lock_database(&thread_db, Subsystem::Webhook).get_id_user("Webhook", true);
+
+    loop {
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_millis(WEBHOOK_CYCLE_MSECS)).await;
+
+        // Notifications (destinataire, document) des tags modifiés depuis le cycle précédent
+        let mut notifications = vec![];
+        {
+            let mut db = lock_database(&thread_db, Subsystem::Webhook);
+            let timestamp = timeline::timestamp();
+
+            // Dernier utilisateur de la modification de chaque tag (dans l'ordre des modifications)
+            let mut changes: Vec<(IdTag, String)> = vec![];
+            while let Some(notification_change) = db.get_change(id_user, false, true) {
+                let id_tag = notification_change.id_tag;
+                if !rules.iter().any(|rule| rule.pattern.matches(id_tag)) {
+                    continue;
+                }
+                let user = db.get_id_user_name(notification_change.id_user);
+                changes.retain(|(change_id_tag, _)| *change_id_tag != id_tag);
+                changes.push((id_tag, user));
+            }
+
+            for (id_tag, user) in changes {
+                let Some(tag) = db.get_tag_from_id_tag(id_tag) else {
+                    continue;
+                };
+                let body = tag_change_body(
+                    tag,
+                    &db.get_t_value_from_tag(id_user, tag),
+                    &user,
+                    &timestamp,
+                );
+                for rule in rules.iter().filter(|rule| rule.pattern.matches(id_tag)) {
+                    notifications.push((rule.url.clone(), body.clone()));
+                }
+            }
+        }
+
+        // Envois en parallèle : un destinataire lent ne retarde pas les autres ni le relevé suivant
+        for (url, body) in notifications {
+            tokio::spawn(async move { notify(&url, &body).await });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::t_data::TFormat;
+
+    #[test]
+    fn test_webhook_url() {
+        let url = WebhookUrl::from_str("http://jenkins:8080/job/bench?token=sim").unwrap();
+        assert_eq!(url.url.host_port, "jenkins:8080");
+        assert_eq!(url.url.path, "/job/bench?token=sim");
+        assert_eq!(url.to_string(), "http://jenkins:8080/job/bench?token=sim");
+
+        let url = WebhookUrl::from_str("http://jenkins").unwrap();
+        assert_eq!(url.to_string(), "http://jenkins:80/");
+        let url = WebhookUrl::from_str("http://jenkins?token=sim").unwrap();
+        assert_eq!(url.url.path, "/?token=sim");
+
+        assert!(WebhookUrl::from_str("https://jenkins").is_err());
+        assert!(WebhookUrl::from_str("http:///hook").is_err());
+    }
+
+    #[test]
+    fn test_escape_json() {
+        assert_eq!(escape_json("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+        assert_eq!(escape_json("\u{1}"), "\\u0001");
+        assert_eq!(escape_json("Zone 5"), "Zone 5");
+    }
+
+    #[test]
+    fn test_webhook_rule() {
+        let rule = WebhookRule::from_str("5/*=http://jenkins:8080/hook").unwrap();
+        assert!(rule.pattern.matches(IdTag::new(5, 0x0F45, [0, 0, 3])));
+        assert!(!rule.pattern.matches(IdTag::new(6, 0x0F45, [0, 0, 3])));
+        assert_eq!(rule.to_string(), "5/*:*:*:* -> http://jenkins:8080/hook");
+
+        assert!(WebhookRule::from_str("5/*").is_err());
+        assert!(WebhookRule::from_str("5=http://jenkins").is_err());
+        assert!(WebhookRule::from_str("5/*=jenkins").is_err());
+    }
+
+    #[test]
+    fn test_tag_change_body() {
+        let tag = Tag {
+            word_address: 0x0010,
+            id_tag: IdTag::new(5, 0x0F45, [0, 0, 3]),
+            t_format: TFormat::U16,
+            label: "Alarme \"V1\"".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            tag_change_body(&tag, &TValue::U16(1), "Console", "10:15:42.120"),
+            "{\"event\":\"tag_change\",\"id_tag\":\"5/0F45:00:00:03\",\"address\":\"0x0010\",\"label\":\"Alarme \\\"V1\\\"\",\"value\":1,\"user\":\"Console\",\"timestamp\":\"10:15:42.120\"}"
+        );
+        assert_eq!(json_value(&TValue::F32(2.5)), "2.5");
+        assert_eq!(json_value(&TValue::F64(f64::NAN)), "null");
+        assert_eq!(json_value(&TValue::Bool(true)), "true");
+        assert_eq!(
+            json_value(&TValue::VecU8(3, b"a\"b".to_vec())),
+            "\"a\\\"b\""
+        );
+    }
+}
@@ -0,0 +1,25 @@
+//! Process de publication périodique des CRC des zones de la [`Database`]
+//!
+//! Les CRC des zones (voir `Database::crc_of_zone`) sont publiés périodiquement (option
+//! `--crc-period` de la ligne de commande) dans des tags propres au simulateur pour qu'un outil
+//! externe détecte à moindre coût une modification inattendue lors d'un test d'endurance.
+
+use std::sync::{Arc, Mutex};
+
+use crate::profiling::{lock_database, Subsystem};
+use crate::Database;
+
+/// Routine d'un thread qui publie périodiquement les CRC des zones de la [`Database`]
+/// En paramètre, la période de publication (en secondes, 0 pour inhiber la publication)
+pub async fn zone_crc_process(thread_db: Arc<Mutex<Database>>, period_in_secs: u64) {
+    if period_in_secs == 0 {
+        return;
+    }
+
+    loop {
+        lock_database(&thread_db, Subsystem::ZoneCrc).publish_zone_crcs();
+
+        // Laisse la main...
+        tokio::time::sleep(tokio::time::Duration::from_secs(period_in_secs)).await;
+    }
+}